@@ -0,0 +1,1321 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared_types::{
+    CashFlow, CostBasisMethod, Fill, FxError, FxRates, Portfolio, PortfolioSnapshot, Position,
+    PositionSide, PositionSnapshot, Symbol, TradeSide, DEFAULT_BASE_CURRENCY, OHLCV,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AccountingError {
+    #[error("Invalid fill {fill_id}: {reason}")]
+    InvalidFill { fill_id: Uuid, reason: String },
+
+    #[error("Fill {fill_id} belongs to portfolio {actual}, expected {expected}")]
+    PortfolioMismatch {
+        fill_id: Uuid,
+        expected: Uuid,
+        actual: Uuid,
+    },
+
+    #[error("Fill {fill_id} has already been applied")]
+    DuplicateFill { fill_id: Uuid },
+
+    #[error("Cash flow {flow_id} belongs to portfolio {actual}, expected {expected}")]
+    CashFlowMismatch {
+        flow_id: Uuid,
+        expected: Uuid,
+        actual: Uuid,
+    },
+
+    #[error("Cash flow {flow_id} has already been applied")]
+    DuplicateCashFlow { flow_id: Uuid },
+
+    #[error("Invalid cash amount: {0}")]
+    InvalidAmount(Decimal),
+
+    #[error(transparent)]
+    Fx(#[from] FxError),
+}
+
+/// An open tax lot within a position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lot {
+    /// Fill that opened this lot
+    pub fill_id: Uuid,
+
+    /// Remaining open quantity (always positive)
+    pub quantity: Decimal,
+
+    /// Cost per unit
+    pub price: Decimal,
+
+    /// Rate from the price currency to the base currency the cost was paid at
+    pub rate: Decimal,
+
+    /// When the lot was opened
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Lot-level book for a single instrument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionBook {
+    /// Instrument held
+    pub symbol: Symbol,
+
+    /// Direction of the open lots (None when flat)
+    pub side: Option<PositionSide>,
+
+    /// Open lots in the order they were opened
+    pub lots: VecDeque<Lot>,
+
+    /// Realized P&L for this instrument, net of fees
+    pub realized_pnl: Decimal,
+
+    /// Realized P&L in the base currency, at each closing fill's rate
+    pub base_realized_pnl: Decimal,
+
+    /// Currency P&L realized on the cost of closed lots, in the base currency
+    pub realized_fx_pnl: Decimal,
+
+    /// Latest mark price
+    pub last_price: Option<Decimal>,
+
+    /// Timestamp of the latest mark
+    pub last_marked_at: Option<DateTime<Utc>>,
+}
+
+impl PositionBook {
+    fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            side: None,
+            lots: VecDeque::new(),
+            realized_pnl: Decimal::ZERO,
+            base_realized_pnl: Decimal::ZERO,
+            realized_fx_pnl: Decimal::ZERO,
+            last_price: None,
+            last_marked_at: None,
+        }
+    }
+
+    /// Total open quantity (always non-negative)
+    pub fn quantity(&self) -> Decimal {
+        self.lots.iter().map(|lot| lot.quantity).sum()
+    }
+
+    /// Open quantity with sign applied: positive for longs, negative for shorts
+    pub fn signed_quantity(&self) -> Decimal {
+        match self.side {
+            Some(PositionSide::Short) => -self.quantity(),
+            _ => self.quantity(),
+        }
+    }
+
+    /// Check if there are no open lots
+    pub fn is_flat(&self) -> bool {
+        self.lots.is_empty()
+    }
+
+    /// Total cost of the open lots
+    pub fn cost_basis(&self) -> Decimal {
+        self.lots
+            .iter()
+            .map(|lot| lot.quantity * lot.price)
+            .sum::<Decimal>()
+            * self.symbol.contract_size
+    }
+
+    /// Weighted average cost per unit of the open lots
+    pub fn average_price(&self) -> Decimal {
+        let quantity = self.quantity();
+        if quantity == Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+        self.cost_basis() / (quantity * self.symbol.contract_size)
+    }
+
+    /// Price used for valuation (latest mark, falling back to average cost)
+    pub fn mark_price(&self) -> Decimal {
+        self.last_price.unwrap_or_else(|| self.average_price())
+    }
+
+    /// Signed market value: positive for longs, negative for shorts
+    pub fn market_value(&self) -> Decimal {
+        let value = self.quantity() * self.mark_price() * self.symbol.contract_size;
+        match self.side {
+            Some(PositionSide::Short) => -value,
+            _ => value,
+        }
+    }
+
+    /// Unrealized P&L of the open lots at the current mark
+    pub fn unrealized_pnl(&self) -> Decimal {
+        let mark = self.mark_price();
+        let contract_size = self.symbol.contract_size;
+        self.lots
+            .iter()
+            .map(|lot| {
+                let per_unit = match self.side {
+                    Some(PositionSide::Short) => lot.price - mark,
+                    _ => mark - lot.price,
+                };
+                per_unit * lot.quantity * contract_size
+            })
+            .sum()
+    }
+
+    /// Currency P&L on the cost of the open lots at `rate`, in the base currency
+    ///
+    /// Market value and unrealized P&L are converted at `rate`, while the
+    /// cash paid for the lots left at the rate they were opened at; this is
+    /// the difference, so cash plus market value still adds up.
+    pub fn unrealized_fx_pnl(&self, rate: Decimal) -> Decimal {
+        let fx_pnl: Decimal = self
+            .lots
+            .iter()
+            .map(|lot| lot.quantity * lot.price * (rate - lot.rate))
+            .sum::<Decimal>()
+            * self.symbol.contract_size;
+        match self.side {
+            Some(PositionSide::Short) => -fx_pnl,
+            _ => fx_pnl,
+        }
+    }
+
+    /// Open a new lot (or blend into the existing one for average cost)
+    fn open(&mut self, fill: &Fill, quantity: Decimal, method: CostBasisMethod, rate: Decimal) {
+        if method == CostBasisMethod::AverageCost {
+            if let Some(lot) = self.lots.front_mut() {
+                let total = lot.quantity + quantity;
+                let cost = lot.quantity * lot.price + quantity * fill.price;
+                // Weighted by cost so the blended lot keeps the base currency cost paid
+                lot.rate =
+                    (lot.quantity * lot.price * lot.rate + quantity * fill.price * rate) / cost;
+                lot.price = cost / total;
+                lot.quantity = total;
+                return;
+            }
+        }
+
+        self.lots.push_back(Lot {
+            fill_id: fill.fill_id,
+            quantity,
+            price: fill.price,
+            rate,
+            opened_at: fill.executed_at,
+        });
+    }
+
+    /// Close up to `quantity` against the open lots at `rate` to the base currency
+    ///
+    /// Returns the quantity closed, the realized P&L in the price currency
+    /// and the currency P&L realized on the closed cost in the base currency.
+    fn close(
+        &mut self,
+        quantity: Decimal,
+        price: Decimal,
+        method: CostBasisMethod,
+        rate: Decimal,
+    ) -> (Decimal, Decimal, Decimal) {
+        let contract_size = self.symbol.contract_size;
+        let mut remaining = quantity;
+        let mut realized = Decimal::ZERO;
+        let mut realized_fx = Decimal::ZERO;
+
+        while remaining > Decimal::ZERO {
+            let lot = match method {
+                CostBasisMethod::Lifo => self.lots.back_mut(),
+                CostBasisMethod::Fifo | CostBasisMethod::AverageCost => self.lots.front_mut(),
+            };
+            let Some(lot) = lot else { break };
+
+            let matched = remaining.min(lot.quantity);
+            let (per_unit, fx_per_unit) = match self.side {
+                Some(PositionSide::Short) => (lot.price - price, lot.price * (lot.rate - rate)),
+                _ => (price - lot.price, lot.price * (rate - lot.rate)),
+            };
+            realized += per_unit * matched * contract_size;
+            realized_fx += fx_per_unit * matched * contract_size;
+            lot.quantity -= matched;
+            remaining -= matched;
+
+            if lot.quantity == Decimal::ZERO {
+                match method {
+                    CostBasisMethod::Lifo => self.lots.pop_back(),
+                    CostBasisMethod::Fifo | CostBasisMethod::AverageCost => self.lots.pop_front(),
+                };
+            }
+        }
+
+        if self.lots.is_empty() {
+            self.side = None;
+        }
+
+        (quantity - remaining, realized, realized_fx)
+    }
+}
+
+/// Portfolio accounting engine that books fills into lots and keeps cash,
+/// realized/unrealized P&L and totals consistent
+///
+/// Lots, marks and each book's realized P&L stay in the instrument's price
+/// currency. Cash, the account's P&L and total value are kept in the base
+/// currency: fills are converted at the rate on their execution time and open
+/// positions at the latest rate. Realized and unrealized P&L measure price
+/// moves only; what the cost of a position gained or lost from the rate
+/// moving is reported separately by [`PortfolioAccount::fx_pnl`], so total
+/// value is always net contributions plus realized, unrealized and FX P&L.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioAccount {
+    /// Portfolio ID (matches `Fill::portfolio_id`)
+    pub id: Uuid,
+
+    /// Portfolio name
+    pub name: String,
+
+    /// Lot matching method for closing fills
+    pub method: CostBasisMethod,
+
+    base_currency: String,
+    fx_rates: FxRates,
+    starting_cash: Decimal,
+    cash_balance: Decimal,
+    net_contributions: Decimal,
+    realized_pnl: Decimal,
+    fees_paid: Decimal,
+    positions: BTreeMap<String, PositionBook>,
+    applied_fills: HashSet<Uuid>,
+    applied_cash_flows: HashSet<Uuid>,
+    last_updated: DateTime<Utc>,
+}
+
+impl PortfolioAccount {
+    /// Create an account funded with `starting_cash`
+    pub fn new(name: impl Into<String>, method: CostBasisMethod, starting_cash: Decimal) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            method,
+            base_currency: DEFAULT_BASE_CURRENCY.to_string(),
+            fx_rates: FxRates::new(),
+            starting_cash,
+            cash_balance: starting_cash,
+            net_contributions: starting_cash,
+            realized_pnl: Decimal::ZERO,
+            fees_paid: Decimal::ZERO,
+            positions: BTreeMap::new(),
+            applied_fills: HashSet::new(),
+            applied_cash_flows: HashSet::new(),
+            last_updated: Utc::now(),
+        }
+    }
+
+    /// Use a specific portfolio ID
+    pub fn with_id(mut self, id: Uuid) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Report cash and totals in `currency` instead of USD
+    pub fn with_base_currency(mut self, currency: &str) -> Self {
+        self.base_currency = currency.to_uppercase();
+        self
+    }
+
+    /// Rates for converting positions priced in other currencies
+    pub fn with_fx_rates(mut self, fx_rates: FxRates) -> Self {
+        self.fx_rates = fx_rates;
+        self
+    }
+
+    /// Rebuild an account by replaying its trade and cash ledgers in time order
+    pub fn replay(
+        id: Uuid,
+        name: impl Into<String>,
+        method: CostBasisMethod,
+        starting_cash: Decimal,
+        ledger: &[Fill],
+        cash_flows: &[CashFlow],
+    ) -> Result<Self, AccountingError> {
+        let account = Self::new(name, method, starting_cash).with_id(id);
+        account.replay_ledger(ledger, cash_flows)
+    }
+
+    fn replay_ledger(
+        mut self,
+        ledger: &[Fill],
+        cash_flows: &[CashFlow],
+    ) -> Result<Self, AccountingError> {
+        let mut fills: Vec<&Fill> = ledger.iter().collect();
+        fills.sort_by_key(|fill| fill.executed_at);
+        let mut flows: Vec<&CashFlow> = cash_flows.iter().collect();
+        flows.sort_by_key(|flow| flow.occurred_at);
+
+        // Cash that arrives at the same moment as a fill is booked first
+        let mut flows = flows.into_iter().peekable();
+        for fill in fills {
+            while let Some(flow) = flows.next_if(|flow| flow.occurred_at <= fill.executed_at) {
+                self.apply_cash_flow(flow)?;
+            }
+            self.apply_fill(fill)?;
+        }
+        for flow in flows {
+            self.apply_cash_flow(flow)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Add cash to the account, returning the ledger entry to persist
+    pub fn deposit(&mut self, amount: Decimal) -> Result<CashFlow, AccountingError> {
+        if amount <= Decimal::ZERO {
+            return Err(AccountingError::InvalidAmount(amount));
+        }
+        let flow = CashFlow::new(self.id, amount, Utc::now());
+        self.apply_cash_flow(&flow)?;
+        Ok(flow)
+    }
+
+    /// Remove cash from the account, returning the ledger entry to persist
+    pub fn withdraw(&mut self, amount: Decimal) -> Result<CashFlow, AccountingError> {
+        if amount <= Decimal::ZERO {
+            return Err(AccountingError::InvalidAmount(amount));
+        }
+        let flow = CashFlow::new(self.id, -amount, Utc::now());
+        self.apply_cash_flow(&flow)?;
+        Ok(flow)
+    }
+
+    /// Book a deposit or withdrawal from the cash ledger
+    pub fn apply_cash_flow(&mut self, flow: &CashFlow) -> Result<(), AccountingError> {
+        if flow.portfolio_id != self.id {
+            return Err(AccountingError::CashFlowMismatch {
+                flow_id: flow.flow_id,
+                expected: self.id,
+                actual: flow.portfolio_id,
+            });
+        }
+        if self.applied_cash_flows.contains(&flow.flow_id) {
+            return Err(AccountingError::DuplicateCashFlow {
+                flow_id: flow.flow_id,
+            });
+        }
+        if flow.amount.is_zero() {
+            return Err(AccountingError::InvalidAmount(flow.amount));
+        }
+
+        self.cash_balance += flow.amount;
+        self.net_contributions += flow.amount;
+        self.applied_cash_flows.insert(flow.flow_id);
+        self.last_updated = Utc::now();
+        Ok(())
+    }
+
+    /// Book a fill, returning the P&L it realized (net of fees) in the base currency
+    ///
+    /// Currency P&L on the closed cost is not included; it is booked to the
+    /// position's `realized_fx_pnl`. Fails without booking anything if there
+    /// is no rate from the fill's price currency to the base currency at its
+    /// execution time.
+    pub fn apply_fill(&mut self, fill: &Fill) -> Result<Decimal, AccountingError> {
+        self.check_fill(fill)?;
+        let rate = self.fx_rates.rate(
+            fill.symbol.price_currency(),
+            &self.base_currency,
+            fill.executed_at,
+        )?;
+
+        let method = self.method;
+        let key = fill.symbol.full_identifier();
+        let book = self
+            .positions
+            .entry(key)
+            .or_insert_with(|| PositionBook::new(fill.symbol.clone()));
+
+        let opening_side = match fill.side {
+            TradeSide::Buy => PositionSide::Long,
+            TradeSide::Sell => PositionSide::Short,
+        };
+
+        // Close against opposite-side lots first, then open any remainder
+        let mut remaining = fill.quantity;
+        let mut realized = Decimal::ZERO;
+        if book.side.is_some() && book.side != Some(opening_side) {
+            let (closed, pnl, fx_pnl) = book.close(remaining, fill.price, method, rate);
+            remaining -= closed;
+            realized += pnl;
+            book.realized_fx_pnl += fx_pnl;
+        }
+        if remaining > Decimal::ZERO {
+            book.side = Some(opening_side);
+            book.open(fill, remaining, method, rate);
+        }
+
+        realized -= fill.fees;
+        book.realized_pnl += realized;
+        book.base_realized_pnl += realized * rate;
+        book.last_price = Some(fill.price);
+        book.last_marked_at = Some(fill.executed_at);
+
+        match fill.side {
+            TradeSide::Buy => self.cash_balance -= fill.notional() * rate,
+            TradeSide::Sell => self.cash_balance += fill.notional() * rate,
+        }
+        self.cash_balance -= fill.fees * rate;
+        self.fees_paid += fill.fees * rate;
+        self.realized_pnl += realized * rate;
+        self.applied_fills.insert(fill.fill_id);
+        self.last_updated = Utc::now();
+
+        Ok(realized * rate)
+    }
+
+    fn check_fill(&self, fill: &Fill) -> Result<(), AccountingError> {
+        if fill.portfolio_id != self.id {
+            return Err(AccountingError::PortfolioMismatch {
+                fill_id: fill.fill_id,
+                expected: self.id,
+                actual: fill.portfolio_id,
+            });
+        }
+        if self.applied_fills.contains(&fill.fill_id) {
+            return Err(AccountingError::DuplicateFill {
+                fill_id: fill.fill_id,
+            });
+        }
+        if fill.quantity <= Decimal::ZERO {
+            return Err(AccountingError::InvalidFill {
+                fill_id: fill.fill_id,
+                reason: format!("quantity must be positive, got {}", fill.quantity),
+            });
+        }
+        if fill.price <= Decimal::ZERO {
+            return Err(AccountingError::InvalidFill {
+                fill_id: fill.fill_id,
+                reason: format!("price must be positive, got {}", fill.price),
+            });
+        }
+        if fill.fees < Decimal::ZERO {
+            return Err(AccountingError::InvalidFill {
+                fill_id: fill.fill_id,
+                reason: format!("fees cannot be negative, got {}", fill.fees),
+            });
+        }
+        Ok(())
+    }
+
+    /// Mark the matching position to the bar's close.
+    ///
+    /// Returns false if no position matches or the bar is older than the last mark.
+    pub fn mark_to_market(&mut self, bar: &OHLCV) -> bool {
+        let Some(book) = self.positions.get_mut(&bar.symbol.full_identifier()) else {
+            return false;
+        };
+        if book
+            .last_marked_at
+            .is_some_and(|marked_at| marked_at > bar.timestamp)
+        {
+            return false;
+        }
+
+        book.last_price = Some(bar.close);
+        book.last_marked_at = Some(bar.timestamp);
+        self.last_updated = Utc::now();
+        true
+    }
+
+    /// Mark every position that has a bar in `bars`, returning how many were updated
+    pub fn mark_all(&mut self, bars: &[OHLCV]) -> usize {
+        bars.iter().filter(|bar| self.mark_to_market(bar)).count()
+    }
+
+    /// Get the book for a symbol
+    pub fn position(&self, symbol: &Symbol) -> Option<&PositionBook> {
+        self.positions.get(&symbol.full_identifier())
+    }
+
+    /// Iterate over all books with open lots
+    pub fn open_positions(&self) -> impl Iterator<Item = &PositionBook> {
+        self.positions.values().filter(|book| !book.is_flat())
+    }
+
+    pub fn base_currency(&self) -> &str {
+        &self.base_currency
+    }
+
+    pub fn fx_rates(&self) -> &FxRates {
+        &self.fx_rates
+    }
+
+    /// Add rates as they arrive, e.g. from forex bars
+    pub fn fx_rates_mut(&mut self) -> &mut FxRates {
+        &mut self.fx_rates
+    }
+
+    /// Latest rate from a symbol's price currency to the base currency
+    ///
+    /// Fills are only booked once a rate exists, so this fails for a held
+    /// position only if the rates were replaced by ones missing its currency.
+    pub fn base_rate(&self, symbol: &Symbol) -> Result<Decimal, FxError> {
+        self.fx_rates
+            .latest_rate(symbol.price_currency(), &self.base_currency)
+    }
+
+    /// Signed market value of a book in the base currency
+    pub fn base_market_value(&self, book: &PositionBook) -> Result<Decimal, FxError> {
+        Ok(book.market_value() * self.base_rate(&book.symbol)?)
+    }
+
+    pub fn cash_balance(&self) -> Decimal {
+        self.cash_balance
+    }
+
+    /// Cash the account was opened with, before any cash flows
+    pub fn starting_cash(&self) -> Decimal {
+        self.starting_cash
+    }
+
+    /// Deposits minus withdrawals (including starting cash)
+    pub fn net_contributions(&self) -> Decimal {
+        self.net_contributions
+    }
+
+    /// Realized P&L across all instruments, net of fees
+    pub fn realized_pnl(&self) -> Decimal {
+        self.realized_pnl
+    }
+
+    pub fn fees_paid(&self) -> Decimal {
+        self.fees_paid
+    }
+
+    /// Unrealized P&L across all open positions, in the base currency
+    pub fn unrealized_pnl(&self) -> Result<Decimal, FxError> {
+        self.open_positions()
+            .map(|book| Ok(book.unrealized_pnl() * self.base_rate(&book.symbol)?))
+            .sum()
+    }
+
+    /// Currency P&L on position cost, realized and at the latest rates, in the base currency
+    pub fn fx_pnl(&self) -> Result<Decimal, FxError> {
+        self.positions
+            .values()
+            .map(|book| {
+                let unrealized = if book.is_flat() {
+                    Decimal::ZERO
+                } else {
+                    book.unrealized_fx_pnl(self.base_rate(&book.symbol)?)
+                };
+                Ok(book.realized_fx_pnl + unrealized)
+            })
+            .sum()
+    }
+
+    /// Cash plus the signed market value of all positions, in the base currency
+    pub fn total_value(&self) -> Result<Decimal, FxError> {
+        let positions = self
+            .open_positions()
+            .map(|book| self.base_market_value(book))
+            .sum::<Result<Decimal, FxError>>()?;
+        Ok(self.cash_balance + positions)
+    }
+
+    /// Realized, unrealized and FX P&L
+    pub fn total_pnl(&self) -> Result<Decimal, FxError> {
+        Ok(self.realized_pnl + self.unrealized_pnl()? + self.fx_pnl()?)
+    }
+
+    /// IDs of every fill booked so far
+    pub fn applied_fills(&self) -> &HashSet<Uuid> {
+        &self.applied_fills
+    }
+
+    /// IDs of every deposit and withdrawal booked so far
+    pub fn applied_cash_flows(&self) -> &HashSet<Uuid> {
+        &self.applied_cash_flows
+    }
+
+    /// Snapshot the account into the shared `Portfolio` type
+    pub fn to_portfolio(&self) -> Result<Portfolio, FxError> {
+        let positions = self
+            .open_positions()
+            .map(|book| Position {
+                symbol: book.symbol.clone(),
+                quantity: book.quantity(),
+                average_price: book.average_price(),
+                side: book.side.unwrap_or(PositionSide::Long),
+                opened_at: book
+                    .lots
+                    .iter()
+                    .map(|lot| lot.opened_at)
+                    .min()
+                    .unwrap_or(self.last_updated),
+                unrealized_pnl: book.unrealized_pnl(),
+                realized_pnl: book.realized_pnl,
+            })
+            .collect();
+
+        Ok(Portfolio {
+            name: self.name.clone(),
+            base_currency: self.base_currency.clone(),
+            positions,
+            cash_balance: self.cash_balance,
+            total_value: self.total_value()?,
+            total_pnl: self.total_pnl()?,
+            last_updated: self.last_updated,
+        })
+    }
+
+    /// Capture a valuation snapshot for performance tracking
+    ///
+    /// Position figures are in the base currency and add up to the account's:
+    /// realized P&L at the closing fills' rates, and open positions at the
+    /// latest rate. Closed books need no rate.
+    pub fn snapshot(&self, as_of: DateTime<Utc>) -> Result<PortfolioSnapshot, FxError> {
+        let positions = self
+            .positions
+            .values()
+            .map(|book| {
+                let (market_value, unrealized_pnl, unrealized_fx_pnl) = if book.is_flat() {
+                    (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO)
+                } else {
+                    let rate = self.base_rate(&book.symbol)?;
+                    (
+                        book.market_value() * rate,
+                        book.unrealized_pnl() * rate,
+                        book.unrealized_fx_pnl(rate),
+                    )
+                };
+                Ok(PositionSnapshot {
+                    symbol: book.symbol.clone(),
+                    side: book.side,
+                    quantity: book.quantity(),
+                    market_value,
+                    unrealized_pnl,
+                    realized_pnl: book.base_realized_pnl,
+                    fx_pnl: book.realized_fx_pnl + unrealized_fx_pnl,
+                })
+            })
+            .collect::<Result<_, FxError>>()?;
+        Ok(PortfolioSnapshot {
+            portfolio_id: self.id,
+            as_of,
+            total_value: self.total_value()?,
+            cash_balance: self.cash_balance,
+            net_contributions: self.net_contributions,
+            positions,
+        })
+    }
+
+    /// Replay the ledgers from this account's starting cash and compare the result
+    ///
+    /// The replay converts fills with this account's FX rates. Every book is
+    /// compared, including closed ones, so realized P&L that was booked on a
+    /// position since flattened is still checked.
+    pub fn reconcile(
+        &self,
+        ledger: &[Fill],
+        cash_flows: &[CashFlow],
+    ) -> Result<ReconciliationReport, AccountingError> {
+        let replayed = Self::new(&self.name, self.method, self.starting_cash)
+            .with_id(self.id)
+            .with_base_currency(&self.base_currency)
+            .with_fx_rates(self.fx_rates.clone())
+            .replay_ledger(ledger, cash_flows)?;
+        Ok(compare(
+            self.id,
+            &replayed.to_books(),
+            &self.to_books(),
+            ledger.len(),
+        ))
+    }
+
+    /// Cash, contributions and every book reduced to what reconciliation compares
+    fn to_books(&self) -> ReconciledBooks {
+        ReconciledBooks {
+            cash_balance: self.cash_balance,
+            net_contributions: Some(self.net_contributions),
+            positions: self
+                .positions
+                .iter()
+                .map(|(key, book)| (key.clone(), (book.signed_quantity(), book.realized_pnl)))
+                .collect(),
+        }
+    }
+}
+
+/// The parts of a portfolio reconciliation compares, keyed by symbol identifier
+struct ReconciledBooks {
+    cash_balance: Decimal,
+    net_contributions: Option<Decimal>,
+
+    /// Signed open quantity and realized P&L per instrument
+    positions: BTreeMap<String, (Decimal, Decimal)>,
+}
+
+impl ReconciledBooks {
+    fn from_portfolio(portfolio: &Portfolio) -> Self {
+        let signed = |position: &Position| match position.side {
+            PositionSide::Long => position.quantity,
+            PositionSide::Short => -position.quantity,
+        };
+        Self {
+            cash_balance: portfolio.cash_balance,
+            net_contributions: None,
+            positions: portfolio
+                .positions
+                .iter()
+                .map(|position| {
+                    (
+                        position.symbol.full_identifier(),
+                        (signed(position), position.realized_pnl),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A single mismatch between a booked portfolio and its trade ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Discrepancy {
+    /// What was compared (e.g. "cash_balance" or "position:AAPL@NASDAQ")
+    pub field: String,
+
+    /// Value derived from the ledger
+    pub ledger_value: Decimal,
+
+    /// Value found in the booked portfolio
+    pub booked_value: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub portfolio_id: Uuid,
+    pub fills_replayed: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    /// Check if the book matches the ledger exactly
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Reconcile a booked portfolio snapshot against its trade and cash ledgers.
+///
+/// Compares cash, open quantities (signed by side) and realized P&L of the
+/// positions the snapshot still holds; a `Portfolio` drops closed positions,
+/// so their realized P&L can only be checked by [`PortfolioAccount::reconcile`].
+/// The ledger is replayed in the booked base currency without FX rates, so
+/// multi-currency books should also use [`PortfolioAccount::reconcile`].
+pub fn reconcile(
+    portfolio_id: Uuid,
+    booked: &Portfolio,
+    method: CostBasisMethod,
+    starting_cash: Decimal,
+    ledger: &[Fill],
+    cash_flows: &[CashFlow],
+) -> Result<ReconciliationReport, AccountingError> {
+    let replayed = PortfolioAccount::new(&booked.name, method, starting_cash)
+        .with_id(portfolio_id)
+        .with_base_currency(&booked.base_currency)
+        .replay_ledger(ledger, cash_flows)?;
+    let mut expected = replayed.to_books();
+    expected
+        .positions
+        .retain(|_, (quantity, _)| !quantity.is_zero());
+    Ok(compare(
+        portfolio_id,
+        &expected,
+        &ReconciledBooks::from_portfolio(booked),
+        ledger.len(),
+    ))
+}
+
+fn compare(
+    portfolio_id: Uuid,
+    expected: &ReconciledBooks,
+    booked: &ReconciledBooks,
+    fills_replayed: usize,
+) -> ReconciliationReport {
+    let mut discrepancies = Vec::new();
+    let mut check = |field: String, ledger_value: Decimal, booked_value: Decimal| {
+        if ledger_value != booked_value {
+            discrepancies.push(Discrepancy {
+                field,
+                ledger_value,
+                booked_value,
+            });
+        }
+    };
+    check(
+        "cash_balance".to_string(),
+        expected.cash_balance,
+        booked.cash_balance,
+    );
+    if let (Some(ledger), Some(booked)) = (expected.net_contributions, booked.net_contributions) {
+        check("net_contributions".to_string(), ledger, booked);
+    }
+
+    let keys: BTreeSet<&String> = expected
+        .positions
+        .keys()
+        .chain(booked.positions.keys())
+        .collect();
+    for key in keys {
+        let (ledger_quantity, ledger_realized) =
+            expected.positions.get(key).copied().unwrap_or_default();
+        let (booked_quantity, booked_realized) =
+            booked.positions.get(key).copied().unwrap_or_default();
+        check(
+            format!("position:{}", key),
+            ledger_quantity,
+            booked_quantity,
+        );
+        check(
+            format!("realized_pnl:{}", key),
+            ledger_realized,
+            booked_realized,
+        );
+    }
+
+    ReconciliationReport {
+        portfolio_id,
+        fills_replayed,
+        discrepancies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use shared_types::{Exchange, TimeFrame};
+
+    fn aapl() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    fn ts(offset_days: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap() + Duration::days(offset_days)
+    }
+
+    fn fill(account: &PortfolioAccount, side: TradeSide, qty: i64, price: i64, day: i64) -> Fill {
+        Fill::new(
+            account.id,
+            aapl(),
+            side,
+            Decimal::new(qty, 0),
+            Decimal::new(price, 0),
+            ts(day),
+        )
+    }
+
+    fn bar(close: i64, day: i64) -> OHLCV {
+        let close = Decimal::new(close, 0);
+        OHLCV::new(
+            aapl(),
+            TimeFrame::OneDay,
+            ts(day),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    fn book_round_trip(method: CostBasisMethod) -> PortfolioAccount {
+        let mut account = PortfolioAccount::new("test", method, Decimal::new(10_000, 0));
+        let buys = [
+            fill(&account, TradeSide::Buy, 10, 100, 0),
+            fill(&account, TradeSide::Buy, 10, 120, 1),
+        ];
+        for f in &buys {
+            account.apply_fill(f).unwrap();
+        }
+        let sell = fill(&account, TradeSide::Sell, 10, 130, 2);
+        account.apply_fill(&sell).unwrap();
+        account
+    }
+
+    #[test]
+    fn test_fifo_lifo_average_realized() {
+        // FIFO closes the 100 lot: (130 - 100) * 10
+        let fifo = book_round_trip(CostBasisMethod::Fifo);
+        assert_eq!(fifo.realized_pnl(), Decimal::new(300, 0));
+        assert_eq!(
+            fifo.position(&aapl()).unwrap().average_price(),
+            Decimal::new(120, 0)
+        );
+
+        // LIFO closes the 120 lot: (130 - 120) * 10
+        let lifo = book_round_trip(CostBasisMethod::Lifo);
+        assert_eq!(lifo.realized_pnl(), Decimal::new(100, 0));
+
+        // Average cost blends to 110: (130 - 110) * 10
+        let average = book_round_trip(CostBasisMethod::AverageCost);
+        assert_eq!(average.realized_pnl(), Decimal::new(200, 0));
+        assert_eq!(
+            average.position(&aapl()).unwrap().average_price(),
+            Decimal::new(110, 0)
+        );
+
+        // Cash is the same regardless of lot method
+        for account in [&fifo, &lifo, &average] {
+            assert_eq!(account.cash_balance(), Decimal::new(9_100, 0));
+        }
+    }
+
+    #[test]
+    fn test_short_position_pnl() {
+        let mut account =
+            PortfolioAccount::new("short", CostBasisMethod::Fifo, Decimal::new(5_000, 0));
+        account
+            .apply_fill(&fill(&account, TradeSide::Sell, 10, 100, 0))
+            .unwrap();
+
+        let book = account.position(&aapl()).unwrap();
+        assert_eq!(book.side, Some(PositionSide::Short));
+        assert_eq!(account.cash_balance(), Decimal::new(6_000, 0));
+
+        assert!(account.mark_to_market(&bar(90, 1)));
+        assert_eq!(account.unrealized_pnl().unwrap(), Decimal::new(100, 0));
+        assert_eq!(account.total_value().unwrap(), Decimal::new(5_100, 0));
+
+        let realized = account
+            .apply_fill(&fill(&account, TradeSide::Buy, 10, 95, 2))
+            .unwrap();
+        assert_eq!(realized, Decimal::new(50, 0));
+        assert!(account.position(&aapl()).unwrap().is_flat());
+        assert_eq!(account.cash_balance(), Decimal::new(5_050, 0));
+    }
+
+    #[test]
+    fn test_fill_flips_long_to_short() {
+        let mut account =
+            PortfolioAccount::new("flip", CostBasisMethod::Fifo, Decimal::new(10_000, 0));
+        account
+            .apply_fill(&fill(&account, TradeSide::Buy, 5, 100, 0))
+            .unwrap();
+        account
+            .apply_fill(&fill(&account, TradeSide::Sell, 8, 110, 1))
+            .unwrap();
+
+        let book = account.position(&aapl()).unwrap();
+        assert_eq!(book.side, Some(PositionSide::Short));
+        assert_eq!(book.quantity(), Decimal::new(3, 0));
+        assert_eq!(book.average_price(), Decimal::new(110, 0));
+        assert_eq!(account.realized_pnl(), Decimal::new(50, 0));
+    }
+
+    #[test]
+    fn test_totals_stay_consistent_with_fees() {
+        let mut account =
+            PortfolioAccount::new("fees", CostBasisMethod::Fifo, Decimal::new(10_000, 0));
+        let buy = fill(&account, TradeSide::Buy, 20, 100, 0).with_fees(Decimal::new(2, 0));
+        let sell = fill(&account, TradeSide::Sell, 5, 105, 1).with_fees(Decimal::ONE);
+        account.apply_fill(&buy).unwrap();
+        account.apply_fill(&sell).unwrap();
+        account.mark_to_market(&bar(108, 2));
+
+        assert_eq!(account.fees_paid(), Decimal::new(3, 0));
+        assert_eq!(
+            account.total_value().unwrap() - account.net_contributions(),
+            account.total_pnl().unwrap()
+        );
+
+        let snapshot = account.to_portfolio().unwrap();
+        assert_eq!(snapshot.positions.len(), 1);
+        assert_eq!(snapshot.total_value, account.total_value().unwrap());
+        assert_eq!(snapshot.positions[0].unrealized_pnl, Decimal::new(120, 0));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_closed_positions() {
+        let mut account =
+            PortfolioAccount::new("snap", CostBasisMethod::Fifo, Decimal::new(1_000, 0));
+        account
+            .apply_fill(&fill(&account, TradeSide::Buy, 2, 100, 0))
+            .unwrap();
+        account
+            .apply_fill(&fill(&account, TradeSide::Sell, 2, 110, 1))
+            .unwrap();
+
+        let snapshot = account.snapshot(ts(1)).unwrap();
+        assert_eq!(snapshot.total_value, Decimal::new(1_020, 0));
+        assert_eq!(snapshot.positions.len(), 1);
+        assert_eq!(snapshot.positions[0].side, None);
+        assert_eq!(snapshot.position_pnl(&aapl()), Decimal::new(20, 0));
+    }
+
+    #[test]
+    fn test_stale_mark_is_ignored() {
+        let mut account =
+            PortfolioAccount::new("marks", CostBasisMethod::Fifo, Decimal::new(1_000, 0));
+        account
+            .apply_fill(&fill(&account, TradeSide::Buy, 1, 100, 1))
+            .unwrap();
+
+        assert!(!account.mark_to_market(&bar(50, 0)));
+        assert_eq!(account.mark_all(&[bar(101, 2), bar(102, 3)]), 2);
+        assert_eq!(
+            account.position(&aapl()).unwrap().last_price,
+            Some(Decimal::new(102, 0))
+        );
+    }
+
+    #[test]
+    fn test_rejects_duplicate_and_foreign_fills() {
+        let mut account =
+            PortfolioAccount::new("guards", CostBasisMethod::Fifo, Decimal::new(1_000, 0));
+        let buy = fill(&account, TradeSide::Buy, 1, 100, 0);
+        account.apply_fill(&buy).unwrap();
+
+        assert!(matches!(
+            account.apply_fill(&buy),
+            Err(AccountingError::DuplicateFill { .. })
+        ));
+
+        let mut foreign = fill(&account, TradeSide::Buy, 1, 100, 0);
+        foreign.portfolio_id = Uuid::new_v4();
+        assert!(matches!(
+            account.apply_fill(&foreign),
+            Err(AccountingError::PortfolioMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reconcile_against_ledger() {
+        let mut account =
+            PortfolioAccount::new("recon", CostBasisMethod::Fifo, Decimal::new(10_000, 0));
+        let ledger = vec![
+            fill(&account, TradeSide::Buy, 10, 100, 0),
+            fill(&account, TradeSide::Sell, 4, 110, 1),
+        ];
+        for f in &ledger {
+            account.apply_fill(f).unwrap();
+        }
+
+        let flows = vec![account.deposit(Decimal::new(2_500, 0)).unwrap()];
+        assert!(account.reconcile(&ledger, &flows).unwrap().is_clean());
+
+        // A deposit missing from the cash ledger breaks cash and contributions
+        let report = account.reconcile(&ledger, &[]).unwrap();
+        let fields: Vec<&str> = report
+            .discrepancies
+            .iter()
+            .map(|d| d.field.as_str())
+            .collect();
+        assert_eq!(fields, ["cash_balance", "net_contributions"]);
+
+        // A fill missing from the ledger shows up as cash and position breaks
+        let report = account.reconcile(&ledger[..1], &flows).unwrap();
+        assert!(!report.is_clean());
+        assert!(report
+            .discrepancies
+            .iter()
+            .any(|d| d.field == "cash_balance"));
+        assert!(report
+            .discrepancies
+            .iter()
+            .any(|d| d.field == "position:AAPL@NASDAQ"
+                && d.ledger_value == Decimal::new(10, 0)
+                && d.booked_value == Decimal::new(6, 0)));
+    }
+
+    #[test]
+    fn test_reconcile_checks_closed_books_and_cash_flows() {
+        let mut account =
+            PortfolioAccount::new("closed", CostBasisMethod::Fifo, Decimal::new(1_000, 0));
+        let ledger = vec![
+            fill(&account, TradeSide::Buy, 2, 100, 0),
+            fill(&account, TradeSide::Sell, 2, 110, 1),
+        ];
+        for f in &ledger {
+            account.apply_fill(f).unwrap();
+        }
+        let flows = vec![
+            account.deposit(Decimal::new(500, 0)).unwrap(),
+            account.withdraw(Decimal::new(200, 0)).unwrap(),
+        ];
+        assert_eq!(account.net_contributions(), Decimal::new(1_300, 0));
+        assert!(matches!(
+            account.apply_cash_flow(&flows[0]),
+            Err(AccountingError::DuplicateCashFlow { .. })
+        ));
+
+        // Reloading from both ledgers gives back the same account
+        let replayed = PortfolioAccount::replay(
+            account.id,
+            "closed",
+            CostBasisMethod::Fifo,
+            account.starting_cash(),
+            &ledger,
+            &flows,
+        )
+        .unwrap();
+        assert_eq!(replayed.cash_balance(), account.cash_balance());
+        assert_eq!(replayed.net_contributions(), Decimal::new(1_300, 0));
+        assert!(account.reconcile(&ledger, &flows).unwrap().is_clean());
+
+        // The position is flat, but its realized P&L is still reconciled
+        let mut repriced = ledger.clone();
+        repriced[1].price = Decimal::new(115, 0);
+        let report = account.reconcile(&repriced, &flows).unwrap();
+        assert!(report
+            .discrepancies
+            .iter()
+            .any(|d| d.field == "realized_pnl:AAPL@NASDAQ"
+                && d.ledger_value == Decimal::new(30, 0)
+                && d.booked_value == Decimal::new(20, 0)));
+    }
+
+    #[test]
+    fn test_multi_currency_valuation_in_base_currency() {
+        let listed = |code: &str, exchange: Exchange, currency: &str| {
+            Symbol::new(
+                code.to_string(),
+                code.to_string(),
+                shared_types::AssetClass::Stock,
+                exchange,
+                currency.to_string(),
+            )
+            .unwrap()
+        };
+        let (vod, sap) = (
+            listed("VOD", Exchange::LSE, "GBP"),
+            listed("SAP", Exchange::XETRA, "EUR"),
+        );
+        let mut account =
+            PortfolioAccount::new("global", CostBasisMethod::Fifo, Decimal::new(100_000, 0));
+        let buy = |account: &PortfolioAccount, symbol: &Symbol, qty: i64, price: i64| {
+            Fill::new(
+                account.id,
+                symbol.clone(),
+                TradeSide::Buy,
+                Decimal::new(qty, 0),
+                Decimal::new(price, 0),
+                ts(1),
+            )
+        };
+
+        let vod_fill = buy(&account, &vod, 100, 80);
+        assert!(matches!(
+            account.apply_fill(&vod_fill),
+            Err(AccountingError::Fx(FxError::MissingRate { .. }))
+        ));
+        assert_eq!(account.cash_balance(), Decimal::new(100_000, 0));
+
+        let rates = account.fx_rates_mut();
+        rates
+            .add_rate("GBP", "USD", ts(0), Decimal::new(125, 2))
+            .unwrap();
+        rates
+            .add_rate("EUR", "USD", ts(0), Decimal::new(110, 2))
+            .unwrap();
+        let mut ledger = vec![
+            vod_fill,
+            buy(&account, &sap, 10, 200),
+            fill(&account, TradeSide::Buy, 10, 100, 1),
+        ];
+        for fill in &ledger {
+            account.apply_fill(fill).unwrap();
+        }
+
+        // 8,000 GBP at 1.25, 2,000 EUR at 1.10 and 1,000 USD
+        assert_eq!(account.cash_balance(), Decimal::new(86_800, 0));
+        assert_eq!(account.total_value().unwrap(), Decimal::new(100_000, 0));
+
+        // Sterling strengthens: the VOD holding gains 8,000 * 0.05 in USD
+        account
+            .fx_rates_mut()
+            .add_rate("GBP", "USD", ts(2), Decimal::new(130, 2))
+            .unwrap();
+        assert_eq!(account.total_value().unwrap(), Decimal::new(100_400, 0));
+        assert_eq!(account.fx_pnl().unwrap(), Decimal::new(400, 0));
+        assert_eq!(account.to_portfolio().unwrap().base_currency, "USD");
+
+        // Selling half at 84 realizes 200 GBP at 1.30 and half the currency gain
+        let sell = Fill::new(
+            account.id,
+            vod.clone(),
+            TradeSide::Sell,
+            Decimal::new(50, 0),
+            Decimal::new(84, 0),
+            ts(3),
+        );
+        account.apply_fill(&sell).unwrap();
+        ledger.push(sell);
+        assert_eq!(account.realized_pnl(), Decimal::new(260, 0));
+        assert_eq!(account.unrealized_pnl().unwrap(), Decimal::new(260, 0));
+        assert_eq!(account.fx_pnl().unwrap(), Decimal::new(400, 0));
+        assert_eq!(
+            account.position(&vod).unwrap().realized_fx_pnl,
+            Decimal::new(200, 0)
+        );
+
+        // Value is contributions plus price and currency P&L, in the account and its snapshot
+        let total_value = account.total_value().unwrap();
+        assert_eq!(total_value, Decimal::new(100_920, 0));
+        assert_eq!(
+            total_value,
+            account.net_contributions() + account.total_pnl().unwrap()
+        );
+        let snapshot = account.snapshot(ts(3)).unwrap();
+        let position_pnl: Decimal = snapshot
+            .positions
+            .iter()
+            .map(|position| position.realized_pnl + position.unrealized_pnl + position.fx_pnl)
+            .sum();
+        assert_eq!(
+            snapshot.total_value,
+            snapshot.net_contributions + position_pnl
+        );
+        assert_eq!(
+            snapshot
+                .positions
+                .iter()
+                .map(|position| position.realized_pnl)
+                .sum::<Decimal>(),
+            account.realized_pnl()
+        );
+        assert_eq!(snapshot.position_pnl(&vod), Decimal::new(920, 0));
+
+        let report = account.reconcile(&ledger, &[]).unwrap();
+        assert_eq!(report.fills_replayed, 4);
+        assert!(report.is_clean(), "{:?}", report.discrepancies);
+
+        // Without a rate the holdings cannot be valued at all
+        let unpriced = account.clone().with_fx_rates(FxRates::new());
+        assert!(matches!(
+            unpriced.total_value(),
+            Err(FxError::MissingRate { .. })
+        ));
+        assert!(unpriced.snapshot(ts(3)).is_err());
+
+        // The same book reported in euros triangulates sterling through USD
+        let mut euro = PortfolioAccount::new("euro", CostBasisMethod::Fifo, Decimal::ZERO)
+            .with_base_currency("eur")
+            .with_fx_rates(account.fx_rates().clone());
+        euro.apply_fill(&Fill::new(
+            euro.id,
+            vod,
+            TradeSide::Buy,
+            Decimal::new(11, 0),
+            Decimal::new(100, 0),
+            ts(3),
+        ))
+        .unwrap();
+        // 1,100 GBP at 1.30 / 1.10 EUR per GBP
+        assert_eq!(euro.cash_balance().round_dp(6), Decimal::new(-1_300, 0));
+        assert_eq!(euro.total_value().unwrap().round_dp(6), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,741 @@
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use shared_types::{
+    AlertCondition, AlertEvent, AlertMode, AlertRule, Comparison, Symbol, TechnicalIndicator,
+    TimeFrame, OHLCV,
+};
+use std::collections::{HashMap, VecDeque};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::expression::{Evaluator, Expression, ExpressionError, MAX_PERIOD};
+use crate::{indicators, patterns};
+
+/// Bars kept per series by default, enough for long EMAs to settle
+///
+/// Series whose rules look further back keep as many bars as those rules need.
+const DEFAULT_HISTORY_BARS: usize = 250;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AlertError {
+    #[error("{0:?} cannot be used in alert conditions")]
+    UnsupportedIndicator(TechnicalIndicator),
+
+    #[error("Unknown pattern '{0}'")]
+    UnknownPattern(String),
+
+    #[error("Invalid alert condition: {0}")]
+    InvalidCondition(String),
+
+    #[error("Invalid alert expression: {0}")]
+    InvalidExpression(#[from] ExpressionError),
+}
+
+/// How [`AlertEngine::record`] placed a bar in its series
+enum Recorded {
+    Appended,
+    /// Same timestamp as the latest bar, which it replaced
+    Replaced,
+}
+
+/// Evaluates alert rules against every new bar
+///
+/// The engine keeps a rolling window of recent bars per symbol and timeframe,
+/// so indicator conditions can be checked as each bar arrives. Cooldowns are
+/// measured on bar timestamps, which keeps replays of history deterministic.
+/// Rules are updated in place when they fire; callers persisting rules should
+/// save [`rule`](Self::rule) after every event.
+///
+/// Each series keeps at least the configured number of bars, and more when
+/// one of its rules looks further back (see [`lookback_bars`]).
+///
+/// Expression rules are compiled when added and keep an [`Evaluator`] that is
+/// advanced by every bar of their series, active or cooling down, along with
+/// a copy taken before the latest bar. A corrected bar restarts from that
+/// copy, since running indicator state cannot be rewound.
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: HashMap<Uuid, AlertRule>,
+    lookbacks: HashMap<Uuid, usize>,
+    expressions: HashMap<Uuid, ExpressionState>,
+    history: HashMap<(String, TimeFrame), VecDeque<OHLCV>>,
+    history_bars: usize,
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        Self {
+            rules: HashMap::new(),
+            lookbacks: HashMap::new(),
+            expressions: HashMap::new(),
+            history: HashMap::new(),
+            history_bars: DEFAULT_HISTORY_BARS,
+        }
+    }
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bars kept per series when none of its rules needs more
+    pub fn with_history_bars(mut self, bars: usize) -> Self {
+        self.history_bars = bars.max(2);
+        self
+    }
+
+    /// Add or replace a rule after checking its condition can be evaluated
+    pub fn add_rule(&mut self, rule: AlertRule) -> Result<(), AlertError> {
+        self.add_rule_with_history(rule, &[])
+    }
+
+    /// Add or replace a rule, first filling its series with older `bars`
+    ///
+    /// `bars` are the rule's series, oldest first, e.g. the latest
+    /// [`lookback_bars`] from storage; those the window already covers are
+    /// ignored. Nothing fires while they are taken in.
+    pub fn add_rule_with_history(
+        &mut self,
+        rule: AlertRule,
+        bars: &[OHLCV],
+    ) -> Result<(), AlertError> {
+        let lookback = lookback_bars(&rule.condition)?;
+        let expression = match &rule.condition {
+            AlertCondition::Expression { expression } => Some(Expression::compile(expression)?),
+            _ => None,
+        };
+        let key = (rule.symbol.code.clone(), rule.timeframe.clone());
+        self.remove_rule(rule.id);
+        self.lookbacks.insert(rule.id, lookback);
+        self.rules.insert(rule.id, rule.clone());
+
+        let limit = self.window_bars(&key);
+        let window = self.history.entry(key).or_default();
+        let oldest = window.front().map(|bar| bar.timestamp);
+        let older: Vec<&OHLCV> = bars
+            .iter()
+            .filter(|bar| oldest.is_none_or(|oldest| bar.timestamp < oldest))
+            .collect();
+        for bar in older.into_iter().rev() {
+            if window
+                .front()
+                .is_none_or(|front| bar.timestamp < front.timestamp)
+            {
+                window.push_front(bar.clone());
+            }
+        }
+        while window.len() > limit {
+            window.pop_front();
+        }
+
+        if let Some(expression) = expression {
+            let state = ExpressionState::new(&expression, window.make_contiguous());
+            self.expressions.insert(rule.id, state);
+        }
+        Ok(())
+    }
+
+    pub fn remove_rule(&mut self, id: Uuid) -> Option<AlertRule> {
+        self.expressions.remove(&id);
+        self.lookbacks.remove(&id);
+        self.rules.remove(&id)
+    }
+
+    /// Bars currently held for a series
+    pub fn history_len(&self, symbol: &Symbol, timeframe: &TimeFrame) -> usize {
+        self.history
+            .get(&(symbol.code.clone(), timeframe.clone()))
+            .map_or(0, VecDeque::len)
+    }
+
+    pub fn rule(&self, id: Uuid) -> Option<&AlertRule> {
+        self.rules.get(&id)
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.values()
+    }
+
+    /// Seed the bar window, e.g. from storage at startup, without firing anything
+    pub fn warm_up(&mut self, bars: &[OHLCV]) {
+        for bar in bars {
+            if let Some(recorded) = self.record(bar) {
+                self.advance_expressions(bar, recorded);
+            }
+        }
+    }
+
+    /// Record a new bar and fire the rules it satisfies
+    pub fn on_bar(&mut self, bar: &OHLCV) -> Vec<AlertEvent> {
+        let Some(recorded) = self.record(bar) else {
+            return Vec::new();
+        };
+        let expressions = self.advance_expressions(bar, recorded);
+        let key = (bar.symbol.code.clone(), bar.timeframe.clone());
+        let Some(window) = self.history.get_mut(&key) else {
+            return Vec::new();
+        };
+        let bars = window.make_contiguous();
+
+        let mut events = Vec::new();
+        for rule in self.rules.values_mut() {
+            if !rule.active
+                || rule.symbol.code != bar.symbol.code
+                || rule.timeframe != bar.timeframe
+                || rule.cooling_down(bar.timestamp)
+            {
+                continue;
+            }
+            let outcome = match &rule.condition {
+                AlertCondition::Expression { expression } => (expressions.get(&rule.id)
+                    == Some(&Some(true)))
+                .then(|| (None, format!("{} holds", expression))),
+                condition => evaluate(condition, bars),
+            };
+            let Some((value, description)) = outcome else {
+                continue;
+            };
+
+            rule.last_triggered = Some(bar.timestamp);
+            if rule.mode == AlertMode::OneShot {
+                rule.active = false;
+            }
+            events.push(AlertEvent {
+                id: Uuid::new_v4(),
+                rule_id: rule.id,
+                rule_name: rule.name.clone(),
+                symbol: bar.symbol.code.clone(),
+                timeframe: bar.timeframe.clone(),
+                triggered_at: bar.timestamp,
+                price: bar.close,
+                value,
+                message: format!("{}: {}", rule.name, description),
+            });
+        }
+        events.sort_by(|a, b| a.rule_name.cmp(&b.rule_name));
+        events
+    }
+
+    /// Bars a series keeps: the configured minimum or its rules' longest lookback
+    fn window_bars(&self, key: &(String, TimeFrame)) -> usize {
+        self.rules
+            .values()
+            .filter(|rule| rule.symbol.code == key.0 && rule.timeframe == key.1)
+            .filter_map(|rule| self.lookbacks.get(&rule.id))
+            .fold(self.history_bars, |bars, lookback| bars.max(*lookback))
+    }
+
+    /// Append to the series window; `None` for bars older than the latest
+    fn record(&mut self, bar: &OHLCV) -> Option<Recorded> {
+        let key = (bar.symbol.code.clone(), bar.timeframe.clone());
+        let limit = self.window_bars(&key);
+        let window = self.history.entry(key).or_default();
+        let recorded = match window.back() {
+            Some(last) if bar.timestamp < last.timestamp => return None,
+            // A corrected bar replaces the one it revises
+            Some(last) if bar.timestamp == last.timestamp => {
+                window.pop_back();
+                Recorded::Replaced
+            }
+            _ => Recorded::Appended,
+        };
+        window.push_back(bar.clone());
+        while window.len() > limit {
+            window.pop_front();
+        }
+        Some(recorded)
+    }
+
+    /// Feed a recorded bar to the expression rules of its series, returning each result
+    fn advance_expressions(
+        &mut self,
+        bar: &OHLCV,
+        recorded: Recorded,
+    ) -> HashMap<Uuid, Option<bool>> {
+        let mut results = HashMap::new();
+        for (id, state) in self.expressions.iter_mut() {
+            let Some(rule) = self.rules.get(id) else {
+                continue;
+            };
+            if rule.symbol.code != bar.symbol.code || rule.timeframe != bar.timeframe {
+                continue;
+            }
+            results.insert(*id, state.advance(bar, &recorded));
+        }
+        results
+    }
+}
+
+/// A compiled expression rule's running state
+#[derive(Debug, Clone)]
+struct ExpressionState {
+    current: Evaluator,
+    /// `current` as it was before the latest bar
+    before_latest: Evaluator,
+}
+
+impl ExpressionState {
+    /// State after feeding `bars`, oldest first
+    fn new(expression: &Expression, bars: &[OHLCV]) -> Self {
+        let mut current = expression.evaluator();
+        let Some((latest, earlier)) = bars.split_last() else {
+            return Self {
+                before_latest: current.clone(),
+                current,
+            };
+        };
+        for bar in earlier {
+            current.update(bar);
+        }
+        let before_latest = current.clone();
+        current.update(latest);
+        Self {
+            current,
+            before_latest,
+        }
+    }
+
+    fn advance(&mut self, bar: &OHLCV, recorded: &Recorded) -> Option<bool> {
+        match recorded {
+            Recorded::Appended => self.before_latest.clone_from(&self.current),
+            Recorded::Replaced => self.current.clone_from(&self.before_latest),
+        }
+        self.current.update(bar)
+    }
+}
+
+/// Bars of its series a condition looks at, after checking it can be evaluated
+pub fn lookback_bars(condition: &AlertCondition) -> Result<usize, AlertError> {
+    match condition {
+        // The previous close tells crossings apart
+        AlertCondition::Price { .. } => Ok(2),
+        AlertCondition::PercentChange {
+            window_bars,
+            percent,
+        } => {
+            if *window_bars == 0 || percent.is_zero() {
+                return Err(AlertError::InvalidCondition(
+                    "percent change needs a window of at least one bar and a non-zero percent"
+                        .to_string(),
+                ));
+            }
+            if *window_bars as usize > MAX_PERIOD {
+                return Err(AlertError::InvalidCondition(format!(
+                    "percent change window must be at most {} bars",
+                    MAX_PERIOD
+                )));
+            }
+            Ok(*window_bars as usize + 1)
+        }
+        AlertCondition::Indicator {
+            indicator, period, ..
+        } => {
+            let default = default_period(indicator)
+                .ok_or(AlertError::UnsupportedIndicator(indicator.clone()))?;
+            let period = period.map_or(default, |period| period as usize);
+            if period == 0 || period > MAX_PERIOD {
+                return Err(AlertError::InvalidCondition(format!(
+                    "indicator period must be between 1 and {} bars",
+                    MAX_PERIOD
+                )));
+            }
+            // One more bar than the first value needs, for the previous value
+            Ok(indicator_warm_up(indicator, period) + 1)
+        }
+        AlertCondition::Expression { expression } => {
+            Ok(Expression::compile(expression)?.warm_up_bars() + 1)
+        }
+        AlertCondition::Pattern { pattern } => {
+            if patterns::PATTERNS.contains(&pattern.as_str()) {
+                // Engulfing patterns compare with the previous bar
+                Ok(2)
+            } else {
+                Err(AlertError::UnknownPattern(pattern.clone()))
+            }
+        }
+    }
+}
+
+/// Bars before [`indicator_values`] has a first value
+fn indicator_warm_up(indicator: &TechnicalIndicator, period: usize) -> usize {
+    match indicator {
+        TechnicalIndicator::RSI | TechnicalIndicator::ATR => period + 1,
+        // The slow EMA, then the signal line over it
+        TechnicalIndicator::MACD => 26 + period - 1,
+        _ => period,
+    }
+}
+
+/// Usual lookback of each indicator alerts support
+fn default_period(indicator: &TechnicalIndicator) -> Option<usize> {
+    match indicator {
+        TechnicalIndicator::RSI | TechnicalIndicator::ATR | TechnicalIndicator::Stochastic => {
+            Some(14)
+        }
+        TechnicalIndicator::MovingAverage | TechnicalIndicator::BollingerBands => Some(20),
+        // The period sets the signal line; the EMAs stay at 12 and 26
+        TechnicalIndicator::MACD => Some(9),
+        TechnicalIndicator::Fibonacci
+        | TechnicalIndicator::SupportResistance
+        | TechnicalIndicator::PatternRecognition => None,
+    }
+}
+
+/// Indicator values for the previous and latest bar
+pub fn indicator_values(
+    indicator: &TechnicalIndicator,
+    period: usize,
+    bars: &[OHLCV],
+) -> (Option<f64>, Option<f64>) {
+    let closes = indicators::closes(bars);
+    let values = match indicator {
+        TechnicalIndicator::RSI => indicators::rsi(&closes, period),
+        TechnicalIndicator::ATR => indicators::atr(bars, period),
+        TechnicalIndicator::Stochastic => indicators::stochastic(bars, period),
+        TechnicalIndicator::MACD => indicators::macd(&closes, 12, 26, period)
+            .iter()
+            .map(|point| point.histogram)
+            .collect(),
+        TechnicalIndicator::BollingerBands => {
+            let bands = indicators::bollinger(&closes, period, 2.0);
+            let tail = &closes[closes.len() - bands.len()..];
+            bands
+                .iter()
+                .zip(tail)
+                .map(|(band, close)| band.percent_b(*close))
+                .collect()
+        }
+        TechnicalIndicator::MovingAverage => {
+            let averages = indicators::sma(&closes, period);
+            let tail = &closes[closes.len() - averages.len()..];
+            averages
+                .iter()
+                .zip(tail)
+                .map(|(average, close)| (close / average - 1.0) * 100.0)
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    match values.as_slice() {
+        [.., previous, current] => (Some(*previous), Some(*current)),
+        [current] => (None, Some(*current)),
+        [] => (None, None),
+    }
+}
+
+/// Value that met the condition and a description, if the latest bar satisfies it
+fn evaluate(condition: &AlertCondition, bars: &[OHLCV]) -> Option<(Option<Decimal>, String)> {
+    let last = bars.last()?;
+    match condition {
+        AlertCondition::Price { comparison, level } => {
+            let previous = bars.len().checked_sub(2).map(|index| bars[index].close);
+            comparison.matches(previous, last.close, *level).then(|| {
+                (
+                    None,
+                    format!("close {} {} {}", last.close, verb(comparison), level),
+                )
+            })
+        }
+        AlertCondition::PercentChange {
+            window_bars,
+            percent,
+        } => {
+            let base = bars.len().checked_sub(*window_bars as usize + 1)?;
+            let start = bars[base].close;
+            if start.is_zero() {
+                return None;
+            }
+            let change = ((last.close - start) / start * Decimal::ONE_HUNDRED).round_dp(4);
+            let reached = if percent.is_sign_positive() {
+                change >= *percent
+            } else {
+                change <= *percent
+            };
+            reached.then(|| {
+                (
+                    Some(change),
+                    format!("moved {}% over {} bars", change, window_bars),
+                )
+            })
+        }
+        AlertCondition::Indicator {
+            indicator,
+            period,
+            comparison,
+            threshold,
+        } => {
+            let period = period
+                .map(|period| period as usize)
+                .or_else(|| default_period(indicator))?;
+            let (previous, current) = indicator_values(indicator, period, bars);
+            let current = current?;
+            let matched = comparison.matches(previous, current, threshold.to_f64()?);
+            matched.then(|| {
+                let value = Decimal::from_f64(current).map(|value| value.round_dp(4));
+                (
+                    value,
+                    format!(
+                        "{:?}({}) {} {} {}",
+                        indicator,
+                        period,
+                        value.unwrap_or_default(),
+                        verb(comparison),
+                        threshold
+                    ),
+                )
+            })
+        }
+        AlertCondition::Pattern { pattern } => patterns::detect(bars)
+            .into_iter()
+            .find(|detected| detected.pattern_type == *pattern)
+            .map(|detected| (None, format!("{} ({})", pattern, detected.description))),
+        // Evaluated incrementally by the engine
+        AlertCondition::Expression { .. } => None,
+    }
+}
+
+fn verb(comparison: &Comparison) -> &'static str {
+    match comparison {
+        Comparison::Above => "is above",
+        Comparison::Below => "is below",
+        Comparison::CrossesAbove => "crossed above",
+        Comparison::CrossesBelow => "crossed below",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use shared_types::{Exchange, Symbol};
+
+    fn apple() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    fn bar(hour: i64, close: i64) -> OHLCV {
+        let close = Decimal::new(close, 0);
+        OHLCV::new(
+            apple(),
+            TimeFrame::OneHour,
+            Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap() + Duration::hours(hour),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    fn rule(name: &str, condition: AlertCondition) -> AlertRule {
+        AlertRule::new(name, apple(), TimeFrame::OneHour, condition)
+    }
+
+    #[test]
+    fn test_price_cross_and_percent_change() {
+        let mut engine = AlertEngine::new();
+        let cross = rule(
+            "breakout",
+            AlertCondition::Price {
+                comparison: Comparison::CrossesAbove,
+                level: Decimal::new(105, 0),
+            },
+        );
+        let cross_id = cross.id;
+        engine.add_rule(cross).unwrap();
+        engine
+            .add_rule(
+                rule(
+                    "drop",
+                    AlertCondition::PercentChange {
+                        window_bars: 2,
+                        percent: Decimal::new(-5, 0),
+                    },
+                )
+                .with_mode(AlertMode::OneShot),
+            )
+            .unwrap();
+
+        assert!(engine.on_bar(&bar(0, 100)).is_empty());
+        let events = engine.on_bar(&bar(1, 106));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule_id, cross_id);
+        assert_eq!(events[0].message, "breakout: close 106 crossed above 105");
+        // Still above the level, so no new cross
+        assert!(engine.on_bar(&bar(2, 107)).is_empty());
+
+        // 107 -> 100 over two bars from 106 is about -5.66%
+        let events = engine.on_bar(&bar(3, 100));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].value, Some(Decimal::new(-56604, 4)));
+        assert!(!engine
+            .rules()
+            .any(|rule| rule.name == "drop" && rule.active));
+        assert!(engine.on_bar(&bar(4, 90)).is_empty());
+
+        // Older bars are ignored
+        assert!(engine.on_bar(&bar(0, 200)).is_empty());
+    }
+
+    #[test]
+    fn test_indicator_rule_respects_cooldown() {
+        let mut engine = AlertEngine::new();
+        let oversold = rule(
+            "oversold",
+            AlertCondition::Indicator {
+                indicator: TechnicalIndicator::RSI,
+                period: Some(3),
+                comparison: Comparison::Below,
+                threshold: Decimal::new(30, 0),
+            },
+        )
+        .with_cooldown(std::time::Duration::from_secs(2 * 3600));
+        engine.add_rule(oversold).unwrap();
+
+        let mut fired = Vec::new();
+        for (hour, close) in [100, 99, 98, 97, 96, 95, 94].into_iter().enumerate() {
+            for event in engine.on_bar(&bar(hour as i64, close)) {
+                fired.push((hour, event.value));
+            }
+        }
+        // RSI is 0 from the fourth bar; the cooldown spaces firings two hours apart
+        assert_eq!(fired, [(3, Some(Decimal::ZERO)), (5, Some(Decimal::ZERO))]);
+    }
+
+    #[test]
+    fn test_rejects_conditions_it_cannot_evaluate() {
+        let mut engine = AlertEngine::new();
+        assert_eq!(
+            engine.add_rule(rule(
+                "fib",
+                AlertCondition::Indicator {
+                    indicator: TechnicalIndicator::Fibonacci,
+                    period: None,
+                    comparison: Comparison::Above,
+                    threshold: Decimal::ONE,
+                }
+            )),
+            Err(AlertError::UnsupportedIndicator(
+                TechnicalIndicator::Fibonacci
+            ))
+        );
+        assert_eq!(
+            engine.add_rule(rule(
+                "cup",
+                AlertCondition::Pattern {
+                    pattern: "cup_and_handle".to_string()
+                }
+            )),
+            Err(AlertError::UnknownPattern("cup_and_handle".to_string()))
+        );
+        assert!(engine
+            .add_rule(rule(
+                "engulfing",
+                AlertCondition::Pattern {
+                    pattern: "bullish_engulfing".to_string()
+                }
+            ))
+            .is_ok());
+        assert_eq!(engine.rules().count(), 1);
+    }
+
+    #[test]
+    fn test_expression_rules_fire_incrementally() {
+        let mut engine = AlertEngine::new();
+        // Bars before the rule exists still warm its indicators up
+        engine.warm_up(&[bar(0, 100), bar(1, 100)]);
+        let dip = rule(
+            "dip",
+            AlertCondition::Expression {
+                expression: "crosses_below(close, sma(3)) and close > 90".to_string(),
+            },
+        );
+        let dip_id = dip.id;
+        engine.add_rule(dip).unwrap();
+
+        assert!(engine.on_bar(&bar(2, 100)).is_empty());
+        let events = engine.on_bar(&bar(3, 97));
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].message,
+            "dip: crosses_below(close, sma(3)) and close > 90 holds"
+        );
+        assert!(engine.on_bar(&bar(4, 96)).is_empty());
+
+        // A correction of the latest bar is judged against the replayed series
+        assert!(engine.on_bar(&bar(5, 99)).is_empty());
+        assert!(engine.on_bar(&bar(5, 95)).is_empty());
+        assert_eq!(
+            engine.rule(dip_id).unwrap().last_triggered,
+            Some(bar(3, 97).timestamp)
+        );
+
+        let error = engine
+            .add_rule(rule(
+                "typo",
+                AlertCondition::Expression {
+                    expression: "rsi(14) <".to_string(),
+                },
+            ))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AlertError::InvalidExpression(ExpressionError::Syntax { column: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn test_series_keep_what_their_rules_look_back_on() {
+        let mut engine = AlertEngine::new();
+        let stored: Vec<OHLCV> = (0..500).map(|hour| bar(hour, 100)).collect();
+        let trend = rule(
+            "above long average",
+            AlertCondition::Expression {
+                expression: "close > sma(300)".to_string(),
+            },
+        );
+        let trend_id = trend.id;
+        engine.add_rule_with_history(trend, &stored).unwrap();
+        assert_eq!(engine.history_len(&apple(), &TimeFrame::OneHour), 301);
+
+        let long = rule(
+            "long ma",
+            AlertCondition::Indicator {
+                indicator: TechnicalIndicator::MovingAverage,
+                period: Some(400),
+                comparison: Comparison::Above,
+                threshold: Decimal::ZERO,
+            },
+        );
+        let long_id = long.id;
+        engine.add_rule_with_history(long, &stored).unwrap();
+        assert_eq!(engine.history_len(&apple(), &TimeFrame::OneHour), 401);
+
+        // Both have a value on the first live bar, and a correction unfires neither
+        let events = engine.on_bar(&bar(500, 101));
+        assert_eq!(events.len(), 2);
+        assert_eq!(engine.history_len(&apple(), &TimeFrame::OneHour), 401);
+        assert!(engine.on_bar(&bar(500, 99)).is_empty());
+        assert_eq!(engine.on_bar(&bar(501, 102)).len(), 2);
+
+        engine.remove_rule(long_id);
+        engine.on_bar(&bar(502, 102));
+        assert_eq!(engine.history_len(&apple(), &TimeFrame::OneHour), 301);
+        assert!(engine.rule(trend_id).is_some());
+
+        assert!(matches!(
+            engine.add_rule(rule(
+                "too long",
+                AlertCondition::Indicator {
+                    indicator: TechnicalIndicator::RSI,
+                    period: Some(MAX_PERIOD as u32 + 1),
+                    comparison: Comparison::Below,
+                    threshold: Decimal::new(30, 0),
+                },
+            )),
+            Err(AlertError::InvalidCondition(_))
+        ));
+    }
+}
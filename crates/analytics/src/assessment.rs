@@ -0,0 +1,858 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use shared_types::{
+    AnalysisError, FxError, FxRates, LiquidityLevel, OhlcvSeries, Portfolio, PositionSide,
+    RiskAssessment, RiskFactor, RiskLevel, RiskMetrics, VolatilityLevel, OHLCV,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::options::PortfolioGreeks;
+use crate::stats::{self, to_f64, TRADING_DAYS_PER_YEAR};
+
+/// Share of a day's traded value a position can be unwound into without moving the market
+const LIQUIDATION_PARTICIPATION: f64 = 0.1;
+
+/// Single holding above this share of gross exposure is flagged as concentrated
+const CONCENTRATION_THRESHOLD: f64 = 0.25;
+
+/// Historical tail losses this much worse than the normal model indicate fat tails
+const FAT_TAIL_RATIO: f64 = 1.25;
+
+/// Implied volatility shock, in percentage points, that option vega is sized against
+const VEGA_SHOCK_POINTS: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskAssessmentConfig {
+    /// VaR/CVaR confidence level (0.95 = 95%)
+    pub confidence_level: f64,
+
+    /// Maximum number of daily returns to use
+    pub lookback_days: usize,
+
+    /// Minimum number of daily returns required for an assessment
+    pub min_observations: usize,
+}
+
+impl Default for RiskAssessmentConfig {
+    fn default() -> Self {
+        Self {
+            confidence_level: 0.95,
+            lookback_days: 252,
+            min_observations: 20,
+        }
+    }
+}
+
+impl RiskAssessmentConfig {
+    pub fn with_confidence_level(mut self, confidence_level: f64) -> Self {
+        self.confidence_level = confidence_level;
+        self
+    }
+
+    pub fn with_lookback_days(mut self, lookback_days: usize) -> Self {
+        self.lookback_days = lookback_days;
+        self
+    }
+
+    pub fn with_min_observations(mut self, min_observations: usize) -> Self {
+        self.min_observations = min_observations;
+        self
+    }
+}
+
+/// Builds [`RiskAssessment`]s from daily price history
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskCalculator {
+    config: RiskAssessmentConfig,
+}
+
+/// Daily closes and traded value for one instrument, keyed by date
+#[derive(Default)]
+struct History {
+    closes: BTreeMap<NaiveDate, Decimal>,
+    value_traded: BTreeMap<NaiveDate, Decimal>,
+}
+
+impl History {
+    fn from_bars<'a>(bars: impl IntoIterator<Item = &'a OHLCV>) -> Self {
+        let mut history = History::default();
+        for bar in bars {
+            history.record(bar, Decimal::ONE);
+        }
+        history
+    }
+
+    /// History in `currency`, converting each bar at the rate as of its timestamp
+    fn from_bars_in<'a>(
+        bars: impl IntoIterator<Item = &'a OHLCV>,
+        currency: &str,
+        fx_rates: &FxRates,
+    ) -> Result<Self, FxError> {
+        let mut history = History::default();
+        for bar in bars {
+            let rate = fx_rates.rate(bar.symbol.price_currency(), currency, bar.timestamp)?;
+            history.record(bar, rate);
+        }
+        Ok(history)
+    }
+
+    fn record(&mut self, bar: &OHLCV, rate: Decimal) {
+        let date = bar.timestamp.date_naive();
+        let close = bar.close * rate;
+        self.closes.insert(date, close);
+        self.value_traded
+            .insert(date, close * bar.volume * bar.symbol.contract_size);
+    }
+
+    fn from_series(series: &OhlcvSeries) -> Self {
+        let contract_size = series.symbol().contract_size;
+        let mut history = History::default();
+        for ((timestamp, close), volume) in series
+            .timestamps()
+            .iter()
+            .zip(series.close())
+            .zip(series.volume())
+        {
+            let date = timestamp.date_naive();
+            history.closes.insert(date, *close);
+            history
+                .value_traded
+                .insert(date, close * volume * contract_size);
+        }
+        history
+    }
+
+    fn average_value_traded(&self, lookback_days: usize) -> Decimal {
+        let recent: Vec<Decimal> = self
+            .value_traded
+            .values()
+            .rev()
+            .take(lookback_days)
+            .copied()
+            .collect();
+        if recent.is_empty() {
+            return Decimal::ZERO;
+        }
+        recent.iter().sum::<Decimal>() / Decimal::from(recent.len())
+    }
+}
+
+impl RiskCalculator {
+    pub fn new(config: RiskAssessmentConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &RiskAssessmentConfig {
+        &self.config
+    }
+
+    /// Assess a single instrument from its daily bars
+    pub fn assess_symbol(&self, bars: &[OHLCV]) -> Result<RiskAssessment, AnalysisError> {
+        self.assess_history(History::from_bars(bars))
+    }
+
+    /// Assess a single instrument from a columnar series of daily bars
+    pub fn assess_series(&self, series: &OhlcvSeries) -> Result<RiskAssessment, AnalysisError> {
+        self.assess_history(History::from_series(series))
+    }
+
+    fn assess_history(&self, history: History) -> Result<RiskAssessment, AnalysisError> {
+        let closes: Vec<Decimal> = history.closes.values().copied().collect();
+        let returns = self.window(simple_returns(&closes)?);
+        let average_value = history.average_value_traded(self.config.lookback_days);
+
+        let metrics = self.metrics(&returns, average_value)?;
+        let liquidity = liquidity_level(average_value)?;
+        Ok(self.build(metrics, liquidity, None))
+    }
+
+    /// Assess a portfolio by historical simulation of its current holdings
+    ///
+    /// `history` holds daily bars for every position. Each bar is converted
+    /// into the portfolio's base currency at `fx_rates` as of its timestamp,
+    /// so returns include currency moves. Holdings are valued at their latest
+    /// converted close and returns are only taken on dates where every
+    /// holding traded. Liquidity reflects the number of days needed to unwind
+    /// the least liquid holding at 10% of its average daily traded value.
+    pub fn assess_portfolio(
+        &self,
+        portfolio: &Portfolio,
+        history: &[OHLCV],
+        fx_rates: &FxRates,
+    ) -> Result<RiskAssessment, AnalysisError> {
+        let positions: Vec<_> = portfolio
+            .positions
+            .iter()
+            .filter(|position| !position.quantity.is_zero())
+            .collect();
+        if positions.is_empty() {
+            return Err(AnalysisError::InsufficientDataForAnalysis {
+                required: 1,
+                available: 0,
+            });
+        }
+
+        let mut by_symbol: HashMap<String, Vec<&OHLCV>> = HashMap::new();
+        for bar in history {
+            by_symbol
+                .entry(bar.symbol.full_identifier())
+                .or_default()
+                .push(bar);
+        }
+
+        let mut holdings = Vec::with_capacity(positions.len());
+        for position in &positions {
+            let bars = by_symbol
+                .remove(&position.symbol.full_identifier())
+                .unwrap_or_default();
+            let history = History::from_bars_in(bars, &portfolio.base_currency, fx_rates).map_err(
+                |error| AnalysisError::InvalidAnalysisParameters {
+                    parameter: "fx_rates".to_string(),
+                    value: error.to_string(),
+                },
+            )?;
+            let Some(last_close) = history.closes.values().next_back().copied() else {
+                return Err(self.insufficient(0));
+            };
+
+            let value = position.quantity * last_close * position.symbol.contract_size;
+            let market_value = match position.side {
+                PositionSide::Long => value,
+                PositionSide::Short => -value,
+            };
+            holdings.push((to_f64("market_value", market_value)?, history));
+        }
+
+        // Dates on which every holding has a close
+        let mut common: BTreeSet<NaiveDate> = holdings[0].1.closes.keys().copied().collect();
+        for (_, history) in &holdings[1..] {
+            common.retain(|date| history.closes.contains_key(date));
+        }
+        let dates: Vec<NaiveDate> = common.into_iter().collect();
+
+        let gross: f64 = holdings.iter().map(|(value, _)| value.abs()).sum();
+        let equity = match to_f64("total_value", portfolio.total_value)? {
+            value if value > 0.0 => value,
+            _ => gross,
+        };
+
+        let mut returns = Vec::with_capacity(dates.len().saturating_sub(1));
+        for pair in dates.windows(2) {
+            let mut pnl = 0.0;
+            for (market_value, history) in &holdings {
+                let previous = history.closes[&pair[0]];
+                let current = history.closes[&pair[1]];
+                pnl += market_value * (to_f64("close", current / previous)? - 1.0);
+            }
+            returns.push(pnl / equity);
+        }
+        let returns = self.window(returns);
+
+        let mut least_liquid_value = Decimal::MAX;
+        let mut days_to_liquidate: f64 = 0.0;
+        let mut largest_weight: f64 = 0.0;
+        for (market_value, history) in &holdings {
+            let average_value = history.average_value_traded(self.config.lookback_days);
+            least_liquid_value = least_liquid_value.min(average_value);
+            let capacity =
+                to_f64("average_value_traded", average_value)? * LIQUIDATION_PARTICIPATION;
+            let days = if capacity > 0.0 {
+                market_value.abs() / capacity
+            } else {
+                f64::INFINITY
+            };
+            days_to_liquidate = days_to_liquidate.max(days);
+            largest_weight = largest_weight.max(market_value.abs() / gross);
+        }
+
+        let metrics = self.metrics(&returns, least_liquid_value)?;
+        let liquidity = liquidation_level(days_to_liquidate);
+        let mut assessment = self.build(metrics, liquidity, Some(days_to_liquidate));
+
+        if largest_weight > CONCENTRATION_THRESHOLD {
+            assessment.risk_factors.push(RiskFactor {
+                factor: "concentration".to_string(),
+                impact: if largest_weight > 2.0 * CONCENTRATION_THRESHOLD {
+                    RiskLevel::High
+                } else {
+                    RiskLevel::Medium
+                },
+                description: format!(
+                    "Largest holding is {:.1}% of gross exposure",
+                    largest_weight * 100.0
+                ),
+            });
+        }
+
+        Ok(assessment)
+    }
+
+    /// Add the risk of an options book to an assessment of the same portfolio
+    ///
+    /// Options are expected to have been left out of the historical
+    /// simulation, as they rarely have enough history of their own. Their
+    /// delta and gamma are sized against a one-standard-deviation daily move
+    /// at the assessment's realized volatility, alongside a day of theta and
+    /// a five-point implied volatility shock. Each loss is graded like VaR as
+    /// a fraction of `equity`, and the overall risk level is raised to the
+    /// worst of them.
+    pub fn assess_option_greeks(
+        &self,
+        assessment: &mut RiskAssessment,
+        greeks: &PortfolioGreeks,
+        equity: Decimal,
+    ) -> Result<(), AnalysisError> {
+        let equity = to_f64("equity", equity)?;
+        if equity <= 0.0 {
+            return Err(AnalysisError::InvalidAnalysisParameters {
+                parameter: "equity".to_string(),
+                value: equity.to_string(),
+            });
+        }
+        let Some(metrics) = &assessment.metrics else {
+            return Err(self.insufficient(0));
+        };
+        let daily_move = metrics.annualized_volatility / TRADING_DAYS_PER_YEAR.sqrt();
+
+        let gamma = to_f64("gamma", greeks.gamma)?;
+        let losses = [
+            (
+                "option_delta",
+                to_f64("delta", greeks.delta)?.abs() * daily_move,
+                format!(
+                    "Options are equivalent to {} of the underlying",
+                    greeks.delta.round_dp(0)
+                ),
+            ),
+            (
+                "option_gamma",
+                // Gamma is per 1% move, and only a short gamma book loses on it
+                (-gamma).max(0.0) * 0.5 * daily_move * daily_move * 100.0,
+                format!(
+                    "Underlying exposure changes by {} for a 1% move",
+                    greeks.gamma.round_dp(0)
+                ),
+            ),
+            (
+                "option_theta",
+                (-to_f64("theta", greeks.theta)?).max(0.0),
+                format!("Theta of {} per day", greeks.theta.round_dp(2)),
+            ),
+            (
+                "option_vega",
+                to_f64("vega", greeks.vega)?.abs() * VEGA_SHOCK_POINTS,
+                format!(
+                    "Value changes by {} per volatility point",
+                    greeks.vega.round_dp(2)
+                ),
+            ),
+        ];
+
+        for (factor, loss, description) in losses {
+            let impact = var_level(loss / equity);
+            if risk_rank(&impact) > risk_rank(&assessment.risk_level) {
+                assessment.risk_level = impact.clone();
+            }
+            assessment.risk_factors.push(RiskFactor {
+                factor: factor.to_string(),
+                impact,
+                description,
+            });
+        }
+        Ok(())
+    }
+
+    /// Compute VaR, CVaR and volatility for a series of daily returns
+    pub fn metrics(
+        &self,
+        returns: &[f64],
+        average_daily_value_traded: Decimal,
+    ) -> Result<RiskMetrics, AnalysisError> {
+        let confidence = self.config.confidence_level;
+        if !(confidence > 0.5 && confidence < 1.0) {
+            return Err(AnalysisError::InvalidAnalysisParameters {
+                parameter: "confidence_level".to_string(),
+                value: confidence.to_string(),
+            });
+        }
+        if returns.len() < self.config.min_observations.max(2) {
+            return Err(self.insufficient(returns.len()));
+        }
+
+        let insufficient = || self.insufficient(returns.len());
+        let (historical_var, historical_cvar) =
+            historical_var(returns, confidence).ok_or_else(insufficient)?;
+        let (parametric_var, parametric_cvar) =
+            parametric_var(returns, confidence).ok_or_else(insufficient)?;
+        let annualized_volatility =
+            stats::std_dev(returns).ok_or_else(insufficient)? * TRADING_DAYS_PER_YEAR.sqrt();
+
+        Ok(RiskMetrics {
+            confidence_level: confidence,
+            observations: returns.len(),
+            historical_var,
+            historical_cvar,
+            parametric_var,
+            parametric_cvar,
+            annualized_volatility,
+            average_daily_value_traded,
+        })
+    }
+
+    fn build(
+        &self,
+        metrics: RiskMetrics,
+        liquidity: LiquidityLevel,
+        days_to_liquidate: Option<f64>,
+    ) -> RiskAssessment {
+        let risk_level = var_level(metrics.historical_var);
+        let volatility = volatility_level(metrics.annualized_volatility);
+        let confidence = metrics.confidence_level * 100.0;
+
+        let mut risk_factors = vec![
+            RiskFactor {
+                factor: "value_at_risk".to_string(),
+                impact: risk_level.clone(),
+                description: format!(
+                    "1-day {:.0}% historical VaR of {:.2}% (CVaR {:.2}%) over {} days",
+                    confidence,
+                    metrics.historical_var * 100.0,
+                    metrics.historical_cvar * 100.0,
+                    metrics.observations
+                ),
+            },
+            RiskFactor {
+                factor: "volatility".to_string(),
+                impact: volatility_risk(&volatility),
+                description: format!(
+                    "Annualized realized volatility of {:.1}%",
+                    metrics.annualized_volatility * 100.0
+                ),
+            },
+            RiskFactor {
+                factor: "liquidity".to_string(),
+                impact: liquidity_risk(&liquidity),
+                description: match days_to_liquidate {
+                    Some(days) => format!("About {:.1} days to liquidate", days),
+                    None => format!(
+                        "Average daily traded value of {}",
+                        metrics.average_daily_value_traded.round_dp(0)
+                    ),
+                },
+            },
+        ];
+
+        if metrics.parametric_cvar > 0.0
+            && metrics.historical_cvar > metrics.parametric_cvar * FAT_TAIL_RATIO
+        {
+            risk_factors.push(RiskFactor {
+                factor: "tail_risk".to_string(),
+                impact: RiskLevel::High,
+                description: format!(
+                    "Historical expected shortfall {:.2}% exceeds the normal estimate of {:.2}%",
+                    metrics.historical_cvar * 100.0,
+                    metrics.parametric_cvar * 100.0
+                ),
+            });
+        }
+
+        RiskAssessment {
+            risk_level,
+            risk_factors,
+            volatility,
+            liquidity,
+            metrics: Some(metrics),
+        }
+    }
+
+    /// Keep the most recent `lookback_days` returns
+    fn window(&self, mut returns: Vec<f64>) -> Vec<f64> {
+        if returns.len() > self.config.lookback_days {
+            returns.drain(..returns.len() - self.config.lookback_days);
+        }
+        returns
+    }
+
+    fn insufficient(&self, available: usize) -> AnalysisError {
+        AnalysisError::InsufficientDataForAnalysis {
+            required: self.config.min_observations as u32,
+            available: available as u32,
+        }
+    }
+}
+
+/// Simple returns between consecutive prices
+pub fn simple_returns(prices: &[Decimal]) -> Result<Vec<f64>, AnalysisError> {
+    prices
+        .windows(2)
+        .filter(|pair| !pair[0].is_zero())
+        .map(|pair| Ok(to_f64("price", pair[1] / pair[0])? - 1.0))
+        .collect()
+}
+
+/// Historical VaR and CVaR as positive loss fractions
+pub fn historical_var(returns: &[f64], confidence: f64) -> Option<(f64, f64)> {
+    if returns.is_empty() {
+        return None;
+    }
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    // Number of observations in the loss tail (guard against float noise in n * (1 - c))
+    let tail = ((sorted.len() as f64 * (1.0 - confidence) - 1e-9).ceil() as usize).max(1);
+    let var = -sorted[tail - 1];
+    let cvar = -stats::mean(&sorted[..tail])?;
+    Some((var.max(0.0), cvar.max(0.0)))
+}
+
+/// Parametric (variance-covariance) VaR and CVaR under a normal distribution
+pub fn parametric_var(returns: &[f64], confidence: f64) -> Option<(f64, f64)> {
+    let mean = stats::mean(returns)?;
+    let std_dev = stats::std_dev(returns)?;
+    let z = stats::normal_inverse_cdf(confidence)?;
+
+    let var = z * std_dev - mean;
+    let cvar = std_dev * stats::normal_pdf(z) / (1.0 - confidence) - mean;
+    Some((var.max(0.0), cvar.max(0.0)))
+}
+
+/// Bucket annualized volatility
+pub fn volatility_level(annualized_volatility: f64) -> VolatilityLevel {
+    match annualized_volatility {
+        v if v < 0.10 => VolatilityLevel::VeryLow,
+        v if v < 0.20 => VolatilityLevel::Low,
+        v if v < 0.35 => VolatilityLevel::Normal,
+        v if v < 0.60 => VolatilityLevel::High,
+        _ => VolatilityLevel::Extreme,
+    }
+}
+
+/// Bucket an instrument by its average daily traded value
+pub fn liquidity_level(
+    average_daily_value_traded: Decimal,
+) -> Result<LiquidityLevel, AnalysisError> {
+    let level = match to_f64("average_daily_value_traded", average_daily_value_traded)? {
+        v if v >= 1e9 => LiquidityLevel::VeryHigh,
+        v if v >= 1e8 => LiquidityLevel::High,
+        v if v >= 1e7 => LiquidityLevel::Normal,
+        v if v >= 1e6 => LiquidityLevel::Low,
+        _ => LiquidityLevel::VeryLow,
+    };
+    Ok(level)
+}
+
+/// Bucket a holding by how many days it would take to unwind
+fn liquidation_level(days_to_liquidate: f64) -> LiquidityLevel {
+    match days_to_liquidate {
+        d if d <= 0.1 => LiquidityLevel::VeryHigh,
+        d if d <= 1.0 => LiquidityLevel::High,
+        d if d <= 5.0 => LiquidityLevel::Normal,
+        d if d <= 20.0 => LiquidityLevel::Low,
+        _ => LiquidityLevel::VeryLow,
+    }
+}
+
+fn var_level(var: f64) -> RiskLevel {
+    match var {
+        v if v < 0.01 => RiskLevel::VeryLow,
+        v if v < 0.02 => RiskLevel::Low,
+        v if v < 0.035 => RiskLevel::Medium,
+        v if v < 0.06 => RiskLevel::High,
+        _ => RiskLevel::VeryHigh,
+    }
+}
+
+fn risk_rank(level: &RiskLevel) -> u8 {
+    match level {
+        RiskLevel::VeryLow => 0,
+        RiskLevel::Low => 1,
+        RiskLevel::Medium => 2,
+        RiskLevel::High => 3,
+        RiskLevel::VeryHigh => 4,
+    }
+}
+
+fn volatility_risk(level: &VolatilityLevel) -> RiskLevel {
+    match level {
+        VolatilityLevel::VeryLow => RiskLevel::VeryLow,
+        VolatilityLevel::Low => RiskLevel::Low,
+        VolatilityLevel::Normal => RiskLevel::Medium,
+        VolatilityLevel::High => RiskLevel::High,
+        VolatilityLevel::Extreme => RiskLevel::VeryHigh,
+    }
+}
+
+fn liquidity_risk(level: &LiquidityLevel) -> RiskLevel {
+    match level {
+        LiquidityLevel::VeryHigh => RiskLevel::VeryLow,
+        LiquidityLevel::High => RiskLevel::Low,
+        LiquidityLevel::Normal => RiskLevel::Medium,
+        LiquidityLevel::Low => RiskLevel::High,
+        LiquidityLevel::VeryLow => RiskLevel::VeryHigh,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use shared_types::{Exchange, Position, Symbol, TimeFrame};
+
+    fn symbol(code: &str) -> Symbol {
+        Symbol::stock(code, code, Exchange::NYSE).unwrap()
+    }
+
+    /// Daily bars whose closes follow `returns` (in basis points) from 100
+    fn bars(symbol: &Symbol, returns_bps: &[i64], volume: i64) -> Vec<OHLCV> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 21, 0, 0).unwrap();
+        let mut close = Decimal::new(100, 0);
+        let mut bars = Vec::new();
+        for day in 0..=returns_bps.len() {
+            if day > 0 {
+                close *= Decimal::ONE + Decimal::new(returns_bps[day - 1], 4);
+            }
+            bars.push(
+                OHLCV::new(
+                    symbol.clone(),
+                    TimeFrame::OneDay,
+                    start + Duration::days(day as i64),
+                    close,
+                    close,
+                    close,
+                    close,
+                    Decimal::new(volume, 0),
+                )
+                .unwrap(),
+            );
+        }
+        bars
+    }
+
+    fn alternating(bps: i64, days: usize) -> Vec<i64> {
+        (0..days)
+            .map(|day| if day % 2 == 0 { bps } else { -bps })
+            .collect()
+    }
+
+    #[test]
+    fn test_historical_and_parametric_var() {
+        let returns: Vec<f64> = (-5..15).map(|r| r as f64 / 100.0).collect();
+
+        let (var, cvar) = historical_var(&returns, 0.9).unwrap();
+        assert!((var - 0.04).abs() < 1e-12);
+        assert!((cvar - 0.045).abs() < 1e-12);
+
+        // Symmetric zero-mean returns: VaR is z * sigma
+        let symmetric = [0.01, -0.01, 0.01, -0.01];
+        let (var, cvar) = parametric_var(&symmetric, 0.95).unwrap();
+        let sigma = stats::std_dev(&symmetric).unwrap();
+        assert!((var - 1.644_853_626_951 * sigma).abs() < 1e-8);
+        assert!(cvar > var);
+    }
+
+    #[test]
+    fn test_assess_symbol_buckets() {
+        let spy = symbol("SPY");
+        // 1% daily moves are about 16% annualized; ~100 * 5m shares is ~500m traded a day
+        let assessment = RiskCalculator::default()
+            .assess_symbol(&bars(&spy, &alternating(100, 60), 5_000_000))
+            .unwrap();
+
+        assert_eq!(assessment.volatility, VolatilityLevel::Low);
+        assert_eq!(assessment.liquidity, LiquidityLevel::High);
+        assert_eq!(assessment.risk_level, RiskLevel::Low);
+        let metrics = assessment.metrics.clone().unwrap();
+        assert_eq!(metrics.observations, 60);
+        assert!((metrics.historical_var - 0.01).abs() < 1e-9);
+
+        let series = OhlcvSeries::try_from(bars(&spy, &alternating(100, 60), 5_000_000)).unwrap();
+        assert_eq!(
+            RiskCalculator::default().assess_series(&series).unwrap(),
+            assessment
+        );
+
+        let short_history = RiskCalculator::default().assess_symbol(&bars(&spy, &[100; 5], 1));
+        assert_eq!(
+            short_history,
+            Err(AnalysisError::InsufficientDataForAnalysis {
+                required: 20,
+                available: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_assess_portfolio_flags_concentration_and_illiquidity() {
+        let (liquid, illiquid) = (symbol("BIG"), symbol("TINY"));
+        let mut history = bars(&liquid, &alternating(100, 30), 1_000_000);
+        history.extend(bars(&illiquid, &alternating(400, 30), 100));
+
+        let position = |symbol: &Symbol, quantity: i64| Position {
+            symbol: symbol.clone(),
+            quantity: Decimal::new(quantity, 0),
+            average_price: Decimal::new(100, 0),
+            side: PositionSide::Long,
+            opened_at: Utc::now(),
+            unrealized_pnl: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+        };
+        let portfolio = Portfolio {
+            name: "mixed".to_string(),
+            base_currency: "USD".to_string(),
+            positions: vec![position(&liquid, 100), position(&illiquid, 300)],
+            cash_balance: Decimal::ZERO,
+            total_value: Decimal::new(40_000, 0),
+            total_pnl: Decimal::ZERO,
+            last_updated: Utc::now(),
+        };
+
+        let assessment = RiskCalculator::default()
+            .assess_portfolio(&portfolio, &history, &FxRates::new())
+            .unwrap();
+
+        // 300 TINY shares against 10% of ~100 shares a day takes ~30 days to unwind
+        assert_eq!(assessment.liquidity, LiquidityLevel::VeryLow);
+        assert!(assessment
+            .risk_factors
+            .iter()
+            .any(|factor| factor.factor == "concentration" && factor.impact == RiskLevel::High));
+        assert_eq!(assessment.metrics.unwrap().observations, 30);
+    }
+
+    #[test]
+    fn test_assess_portfolio_converts_holdings_into_base_currency() {
+        let vod = Symbol::new(
+            "VOD".to_string(),
+            "VOD".to_string(),
+            shared_types::AssetClass::Stock,
+            Exchange::LSE,
+            "GBP".to_string(),
+        )
+        .unwrap();
+        let spy = symbol("SPY");
+        // Both trade flat at 100 in their own currency
+        let mut history = bars(&vod, &[0; 30], 1_000_000);
+        history.extend(bars(&spy, &[0; 30], 1_000_000));
+
+        // Sterling alternates between 1.30 and 1.25 dollars, ending at 1.25
+        let mut fx_rates = FxRates::new();
+        for (day, bar) in history.iter().take(31).enumerate() {
+            let rate = if day % 2 == 0 { 125 } else { 130 };
+            fx_rates
+                .add_rate("GBP", "USD", bar.timestamp, Decimal::new(rate, 2))
+                .unwrap();
+        }
+
+        let position = |symbol: &Symbol| Position {
+            symbol: symbol.clone(),
+            quantity: Decimal::new(100, 0),
+            average_price: Decimal::new(100, 0),
+            side: PositionSide::Long,
+            opened_at: Utc::now(),
+            unrealized_pnl: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+        };
+        let portfolio = Portfolio {
+            name: "global".to_string(),
+            base_currency: "USD".to_string(),
+            positions: vec![position(&vod), position(&spy)],
+            cash_balance: Decimal::ZERO,
+            total_value: Decimal::new(22_500, 0),
+            total_pnl: Decimal::ZERO,
+            last_updated: Utc::now(),
+        };
+
+        let assessment = RiskCalculator::default()
+            .assess_portfolio(&portfolio, &history, &fx_rates)
+            .unwrap();
+
+        // $12,500 of VOD against $10,000 of SPY
+        let concentration = assessment
+            .risk_factors
+            .iter()
+            .find(|factor| factor.factor == "concentration")
+            .unwrap();
+        assert_eq!(concentration.impact, RiskLevel::High);
+        assert_eq!(
+            concentration.description,
+            "Largest holding is 55.6% of gross exposure"
+        );
+        // Prices never move, so every loss comes from sterling falling 1.30 -> 1.25
+        let metrics = assessment.metrics.unwrap();
+        let loss = 12_500.0 * (1.0 - 1.25 / 1.30) / 22_500.0;
+        assert!((metrics.historical_var - loss).abs() < 1e-9);
+
+        assert_eq!(
+            RiskCalculator::default().assess_portfolio(&portfolio, &history, &FxRates::new()),
+            Err(AnalysisError::InvalidAnalysisParameters {
+                parameter: "fx_rates".to_string(),
+                value: "No GBP/USD rate at or before 2024-01-01 21:00:00 UTC".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_option_greeks_raise_risk_level() {
+        use crate::options::{MarketInputs, OptionPricer, PortfolioGreeks};
+        use chrono::NaiveDate;
+        use shared_types::{OptionRight, OptionSpec};
+
+        let spy = symbol("SPY");
+        let mut assessment = RiskCalculator::default()
+            .assess_symbol(&bars(&spy, &alternating(100, 60), 5_000_000))
+            .unwrap();
+        assert_eq!(assessment.risk_level, RiskLevel::Low);
+
+        // Ten short at-the-money calls a month out
+        let spec = OptionSpec::new(
+            "SPY",
+            NaiveDate::from_ymd_opt(2024, 4, 1).unwrap(),
+            Decimal::new(100, 0),
+            OptionRight::Call,
+        )
+        .unwrap();
+        let market = MarketInputs::new(
+            Decimal::new(100, 0),
+            Decimal::new(16, 2),
+            NaiveDate::from_ymd_opt(2024, 3, 2).unwrap(),
+        );
+        let call = Symbol::option(spec, Exchange::Other("CBOE".to_string()), "USD").unwrap();
+        let valuation = OptionPricer::default()
+            .value_symbol(&call, &market)
+            .unwrap();
+        let mut greeks = PortfolioGreeks::default();
+        greeks.add_position(
+            &Position {
+                symbol: call,
+                quantity: Decimal::new(10, 0),
+                average_price: valuation.price,
+                side: PositionSide::Short,
+                opened_at: Utc::now(),
+                unrealized_pnl: Decimal::ZERO,
+                realized_pnl: Decimal::ZERO,
+            },
+            &valuation,
+        );
+        assert!(greeks.delta < Decimal::ZERO && greeks.theta > Decimal::ZERO);
+
+        let calculator = RiskCalculator::default();
+        calculator
+            .assess_option_greeks(&mut assessment, &greeks, Decimal::new(10_000, 0))
+            .unwrap();
+        let impact = |name: &str| {
+            assessment
+                .risk_factors
+                .iter()
+                .find(|factor| factor.factor == name)
+                .map(|factor| factor.impact.clone())
+        };
+        // About 115 of vega per point against a five-point shock is 5.7% of equity
+        assert_eq!(impact("option_vega"), Some(RiskLevel::High));
+        assert_eq!(impact("option_theta"), Some(RiskLevel::VeryLow));
+        assert_eq!(assessment.risk_level, RiskLevel::High);
+
+        assert!(calculator
+            .assess_option_greeks(&mut assessment, &greeks, Decimal::ZERO)
+            .is_err());
+    }
+}
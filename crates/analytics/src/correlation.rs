@@ -0,0 +1,559 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use shared_types::{
+    AnalysisError, BetaEstimate, CointegrationResult, CorrelationAnalysisResponse,
+    CorrelationMethod, OhlcvSeries, RollingCorrelation, Symbol, OHLCV,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::stats;
+
+/// Engle-Granger 5% critical value for two variables (MacKinnon, 2010)
+const ENGLE_GRANGER_CRITICAL_5PCT: f64 = -3.34;
+
+/// How timestamps missing from some series are handled during alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingDataPolicy {
+    /// Keep only timestamps present in every series
+    Drop,
+    /// Carry the last close forward over gaps of up to `max_gap` bars
+    ForwardFill { max_gap: usize },
+}
+
+impl Default for MissingDataPolicy {
+    fn default() -> Self {
+        MissingDataPolicy::ForwardFill { max_gap: 3 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CorrelationConfig {
+    pub method: CorrelationMethod,
+
+    pub missing_data: MissingDataPolicy,
+
+    /// Window (in returns) for rolling correlation matrices
+    pub rolling_window: Option<usize>,
+
+    /// Symbol to compute betas against
+    pub benchmark: Option<Symbol>,
+}
+
+impl CorrelationConfig {
+    pub fn with_method(mut self, method: CorrelationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_missing_data(mut self, policy: MissingDataPolicy) -> Self {
+        self.missing_data = policy;
+        self
+    }
+
+    pub fn with_rolling_window(mut self, window: usize) -> Self {
+        self.rolling_window = Some(window);
+        self
+    }
+
+    pub fn with_benchmark(mut self, benchmark: Symbol) -> Self {
+        self.benchmark = Some(benchmark);
+        self
+    }
+}
+
+/// Several close series on a common set of timestamps
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignedSeries {
+    pub symbols: Vec<Symbol>,
+    pub timestamps: Vec<DateTime<Utc>>,
+
+    /// `closes[i]` holds the closes of `symbols[i]`, one per timestamp
+    pub closes: Vec<Vec<f64>>,
+}
+
+impl AlignedSeries {
+    /// Align one bar series per symbol on their timestamps
+    ///
+    /// Timestamps before a series' first bar, or inside a gap the policy does
+    /// not fill, are dropped for every symbol.
+    pub fn align(series: &[Vec<OHLCV>], policy: MissingDataPolicy) -> Result<Self, AnalysisError> {
+        let mut symbols = Vec::with_capacity(series.len());
+        let mut closes_by_time = Vec::with_capacity(series.len());
+
+        for bars in series {
+            let Some(first) = bars.first() else {
+                return Err(AnalysisError::InsufficientDataForAnalysis {
+                    required: 2,
+                    available: 0,
+                });
+            };
+            symbols.push(first.symbol.clone());
+            closes_by_time.push(
+                bars.iter()
+                    .map(|bar| (bar.timestamp, bar.close.to_f64().unwrap_or(0.0)))
+                    .collect(),
+            );
+        }
+
+        Ok(Self::from_closes(symbols, closes_by_time, policy))
+    }
+
+    /// Align columnar series, as [`AlignedSeries::align`] does for bars
+    pub fn align_series(
+        series: &[OhlcvSeries],
+        policy: MissingDataPolicy,
+    ) -> Result<Self, AnalysisError> {
+        if series.iter().any(OhlcvSeries::is_empty) {
+            return Err(AnalysisError::InsufficientDataForAnalysis {
+                required: 2,
+                available: 0,
+            });
+        }
+
+        let symbols = series.iter().map(|s| s.symbol().clone()).collect();
+        let closes_by_time = series
+            .iter()
+            .map(|s| {
+                s.timestamps()
+                    .iter()
+                    .copied()
+                    .zip(s.close().iter().map(|close| close.to_f64().unwrap_or(0.0)))
+                    .collect()
+            })
+            .collect();
+        Ok(Self::from_closes(symbols, closes_by_time, policy))
+    }
+
+    fn from_closes(
+        symbols: Vec<Symbol>,
+        closes_by_time: Vec<BTreeMap<DateTime<Utc>, f64>>,
+        policy: MissingDataPolicy,
+    ) -> Self {
+        let timestamps: BTreeSet<DateTime<Utc>> = closes_by_time
+            .iter()
+            .flat_map(|closes| closes.keys().copied())
+            .collect();
+
+        let mut aligned = AlignedSeries {
+            symbols,
+            timestamps: Vec::new(),
+            closes: vec![Vec::new(); closes_by_time.len()],
+        };
+        // Last seen close and how many bars it has been carried for, per series
+        let mut carried: Vec<Option<(f64, usize)>> = vec![None; closes_by_time.len()];
+
+        for timestamp in timestamps {
+            let mut row = Vec::with_capacity(closes_by_time.len());
+            for (closes, carry) in closes_by_time.iter().zip(carried.iter_mut()) {
+                match closes.get(&timestamp) {
+                    Some(close) => {
+                        *carry = Some((*close, 0));
+                        row.push(Some(*close));
+                    }
+                    None => {
+                        let filled = match (policy, carry.as_mut()) {
+                            (MissingDataPolicy::ForwardFill { max_gap }, Some((close, gap)))
+                                if *gap < max_gap =>
+                            {
+                                *gap += 1;
+                                Some(*close)
+                            }
+                            _ => None,
+                        };
+                        row.push(filled);
+                    }
+                }
+            }
+
+            if row.iter().all(Option::is_some) {
+                aligned.timestamps.push(timestamp);
+                for (column, close) in aligned.closes.iter_mut().zip(row) {
+                    column.push(close.unwrap_or_default());
+                }
+            }
+        }
+
+        aligned
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Simple returns per symbol; `returns()[i][t]` ends at `timestamps[t + 1]`
+    pub fn returns(&self) -> Vec<Vec<f64>> {
+        self.closes
+            .iter()
+            .map(|closes| {
+                closes
+                    .windows(2)
+                    .map(|pair| {
+                        if pair[0] == 0.0 {
+                            0.0
+                        } else {
+                            pair[1] / pair[0] - 1.0
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Run a full correlation analysis over one bar series per symbol
+pub fn analyze(
+    series: &[Vec<OHLCV>],
+    config: &CorrelationConfig,
+) -> Result<CorrelationAnalysisResponse, AnalysisError> {
+    if series.len() < 2 {
+        return Err(AnalysisError::InvalidAnalysisParameters {
+            parameter: "series".to_string(),
+            value: series.len().to_string(),
+        });
+    }
+
+    let aligned = AlignedSeries::align(series, config.missing_data)?;
+    if aligned.len() < 3 {
+        return Err(AnalysisError::InsufficientDataForAnalysis {
+            required: 3,
+            available: aligned.len() as u32,
+        });
+    }
+    let returns = aligned.returns();
+
+    let rolling = match config.rolling_window {
+        Some(window) if window < 2 => {
+            return Err(AnalysisError::InvalidAnalysisParameters {
+                parameter: "rolling_window".to_string(),
+                value: window.to_string(),
+            })
+        }
+        Some(window) => rolling_correlation(&aligned, &returns, window, config.method),
+        None => Vec::new(),
+    };
+
+    let betas = match &config.benchmark {
+        Some(benchmark) => {
+            let key = benchmark.full_identifier();
+            let index = aligned
+                .symbols
+                .iter()
+                .position(|symbol| symbol.full_identifier() == key)
+                .ok_or_else(|| AnalysisError::InvalidAnalysisParameters {
+                    parameter: "benchmark".to_string(),
+                    value: key,
+                })?;
+            aligned
+                .symbols
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(i, symbol)| BetaEstimate {
+                    symbol: symbol.clone(),
+                    benchmark: aligned.symbols[index].clone(),
+                    beta: beta(&returns[i], &returns[index]).unwrap_or(0.0),
+                    correlation: pearson(&returns[i], &returns[index]).unwrap_or(0.0),
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let mut cointegration = Vec::new();
+    for i in 0..aligned.symbols.len() {
+        for j in (i + 1)..aligned.symbols.len() {
+            let log_first: Vec<f64> = aligned.closes[i].iter().map(|close| close.ln()).collect();
+            let log_second: Vec<f64> = aligned.closes[j].iter().map(|close| close.ln()).collect();
+            cointegration.extend(engle_granger(
+                &aligned.symbols[i],
+                &aligned.symbols[j],
+                &log_first,
+                &log_second,
+            ));
+        }
+    }
+
+    Ok(CorrelationAnalysisResponse {
+        symbols: aligned.symbols.clone(),
+        method: config.method,
+        period_start: aligned.timestamps[0],
+        period_end: aligned.timestamps[aligned.len() - 1],
+        observations: returns[0].len(),
+        correlation_matrix: correlation_matrix(&returns, config.method),
+        covariance_matrix: covariance_matrix(&returns),
+        rolling,
+        betas,
+        cointegration,
+        generated_at: Utc::now(),
+    })
+}
+
+/// Pearson correlation, `None` if either series is constant
+pub fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let covariance = stats::covariance(xs, ys)?;
+    let denominator = stats::std_dev(xs)? * stats::std_dev(ys)?;
+    if denominator == 0.0 {
+        return None;
+    }
+    Some((covariance / denominator).clamp(-1.0, 1.0))
+}
+
+/// Spearman rank correlation (ties receive their average rank)
+pub fn spearman(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    pearson(&ranks(xs), &ranks(ys))
+}
+
+/// Beta of `asset` against `benchmark`
+pub fn beta(asset: &[f64], benchmark: &[f64]) -> Option<f64> {
+    let variance = stats::variance(benchmark)?;
+    if variance == 0.0 {
+        return None;
+    }
+    Some(stats::covariance(asset, benchmark)? / variance)
+}
+
+/// Correlation matrix; undefined entries (constant series) are reported as 0
+pub fn correlation_matrix(series: &[Vec<f64>], method: CorrelationMethod) -> Vec<Vec<f64>> {
+    let correlate = match method {
+        CorrelationMethod::Pearson => pearson,
+        CorrelationMethod::Spearman => spearman,
+    };
+    square_matrix(series.len(), |i, j| {
+        if i == j {
+            1.0
+        } else {
+            correlate(&series[i], &series[j]).unwrap_or(0.0)
+        }
+    })
+}
+
+pub fn covariance_matrix(series: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    square_matrix(series.len(), |i, j| {
+        stats::covariance(&series[i], &series[j]).unwrap_or(0.0)
+    })
+}
+
+fn rolling_correlation(
+    aligned: &AlignedSeries,
+    returns: &[Vec<f64>],
+    window: usize,
+    method: CorrelationMethod,
+) -> Vec<RollingCorrelation> {
+    let observations = returns[0].len();
+    (window..=observations)
+        .map(|end| {
+            let slice: Vec<Vec<f64>> = returns
+                .iter()
+                .map(|column| column[end - window..end].to_vec())
+                .collect();
+            RollingCorrelation {
+                timestamp: aligned.timestamps[end],
+                matrix: correlation_matrix(&slice, method),
+            }
+        })
+        .collect()
+}
+
+/// Engle-Granger two-step test on two (log) price series
+///
+/// Regresses `ys` on `xs`, then runs a Dickey-Fuller regression without
+/// constant on the residual spread: `Δe_t = γ e_{t-1} + ε_t`.
+fn engle_granger(
+    first: &Symbol,
+    second: &Symbol,
+    xs: &[f64],
+    ys: &[f64],
+) -> Option<CointegrationResult> {
+    let (intercept, hedge_ratio) = ols(xs, ys)?;
+    let spread: Vec<f64> = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| y - intercept - hedge_ratio * x)
+        .collect();
+
+    let lagged = &spread[..spread.len() - 1];
+    let deltas: Vec<f64> = spread.windows(2).map(|pair| pair[1] - pair[0]).collect();
+
+    let sum_sq: f64 = lagged.iter().map(|e| e * e).sum();
+    if sum_sq == 0.0 || deltas.len() < 3 {
+        return None;
+    }
+    let gamma = lagged.iter().zip(&deltas).map(|(e, d)| e * d).sum::<f64>() / sum_sq;
+    let residual_variance = lagged
+        .iter()
+        .zip(&deltas)
+        .map(|(e, d)| (d - gamma * e).powi(2))
+        .sum::<f64>()
+        / (deltas.len() - 1) as f64;
+    let standard_error = (residual_variance / sum_sq).sqrt();
+    let adf_statistic = if standard_error > 0.0 {
+        gamma / standard_error
+    } else {
+        f64::NEG_INFINITY
+    };
+
+    let half_life = (gamma < 0.0 && gamma > -1.0).then(|| -(2f64.ln()) / (1.0 + gamma).ln());
+
+    Some(CointegrationResult {
+        first: first.clone(),
+        second: second.clone(),
+        hedge_ratio,
+        intercept,
+        adf_statistic,
+        critical_value: ENGLE_GRANGER_CRITICAL_5PCT,
+        is_cointegrated: adf_statistic < ENGLE_GRANGER_CRITICAL_5PCT,
+        half_life,
+    })
+}
+
+/// Ordinary least squares `y = a + b x`, returning `(a, b)`
+fn ols(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    let slope = beta(ys, xs)?;
+    let intercept = stats::mean(ys)? - slope * stats::mean(xs)?;
+    Some((intercept, slope))
+}
+
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        // Ranks are 1-based; ties share the mean of the ranks they span
+        let rank = (start + end) as f64 / 2.0 + 1.0;
+        for index in &order[start..=end] {
+            ranks[*index] = rank;
+        }
+        start = end + 1;
+    }
+    ranks
+}
+
+fn square_matrix(size: usize, entry: impl Fn(usize, usize) -> f64) -> Vec<Vec<f64>> {
+    (0..size)
+        .map(|i| (0..size).map(|j| entry(i, j)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal::Decimal;
+    use shared_types::{Exchange, TimeFrame};
+
+    fn symbol(code: &str) -> Symbol {
+        Symbol::stock(code, code, Exchange::NYSE).unwrap()
+    }
+
+    fn series(code: &str, closes: &[f64], skip: &[usize]) -> Vec<OHLCV> {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 21, 0, 0).unwrap();
+        closes
+            .iter()
+            .enumerate()
+            .filter(|(day, _)| !skip.contains(day))
+            .map(|(day, close)| {
+                let close = Decimal::from_f64_retain(*close).unwrap().round_dp(6);
+                OHLCV::new(
+                    symbol(code),
+                    TimeFrame::OneDay,
+                    start + Duration::days(day as i64),
+                    close,
+                    close,
+                    close,
+                    close,
+                    Decimal::new(1000, 0),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_alignment_policies() {
+        let closes = [10.0, 11.0, 12.0, 13.0, 14.0];
+        let input = vec![series("A", &closes, &[]), series("B", &closes, &[2])];
+
+        let dropped = AlignedSeries::align(&input, MissingDataPolicy::Drop).unwrap();
+        assert_eq!(dropped.len(), 4);
+
+        let filled =
+            AlignedSeries::align(&input, MissingDataPolicy::ForwardFill { max_gap: 1 }).unwrap();
+        assert_eq!(filled.len(), 5);
+        assert_eq!(filled.closes[1], vec![10.0, 11.0, 11.0, 13.0, 14.0]);
+
+        let columnar: Vec<OhlcvSeries> = input
+            .into_iter()
+            .map(|bars| OhlcvSeries::try_from(bars).unwrap())
+            .collect();
+        assert_eq!(
+            AlignedSeries::align_series(&columnar, MissingDataPolicy::ForwardFill { max_gap: 1 })
+                .unwrap(),
+            filled
+        );
+    }
+
+    #[test]
+    fn test_pearson_spearman_and_beta() {
+        let xs = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let ys = [2.0, 4.0, 6.0, 8.0, 10.0];
+        let cubes: Vec<f64> = xs.iter().map(|x: &f64| x.powi(3)).collect();
+
+        assert!((pearson(&xs, &ys).unwrap() - 1.0).abs() < 1e-12);
+        assert!(pearson(&xs, &cubes).unwrap() < 1.0);
+        assert!((spearman(&xs, &cubes).unwrap() - 1.0).abs() < 1e-12);
+        assert!((beta(&ys, &xs).unwrap() - 2.0).abs() < 1e-12);
+        assert_eq!(pearson(&xs, &[1.0; 5]), None);
+        assert_eq!(ranks(&[3.0, 1.0, 3.0]), vec![2.5, 1.0, 2.5]);
+    }
+
+    #[test]
+    fn test_analyze_pair() {
+        // B tracks A with a mean-reverting wiggle; C moves independently
+        let a: Vec<f64> = (0..60)
+            .map(|t| 100.0 * (1.0 + 0.02 * ((t * 7 % 11) as f64 - 5.0) / 5.0))
+            .collect();
+        let b: Vec<f64> = a
+            .iter()
+            .enumerate()
+            .map(|(t, close)| close * 2.0 * (1.0 + if t % 2 == 0 { 0.001 } else { -0.001 }))
+            .collect();
+        let c: Vec<f64> = (0..60).map(|t| 50.0 + (t % 5) as f64).collect();
+        let input = vec![
+            series("A", &a, &[]),
+            series("B", &b, &[]),
+            series("C", &c, &[]),
+        ];
+
+        let config = CorrelationConfig::default()
+            .with_rolling_window(20)
+            .with_benchmark(symbol("A"));
+        let response = analyze(&input, &config).unwrap();
+
+        assert_eq!(response.observations, 59);
+        assert_eq!(response.correlation_matrix[0][0], 1.0);
+        assert!(response.correlation_matrix[0][1] > 0.95);
+        assert_eq!(
+            response.correlation_matrix[0][1],
+            response.correlation_matrix[1][0]
+        );
+        assert_eq!(response.rolling.len(), 40);
+        assert_eq!(response.betas.len(), 2);
+        assert!((response.betas[0].beta - 1.0).abs() < 0.1);
+
+        let pair = &response.cointegration[0];
+        assert_eq!(pair.second, symbol("B"));
+        assert!(pair.is_cointegrated);
+        assert!((pair.hedge_ratio - 1.0).abs() < 0.05);
+        assert_eq!(response.cointegration.len(), 3);
+    }
+}
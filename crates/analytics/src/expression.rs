@@ -0,0 +1,1503 @@
+//! Alert rule expressions such as `rsi(14) < 30 and close > sma(200)`
+//!
+//! An expression is compiled once into a typed tree and then evaluated bar by
+//! bar by an [`Evaluator`], which keeps running state for every indicator it
+//! references, so each new bar costs a constant amount of work per indicator
+//! rather than a pass over the history.
+//!
+//! Grammar, loosest binding first:
+//!
+//! ```text
+//! expr       := and ("or" and)*
+//! and        := not ("and" not)*
+//! not        := "not" not | comparison
+//! comparison := sum (("<" | "<=" | ">" | ">=" | "==" | "!=") sum)?
+//! sum        := product (("+" | "-") product)*
+//! product    := unary (("*" | "/") unary)*
+//! unary      := "-" unary | primary
+//! primary    := number | "true" | "false" | name | name "(" args ")" | "(" expr ")"
+//! ```
+//!
+//! Sources are limited to [`MAX_SOURCE_LENGTH`] characters and [`MAX_DEPTH`]
+//! levels of nesting, so user input cannot exhaust the stack.
+//!
+//! Names are the bar fields `open`, `high`, `low`, `close` and `volume`, and
+//! the candlestick patterns of [`patterns::PATTERNS`]. Functions are the
+//! indicators `sma`, `ema`, `rsi`, `atr`, `stoch`, `highest`, `lowest`,
+//! `change` (percent), `avg_volume`, `bb_upper`, `bb_middle`, `bb_lower`,
+//! `macd`, `macd_signal` and `macd_hist`, plus `crosses_above(a, b)` and
+//! `crosses_below(a, b)`. A rule's expression must be a condition; screener
+//! columns such as `volume / avg_volume(20)` are compiled as formulas instead.
+
+use rust_decimal::prelude::ToPrimitive;
+use shared_types::{ValidationError, OHLCV};
+use std::collections::VecDeque;
+use std::fmt;
+use thiserror::Error;
+
+use crate::patterns;
+
+/// Longest lookback an indicator may ask for
+pub const MAX_PERIOD: usize = 1000;
+
+/// Longest expression accepted, in characters
+pub const MAX_SOURCE_LENGTH: usize = 1024;
+
+/// Deepest nesting of parentheses, calls, `not` and unary minus accepted
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    #[error("Expected {expected} at column {column}, found {found}")]
+    Syntax {
+        column: usize,
+        expected: String,
+        found: String,
+    },
+
+    #[error("Expected {expected} at column {column}, found {found}")]
+    Type {
+        column: usize,
+        expected: String,
+        found: String,
+    },
+
+    #[error("Unknown name '{name}' at column {column}")]
+    UnknownName { column: usize, name: String },
+
+    #[error("Invalid arguments to {function} at column {column}: {message}")]
+    InvalidArguments {
+        column: usize,
+        function: String,
+        message: String,
+    },
+}
+
+impl From<ExpressionError> for ValidationError {
+    fn from(error: ExpressionError) -> Self {
+        let expected_format = match &error {
+            ExpressionError::Syntax {
+                column,
+                expected,
+                found,
+            }
+            | ExpressionError::Type {
+                column,
+                expected,
+                found,
+            } => format!("{} at column {}, found {}", expected, column, found),
+            ExpressionError::UnknownName { column, name } => format!(
+                "a bar field, indicator or pattern at column {}, found '{}'",
+                column, name
+            ),
+            ExpressionError::InvalidArguments {
+                column,
+                function,
+                message,
+            } => format!("{} for {} at column {}", message, function, column),
+        };
+        ValidationError::InvalidFormat {
+            field: "expression".to_string(),
+            expected_format,
+        }
+    }
+}
+
+// ============================================================================
+// Tokens
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    LeftParen,
+    RightParen,
+    Comma,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Compare(CompareOp),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "number {}", value),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+            Token::Plus => write!(f, "'+'"),
+            Token::Minus => write!(f, "'-'"),
+            Token::Star => write!(f, "'*'"),
+            Token::Slash => write!(f, "'/'"),
+            Token::Compare(op) => write!(f, "'{}'", op),
+            Token::End => write!(f, "end of expression"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            CompareOp::Less => "<",
+            CompareOp::LessOrEqual => "<=",
+            CompareOp::Greater => ">",
+            CompareOp::GreaterOrEqual => ">=",
+            CompareOp::Equal => "==",
+            CompareOp::NotEqual => "!=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Tokens with their 1-based column
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let column = index + 1;
+        let c = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            let text: String = chars[start..index].iter().collect();
+            let value = text.parse().map_err(|_| ExpressionError::Syntax {
+                column,
+                expected: "a number".to_string(),
+                found: format!("'{}'", text),
+            })?;
+            tokens.push((Token::Number(value), column));
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_ascii_alphanumeric() || chars[index] == '_')
+            {
+                index += 1;
+            }
+            let name: String = chars[start..index].iter().collect();
+            tokens.push((Token::Name(name.to_ascii_lowercase()), column));
+            continue;
+        }
+
+        let next = chars.get(index + 1).copied();
+        let (token, width) = match (c, next) {
+            ('<', Some('=')) => (Token::Compare(CompareOp::LessOrEqual), 2),
+            ('>', Some('=')) => (Token::Compare(CompareOp::GreaterOrEqual), 2),
+            ('=', Some('=')) => (Token::Compare(CompareOp::Equal), 2),
+            ('!', Some('=')) => (Token::Compare(CompareOp::NotEqual), 2),
+            ('<', _) => (Token::Compare(CompareOp::Less), 1),
+            ('>', _) => (Token::Compare(CompareOp::Greater), 1),
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            (',', _) => (Token::Comma, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('*', _) => (Token::Star, 1),
+            ('/', _) => (Token::Slash, 1),
+            _ => {
+                return Err(ExpressionError::Syntax {
+                    column,
+                    expected: "a number, name, operator or parenthesis".to_string(),
+                    found: format!("'{}'", c),
+                })
+            }
+        };
+        tokens.push((token, column));
+        index += width;
+    }
+    tokens.push((Token::End, chars.len() + 1));
+    Ok(tokens)
+}
+
+// ============================================================================
+// Syntax tree
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+struct Ast {
+    column: usize,
+    kind: AstKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AstKind {
+    Number(f64),
+    Bool(bool),
+    Name(String),
+    Call { name: String, args: Vec<Ast> },
+    Negate(Box<Ast>),
+    Not(Box<Ast>),
+    Arithmetic(ArithmeticOp, Box<Ast>, Box<Ast>),
+    Compare(CompareOp, Box<Ast>, Box<Ast>),
+    And(Box<Ast>, Box<Ast>),
+    Or(Box<Ast>, Box<Ast>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArithmeticOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, ExpressionError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            position: 0,
+            depth: 0,
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    fn column(&self) -> usize {
+        self.tokens[self.position].1
+    }
+
+    fn advance(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.position].clone();
+        if self.position < self.tokens.len() - 1 {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Name(name) if name == keyword)
+    }
+
+    fn unexpected(&self, expected: &str) -> ExpressionError {
+        ExpressionError::Syntax {
+            column: self.column(),
+            expected: expected.to_string(),
+            found: self.peek().to_string(),
+        }
+    }
+
+    fn expect(&mut self, token: Token, expected: &str) -> Result<(), ExpressionError> {
+        if *self.peek() == token {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    /// Run `parse` one nesting level deeper, failing past [`MAX_DEPTH`]
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ExpressionError>,
+    ) -> Result<T, ExpressionError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ExpressionError::Syntax {
+                column: self.column(),
+                expected: format!("at most {} levels of nesting", MAX_DEPTH),
+                found: "deeper nesting".to_string(),
+            });
+        }
+        self.depth += 1;
+        let parsed = parse(self);
+        self.depth -= 1;
+        parsed
+    }
+
+    fn parse(mut self) -> Result<Ast, ExpressionError> {
+        let ast = self.or()?;
+        if *self.peek() != Token::End {
+            return Err(self.unexpected("an operator or end of expression"));
+        }
+        Ok(ast)
+    }
+
+    fn or(&mut self) -> Result<Ast, ExpressionError> {
+        let mut left = self.and()?;
+        while self.keyword("or") {
+            let (_, column) = self.advance();
+            let right = self.and()?;
+            left = Ast {
+                column,
+                kind: AstKind::Or(Box::new(left), Box::new(right)),
+            };
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Ast, ExpressionError> {
+        let mut left = self.not()?;
+        while self.keyword("and") {
+            let (_, column) = self.advance();
+            let right = self.not()?;
+            left = Ast {
+                column,
+                kind: AstKind::And(Box::new(left), Box::new(right)),
+            };
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Ast, ExpressionError> {
+        if self.keyword("not") {
+            let (_, column) = self.advance();
+            let operand = self.nested(Self::not)?;
+            return Ok(Ast {
+                column,
+                kind: AstKind::Not(Box::new(operand)),
+            });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Ast, ExpressionError> {
+        let left = self.sum()?;
+        if let Token::Compare(op) = *self.peek() {
+            let (_, column) = self.advance();
+            let right = self.sum()?;
+            return Ok(Ast {
+                column,
+                kind: AstKind::Compare(op, Box::new(left), Box::new(right)),
+            });
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Ast, ExpressionError> {
+        let mut left = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Token::Plus => ArithmeticOp::Add,
+                Token::Minus => ArithmeticOp::Subtract,
+                _ => return Ok(left),
+            };
+            let (_, column) = self.advance();
+            let right = self.product()?;
+            left = Ast {
+                column,
+                kind: AstKind::Arithmetic(op, Box::new(left), Box::new(right)),
+            };
+        }
+    }
+
+    fn product(&mut self) -> Result<Ast, ExpressionError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Token::Star => ArithmeticOp::Multiply,
+                Token::Slash => ArithmeticOp::Divide,
+                _ => return Ok(left),
+            };
+            let (_, column) = self.advance();
+            let right = self.unary()?;
+            left = Ast {
+                column,
+                kind: AstKind::Arithmetic(op, Box::new(left), Box::new(right)),
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Ast, ExpressionError> {
+        if *self.peek() == Token::Minus {
+            let (_, column) = self.advance();
+            let operand = self.nested(Self::unary)?;
+            return Ok(Ast {
+                column,
+                kind: AstKind::Negate(Box::new(operand)),
+            });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Ast, ExpressionError> {
+        let column = self.column();
+        match self.peek().clone() {
+            Token::Number(value) => {
+                self.advance();
+                Ok(Ast {
+                    column,
+                    kind: AstKind::Number(value),
+                })
+            }
+            Token::LeftParen => {
+                self.advance();
+                let inner = self.nested(Self::or)?;
+                self.expect(Token::RightParen, "')'")?;
+                Ok(inner)
+            }
+            Token::Name(name) if matches!(name.as_str(), "and" | "or" | "not") => {
+                Err(self.unexpected("a value"))
+            }
+            Token::Name(name) => {
+                self.advance();
+                let kind = match name.as_str() {
+                    "true" => AstKind::Bool(true),
+                    "false" => AstKind::Bool(false),
+                    _ if *self.peek() == Token::LeftParen => {
+                        self.advance();
+                        let mut args = Vec::new();
+                        if *self.peek() != Token::RightParen {
+                            args.push(self.nested(Self::or)?);
+                            while *self.peek() == Token::Comma {
+                                self.advance();
+                                args.push(self.nested(Self::or)?);
+                            }
+                        }
+                        self.expect(Token::RightParen, "',' or ')'")?;
+                        AstKind::Call { name, args }
+                    }
+                    _ => AstKind::Name(name),
+                };
+                Ok(Ast { column, kind })
+            }
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+}
+
+// ============================================================================
+// Type checking
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+}
+
+/// An indicator whose running state an evaluator keeps
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IndicatorSpec {
+    Sma(usize),
+    Ema(usize),
+    Rsi(usize),
+    Atr(usize),
+    Stochastic(usize),
+    Highest(usize),
+    Lowest(usize),
+    Change(usize),
+    AverageVolume(usize),
+    Bollinger(BandLine, usize, f64),
+    Macd(MacdLine, usize, usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BandLine {
+    Upper,
+    Middle,
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MacdLine {
+    Line,
+    Signal,
+    Histogram,
+}
+
+impl IndicatorSpec {
+    /// Bars before the indicator has a value
+    fn warm_up(&self) -> usize {
+        match *self {
+            IndicatorSpec::Sma(period)
+            | IndicatorSpec::Ema(period)
+            | IndicatorSpec::Stochastic(period)
+            | IndicatorSpec::Highest(period)
+            | IndicatorSpec::Lowest(period)
+            | IndicatorSpec::AverageVolume(period)
+            | IndicatorSpec::Bollinger(_, period, _) => period,
+            IndicatorSpec::Rsi(period)
+            | IndicatorSpec::Atr(period)
+            | IndicatorSpec::Change(period) => period + 1,
+            IndicatorSpec::Macd(_, _, slow, signal) => slow + signal - 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Number {
+    Constant(f64),
+    Field(Field),
+    Indicator(usize),
+    Negate(Box<Number>),
+    Arithmetic(ArithmeticOp, Box<Number>, Box<Number>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Constant(bool),
+    Pattern(&'static str),
+    Compare(CompareOp, Number, Number),
+    Crosses {
+        above: bool,
+        slot: usize,
+        left: Number,
+        right: Number,
+    },
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+enum Typed {
+    Number(Number),
+    Condition(Condition),
+}
+
+/// What an expression evaluates to: a rule's condition or a formula's number
+#[derive(Debug, Clone, PartialEq)]
+enum Root {
+    Condition(Condition),
+    Formula(Number),
+}
+
+/// Result of evaluating an [`Expression`] on one bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Number(f64),
+}
+
+impl Value {
+    pub fn as_bool(self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(value),
+            Value::Number(_) => None,
+        }
+    }
+
+    pub fn as_number(self) -> Option<f64> {
+        match self {
+            Value::Number(value) => Some(value),
+            Value::Bool(_) => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    indicators: Vec<IndicatorSpec>,
+    crosses: usize,
+    patterns: bool,
+}
+
+impl Checker {
+    fn number(&mut self, ast: &Ast) -> Result<Number, ExpressionError> {
+        match self.check(ast)? {
+            Typed::Number(number) => Ok(number),
+            Typed::Condition(_) => Err(ExpressionError::Type {
+                column: ast.column,
+                expected: "a number".to_string(),
+                found: "a condition".to_string(),
+            }),
+        }
+    }
+
+    fn condition(&mut self, ast: &Ast) -> Result<Condition, ExpressionError> {
+        match self.check(ast)? {
+            Typed::Condition(condition) => Ok(condition),
+            Typed::Number(_) => Err(ExpressionError::Type {
+                column: ast.column,
+                expected: "a condition".to_string(),
+                found: "a number".to_string(),
+            }),
+        }
+    }
+
+    fn check(&mut self, ast: &Ast) -> Result<Typed, ExpressionError> {
+        Ok(match &ast.kind {
+            AstKind::Number(value) => Typed::Number(Number::Constant(*value)),
+            AstKind::Bool(value) => Typed::Condition(Condition::Constant(*value)),
+            AstKind::Name(name) => {
+                let field = match name.as_str() {
+                    "open" => Field::Open,
+                    "high" => Field::High,
+                    "low" => Field::Low,
+                    "close" => Field::Close,
+                    "volume" => Field::Volume,
+                    _ => {
+                        return match patterns::PATTERNS.iter().find(|pattern| *pattern == name) {
+                            Some(pattern) => {
+                                self.patterns = true;
+                                Ok(Typed::Condition(Condition::Pattern(pattern)))
+                            }
+                            None => Err(ExpressionError::UnknownName {
+                                column: ast.column,
+                                name: name.clone(),
+                            }),
+                        }
+                    }
+                };
+                Typed::Number(Number::Field(field))
+            }
+            AstKind::Call { name, args } => self.call(ast.column, name, args)?,
+            AstKind::Negate(operand) => {
+                Typed::Number(Number::Negate(Box::new(self.number(operand)?)))
+            }
+            AstKind::Not(operand) => {
+                Typed::Condition(Condition::Not(Box::new(self.condition(operand)?)))
+            }
+            AstKind::Arithmetic(op, left, right) => Typed::Number(Number::Arithmetic(
+                *op,
+                Box::new(self.number(left)?),
+                Box::new(self.number(right)?),
+            )),
+            AstKind::Compare(op, left, right) => Typed::Condition(Condition::Compare(
+                *op,
+                self.number(left)?,
+                self.number(right)?,
+            )),
+            AstKind::And(left, right) => Typed::Condition(Condition::And(
+                Box::new(self.condition(left)?),
+                Box::new(self.condition(right)?),
+            )),
+            AstKind::Or(left, right) => Typed::Condition(Condition::Or(
+                Box::new(self.condition(left)?),
+                Box::new(self.condition(right)?),
+            )),
+        })
+    }
+
+    fn call(&mut self, column: usize, name: &str, args: &[Ast]) -> Result<Typed, ExpressionError> {
+        let invalid = |message: &str| ExpressionError::InvalidArguments {
+            column,
+            function: name.to_string(),
+            message: message.to_string(),
+        };
+
+        if let "crosses_above" | "crosses_below" = name {
+            let [left, right] = args else {
+                return Err(invalid("expected two values"));
+            };
+            let condition = Condition::Crosses {
+                above: name == "crosses_above",
+                slot: self.crosses,
+                left: self.number(left)?,
+                right: self.number(right)?,
+            };
+            self.crosses += 1;
+            return Ok(Typed::Condition(condition));
+        }
+
+        // Indicator arguments are constants, fixed when the rule is compiled
+        let constants = args
+            .iter()
+            .map(|arg| match arg.kind {
+                AstKind::Number(value) => Ok(value),
+                _ => Err(ExpressionError::Type {
+                    column: arg.column,
+                    expected: "a constant".to_string(),
+                    found: "an expression".to_string(),
+                }),
+            })
+            .collect::<Result<Vec<f64>, _>>()?;
+        let period = |value: f64| {
+            if value.fract() == 0.0 && value >= 1.0 && value <= MAX_PERIOD as f64 {
+                Ok(value as usize)
+            } else {
+                Err(invalid(&format!(
+                    "periods are whole numbers of bars from 1 to {}",
+                    MAX_PERIOD
+                )))
+            }
+        };
+        let single = |constructor: fn(usize) -> IndicatorSpec| match constants.as_slice() {
+            [value] => Ok(constructor(period(*value)?)),
+            _ => Err(invalid("expected a period")),
+        };
+        let band = |line: BandLine| match constants.as_slice() {
+            [value] => Ok(IndicatorSpec::Bollinger(line, period(*value)?, 2.0)),
+            [value, width] if *width > 0.0 => {
+                Ok(IndicatorSpec::Bollinger(line, period(*value)?, *width))
+            }
+            _ => Err(invalid("expected a period and an optional positive width")),
+        };
+        let macd = |line: MacdLine| match constants.as_slice() {
+            [] => Ok(IndicatorSpec::Macd(line, 12, 26, 9)),
+            [fast, slow, signal] => {
+                let (fast, slow, signal) = (period(*fast)?, period(*slow)?, period(*signal)?);
+                if fast >= slow {
+                    return Err(invalid("the fast period must be shorter than the slow one"));
+                }
+                Ok(IndicatorSpec::Macd(line, fast, slow, signal))
+            }
+            _ => Err(invalid(
+                "expected no arguments or fast, slow and signal periods",
+            )),
+        };
+
+        let spec = match name {
+            "sma" => single(IndicatorSpec::Sma)?,
+            "ema" => single(IndicatorSpec::Ema)?,
+            "rsi" => single(IndicatorSpec::Rsi)?,
+            "atr" => single(IndicatorSpec::Atr)?,
+            "stoch" => single(IndicatorSpec::Stochastic)?,
+            "highest" => single(IndicatorSpec::Highest)?,
+            "lowest" => single(IndicatorSpec::Lowest)?,
+            "change" => single(IndicatorSpec::Change)?,
+            "avg_volume" => single(IndicatorSpec::AverageVolume)?,
+            "bb_upper" => band(BandLine::Upper)?,
+            "bb_middle" => band(BandLine::Middle)?,
+            "bb_lower" => band(BandLine::Lower)?,
+            "macd" => macd(MacdLine::Line)?,
+            "macd_signal" => macd(MacdLine::Signal)?,
+            "macd_hist" => macd(MacdLine::Histogram)?,
+            _ => {
+                return Err(ExpressionError::UnknownName {
+                    column,
+                    name: name.to_string(),
+                })
+            }
+        };
+        let slot = match self.indicators.iter().position(|known| *known == spec) {
+            Some(slot) => slot,
+            None => {
+                self.indicators.push(spec);
+                self.indicators.len() - 1
+            }
+        };
+        Ok(Typed::Number(Number::Indicator(slot)))
+    }
+}
+
+/// A compiled alert condition or numeric formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Root,
+    indicators: Vec<IndicatorSpec>,
+    crosses: usize,
+    /// Whether candlestick patterns are used, which needs the bars themselves
+    patterns: bool,
+}
+
+impl Expression {
+    /// Parse and type-check `source`; the result must be a condition
+    pub fn compile(source: &str) -> Result<Self, ExpressionError> {
+        Self::build(source, |checker, ast| {
+            checker.condition(ast).map(Root::Condition)
+        })
+    }
+
+    /// Parse and type-check `source`, which must evaluate to a number
+    pub fn compile_formula(source: &str) -> Result<Self, ExpressionError> {
+        Self::build(source, |checker, ast| {
+            checker.number(ast).map(Root::Formula)
+        })
+    }
+
+    fn build(
+        source: &str,
+        root: impl FnOnce(&mut Checker, &Ast) -> Result<Root, ExpressionError>,
+    ) -> Result<Self, ExpressionError> {
+        let length = source.chars().count();
+        if length > MAX_SOURCE_LENGTH {
+            return Err(ExpressionError::Syntax {
+                column: MAX_SOURCE_LENGTH + 1,
+                expected: format!("at most {} characters", MAX_SOURCE_LENGTH),
+                found: format!("{} characters", length),
+            });
+        }
+        let ast = Parser::new(source)?.parse()?;
+        let mut checker = Checker::default();
+        let root = root(&mut checker, &ast)?;
+        Ok(Self {
+            source: source.trim().to_string(),
+            root,
+            indicators: checker.indicators,
+            crosses: checker.crosses,
+            patterns: checker.patterns,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether this was compiled with [`compile_formula`](Self::compile_formula)
+    pub fn is_formula(&self) -> bool {
+        matches!(self.root, Root::Formula(_))
+    }
+
+    /// Bars needed before every indicator in the expression has a value
+    pub fn warm_up_bars(&self) -> usize {
+        self.indicators
+            .iter()
+            .map(IndicatorSpec::warm_up)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Fresh evaluation state, to be fed one series' bars in order
+    pub fn evaluator(&self) -> Evaluator {
+        Evaluator {
+            indicators: self.indicators.iter().map(IndicatorState::new).collect(),
+            crosses: vec![None; self.crosses],
+            previous_bar: None,
+            expression: self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+struct Ema {
+    period: usize,
+    seed: Vec<f64>,
+    value: Option<f64>,
+}
+
+impl Ema {
+    fn new(period: usize) -> Self {
+        Self {
+            period,
+            seed: Vec::with_capacity(period),
+            value: None,
+        }
+    }
+
+    /// Seeded with the SMA of the first `period` values, like [`indicators::ema`](crate::indicators::ema)
+    fn update(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(previous) => {
+                Some(previous + 2.0 / (self.period as f64 + 1.0) * (value - previous))
+            }
+            None => {
+                self.seed.push(value);
+                (self.seed.len() == self.period)
+                    .then(|| self.seed.iter().sum::<f64>() / self.period as f64)
+            }
+        };
+        self.value
+    }
+}
+
+/// Wilder smoothing seeded with a plain average, shared by RSI and ATR
+#[derive(Debug, Clone, PartialEq)]
+struct Wilder {
+    period: usize,
+    count: usize,
+    sum: f64,
+    value: Option<f64>,
+}
+
+impl Wilder {
+    fn new(period: usize) -> Self {
+        Self {
+            period,
+            count: 0,
+            sum: 0.0,
+            value: None,
+        }
+    }
+
+    fn update(&mut self, value: f64) -> Option<f64> {
+        let period = self.period as f64;
+        self.value = match self.value {
+            Some(previous) => Some((previous * (period - 1.0) + value) / period),
+            None => {
+                self.count += 1;
+                self.sum += value;
+                (self.count == self.period).then(|| self.sum / period)
+            }
+        };
+        self.value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum IndicatorState {
+    /// Last `period` values of one input and their sum
+    Window {
+        spec: IndicatorSpec,
+        values: VecDeque<f64>,
+    },
+    Ema(Ema),
+    Rsi {
+        previous: Option<f64>,
+        gains: Wilder,
+        losses: Wilder,
+    },
+    Atr {
+        previous: Option<f64>,
+        ranges: Wilder,
+    },
+    /// Highs, lows and the latest close
+    Stochastic {
+        period: usize,
+        bars: VecDeque<(f64, f64)>,
+    },
+    Macd {
+        line: MacdLine,
+        fast: Ema,
+        slow: Ema,
+        signal: Ema,
+    },
+}
+
+impl IndicatorState {
+    fn new(spec: &IndicatorSpec) -> Self {
+        match *spec {
+            IndicatorSpec::Ema(period) => IndicatorState::Ema(Ema::new(period)),
+            IndicatorSpec::Rsi(period) => IndicatorState::Rsi {
+                previous: None,
+                gains: Wilder::new(period),
+                losses: Wilder::new(period),
+            },
+            IndicatorSpec::Atr(period) => IndicatorState::Atr {
+                previous: None,
+                ranges: Wilder::new(period),
+            },
+            IndicatorSpec::Stochastic(period) => IndicatorState::Stochastic {
+                period,
+                bars: VecDeque::with_capacity(period + 1),
+            },
+            IndicatorSpec::Macd(line, fast, slow, signal) => IndicatorState::Macd {
+                line,
+                fast: Ema::new(fast),
+                slow: Ema::new(slow),
+                signal: Ema::new(signal),
+            },
+            spec => IndicatorState::Window {
+                spec,
+                values: VecDeque::new(),
+            },
+        }
+    }
+
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        match self {
+            IndicatorState::Window { spec, values } => {
+                let (input, length) = match *spec {
+                    IndicatorSpec::Highest(period) => (bar.high, period),
+                    IndicatorSpec::Lowest(period) => (bar.low, period),
+                    IndicatorSpec::Change(period) => (bar.close, period + 1),
+                    IndicatorSpec::AverageVolume(period) => (bar.volume, period),
+                    IndicatorSpec::Sma(period) | IndicatorSpec::Bollinger(_, period, _) => {
+                        (bar.close, period)
+                    }
+                    _ => return None,
+                };
+                values.push_back(input);
+                if values.len() > length {
+                    values.pop_front();
+                }
+                if values.len() < length {
+                    return None;
+                }
+                let mean = || values.iter().sum::<f64>() / length as f64;
+                match *spec {
+                    IndicatorSpec::Highest(_) => values.iter().copied().reduce(f64::max),
+                    IndicatorSpec::Lowest(_) => values.iter().copied().reduce(f64::min),
+                    IndicatorSpec::Change(_) => {
+                        let start = values[0];
+                        (start != 0.0).then(|| (bar.close / start - 1.0) * 100.0)
+                    }
+                    IndicatorSpec::Sma(_) | IndicatorSpec::AverageVolume(_) => Some(mean()),
+                    IndicatorSpec::Bollinger(line, _, width) => {
+                        let middle = mean();
+                        let deviation = (values
+                            .iter()
+                            .map(|value| (value - middle).powi(2))
+                            .sum::<f64>()
+                            / length as f64)
+                            .sqrt();
+                        Some(match line {
+                            BandLine::Upper => middle + width * deviation,
+                            BandLine::Middle => middle,
+                            BandLine::Lower => middle - width * deviation,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            IndicatorState::Ema(ema) => ema.update(bar.close),
+            IndicatorState::Rsi {
+                previous,
+                gains,
+                losses,
+            } => {
+                let change = bar.close - previous.replace(bar.close)?;
+                let gain = gains.update(change.max(0.0));
+                let loss = losses.update((-change).max(0.0));
+                let (gain, loss) = (gain?, loss?);
+                Some(match (gain == 0.0, loss == 0.0) {
+                    (true, true) => 50.0,
+                    (_, true) => 100.0,
+                    _ => 100.0 - 100.0 / (1.0 + gain / loss),
+                })
+            }
+            IndicatorState::Atr { previous, ranges } => {
+                let previous = previous.replace(bar.close)?;
+                let range = (bar.high - bar.low)
+                    .max((bar.high - previous).abs())
+                    .max((bar.low - previous).abs());
+                ranges.update(range)
+            }
+            IndicatorState::Stochastic { period, bars } => {
+                bars.push_back((bar.high, bar.low));
+                if bars.len() > *period {
+                    bars.pop_front();
+                }
+                if bars.len() < *period {
+                    return None;
+                }
+                let high = bars.iter().map(|(high, _)| *high).reduce(f64::max)?;
+                let low = bars.iter().map(|(_, low)| *low).reduce(f64::min)?;
+                Some(if high == low {
+                    50.0
+                } else {
+                    (bar.close - low) / (high - low) * 100.0
+                })
+            }
+            IndicatorState::Macd {
+                line,
+                fast,
+                slow,
+                signal,
+            } => {
+                let (fast, slow) = (fast.update(bar.close), slow.update(bar.close));
+                let macd = fast? - slow?;
+                let signal = signal.update(macd);
+                match line {
+                    MacdLine::Line => signal.map(|_| macd),
+                    MacdLine::Signal => signal,
+                    MacdLine::Histogram => signal.map(|signal| macd - signal),
+                }
+            }
+        }
+    }
+}
+
+/// Bar prices as floats
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bar {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+impl Bar {
+    fn from_ohlcv(bar: &OHLCV) -> Self {
+        let value = |value: rust_decimal::Decimal| value.to_f64().unwrap_or(f64::NAN);
+        Self {
+            open: value(bar.open),
+            high: value(bar.high),
+            low: value(bar.low),
+            close: value(bar.close),
+            volume: value(bar.volume),
+        }
+    }
+}
+
+/// Running evaluation of an [`Expression`] over one series
+///
+/// Every bar must be passed to [`update`](Self::update) in order, including
+/// bars on which the result is not needed, so crossings and indicators see
+/// the whole series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluator {
+    expression: Expression,
+    indicators: Vec<IndicatorState>,
+    /// Previous left and right values of each crossing
+    crosses: Vec<Option<(f64, f64)>>,
+    previous_bar: Option<OHLCV>,
+}
+
+impl Evaluator {
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Advance by one bar and evaluate a condition on it
+    ///
+    /// `None` until every indicator the result depends on has warmed up, and
+    /// always `None` for formulas.
+    pub fn update(&mut self, bar: &OHLCV) -> Option<bool> {
+        self.evaluate(bar).and_then(Value::as_bool)
+    }
+
+    /// Advance by one bar and evaluate on it, whatever the expression's type
+    pub fn evaluate(&mut self, bar: &OHLCV) -> Option<Value> {
+        let prices = Bar::from_ohlcv(bar);
+        let values: Vec<Option<f64>> = self
+            .indicators
+            .iter_mut()
+            .map(|indicator| indicator.update(&prices))
+            .collect();
+
+        let mut context = Context {
+            bar: &prices,
+            values: &values,
+            crosses: &mut self.crosses,
+            candles: match &self.previous_bar {
+                Some(previous) => vec![previous.clone(), bar.clone()],
+                None if self.expression.patterns => vec![bar.clone()],
+                None => Vec::new(),
+            },
+        };
+        let result = match &self.expression.root {
+            Root::Condition(condition) => context.condition(condition).map(Value::Bool),
+            Root::Formula(number) => context.number(number).map(Value::Number),
+        };
+        if self.expression.patterns {
+            self.previous_bar = Some(bar.clone());
+        }
+        result
+    }
+
+    /// Forget every bar seen, e.g. before replaying a corrected series
+    pub fn reset(&mut self) {
+        *self = self.expression.evaluator();
+    }
+}
+
+struct Context<'a> {
+    bar: &'a Bar,
+    values: &'a [Option<f64>],
+    crosses: &'a mut Vec<Option<(f64, f64)>>,
+    candles: Vec<OHLCV>,
+}
+
+impl Context<'_> {
+    fn number(&self, number: &Number) -> Option<f64> {
+        let value = match number {
+            Number::Constant(value) => *value,
+            Number::Field(field) => match field {
+                Field::Open => self.bar.open,
+                Field::High => self.bar.high,
+                Field::Low => self.bar.low,
+                Field::Close => self.bar.close,
+                Field::Volume => self.bar.volume,
+            },
+            Number::Indicator(slot) => self.values[*slot]?,
+            Number::Negate(operand) => -self.number(operand)?,
+            Number::Arithmetic(op, left, right) => {
+                let (left, right) = (self.number(left)?, self.number(right)?);
+                match op {
+                    ArithmeticOp::Add => left + right,
+                    ArithmeticOp::Subtract => left - right,
+                    ArithmeticOp::Multiply => left * right,
+                    ArithmeticOp::Divide if right == 0.0 => return None,
+                    ArithmeticOp::Divide => left / right,
+                }
+            }
+        };
+        value.is_finite().then_some(value)
+    }
+
+    /// Three-valued: `None` while an input is unknown, but `false and unknown` is false.
+    /// Both sides are always evaluated so every crossing tracks every bar.
+    fn condition(&mut self, condition: &Condition) -> Option<bool> {
+        match condition {
+            Condition::Constant(value) => Some(*value),
+            Condition::Pattern(pattern) => Some(
+                patterns::detect(&self.candles)
+                    .iter()
+                    .any(|detected| detected.pattern_type == *pattern),
+            ),
+            Condition::Compare(op, left, right) => {
+                let (left, right) = (self.number(left)?, self.number(right)?);
+                Some(match op {
+                    CompareOp::Less => left < right,
+                    CompareOp::LessOrEqual => left <= right,
+                    CompareOp::Greater => left > right,
+                    CompareOp::GreaterOrEqual => left >= right,
+                    CompareOp::Equal => left == right,
+                    CompareOp::NotEqual => left != right,
+                })
+            }
+            Condition::Crosses {
+                above,
+                slot,
+                left,
+                right,
+            } => {
+                let current = self.number(left).zip(self.number(right));
+                let previous = std::mem::replace(&mut self.crosses[*slot], current);
+                let ((left, right), (previous_left, previous_right)) = (current?, previous?);
+                Some(if *above {
+                    previous_left <= previous_right && left > right
+                } else {
+                    previous_left >= previous_right && left < right
+                })
+            }
+            Condition::Not(operand) => self.condition(operand).map(|value| !value),
+            Condition::And(left, right) => match (self.condition(left), self.condition(right)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Condition::Or(left, right) => match (self.condition(left), self.condition(right)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators;
+    use chrono::{Duration, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use shared_types::{Exchange, Symbol, TimeFrame};
+
+    fn bars(closes: &[f64]) -> Vec<OHLCV> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(index, close)| {
+                let close = Decimal::from_f64_retain(*close).unwrap();
+                OHLCV::new(
+                    Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap(),
+                    TimeFrame::OneDay,
+                    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+                        + Duration::days(index as i64),
+                    close,
+                    close + Decimal::ONE,
+                    close - Decimal::ONE,
+                    close,
+                    Decimal::new(1000, 0),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn run(source: &str, bars: &[OHLCV]) -> Vec<Option<bool>> {
+        let mut evaluator = Expression::compile(source).unwrap().evaluator();
+        bars.iter().map(|bar| evaluator.update(bar)).collect()
+    }
+
+    #[test]
+    fn test_incremental_indicators_match_batch() {
+        let closes: Vec<f64> = (0..60)
+            .map(|day| 100.0 + (day as f64 * 0.7).sin() * 5.0 + day as f64 * 0.1)
+            .collect();
+        let series = bars(&closes);
+        let mut checker = Checker::default();
+        for source in [
+            "rsi(14)",
+            "ema(10)",
+            "sma(20)",
+            "macd_hist()",
+            "atr(14)",
+            "stoch(14)",
+        ] {
+            let ast = Parser::new(source).unwrap().parse().unwrap();
+            checker.number(&ast).unwrap();
+        }
+        let mut states: Vec<IndicatorState> =
+            checker.indicators.iter().map(IndicatorState::new).collect();
+        let mut last = Vec::new();
+        for bar in &series {
+            last = states
+                .iter_mut()
+                .map(|state| state.update(&Bar::from_ohlcv(bar)))
+                .collect();
+        }
+
+        let expected = [
+            *indicators::rsi(&closes, 14).last().unwrap(),
+            *indicators::ema(&closes, 10).last().unwrap(),
+            *indicators::sma(&closes, 20).last().unwrap(),
+            indicators::macd(&closes, 12, 26, 9)
+                .last()
+                .unwrap()
+                .histogram,
+            *indicators::atr(&series, 14).last().unwrap(),
+            *indicators::stochastic(&series, 14).last().unwrap(),
+        ];
+        for (value, expected) in last.iter().zip(expected) {
+            assert!(
+                (value.unwrap() - expected).abs() < 1e-9,
+                "{:?} vs {}",
+                value,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluates_conditions_per_bar() {
+        let series = bars(&[10.0, 11.0, 12.0, 11.0, 9.0, 8.0, 10.0]);
+        assert_eq!(
+            run("close > sma(3)", &series),
+            [
+                None,
+                None,
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(true)
+            ]
+        );
+        assert_eq!(
+            run("crosses_below(close, 10.5) or change(2) >= 10", &series)[3..],
+            [Some(false), Some(true), Some(false), Some(true)]
+        );
+        // A false side decides `and` even while the other is warming up
+        assert_eq!(run("close > 100 and rsi(14) < 30", &series)[0], Some(false));
+        assert_eq!(
+            run("not (high - low == 2) or -close < 0 * volume", &series)[0],
+            Some(true)
+        );
+        assert_eq!(
+            Expression::compile("rsi(14) < 30").unwrap().warm_up_bars(),
+            15
+        );
+    }
+
+    #[test]
+    fn test_formulas_evaluate_to_numbers() {
+        let series = bars(&[10.0, 11.0, 12.0, 11.0]);
+        let formula = Expression::compile_formula("volume / avg_volume(3) + close").unwrap();
+        assert!(formula.is_formula());
+        let mut evaluator = formula.evaluator();
+        let values: Vec<Option<f64>> = series
+            .iter()
+            .map(|bar| evaluator.evaluate(bar).and_then(Value::as_number))
+            .collect();
+        assert_eq!(values, [None, None, Some(13.0), Some(12.0)]);
+        assert_eq!(evaluator.update(&series[0]), None);
+
+        assert!(matches!(
+            Expression::compile_formula("rsi(14) < 30"),
+            Err(ExpressionError::Type { column: 9, .. })
+        ));
+        assert_eq!(
+            run("volume >= 2 * avg_volume(2)", &series)[1..],
+            [Some(false), Some(false), Some(false)]
+        );
+    }
+
+    #[test]
+    fn test_reports_errors_with_columns() {
+        let error = |source: &str| Expression::compile(source).unwrap_err();
+        assert_eq!(
+            error("rsi(14) < "),
+            ExpressionError::Syntax {
+                column: 11,
+                expected: "a value".to_string(),
+                found: "end of expression".to_string(),
+            }
+        );
+        assert_eq!(
+            error("close + sma(20)"),
+            ExpressionError::Type {
+                column: 7,
+                expected: "a condition".to_string(),
+                found: "a number".to_string(),
+            }
+        );
+        assert_eq!(
+            error("rsi(14) < 30 and vwap > close"),
+            ExpressionError::UnknownName {
+                column: 18,
+                name: "vwap".to_string()
+            }
+        );
+        assert!(matches!(
+            error("sma(2.5) > close"),
+            ExpressionError::InvalidArguments { column: 1, .. }
+        ));
+        assert!(matches!(
+            error("sma(close) > 1"),
+            ExpressionError::Type { column: 5, .. }
+        ));
+        assert!(matches!(
+            error("close > 1 $"),
+            ExpressionError::Syntax { column: 11, .. }
+        ));
+
+        let validation: ValidationError = error("hammer and close").into();
+        assert_eq!(
+            validation,
+            ValidationError::InvalidFormat {
+                field: "expression".to_string(),
+                expected_format: "a condition at column 12, found a number".to_string(),
+            }
+        );
+        assert!(Expression::compile("bullish_engulfing and close > open").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_deep_nesting_and_long_sources() {
+        let nested = |open: &str, close: &str, depth: usize| {
+            format!("{}close{} > 1", open.repeat(depth), close.repeat(depth))
+        };
+        assert!(Expression::compile(&nested("(", ")", MAX_DEPTH)).is_ok());
+        assert!(Expression::compile(&nested("-", "", MAX_DEPTH)).is_ok());
+        for source in [
+            nested("(", ")", MAX_DEPTH + 1),
+            nested("-", "", MAX_DEPTH + 1),
+            nested("f(", ")", MAX_DEPTH + 1),
+            format!("{}close > 1", "not ".repeat(MAX_DEPTH + 1)),
+        ] {
+            assert!(matches!(
+                Expression::compile(&source),
+                Err(ExpressionError::Syntax { expected, .. }) if expected.contains("nesting")
+            ));
+        }
+
+        // Far past any stack the parser could survive, and refused before parsing
+        assert!(Expression::compile(&nested("(", ")", 100_000)).is_err());
+        let long = format!("close > {}", "1".repeat(MAX_SOURCE_LENGTH));
+        assert_eq!(
+            Expression::compile(&long).unwrap_err(),
+            ExpressionError::Syntax {
+                column: MAX_SOURCE_LENGTH + 1,
+                expected: format!("at most {} characters", MAX_SOURCE_LENGTH),
+                found: format!("{} characters", MAX_SOURCE_LENGTH + 8),
+            }
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Technical indicators over price series
+//!
+//! Each function returns one value per input from the first bar with a
+//! complete lookback onwards, so the last value always belongs to the last
+//! input and a series too short for the lookback gives an empty result.
+//!
+//! Every indicator has an entry point over `&[f64]` columns. The float64
+//! columns of [`OhlcvSeries::to_record_batch`](shared_types::OhlcvSeries) can
+//! be passed straight in through `Float64Array::values`, without copying, and
+//! [`floats`] converts a decimal column of an [`OhlcvSeries`] once up front.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use shared_types::{OhlcvSeries, OHLCV};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdPoint {
+    /// Fast EMA minus slow EMA
+    pub line: f64,
+    /// EMA of the MACD line
+    pub signal: f64,
+    /// Line minus signal
+    pub histogram: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BollingerPoint {
+    pub middle: f64,
+    pub upper: f64,
+    pub lower: f64,
+}
+
+impl BollingerPoint {
+    /// Where `price` sits in the bands: 0 at the lower band, 1 at the upper
+    pub fn percent_b(&self, price: f64) -> f64 {
+        let width = self.upper - self.lower;
+        if width == 0.0 {
+            return 0.5;
+        }
+        (price - self.lower) / width
+    }
+}
+
+/// Closes of `bars` as floats
+pub fn closes(bars: &[OHLCV]) -> Vec<f64> {
+    bars.iter().map(|bar| float(bar.close)).collect()
+}
+
+/// A decimal column, such as [`OhlcvSeries::close`], as floats
+pub fn floats(column: &[Decimal]) -> Vec<f64> {
+    column.iter().copied().map(float).collect()
+}
+
+/// High, low and close columns of `bars` as floats
+fn price_columns(bars: &[OHLCV]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let high = bars.iter().map(|bar| float(bar.high)).collect();
+    let low = bars.iter().map(|bar| float(bar.low)).collect();
+    (high, low, closes(bars))
+}
+
+fn float(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Simple moving average
+pub fn sma(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+    values
+        .windows(period)
+        .map(|window| window.iter().sum::<f64>() / period as f64)
+        .collect()
+}
+
+/// Exponential moving average seeded with the SMA of the first `period` values
+pub fn ema(values: &[f64], period: usize) -> Vec<f64> {
+    let Some(seed) = sma(&values[..period.min(values.len())], period).pop() else {
+        return Vec::new();
+    };
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut result = Vec::with_capacity(values.len() - period + 1);
+    result.push(seed);
+    for value in &values[period..] {
+        let previous = result[result.len() - 1];
+        result.push(previous + alpha * (value - previous));
+    }
+    result
+}
+
+/// Wilder's relative strength index, from 0 to 100
+pub fn rsi(closes: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || closes.len() <= period {
+        return Vec::new();
+    }
+    let changes: Vec<f64> = closes.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let gain = |change: &f64| change.max(0.0);
+    let loss = |change: &f64| (-change).max(0.0);
+
+    let mut average_gain = changes[..period].iter().map(gain).sum::<f64>() / period as f64;
+    let mut average_loss = changes[..period].iter().map(loss).sum::<f64>() / period as f64;
+    let index = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            if gain == 0.0 {
+                50.0
+            } else {
+                100.0
+            }
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+
+    let mut result = vec![index(average_gain, average_loss)];
+    for change in &changes[period..] {
+        average_gain = (average_gain * (period - 1) as f64 + gain(change)) / period as f64;
+        average_loss = (average_loss * (period - 1) as f64 + loss(change)) / period as f64;
+        result.push(index(average_gain, average_loss));
+    }
+    result
+}
+
+/// MACD line, signal and histogram
+pub fn macd(closes: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<MacdPoint> {
+    if fast == 0 || fast >= slow {
+        return Vec::new();
+    }
+    let fast_ema = ema(closes, fast);
+    let slow_ema = ema(closes, slow);
+    // Align the fast EMA to the later start of the slow one
+    let line: Vec<f64> = fast_ema[(slow - fast).min(fast_ema.len())..]
+        .iter()
+        .zip(&slow_ema)
+        .map(|(fast, slow)| fast - slow)
+        .collect();
+    let signal_line = ema(&line, signal);
+    line[line.len() - signal_line.len()..]
+        .iter()
+        .zip(signal_line)
+        .map(|(line, signal)| MacdPoint {
+            line: *line,
+            signal,
+            histogram: line - signal,
+        })
+        .collect()
+}
+
+/// Bollinger bands `width` population standard deviations around the SMA
+pub fn bollinger(closes: &[f64], period: usize, width: f64) -> Vec<BollingerPoint> {
+    if period == 0 || closes.len() < period {
+        return Vec::new();
+    }
+    closes
+        .windows(period)
+        .map(|window| {
+            let middle = window.iter().sum::<f64>() / period as f64;
+            let deviation = (window
+                .iter()
+                .map(|value| (value - middle).powi(2))
+                .sum::<f64>()
+                / period as f64)
+                .sqrt();
+            BollingerPoint {
+                middle,
+                upper: middle + width * deviation,
+                lower: middle - width * deviation,
+            }
+        })
+        .collect()
+}
+
+/// Wilder's average true range
+pub fn atr(bars: &[OHLCV], period: usize) -> Vec<f64> {
+    let (high, low, close) = price_columns(bars);
+    atr_columns(&high, &low, &close, period)
+}
+
+/// Wilder's average true range of an [`OhlcvSeries`]
+pub fn atr_series(series: &OhlcvSeries, period: usize) -> Vec<f64> {
+    let (high, low, close) = (
+        floats(series.high()),
+        floats(series.low()),
+        floats(series.close()),
+    );
+    atr_columns(&high, &low, &close, period)
+}
+
+/// Wilder's average true range over high, low and close columns of equal length
+pub fn atr_columns(high: &[f64], low: &[f64], close: &[f64], period: usize) -> Vec<f64> {
+    let len = high.len().min(low.len()).min(close.len());
+    if period == 0 || len <= period {
+        return Vec::new();
+    }
+    let true_ranges: Vec<f64> = (1..len)
+        .map(|i| {
+            let (high, low, previous) = (high[i], low[i], close[i - 1]);
+            (high - low)
+                .max((high - previous).abs())
+                .max((low - previous).abs())
+        })
+        .collect();
+
+    let mut average = true_ranges[..period].iter().sum::<f64>() / period as f64;
+    let mut result = vec![average];
+    for range in &true_ranges[period..] {
+        average = (average * (period - 1) as f64 + range) / period as f64;
+        result.push(average);
+    }
+    result
+}
+
+/// Stochastic %K: the close's position in the high-low range of `period` bars, 0 to 100
+pub fn stochastic(bars: &[OHLCV], period: usize) -> Vec<f64> {
+    let (high, low, close) = price_columns(bars);
+    stochastic_columns(&high, &low, &close, period)
+}
+
+/// Stochastic %K of an [`OhlcvSeries`]
+pub fn stochastic_series(series: &OhlcvSeries, period: usize) -> Vec<f64> {
+    let (high, low, close) = (
+        floats(series.high()),
+        floats(series.low()),
+        floats(series.close()),
+    );
+    stochastic_columns(&high, &low, &close, period)
+}
+
+/// Stochastic %K over high, low and close columns of equal length
+pub fn stochastic_columns(high: &[f64], low: &[f64], close: &[f64], period: usize) -> Vec<f64> {
+    let len = high.len().min(low.len()).min(close.len());
+    if period == 0 || len < period {
+        return Vec::new();
+    }
+    (period..=len)
+        .map(|end| {
+            let range = end - period..end;
+            let highest = high[range.clone()].iter().copied().fold(f64::MIN, f64::max);
+            let lowest = low[range].iter().copied().fold(f64::MAX, f64::min);
+            if highest == lowest {
+                50.0
+            } else {
+                (close[end - 1] - lowest) / (highest - lowest) * 100.0
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_averages() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(sma(&values, 3), [2.0, 3.0, 4.0]);
+        assert_eq!(ema(&values, 3), [2.0, 3.0, 4.0]);
+        assert!(sma(&values, 6).is_empty());
+        assert!(ema(&values, 0).is_empty());
+
+        let ema = ema(&[10.0, 10.0, 10.0, 16.0], 3);
+        assert_eq!(ema, [10.0, 13.0]);
+
+        let bands = bollinger(&[1.0, 3.0, 1.0, 3.0], 4, 2.0);
+        assert_eq!(
+            bands,
+            [BollingerPoint {
+                middle: 2.0,
+                upper: 4.0,
+                lower: 0.0
+            }]
+        );
+        assert_eq!(bands[0].percent_b(3.0), 0.75);
+    }
+
+    #[test]
+    fn test_rsi_matches_wilder_reference() {
+        // Wilder's worked example, as reproduced by StockCharts
+        let closes = [
+            44.3389, 44.0902, 44.1497, 43.6124, 44.3278, 44.8264, 45.0955, 45.4245, 45.8433,
+            46.0826, 45.8931, 46.0328, 45.6140, 46.2820, 46.2820, 46.0028, 46.0328, 46.4116,
+        ];
+        let values = rsi(&closes, 14);
+        assert_eq!(values.len(), 4);
+        for (value, expected) in values.iter().zip([70.53, 66.32, 66.55, 69.41]) {
+            assert!((value - expected).abs() < 0.01, "{} vs {}", value, expected);
+        }
+        assert_eq!(rsi(&[1.0, 2.0, 3.0], 2), [100.0]);
+        assert!(rsi(&closes, 18).is_empty());
+    }
+
+    #[test]
+    fn test_columnar_entry_points_match_bars() {
+        use arrow::array::{Array, Float64Array};
+        use chrono::{Duration, TimeZone, Utc};
+        use shared_types::{Exchange, Symbol, TimeFrame};
+
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let bars: Vec<OHLCV> = (0..30)
+            .map(|day| {
+                let close = Decimal::new(10_000 + (day * 37 % 11) * 50 - day * 10, 2);
+                OHLCV::new(
+                    symbol.clone(),
+                    TimeFrame::OneDay,
+                    start + Duration::days(day),
+                    close,
+                    close + Decimal::ONE,
+                    close - Decimal::ONE,
+                    close,
+                    Decimal::new(1_000, 0),
+                )
+                .unwrap()
+            })
+            .collect();
+        let series = OhlcvSeries::try_from(bars.clone()).unwrap();
+
+        assert_eq!(atr_series(&series, 14), atr(&bars, 14));
+        assert_eq!(stochastic_series(&series, 14), stochastic(&bars, 14));
+        assert_eq!(floats(series.close()), closes(&bars));
+
+        // Arrow columns are read in place
+        let batch = series.to_record_batch().unwrap();
+        let column = |name| {
+            batch
+                .column_by_name(name)
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .unwrap()
+                .clone()
+        };
+        let (high, low, close) = (column("high"), column("low"), column("close"));
+        assert_eq!(close.null_count(), 0);
+        assert_eq!(
+            atr_columns(high.values(), low.values(), close.values(), 14),
+            atr(&bars, 14)
+        );
+        assert_eq!(rsi(close.values(), 14), rsi(&closes(&bars), 14));
+        assert_eq!(
+            stochastic(&bars[..14], 14),
+            stochastic_columns(
+                &high.values()[..14],
+                &low.values()[..14],
+                &close.values()[..14],
+                14
+            )
+        );
+    }
+
+    #[test]
+    fn test_macd_aligns_to_last_close() {
+        let closes: Vec<f64> = (0..40).map(|day| 100.0 + day as f64).collect();
+        let points = macd(&closes, 12, 26, 9);
+        assert_eq!(points.len(), 40 - 26 - 9 + 2);
+        // A steady trend settles to a constant gap between the EMAs
+        let last = points[points.len() - 1];
+        assert!((last.line - 7.0).abs() < 0.01);
+        assert!(last.histogram.abs() < 0.01);
+    }
+}
@@ -0,0 +1,30 @@
+//! Deterministic analytics for the Trading Intelligence Orchestrator
+
+// `TradingError` carries full error context, so results that return it are large by design.
+#![allow(clippy::result_large_err)]
+
+pub mod accounting;
+pub mod alerts;
+pub mod assessment;
+pub mod correlation;
+pub mod expression;
+pub mod indicators;
+pub mod options;
+pub mod patterns;
+pub mod performance;
+pub mod risk;
+pub mod screener;
+pub mod stats;
+
+pub use accounting::*;
+pub use alerts::{lookback_bars, AlertEngine, AlertError};
+pub use assessment::{RiskAssessmentConfig, RiskCalculator};
+pub use correlation::{AlignedSeries, CorrelationConfig, MissingDataPolicy};
+pub use expression::{Evaluator, Expression, ExpressionError};
+pub use options::{
+    Greeks, MarketInputs, OptionPricer, OptionPricingError, OptionValuation, PortfolioGreeks,
+    PricingModel,
+};
+pub use performance::analyze as analyze_performance;
+pub use risk::{RiskEngine, RiskType, RiskViolation};
+pub use screener::{ScreenError, ScreenResults, Screener};
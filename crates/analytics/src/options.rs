@@ -0,0 +1,682 @@
+use chrono::NaiveDate;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared_types::{ExerciseStyle, OptionRight, OptionSpec, Position, PositionSide, Symbol, OHLCV};
+use thiserror::Error;
+
+use crate::stats;
+
+/// Calendar days per year for time to expiry and daily theta
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// Volatility search range for implied volatility
+const MIN_IMPLIED_VOLATILITY: f64 = 1e-4;
+const MAX_IMPLIED_VOLATILITY: f64 = 5.0;
+
+/// Price error at which the implied volatility search stops
+const IMPLIED_VOLATILITY_TOLERANCE: f64 = 1e-8;
+
+/// Rate and volatility bump, in absolute terms, for finite-difference Greeks
+const BUMP: f64 = 1e-4;
+
+/// Greeks and prices are reported to this many decimal places
+const OUTPUT_DP: u32 = 6;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum OptionPricingError {
+    #[error("{0} is not an option")]
+    NotAnOption(String),
+
+    #[error("Option expired on {expiry}, before the {valuation_date} valuation date")]
+    Expired {
+        expiry: NaiveDate,
+        valuation_date: NaiveDate,
+    },
+
+    #[error("Invalid {parameter}: {value}")]
+    InvalidInput { parameter: String, value: String },
+
+    #[error("No volatility reproduces the option price {price}")]
+    NoImpliedVolatility { price: Decimal },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PricingModel {
+    /// Closed-form Black-Scholes-Merton; every contract is valued as European
+    #[default]
+    BlackScholes,
+    /// Cox-Ross-Rubinstein tree, which honours early exercise of American contracts
+    Binomial { steps: usize },
+}
+
+/// Market state an option is valued against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketInputs {
+    /// Underlying price
+    pub spot: Decimal,
+
+    /// Annualized volatility of the underlying (0.2 = 20%)
+    pub volatility: Decimal,
+
+    /// Continuously compounded risk-free rate
+    pub rate: Decimal,
+
+    /// Continuously compounded dividend yield of the underlying
+    pub dividend_yield: Decimal,
+
+    pub valuation_date: NaiveDate,
+}
+
+impl MarketInputs {
+    pub fn new(spot: Decimal, volatility: Decimal, valuation_date: NaiveDate) -> Self {
+        Self {
+            spot,
+            volatility,
+            rate: Decimal::ZERO,
+            dividend_yield: Decimal::ZERO,
+            valuation_date,
+        }
+    }
+
+    /// Spot and valuation date from the underlying's latest bar
+    pub fn from_bar(bar: &OHLCV, volatility: Decimal) -> Self {
+        Self::new(bar.close, volatility, bar.timestamp.date_naive())
+    }
+
+    pub fn with_volatility(mut self, volatility: Decimal) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    pub fn with_rate(mut self, rate: Decimal) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_dividend_yield(mut self, dividend_yield: Decimal) -> Self {
+        self.dividend_yield = dividend_yield;
+        self
+    }
+}
+
+/// Sensitivities of one option's value per unit of the underlying
+///
+/// Vega and rho are per percentage point of volatility and rate, and theta
+/// is the value lost per calendar day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Greeks {
+    pub delta: Decimal,
+    pub gamma: Decimal,
+    pub vega: Decimal,
+    pub theta: Decimal,
+    pub rho: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionValuation {
+    /// Model value per unit of the underlying
+    pub price: Decimal,
+    pub greeks: Greeks,
+
+    /// Underlying price and volatility the option was valued at
+    pub spot: Decimal,
+    pub volatility: Decimal,
+}
+
+/// Prices options and their Greeks under a [`PricingModel`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OptionPricer {
+    model: PricingModel,
+}
+
+impl OptionPricer {
+    pub fn new(model: PricingModel) -> Self {
+        Self { model }
+    }
+
+    pub fn model(&self) -> PricingModel {
+        self.model
+    }
+
+    pub fn price(
+        &self,
+        spec: &OptionSpec,
+        market: &MarketInputs,
+    ) -> Result<Decimal, OptionPricingError> {
+        let params = Params::new(spec, market, to_f64("volatility", market.volatility)?)?;
+        self.check_model()?;
+        Ok(to_decimal(self.value_of(&params)))
+    }
+
+    /// Price and Greeks of a contract
+    pub fn value(
+        &self,
+        spec: &OptionSpec,
+        market: &MarketInputs,
+    ) -> Result<OptionValuation, OptionPricingError> {
+        let params = Params::new(spec, market, to_f64("volatility", market.volatility)?)?;
+        let (price, greeks) = match self.model {
+            PricingModel::BlackScholes => (black_scholes(&params), black_scholes_greeks(&params)),
+            PricingModel::Binomial { steps } => {
+                self.check_model()?;
+                binomial_valuation(&params, steps)
+            }
+        };
+        Ok(OptionValuation {
+            price: to_decimal(price),
+            greeks: Greeks {
+                delta: to_decimal(greeks[0]),
+                gamma: to_decimal(greeks[1]),
+                vega: to_decimal(greeks[2]),
+                theta: to_decimal(greeks[3]),
+                rho: to_decimal(greeks[4]),
+            },
+            spot: market.spot,
+            volatility: market.volatility,
+        })
+    }
+
+    /// Value an option symbol from its contract terms
+    pub fn value_symbol(
+        &self,
+        symbol: &Symbol,
+        market: &MarketInputs,
+    ) -> Result<OptionValuation, OptionPricingError> {
+        let spec = symbol
+            .option_spec()
+            .ok_or_else(|| OptionPricingError::NotAnOption(symbol.code.clone()))?;
+        self.value(spec, market)
+    }
+
+    /// Volatility at which the model reproduces `price`, ignoring `market.volatility`
+    ///
+    /// Newton steps on the Black-Scholes vega, kept inside a shrinking
+    /// bracket and replaced by bisection whenever they would leave it, so the
+    /// search also converges for tree prices and deep out-of-the-money contracts.
+    pub fn implied_volatility(
+        &self,
+        spec: &OptionSpec,
+        market: &MarketInputs,
+        price: Decimal,
+    ) -> Result<Decimal, OptionPricingError> {
+        self.check_model()?;
+        let base = Params::new(spec, market, MIN_IMPLIED_VOLATILITY)?;
+        let target = to_f64("price", price)?;
+        let not_found = || OptionPricingError::NoImpliedVolatility { price };
+        if base.years <= 0.0 {
+            return Err(not_found());
+        }
+
+        let value_at = |volatility: f64| self.value_of(&Params { volatility, ..base });
+        let (mut low, mut high) = (MIN_IMPLIED_VOLATILITY, MAX_IMPLIED_VOLATILITY);
+        if target < value_at(low) - IMPLIED_VOLATILITY_TOLERANCE
+            || target > value_at(high) + IMPLIED_VOLATILITY_TOLERANCE
+        {
+            return Err(not_found());
+        }
+
+        let mut volatility: f64 = 0.2;
+        for _ in 0..100 {
+            let error = value_at(volatility) - target;
+            if error.abs() < IMPLIED_VOLATILITY_TOLERANCE || high - low < 1e-12 {
+                return Ok(to_decimal(volatility));
+            }
+            if error > 0.0 {
+                high = volatility;
+            } else {
+                low = volatility;
+            }
+            let vega = black_scholes_vega(&Params { volatility, ..base });
+            let newton = volatility - error / vega;
+            volatility = if vega > 1e-12 && newton > low && newton < high {
+                newton
+            } else {
+                (low + high) / 2.0
+            };
+        }
+        Ok(to_decimal(volatility))
+    }
+
+    fn check_model(&self) -> Result<(), OptionPricingError> {
+        match self.model {
+            PricingModel::Binomial { steps } if steps < 2 => {
+                Err(OptionPricingError::InvalidInput {
+                    parameter: "steps".to_string(),
+                    value: steps.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn value_of(&self, params: &Params) -> f64 {
+        match self.model {
+            PricingModel::BlackScholes => black_scholes(params),
+            PricingModel::Binomial { steps } => binomial_tree(params, steps).price,
+        }
+    }
+}
+
+/// Net option sensitivities of a book, in currency units
+///
+/// Delta is the underlying exposure the options are equivalent to and gamma
+/// the change in that exposure for a 1% move of the underlying; vega, theta
+/// and rho are scaled from the per-contract Greeks by the position size.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioGreeks {
+    pub delta: Decimal,
+    pub gamma: Decimal,
+    pub vega: Decimal,
+    pub theta: Decimal,
+    pub rho: Decimal,
+}
+
+impl PortfolioGreeks {
+    pub fn add_position(&mut self, position: &Position, valuation: &OptionValuation) {
+        let quantity = match position.side {
+            PositionSide::Long => position.quantity,
+            PositionSide::Short => -position.quantity,
+        };
+        let units = quantity * position.symbol.contract_size;
+        let greeks = &valuation.greeks;
+        let spot = valuation.spot;
+
+        self.delta += greeks.delta * spot * units;
+        self.gamma += greeks.gamma * spot * spot * units / Decimal::ONE_HUNDRED;
+        self.vega += greeks.vega * units;
+        self.theta += greeks.theta * units;
+        self.rho += greeks.rho * units;
+    }
+}
+
+/// Pricing inputs as floats, with time to expiry in years
+#[derive(Debug, Clone, Copy)]
+struct Params {
+    spot: f64,
+    strike: f64,
+    years: f64,
+    rate: f64,
+    dividend_yield: f64,
+    volatility: f64,
+    right: OptionRight,
+    american: bool,
+}
+
+impl Params {
+    fn new(
+        spec: &OptionSpec,
+        market: &MarketInputs,
+        volatility: f64,
+    ) -> Result<Self, OptionPricingError> {
+        let invalid = |parameter: &str, value: String| OptionPricingError::InvalidInput {
+            parameter: parameter.to_string(),
+            value,
+        };
+        if market.spot <= Decimal::ZERO {
+            return Err(invalid("spot", market.spot.to_string()));
+        }
+        if volatility <= 0.0 {
+            return Err(invalid("volatility", market.volatility.to_string()));
+        }
+        let days = (spec.expiry - market.valuation_date).num_days();
+        if days < 0 {
+            return Err(OptionPricingError::Expired {
+                expiry: spec.expiry,
+                valuation_date: market.valuation_date,
+            });
+        }
+
+        Ok(Self {
+            spot: to_f64("spot", market.spot)?,
+            strike: to_f64("strike", spec.strike)?,
+            years: days as f64 / DAYS_PER_YEAR,
+            rate: to_f64("rate", market.rate)?,
+            dividend_yield: to_f64("dividend_yield", market.dividend_yield)?,
+            volatility,
+            right: spec.right,
+            american: spec.style == ExerciseStyle::American,
+        })
+    }
+
+    fn intrinsic(&self, spot: f64) -> f64 {
+        match self.right {
+            OptionRight::Call => (spot - self.strike).max(0.0),
+            OptionRight::Put => (self.strike - spot).max(0.0),
+        }
+    }
+
+    fn d1_d2(&self) -> (f64, f64) {
+        let deviation = self.volatility * self.years.sqrt();
+        let d1 = ((self.spot / self.strike).ln()
+            + (self.rate - self.dividend_yield + 0.5 * self.volatility * self.volatility)
+                * self.years)
+            / deviation;
+        (d1, d1 - deviation)
+    }
+}
+
+fn black_scholes(params: &Params) -> f64 {
+    if params.years <= 0.0 {
+        return params.intrinsic(params.spot);
+    }
+    let (d1, d2) = params.d1_d2();
+    let carry = (-params.dividend_yield * params.years).exp() * params.spot;
+    let discounted_strike = (-params.rate * params.years).exp() * params.strike;
+    match params.right {
+        OptionRight::Call => {
+            carry * stats::normal_cdf(d1) - discounted_strike * stats::normal_cdf(d2)
+        }
+        OptionRight::Put => {
+            discounted_strike * stats::normal_cdf(-d2) - carry * stats::normal_cdf(-d1)
+        }
+    }
+}
+
+/// Vega per unit of volatility
+fn black_scholes_vega(params: &Params) -> f64 {
+    if params.years <= 0.0 {
+        return 0.0;
+    }
+    let (d1, _) = params.d1_d2();
+    (-params.dividend_yield * params.years).exp()
+        * params.spot
+        * stats::normal_pdf(d1)
+        * params.years.sqrt()
+}
+
+/// Delta, gamma, vega, theta and rho in reporting units
+fn black_scholes_greeks(params: &Params) -> [f64; 5] {
+    if params.years <= 0.0 {
+        let delta = match params.right {
+            OptionRight::Call if params.spot > params.strike => 1.0,
+            OptionRight::Put if params.spot < params.strike => -1.0,
+            _ => 0.0,
+        };
+        return [delta, 0.0, 0.0, 0.0, 0.0];
+    }
+
+    let Params {
+        spot,
+        strike,
+        years,
+        rate,
+        dividend_yield,
+        volatility,
+        ..
+    } = *params;
+    let (d1, d2) = params.d1_d2();
+    let dividend_discount = (-dividend_yield * years).exp();
+    let discount = (-rate * years).exp();
+    let decay =
+        -spot * dividend_discount * stats::normal_pdf(d1) * volatility / (2.0 * years.sqrt());
+    let gamma = dividend_discount * stats::normal_pdf(d1) / (spot * volatility * years.sqrt());
+
+    let (delta, theta, rho) = match params.right {
+        OptionRight::Call => (
+            dividend_discount * stats::normal_cdf(d1),
+            decay - rate * strike * discount * stats::normal_cdf(d2)
+                + dividend_yield * spot * dividend_discount * stats::normal_cdf(d1),
+            strike * years * discount * stats::normal_cdf(d2),
+        ),
+        OptionRight::Put => (
+            -dividend_discount * stats::normal_cdf(-d1),
+            decay + rate * strike * discount * stats::normal_cdf(-d2)
+                - dividend_yield * spot * dividend_discount * stats::normal_cdf(-d1),
+            -strike * years * discount * stats::normal_cdf(-d2),
+        ),
+    };
+    [
+        delta,
+        gamma,
+        black_scholes_vega(params) / 100.0,
+        theta / DAYS_PER_YEAR,
+        rho / 100.0,
+    ]
+}
+
+/// Tree value with the delta, gamma and theta read off its first two steps
+struct TreeValue {
+    price: f64,
+    delta: f64,
+    gamma: f64,
+    theta: f64,
+}
+
+fn binomial_tree(params: &Params, steps: usize) -> TreeValue {
+    if params.years <= 0.0 {
+        let [delta, ..] = black_scholes_greeks(params);
+        return TreeValue {
+            price: params.intrinsic(params.spot),
+            delta,
+            gamma: 0.0,
+            theta: 0.0,
+        };
+    }
+
+    let dt = params.years / steps as f64;
+    let up = (params.volatility * dt.sqrt()).exp();
+    let probability =
+        (((params.rate - params.dividend_yield) * dt).exp() - 1.0 / up) / (up - 1.0 / up);
+    let discount = (-params.rate * dt).exp();
+    // Underlying price after `step` moves, `ups` of them up
+    let node = |step: usize, ups: usize| params.spot * up.powi(2 * ups as i32 - step as i32);
+
+    let mut values: Vec<f64> = (0..=steps)
+        .map(|ups| params.intrinsic(node(steps, ups)))
+        .collect();
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    for step in (0..steps).rev() {
+        for ups in 0..=step {
+            let held =
+                discount * (probability * values[ups + 1] + (1.0 - probability) * values[ups]);
+            values[ups] = if params.american {
+                held.max(params.intrinsic(node(step, ups)))
+            } else {
+                held
+            };
+        }
+        match step {
+            2 => second = values[..3].to_vec(),
+            1 => first = values[..2].to_vec(),
+            _ => {}
+        }
+    }
+
+    let price = values[0];
+    if second.is_empty() {
+        // Two-step tree: the second step is the payoff itself
+        second = (0..=2).map(|ups| params.intrinsic(node(2, ups))).collect();
+    }
+    let delta = (first[1] - first[0]) / (node(1, 1) - node(1, 0));
+    let upper = (second[2] - second[1]) / (node(2, 2) - params.spot);
+    let lower = (second[1] - second[0]) / (params.spot - node(2, 0));
+    let gamma = (upper - lower) / (0.5 * (node(2, 2) - node(2, 0)));
+    let theta = (second[1] - price) / (2.0 * dt);
+    TreeValue {
+        price,
+        delta,
+        gamma,
+        theta,
+    }
+}
+
+/// Tree price and Greeks, with vega and rho by central differences
+fn binomial_valuation(params: &Params, steps: usize) -> (f64, [f64; 5]) {
+    let tree = binomial_tree(params, steps);
+    let reprice = |bumped: Params| binomial_tree(&bumped, steps).price;
+    let (vega, rho) = if params.years > 0.0 {
+        let vega = (reprice(Params {
+            volatility: params.volatility + BUMP,
+            ..*params
+        }) - reprice(Params {
+            volatility: (params.volatility - BUMP).max(BUMP / 2.0),
+            ..*params
+        })) / (params.volatility + BUMP - (params.volatility - BUMP).max(BUMP / 2.0));
+        let rho = (reprice(Params {
+            rate: params.rate + BUMP,
+            ..*params
+        }) - reprice(Params {
+            rate: params.rate - BUMP,
+            ..*params
+        })) / (2.0 * BUMP);
+        (vega, rho)
+    } else {
+        (0.0, 0.0)
+    };
+    (
+        tree.price,
+        [
+            tree.delta,
+            tree.gamma,
+            vega / 100.0,
+            tree.theta / DAYS_PER_YEAR,
+            rho / 100.0,
+        ],
+    )
+}
+
+fn to_f64(parameter: &str, value: Decimal) -> Result<f64, OptionPricingError> {
+    stats::to_f64(parameter, value).map_err(|_| OptionPricingError::InvalidInput {
+        parameter: parameter.to_string(),
+        value: value.to_string(),
+    })
+}
+
+fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value)
+        .unwrap_or_default()
+        .round_dp(OUTPUT_DP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::Exchange;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    /// At the money, one year out: S = K = 100, r = 5%, vol = 20%
+    fn at_the_money(right: OptionRight) -> (OptionSpec, MarketInputs) {
+        let spec = OptionSpec::new(
+            "XYZ",
+            NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            Decimal::ONE_HUNDRED,
+            right,
+        )
+        .unwrap()
+        .with_style(ExerciseStyle::European);
+        let market = MarketInputs::new(
+            Decimal::ONE_HUNDRED,
+            dec("0.2"),
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        )
+        .with_rate(dec("0.05"));
+        (spec, market)
+    }
+
+    fn close(actual: Decimal, expected: &str, tolerance: &str) -> bool {
+        (actual - dec(expected)).abs() <= dec(tolerance)
+    }
+
+    #[test]
+    fn test_black_scholes_reference_values() {
+        let pricer = OptionPricer::default();
+        let (call, market) = at_the_money(OptionRight::Call);
+        let valuation = pricer.value(&call, &market).unwrap();
+
+        assert!(close(valuation.price, "10.450584", "0.000001"));
+        assert!(close(valuation.greeks.delta, "0.636831", "0.000001"));
+        assert!(close(valuation.greeks.gamma, "0.018762", "0.000001"));
+        assert!(close(valuation.greeks.vega, "0.375240", "0.000001"));
+        assert!(close(valuation.greeks.theta, "-0.017573", "0.000001"));
+        assert!(close(valuation.greeks.rho, "0.532325", "0.000001"));
+
+        let (put, _) = at_the_money(OptionRight::Put);
+        let put_value = pricer.value(&put, &market).unwrap();
+        assert!(close(put_value.price, "5.573526", "0.000001"));
+        assert!(close(put_value.greeks.delta, "-0.363169", "0.000001"));
+    }
+
+    #[test]
+    fn test_binomial_converges_and_exercises_early() {
+        let tree = OptionPricer::new(PricingModel::Binomial { steps: 500 });
+        let (put, market) = at_the_money(OptionRight::Put);
+
+        let european = tree.value(&put, &market).unwrap();
+        assert!(close(european.price, "5.5735", "0.01"));
+        assert!(close(european.greeks.delta, "-0.3632", "0.005"));
+        assert!(close(european.greeks.vega, "0.3752", "0.005"));
+
+        let american = tree
+            .price(&put.clone().with_style(ExerciseStyle::American), &market)
+            .unwrap();
+        assert!(close(american, "6.0896", "0.01"));
+
+        assert!(OptionPricer::new(PricingModel::Binomial { steps: 1 })
+            .price(&put, &market)
+            .is_err());
+    }
+
+    #[test]
+    fn test_implied_volatility_round_trip() {
+        let (call, market) = at_the_money(OptionRight::Call);
+        for model in [
+            PricingModel::BlackScholes,
+            PricingModel::Binomial { steps: 200 },
+        ] {
+            let pricer = OptionPricer::new(model);
+            let quoted = pricer
+                .price(&call, &market.clone().with_volatility(dec("0.35")))
+                .unwrap();
+            let implied = pricer.implied_volatility(&call, &market, quoted).unwrap();
+            assert!(close(implied, "0.35", "0.0001"), "{:?}: {}", model, implied);
+        }
+
+        // Below the discounted intrinsic value of a deep in-the-money call
+        let pricer = OptionPricer::default();
+        let deep = market.clone().with_volatility(Decimal::ZERO);
+        let deep = MarketInputs {
+            spot: dec("150"),
+            ..deep
+        };
+        assert_eq!(
+            pricer.implied_volatility(&call, &deep, dec("40")),
+            Err(OptionPricingError::NoImpliedVolatility { price: dec("40") })
+        );
+    }
+
+    #[test]
+    fn test_symbols_and_invalid_inputs() {
+        let pricer = OptionPricer::default();
+        let (call, market) = at_the_money(OptionRight::Call);
+        let option =
+            Symbol::option(call.clone(), Exchange::Other("CBOE".to_string()), "USD").unwrap();
+        assert_eq!(
+            pricer.value_symbol(&option, &market).unwrap(),
+            pricer.value(&call, &market).unwrap()
+        );
+
+        let stock = Symbol::stock("XYZ", "XYZ Corp", Exchange::NYSE).unwrap();
+        assert_eq!(
+            pricer.value_symbol(&stock, &market),
+            Err(OptionPricingError::NotAnOption("XYZ".to_string()))
+        );
+
+        let later = MarketInputs {
+            valuation_date: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+            ..market.clone()
+        };
+        assert!(matches!(
+            pricer.price(&call, &later),
+            Err(OptionPricingError::Expired { .. })
+        ));
+        assert!(pricer
+            .price(&call, &market.with_volatility(Decimal::ZERO))
+            .is_err());
+    }
+}
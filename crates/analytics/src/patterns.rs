@@ -0,0 +1,139 @@
+//! Candlestick patterns completed by the latest bar
+
+use rust_decimal::Decimal;
+use shared_types::{ChartPattern, TechnicalSignal, OHLCV};
+
+/// Names of the patterns [`detect`] can report
+pub const PATTERNS: &[&str] = &[
+    "doji",
+    "hammer",
+    "shooting_star",
+    "bullish_engulfing",
+    "bearish_engulfing",
+];
+
+/// Patterns that end on the last bar of `bars`
+///
+/// Single-bar shapes are judged by the size of the body and wicks relative to
+/// the bar's range; engulfing patterns need the previous bar as well.
+pub fn detect(bars: &[OHLCV]) -> Vec<ChartPattern> {
+    let Some(last) = bars.last() else {
+        return Vec::new();
+    };
+    let range = last.high - last.low;
+    let mut patterns = Vec::new();
+    if range <= Decimal::ZERO {
+        return patterns;
+    }
+
+    let body = (last.close - last.open).abs();
+    let upper_wick = last.high - last.open.max(last.close);
+    let lower_wick = last.open.min(last.close) - last.low;
+    let pattern =
+        |name: &str, confidence: f64, start: &OHLCV, prediction, description: &str| ChartPattern {
+            pattern_type: name.to_string(),
+            confidence,
+            start_time: start.timestamp,
+            end_time: last.timestamp,
+            price_levels: vec![last.low, last.high],
+            description: description.to_string(),
+            prediction,
+        };
+
+    if body * Decimal::TEN <= range {
+        patterns.push(pattern(
+            "doji",
+            0.6,
+            last,
+            TechnicalSignal::Neutral,
+            "Open and close almost equal after trading through a range",
+        ));
+    } else if lower_wick >= body * Decimal::TWO && upper_wick * Decimal::TEN <= range {
+        patterns.push(pattern(
+            "hammer",
+            0.6,
+            last,
+            TechnicalSignal::Bullish,
+            "Long lower wick: sellers pushed down and buyers took it back",
+        ));
+    } else if upper_wick >= body * Decimal::TWO && lower_wick * Decimal::TEN <= range {
+        patterns.push(pattern(
+            "shooting_star",
+            0.6,
+            last,
+            TechnicalSignal::Bearish,
+            "Long upper wick: buyers pushed up and sellers took it back",
+        ));
+    }
+
+    if let [.., previous, _] = bars {
+        let previous_up = previous.close > previous.open;
+        let previous_down = previous.close < previous.open;
+        let engulfs = last.open.min(last.close) <= previous.open.min(previous.close)
+            && last.open.max(last.close) >= previous.open.max(previous.close)
+            && body > (previous.close - previous.open).abs();
+        if engulfs && previous_down && last.close > last.open {
+            patterns.push(pattern(
+                "bullish_engulfing",
+                0.7,
+                previous,
+                TechnicalSignal::Bullish,
+                "Up bar whose body covers the previous down bar",
+            ));
+        } else if engulfs && previous_up && last.close < last.open {
+            patterns.push(pattern(
+                "bearish_engulfing",
+                0.7,
+                previous,
+                TechnicalSignal::Bearish,
+                "Down bar whose body covers the previous up bar",
+            ));
+        }
+    }
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use shared_types::{Exchange, Symbol, TimeFrame};
+
+    fn bar(day: u32, open: i64, high: i64, low: i64, close: i64) -> OHLCV {
+        OHLCV::new(
+            Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap(),
+            TimeFrame::OneDay,
+            Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap(),
+            Decimal::new(open, 0),
+            Decimal::new(high, 0),
+            Decimal::new(low, 0),
+            Decimal::new(close, 0),
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    fn names(bars: &[OHLCV]) -> Vec<String> {
+        detect(bars)
+            .into_iter()
+            .map(|pattern| pattern.pattern_type)
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_candlestick_patterns() {
+        assert_eq!(names(&[bar(4, 100, 105, 95, 100)]), ["doji"]);
+        assert_eq!(names(&[bar(4, 100, 103, 90, 102)]), ["hammer"]);
+        assert_eq!(names(&[bar(4, 102, 115, 100, 100)]), ["shooting_star"]);
+        assert_eq!(
+            names(&[bar(4, 104, 105, 99, 100), bar(5, 99, 107, 98, 106)]),
+            ["bullish_engulfing"]
+        );
+        assert_eq!(
+            names(&[bar(4, 100, 105, 99, 104), bar(5, 105, 106, 97, 98)]),
+            ["bearish_engulfing"]
+        );
+        assert!(names(&[bar(4, 100, 106, 99, 105)]).is_empty());
+        assert!(detect(&[]).is_empty());
+    }
+}
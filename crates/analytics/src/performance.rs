@@ -0,0 +1,438 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use shared_types::{
+    AnalysisError, BenchmarkComparison, DrawdownPoint, PortfolioPerformance, PortfolioSnapshot,
+    PositionAttribution, Symbol, OHLCV,
+};
+use std::collections::BTreeMap;
+
+use crate::stats::{self, to_f64, TRADING_DAYS_PER_YEAR};
+
+/// Bisection bounds and tolerance for the money-weighted return solver
+const IRR_LOWER_BOUND: f64 = -0.9999;
+const IRR_UPPER_BOUND: f64 = 100.0;
+const IRR_TOLERANCE: f64 = 1e-10;
+const IRR_MAX_ITERATIONS: usize = 200;
+
+/// Analyze a series of portfolio snapshots, optionally against benchmark bars
+///
+/// Snapshots may be passed in any order but must all belong to the same
+/// portfolio. Cash flows between snapshots are taken from the change in
+/// `net_contributions` and are assumed to arrive at the start of each period,
+/// both for the time-weighted return and for the money-weighted return.
+pub fn analyze(
+    snapshots: &[PortfolioSnapshot],
+    benchmark: Option<(&Symbol, &[OHLCV])>,
+) -> Result<PortfolioPerformance, AnalysisError> {
+    let snapshots = sorted_snapshots(snapshots)?;
+    let first = snapshots[0];
+    let last = snapshots[snapshots.len() - 1];
+
+    let returns = period_returns(&snapshots)?;
+    let time_weighted_return = compound(&returns);
+    let drawdown_series = drawdown_series(&snapshots, &returns);
+    let max_drawdown = drawdown_series
+        .iter()
+        .map(|point| point.drawdown)
+        .fold(0.0, f64::max);
+    let annualized_volatility = stats::std_dev(&returns)
+        .map(|std_dev| std_dev * TRADING_DAYS_PER_YEAR.sqrt())
+        .unwrap_or(0.0);
+
+    let benchmark = benchmark
+        .map(|(symbol, bars)| compare_to_benchmark(symbol, bars, &snapshots, &returns))
+        .transpose()?
+        .map(|mut comparison| {
+            comparison.excess_return = time_weighted_return - comparison.benchmark_return;
+            comparison
+        });
+
+    Ok(PortfolioPerformance {
+        portfolio_id: first.portfolio_id,
+        period_start: first.as_of,
+        period_end: last.as_of,
+        starting_value: first.total_value,
+        ending_value: last.total_value,
+        net_flows: last.net_contributions - first.net_contributions,
+        time_weighted_return,
+        money_weighted_return: money_weighted_return(&snapshots)?,
+        annualized_volatility,
+        max_drawdown,
+        drawdown_series,
+        benchmark,
+        attribution: attribution(&snapshots)?,
+    })
+}
+
+/// Per-period returns with flows applied at the start of each period:
+/// `r_t = V_t / (V_{t-1} + F_t) - 1`
+pub fn period_returns(snapshots: &[&PortfolioSnapshot]) -> Result<Vec<f64>, AnalysisError> {
+    snapshots
+        .windows(2)
+        .map(|pair| {
+            let flow = pair[1].net_contributions - pair[0].net_contributions;
+            let base = pair[0].total_value + flow;
+            if base <= Decimal::ZERO {
+                return Ok(0.0);
+            }
+            Ok(to_f64("total_value", pair[1].total_value / base)? - 1.0)
+        })
+        .collect()
+}
+
+/// Geometrically link period returns
+pub fn compound(returns: &[f64]) -> f64 {
+    returns.iter().fold(1.0, |growth, r| growth * (1.0 + r)) - 1.0
+}
+
+fn sorted_snapshots(
+    snapshots: &[PortfolioSnapshot],
+) -> Result<Vec<&PortfolioSnapshot>, AnalysisError> {
+    if snapshots.len() < 2 {
+        return Err(AnalysisError::InsufficientDataForAnalysis {
+            required: 2,
+            available: snapshots.len() as u32,
+        });
+    }
+
+    let portfolio_id = snapshots[0].portfolio_id;
+    if let Some(other) = snapshots.iter().find(|s| s.portfolio_id != portfolio_id) {
+        return Err(AnalysisError::InvalidAnalysisParameters {
+            parameter: "portfolio_id".to_string(),
+            value: other.portfolio_id.to_string(),
+        });
+    }
+
+    let mut sorted: Vec<&PortfolioSnapshot> = snapshots.iter().collect();
+    sorted.sort_by_key(|snapshot| snapshot.as_of);
+    Ok(sorted)
+}
+
+fn drawdown_series(snapshots: &[&PortfolioSnapshot], returns: &[f64]) -> Vec<DrawdownPoint> {
+    let mut index_value = 1.0;
+    let mut peak = 1.0;
+    let mut series = Vec::with_capacity(snapshots.len());
+
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        if i > 0 {
+            index_value *= 1.0 + returns[i - 1];
+        }
+        peak = f64::max(peak, index_value);
+        series.push(DrawdownPoint {
+            timestamp: snapshot.as_of,
+            index_value,
+            drawdown: if peak > 0.0 {
+                1.0 - index_value / peak
+            } else {
+                0.0
+            },
+        });
+    }
+
+    series
+}
+
+/// Internal rate of return for the whole snapshot period
+///
+/// The starting value is treated as an initial investment, every change in
+/// contributions as an additional investment (or withdrawal) and the final
+/// value as the terminal payoff. Like [`period_returns`], a flow is dated at
+/// the start of the period it was seen in. Flow times are expressed as
+/// fractions of the whole period so the result is directly comparable with the
+/// time-weighted return. Returns `None` when no rate balances the flows.
+fn money_weighted_return(snapshots: &[&PortfolioSnapshot]) -> Result<Option<f64>, AnalysisError> {
+    let first = snapshots[0];
+    let last = snapshots[snapshots.len() - 1];
+    if last.as_of <= first.as_of {
+        return Ok(None);
+    }
+
+    let mut flows = vec![(0.0, -to_f64("total_value", first.total_value)?)];
+    for pair in snapshots.windows(2) {
+        let flow = pair[1].net_contributions - pair[0].net_contributions;
+        if !flow.is_zero() {
+            flows.push((
+                period_fraction(first.as_of, last.as_of, pair[0].as_of),
+                -to_f64("net_contributions", flow)?,
+            ));
+        }
+    }
+    flows.push((1.0, to_f64("total_value", last.total_value)?));
+    Ok(internal_rate_of_return(&flows))
+}
+
+/// Rate at which the `(time, amount)` flows have zero net present value
+fn internal_rate_of_return(flows: &[(f64, f64)]) -> Option<f64> {
+    let npv = |rate: f64| -> f64 {
+        flows
+            .iter()
+            .map(|(time, amount)| amount / (1.0 + rate).powf(*time))
+            .sum()
+    };
+
+    let (mut lower, mut upper) = (IRR_LOWER_BOUND, IRR_UPPER_BOUND);
+    let mut npv_lower = npv(lower);
+    if npv_lower.signum() == npv(upper).signum() {
+        return None;
+    }
+
+    for _ in 0..IRR_MAX_ITERATIONS {
+        let mid = (lower + upper) / 2.0;
+        let npv_mid = npv(mid);
+        if npv_mid.abs() < IRR_TOLERANCE || (upper - lower) / 2.0 < IRR_TOLERANCE {
+            return Some(mid);
+        }
+        if npv_mid.signum() == npv_lower.signum() {
+            lower = mid;
+            npv_lower = npv_mid;
+        } else {
+            upper = mid;
+        }
+    }
+
+    Some((lower + upper) / 2.0)
+}
+
+fn compare_to_benchmark(
+    symbol: &Symbol,
+    bars: &[OHLCV],
+    snapshots: &[&PortfolioSnapshot],
+    returns: &[f64],
+) -> Result<BenchmarkComparison, AnalysisError> {
+    let closes: BTreeMap<NaiveDate, Decimal> = bars
+        .iter()
+        .filter(|bar| bar.symbol.full_identifier() == symbol.full_identifier())
+        .map(|bar| (bar.timestamp.date_naive(), bar.close))
+        .collect();
+
+    let start = snapshots[0].as_of.date_naive();
+    let end = snapshots[snapshots.len() - 1].as_of.date_naive();
+    let in_period: Vec<Decimal> = closes.range(start..=end).map(|(_, close)| *close).collect();
+    if in_period.len() < 2 {
+        return Err(AnalysisError::InsufficientDataForAnalysis {
+            required: 2,
+            available: in_period.len() as u32,
+        });
+    }
+    let benchmark_return = to_f64("close", in_period[in_period.len() - 1] / in_period[0])? - 1.0;
+
+    // Pair portfolio and benchmark returns over periods where both ends have a close
+    let mut portfolio_returns = Vec::new();
+    let mut benchmark_returns = Vec::new();
+    for (pair, portfolio_return) in snapshots.windows(2).zip(returns) {
+        let closes = (
+            closes.get(&pair[0].as_of.date_naive()),
+            closes.get(&pair[1].as_of.date_naive()),
+        );
+        if let (Some(previous), Some(current)) = closes {
+            portfolio_returns.push(*portfolio_return);
+            benchmark_returns.push(to_f64("close", *current / *previous)? - 1.0);
+        }
+    }
+
+    let beta = match (
+        stats::covariance(&portfolio_returns, &benchmark_returns),
+        stats::variance(&benchmark_returns),
+    ) {
+        (Some(covariance), Some(variance)) if variance > 0.0 => Some(covariance / variance),
+        _ => None,
+    };
+
+    Ok(BenchmarkComparison {
+        symbol: symbol.clone(),
+        benchmark_return,
+        beta,
+        excess_return: 0.0,
+    })
+}
+
+/// P&L earned by each instrument between the first and last snapshot
+///
+/// Contribution is measured against the capital at work (starting value plus
+/// net flows) so the contributions sum to the period's total P&L share.
+fn attribution(
+    snapshots: &[&PortfolioSnapshot],
+) -> Result<Vec<PositionAttribution>, AnalysisError> {
+    let first = snapshots[0];
+    let last = snapshots[snapshots.len() - 1];
+    let capital = first.total_value + (last.net_contributions - first.net_contributions);
+
+    let mut symbols: BTreeMap<String, &Symbol> = BTreeMap::new();
+    for snapshot in snapshots {
+        for position in &snapshot.positions {
+            symbols
+                .entry(position.symbol.full_identifier())
+                .or_insert(&position.symbol);
+        }
+    }
+
+    let mut attribution: Vec<PositionAttribution> = symbols
+        .into_iter()
+        .map(|(key, symbol)| {
+            let pnl = last.position_pnl(symbol) - first.position_pnl(symbol);
+            let weights = snapshots
+                .iter()
+                .map(|snapshot| {
+                    let market_value = snapshot
+                        .positions
+                        .iter()
+                        .find(|position| position.symbol.full_identifier() == key)
+                        .map(|position| position.market_value.abs())
+                        .unwrap_or_default();
+                    if snapshot.total_value > Decimal::ZERO {
+                        to_f64("market_value", market_value / snapshot.total_value)
+                    } else {
+                        Ok(0.0)
+                    }
+                })
+                .collect::<Result<Vec<f64>, _>>()?;
+
+            Ok(PositionAttribution {
+                symbol: symbol.clone(),
+                pnl,
+                contribution: if capital > Decimal::ZERO {
+                    to_f64("pnl", pnl / capital)?
+                } else {
+                    0.0
+                },
+                average_weight: stats::mean(&weights).unwrap_or(0.0),
+            })
+        })
+        .collect::<Result<_, AnalysisError>>()?;
+
+    attribution.sort_by_key(|entry| std::cmp::Reverse(entry.pnl));
+    Ok(attribution)
+}
+
+fn period_fraction(start: DateTime<Utc>, end: DateTime<Utc>, at: DateTime<Utc>) -> f64 {
+    (at - start).num_seconds() as f64 / (end - start).num_seconds() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortfolioAccount;
+    use chrono::{Duration, TimeZone};
+    use shared_types::{CostBasisMethod, Exchange, Fill, TimeFrame, TradeSide};
+
+    fn spy() -> Symbol {
+        Symbol::stock("SPY", "SPDR S&P 500", Exchange::NYSE).unwrap()
+    }
+
+    fn aapl() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    fn ts(day: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 21, 0, 0).unwrap() + Duration::days(day)
+    }
+
+    fn bar(symbol: Symbol, close: i64, day: i64) -> OHLCV {
+        let close = Decimal::new(close, 0);
+        OHLCV::new(
+            symbol,
+            TimeFrame::OneDay,
+            ts(day),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    /// Buy 10 AAPL at 100 and mark it through `closes`, one snapshot per day
+    fn snapshots_for(closes: &[i64]) -> (PortfolioAccount, Vec<PortfolioSnapshot>) {
+        let mut account =
+            PortfolioAccount::new("perf", CostBasisMethod::Fifo, Decimal::new(1_000, 0));
+        let buy = Fill::new(
+            account.id,
+            aapl(),
+            TradeSide::Buy,
+            Decimal::new(10, 0),
+            Decimal::new(100, 0),
+            ts(0),
+        );
+        account.apply_fill(&buy).unwrap();
+
+        let snapshots = closes
+            .iter()
+            .enumerate()
+            .map(|(day, close)| {
+                account.mark_to_market(&bar(aapl(), *close, day as i64));
+                account.snapshot(ts(day as i64)).unwrap()
+            })
+            .collect();
+        (account, snapshots)
+    }
+
+    #[test]
+    fn test_time_weighted_return_ignores_deposits() {
+        let (mut account, mut snapshots) = snapshots_for(&[100, 110]);
+
+        // A large deposit on day 2 with an unchanged price must not look like performance
+        account.deposit(Decimal::new(5_000, 0)).unwrap();
+        account.mark_to_market(&bar(aapl(), 110, 2));
+        snapshots.push(account.snapshot(ts(2)).unwrap());
+
+        let performance = analyze(&snapshots, None).unwrap();
+        assert!((performance.time_weighted_return - 0.1).abs() < 1e-12);
+        assert_eq!(performance.net_flows, Decimal::new(5_000, 0));
+        assert_eq!(performance.ending_value, Decimal::new(6_100, 0));
+        // The deposit is dated at the start of the flat period, so it drags the
+        // money-weighted return below the time-weighted one:
+        // 1000 * (1 + r) + 5000 * (1 + r)^0.5 = 6100
+        assert!((performance.money_weighted_return.unwrap() - 0.028_716_6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_drawdown_and_attribution() {
+        let (_, snapshots) = snapshots_for(&[100, 120, 90, 100]);
+
+        let performance = analyze(&snapshots, None).unwrap();
+        // Peak value 1200, trough 900
+        assert!((performance.max_drawdown - 0.25).abs() < 1e-12);
+        assert_eq!(performance.drawdown_series.len(), 4);
+        assert_eq!(performance.drawdown_series[1].drawdown, 0.0);
+        assert!(performance.annualized_volatility > 0.0);
+
+        // Without flows both returns are the plain growth of the portfolio
+        assert!(performance.time_weighted_return.abs() < 1e-12);
+        assert!(performance.money_weighted_return.unwrap().abs() < 1e-6);
+
+        assert_eq!(performance.attribution.len(), 1);
+        assert_eq!(performance.attribution[0].pnl, Decimal::ZERO);
+        assert!((performance.attribution[0].average_weight - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_benchmark_beta() {
+        let (_, snapshots) = snapshots_for(&[100, 102, 98, 104]);
+        // Benchmark moves exactly half as much as the portfolio
+        let bars: Vec<OHLCV> = [200, 202, 198, 204]
+            .iter()
+            .enumerate()
+            .map(|(day, close)| bar(spy(), *close, day as i64))
+            .collect();
+
+        let performance = analyze(&snapshots, Some((&spy(), &bars))).unwrap();
+        let benchmark = performance.benchmark.unwrap();
+        assert!((benchmark.benchmark_return - 0.02).abs() < 1e-12);
+        assert!((benchmark.excess_return - 0.02).abs() < 1e-12);
+        assert!(benchmark.beta.unwrap() > 1.9 && benchmark.beta.unwrap() < 2.1);
+    }
+
+    #[test]
+    fn test_requires_two_snapshots() {
+        let (_, snapshots) = snapshots_for(&[100]);
+
+        assert_eq!(
+            analyze(&snapshots, None),
+            Err(AnalysisError::InsufficientDataForAnalysis {
+                required: 2,
+                available: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,608 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared_types::{
+    ErrorCode, ErrorSeverity, ErrorType, FxError, MarketDataError, OrderRequest, RiskLimits,
+    RiskTolerance, TradingError, TradingErrorDetails,
+};
+use std::fmt;
+
+use crate::PortfolioAccount;
+
+/// Which limit a pre-trade check tripped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskType {
+    PositionQuantity,
+    PositionNotional,
+    AssetClassNotional,
+    DailyLoss,
+    Leverage,
+    Concentration,
+}
+
+impl fmt::Display for RiskType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskType::PositionQuantity => write!(f, "position_quantity"),
+            RiskType::PositionNotional => write!(f, "position_notional"),
+            RiskType::AssetClassNotional => write!(f, "asset_class_notional"),
+            RiskType::DailyLoss => write!(f, "daily_loss"),
+            RiskType::Leverage => write!(f, "leverage"),
+            RiskType::Concentration => write!(f, "concentration"),
+        }
+    }
+}
+
+/// A single limit breach, measured on the portfolio as it would look after the order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskViolation {
+    pub risk_type: RiskType,
+
+    /// Value the portfolio would reach if the order filled
+    pub current: Decimal,
+
+    /// Configured limit
+    pub limit: Decimal,
+}
+
+impl RiskViolation {
+    fn new(risk_type: RiskType, current: Decimal, limit: Decimal) -> Self {
+        Self {
+            risk_type,
+            current: current.round_dp(6).normalize(),
+            limit,
+        }
+    }
+
+    fn details(&self) -> TradingErrorDetails {
+        TradingErrorDetails::RiskLimitExceeded {
+            risk_type: self.risk_type.to_string(),
+            current: self.current.to_string(),
+            limit: self.limit.to_string(),
+        }
+    }
+
+    /// Convert into the shared error type used across services
+    pub fn to_trading_error(&self, order: &OrderRequest) -> TradingError {
+        rejection(order, std::slice::from_ref(self))
+    }
+}
+
+fn market_closed(order: &OrderRequest, details: MarketDataError) -> TradingError {
+    let message = format!(
+        "Order for {} rejected: {}",
+        order.symbol.full_identifier(),
+        details
+    );
+    TradingError::builder(
+        ErrorCode::MarketClosed,
+        ErrorType::MarketData {
+            details,
+            symbol: Some(order.symbol.full_identifier()),
+            timeframe: None,
+        },
+    )
+    .user_message(message.clone())
+    .developer_message(message)
+    .severity(ErrorSeverity::Warning)
+    // The same order can go through once the market reopens
+    .recoverable(true)
+    .component("risk")
+    .build()
+}
+
+fn missing_rate(order: &OrderRequest, error: FxError) -> TradingError {
+    let message = format!(
+        "Order for {} rejected: cannot value it in the base currency: {}",
+        order.symbol.full_identifier(),
+        error
+    );
+    TradingError::builder(
+        ErrorCode::OrderRejected,
+        ErrorType::Trading {
+            details: Box::new(TradingErrorDetails::OrderRejected {
+                reason: error.to_string(),
+            }),
+            order_id: order.order_id.clone(),
+            portfolio_id: Some(order.portfolio_id.to_string()),
+        },
+    )
+    .user_message(message.clone())
+    .developer_message(message)
+    .severity(ErrorSeverity::Error)
+    // Limits can be checked once the missing rate arrives
+    .recoverable(true)
+    .component("risk")
+    .build()
+}
+
+/// One [`TradingError`] reporting every violation of an order
+///
+/// The first violation becomes the error details and the rest are chained
+/// behind it, while the messages list them all.
+fn rejection(order: &OrderRequest, violations: &[RiskViolation]) -> TradingError {
+    let breaches: Vec<String> = violations
+        .iter()
+        .map(|violation| violation.details().to_string())
+        .collect();
+    let message = format!(
+        "Order for {} rejected: {}",
+        order.symbol.full_identifier(),
+        breaches.join("; ")
+    );
+
+    let error = TradingError::builder(
+        ErrorCode::RiskLimitExceeded,
+        ErrorType::Trading {
+            details: Box::new(violations[0].details()),
+            order_id: order.order_id.clone(),
+            portfolio_id: Some(order.portfolio_id.to_string()),
+        },
+    )
+    .user_message(message.clone())
+    .developer_message(message)
+    .severity(ErrorSeverity::Warning)
+    .recoverable(false)
+    .component("risk")
+    .metadata("violations", violations.len().to_string())
+    .build();
+
+    violations[1..].iter().fold(error, |error, violation| {
+        error.chain_error(
+            violation.details().to_string(),
+            violation.risk_type.to_string(),
+        )
+    })
+}
+
+/// Position and exposure figures for the portfolio after a hypothetical fill
+struct Projection {
+    position_quantity: Decimal,
+    position_notional: Decimal,
+    asset_class_notional: Decimal,
+    gross_exposure: Decimal,
+    equity: Decimal,
+    reduces_risk: bool,
+
+    /// Current account value in the base currency
+    total_value: Decimal,
+}
+
+/// Enforces a portfolio's [`RiskLimits`] before orders are sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskEngine {
+    limits: RiskLimits,
+}
+
+impl RiskEngine {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self { limits }
+    }
+
+    /// Engine using the default limit profile for a risk tolerance preset
+    pub fn from_tolerance(tolerance: &RiskTolerance) -> Self {
+        Self::new(RiskLimits::from(tolerance))
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Check an order, rejecting it with a [`TradingError`] that reports every violation
+    ///
+    /// Orders for a market that is not trading at `at` are rejected with
+    /// `MarketClosed` before any limit is looked at; pre- and post-market
+    /// sessions count only for orders that allow extended hours.
+    /// `day_open_value` is the portfolio value at the start of the trading day
+    /// and is used for the daily loss limit.
+    pub fn check_order(
+        &self,
+        account: &PortfolioAccount,
+        order: &OrderRequest,
+        day_open_value: Decimal,
+        at: DateTime<Utc>,
+    ) -> Result<(), TradingError> {
+        order
+            .symbol
+            .ensure_market_open(at, order.extended_hours)
+            .map_err(|details| market_closed(order, details))?;
+
+        let violations = self
+            .evaluate(account, order, day_open_value)
+            .map_err(|error| missing_rate(order, error))?;
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(rejection(order, &violations))
+        }
+    }
+
+    /// Every limit the order would breach
+    ///
+    /// Orders that only shrink an existing position are always allowed so a
+    /// portfolio that is already over its limits can still be de-risked.
+    /// Fails if the order or a held position has no rate to the base currency.
+    pub fn evaluate(
+        &self,
+        account: &PortfolioAccount,
+        order: &OrderRequest,
+        day_open_value: Decimal,
+    ) -> Result<Vec<RiskViolation>, FxError> {
+        let projection = project(account, order)?;
+        if projection.reduces_risk {
+            return Ok(Vec::new());
+        }
+
+        let limits = &self.limits;
+        let mut violations = Vec::new();
+
+        if let Some(limit) = limits.max_position_quantity {
+            if projection.position_quantity > limit {
+                violations.push(RiskViolation::new(
+                    RiskType::PositionQuantity,
+                    projection.position_quantity,
+                    limit,
+                ));
+            }
+        }
+
+        if let Some(limit) = limits.position_notional_limit(&order.symbol) {
+            if projection.position_notional > limit {
+                violations.push(RiskViolation::new(
+                    RiskType::PositionNotional,
+                    projection.position_notional,
+                    limit,
+                ));
+            }
+        }
+
+        if let Some(limit) = limits
+            .max_asset_class_notional
+            .get(&order.symbol.asset_class)
+        {
+            if projection.asset_class_notional > *limit {
+                violations.push(RiskViolation::new(
+                    RiskType::AssetClassNotional,
+                    projection.asset_class_notional,
+                    *limit,
+                ));
+            }
+        }
+
+        if let Some(limit) = limits.max_daily_loss {
+            if day_open_value > Decimal::ZERO {
+                let loss = (day_open_value - projection.total_value) / day_open_value;
+                if loss > limit {
+                    violations.push(RiskViolation::new(RiskType::DailyLoss, loss, limit));
+                }
+            }
+        }
+
+        // With no equity left any exposure is unbounded leverage
+        let ratio = |value: Decimal| {
+            if projection.equity > Decimal::ZERO {
+                value / projection.equity
+            } else {
+                Decimal::MAX
+            }
+        };
+
+        if let Some(limit) = limits.max_leverage {
+            let leverage = ratio(projection.gross_exposure);
+            if leverage > limit {
+                violations.push(RiskViolation::new(RiskType::Leverage, leverage, limit));
+            }
+        }
+
+        if let Some(limit) = limits.max_concentration {
+            let concentration = ratio(projection.position_notional);
+            if concentration > limit {
+                violations.push(RiskViolation::new(
+                    RiskType::Concentration,
+                    concentration,
+                    limit,
+                ));
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+fn project(account: &PortfolioAccount, order: &OrderRequest) -> Result<Projection, FxError> {
+    let key = order.symbol.full_identifier();
+    let current = account
+        .position(&order.symbol)
+        .map(|book| book.signed_quantity())
+        .unwrap_or_default();
+    let projected = current + order.signed_quantity();
+
+    // Everything is measured in the account's base currency
+    let rate = account.base_rate(&order.symbol)?;
+    let position_quantity = projected.abs();
+    let position_notional = position_quantity * order.price * order.symbol.contract_size * rate;
+
+    // Exposure of every other open position stays at its current mark
+    let others = account
+        .open_positions()
+        .filter(|book| book.symbol.full_identifier() != key);
+    let (mut gross_exposure, mut asset_class_notional) = (Decimal::ZERO, Decimal::ZERO);
+    for book in others {
+        let exposure = account.base_market_value(book)?.abs();
+        gross_exposure += exposure;
+        if book.symbol.asset_class == order.symbol.asset_class {
+            asset_class_notional += exposure;
+        }
+    }
+
+    // Cash moves by the order's notional while the position absorbs it at the
+    // order price, so equity only changes by the re-mark of the existing position
+    let existing_notional_at_order_price =
+        current * order.price * order.symbol.contract_size * rate;
+    let existing_market_value = account
+        .position(&order.symbol)
+        .map(|book| account.base_market_value(book))
+        .transpose()?
+        .unwrap_or_default();
+    let total_value = account.total_value()?;
+    let equity = total_value - existing_market_value + existing_notional_at_order_price;
+
+    Ok(Projection {
+        position_quantity,
+        position_notional,
+        asset_class_notional: asset_class_notional + position_notional,
+        gross_exposure: gross_exposure + position_notional,
+        equity,
+        // Shrinking towards (or exactly to) flat, without crossing into the other side
+        reduces_risk: !current.is_zero()
+            && projected.abs() < current.abs()
+            && (projected.is_zero() || projected.is_sign_positive() == current.is_sign_positive()),
+        total_value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use shared_types::{
+        AssetClass, CostBasisMethod, Exchange, Fill, Symbol, TimeFrame, TradeSide, OHLCV,
+    };
+
+    /// Tuesday 2 January 2024, 10:00 in New York: NASDAQ is in its regular session
+    fn open() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap()
+    }
+
+    fn aapl() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    fn account_holding(quantity: i64, price: i64) -> PortfolioAccount {
+        let mut account =
+            PortfolioAccount::new("risk", CostBasisMethod::Fifo, Decimal::new(10_000, 0));
+        let fill = Fill::new(
+            account.id,
+            aapl(),
+            TradeSide::Buy,
+            Decimal::new(quantity, 0),
+            Decimal::new(price, 0),
+            Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap(),
+        );
+        account.apply_fill(&fill).unwrap();
+        account
+    }
+
+    fn order(
+        account: &PortfolioAccount,
+        side: TradeSide,
+        quantity: i64,
+        price: i64,
+    ) -> OrderRequest {
+        OrderRequest::new(
+            account.id,
+            aapl(),
+            side,
+            Decimal::new(quantity, 0),
+            Decimal::new(price, 0),
+        )
+        .with_order_id("ord-1")
+    }
+
+    #[test]
+    fn test_rejects_position_and_concentration_breaches() {
+        let account = account_holding(10, 100);
+        let engine = RiskEngine::new(
+            RiskLimits::unlimited()
+                .with_max_position_quantity(Decimal::new(15, 0))
+                .with_max_concentration(Decimal::new(20, 2)),
+        );
+
+        // 21 shares at 100 = 2,100 of 10,000 equity
+        let buy = order(&account, TradeSide::Buy, 11, 100);
+        let violations = engine
+            .evaluate(&account, &buy, Decimal::new(10_000, 0))
+            .unwrap();
+        assert_eq!(
+            violations,
+            vec![
+                RiskViolation::new(
+                    RiskType::PositionQuantity,
+                    Decimal::new(21, 0),
+                    Decimal::new(15, 0)
+                ),
+                RiskViolation::new(
+                    RiskType::Concentration,
+                    Decimal::new(21, 2),
+                    Decimal::new(20, 2)
+                ),
+            ]
+        );
+
+        let error = engine
+            .check_order(&account, &buy, Decimal::new(10_000, 0), open())
+            .unwrap_err();
+        assert_eq!(error.error_code, ErrorCode::RiskLimitExceeded);
+        match error.error_type {
+            ErrorType::Trading {
+                details,
+                order_id,
+                portfolio_id,
+            } => {
+                assert_eq!(
+                    *details,
+                    TradingErrorDetails::RiskLimitExceeded {
+                        risk_type: "position_quantity".to_string(),
+                        current: "21".to_string(),
+                        limit: "15".to_string(),
+                    }
+                );
+                assert_eq!(order_id.as_deref(), Some("ord-1"));
+                assert_eq!(portfolio_id, Some(account.id.to_string()));
+            }
+            other => panic!("unexpected error type: {:?}", other),
+        }
+        // The concentration breach is reported too, not just the first one
+        assert_eq!(error.error_chain.len(), 1);
+        assert_eq!(error.error_chain[0].error_type, "concentration");
+        assert!(error.user_message.contains("concentration"));
+        assert_eq!(error.context.metadata["violations"], "2");
+
+        // Exactly at the concentration limit is still allowed
+        let at_limit =
+            RiskEngine::new(RiskLimits::unlimited().with_max_concentration(Decimal::new(20, 2)));
+        let buy = order(&account, TradeSide::Buy, 10, 100);
+        assert!(at_limit
+            .check_order(&account, &buy, Decimal::new(10_000, 0), open())
+            .is_ok());
+
+        // 15 shares (15% of equity) is within both limits
+        let small = order(&account, TradeSide::Buy, 5, 100);
+        assert!(engine
+            .check_order(&account, &small, Decimal::new(10_000, 0), open())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_leverage_and_asset_class_limits() {
+        let account = account_holding(50, 100);
+        let engine = RiskEngine::new(
+            RiskLimits::unlimited()
+                .with_max_leverage(Decimal::ONE)
+                .with_max_asset_class_notional(AssetClass::Stock, Decimal::new(12_000, 0)),
+        );
+
+        // 150 shares at 100 = 15,000 gross on 10,000 equity
+        let buy = order(&account, TradeSide::Buy, 100, 100);
+        let risk_types: Vec<RiskType> = engine
+            .evaluate(&account, &buy, Decimal::new(10_000, 0))
+            .unwrap()
+            .into_iter()
+            .map(|violation| violation.risk_type)
+            .collect();
+        assert_eq!(
+            risk_types,
+            vec![RiskType::AssetClassNotional, RiskType::Leverage]
+        );
+    }
+
+    #[test]
+    fn test_position_notional_overrides_by_symbol_and_asset_class() {
+        let account = account_holding(10, 100);
+        let buy = order(&account, TradeSide::Buy, 40, 100);
+        let day_open = Decimal::new(10_000, 0);
+
+        // 50 shares at 100 = 5,000, over the 2,000 default
+        let limits = RiskLimits::unlimited().with_max_position_notional(Decimal::new(2_000, 0));
+        let violations = RiskEngine::new(limits.clone())
+            .evaluate(&account, &buy, day_open)
+            .unwrap();
+        assert_eq!(violations[0].risk_type, RiskType::PositionNotional);
+        assert_eq!(violations[0].limit, Decimal::new(2_000, 0));
+
+        let stocks = limits
+            .clone()
+            .with_asset_class_position_notional(AssetClass::Stock, Decimal::new(4_000, 0));
+        let violations = RiskEngine::new(stocks.clone())
+            .evaluate(&account, &buy, day_open)
+            .unwrap();
+        assert_eq!(violations[0].limit, Decimal::new(4_000, 0));
+
+        let engine =
+            RiskEngine::new(stocks.with_symbol_position_notional(&aapl(), Decimal::new(6_000, 0)));
+        assert!(engine.check_order(&account, &buy, day_open, open()).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_orders_while_market_is_closed() {
+        let account = account_holding(10, 100);
+        let engine = RiskEngine::new(RiskLimits::unlimited());
+        let buy = order(&account, TradeSide::Buy, 1, 100);
+        let day_open = Decimal::new(10_000, 0);
+
+        // 08:00 in New York is pre-market
+        let early = Utc.with_ymd_and_hms(2024, 1, 2, 13, 0, 0).unwrap();
+        let error = engine
+            .check_order(&account, &buy, day_open, early)
+            .unwrap_err();
+        assert_eq!(error.error_code, ErrorCode::MarketClosed);
+        assert!(error.recoverable);
+        assert!(matches!(
+            error.error_type,
+            ErrorType::MarketData {
+                details: MarketDataError::MarketClosed { .. },
+                ..
+            }
+        ));
+        let extended = buy.clone().with_extended_hours(true);
+        assert!(engine
+            .check_order(&account, &extended, day_open, early)
+            .is_ok());
+
+        // New Year's Day is a holiday, even for extended-hours orders
+        let holiday = Utc.with_ymd_and_hms(2024, 1, 1, 15, 0, 0).unwrap();
+        assert!(engine
+            .check_order(&account, &extended, day_open, holiday)
+            .is_err());
+        assert!(engine.check_order(&account, &buy, day_open, open()).is_ok());
+    }
+
+    #[test]
+    fn test_daily_loss_blocks_new_risk_but_allows_exits() {
+        let mut account = account_holding(10, 100);
+        let engine = RiskEngine::from_tolerance(&RiskTolerance::Conservative);
+
+        // Opened the day at 10,000, then AAPL halves: 500 lost (5% > 2% conservative limit)
+        let price = Decimal::new(50, 0);
+        let bar = OHLCV::new(
+            aapl(),
+            TimeFrame::OneDay,
+            Utc.with_ymd_and_hms(2024, 1, 3, 21, 0, 0).unwrap(),
+            price,
+            price,
+            price,
+            price,
+            Decimal::new(1_000, 0),
+        )
+        .unwrap();
+        assert!(account.mark_to_market(&bar));
+        let day_open = Decimal::new(10_000, 0);
+
+        let buy = order(&account, TradeSide::Buy, 1, 100);
+        let violations = engine.evaluate(&account, &buy, day_open).unwrap();
+        assert_eq!(violations[0].risk_type, RiskType::DailyLoss);
+        assert_eq!(violations[0].current, Decimal::new(5, 2));
+
+        let sell = order(&account, TradeSide::Sell, 5, 100);
+        assert!(engine
+            .check_order(&account, &sell, day_open, open())
+            .is_ok());
+
+        // Selling through zero opens new short risk and is checked again
+        let flip = order(&account, TradeSide::Sell, 15, 100);
+        assert!(engine
+            .check_order(&account, &flip, day_open, open())
+            .is_err());
+    }
+}
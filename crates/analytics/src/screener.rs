@@ -0,0 +1,318 @@
+//! Screens that filter a symbol universe with alert expressions
+//!
+//! A [`Screener`] compiles a [`ScreenCriteria`] once, then replays each
+//! symbol's recent bars through the filter and column expressions and keeps
+//! the symbols whose filter holds on the latest bar. Symbols are independent,
+//! so [`Screener::run`] spreads them over the rayon thread pool.
+
+use rayon::prelude::*;
+use shared_types::{
+    PaginationInfo, ScreenCriteria, ScreenCriteriaError, ScreenPage, ScreenRow,
+    MAX_SCREEN_PAGE_SIZE, OHLCV,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::expression::{Expression, ExpressionError, Value};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScreenError {
+    #[error(transparent)]
+    Criteria(#[from] ScreenCriteriaError),
+
+    #[error("Invalid filter: {0}")]
+    Filter(#[source] ExpressionError),
+
+    #[error("Invalid formula for column {column}: {error}")]
+    Column {
+        column: String,
+        #[source]
+        error: ExpressionError,
+    },
+}
+
+/// How one symbol fared against a screen
+#[derive(Debug, Clone, PartialEq)]
+pub enum Screened {
+    Match(Box<ScreenRow>),
+    NoMatch,
+
+    /// Too few bars for the filter to have a value on the latest one
+    InsufficientData,
+}
+
+/// Every match of a screen across a universe, in the screen's order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScreenResults {
+    pub rows: Vec<ScreenRow>,
+    pub screened: usize,
+    pub insufficient_data: usize,
+}
+
+impl ScreenResults {
+    /// Page `page`, counting from 1; sizes are clamped to [`MAX_SCREEN_PAGE_SIZE`]
+    pub fn page(&self, page: u32, page_size: u32) -> ScreenPage {
+        let pagination = PaginationInfo::new(
+            page,
+            page_size.clamp(1, MAX_SCREEN_PAGE_SIZE),
+            self.rows.len() as u64,
+        );
+        let start = pagination.offset().min(self.rows.len());
+        let end = (start + pagination.page_size as usize).min(self.rows.len());
+        ScreenPage {
+            rows: self.rows[start..end].to_vec(),
+            pagination,
+            screened: self.screened,
+            insufficient_data: self.insufficient_data,
+        }
+    }
+}
+
+/// A compiled screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screener {
+    criteria: ScreenCriteria,
+    filter: Expression,
+    columns: Vec<(String, Expression)>,
+}
+
+impl Screener {
+    pub fn new(criteria: ScreenCriteria) -> Result<Self, ScreenError> {
+        criteria.validate()?;
+        let filter = Expression::compile(&criteria.filter).map_err(ScreenError::Filter)?;
+        let columns = criteria
+            .columns
+            .iter()
+            .map(|column| {
+                Expression::compile_formula(&column.formula)
+                    .map(|formula| (column.name.clone(), formula))
+                    .map_err(|error| ScreenError::Column {
+                        column: column.name.clone(),
+                        error,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            criteria,
+            filter,
+            columns,
+        })
+    }
+
+    pub fn criteria(&self) -> &ScreenCriteria {
+        &self.criteria
+    }
+
+    /// Bars each symbol needs before the filter and every column have values
+    pub fn warm_up_bars(&self) -> usize {
+        self.columns
+            .iter()
+            .map(|(_, formula)| formula.warm_up_bars())
+            .fold(self.filter.warm_up_bars(), usize::max)
+    }
+
+    /// Screen one symbol's bars, oldest first
+    pub fn screen(&self, bars: &[OHLCV]) -> Screened {
+        let Some(latest) = bars.last() else {
+            return Screened::InsufficientData;
+        };
+        let mut filter = self.filter.evaluator();
+        let mut held = None;
+        for bar in bars {
+            held = filter.update(bar);
+        }
+        match held {
+            None => return Screened::InsufficientData,
+            Some(false) => return Screened::NoMatch,
+            Some(true) => {}
+        }
+
+        // Columns are only worth computing for the symbols that pass
+        let values = self
+            .columns
+            .iter()
+            .map(|(name, formula)| {
+                let mut evaluator = formula.evaluator();
+                let mut value = None;
+                for bar in bars {
+                    value = evaluator.evaluate(bar).and_then(Value::as_number);
+                }
+                (name.clone(), value)
+            })
+            .collect::<BTreeMap<_, _>>();
+        Screened::Match(Box::new(ScreenRow {
+            symbol: latest.symbol.clone(),
+            timestamp: latest.timestamp,
+            close: latest.close,
+            volume: latest.volume,
+            values,
+        }))
+    }
+
+    /// Screen every series in parallel and sort the matches
+    pub fn run<S>(&self, series: &[S]) -> ScreenResults
+    where
+        S: AsRef<[OHLCV]> + Sync,
+    {
+        let (mut rows, insufficient_data) = series
+            .par_iter()
+            .map(|bars| self.screen(bars.as_ref()))
+            .fold(
+                || (Vec::new(), 0),
+                |(mut rows, insufficient), screened| match screened {
+                    Screened::Match(row) => {
+                        rows.push(*row);
+                        (rows, insufficient)
+                    }
+                    Screened::NoMatch => (rows, insufficient),
+                    Screened::InsufficientData => (rows, insufficient + 1),
+                },
+            )
+            .reduce(
+                || (Vec::new(), 0),
+                |(mut rows, insufficient), (more, more_insufficient)| {
+                    rows.extend(more);
+                    (rows, insufficient + more_insufficient)
+                },
+            );
+        rows.sort_by(|a, b| self.compare(a, b));
+        ScreenResults {
+            rows,
+            screened: series.len(),
+            insufficient_data,
+        }
+    }
+
+    /// The screen's sort, with missing values last in either direction and ties by symbol
+    fn compare(&self, a: &ScreenRow, b: &ScreenRow) -> Ordering {
+        let by_symbol = || a.symbol.full_identifier().cmp(&b.symbol.full_identifier());
+        let Some(sort) = &self.criteria.sort else {
+            return by_symbol();
+        };
+        let direct = |ordering: Ordering| {
+            if sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        let ordering = match sort.column.as_str() {
+            "symbol" => direct(by_symbol()),
+            "close" => direct(a.close.cmp(&b.close)),
+            "volume" => direct(a.volume.cmp(&b.volume)),
+            column => {
+                let value = |row: &ScreenRow| row.values.get(column).copied().flatten();
+                match (value(a), value(b)) {
+                    (Some(a), Some(b)) => direct(a.total_cmp(&b)),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
+        };
+        ordering.then_with(by_symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use shared_types::{Exchange, Symbol, TimeFrame};
+
+    /// Daily bars closing at `closes`, the last one on `last_volume` and the rest on 1000
+    fn series(code: &str, closes: &[f64], last_volume: i64) -> Vec<OHLCV> {
+        let symbol = Symbol::stock(code, code, Exchange::NASDAQ).unwrap();
+        closes
+            .iter()
+            .enumerate()
+            .map(|(index, close)| {
+                let close = Decimal::from_f64_retain(*close).unwrap();
+                let volume = if index + 1 == closes.len() {
+                    last_volume
+                } else {
+                    1000
+                };
+                OHLCV::new(
+                    symbol.clone(),
+                    TimeFrame::OneDay,
+                    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+                        + Duration::days(index as i64),
+                    close,
+                    close + Decimal::ONE,
+                    close - Decimal::ONE,
+                    close,
+                    Decimal::new(volume, 0),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn falling(from: f64) -> Vec<f64> {
+        (0..20).map(|day| from - day as f64).collect()
+    }
+
+    #[test]
+    fn test_screens_sorts_and_pages() {
+        let universe = vec![
+            series("AAPL", &falling(100.0), 3000),
+            series("MSFT", &falling(200.0), 2500),
+            series("NVDA", &falling(300.0), 1000),
+            series(
+                "TSLA",
+                &(0..20).map(|day| 50.0 + day as f64).collect::<Vec<_>>(),
+                5000,
+            ),
+            series("AMD", &falling(80.0)[..5], 9000),
+        ];
+        let criteria = ScreenCriteria::new("rsi(14) < 30 and volume > 2 * avg_volume(20)")
+            .with_column("volume_ratio", "volume / avg_volume(20)")
+            .with_column("distance", "close / sma(50)")
+            .with_sort("volume_ratio", true);
+        let screener = Screener::new(criteria.clone()).unwrap();
+        assert_eq!(screener.warm_up_bars(), 50);
+
+        let results = screener.run(&universe);
+        assert_eq!(results.screened, 5);
+        assert_eq!(results.insufficient_data, 1);
+        let codes: Vec<&str> = results
+            .rows
+            .iter()
+            .map(|row| row.symbol.code.as_str())
+            .collect();
+        assert_eq!(codes, ["AAPL", "MSFT"]);
+        let ratio = results.rows[0].values["volume_ratio"].unwrap();
+        assert!((ratio - 3000.0 / 1100.0).abs() < 1e-9);
+        assert_eq!(results.rows[0].values["distance"], None);
+
+        let page = results.page(2, 1);
+        assert_eq!(page.rows[0].symbol.code, "MSFT");
+        assert_eq!(page.pagination.total_pages, 2);
+        assert!(page.pagination.has_previous && !page.pagination.has_next);
+        assert!(results.page(3, 1).rows.is_empty());
+
+        let ascending = Screener::new(criteria.with_sort("close", false)).unwrap();
+        assert_eq!(ascending.run(&universe).rows[0].symbol.code, "AAPL");
+    }
+
+    #[test]
+    fn test_invalid_screens_name_the_problem() {
+        assert!(matches!(
+            Screener::new(ScreenCriteria::new("rsi(14)")),
+            Err(ScreenError::Filter(ExpressionError::Type { .. }))
+        ));
+        assert!(matches!(
+            Screener::new(ScreenCriteria::new("close > 1").with_column("rsi", "rsi(14) < 30")),
+            Err(ScreenError::Column { column, .. }) if column == "rsi"
+        ));
+        assert_eq!(
+            Screener::new(ScreenCriteria::new("close > 1").with_sort("rsi", true)),
+            Err(ScreenError::Criteria(
+                ScreenCriteriaError::UnknownSortColumn("rsi".to_string())
+            ))
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Small descriptive statistics helpers shared by the analytics modules
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use shared_types::AnalysisError;
+
+/// Trading days per year used to annualize daily figures
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Convert a decimal input for floating-point math
+///
+/// Fails, naming `parameter`, rather than letting an unrepresentable value
+/// quietly become zero in the result.
+pub fn to_f64(parameter: &str, value: Decimal) -> Result<f64, AnalysisError> {
+    value
+        .to_f64()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| AnalysisError::InvalidAnalysisParameters {
+            parameter: parameter.to_string(),
+            value: value.to_string(),
+        })
+}
+
+/// Arithmetic mean, `None` for an empty sample
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sample variance (n - 1 denominator), `None` with fewer than two values
+pub fn variance(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let sum_sq: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+    Some(sum_sq / (values.len() - 1) as f64)
+}
+
+/// Sample standard deviation
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    variance(values).map(f64::sqrt)
+}
+
+/// Sample covariance of two equally long series
+pub fn covariance(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+    let mean_x = mean(xs)?;
+    let mean_y = mean(ys)?;
+    let sum: f64 = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    Some(sum / (xs.len() - 1) as f64)
+}
+
+/// Standard normal probability density
+pub fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Standard normal CDF (Hart's double-precision approximation, as given by West)
+pub fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs();
+    let tail = if z > 37.0 {
+        0.0
+    } else if z < 7.071_067_811_865_47 {
+        const N: [f64; 7] = [
+            3.526_249_659_989_11e-2,
+            0.700_383_064_443_688,
+            6.373_962_203_531_65,
+            33.912_866_078_383,
+            112.079_291_497_871,
+            221.213_596_169_931,
+            220.206_867_912_376,
+        ];
+        const D: [f64; 8] = [
+            8.838_834_764_831_84e-2,
+            1.755_667_163_182_64,
+            16.064_177_579_207,
+            86.780_732_202_946_1,
+            296.564_248_779_674,
+            637.333_633_378_831,
+            793.826_512_519_948,
+            440.413_735_824_752,
+        ];
+        let horner = |coefficients: &[f64]| coefficients.iter().fold(0.0, |acc, c| acc * z + c);
+        (-0.5 * z * z).exp() * horner(&N) / horner(&D)
+    } else {
+        // Continued fraction for the far tail
+        let fraction = z + 1.0 / (z + 2.0 / (z + 3.0 / (z + 4.0 / (z + 0.65))));
+        (-0.5 * z * z).exp() / fraction / (2.0 * std::f64::consts::PI).sqrt()
+    };
+    if x > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// Inverse of the standard normal CDF (Acklam's rational approximation)
+///
+/// Accurate to about 1e-9 for `p` in (0, 1); returns `None` outside that range.
+pub fn normal_inverse_cdf(p: f64) -> Option<f64> {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if !(p > 0.0 && p < 1.0) {
+        return None;
+    }
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    let x = if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    };
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_statistics() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+
+        assert_eq!(mean(&values), Some(5.0));
+        assert!((variance(&values).unwrap() - 32.0 / 7.0).abs() < 1e-12);
+        assert!((covariance(&values, &values).unwrap() - variance(&values).unwrap()).abs() < 1e-12);
+        assert_eq!(mean(&[]), None);
+        assert_eq!(std_dev(&[1.0]), None);
+        assert_eq!(covariance(&[1.0, 2.0], &[1.0]), None);
+    }
+
+    #[test]
+    fn test_normal_inverse_cdf() {
+        assert!((normal_inverse_cdf(0.5).unwrap()).abs() < 1e-9);
+        assert!((normal_inverse_cdf(0.95).unwrap() - 1.644_853_626_951).abs() < 1e-8);
+        assert!((normal_inverse_cdf(0.01).unwrap() + 2.326_347_874_041).abs() < 1e-8);
+        assert_eq!(normal_inverse_cdf(1.0), None);
+        assert!((normal_pdf(0.0) - 0.398_942_280_401).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-15);
+        assert!((normal_cdf(1.96) - 0.975_002_104_851_78).abs() < 1e-12);
+        assert!((normal_cdf(-8.0) / 6.220_960_574_271_78e-16 - 1.0).abs() < 1e-7);
+        for p in [0.01, 0.3, 0.95] {
+            assert!((normal_cdf(normal_inverse_cdf(p).unwrap()) - p).abs() < 1e-8);
+        }
+    }
+}
@@ -0,0 +1,586 @@
+use analytics::{lookback_bars, AlertEngine};
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use database::{AlertRepository, OhlcvRepository};
+use serde::{Deserialize, Serialize};
+use shared_types::{
+    AlertCondition, AlertEvent, AlertMode, AlertRule, ApiError, ApiResponse, Symbol, TimeFrame,
+    WebSocketMessage, OHLCV,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::error::{self, GatewayError};
+use crate::notifications::NotificationDispatcher;
+use crate::GatewayState;
+
+/// Alert messages buffered for slow WebSocket subscribers before they lag
+const ALERT_CHANNEL_CAPACITY: usize = 256;
+
+/// Alert rules evaluated on live bars, with SQLite persistence and WebSocket delivery
+///
+/// Rules live in the repository and are mirrored in an [`AlertEngine`]. Every
+/// bar passed to [`on_bar`](Self::on_bar) is evaluated; each firing is written
+/// to the alert history, the rule's trigger state is saved, and an `alert`
+/// message is broadcast to every [`subscribe`](Self::subscribe)r. With a
+/// [`NotificationDispatcher`] attached, each event is also sent to the users
+/// who asked for it, in the background so slow channels never hold up bars.
+///
+/// With a bar store attached, rules whose lookback is longer than the bars
+/// already held for their series are seeded from it when added or loaded.
+pub struct AlertService {
+    engine: Mutex<AlertEngine>,
+    repository: Arc<AlertRepository>,
+    bars: Option<Arc<OhlcvRepository>>,
+    sender: broadcast::Sender<WebSocketMessage>,
+    notifications: Option<Arc<NotificationDispatcher>>,
+}
+
+impl AlertService {
+    pub fn new(repository: Arc<AlertRepository>) -> Self {
+        let (sender, _) = broadcast::channel(ALERT_CHANNEL_CAPACITY);
+        Self {
+            engine: Mutex::new(AlertEngine::new()),
+            repository,
+            bars: None,
+            sender,
+            notifications: None,
+        }
+    }
+
+    pub fn with_bars(mut self, bars: Arc<OhlcvRepository>) -> Self {
+        self.bars = Some(bars);
+        self
+    }
+
+    pub fn with_notifications(mut self, dispatcher: Arc<NotificationDispatcher>) -> Self {
+        self.notifications = Some(dispatcher);
+        self
+    }
+
+    /// Load active rules from storage, returning how many the engine accepted
+    ///
+    /// Stored rules the engine can no longer evaluate are skipped with a warning.
+    pub async fn load(&self) -> Result<usize, GatewayError> {
+        let rules = self.repository.active_rules().await?;
+        let mut loaded = 0;
+        for rule in rules {
+            let id = rule.id;
+            let history = self.stored_history(&rule).await?;
+            match self.engine().add_rule_with_history(rule, &history) {
+                Ok(()) => loaded += 1,
+                Err(error) => tracing::warn!(rule = %id, %error, "skipping stored alert rule"),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Receiver of `alert` WebSocket messages for one connection
+    pub fn subscribe(&self) -> broadcast::Receiver<WebSocketMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Validate and store a new rule, then start evaluating it
+    pub async fn create_rule(&self, rule: AlertRule) -> Result<AlertRule, GatewayError> {
+        let history = self.stored_history(&rule).await?;
+        self.engine()
+            .add_rule_with_history(rule.clone(), &history)?;
+        if let Err(error) = self.repository.save_rule(&rule).await {
+            self.engine().remove_rule(rule.id);
+            return Err(error.into());
+        }
+        Ok(rule)
+    }
+
+    /// Replace a rule's definition, re-arming it with a clean trigger state
+    ///
+    /// The previous definition keeps being evaluated if the new one is invalid
+    /// or cannot be stored.
+    pub async fn update_rule(
+        &self,
+        id: Uuid,
+        request: AlertRuleRequest,
+    ) -> Result<AlertRule, GatewayError> {
+        let previous = self.rule(id).await?;
+        let rule = AlertRule {
+            id,
+            created_at: previous.created_at,
+            ..request.into_rule()
+        };
+
+        let history = self.stored_history(&rule).await?;
+        self.replace(&previous, &rule, &history)?;
+        if let Err(error) = self.repository.save_rule(&rule).await {
+            self.replace(&rule, &previous, &[])?;
+            return Err(error.into());
+        }
+        Ok(rule)
+    }
+
+    /// Stop evaluating and delete a rule, keeping its history
+    pub async fn delete_rule(&self, id: Uuid) -> Result<(), GatewayError> {
+        self.engine().remove_rule(id);
+        if self.repository.delete_rule(id).await? {
+            Ok(())
+        } else {
+            Err(not_found(id))
+        }
+    }
+
+    pub async fn rule(&self, id: Uuid) -> Result<AlertRule, GatewayError> {
+        self.repository
+            .get_rule(id)
+            .await?
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Current state of every rule, including deactivated one-shot rules
+    pub async fn rules(&self) -> Result<Vec<AlertRule>, GatewayError> {
+        Ok(self.repository.rules().await?)
+    }
+
+    pub async fn history(
+        &self,
+        rule_id: Uuid,
+        limit: u32,
+    ) -> Result<Vec<AlertEvent>, GatewayError> {
+        Ok(self.repository.rule_history(rule_id, limit).await?)
+    }
+
+    /// Evaluate a new bar, then persist and broadcast whatever it fired
+    pub async fn on_bar(&self, bar: &OHLCV) -> Result<Vec<AlertEvent>, GatewayError> {
+        let fired: Vec<(AlertEvent, Option<AlertRule>)> = {
+            let mut engine = self.engine();
+            engine
+                .on_bar(bar)
+                .into_iter()
+                .map(|event| {
+                    let rule = engine.rule(event.rule_id).cloned();
+                    (event, rule)
+                })
+                .collect()
+        };
+
+        let mut events = Vec::with_capacity(fired.len());
+        for (event, rule) in fired {
+            if let Some(rule) = rule {
+                self.repository.save_rule(&rule).await?;
+            }
+            self.repository.record_event(&event).await?;
+            // Nobody listening is not an error; the history still has the alert
+            let _ = self.sender.send(event.to_message());
+            if let Some(dispatcher) = &self.notifications {
+                let dispatcher = dispatcher.clone();
+                let event = event.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = dispatcher.notify_alert(&event).await {
+                        tracing::error!(event = %event.id, ?error, "alert notification failed");
+                    }
+                });
+            }
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    fn engine(&self) -> std::sync::MutexGuard<'_, AlertEngine> {
+        self.engine
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Stored bars of a rule's series, when the engine holds fewer than it looks back on
+    ///
+    /// Empty for rules the engine will reject; adding them reports why.
+    async fn stored_history(&self, rule: &AlertRule) -> Result<Vec<OHLCV>, GatewayError> {
+        let (Some(bars), Ok(lookback)) = (&self.bars, lookback_bars(&rule.condition)) else {
+            return Ok(Vec::new());
+        };
+        let held = self.engine().history_len(&rule.symbol, &rule.timeframe);
+        if held >= lookback {
+            return Ok(Vec::new());
+        }
+        Ok(bars
+            .latest_bars(&rule.symbol, &rule.timeframe, lookback as u32)
+            .await?)
+    }
+
+    /// Swap `current` for `next` in the engine, restoring `current` if `next` is rejected
+    fn replace(
+        &self,
+        current: &AlertRule,
+        next: &AlertRule,
+        history: &[OHLCV],
+    ) -> Result<(), GatewayError> {
+        let mut engine = self.engine();
+        engine.remove_rule(current.id);
+        if !next.active {
+            return Ok(());
+        }
+        if let Err(error) = engine.add_rule_with_history(next.clone(), history) {
+            if current.active {
+                // It was accepted before, so it is accepted again
+                let _ = engine.add_rule(current.clone());
+            }
+            return Err(error.into());
+        }
+        Ok(())
+    }
+}
+
+fn not_found(id: Uuid) -> GatewayError {
+    GatewayError(ApiError::NotFound {
+        resource: format!("alert rule {}", id),
+    })
+}
+
+// ============================================================================
+// Routes
+// ============================================================================
+
+/// Alert history entries returned when no `limit` is given
+pub const DEFAULT_HISTORY_LIMIT: u32 = 50;
+
+/// A rule definition as sent by clients; the trigger state is the service's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRuleRequest {
+    pub name: String,
+    pub symbol: Symbol,
+    pub timeframe: TimeFrame,
+    pub condition: AlertCondition,
+
+    #[serde(default)]
+    pub mode: AlertMode,
+
+    #[serde(default)]
+    pub cooldown_seconds: u64,
+}
+
+impl AlertRuleRequest {
+    pub fn into_rule(self) -> AlertRule {
+        AlertRule::new(self.name, self.symbol, self.timeframe, self.condition)
+            .with_mode(self.mode)
+            .with_cooldown(std::time::Duration::from_secs(self.cooldown_seconds))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default = "default_history_limit")]
+    pub limit: u32,
+}
+
+fn default_history_limit() -> u32 {
+    DEFAULT_HISTORY_LIMIT
+}
+
+/// Alert rule routes, under `/api/v1/alerts`
+pub fn routes() -> Router<GatewayState> {
+    Router::new()
+        .route("/api/v1/alerts/rules", get(list).post(create))
+        .route(
+            "/api/v1/alerts/rules/{id}",
+            get(show).put(update).delete(delete),
+        )
+        .route("/api/v1/alerts/rules/{id}/history", get(history))
+}
+
+type Reply<T> = Result<Json<ApiResponse<T>>, GatewayError>;
+
+fn service(state: &GatewayState) -> Result<&AlertService, GatewayError> {
+    state.alerts.as_deref().ok_or_else(|| {
+        GatewayError(ApiError::ServiceUnavailable {
+            message: "Alerts are not configured".to_string(),
+        })
+    })
+}
+
+fn id(path: Result<Path<Uuid>, PathRejection>) -> Result<Uuid, GatewayError> {
+    path.map(|Path(id)| id)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+fn body<T>(body: Result<Json<T>, JsonRejection>) -> Result<T, GatewayError> {
+    body.map(|Json(body)| body)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+async fn list(State(state): State<GatewayState>) -> Reply<Vec<AlertRule>> {
+    let started = Instant::now();
+    Ok(error::success(service(&state)?.rules().await?, started))
+}
+
+async fn create(
+    State(state): State<GatewayState>,
+    request: Result<Json<AlertRuleRequest>, JsonRejection>,
+) -> Reply<AlertRule> {
+    let started = Instant::now();
+    let rule = body(request)?.into_rule();
+    Ok(error::success(
+        service(&state)?.create_rule(rule).await?,
+        started,
+    ))
+}
+
+async fn show(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+) -> Reply<AlertRule> {
+    let started = Instant::now();
+    Ok(error::success(
+        service(&state)?.rule(id(path)?).await?,
+        started,
+    ))
+}
+
+async fn update(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+    request: Result<Json<AlertRuleRequest>, JsonRejection>,
+) -> Reply<AlertRule> {
+    let started = Instant::now();
+    let (id, request) = (id(path)?, body(request)?);
+    Ok(error::success(
+        service(&state)?.update_rule(id, request).await?,
+        started,
+    ))
+}
+
+async fn delete(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+) -> Reply<Uuid> {
+    let started = Instant::now();
+    let id = id(path)?;
+    service(&state)?.delete_rule(id).await?;
+    Ok(error::success(id, started))
+}
+
+async fn history(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+    query: Result<Query<HistoryQuery>, QueryRejection>,
+) -> Reply<Vec<AlertEvent>> {
+    let started = Instant::now();
+    let id = id(path)?;
+    let Query(query) =
+        query.map_err(|rejection| GatewayError::bad_request(rejection.body_text()))?;
+    let service = service(&state)?;
+    service.rule(id).await?;
+    Ok(error::success(
+        service.history(id, query.limit).await?,
+        started,
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use chrono::{Duration, TimeZone, Utc};
+    use database::{SqlitePool, SqlitePoolConfig};
+    use http_body_util::BodyExt;
+    use rust_decimal::Decimal;
+    use serde_json::{json, Value};
+    use shared_types::{Comparison, Exchange, WebSocketMessageType};
+    use tower::ServiceExt;
+
+    pub(crate) fn apple() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    pub(crate) fn bar(hour: i64, close: i64) -> OHLCV {
+        let close = Decimal::new(close, 0);
+        OHLCV::new(
+            apple(),
+            TimeFrame::OneHour,
+            Utc.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap() + Duration::hours(hour),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    pub(crate) async fn repository() -> Arc<AlertRepository> {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = AlertRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        Arc::new(repository)
+    }
+
+    #[tokio::test]
+    async fn test_fired_alerts_are_stored_and_broadcast() {
+        let repository = repository().await;
+        let service = AlertService::new(repository.clone());
+        let mut alerts = service.subscribe();
+
+        let rule = service
+            .create_rule(
+                AlertRule::new(
+                    "above 105",
+                    apple(),
+                    TimeFrame::OneHour,
+                    AlertCondition::Price {
+                        comparison: Comparison::Above,
+                        level: Decimal::new(105, 0),
+                    },
+                )
+                .with_mode(AlertMode::OneShot),
+            )
+            .await
+            .unwrap();
+
+        assert!(service.on_bar(&bar(0, 100)).await.unwrap().is_empty());
+        let events = service.on_bar(&bar(1, 106)).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(service.on_bar(&bar(2, 107)).await.unwrap().is_empty());
+
+        let message = alerts.try_recv().unwrap();
+        assert_eq!(message.message_type, WebSocketMessageType::Alert);
+        assert_eq!(message.payload["event"]["rule_name"], "above 105");
+        assert!(alerts.try_recv().is_err());
+
+        assert_eq!(service.history(rule.id, 10).await.unwrap(), events);
+        let stored = repository.get_rule(rule.id).await.unwrap().unwrap();
+        assert!(!stored.active);
+        assert_eq!(stored.last_triggered, Some(bar(1, 106).timestamp));
+
+        // A restarted service does not pick the spent one-shot rule back up
+        assert_eq!(AlertService::new(repository).load().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_rules_are_not_stored() {
+        let service = AlertService::new(repository().await);
+        let error = service
+            .create_rule(AlertRule::new(
+                "triangle",
+                apple(),
+                TimeFrame::OneHour,
+                AlertCondition::Pattern {
+                    pattern: "ascending_triangle".to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(service.rules().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_new_rules_are_seeded_from_stored_bars() {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let pool = Arc::new(SqlitePool::new(config).await.unwrap());
+        let rules = AlertRepository::new(pool.clone());
+        rules.migrate().await.unwrap();
+        let bars = Arc::new(OhlcvRepository::new(pool));
+        bars.migrate().await.unwrap();
+        let stored: Vec<OHLCV> = (0..30).map(|hour| bar(hour, 100)).collect();
+        bars.upsert_bars(&stored).await.unwrap();
+
+        let service = AlertService::new(Arc::new(rules)).with_bars(bars);
+        service
+            .create_rule(AlertRule::new(
+                "breakout",
+                apple(),
+                TimeFrame::OneHour,
+                AlertCondition::Expression {
+                    expression: "close > sma(20)".to_string(),
+                },
+            ))
+            .await
+            .unwrap();
+        // The average already has a value on the first live bar
+        assert_eq!(service.on_bar(&bar(30, 101)).await.unwrap().len(), 1);
+    }
+
+    async fn call(
+        state: &GatewayState,
+        method: Method,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = crate::app(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_rule_routes() {
+        let without = crate::watchlists::tests::state().await;
+        let (status, _) = call(&without, Method::GET, "/api/v1/alerts/rules", Value::Null).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let service = Arc::new(AlertService::new(repository().await));
+        let state = without.with_alerts(service.clone());
+        let rule = json!({
+            "name": "above 105",
+            "symbol": apple(),
+            "timeframe": "1h",
+            "condition": {"type": "price", "comparison": "above", "level": 105},
+        });
+        let (status, body) = call(&state, Method::POST, "/api/v1/alerts/rules", rule.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = body["data"]["id"].as_str().unwrap().to_string();
+        let uri = format!("/api/v1/alerts/rules/{}", id);
+
+        service.on_bar(&bar(0, 106)).await.unwrap();
+        let (_, body) = call(
+            &state,
+            Method::GET,
+            &format!("{}/history", uri),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        // Editing re-arms the rule under its new definition
+        let mut edited = rule.clone();
+        edited["condition"]["level"] = json!(110);
+        let (status, body) = call(&state, Method::PUT, &uri, edited).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["condition"]["level"], 110.0);
+        assert_eq!(body["data"]["last_triggered"], Value::Null);
+        assert!(service.on_bar(&bar(1, 107)).await.unwrap().is_empty());
+        assert_eq!(service.on_bar(&bar(2, 111)).await.unwrap().len(), 1);
+
+        let mut invalid = rule;
+        invalid["condition"] = json!({"type": "pattern", "pattern": "ascending_triangle"});
+        let (status, _) = call(&state, Method::PUT, &uri, invalid).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (_, body) = call(&state, Method::GET, &uri, Value::Null).await;
+        assert_eq!(body["data"]["condition"]["level"], 110.0);
+
+        let (status, _) = call(&state, Method::DELETE, &uri, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&state, Method::GET, &uri, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = call(&state, Method::GET, "/api/v1/alerts/rules", Value::Null).await;
+        assert_eq!(body["data"], json!([]));
+    }
+}
@@ -0,0 +1,151 @@
+use analytics::{AlertError, ScreenError};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use database::DatabaseError;
+use serde::Serialize;
+use shared_types::{
+    ApiError, ApiResponse, PaginationInfo, ResponseMetadata, ScreenCriteriaError, ValidationError,
+    WatchlistError,
+};
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::SOURCE;
+
+/// An [`ApiError`] returned from a handler, sent as an error `ApiResponse`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatewayError(pub ApiError);
+
+impl GatewayError {
+    pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self(ApiError::Validation {
+            message: message.into(),
+            field: Some(field.into()),
+        })
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self(ApiError::BadRequest {
+            message: message.into(),
+        })
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match &self.0 {
+            ApiError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Authentication { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Authorization { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::RateLimit { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::ExternalService { .. } => StatusCode::BAD_GATEWAY,
+            ApiError::ServiceUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Database { .. } | ApiError::Internal { .. } => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+impl From<ApiError> for GatewayError {
+    fn from(error: ApiError) -> Self {
+        Self(error)
+    }
+}
+
+impl From<DatabaseError> for GatewayError {
+    fn from(error: DatabaseError) -> Self {
+        Self(ApiError::Database {
+            message: error.to_string(),
+        })
+    }
+}
+
+/// Rules are user input, so a condition the engine cannot evaluate is a validation failure
+impl From<AlertError> for GatewayError {
+    fn from(error: AlertError) -> Self {
+        match error {
+            AlertError::InvalidExpression(error) => Self::validation(
+                "condition.expression",
+                ValidationError::from(error).to_string(),
+            ),
+            error => Self::validation("condition", error.to_string()),
+        }
+    }
+}
+
+impl From<WatchlistError> for GatewayError {
+    fn from(error: WatchlistError) -> Self {
+        match error {
+            WatchlistError::InvalidName => Self::validation("name", error.to_string()),
+            WatchlistError::DuplicateMember(_) => Self::validation("symbol", error.to_string()),
+            WatchlistError::NotAMember(key) => Self(ApiError::NotFound {
+                resource: format!("watchlist member {}", key),
+            }),
+        }
+    }
+}
+
+impl From<ScreenCriteriaError> for GatewayError {
+    fn from(error: ScreenCriteriaError) -> Self {
+        let field = match error {
+            ScreenCriteriaError::InvalidName => "name",
+            ScreenCriteriaError::DuplicateColumn(_) => "columns",
+            ScreenCriteriaError::UnknownSortColumn(_) => "sort.column",
+        };
+        Self::validation(field, error.to_string())
+    }
+}
+
+impl From<ScreenError> for GatewayError {
+    fn from(error: ScreenError) -> Self {
+        match error {
+            ScreenError::Criteria(error) => error.into(),
+            ScreenError::Filter(error) => {
+                Self::validation("filter", ValidationError::from(error).to_string())
+            }
+            ScreenError::Column { column, error } => Self::validation(
+                format!("columns.{}", column),
+                ValidationError::from(error).to_string(),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body: ApiResponse<()> =
+            ApiResponse::error(Uuid::new_v4(), self.0, metadata(Instant::now()));
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Successful response envelope, timed from `started`
+pub fn success<T: Serialize>(data: T, started: Instant) -> Json<ApiResponse<T>> {
+    Json(ApiResponse::success(
+        Uuid::new_v4(),
+        data,
+        metadata(started),
+    ))
+}
+
+/// Successful response envelope for one page of a longer result
+pub fn paginated<T: Serialize>(
+    data: T,
+    pagination: PaginationInfo,
+    started: Instant,
+) -> Json<ApiResponse<T>> {
+    let mut response = success(data, started);
+    response.0.metadata.pagination = Some(pagination);
+    response
+}
+
+fn metadata(started: Instant) -> ResponseMetadata {
+    ResponseMetadata {
+        processing_time_ms: started.elapsed().as_millis() as u64,
+        source: SOURCE.to_string(),
+        ..ResponseMetadata::default()
+    }
+}
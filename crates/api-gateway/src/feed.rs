@@ -0,0 +1,310 @@
+//! Live exchange feeds for the symbols stream connections subscribe to
+//!
+//! [`LiveFeed`] follows the [`QuoteHub`]'s demand. Market data subscriptions
+//! are grouped by exchange and timeframe, and each group runs one
+//! [`ExchangeFeed`]: trades for subscriptions without a timeframe, klines for
+//! the rest. A group restarts when its symbols change and stops when its last
+//! subscriber lets go. Klines missed while a feed reconnects are fetched from
+//! the exchange's REST API. Only Binance, Coinbase and Kraken stream; symbols
+//! on other exchanges get no live quotes.
+//!
+//! Trades and every kline update are published to the hub. Closed bars also
+//! go to the [`AlertService`] and the [`ScreenerService`] when they are set.
+
+use market_data::{
+    ExchangeFeed, ExchangeRestProvider, FeedEvent, FeedSubscription, MarketDataProvider,
+    ProviderResult,
+};
+use shared_types::{
+    Exchange, SubscriptionType, Symbol, Tick, TimeFrame, WebSocketMessage, WebSocketMessageType,
+    OHLCV,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+use crate::stream::{message, QuoteHub};
+use crate::{AlertService, ScreenerService};
+
+/// The exchange and timeframe one [`ExchangeFeed`] streams
+type FeedKey = (Exchange, Option<TimeFrame>);
+
+/// Exchange feeds for everything stream connections are quoted on
+pub struct LiveFeed {
+    hub: Arc<QuoteHub>,
+    alerts: Option<Arc<AlertService>>,
+    screener: Option<Arc<ScreenerService>>,
+    urls: HashMap<Exchange, String>,
+    resync: HashMap<Exchange, Arc<dyn MarketDataProvider>>,
+}
+
+impl LiveFeed {
+    pub fn new(hub: Arc<QuoteHub>) -> Self {
+        let mut resync: HashMap<Exchange, Arc<dyn MarketDataProvider>> = HashMap::new();
+        for exchange in [Exchange::Binance, Exchange::Coinbase, Exchange::Kraken] {
+            match ExchangeRestProvider::new(&exchange) {
+                Ok(provider) => {
+                    resync.insert(exchange, Arc::new(provider));
+                }
+                Err(error) => tracing::warn!(%exchange, %error, "klines will not be resynced"),
+            }
+        }
+        Self {
+            hub,
+            alerts: None,
+            screener: None,
+            urls: HashMap::new(),
+            resync,
+        }
+    }
+
+    /// Evaluate alert rules on closed bars
+    pub fn with_alerts(mut self, alerts: Arc<AlertService>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Keep the screener's cached series current with closed bars
+    pub fn with_screener(mut self, screener: Arc<ScreenerService>) -> Self {
+        self.screener = Some(screener);
+        self
+    }
+
+    /// Override an exchange's endpoint, e.g. to point at a local test server
+    pub fn with_url(mut self, exchange: Exchange, url: impl Into<String>) -> Self {
+        self.urls.insert(exchange, url.into());
+        self
+    }
+
+    /// Fill an exchange's reconnect gaps from `provider` instead of its REST API
+    pub fn with_resync(
+        mut self,
+        exchange: Exchange,
+        provider: Arc<dyn MarketDataProvider>,
+    ) -> Self {
+        self.resync.insert(exchange, provider);
+        self
+    }
+
+    /// Follow the hub's demand; spawn it, since it only returns if the hub goes away
+    pub async fn run(self) {
+        let mut upstream = self.hub.upstream();
+        let mut running: HashMap<FeedKey, Running> = HashMap::new();
+        loop {
+            self.follow(&mut running);
+            match upstream.recv().await {
+                // The hub's demand is the whole picture, so a missed delta costs nothing
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Start, restart and stop feeds so they match the hub's demand
+    fn follow(&self, running: &mut HashMap<FeedKey, Running>) {
+        let mut wanted: HashMap<FeedKey, BTreeMap<String, Symbol>> = HashMap::new();
+        for request in self.hub.demand() {
+            if request.subscription_type != SubscriptionType::MarketData {
+                continue;
+            }
+            wanted
+                .entry((request.symbol.exchange.clone(), request.timeframe.clone()))
+                .or_default()
+                .insert(request.symbol.full_identifier(), request.symbol);
+        }
+
+        running.retain(|key, _| wanted.contains_key(key));
+        for (key, symbols) in wanted {
+            let identifiers: Vec<String> = symbols.keys().cloned().collect();
+            if running
+                .get(&key)
+                .is_some_and(|feed| feed.identifiers == identifiers)
+            {
+                continue;
+            }
+            running.remove(&key);
+            match self.start(&key, symbols.into_values().collect()) {
+                Ok(subscription) => {
+                    let task = tokio::spawn(self.pump(subscription));
+                    running.insert(key, Running { identifiers, task });
+                }
+                Err(error) => {
+                    tracing::debug!(exchange = %key.0, %error, "no live feed for these symbols")
+                }
+            }
+        }
+    }
+
+    fn start(&self, key: &FeedKey, symbols: Vec<Symbol>) -> ProviderResult<FeedSubscription> {
+        let (exchange, timeframe) = key;
+        let mut feed = ExchangeFeed::new(exchange, symbols)?.with_trades(timeframe.is_none());
+        if let Some(timeframe) = timeframe {
+            feed = feed.with_bars(timeframe.clone());
+        }
+        if let Some(url) = self.urls.get(exchange) {
+            feed = feed.with_url(url.clone());
+        }
+        if let Some(provider) = self.resync.get(exchange) {
+            feed = feed.with_resync(provider.clone());
+        }
+        feed.start()
+    }
+
+    /// Publish a feed's events until it gives up or is stopped
+    fn pump(&self, mut subscription: FeedSubscription) -> impl std::future::Future<Output = ()> {
+        let hub = self.hub.clone();
+        let alerts = self.alerts.clone();
+        let screener = self.screener.clone();
+        async move {
+            while let Some(event) = subscription.next().await {
+                match event {
+                    Ok(FeedEvent::Trade(tick)) => hub.publish(trade_message(&tick)),
+                    Ok(FeedEvent::Bar { bar, is_closed }) => {
+                        hub.publish(bar_message(&bar, is_closed));
+                        if !is_closed {
+                            continue;
+                        }
+                        if let Some(screener) = &screener {
+                            screener.on_bar(&bar);
+                        }
+                        if let Some(alerts) = &alerts {
+                            if let Err(error) = alerts.on_bar(&bar).await {
+                                tracing::warn!(error = %error.0, "could not evaluate alerts on a live bar");
+                            }
+                        }
+                    }
+                    Err(error) => tracing::warn!(%error, "exchange feed interrupted"),
+                }
+            }
+        }
+    }
+}
+
+/// A started feed and the symbols it streams
+struct Running {
+    identifiers: Vec<String>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        // The task owns the FeedSubscription, whose drop stops the feed
+        self.task.abort();
+    }
+}
+
+fn trade_message(tick: &Tick) -> WebSocketMessage {
+    message(
+        WebSocketMessageType::MarketData,
+        serde_json::to_value(tick).unwrap_or_default(),
+    )
+}
+
+fn bar_message(bar: &OHLCV, is_closed: bool) -> WebSocketMessage {
+    message(
+        WebSocketMessageType::MarketData,
+        serde_json::json!({
+            "symbol": bar.symbol,
+            "timeframe": bar.timeframe,
+            "bar": bar,
+            "is_closed": is_closed,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SubscriptionDelta;
+    use futures::{SinkExt, StreamExt};
+    use shared_types::SubscriptionRequest;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::Message;
+
+    const TRADE: &str = r#"{"e":"trade","E":1715608812345,"s":"BTCUSD","t":3579011,"p":"67123.45","q":"0.015","T":1715608812340,"m":true,"M":true}"#;
+
+    fn btc_usd() -> SubscriptionRequest {
+        SubscriptionRequest {
+            subscription_type: SubscriptionType::MarketData,
+            symbol: Symbol::crypto("BTC", "USD", Exchange::Binance).unwrap(),
+            timeframe: None,
+            parameters: HashMap::new(),
+        }
+    }
+
+    /// Accept one connection, report its subscription, send a trade, and
+    /// report when the client goes away
+    async fn exchange() -> (String, mpsc::Receiver<Option<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (events, received) = mpsc::channel(4);
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            if let Some(Ok(Message::Text(text))) = socket.next().await {
+                let _ = events.send(Some(text.to_string())).await;
+            }
+            socket.send(Message::text(TRADE)).await.unwrap();
+            while let Some(Ok(frame)) = socket.next().await {
+                if frame.is_close() {
+                    break;
+                }
+            }
+            let _ = events.send(None).await;
+        });
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn test_feed_follows_stream_demand() {
+        let (url, mut exchange) = exchange().await;
+        let hub = Arc::new(QuoteHub::new());
+        let mut quotes = hub.quotes();
+        // Every streaming exchange refills reconnect gaps over REST
+        let resynced = LiveFeed::new(hub.clone()).resync;
+        for exchange in [Exchange::Binance, Exchange::Coinbase, Exchange::Kraken] {
+            assert_eq!(
+                resynced[&exchange].name(),
+                exchange.to_string().to_lowercase()
+            );
+        }
+        tokio::spawn(
+            LiveFeed::new(hub.clone())
+                .with_url(Exchange::Binance, url)
+                .run(),
+        );
+
+        let subscribe = SubscriptionDelta {
+            subscribe: vec![btc_usd()],
+            unsubscribe: Vec::new(),
+        };
+        hub.apply(&subscribe);
+
+        let wait = Duration::from_secs(5);
+        let subscription = tokio::time::timeout(wait, exchange.recv())
+            .await
+            .unwrap()
+            .flatten()
+            .unwrap();
+        assert!(subscription.contains("btcusd@trade"));
+
+        let quote = tokio::time::timeout(wait, quotes.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.identifier, btc_usd().symbol.full_identifier());
+        assert_eq!(quote.timeframe, None);
+        assert_eq!(quote.message.payload["price"], serde_json::json!(67123.45));
+
+        // The last subscriber letting go stops the exchange connection
+        hub.apply(&SubscriptionDelta {
+            subscribe: Vec::new(),
+            unsubscribe: vec![btc_usd()],
+        });
+        let closed = tokio::time::timeout(wait, exchange.recv()).await.unwrap();
+        assert_eq!(closed, Some(None));
+    }
+}
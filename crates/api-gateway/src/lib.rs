@@ -0,0 +1,89 @@
+//! HTTP gateway in front of the Trading Intelligence Orchestrator services
+
+pub mod alerts;
+pub mod error;
+pub mod feed;
+pub mod notifications;
+pub mod options;
+pub mod screener;
+pub mod stream;
+pub mod watchlists;
+
+use axum::routing::post;
+use axum::Router;
+use std::sync::Arc;
+
+pub use alerts::AlertService;
+pub use error::GatewayError;
+pub use feed::LiveFeed;
+pub use notifications::{NotificationDispatcher, NotificationSender};
+pub use screener::ScreenerService;
+pub use stream::{PublishedQuote, QuoteHub, SubscriptionDelta, Subscriptions};
+pub use watchlists::{WatchlistChange, WatchlistService};
+
+/// Source recorded in the metadata of every gateway response
+pub const SOURCE: &str = "api-gateway";
+
+/// Services shared by the stateful routes and the quote stream
+#[derive(Clone)]
+pub struct GatewayState {
+    pub watchlists: Arc<WatchlistService>,
+    pub quotes: Arc<QuoteHub>,
+
+    /// Screener routes answer 503 without one
+    pub screener: Option<Arc<ScreenerService>>,
+
+    /// Alert routes answer 503 without one, and the stream sends no alerts
+    pub alerts: Option<Arc<AlertService>>,
+
+    /// Notification routes answer 503 without one
+    pub notifications: Option<Arc<NotificationDispatcher>>,
+}
+
+impl GatewayState {
+    pub fn new(watchlists: Arc<WatchlistService>) -> Self {
+        Self {
+            watchlists,
+            quotes: Arc::new(QuoteHub::new()),
+            screener: None,
+            alerts: None,
+            notifications: None,
+        }
+    }
+
+    pub fn with_quotes(mut self, quotes: Arc<QuoteHub>) -> Self {
+        self.quotes = quotes;
+        self
+    }
+
+    pub fn with_screener(mut self, screener: Arc<ScreenerService>) -> Self {
+        self.screener = Some(screener);
+        self
+    }
+
+    pub fn with_alerts(mut self, alerts: Arc<AlertService>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    pub fn with_notifications(mut self, notifications: Arc<NotificationDispatcher>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+}
+
+/// All gateway routes
+pub fn router() -> Router {
+    Router::new().route("/api/v1/options/greeks", post(options::greeks_table))
+}
+
+/// Every route, including those backed by `state`
+pub fn app(state: GatewayState) -> Router {
+    router().merge(
+        watchlists::routes()
+            .merge(screener::routes())
+            .merge(alerts::routes())
+            .merge(notifications::routes())
+            .with_state(state),
+    )
+}
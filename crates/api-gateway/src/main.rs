@@ -1,3 +1,117 @@
-fn main() {
-    println!("API Gateway starting...");
+use api_gateway::notifications::{ChatSender, SmtpConfig, SmtpSender, WebhookSender};
+use api_gateway::{
+    AlertService, GatewayError, GatewayState, LiveFeed, NotificationDispatcher, ScreenerService,
+    WatchlistService,
+};
+use database::{
+    AlertRepository, NotificationRepository, OhlcvRepository, ScreenRepository, SqlitePool,
+    SqlitePoolConfig, SymbolRepository, WatchlistRepository,
+};
+use std::sync::Arc;
+
+/// Listen address when `GATEWAY_ADDR` is unset
+const DEFAULT_ADDR: &str = "0.0.0.0:8080";
+
+/// Quote stream address when `GATEWAY_STREAM_ADDR` is unset
+const DEFAULT_STREAM_ADDR: &str = "0.0.0.0:8081";
+
+/// Gateway database when `GATEWAY_DATABASE_URL` is unset
+const DEFAULT_DATABASE_URL: &str = "sqlite://gateway.db?mode=rwc";
+
+/// Submission port when `GATEWAY_SMTP_PORT` is unset
+const DEFAULT_SMTP_PORT: u16 = 587;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let database_url =
+        std::env::var("GATEWAY_DATABASE_URL").unwrap_or_else(|_| DEFAULT_DATABASE_URL.to_string());
+    let state = match state(&database_url).await {
+        Ok(state) => state,
+        Err(GatewayError(error)) => {
+            tracing::error!(%error, "could not open the gateway database");
+            return Err(std::io::Error::other(error.to_string()));
+        }
+    };
+
+    let mut feed = LiveFeed::new(state.quotes.clone());
+    if let Some(alerts) = &state.alerts {
+        feed = feed.with_alerts(alerts.clone());
+    }
+    if let Some(screener) = &state.screener {
+        feed = feed.with_screener(screener.clone());
+    }
+    tokio::spawn(feed.run());
+
+    let stream_addr =
+        std::env::var("GATEWAY_STREAM_ADDR").unwrap_or_else(|_| DEFAULT_STREAM_ADDR.to_string());
+    let stream_listener = tokio::net::TcpListener::bind(&stream_addr).await?;
+    tracing::info!("Quote stream listening on {}", stream_addr);
+    tokio::spawn(api_gateway::stream::serve(stream_listener, state.clone()));
+
+    let addr = std::env::var("GATEWAY_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!("API gateway listening on {}", addr);
+    axum::serve(listener, api_gateway::app(state)).await
+}
+
+async fn state(url: &str) -> Result<GatewayState, GatewayError> {
+    let pool = Arc::new(SqlitePool::new(SqlitePoolConfig::builder().url(url).build()).await?);
+
+    let watchlists = WatchlistRepository::new(pool.clone());
+    watchlists.migrate().await?;
+    let symbols = SymbolRepository::new(pool.clone());
+    symbols.migrate().await?;
+    let bars = Arc::new(OhlcvRepository::new(pool.clone()));
+    bars.migrate().await?;
+    let screens = ScreenRepository::new(pool.clone());
+    screens.migrate().await?;
+    let alert_rules = AlertRepository::new(pool.clone());
+    alert_rules.migrate().await?;
+    let deliveries = NotificationRepository::new(pool);
+    deliveries.migrate().await?;
+
+    let notifications = Arc::new(dispatcher(deliveries));
+    let screener = ScreenerService::new(Arc::new(symbols), bars.clone(), Arc::new(screens));
+    let alerts = AlertService::new(Arc::new(alert_rules))
+        .with_bars(bars)
+        .with_notifications(notifications.clone());
+    let loaded = alerts.load().await?;
+    tracing::info!("Loaded {} active alert rules", loaded);
+
+    Ok(
+        GatewayState::new(Arc::new(WatchlistService::new(Arc::new(watchlists))))
+            .with_screener(Arc::new(screener))
+            .with_alerts(Arc::new(alerts))
+            .with_notifications(notifications),
+    )
+}
+
+/// Webhook and chat delivery, plus email when `GATEWAY_SMTP_HOST` names a relay
+///
+/// The relay is reached with STARTTLS on `GATEWAY_SMTP_PORT`, sending as
+/// `GATEWAY_SMTP_FROM` and signing in with `GATEWAY_SMTP_USERNAME` and
+/// `GATEWAY_SMTP_PASSWORD` when both are set.
+fn dispatcher(repository: NotificationRepository) -> NotificationDispatcher {
+    let dispatcher = NotificationDispatcher::new(Arc::new(repository))
+        .with_sender(Arc::new(WebhookSender::new()))
+        .with_sender(Arc::new(ChatSender::new()));
+    let Ok(host) = std::env::var("GATEWAY_SMTP_HOST") else {
+        tracing::info!("GATEWAY_SMTP_HOST is unset; email notifications will be dead-lettered");
+        return dispatcher;
+    };
+    let port = std::env::var("GATEWAY_SMTP_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_SMTP_PORT);
+    let from = std::env::var("GATEWAY_SMTP_FROM").unwrap_or_else(|_| format!("alerts@{}", host));
+    let mut config = SmtpConfig::new(host, port, from);
+    if let (Ok(username), Ok(password)) = (
+        std::env::var("GATEWAY_SMTP_USERNAME"),
+        std::env::var("GATEWAY_SMTP_PASSWORD"),
+    ) {
+        config = config.with_credentials(username, password);
+    }
+    dispatcher.with_sender(Arc::new(SmtpSender::new(config)))
 }
@@ -0,0 +1,466 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect;
+use sha2::Sha256;
+use shared_types::{ChannelKind, Notification, NotificationChannel};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{DeliveryError, NotificationSender};
+
+/// Header carrying the Unix time the webhook body was signed at
+pub const TIMESTAMP_HEADER: &str = "X-TIO-Timestamp";
+
+/// Header carrying `sha256=<hex HMAC>` of `"{timestamp}.{body}"`
+pub const SIGNATURE_HEADER: &str = "X-TIO-Signature";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where webhook and chat deliveries may be sent
+///
+/// Users choose their own endpoints, so by default the gateway only posts to
+/// public HTTPS hosts: a channel pointing at loopback, private or link-local
+/// addresses would otherwise let anyone make the gateway call into its own
+/// network. Host names are checked again when they resolve, and redirects are
+/// never followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EndpointPolicy {
+    /// HTTPS to public addresses only
+    #[default]
+    Public,
+
+    /// Any http(s) endpoint, for tests and closed networks
+    Local,
+}
+
+impl EndpointPolicy {
+    /// `url`, when deliveries may be sent to it
+    pub fn check(&self, url: &str) -> Result<reqwest::Url, DeliveryError> {
+        let invalid = |reason: &str| DeliveryError::InvalidChannel(format!("'{}' {}", url, reason));
+        let parsed = reqwest::Url::parse(url).map_err(|_| invalid("is not a URL"))?;
+        let host = parsed.host_str().ok_or_else(|| invalid("has no host"))?;
+        match (self, parsed.scheme()) {
+            (EndpointPolicy::Public, "https") | (EndpointPolicy::Local, "https" | "http") => {}
+            (EndpointPolicy::Public, _) => return Err(invalid("is not an https URL")),
+            (EndpointPolicy::Local, _) => return Err(invalid("is not an http(s) URL")),
+        }
+        if *self == EndpointPolicy::Local {
+            return Ok(parsed);
+        }
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let local_name = host.eq_ignore_ascii_case("localhost")
+            || host.to_ascii_lowercase().ends_with(".localhost");
+        let local_address = host.parse::<IpAddr>().is_ok_and(|ip| !is_public(ip));
+        if local_name || local_address {
+            return Err(invalid("is not a public address"));
+        }
+        Ok(parsed)
+    }
+}
+
+/// Whether `ip` is reachable on the public internet
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Resolves host names to their public addresses only, so a public-looking
+/// name cannot lead to the gateway's own network
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            let addresses: Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+/// Signature a receiver should find in [`SIGNATURE_HEADER`]
+///
+/// Signing the timestamp along with the body lets receivers reject replays
+/// of old deliveries.
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn client(timeout: Duration, endpoints: EndpointPolicy) -> reqwest::Client {
+    let builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(redirect::Policy::none());
+    let builder = match endpoints {
+        EndpointPolicy::Public => builder.dns_resolver(Arc::new(PublicResolver)),
+        EndpointPolicy::Local => builder,
+    };
+    builder.build().unwrap_or_default()
+}
+
+/// An HTTP client that only reaches endpoints its policy allows
+#[derive(Debug, Clone)]
+struct Client {
+    http: reqwest::Client,
+    timeout: Duration,
+    endpoints: EndpointPolicy,
+}
+
+impl Client {
+    fn new(timeout: Duration, endpoints: EndpointPolicy) -> Self {
+        Self {
+            http: client(timeout, endpoints),
+            timeout,
+            endpoints,
+        }
+    }
+}
+
+/// POST `body` as JSON with optional extra headers and map the outcome
+async fn post_json(
+    client: &Client,
+    url: &str,
+    body: Vec<u8>,
+    headers: &[(&str, String)],
+) -> Result<(), DeliveryError> {
+    let url = client.endpoints.check(url)?;
+    let mut request = client
+        .http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, value);
+    }
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .map_err(|error| DeliveryError::Transport {
+            timed_out: error.is_timeout(),
+            message: error.to_string(),
+        })?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(DeliveryError::Http {
+        status: status.as_u16(),
+        body: body.chars().take(200).collect(),
+    })
+}
+
+/// Posts the notification as JSON to the user's endpoint
+///
+/// The channel, and so the signing secret, is never part of the body.
+#[derive(Debug, Clone)]
+pub struct WebhookSender {
+    client: Client,
+}
+
+impl Default for WebhookSender {
+    fn default() -> Self {
+        Self {
+            client: Client::new(DEFAULT_TIMEOUT, EndpointPolicy::default()),
+        }
+    }
+}
+
+impl WebhookSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Client::new(timeout, self.client.endpoints);
+        self
+    }
+
+    pub fn with_endpoints(mut self, endpoints: EndpointPolicy) -> Self {
+        self.client = Client::new(self.client.timeout, endpoints);
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationSender for WebhookSender {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Webhook
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError> {
+        let NotificationChannel::Webhook { url, secret } = &notification.channel else {
+            return Err(DeliveryError::InvalidChannel(
+                "webhook sender given a non-webhook channel".to_string(),
+            ));
+        };
+        let body = serde_json::to_vec(&serde_json::json!({
+            "id": notification.id,
+            "user_id": notification.user_id,
+            "subject": notification.subject,
+            "body": notification.body,
+            "payload": notification.payload,
+            "created_at": notification.created_at,
+        }))
+        .map_err(|error| DeliveryError::InvalidChannel(error.to_string()))?;
+
+        let mut headers = Vec::new();
+        if let Some(secret) = secret {
+            let timestamp = chrono::Utc::now().timestamp();
+            headers.push((SIGNATURE_HEADER, sign_webhook(secret, timestamp, &body)));
+            headers.push((TIMESTAMP_HEADER, timestamp.to_string()));
+        }
+        post_json(&self.client, url, body, &headers).await
+    }
+}
+
+/// Posts `{"text": ...}` to a Slack-compatible incoming webhook
+#[derive(Debug, Clone)]
+pub struct ChatSender {
+    client: Client,
+}
+
+impl Default for ChatSender {
+    fn default() -> Self {
+        Self {
+            client: Client::new(DEFAULT_TIMEOUT, EndpointPolicy::default()),
+        }
+    }
+}
+
+impl ChatSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Client::new(timeout, self.client.endpoints);
+        self
+    }
+
+    pub fn with_endpoints(mut self, endpoints: EndpointPolicy) -> Self {
+        self.client = Client::new(self.client.timeout, endpoints);
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationSender for ChatSender {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Chat
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError> {
+        let NotificationChannel::Chat { url } = &notification.channel else {
+            return Err(DeliveryError::InvalidChannel(
+                "chat sender given a non-chat channel".to_string(),
+            ));
+        };
+        let body = serde_json::to_vec(&serde_json::json!({
+            "text": format!("*{}*\n{}", notification.subject, notification.body),
+        }))
+        .map_err(|error| DeliveryError::InvalidChannel(error.to_string()))?;
+        post_json(&self.client, url, body, &[]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use wiremock::matchers::{body_partial_json, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn notification(channel: NotificationChannel) -> Notification {
+        Notification {
+            id: Uuid::new_v4(),
+            user_id: "trader-1".to_string(),
+            channel,
+            subject: "AAPL alert: breakout".to_string(),
+            body: "breakout: close 106 crossed above 105".to_string(),
+            payload: serde_json::json!({"category": "rule"}),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_is_signed_and_chat_uses_text() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header_exists(SIGNATURE_HEADER))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .and(body_partial_json(serde_json::json!({
+                "text": "*AAPL alert: breakout*\nbreakout: close 106 crossed above 105"
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        WebhookSender::new()
+            .with_endpoints(EndpointPolicy::Local)
+            .send(&notification(NotificationChannel::Webhook {
+                url: format!("{}/hook", server.uri()),
+                secret: Some("s3cret".to_string()),
+            }))
+            .await
+            .unwrap();
+        ChatSender::new()
+            .with_endpoints(EndpointPolicy::Local)
+            .send(&notification(NotificationChannel::Chat {
+                url: format!("{}/chat", server.uri()),
+            }))
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let hook = requests
+            .iter()
+            .find(|request| request.url.path() == "/hook")
+            .unwrap();
+        let timestamp: i64 = hook.headers[TIMESTAMP_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            hook.headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign_webhook("s3cret", timestamp, &hook.body)
+        );
+        let body: serde_json::Value = serde_json::from_slice(&hook.body).unwrap();
+        assert_eq!(body["subject"], "AAPL alert: breakout");
+        assert!(body.get("channel").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_failures_are_classified() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+        let error = ChatSender::new()
+            .with_endpoints(EndpointPolicy::Local)
+            .send(&notification(NotificationChannel::Chat {
+                url: server.uri(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.condition(), "rate_limit");
+
+        // Nothing listens on port 9 locally
+        let error = ChatSender::new()
+            .with_endpoints(EndpointPolicy::Local)
+            .send(&notification(NotificationChannel::Chat {
+                url: "http://127.0.0.1:9/hook".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.condition(), "connection");
+
+        let error = WebhookSender::new()
+            .with_endpoints(EndpointPolicy::Local)
+            .send(&notification(NotificationChannel::Chat {
+                url: server.uri(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.condition(), "not_configured");
+    }
+
+    #[tokio::test]
+    async fn test_deliveries_stay_on_public_https_endpoints() {
+        let public = EndpointPolicy::Public;
+        assert!(public.check("https://hooks.example.com/alerts").is_ok());
+        for url in [
+            "http://hooks.example.com/alerts",
+            "https://localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://10.1.2.3/hook",
+            "https://192.168.0.10/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+            "ftp://example.com",
+        ] {
+            assert_eq!(
+                public.check(url).unwrap_err().condition(),
+                "not_configured",
+                "{}",
+                url
+            );
+        }
+        assert!(EndpointPolicy::Local
+            .check("http://127.0.0.1:9/hook")
+            .is_ok());
+
+        // Names resolving to the local network are refused when connecting
+        let resolved = PublicResolver.resolve("localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+
+        // Redirects are reported, not followed
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(
+                ResponseTemplate::new(307).insert_header("Location", "http://169.254.169.254/"),
+            )
+            .mount(&server)
+            .await;
+        let error = WebhookSender::new()
+            .with_endpoints(EndpointPolicy::Local)
+            .send(&notification(NotificationChannel::Webhook {
+                url: format!("{}/hook", server.uri()),
+                secret: None,
+            }))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DeliveryError::Http { status: 307, .. }));
+    }
+}
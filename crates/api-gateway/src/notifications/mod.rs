@@ -0,0 +1,845 @@
+//! Outbound alert notifications: signed webhooks, SMTP email and chat hooks
+//!
+//! The [`NotificationDispatcher`] fans each alert out to the channels in every
+//! interested user's [`DeliveryPreferences`], retrying failed deliveries as its
+//! [`RetryStrategy`] allows and parking the ones that still fail in a SQLite
+//! dead-letter queue, from which they can be redriven.
+
+pub mod http;
+pub mod smtp;
+
+use async_trait::async_trait;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use database::NotificationRepository;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use shared_types::{
+    AlertEvent, ApiError, ApiResponse, BackoffStrategy, ChannelKind, DeadLetter,
+    DeliveryPreferences, Notification, NotificationChannel, RetryStrategy,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::error::{self, GatewayError};
+use crate::GatewayState;
+
+pub use http::{sign_webhook, ChatSender, EndpointPolicy, WebhookSender};
+pub use smtp::{SmtpConfig, SmtpSecurity, SmtpSender};
+
+/// Deliveries of one alert in flight at once
+const DELIVERY_CONCURRENCY: usize = 16;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum DeliveryError {
+    #[error("HTTP {status}: {body}")]
+    Http { status: u16, body: String },
+
+    #[error("Connection failed: {message}")]
+    Transport { message: String, timed_out: bool },
+
+    #[error("SMTP {code}: {message}")]
+    Smtp { code: u16, message: String },
+
+    #[error("No sender configured for {0} notifications")]
+    NoSender(ChannelKind),
+
+    #[error("Invalid channel: {0}")]
+    InvalidChannel(String),
+
+    #[error("Refusing an unencrypted connection: {0}")]
+    Insecure(String),
+}
+
+impl DeliveryError {
+    /// Failure class matched against [`RetryStrategy::retry_conditions`]
+    pub fn condition(&self) -> &'static str {
+        match self {
+            DeliveryError::Http { status: 429, .. } => "rate_limit",
+            DeliveryError::Http { status, .. } if *status >= 500 => "server_error",
+            DeliveryError::Http { .. } => "client_error",
+            DeliveryError::Transport {
+                timed_out: true, ..
+            } => "timeout",
+            DeliveryError::Transport { .. } => "connection",
+            DeliveryError::Smtp { code, .. } if (400..500).contains(code) => "smtp_transient",
+            DeliveryError::Smtp { .. } => "smtp_permanent",
+            DeliveryError::NoSender(_)
+            | DeliveryError::InvalidChannel(_)
+            | DeliveryError::Insecure(_) => "not_configured",
+        }
+    }
+}
+
+/// Delivers notifications over one kind of channel
+#[async_trait]
+pub trait NotificationSender: Send + Sync {
+    fn kind(&self) -> ChannelKind;
+
+    /// Make one delivery attempt; retrying is the dispatcher's job
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError>;
+}
+
+/// Retries on failures that may clear up: timeouts, dropped connections,
+/// rate limits, server errors and transient SMTP replies
+pub fn default_retry_strategy() -> RetryStrategy {
+    RetryStrategy {
+        should_retry: true,
+        max_attempts: 3,
+        delay_seconds: 2,
+        backoff_strategy: BackoffStrategy::Jittered,
+        retry_conditions: [
+            "timeout",
+            "connection",
+            "rate_limit",
+            "server_error",
+            "smtp_transient",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect(),
+    }
+}
+
+/// Outcome of delivering one notification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeliveryReport {
+    pub notification_id: Uuid,
+    pub user_id: String,
+    pub channel: ChannelKind,
+    pub attempts: u32,
+
+    /// The last error when delivery failed
+    pub error: Option<String>,
+
+    /// Dead letter holding the notification when delivery failed and it could be stored
+    pub dead_letter: Option<Uuid>,
+}
+
+impl DeliveryReport {
+    pub fn delivered(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Sends notifications to each user's channels with retries and a dead-letter queue
+pub struct NotificationDispatcher {
+    repository: Arc<NotificationRepository>,
+    senders: HashMap<ChannelKind, Arc<dyn NotificationSender>>,
+    retry: RetryStrategy,
+    endpoints: EndpointPolicy,
+}
+
+impl NotificationDispatcher {
+    /// A dispatcher without senders; channels of kinds with no sender dead-letter at once
+    pub fn new(repository: Arc<NotificationRepository>) -> Self {
+        Self {
+            repository,
+            senders: HashMap::new(),
+            retry: default_retry_strategy(),
+            endpoints: EndpointPolicy::default(),
+        }
+    }
+
+    /// Deliver a channel kind through `sender`, replacing any earlier sender of that kind
+    pub fn with_sender(mut self, sender: Arc<dyn NotificationSender>) -> Self {
+        self.senders.insert(sender.kind(), sender);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryStrategy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Webhook and chat URLs accepted in preferences; match the senders' policy
+    pub fn with_endpoints(mut self, endpoints: EndpointPolicy) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Store a user's preferences after checking every channel is usable
+    pub async fn set_preferences(
+        &self,
+        preferences: &DeliveryPreferences,
+    ) -> Result<(), GatewayError> {
+        for channel in &preferences.channels {
+            validate_channel(channel, self.endpoints)
+                .map_err(|error| GatewayError::validation("channels", error.to_string()))?;
+        }
+        Ok(self.repository.save_preferences(preferences).await?)
+    }
+
+    pub async fn preferences(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<DeliveryPreferences>, GatewayError> {
+        Ok(self.repository.preferences(user_id).await?)
+    }
+
+    pub async fn dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, GatewayError> {
+        Ok(self.repository.dead_letters(limit).await?)
+    }
+
+    pub async fn delete_preferences(&self, user_id: &str) -> Result<bool, GatewayError> {
+        Ok(self.repository.delete_preferences(user_id).await?)
+    }
+
+    /// Notify every user who wants alerts on the event's symbol, on each of their channels
+    ///
+    /// Channels are delivered concurrently and independently: one that fails,
+    /// or whose dead letter cannot be stored, does not hold up or cancel the
+    /// others. Only loading the preferences can fail the whole call.
+    pub async fn notify_alert(
+        &self,
+        event: &AlertEvent,
+    ) -> Result<Vec<DeliveryReport>, GatewayError> {
+        let notifications: Vec<Notification> = self
+            .repository
+            .enabled_preferences()
+            .await?
+            .into_iter()
+            .filter(|preferences| preferences.wants(&event.symbol))
+            .flat_map(|preferences| {
+                let user_id = preferences.user_id;
+                preferences
+                    .channels
+                    .into_iter()
+                    .map(move |channel| Notification::from_alert(&user_id, channel, event))
+            })
+            .collect();
+
+        Ok(futures::stream::iter(notifications)
+            .map(|notification| async move {
+                let (mut report, letter) = self.attempt_delivery(notification).await;
+                if let Err(error) = self.park(&mut report, letter).await {
+                    tracing::error!(
+                        notification = %report.notification_id,
+                        ?error,
+                        "could not dead-letter notification"
+                    );
+                }
+                report
+            })
+            .buffered(DELIVERY_CONCURRENCY)
+            .collect()
+            .await)
+    }
+
+    /// Deliver one notification, dead-lettering it if every attempt fails
+    ///
+    /// Only storage errors are returned as errors; delivery failures are in the report.
+    pub async fn deliver(
+        &self,
+        notification: Notification,
+    ) -> Result<DeliveryReport, GatewayError> {
+        let (mut report, letter) = self.attempt_delivery(notification).await;
+        self.park(&mut report, letter).await?;
+        Ok(report)
+    }
+
+    /// Report of delivering a notification, and the dead letter to store if it failed
+    async fn attempt_delivery(
+        &self,
+        notification: Notification,
+    ) -> (DeliveryReport, Option<DeadLetter>) {
+        let (attempts, result) = self.attempt(&notification).await;
+        let mut report = DeliveryReport {
+            notification_id: notification.id,
+            user_id: notification.user_id.clone(),
+            channel: notification.channel.kind(),
+            attempts,
+            error: None,
+            dead_letter: None,
+        };
+        let Err(error) = result else {
+            return (report, None);
+        };
+        tracing::warn!(
+            notification = %notification.id,
+            channel = %report.channel,
+            attempts,
+            %error,
+            "notification dead-lettered"
+        );
+        report.error = Some(error.to_string());
+        let letter = DeadLetter {
+            id: Uuid::new_v4(),
+            notification,
+            attempts,
+            error: error.to_string(),
+            failed_at: Utc::now(),
+        };
+        (report, Some(letter))
+    }
+
+    /// Store a failed delivery's dead letter and point its report at it
+    async fn park(
+        &self,
+        report: &mut DeliveryReport,
+        letter: Option<DeadLetter>,
+    ) -> Result<(), GatewayError> {
+        if let Some(letter) = letter {
+            self.repository.push_dead_letter(&letter).await?;
+            report.dead_letter = Some(letter.id);
+        }
+        Ok(())
+    }
+
+    /// Retry a dead letter; it leaves the queue either way, and a failure parks a new one
+    pub async fn redrive(&self, id: Uuid) -> Result<Option<DeliveryReport>, GatewayError> {
+        let Some(letter) = self.repository.dead_letter(id).await? else {
+            return Ok(None);
+        };
+        let report = self.deliver(letter.notification).await?;
+        self.repository.remove_dead_letter(id).await?;
+        Ok(Some(report))
+    }
+
+    /// Attempts made and the final result
+    async fn attempt(&self, notification: &Notification) -> (u32, Result<(), DeliveryError>) {
+        let kind = notification.channel.kind();
+        let Some(sender) = self.senders.get(&kind) else {
+            return (0, Err(DeliveryError::NoSender(kind)));
+        };
+        let mut failures = 0;
+        loop {
+            let error = match sender.send(notification).await {
+                Ok(()) => return (failures + 1, Ok(())),
+                Err(error) => error,
+            };
+            failures += 1;
+            if !self.retry.should_retry_after(failures, error.condition()) {
+                return (failures, Err(error));
+            }
+            tokio::time::sleep(self.retry.delay_before_retry(failures)).await;
+        }
+    }
+}
+
+fn validate_channel(
+    channel: &NotificationChannel,
+    endpoints: EndpointPolicy,
+) -> Result<(), DeliveryError> {
+    match channel {
+        NotificationChannel::Webhook { url, .. } | NotificationChannel::Chat { url } => {
+            endpoints.check(url).map(|_| ())
+        }
+        NotificationChannel::Email { to } => smtp::validate_address(to),
+    }
+}
+
+// ============================================================================
+// Routes
+// ============================================================================
+
+/// Dead letters listed when no `limit` is given
+pub const DEFAULT_DEAD_LETTER_LIMIT: u32 = 50;
+
+/// A user's delivery settings as sent by clients; the user comes from the path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreferencesRequest {
+    pub channels: Vec<NotificationChannel>,
+
+    #[serde(default)]
+    pub symbols: Vec<String>,
+
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterQuery {
+    #[serde(default = "default_dead_letter_limit")]
+    pub limit: u32,
+}
+
+fn default_dead_letter_limit() -> u32 {
+    DEFAULT_DEAD_LETTER_LIMIT
+}
+
+/// A channel as shown to clients; a webhook's signing secret is never sent back
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelView {
+    Webhook {
+        url: String,
+
+        /// Whether deliveries carry a signature
+        signed: bool,
+    },
+    Email {
+        to: String,
+    },
+    Chat {
+        url: String,
+    },
+}
+
+impl From<&NotificationChannel> for ChannelView {
+    fn from(channel: &NotificationChannel) -> Self {
+        match channel {
+            NotificationChannel::Webhook { url, secret } => ChannelView::Webhook {
+                url: url.clone(),
+                signed: secret.is_some(),
+            },
+            NotificationChannel::Email { to } => ChannelView::Email { to: to.clone() },
+            NotificationChannel::Chat { url } => ChannelView::Chat { url: url.clone() },
+        }
+    }
+}
+
+/// [`DeliveryPreferences`] with its channels shown as [`ChannelView`]s
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreferencesView {
+    pub user_id: String,
+    pub channels: Vec<ChannelView>,
+    pub symbols: Vec<String>,
+    pub enabled: bool,
+}
+
+impl From<DeliveryPreferences> for PreferencesView {
+    fn from(preferences: DeliveryPreferences) -> Self {
+        Self {
+            user_id: preferences.user_id,
+            channels: preferences.channels.iter().map(ChannelView::from).collect(),
+            symbols: preferences.symbols,
+            enabled: preferences.enabled,
+        }
+    }
+}
+
+/// [`Notification`] with its channel shown as a [`ChannelView`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotificationView {
+    pub id: Uuid,
+    pub user_id: String,
+    pub channel: ChannelView,
+    pub subject: String,
+    pub body: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// [`DeadLetter`] with its notification shown as a [`NotificationView`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeadLetterView {
+    pub id: Uuid,
+    pub notification: NotificationView,
+    pub attempts: u32,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl From<DeadLetter> for DeadLetterView {
+    fn from(letter: DeadLetter) -> Self {
+        let notification = letter.notification;
+        Self {
+            id: letter.id,
+            notification: NotificationView {
+                id: notification.id,
+                user_id: notification.user_id,
+                channel: ChannelView::from(&notification.channel),
+                subject: notification.subject,
+                body: notification.body,
+                payload: notification.payload,
+                created_at: notification.created_at,
+            },
+            attempts: letter.attempts,
+            error: letter.error,
+            failed_at: letter.failed_at,
+        }
+    }
+}
+
+/// Delivery preference and dead-letter routes, under `/api/v1/notifications`
+pub fn routes() -> Router<GatewayState> {
+    Router::new()
+        .route(
+            "/api/v1/notifications/preferences/{user_id}",
+            get(show_preferences)
+                .put(save_preferences)
+                .delete(delete_preferences),
+        )
+        .route("/api/v1/notifications/dead-letters", get(dead_letters))
+        .route(
+            "/api/v1/notifications/dead-letters/{id}/redrive",
+            post(redrive),
+        )
+}
+
+type Reply<T> = Result<Json<ApiResponse<T>>, GatewayError>;
+
+fn dispatcher(state: &GatewayState) -> Result<&NotificationDispatcher, GatewayError> {
+    state.notifications.as_deref().ok_or_else(|| {
+        GatewayError(ApiError::ServiceUnavailable {
+            message: "Notifications are not configured".to_string(),
+        })
+    })
+}
+
+fn path<T>(path: Result<Path<T>, PathRejection>) -> Result<T, GatewayError> {
+    path.map(|Path(value)| value)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+fn preferences_not_found(user_id: &str) -> GatewayError {
+    GatewayError(ApiError::NotFound {
+        resource: format!("notification preferences of {}", user_id),
+    })
+}
+
+async fn show_preferences(
+    State(state): State<GatewayState>,
+    user_id: Result<Path<String>, PathRejection>,
+) -> Reply<PreferencesView> {
+    let started = Instant::now();
+    let user_id = path(user_id)?;
+    let preferences = dispatcher(&state)?
+        .preferences(&user_id)
+        .await?
+        .ok_or_else(|| preferences_not_found(&user_id))?;
+    Ok(error::success(preferences.into(), started))
+}
+
+async fn save_preferences(
+    State(state): State<GatewayState>,
+    user_id: Result<Path<String>, PathRejection>,
+    request: Result<Json<PreferencesRequest>, JsonRejection>,
+) -> Reply<PreferencesView> {
+    let started = Instant::now();
+    let user_id = path(user_id)?;
+    let Json(request) =
+        request.map_err(|rejection| GatewayError::bad_request(rejection.body_text()))?;
+    let preferences = DeliveryPreferences {
+        user_id,
+        channels: request.channels,
+        symbols: request.symbols,
+        enabled: request.enabled,
+    };
+    dispatcher(&state)?.set_preferences(&preferences).await?;
+    Ok(error::success(preferences.into(), started))
+}
+
+async fn delete_preferences(
+    State(state): State<GatewayState>,
+    user_id: Result<Path<String>, PathRejection>,
+) -> Reply<String> {
+    let started = Instant::now();
+    let user_id = path(user_id)?;
+    if !dispatcher(&state)?.delete_preferences(&user_id).await? {
+        return Err(preferences_not_found(&user_id));
+    }
+    Ok(error::success(user_id, started))
+}
+
+async fn dead_letters(
+    State(state): State<GatewayState>,
+    query: Result<Query<DeadLetterQuery>, QueryRejection>,
+) -> Reply<Vec<DeadLetterView>> {
+    let started = Instant::now();
+    let Query(query) =
+        query.map_err(|rejection| GatewayError::bad_request(rejection.body_text()))?;
+    let letters = dispatcher(&state)?.dead_letters(query.limit).await?;
+    Ok(error::success(
+        letters.into_iter().map(DeadLetterView::from).collect(),
+        started,
+    ))
+}
+
+async fn redrive(
+    State(state): State<GatewayState>,
+    id: Result<Path<Uuid>, PathRejection>,
+) -> Reply<DeliveryReport> {
+    let started = Instant::now();
+    let id = path(id)?;
+    let report = dispatcher(&state)?.redrive(id).await?.ok_or_else(|| {
+        GatewayError(ApiError::NotFound {
+            resource: format!("dead letter {}", id),
+        })
+    })?;
+    Ok(error::success(report, started))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use database::{SqlitePool, SqlitePoolConfig};
+    use rust_decimal::Decimal;
+    use shared_types::TimeFrame;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn repository() -> Arc<NotificationRepository> {
+        repository_and_pool().await.0
+    }
+
+    async fn repository_and_pool() -> (Arc<NotificationRepository>, Arc<SqlitePool>) {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let pool = Arc::new(SqlitePool::new(config).await.unwrap());
+        let repository = NotificationRepository::new(pool.clone());
+        repository.migrate().await.unwrap();
+        (Arc::new(repository), pool)
+    }
+
+    fn event(symbol: &str) -> AlertEvent {
+        AlertEvent {
+            id: Uuid::new_v4(),
+            rule_id: Uuid::new_v4(),
+            rule_name: "breakout".to_string(),
+            symbol: symbol.to_string(),
+            timeframe: TimeFrame::OneHour,
+            triggered_at: Utc::now(),
+            price: Decimal::new(106, 0),
+            value: None,
+            message: "breakout: close 106 crossed above 105".to_string(),
+        }
+    }
+
+    fn immediate_retry(max_attempts: u32) -> RetryStrategy {
+        RetryStrategy {
+            delay_seconds: 0,
+            max_attempts,
+            ..default_retry_strategy()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_then_delivers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dispatcher = NotificationDispatcher::new(repository().await)
+            .with_sender(Arc::new(
+                ChatSender::new().with_endpoints(EndpointPolicy::Local),
+            ))
+            .with_retry(immediate_retry(3))
+            .with_endpoints(EndpointPolicy::Local);
+        let chat = NotificationChannel::Chat {
+            url: format!("{}/hook", server.uri()),
+        };
+        dispatcher
+            .set_preferences(&DeliveryPreferences::new("trader-1").with_channel(chat.clone()))
+            .await
+            .unwrap();
+        dispatcher
+            .set_preferences(
+                &DeliveryPreferences::new("trader-2")
+                    .with_channel(chat)
+                    .with_symbols(["MSFT"]),
+            )
+            .await
+            .unwrap();
+
+        let reports = dispatcher.notify_alert(&event("AAPL")).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].user_id, "trader-1");
+        assert!(reports[0].delivered());
+        assert_eq!(reports[0].attempts, 3);
+        assert!(dispatcher.dead_letters(10).await.unwrap().is_empty());
+
+        let error = dispatcher
+            .set_preferences(&DeliveryPreferences::new("trader-3").with_channel(
+                NotificationChannel::Webhook {
+                    url: "ftp://example.com".to_string(),
+                    secret: None,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failures_are_dead_lettered_and_redriven() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(410).set_body_string("hook removed"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let dispatcher = NotificationDispatcher::new(repository().await)
+            .with_sender(Arc::new(
+                WebhookSender::new().with_endpoints(EndpointPolicy::Local),
+            ))
+            .with_retry(immediate_retry(3));
+        let webhook = NotificationChannel::Webhook {
+            url: server.uri(),
+            secret: Some("s3cret".to_string()),
+        };
+        let report = dispatcher
+            .deliver(Notification::from_alert(
+                "trader-1",
+                webhook,
+                &event("AAPL"),
+            ))
+            .await
+            .unwrap();
+        // Client errors are not retried
+        assert_eq!(report.attempts, 1);
+        assert_eq!(report.error.as_deref(), Some("HTTP 410: hook removed"));
+
+        let letters = dispatcher.dead_letters(10).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(Some(letters[0].id), report.dead_letter);
+        let listed = serde_json::to_value(DeadLetterView::from(letters[0].clone())).unwrap();
+        assert_eq!(listed["notification"]["channel"]["signed"], true);
+        assert!(!listed.to_string().contains("s3cret"));
+
+        let redriven = dispatcher.redrive(letters[0].id).await.unwrap().unwrap();
+        assert!(redriven.delivered());
+        assert_eq!(redriven.notification_id, report.notification_id);
+        assert!(dispatcher.dead_letters(10).await.unwrap().is_empty());
+        assert_eq!(dispatcher.redrive(letters[0].id).await.unwrap(), None);
+
+        // Email without an SMTP sender goes straight to the queue
+        let report = dispatcher
+            .deliver(Notification::from_alert(
+                "trader-1",
+                NotificationChannel::Email {
+                    to: "trader@example.com".to_string(),
+                },
+                &event("AAPL"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(report.attempts, 0);
+        assert_eq!(dispatcher.dead_letters(10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_each_channel_is_reported_on_its_own() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/gone"))
+            .respond_with(ResponseTemplate::new(410))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let (repository, pool) = repository_and_pool().await;
+        let dispatcher = NotificationDispatcher::new(repository)
+            .with_sender(Arc::new(
+                ChatSender::new().with_endpoints(EndpointPolicy::Local),
+            ))
+            .with_retry(immediate_retry(1))
+            .with_endpoints(EndpointPolicy::Local);
+        for (user, hook) in [("trader-1", "gone"), ("trader-2", "hook")] {
+            dispatcher
+                .set_preferences(&DeliveryPreferences::new(user).with_channel(
+                    NotificationChannel::Chat {
+                        url: format!("{}/{}", server.uri(), hook),
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+        // Parking the failed delivery breaks; the other channel is still delivered
+        pool.execute("DROP TABLE notification_dead_letters")
+            .await
+            .unwrap();
+
+        let mut reports = dispatcher.notify_alert(&event("AAPL")).await.unwrap();
+        reports.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].error.as_deref(), Some("HTTP 410: "));
+        assert_eq!(reports[0].dead_letter, None);
+        assert!(reports[1].delivered());
+    }
+
+    #[tokio::test]
+    async fn test_preference_routes() {
+        use axum::body::Body;
+        use axum::http::{header, Method, Request, StatusCode};
+        use http_body_util::BodyExt;
+        use serde_json::{json, Value};
+        use tower::ServiceExt;
+
+        let without = crate::watchlists::tests::state().await;
+        let state = without
+            .clone()
+            .with_notifications(Arc::new(NotificationDispatcher::new(repository().await)));
+        let call = |state: &GatewayState, method: Method, body: Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri("/api/v1/notifications/preferences/trader-1")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let app = crate::app(state.clone());
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = response.into_body().collect().await.unwrap().to_bytes();
+                (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, _) = call(&without, Method::GET, Value::Null).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (status, _) = call(&state, Method::GET, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let email = json!({"channels": [{"type": "email", "to": "trader@example.com"}]});
+        let (status, body) = call(&state, Method::PUT, email).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["user_id"], "trader-1");
+        assert_eq!(body["data"]["enabled"], true);
+        let (_, body) = call(&state, Method::GET, Value::Null).await;
+        assert_eq!(body["data"]["channels"][0]["to"], "trader@example.com");
+
+        // Signing secrets go in but never come back out
+        let webhook = json!({"channels": [
+            {"type": "webhook", "url": "https://hooks.example.com/alerts", "secret": "s3cret"}
+        ]});
+        let (status, body) = call(&state, Method::PUT, webhook).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body.to_string().contains("s3cret"));
+        let (_, body) = call(&state, Method::GET, Value::Null).await;
+        assert_eq!(body["data"]["channels"][0]["signed"], true);
+        assert!(!body.to_string().contains("s3cret"));
+
+        let invalid = json!({"channels": [{"type": "email", "to": "trader"}]});
+        let (status, _) = call(&state, Method::PUT, invalid).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = call(&state, Method::DELETE, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&state, Method::DELETE, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
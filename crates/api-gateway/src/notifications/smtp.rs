@@ -0,0 +1,528 @@
+//! Minimal SMTP client for plain-text alert email
+//!
+//! Speaks just enough of RFC 5321 to hand a message to a relay: `EHLO`,
+//! `STARTTLS`, optional `AUTH PLAIN`, `MAIL`, `RCPT`, `DATA` and `QUIT`.
+//! Connections are encrypted with `STARTTLS` by default, or with TLS from the
+//! start for submission ports such as 465. Unencrypted delivery is only for
+//! local relays, and never carries credentials.
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use shared_types::{ChannelKind, Notification, NotificationChannel};
+use std::fmt;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector, TlsStream};
+
+use super::{DeliveryError, NotificationSender};
+
+/// How the connection to the relay is encrypted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Upgrade with `STARTTLS`, failing if the relay does not offer it
+    #[default]
+    StartTls,
+
+    /// TLS from the first byte, as on port 465
+    Tls,
+
+    /// No encryption, for a relay on the same host or network; credentials are refused
+    None,
+}
+
+/// Relay connection settings
+///
+/// `Debug` output leaves the password out.
+#[derive(Clone, PartialEq)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+
+    /// Envelope and header sender
+    pub from: String,
+
+    /// Username and password for `AUTH PLAIN`
+    pub credentials: Option<(String, String)>,
+
+    /// Name announced in `EHLO`
+    pub hello_name: String,
+
+    /// Limit on a whole delivery, connection included
+    pub timeout: Duration,
+}
+
+impl SmtpConfig {
+    pub fn new(host: impl Into<String>, port: u16, from: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            security: SmtpSecurity::default(),
+            from: from.into(),
+            credentials: None,
+            hello_name: "localhost".to_string(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    pub fn with_security(mut self, security: SmtpSecurity) -> Self {
+        self.security = security;
+        self
+    }
+
+    pub fn with_hello_name(mut self, hello_name: impl Into<String>) -> Self {
+        self.hello_name = hello_name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("security", &self.security)
+            .field("from", &self.from)
+            .field(
+                "credentials",
+                &self
+                    .credentials
+                    .as_ref()
+                    .map(|(username, _)| (username, "<redacted>")),
+            )
+            .field("hello_name", &self.hello_name)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Check an address can go into an SMTP envelope as is
+pub fn validate_address(address: &str) -> Result<(), DeliveryError> {
+    let valid = address
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty())
+        && !address
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ','));
+    if valid {
+        Ok(())
+    } else {
+        Err(DeliveryError::InvalidChannel(format!(
+            "'{}' is not an email address",
+            address
+        )))
+    }
+}
+
+/// Sends email channels through an SMTP relay
+#[derive(Debug, Clone)]
+pub struct SmtpSender {
+    config: SmtpConfig,
+}
+
+impl SmtpSender {
+    pub fn new(config: SmtpConfig) -> Self {
+        Self { config }
+    }
+
+    async fn deliver(&self, to: &str, message: &str) -> Result<(), DeliveryError> {
+        let stream = TcpStream::connect((self.config.host.as_str(), self.config.port))
+            .await
+            .map_err(transport)?;
+        match self.config.security {
+            SmtpSecurity::Tls => {
+                let mut session = Session::new(self.handshake(stream).await?);
+                session.reply(&[220]).await?;
+                session.hello(&self.config.hello_name).await?;
+                self.transact(session, to, message).await
+            }
+            SmtpSecurity::StartTls => {
+                let mut session = Session::new(stream);
+                session.reply(&[220]).await?;
+                let extensions = session.hello(&self.config.hello_name).await?;
+                if !extensions
+                    .iter()
+                    .any(|extension| extension.eq_ignore_ascii_case("STARTTLS"))
+                {
+                    return Err(DeliveryError::Insecure(
+                        "the relay does not offer STARTTLS".to_string(),
+                    ));
+                }
+                session.command("STARTTLS").await?;
+                session.reply(&[220]).await?;
+                // The session starts over once encrypted, greeting aside
+                let mut session = Session::new(self.handshake(session.into_inner()).await?);
+                session.hello(&self.config.hello_name).await?;
+                self.transact(session, to, message).await
+            }
+            SmtpSecurity::None => {
+                let mut session = Session::new(stream);
+                session.reply(&[220]).await?;
+                session.hello(&self.config.hello_name).await?;
+                self.transact(session, to, message).await
+            }
+        }
+    }
+
+    async fn handshake(&self, stream: TcpStream) -> Result<TlsStream<TcpStream>, DeliveryError> {
+        let connector = native_tls::TlsConnector::new().map_err(tls)?;
+        TlsConnector::from(connector)
+            .connect(&self.config.host, stream)
+            .await
+            .map_err(tls)
+    }
+
+    /// Authenticate if configured, then hand over the message and quit
+    async fn transact<S>(
+        &self,
+        mut session: Session<S>,
+        to: &str,
+        message: &str,
+    ) -> Result<(), DeliveryError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if let Some((username, password)) = &self.config.credentials {
+            let token = BASE64.encode(format!("\0{}\0{}", username, password));
+            session.command(&format!("AUTH PLAIN {}", token)).await?;
+            session.reply(&[235]).await?;
+        }
+        session
+            .command(&format!("MAIL FROM:<{}>", self.config.from))
+            .await?;
+        session.reply(&[250]).await?;
+        session.command(&format!("RCPT TO:<{}>", to)).await?;
+        session.reply(&[250, 251]).await?;
+        session.command("DATA").await?;
+        session.reply(&[354]).await?;
+        session.write(message).await?;
+        session.command(".").await?;
+        session.reply(&[250]).await?;
+        // The message is accepted; a relay hanging up early is not a failure
+        let _ = session.command("QUIT").await;
+        Ok(())
+    }
+
+    /// Headers and dot-stuffed body, without the terminating `.` line
+    fn message(&self, to: &str, notification: &Notification) -> String {
+        let mut message = format!(
+            "From: <{}>\r\nTo: <{}>\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+             MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Transfer-Encoding: 8bit\r\n\r\n",
+            self.config.from,
+            to,
+            encode_header(&notification.subject),
+            Utc::now().to_rfc2822(),
+            notification.id,
+            self.config.hello_name,
+        );
+        for line in notification.body.lines() {
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+        message
+    }
+}
+
+#[async_trait]
+impl NotificationSender for SmtpSender {
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Email
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError> {
+        let NotificationChannel::Email { to } = &notification.channel else {
+            return Err(DeliveryError::InvalidChannel(
+                "SMTP sender given a non-email channel".to_string(),
+            ));
+        };
+        validate_address(to)?;
+        if self.config.security == SmtpSecurity::None && self.config.credentials.is_some() {
+            return Err(DeliveryError::Insecure(
+                "credentials are never sent without TLS".to_string(),
+            ));
+        }
+        let message = self.message(to, notification);
+        tokio::time::timeout(self.config.timeout, self.deliver(to, &message))
+            .await
+            .map_err(|_| DeliveryError::Transport {
+                message: format!("SMTP delivery timed out after {:?}", self.config.timeout),
+                timed_out: true,
+            })?
+    }
+}
+
+/// ASCII subjects go as they are; anything else as an RFC 2047 encoded word
+fn encode_header(value: &str) -> String {
+    let value: String = value.chars().filter(|c| !c.is_control()).collect();
+    if value.is_ascii() {
+        value
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(value))
+    }
+}
+
+fn transport(error: std::io::Error) -> DeliveryError {
+    DeliveryError::Transport {
+        timed_out: error.kind() == std::io::ErrorKind::TimedOut,
+        message: error.to_string(),
+    }
+}
+
+fn tls(error: native_tls::Error) -> DeliveryError {
+    DeliveryError::Transport {
+        message: format!("TLS: {}", error),
+        timed_out: false,
+    }
+}
+
+/// One SMTP conversation over a plain or encrypted stream
+struct Session<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    async fn write(&mut self, text: &str) -> Result<(), DeliveryError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(text.as_bytes()).await.map_err(transport)?;
+        stream.flush().await.map_err(transport)
+    }
+
+    async fn command(&mut self, line: &str) -> Result<(), DeliveryError> {
+        self.write(&format!("{}\r\n", line)).await
+    }
+
+    /// Send `EHLO`, returning the extensions the server lists
+    async fn hello(&mut self, name: &str) -> Result<Vec<String>, DeliveryError> {
+        self.command(&format!("EHLO {}", name)).await?;
+        let mut lines = self.reply(&[250]).await?;
+        // The first line is the server's greeting, the rest one extension each
+        lines.remove(0);
+        Ok(lines)
+    }
+
+    /// Read a possibly multi-line reply and check its code is one of `accepted`
+    async fn reply(&mut self, accepted: &[u16]) -> Result<Vec<String>, DeliveryError> {
+        let mut text = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await.map_err(transport)? == 0 {
+                return Err(DeliveryError::Transport {
+                    message: "SMTP server closed the connection".to_string(),
+                    timed_out: false,
+                });
+            }
+            let line = line.trim_end();
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| DeliveryError::Smtp {
+                    code: 0,
+                    message: format!("malformed reply '{}'", line),
+                })?;
+            text.push(line.get(4..).unwrap_or_default().to_string());
+            // `250-` continues a multi-line reply, `250 ` ends it
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            if accepted.contains(&code) {
+                return Ok(text);
+            }
+            return Err(DeliveryError::Smtp {
+                code,
+                message: text.join(" "),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use uuid::Uuid;
+
+    /// Extensions of a relay that can upgrade to TLS
+    const WITH_STARTTLS: &str = "250-mock\r\n250-STARTTLS\r\n250 AUTH PLAIN\r\n";
+
+    /// Extensions of a relay that cannot
+    const WITHOUT_STARTTLS: &str = "250-mock\r\n250 AUTH PLAIN\r\n";
+
+    /// One-connection SMTP server without TLS, replying `ehlo_reply` to `EHLO`
+    /// and `rcpt_reply` to `RCPT`; yields the commands and message
+    async fn mock_server(
+        ehlo_reply: &'static str,
+        rcpt_reply: &'static str,
+    ) -> (u16, oneshot::Receiver<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (done, received) = oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut commands = Vec::new();
+            let mut message = String::new();
+            writer.write_all(b"220 mock ESMTP\r\n").await.unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                commands.push(line.clone());
+                let reply = match line.split(' ').next().unwrap_or_default() {
+                    "EHLO" => ehlo_reply,
+                    "STARTTLS" => "454 TLS not available\r\n",
+                    "AUTH" => "235 ok\r\n",
+                    "MAIL" => "250 ok\r\n",
+                    "RCPT" => rcpt_reply,
+                    "DATA" => {
+                        writer.write_all(b"354 go ahead\r\n").await.unwrap();
+                        while let Some(line) = lines.next_line().await.unwrap() {
+                            if line == "." {
+                                break;
+                            }
+                            message.push_str(&line);
+                            message.push('\n');
+                        }
+                        "250 queued\r\n"
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => "500 unknown\r\n",
+                };
+                writer.write_all(reply.as_bytes()).await.unwrap();
+            }
+            let _ = done.send((commands, message));
+        });
+        (port, received)
+    }
+
+    fn notification(to: &str) -> Notification {
+        Notification {
+            id: Uuid::new_v4(),
+            user_id: "trader-1".to_string(),
+            channel: NotificationChannel::Email { to: to.to_string() },
+            subject: "AAPL alert: breakout ↑".to_string(),
+            body: "breakout: close 106 crossed above 105\n.leading dot".to_string(),
+            payload: serde_json::Value::Null,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn local_relay(port: u16) -> SmtpConfig {
+        SmtpConfig::new("127.0.0.1", port, "alerts@tio.local").with_security(SmtpSecurity::None)
+    }
+
+    #[tokio::test]
+    async fn test_delivers_to_mock_relay() {
+        let (port, received) = mock_server(WITHOUT_STARTTLS, "250 ok\r\n").await;
+        let sender = SmtpSender::new(local_relay(port));
+        sender
+            .send(&notification("trader@example.com"))
+            .await
+            .unwrap();
+
+        let (commands, message) = received.await.unwrap();
+        assert_eq!(commands[0], "EHLO localhost");
+        assert_eq!(commands[1], "MAIL FROM:<alerts@tio.local>");
+        assert_eq!(commands[2], "RCPT TO:<trader@example.com>");
+        assert_eq!(commands.last().unwrap(), "QUIT");
+        assert!(message.contains(&format!(
+            "Subject: =?UTF-8?B?{}?=",
+            BASE64.encode("AAPL alert: breakout ↑")
+        )));
+        assert!(message.contains("\nbreakout: close 106 crossed above 105\n..leading dot\n"));
+    }
+
+    #[tokio::test]
+    async fn test_credentials_only_travel_encrypted() {
+        let error = SmtpSender::new(local_relay(1).with_credentials("tio", "pw"))
+            .send(&notification("trader@example.com"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DeliveryError::Insecure(_)));
+        let config = format!("{:?}", local_relay(1).with_credentials("tio", "pw"));
+        assert!(config.contains("\"tio\"") && !config.contains("\"pw\""));
+
+        // STARTTLS comes before anything else, and a relay without it is refused
+        let (port, received) = mock_server(WITHOUT_STARTTLS, "250 ok\r\n").await;
+        let sender = SmtpSender::new(
+            SmtpConfig::new("127.0.0.1", port, "alerts@tio.local").with_credentials("tio", "pw"),
+        );
+        let error = sender
+            .send(&notification("trader@example.com"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DeliveryError::Insecure(_)));
+        assert_eq!(received.await.unwrap().0, ["EHLO localhost"]);
+
+        let (port, received) = mock_server(WITH_STARTTLS, "250 ok\r\n").await;
+        let sender = SmtpSender::new(
+            SmtpConfig::new("127.0.0.1", port, "alerts@tio.local").with_credentials("tio", "pw"),
+        );
+        let error = sender
+            .send(&notification("trader@example.com"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.condition(), "smtp_transient");
+        assert_eq!(received.await.unwrap().0, ["EHLO localhost", "STARTTLS"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejections_are_classified() {
+        let (port, _) = mock_server(WITHOUT_STARTTLS, "450 mailbox busy\r\n").await;
+        let error = SmtpSender::new(local_relay(port))
+            .send(&notification("trader@example.com"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            DeliveryError::Smtp {
+                code: 450,
+                message: "mailbox busy".to_string()
+            }
+        );
+        assert_eq!(error.condition(), "smtp_transient");
+
+        let (port, _) = mock_server(WITHOUT_STARTTLS, "550 no such user\r\n").await;
+        let error = SmtpSender::new(local_relay(port))
+            .send(&notification("nobody@example.com"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.condition(), "smtp_permanent");
+
+        assert!(validate_address("trader@example.com").is_ok());
+        assert!(validate_address("trader@example.com>\r\nRCPT TO:<x@y").is_err());
+        assert!(validate_address("trader").is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use analytics::{Greeks, MarketInputs, OptionPricer, OptionPricingError, PricingModel};
+use axum::extract::rejection::JsonRejection;
+use axum::Json;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared_types::{ApiResponse, OptionRight, OptionSpec};
+use std::time::Instant;
+
+use crate::error::{self, GatewayError};
+
+/// Option chain on one underlying to value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreeksRequest {
+    pub spot: Decimal,
+    pub valuation_date: NaiveDate,
+
+    #[serde(default)]
+    pub rate: Decimal,
+
+    #[serde(default)]
+    pub dividend_yield: Decimal,
+
+    /// Volatility for contracts with neither their own volatility nor a market price
+    #[serde(default)]
+    pub volatility: Option<Decimal>,
+
+    #[serde(default)]
+    pub model: PricingModel,
+
+    pub contracts: Vec<ChainContract>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainContract {
+    /// OCC option symbol, padded or not
+    pub symbol: String,
+
+    /// Quoted premium to solve the implied volatility from
+    #[serde(default)]
+    pub market_price: Option<Decimal>,
+
+    /// Volatility to value this contract at, overriding the market price
+    #[serde(default)]
+    pub volatility: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreeksTable {
+    pub model: PricingModel,
+    pub spot: Decimal,
+    pub valuation_date: NaiveDate,
+
+    /// One row per contract, by expiry, strike and calls before puts
+    pub rows: Vec<GreeksRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreeksRow {
+    /// Unpadded OCC symbol
+    pub symbol: String,
+    pub expiry: NaiveDate,
+    pub strike: Decimal,
+    pub right: OptionRight,
+
+    /// Volatility the contract was valued at
+    pub volatility: Decimal,
+
+    /// Whether `volatility` was implied from the contract's market price
+    pub implied: bool,
+
+    pub price: Decimal,
+    pub greeks: Greeks,
+}
+
+/// `POST /api/v1/options/greeks`
+pub async fn greeks_table(
+    body: Result<Json<GreeksRequest>, JsonRejection>,
+) -> Result<Json<ApiResponse<GreeksTable>>, GatewayError> {
+    let started = Instant::now();
+    let Json(request) =
+        body.map_err(|rejection| GatewayError::bad_request(rejection.body_text()))?;
+    let table = build_table(&request)?;
+    Ok(error::success(table, started))
+}
+
+fn build_table(request: &GreeksRequest) -> Result<GreeksTable, GatewayError> {
+    if request.contracts.is_empty() {
+        return Err(GatewayError::validation(
+            "contracts",
+            "at least one contract is required",
+        ));
+    }
+    let pricer = OptionPricer::new(request.model);
+    let market = MarketInputs::new(
+        request.spot,
+        request.volatility.unwrap_or_default(),
+        request.valuation_date,
+    )
+    .with_rate(request.rate)
+    .with_dividend_yield(request.dividend_yield);
+
+    let mut rows = Vec::with_capacity(request.contracts.len());
+    for (index, contract) in request.contracts.iter().enumerate() {
+        let field = |name: &str| format!("contracts[{}].{}", index, name);
+        let spec = OptionSpec::parse_occ(&contract.symbol)
+            .map_err(|error| GatewayError::validation(field("symbol"), error.to_string()))?;
+
+        let (volatility, implied) = match (contract.volatility, contract.market_price) {
+            (Some(volatility), _) => (volatility, false),
+            (None, Some(price)) => {
+                let volatility = pricer
+                    .implied_volatility(&spec, &market, price)
+                    .map_err(|error| pricing_error(field("market_price"), error))?;
+                (volatility, true)
+            }
+            (None, None) => match request.volatility {
+                Some(volatility) => (volatility, false),
+                None => {
+                    return Err(GatewayError::validation(
+                        field("volatility"),
+                        "needs a volatility or market price when the request has no volatility",
+                    ))
+                }
+            },
+        };
+        let valuation = pricer
+            .value(&spec, &market.clone().with_volatility(volatility))
+            .map_err(|error| pricing_error(field("symbol"), error))?;
+
+        rows.push(GreeksRow {
+            symbol: spec.occ_code(),
+            expiry: spec.expiry,
+            strike: spec.strike,
+            right: spec.right,
+            volatility,
+            implied,
+            price: valuation.price,
+            greeks: valuation.greeks,
+        });
+    }
+    rows.sort_by(|a, b| {
+        (a.expiry, a.strike, a.right == OptionRight::Put).cmp(&(
+            b.expiry,
+            b.strike,
+            b.right == OptionRight::Put,
+        ))
+    });
+
+    Ok(GreeksTable {
+        model: request.model,
+        spot: request.spot,
+        valuation_date: request.valuation_date,
+        rows,
+    })
+}
+
+/// Pricing failures are all problems with the submitted chain
+fn pricing_error(field: String, error: OptionPricingError) -> GatewayError {
+    let field = match &error {
+        OptionPricingError::InvalidInput { parameter, .. } if parameter == "spot" => {
+            parameter.clone()
+        }
+        _ => field,
+    };
+    GatewayError::validation(field, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn post(body: Value) -> (StatusCode, Value) {
+        let request = Request::post("/api/v1/options/greeks")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = crate::router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_greeks_table_for_chain() {
+        let (status, body) = post(json!({
+            "spot": 100,
+            "valuation_date": "2024-01-01",
+            "rate": 0.05,
+            "volatility": 0.2,
+            "contracts": [
+                { "symbol": "XYZ241231P00100000" },
+                { "symbol": "XYZ   241231C00100000" },
+                { "symbol": "XYZ241231C00110000", "market_price": 6.04 }
+            ]
+        }))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let table: GreeksTable = serde_json::from_value(body["data"].clone()).unwrap();
+        let symbols: Vec<&str> = table.rows.iter().map(|row| row.symbol.as_str()).collect();
+        assert_eq!(
+            symbols,
+            [
+                "XYZ241231C00100000",
+                "XYZ241231P00100000",
+                "XYZ241231C00110000"
+            ]
+        );
+        let call = &table.rows[0];
+        assert!((call.price - Decimal::new(104_506, 4)).abs() < Decimal::new(1, 3));
+        assert!((call.greeks.delta - Decimal::new(6368, 4)).abs() < Decimal::new(1, 3));
+        assert!(table.rows[2].implied);
+        assert!(table.rows[2].volatility > Decimal::new(19, 2));
+        assert_eq!(body["metadata"]["source"], "api-gateway");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_chains_are_rejected() {
+        let (status, body) = post(json!({
+            "spot": 100,
+            "valuation_date": "2024-01-01",
+            "contracts": [{ "symbol": "XYZ241231C00100000" }]
+        }))
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["status"], "error");
+        assert_eq!(
+            body["error"]["Validation"]["field"],
+            "contracts[0].volatility"
+        );
+
+        let (status, _) = post(json!({ "spot": 100 })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = post(json!({
+            "spot": 100,
+            "valuation_date": "2025-06-01",
+            "volatility": 0.2,
+            "contracts": [{ "symbol": "XYZ241231C00100000" }]
+        }))
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,615 @@
+use analytics::Screener;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use database::{OhlcvRepository, ScreenRepository, SymbolRepository};
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use shared_types::{
+    ApiError, ApiResponse, SavedScreen, ScreenCriteria, ScreenPage, Symbol, TimeFrame,
+    DEFAULT_SCREEN_PAGE_SIZE, OHLCV,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::error::{self, GatewayError};
+use crate::GatewayState;
+
+/// Bars kept per symbol and timeframe when not configured
+pub const DEFAULT_HISTORY_BARS: usize = 300;
+
+/// How long a cached series is trusted before it is reloaded, when not configured
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Series loaded from the database at once when filling the cache
+const LOAD_CONCURRENCY: usize = 16;
+
+/// Latest bars of one symbol on one timeframe, oldest first
+type Series = Arc<[OHLCV]>;
+
+/// A series and when it was read from the bar store
+#[derive(Debug, Clone)]
+struct Cached {
+    series: Series,
+    loaded: Instant,
+}
+
+/// Screens over the symbol master, run against cached latest bars
+///
+/// Each symbol's recent bars are loaded from the bar store the first time a
+/// screen needs them and then kept current by [`on_bar`](Self::on_bar), so
+/// repeated screens only pay for evaluation, which runs on the blocking pool
+/// spread across every core. Series are reloaded once they are older than the
+/// cache TTL, so bars written to the store by ingestion or corrections show up
+/// even for symbols with no live feed.
+pub struct ScreenerService {
+    symbols: Arc<SymbolRepository>,
+    bars: Arc<OhlcvRepository>,
+    screens: Arc<ScreenRepository>,
+    latest: RwLock<HashMap<(String, TimeFrame), Cached>>,
+    history: usize,
+    ttl: Duration,
+}
+
+impl ScreenerService {
+    pub fn new(
+        symbols: Arc<SymbolRepository>,
+        bars: Arc<OhlcvRepository>,
+        screens: Arc<ScreenRepository>,
+    ) -> Self {
+        Self {
+            symbols,
+            bars,
+            screens,
+            latest: RwLock::new(HashMap::new()),
+            history: DEFAULT_HISTORY_BARS,
+            ttl: DEFAULT_CACHE_TTL,
+        }
+    }
+
+    /// Bars kept per symbol; screens needing a longer warm-up are rejected
+    pub fn with_history(mut self, bars: usize) -> Self {
+        self.history = bars.max(1);
+        self
+    }
+
+    /// How long a loaded series is used before it is read from the store again
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Keep a cached series current with a new or revised bar
+    ///
+    /// Series no screen has asked for yet are left to be loaded when needed.
+    pub fn on_bar(&self, bar: &OHLCV) {
+        let key = (bar.symbol.full_identifier(), bar.timeframe.clone());
+        let mut latest = self.latest.write().unwrap_or_else(|e| e.into_inner());
+        let Some(Cached { series, .. }) = latest.get_mut(&key) else {
+            return;
+        };
+        let mut bars = series.to_vec();
+        match bars.last() {
+            Some(last) if last.timestamp == bar.timestamp => {
+                let index = bars.len() - 1;
+                bars[index] = bar.clone();
+            }
+            Some(last) if last.timestamp > bar.timestamp => return,
+            _ => bars.push(bar.clone()),
+        }
+        if bars.len() > self.history {
+            bars.drain(..bars.len() - self.history);
+        }
+        *series = bars.into();
+    }
+
+    /// Drop every cached series, e.g. after bars were corrected in the store
+    pub fn clear_cache(&self) {
+        self.latest
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Run `criteria` over its universe and return one page of the matches
+    pub async fn run(
+        &self,
+        criteria: ScreenCriteria,
+        page: u32,
+        page_size: u32,
+    ) -> Result<ScreenPage, GatewayError> {
+        let screener = Screener::new(criteria)?;
+        if screener.warm_up_bars() > self.history {
+            return Err(GatewayError::validation(
+                "filter",
+                format!(
+                    "The screen needs {} bars per symbol but only the latest {} are kept",
+                    screener.warm_up_bars(),
+                    self.history
+                ),
+            ));
+        }
+        let criteria = screener.criteria();
+        let universe: Vec<Symbol> = self
+            .symbols
+            .active_symbols()
+            .await?
+            .into_iter()
+            .filter(|symbol| criteria.universe.includes(symbol))
+            .collect();
+        let series = self.series(&universe, &criteria.timeframe).await?;
+
+        let results = tokio::task::spawn_blocking(move || screener.run(&series))
+            .await
+            .map_err(|error| {
+                GatewayError(ApiError::Internal {
+                    message: format!("Screen failed: {}", error),
+                    error_id: Uuid::new_v4(),
+                })
+            })?;
+        Ok(results.page(page, page_size))
+    }
+
+    /// Validate and store a screen under `name`
+    pub async fn save_screen(
+        &self,
+        name: &str,
+        criteria: ScreenCriteria,
+    ) -> Result<SavedScreen, GatewayError> {
+        Screener::new(criteria.clone())?;
+        let screen = SavedScreen::new(name, criteria)?;
+        self.screens.save(&screen).await?;
+        Ok(screen)
+    }
+
+    pub async fn screens(&self) -> Result<Vec<SavedScreen>, GatewayError> {
+        Ok(self.screens.list().await?)
+    }
+
+    pub async fn screen(&self, id: Uuid) -> Result<SavedScreen, GatewayError> {
+        self.screens.get(id).await?.ok_or_else(|| {
+            GatewayError(ApiError::NotFound {
+                resource: format!("screen {}", id),
+            })
+        })
+    }
+
+    pub async fn delete_screen(&self, id: Uuid) -> Result<(), GatewayError> {
+        if self.screens.delete(id).await? {
+            Ok(())
+        } else {
+            Err(GatewayError(ApiError::NotFound {
+                resource: format!("screen {}", id),
+            }))
+        }
+    }
+
+    pub async fn run_saved(
+        &self,
+        id: Uuid,
+        page: u32,
+        page_size: u32,
+    ) -> Result<ScreenPage, GatewayError> {
+        let screen = self.screen(id).await?;
+        self.run(screen.criteria, page, page_size).await
+    }
+
+    /// Cached series for `symbols`, loading the missing ones
+    async fn series(
+        &self,
+        symbols: &[Symbol],
+        timeframe: &TimeFrame,
+    ) -> Result<Vec<Series>, GatewayError> {
+        let mut found = Vec::with_capacity(symbols.len());
+        let mut missing = Vec::new();
+        {
+            let latest = self.latest.read().unwrap_or_else(|e| e.into_inner());
+            for symbol in symbols {
+                match latest.get(&(symbol.full_identifier(), timeframe.clone())) {
+                    Some(cached) if cached.loaded.elapsed() < self.ttl => {
+                        found.push(cached.series.clone())
+                    }
+                    _ => missing.push(symbol.clone()),
+                }
+            }
+        }
+
+        let loaded: Vec<(String, Cached)> = futures::stream::iter(missing)
+            .map(|symbol| async move {
+                let loaded = Instant::now();
+                let bars = self
+                    .bars
+                    .latest_bars(&symbol, timeframe, self.history as u32)
+                    .await?;
+                let series = Series::from(bars);
+                Ok::<_, GatewayError>((symbol.full_identifier(), Cached { series, loaded }))
+            })
+            .buffer_unordered(LOAD_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        let mut latest = self.latest.write().unwrap_or_else(|e| e.into_inner());
+        for (identifier, fresh) in loaded {
+            // Another screen may have loaded the series meanwhile and bars since applied to it
+            let cached = latest
+                .entry((identifier, timeframe.clone()))
+                .and_modify(|cached| {
+                    if cached.loaded < fresh.loaded {
+                        *cached = fresh.clone();
+                    }
+                })
+                .or_insert_with(|| fresh.clone());
+            found.push(cached.series.clone());
+        }
+        Ok(found)
+    }
+}
+
+// ============================================================================
+// Routes
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PageQuery {
+    #[serde(default = "first_page")]
+    pub page: u32,
+
+    #[serde(default = "default_page_size")]
+    pub page_size: u32,
+}
+
+fn first_page() -> u32 {
+    1
+}
+
+fn default_page_size() -> u32 {
+    DEFAULT_SCREEN_PAGE_SIZE
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewScreen {
+    pub name: String,
+    pub criteria: ScreenCriteria,
+}
+
+/// Screener routes, under `/api/v1/screener` and `/api/v1/screens`
+pub fn routes() -> Router<GatewayState> {
+    Router::new()
+        .route("/api/v1/screener", post(run))
+        .route("/api/v1/screens", get(list).post(create))
+        .route("/api/v1/screens/{id}", get(show).delete(delete))
+        .route("/api/v1/screens/{id}/results", get(results))
+}
+
+type Reply<T> = Result<Json<ApiResponse<T>>, GatewayError>;
+
+fn service(state: &GatewayState) -> Result<&ScreenerService, GatewayError> {
+    state.screener.as_deref().ok_or_else(|| {
+        GatewayError(ApiError::ServiceUnavailable {
+            message: "The screener is not configured".to_string(),
+        })
+    })
+}
+
+fn page(query: Result<Query<PageQuery>, QueryRejection>) -> Result<PageQuery, GatewayError> {
+    query
+        .map(|Query(query)| query)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+fn id(path: Result<Path<Uuid>, PathRejection>) -> Result<Uuid, GatewayError> {
+    path.map(|Path(id)| id)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+fn body<T>(body: Result<Json<T>, JsonRejection>) -> Result<T, GatewayError> {
+    body.map(|Json(body)| body)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+async fn run(
+    State(state): State<GatewayState>,
+    query: Result<Query<PageQuery>, QueryRejection>,
+    criteria: Result<Json<ScreenCriteria>, JsonRejection>,
+) -> Reply<ScreenPage> {
+    let started = Instant::now();
+    let (query, criteria) = (page(query)?, body(criteria)?);
+    let page = service(&state)?
+        .run(criteria, query.page, query.page_size)
+        .await?;
+    Ok(error::paginated(page.clone(), page.pagination, started))
+}
+
+async fn list(State(state): State<GatewayState>) -> Reply<Vec<SavedScreen>> {
+    let started = Instant::now();
+    Ok(error::success(service(&state)?.screens().await?, started))
+}
+
+async fn create(
+    State(state): State<GatewayState>,
+    request: Result<Json<NewScreen>, JsonRejection>,
+) -> Reply<SavedScreen> {
+    let started = Instant::now();
+    let request = body(request)?;
+    Ok(error::success(
+        service(&state)?
+            .save_screen(&request.name, request.criteria)
+            .await?,
+        started,
+    ))
+}
+
+async fn show(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+) -> Reply<SavedScreen> {
+    let started = Instant::now();
+    Ok(error::success(
+        service(&state)?.screen(id(path)?).await?,
+        started,
+    ))
+}
+
+async fn delete(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+) -> Reply<Uuid> {
+    let started = Instant::now();
+    let id = id(path)?;
+    service(&state)?.delete_screen(id).await?;
+    Ok(error::success(id, started))
+}
+
+async fn results(
+    State(state): State<GatewayState>,
+    path: Result<Path<Uuid>, PathRejection>,
+    query: Result<Query<PageQuery>, QueryRejection>,
+) -> Reply<ScreenPage> {
+    let started = Instant::now();
+    let (id, query) = (id(path)?, page(query)?);
+    let page = service(&state)?
+        .run_saved(id, query.page, query.page_size)
+        .await?;
+    Ok(error::paginated(page.clone(), page.pagination, started))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use chrono::{Duration, TimeZone, Utc};
+    use database::{SqlitePool, SqlitePoolConfig};
+    use http_body_util::BodyExt;
+    use rust_decimal::Decimal;
+    use serde_json::{json, Value};
+    use shared_types::Exchange;
+    use tower::ServiceExt;
+
+    async fn state() -> (GatewayState, Arc<ScreenerService>, Arc<OhlcvRepository>) {
+        cached_for(DEFAULT_CACHE_TTL).await
+    }
+
+    async fn cached_for(
+        ttl: std::time::Duration,
+    ) -> (GatewayState, Arc<ScreenerService>, Arc<OhlcvRepository>) {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let pool = Arc::new(SqlitePool::new(config).await.unwrap());
+        let symbols = Arc::new(SymbolRepository::new(pool.clone()));
+        let bars = Arc::new(OhlcvRepository::new(pool.clone()));
+        let screens = Arc::new(ScreenRepository::new(pool));
+        symbols.migrate().await.unwrap();
+        bars.migrate().await.unwrap();
+        screens.migrate().await.unwrap();
+
+        let mut universe = vec![
+            Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap(),
+            Symbol::stock("MSFT", "Microsoft Corporation", Exchange::NASDAQ).unwrap(),
+            Symbol::stock("NVDA", "NVIDIA Corporation", Exchange::NASDAQ).unwrap(),
+            Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap(),
+        ];
+        symbols.upsert_symbols(&universe).await.unwrap();
+        // NVDA has no bars yet; the others fall for 30 days
+        universe.remove(2);
+        for (offset, symbol) in universe.iter().enumerate() {
+            let series: Vec<OHLCV> = (0..30)
+                .map(|day| bar(symbol, day, 100.0 + 10.0 * offset as f64 - day as f64))
+                .collect();
+            bars.upsert_bars(&series).await.unwrap();
+        }
+
+        let screener = Arc::new(
+            ScreenerService::new(symbols, bars.clone(), screens)
+                .with_history(40)
+                .with_cache_ttl(ttl),
+        );
+        let watchlists = crate::watchlists::tests::state().await.watchlists;
+        let state = GatewayState::new(watchlists).with_screener(screener.clone());
+        (state, screener, bars)
+    }
+
+    fn bar(symbol: &Symbol, day: i64, close: f64) -> OHLCV {
+        let close = Decimal::from_f64_retain(close).unwrap();
+        OHLCV::new(
+            symbol.clone(),
+            TimeFrame::OneDay,
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::days(day),
+            close,
+            close + Decimal::ONE,
+            close - Decimal::ONE,
+            close,
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    async fn call(
+        state: &GatewayState,
+        method: Method,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = crate::app(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_screens_run_over_cached_bars() {
+        let (state, screener, bars) = state().await;
+        let criteria = json!({
+            "universe": {"exchanges": ["NASDAQ"]},
+            "filter": "rsi(14) < 30",
+            "columns": [{"name": "rsi", "formula": "rsi(14)"}],
+            "sort": {"column": "close", "descending": true},
+        });
+
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            "/api/v1/screener?page_size=1",
+            criteria.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["screened"], 3);
+        assert_eq!(body["data"]["insufficient_data"], 1);
+        assert_eq!(body["data"]["rows"][0]["symbol"]["code"], "MSFT");
+        assert_eq!(body["data"]["rows"][0]["values"]["rsi"], 0.0);
+        assert_eq!(body["metadata"]["pagination"]["total_items"], 2);
+        assert_eq!(body["metadata"]["pagination"]["has_next"], true);
+
+        // New bars reach cached series without touching the store
+        let apple = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        for day in 30..45 {
+            screener.on_bar(&bar(&apple, day, 80.0 + 3.0 * (day - 29) as f64));
+        }
+        let (_, body) = call(&state, Method::POST, "/api/v1/screener", criteria.clone()).await;
+        assert_eq!(body["data"]["pagination"]["total_items"], 1);
+        assert_eq!(body["data"]["rows"][0]["symbol"]["code"], "MSFT");
+        let stored = bars
+            .latest_bars(&apple, &TimeFrame::OneDay, 100)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 30);
+
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            "/api/v1/screens",
+            json!({"name": "Oversold", "criteria": criteria}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let id = body["data"]["id"].as_str().unwrap().to_string();
+        let (_, body) = call(
+            &state,
+            Method::GET,
+            &format!("/api/v1/screens/{}/results?page=2&page_size=1", id),
+            Value::Null,
+        )
+        .await;
+        assert!(body["data"]["rows"].as_array().unwrap().is_empty());
+        assert_eq!(body["metadata"]["pagination"]["page"], 2);
+
+        let (status, _) = call(
+            &state,
+            Method::DELETE,
+            &format!("/api/v1/screens/{}", id),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &state,
+            Method::GET,
+            &format!("/api/v1/screens/{}/results", id),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_expired_series_are_reloaded_from_the_store() {
+        let (state, _, bars) = cached_for(std::time::Duration::ZERO).await;
+        let criteria = json!({"universe": {"exchanges": ["NASDAQ"]}, "filter": "rsi(14) < 30"});
+        let (_, body) = call(&state, Method::POST, "/api/v1/screener", criteria.clone()).await;
+        assert_eq!(body["data"]["pagination"]["total_items"], 2);
+
+        // Bars ingested straight into the store, with no live feed for the symbol
+        let apple = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let rally: Vec<OHLCV> = (30..45)
+            .map(|day| bar(&apple, day, 80.0 + 3.0 * (day - 29) as f64))
+            .collect();
+        bars.upsert_bars(&rally).await.unwrap();
+        let (_, body) = call(&state, Method::POST, "/api/v1/screener", criteria).await;
+        assert_eq!(body["data"]["pagination"]["total_items"], 1);
+        assert_eq!(body["data"]["rows"][0]["symbol"]["code"], "MSFT");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_screens_are_rejected() {
+        let (state, _, _) = state().await;
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            "/api/v1/screener",
+            json!({"filter": "rsi(14) < 30 and vwap > close"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["Validation"]["field"], "filter");
+
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            "/api/v1/screener",
+            json!({"filter": "close > sma(50)"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["error"]["Validation"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("needs 50 bars"));
+
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            "/api/v1/screens",
+            json!({"name": "Bad sort", "criteria": {"filter": "close > 1", "sort": {"column": "rsi"}}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["Validation"]["field"], "sort.column");
+
+        let (status, _) = call(
+            &state,
+            Method::POST,
+            "/api/v1/screener?page=zero",
+            json!({"filter": "close > 1"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let unconfigured = GatewayState::new(state.watchlists.clone());
+        let (status, _) = call(&unconfigured, Method::GET, "/api/v1/screens", Value::Null).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,784 @@
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use shared_types::{
+    ApiError, SubscriptionRequest, SubscriptionType, Symbol, TimeFrame, Watchlist,
+    WebSocketMessage, WebSocketMessageType,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use crate::error::GatewayError;
+use crate::watchlists::WatchlistChange;
+use crate::GatewayState;
+
+/// Quotes buffered for slow connections before they lag
+const QUOTE_CHANNEL_CAPACITY: usize = 4096;
+
+/// Upstream subscription changes buffered for the market data feed
+const UPSTREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// Subscriptions to start and stop
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionDelta {
+    pub subscribe: Vec<SubscriptionRequest>,
+    pub unsubscribe: Vec<SubscriptionRequest>,
+}
+
+impl SubscriptionDelta {
+    pub fn is_empty(&self) -> bool {
+        self.subscribe.is_empty() && self.unsubscribe.is_empty()
+    }
+}
+
+/// Identity of a subscription; parameters such as the originating watchlist do not count
+fn subscription_key(request: &SubscriptionRequest) -> String {
+    format!(
+        "{:?}|{}|{:?}",
+        request.subscription_type,
+        request.symbol.full_identifier(),
+        request.timeframe
+    )
+}
+
+/// A `market_data` message with the symbol and timeframe it quotes
+///
+/// Read once when published, so connections match quotes without parsing them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishedQuote {
+    /// [`Symbol::full_identifier`] of the quoted symbol
+    pub identifier: String,
+
+    /// Set on bars
+    pub timeframe: Option<TimeFrame>,
+
+    pub message: WebSocketMessage,
+}
+
+impl PublishedQuote {
+    /// `None` unless `message` is `market_data` naming the `symbol` it quotes
+    pub fn new(message: WebSocketMessage) -> Option<Self> {
+        if message.message_type != WebSocketMessageType::MarketData {
+            return None;
+        }
+        let symbol: Symbol = serde_json::from_value(message.payload.get("symbol")?.clone()).ok()?;
+        Some(Self {
+            identifier: symbol.full_identifier(),
+            timeframe: timeframe(&message.payload),
+            message,
+        })
+    }
+}
+
+/// Live quotes going out to stream connections, and the demand for them
+///
+/// The market data feed publishes `market_data` messages here and follows
+/// [`upstream`](Self::upstream), which announces a subscription when its first
+/// connection asks for it and an unsubscription when its last one lets go.
+pub struct QuoteHub {
+    quotes: broadcast::Sender<Arc<PublishedQuote>>,
+    upstream: broadcast::Sender<SubscriptionDelta>,
+    demand: Mutex<HashMap<String, (SubscriptionRequest, usize)>>,
+}
+
+impl Default for QuoteHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuoteHub {
+    pub fn new() -> Self {
+        let (quotes, _) = broadcast::channel(QUOTE_CHANNEL_CAPACITY);
+        let (upstream, _) = broadcast::channel(UPSTREAM_CHANNEL_CAPACITY);
+        Self {
+            quotes,
+            upstream,
+            demand: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send a `market_data` message to the connections that want it
+    ///
+    /// Messages that are not `market_data` or do not name their `symbol` are dropped.
+    pub fn publish(&self, message: WebSocketMessage) {
+        match PublishedQuote::new(message) {
+            Some(quote) => {
+                let _ = self.quotes.send(Arc::new(quote));
+            }
+            None => tracing::warn!("dropping a quote that does not name its symbol"),
+        }
+    }
+
+    pub fn quotes(&self) -> broadcast::Receiver<Arc<PublishedQuote>> {
+        self.quotes.subscribe()
+    }
+
+    pub fn upstream(&self) -> broadcast::Receiver<SubscriptionDelta> {
+        self.upstream.subscribe()
+    }
+
+    /// Everything at least one connection is subscribed to
+    pub fn demand(&self) -> Vec<SubscriptionRequest> {
+        let demand = self.demand.lock().unwrap_or_else(|e| e.into_inner());
+        let mut requests: Vec<(&String, &SubscriptionRequest)> = demand
+            .iter()
+            .map(|(key, (request, _))| (key, request))
+            .collect();
+        requests.sort_by(|a, b| a.0.cmp(b.0));
+        requests
+            .into_iter()
+            .map(|(_, request)| request.clone())
+            .collect()
+    }
+
+    /// Count one connection's change, forwarding the subscriptions that start or stop
+    pub fn apply(&self, delta: &SubscriptionDelta) {
+        let mut upstream = SubscriptionDelta::default();
+        {
+            let mut demand = self.demand.lock().unwrap_or_else(|e| e.into_inner());
+            for request in &delta.subscribe {
+                let entry = demand
+                    .entry(subscription_key(request))
+                    .or_insert_with(|| (request.clone(), 0));
+                entry.1 += 1;
+                if entry.1 == 1 {
+                    upstream.subscribe.push(request.clone());
+                }
+            }
+            for request in &delta.unsubscribe {
+                let key = subscription_key(request);
+                if let Some(entry) = demand.get_mut(&key) {
+                    entry.1 -= 1;
+                    if entry.1 == 0 {
+                        demand.remove(&key);
+                        upstream.unsubscribe.push(request.clone());
+                    }
+                }
+            }
+        }
+        if !upstream.is_empty() {
+            let _ = self.upstream.send(upstream);
+        }
+    }
+}
+
+/// A watchlist followed by one connection, with the requests it expanded to
+#[derive(Debug, Clone)]
+struct WatchedList {
+    timeframe: Option<TimeFrame>,
+    requests: Vec<SubscriptionRequest>,
+}
+
+/// What one stream connection is subscribed to
+///
+/// Symbols can be subscribed directly or through watchlists; a symbol reached
+/// both ways is one subscription. Every change returns the resulting
+/// [`SubscriptionDelta`] so the connection can report it and update the hub.
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    direct: BTreeMap<String, SubscriptionRequest>,
+    watchlists: HashMap<Uuid, WatchedList>,
+
+    /// Timeframes of market data subscriptions, by symbol identifier
+    quoted: HashMap<String, Vec<Option<TimeFrame>>>,
+
+    /// Timeframes of alert subscriptions, by symbol code
+    alerted: HashMap<String, Vec<Option<TimeFrame>>>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&mut self, request: SubscriptionRequest) -> SubscriptionDelta {
+        self.change(|subscriptions| {
+            subscriptions
+                .direct
+                .insert(subscription_key(&request), request);
+        })
+    }
+
+    pub fn unsubscribe(&mut self, request: &SubscriptionRequest) -> SubscriptionDelta {
+        self.change(|subscriptions| {
+            subscriptions.direct.remove(&subscription_key(request));
+        })
+    }
+
+    /// Subscribe to every member of `watchlist`, following it as it changes
+    pub fn subscribe_watchlist(
+        &mut self,
+        watchlist: &Watchlist,
+        timeframe: Option<TimeFrame>,
+    ) -> SubscriptionDelta {
+        self.change(|subscriptions| {
+            subscriptions.watchlists.insert(
+                watchlist.id,
+                WatchedList {
+                    requests: watchlist.subscriptions(timeframe.clone()),
+                    timeframe,
+                },
+            );
+        })
+    }
+
+    pub fn unsubscribe_watchlist(&mut self, id: Uuid) -> SubscriptionDelta {
+        self.change(|subscriptions| {
+            subscriptions.watchlists.remove(&id);
+        })
+    }
+
+    /// Follow an edit or deletion; `None` when the watchlist is not followed here
+    pub fn on_change(&mut self, change: &WatchlistChange) -> Option<SubscriptionDelta> {
+        match change {
+            WatchlistChange::Updated(watchlist) => {
+                let timeframe = self.watchlists.get(&watchlist.id)?.timeframe.clone();
+                Some(self.subscribe_watchlist(watchlist, timeframe))
+            }
+            WatchlistChange::Deleted(id) => {
+                self.watchlists.get(id)?;
+                Some(self.unsubscribe_watchlist(*id))
+            }
+        }
+    }
+
+    pub fn watchlist_ids(&self) -> Vec<Uuid> {
+        self.watchlists.keys().copied().collect()
+    }
+
+    /// Drop everything, e.g. when the connection closes
+    pub fn clear(&mut self) -> SubscriptionDelta {
+        self.change(|subscriptions| {
+            subscriptions.direct.clear();
+            subscriptions.watchlists.clear();
+        })
+    }
+
+    /// Whether a quote is for a subscribed symbol and timeframe
+    pub fn wants_quote(&self, quote: &PublishedQuote) -> bool {
+        self.quoted
+            .get(&quote.identifier)
+            .is_some_and(|timeframes| covers(timeframes, quote.timeframe.as_ref()))
+    }
+
+    /// Whether an `alert` message is for a symbol code with an alert subscription
+    ///
+    /// Alerts carry the fired [`AlertEvent`](shared_types::AlertEvent), which
+    /// names its symbol by code.
+    pub fn wants_alert(&self, message: &WebSocketMessage) -> bool {
+        if message.message_type != WebSocketMessageType::Alert {
+            return false;
+        }
+        let Some(event) = message.payload.get("event") else {
+            return false;
+        };
+        let Some(code) = event.get("symbol").and_then(|symbol| symbol.as_str()) else {
+            return false;
+        };
+        self.alerted
+            .get(code)
+            .is_some_and(|timeframes| covers(timeframes, timeframe(event).as_ref()))
+    }
+
+    fn requests(&self) -> impl Iterator<Item = &SubscriptionRequest> {
+        self.direct.values().chain(
+            self.watchlists
+                .values()
+                .flat_map(|watched| watched.requests.iter()),
+        )
+    }
+
+    fn effective(&self) -> BTreeMap<String, SubscriptionRequest> {
+        let mut effective = BTreeMap::new();
+        for request in self.requests() {
+            effective
+                .entry(subscription_key(request))
+                .or_insert_with(|| request.clone());
+        }
+        effective
+    }
+
+    fn change(&mut self, edit: impl FnOnce(&mut Self)) -> SubscriptionDelta {
+        let before = self.effective();
+        edit(self);
+        let mut after = self.effective();
+        let mut delta = SubscriptionDelta::default();
+        for (key, request) in before {
+            if after.remove(&key).is_none() {
+                delta.unsubscribe.push(request);
+            }
+        }
+        delta.subscribe = after.into_values().collect();
+        self.index();
+        delta
+    }
+
+    /// Rebuild the lookups `wants_quote` and `wants_alert` use
+    fn index(&mut self) {
+        let mut quoted: HashMap<String, Vec<Option<TimeFrame>>> = HashMap::new();
+        let mut alerted: HashMap<String, Vec<Option<TimeFrame>>> = HashMap::new();
+        for request in self.requests() {
+            let (index, key) = match request.subscription_type {
+                SubscriptionType::MarketData => (&mut quoted, request.symbol.full_identifier()),
+                SubscriptionType::Alerts => (&mut alerted, request.symbol.code.clone()),
+                _ => continue,
+            };
+            index
+                .entry(key)
+                .or_default()
+                .push(request.timeframe.clone());
+        }
+        self.quoted = quoted;
+        self.alerted = alerted;
+    }
+}
+
+/// Whether a subscription to any of `wanted` receives messages on `sent`
+///
+/// A side without a timeframe matches every timeframe.
+fn covers(wanted: &[Option<TimeFrame>], sent: Option<&TimeFrame>) -> bool {
+    wanted.iter().any(|wanted| match (wanted, sent) {
+        (Some(wanted), Some(sent)) => wanted == sent,
+        _ => true,
+    })
+}
+
+fn timeframe(payload: &serde_json::Value) -> Option<TimeFrame> {
+    payload
+        .get("timeframe")
+        .and_then(|timeframe| serde_json::from_value::<TimeFrame>(timeframe.clone()).ok())
+}
+
+/// What a `subscribe` or `unsubscribe` message names
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Target {
+    Watchlist {
+        watchlist_id: Uuid,
+        #[serde(default)]
+        timeframe: Option<TimeFrame>,
+    },
+    Symbol(Box<SubscriptionRequest>),
+}
+
+pub(crate) fn message(
+    message_type: WebSocketMessageType,
+    payload: serde_json::Value,
+) -> WebSocketMessage {
+    WebSocketMessage {
+        message_id: Uuid::new_v4(),
+        message_type,
+        payload,
+        timestamp: Utc::now(),
+    }
+}
+
+fn error_message(error: GatewayError) -> WebSocketMessage {
+    message(
+        WebSocketMessageType::Error,
+        serde_json::to_value(&error.0).unwrap_or_default(),
+    )
+}
+
+fn delta_message(delta: &SubscriptionDelta, watchlist_id: Option<Uuid>) -> WebSocketMessage {
+    message(
+        WebSocketMessageType::Response,
+        serde_json::json!({
+            "watchlist_id": watchlist_id,
+            "subscribe": delta.subscribe,
+            "unsubscribe": delta.unsubscribe,
+        }),
+    )
+}
+
+/// Accept stream connections on `listener` until it fails
+///
+/// Clients send `subscribe` and `unsubscribe` messages whose payload is either
+/// a [`SubscriptionRequest`] or `{"watchlist_id": ..., "timeframe": ...}`.
+/// Each is answered with a `response` listing the subscriptions started and
+/// stopped; watchlist edits made elsewhere produce the same `response`
+/// unprompted, so clients always know what they will receive. Requests of
+/// type `alerts` receive the `alert` messages fired for their symbol.
+pub async fn serve(listener: TcpListener, state: GatewayState) -> std::io::Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(error) = connection(stream, state).await {
+                tracing::debug!(%peer, %error, "stream connection ended");
+            }
+        });
+    }
+}
+
+async fn connection(
+    stream: TcpStream,
+    state: GatewayState,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let mut socket = tokio_tungstenite::accept_async(stream).await?;
+    let mut changes = state.watchlists.subscribe();
+    let mut quotes = state.quotes.quotes();
+    let mut alerts = state.alerts.as_ref().map(|alerts| alerts.subscribe());
+    let mut subscriptions = Subscriptions::new();
+
+    let result = loop {
+        let outgoing = tokio::select! {
+            frame = socket.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    vec![request(&state, &mut subscriptions, text.as_str()).await]
+                }
+                Some(Ok(Message::Close(_))) | None => break Ok(()),
+                Some(Ok(_)) => continue,
+                Some(Err(error)) => break Err(error),
+            },
+            change = changes.recv() => match change {
+                Ok(change) => follow(&state, &mut subscriptions, &change).into_iter().collect(),
+                Err(RecvError::Lagged(_)) => resync(&state, &mut subscriptions).await,
+                Err(RecvError::Closed) => break Ok(()),
+            },
+            quote = quotes.recv() => match quote {
+                Ok(quote) if subscriptions.wants_quote(&quote) => vec![quote.message.clone()],
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "stream connection fell behind on quotes");
+                    continue;
+                }
+                Err(RecvError::Closed) => break Ok(()),
+            },
+            alert = next_alert(&mut alerts) => match alert {
+                Ok(alert) if subscriptions.wants_alert(&alert) => vec![alert],
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "stream connection fell behind on alerts");
+                    continue;
+                }
+                Err(RecvError::Closed) => break Ok(()),
+            },
+        };
+        let mut sent = Ok(());
+        for outgoing in outgoing {
+            let text = serde_json::to_string(&outgoing).unwrap_or_default();
+            sent = socket.send(Message::text(text)).await;
+            if sent.is_err() {
+                break;
+            }
+        }
+        if let Err(error) = sent {
+            break Err(error);
+        }
+    };
+
+    state.quotes.apply(&subscriptions.clear());
+    result
+}
+
+/// The next alert, or never when the gateway has no alert service
+async fn next_alert(
+    alerts: &mut Option<broadcast::Receiver<WebSocketMessage>>,
+) -> Result<WebSocketMessage, RecvError> {
+    match alerts {
+        Some(alerts) => alerts.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn request(
+    state: &GatewayState,
+    subscriptions: &mut Subscriptions,
+    text: &str,
+) -> WebSocketMessage {
+    let incoming: WebSocketMessage = match serde_json::from_str(text) {
+        Ok(incoming) => incoming,
+        Err(error) => return error_message(GatewayError::bad_request(error.to_string())),
+    };
+    let subscribe = match incoming.message_type {
+        WebSocketMessageType::Subscribe => true,
+        WebSocketMessageType::Unsubscribe => false,
+        WebSocketMessageType::Heartbeat => {
+            return message(WebSocketMessageType::Heartbeat, serde_json::Value::Null)
+        }
+        other => {
+            return error_message(GatewayError::bad_request(format!(
+                "Unsupported message type: {:?}",
+                other
+            )))
+        }
+    };
+    let target: Target = match serde_json::from_value(incoming.payload) {
+        Ok(target) => target,
+        Err(error) => return error_message(GatewayError::validation("payload", error.to_string())),
+    };
+
+    let (delta, watchlist_id) = match target {
+        Target::Watchlist {
+            watchlist_id,
+            timeframe,
+        } if subscribe => match state.watchlists.get(watchlist_id).await {
+            Ok(watchlist) => (
+                subscriptions.subscribe_watchlist(&watchlist, timeframe),
+                Some(watchlist_id),
+            ),
+            Err(error) => return error_message(error),
+        },
+        Target::Watchlist { watchlist_id, .. } => (
+            subscriptions.unsubscribe_watchlist(watchlist_id),
+            Some(watchlist_id),
+        ),
+        Target::Symbol(request) if subscribe => (subscriptions.subscribe(*request), None),
+        Target::Symbol(request) => (subscriptions.unsubscribe(&request), None),
+    };
+    state.quotes.apply(&delta);
+    delta_message(&delta, watchlist_id)
+}
+
+fn follow(
+    state: &GatewayState,
+    subscriptions: &mut Subscriptions,
+    change: &WatchlistChange,
+) -> Option<WebSocketMessage> {
+    let delta = subscriptions.on_change(change)?;
+    if delta.is_empty() {
+        return None;
+    }
+    state.quotes.apply(&delta);
+    let id = match change {
+        WatchlistChange::Updated(watchlist) => watchlist.id,
+        WatchlistChange::Deleted(id) => *id,
+    };
+    Some(delta_message(&delta, Some(id)))
+}
+
+/// Reload followed watchlists after missing some of their changes
+async fn resync(state: &GatewayState, subscriptions: &mut Subscriptions) -> Vec<WebSocketMessage> {
+    let mut outgoing = Vec::new();
+    for id in subscriptions.watchlist_ids() {
+        let change = match state.watchlists.get(id).await {
+            Ok(watchlist) => WatchlistChange::Updated(watchlist),
+            Err(GatewayError(ApiError::NotFound { .. })) => WatchlistChange::Deleted(id),
+            Err(error) => {
+                tracing::warn!(watchlist = %id, ?error, "could not reload watchlist");
+                continue;
+            }
+        };
+        outgoing.extend(follow(state, subscriptions, &change));
+    }
+    outgoing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::{Exchange, WatchlistMember};
+    use std::time::Duration;
+
+    fn stock(code: &str) -> Symbol {
+        Symbol::stock(code, code, Exchange::NASDAQ).unwrap()
+    }
+
+    fn direct(code: &str) -> SubscriptionRequest {
+        SubscriptionRequest {
+            subscription_type: SubscriptionType::MarketData,
+            symbol: stock(code),
+            timeframe: Some(TimeFrame::OneMinute),
+            parameters: HashMap::new(),
+        }
+    }
+
+    fn codes(requests: &[SubscriptionRequest]) -> Vec<&str> {
+        requests
+            .iter()
+            .map(|request| request.symbol.code.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_watchlist_fan_out_follows_changes() {
+        let hub = QuoteHub::new();
+        let mut upstream = hub.upstream();
+        let mut watchlist = Watchlist::new("Tech").unwrap();
+        watchlist.add(WatchlistMember::new(stock("AAPL"))).unwrap();
+        watchlist.add(WatchlistMember::new(stock("MSFT"))).unwrap();
+
+        let mut first = Subscriptions::new();
+        hub.apply(&first.subscribe(direct("AAPL")));
+        let delta = first.subscribe_watchlist(&watchlist, Some(TimeFrame::OneMinute));
+        assert_eq!(codes(&delta.subscribe), ["MSFT"]);
+        hub.apply(&delta);
+
+        let mut second = Subscriptions::new();
+        hub.apply(&second.subscribe_watchlist(&watchlist, Some(TimeFrame::OneMinute)));
+        assert_eq!(codes(&hub.demand()), ["AAPL", "MSFT"]);
+
+        watchlist.remove("AAPL@NASDAQ").unwrap();
+        watchlist.add(WatchlistMember::new(stock("NVDA"))).unwrap();
+        let change = WatchlistChange::Updated(watchlist.clone());
+        let delta = first.on_change(&change).unwrap();
+        assert_eq!(codes(&delta.subscribe), ["NVDA"]);
+        assert!(delta.unsubscribe.is_empty(), "AAPL is still held directly");
+        hub.apply(&delta);
+        let delta = second.on_change(&change).unwrap();
+        assert_eq!(codes(&delta.unsubscribe), ["AAPL"]);
+        hub.apply(&delta);
+        assert!(Subscriptions::new().on_change(&change).is_none());
+
+        let quote = PublishedQuote::new(message(
+            WebSocketMessageType::MarketData,
+            serde_json::json!({"symbol": stock("AAPL"), "timeframe": "1m"}),
+        ))
+        .unwrap();
+        assert!(first.wants_quote(&quote));
+        assert!(!second.wants_quote(&quote));
+
+        hub.apply(&first.clear());
+        hub.apply(
+            &second
+                .on_change(&WatchlistChange::Deleted(watchlist.id))
+                .unwrap(),
+        );
+        assert!(hub.demand().is_empty());
+
+        let mut started = Vec::new();
+        let mut stopped = Vec::new();
+        while let Ok(delta) = upstream.try_recv() {
+            started.extend(delta.subscribe);
+            stopped.extend(delta.unsubscribe);
+        }
+        assert_eq!(codes(&started), ["AAPL", "MSFT", "NVDA"]);
+        assert_eq!(codes(&stopped).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_subscribes_to_watchlist() {
+        let state = crate::watchlists::tests::state().await;
+        let watchlist = state.watchlists.create("Tech").await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, state.clone()));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let subscribe = message(
+            WebSocketMessageType::Subscribe,
+            serde_json::json!({"watchlist_id": watchlist.id, "timeframe": "1m"}),
+        );
+        socket
+            .send(Message::text(serde_json::to_string(&subscribe).unwrap()))
+            .await
+            .unwrap();
+        let reply = receive(&mut socket).await;
+        assert_eq!(reply.message_type, WebSocketMessageType::Response);
+        assert_eq!(reply.payload["subscribe"], serde_json::json!([]));
+
+        state
+            .watchlists
+            .add_member(watchlist.id, WatchlistMember::new(stock("AAPL")), None)
+            .await
+            .unwrap();
+        let update = receive(&mut socket).await;
+        assert_eq!(update.payload["watchlist_id"], watchlist.id.to_string());
+        assert_eq!(update.payload["subscribe"][0]["symbol"]["code"], "AAPL");
+        assert_eq!(codes(&state.quotes.demand()), ["AAPL"]);
+
+        for code in ["MSFT", "AAPL"] {
+            state.quotes.publish(message(
+                WebSocketMessageType::MarketData,
+                serde_json::json!({"symbol": stock(code), "price": "190.5"}),
+            ));
+        }
+        let quote = receive(&mut socket).await;
+        assert_eq!(quote.message_type, WebSocketMessageType::MarketData);
+        assert_eq!(quote.payload["symbol"]["code"], "AAPL");
+
+        socket.close(None).await.unwrap();
+        for _ in 0..50 {
+            if state.quotes.demand().is_empty() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("closing the connection should release its subscriptions");
+    }
+
+    #[tokio::test]
+    async fn test_stream_forwards_subscribed_alerts() {
+        use crate::alerts::tests::{apple, bar, repository};
+        use crate::AlertService;
+        use rust_decimal::Decimal;
+        use shared_types::{AlertCondition, AlertRule, Comparison};
+
+        let alerts = Arc::new(AlertService::new(repository().await));
+        let state = crate::watchlists::tests::state()
+            .await
+            .with_alerts(alerts.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, state));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let subscribe = message(
+            WebSocketMessageType::Subscribe,
+            serde_json::json!({"subscription_type": "alerts", "symbol": apple(), "parameters": {}}),
+        );
+        socket
+            .send(Message::text(serde_json::to_string(&subscribe).unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(
+            receive(&mut socket).await.message_type,
+            WebSocketMessageType::Response
+        );
+
+        alerts
+            .create_rule(AlertRule::new(
+                "above 105",
+                apple(),
+                TimeFrame::OneHour,
+                AlertCondition::Price {
+                    comparison: Comparison::Above,
+                    level: Decimal::new(105, 0),
+                },
+            ))
+            .await
+            .unwrap();
+        alerts.on_bar(&bar(0, 106)).await.unwrap();
+        let alert = receive(&mut socket).await;
+        assert_eq!(alert.message_type, WebSocketMessageType::Alert);
+        assert_eq!(alert.payload["event"]["rule_name"], "above 105");
+
+        // Alerts only reach alert subscriptions, and quotes only market data ones
+        let mut quotes_only = Subscriptions::new();
+        quotes_only.subscribe(direct("AAPL"));
+        assert!(!quotes_only.wants_alert(&alert));
+        let mut alerts_only = Subscriptions::new();
+        alerts_only.subscribe(SubscriptionRequest {
+            subscription_type: SubscriptionType::Alerts,
+            timeframe: Some(TimeFrame::OneHour),
+            ..direct("AAPL")
+        });
+        assert!(alerts_only.wants_alert(&alert));
+        let quote = PublishedQuote::new(message(
+            WebSocketMessageType::MarketData,
+            serde_json::json!({"symbol": stock("AAPL")}),
+        ))
+        .unwrap();
+        assert!(!alerts_only.wants_quote(&quote));
+        assert!(quotes_only.wants_quote(&quote));
+    }
+
+    async fn receive<S>(socket: &mut S) -> WebSocketMessage
+    where
+        S: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(frame.to_text().unwrap()).unwrap()
+    }
+}
@@ -0,0 +1,462 @@
+use axum::extract::rejection::{JsonRejection, PathRejection};
+use axum::extract::{Path, State};
+use axum::routing::{get, patch};
+use axum::{Json, Router};
+use database::WatchlistRepository;
+use serde::{Deserialize, Deserializer, Serialize};
+use shared_types::{ApiError, ApiResponse, Symbol, Watchlist, WatchlistError, WatchlistMember};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+
+use crate::error::{self, GatewayError};
+use crate::GatewayState;
+
+/// Watchlist changes buffered for slow stream connections before they lag
+const CHANGE_CHANNEL_CAPACITY: usize = 256;
+
+/// Tell a field sent as `null` (`Some(None)`) from one left out (`None`)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// A stored watchlist after an edit, or its removal
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchlistChange {
+    Updated(Watchlist),
+    Deleted(Uuid),
+}
+
+/// Watchlist CRUD over SQLite, announcing every change to stream connections
+pub struct WatchlistService {
+    repository: Arc<WatchlistRepository>,
+    changes: broadcast::Sender<WatchlistChange>,
+    /// Edits load, modify and save the whole list, so they take turns
+    writes: Mutex<()>,
+}
+
+/// Fields of a member to change; absent fields keep their value
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemberUpdate {
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+
+    /// `null` or an empty string clears the notes
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notes: Option<Option<String>>,
+
+    #[serde(default)]
+    pub position: Option<usize>,
+}
+
+impl WatchlistService {
+    pub fn new(repository: Arc<WatchlistRepository>) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
+        Self {
+            repository,
+            changes,
+            writes: Mutex::new(()),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchlistChange> {
+        self.changes.subscribe()
+    }
+
+    pub async fn list(&self) -> Result<Vec<Watchlist>, GatewayError> {
+        Ok(self.repository.list().await?)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Watchlist, GatewayError> {
+        self.repository.get(id).await?.ok_or_else(|| not_found(id))
+    }
+
+    pub async fn create(&self, name: &str) -> Result<Watchlist, GatewayError> {
+        let watchlist = Watchlist::new(name)?;
+        self.repository.save(&watchlist).await?;
+        let _ = self
+            .changes
+            .send(WatchlistChange::Updated(watchlist.clone()));
+        Ok(watchlist)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<(), GatewayError> {
+        let _writing = self.writes.lock().await;
+        if !self.repository.delete(id).await? {
+            return Err(not_found(id));
+        }
+        let _ = self.changes.send(WatchlistChange::Deleted(id));
+        Ok(())
+    }
+
+    pub async fn rename(&self, id: Uuid, name: &str) -> Result<Watchlist, GatewayError> {
+        self.modify(id, |watchlist| watchlist.rename(name)).await
+    }
+
+    /// Add a member at `position`, or at the end
+    pub async fn add_member(
+        &self,
+        id: Uuid,
+        member: WatchlistMember,
+        position: Option<usize>,
+    ) -> Result<Watchlist, GatewayError> {
+        self.modify(id, |watchlist| {
+            let key = member.key();
+            watchlist.add(member)?;
+            match position {
+                Some(position) => watchlist.move_member(&key, position),
+                None => Ok(()),
+            }
+        })
+        .await
+    }
+
+    pub async fn update_member(
+        &self,
+        id: Uuid,
+        key: &str,
+        update: MemberUpdate,
+    ) -> Result<Watchlist, GatewayError> {
+        self.modify(id, |watchlist| {
+            let member = watchlist
+                .member(key)
+                .ok_or_else(|| WatchlistError::NotAMember(key.to_string()))?;
+            let tags = update.tags.unwrap_or_else(|| member.tags.clone());
+            let notes = update.notes.unwrap_or_else(|| member.notes.clone());
+            watchlist.annotate(key, tags, notes)?;
+            match update.position {
+                Some(position) => watchlist.move_member(key, position),
+                None => Ok(()),
+            }
+        })
+        .await
+    }
+
+    pub async fn remove_member(&self, id: Uuid, key: &str) -> Result<Watchlist, GatewayError> {
+        self.modify(id, |watchlist| watchlist.remove(key).map(|_| ()))
+            .await
+    }
+
+    async fn modify(
+        &self,
+        id: Uuid,
+        edit: impl FnOnce(&mut Watchlist) -> Result<(), WatchlistError>,
+    ) -> Result<Watchlist, GatewayError> {
+        let _writing = self.writes.lock().await;
+        let mut watchlist = self.get(id).await?;
+        edit(&mut watchlist)?;
+        self.repository.save(&watchlist).await?;
+        let _ = self
+            .changes
+            .send(WatchlistChange::Updated(watchlist.clone()));
+        Ok(watchlist)
+    }
+}
+
+fn not_found(id: Uuid) -> GatewayError {
+    GatewayError(ApiError::NotFound {
+        resource: format!("watchlist {}", id),
+    })
+}
+
+// ============================================================================
+// Routes
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistName {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewMember {
+    pub symbol: Symbol,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub notes: Option<String>,
+
+    /// Where to insert, counting from 0; the end when absent
+    #[serde(default)]
+    pub position: Option<usize>,
+}
+
+/// Watchlist routes, under `/api/v1/watchlists`
+pub fn routes() -> Router<GatewayState> {
+    Router::new()
+        .route("/api/v1/watchlists", get(list).post(create))
+        .route(
+            "/api/v1/watchlists/{id}",
+            get(show).patch(rename).delete(delete),
+        )
+        .route(
+            "/api/v1/watchlists/{id}/members",
+            axum::routing::post(add_member),
+        )
+        .route(
+            "/api/v1/watchlists/{id}/members/{key}",
+            patch(update_member).delete(remove_member),
+        )
+}
+
+type Reply<T> = Result<Json<ApiResponse<T>>, GatewayError>;
+
+fn body<T>(body: Result<Json<T>, JsonRejection>) -> Result<T, GatewayError> {
+    body.map(|Json(body)| body)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+fn path<T>(path: Result<Path<T>, PathRejection>) -> Result<T, GatewayError> {
+    path.map(|Path(path)| path)
+        .map_err(|rejection| GatewayError::bad_request(rejection.body_text()))
+}
+
+async fn list(State(state): State<GatewayState>) -> Reply<Vec<Watchlist>> {
+    let started = Instant::now();
+    Ok(error::success(state.watchlists.list().await?, started))
+}
+
+async fn create(
+    State(state): State<GatewayState>,
+    request: Result<Json<WatchlistName>, JsonRejection>,
+) -> Reply<Watchlist> {
+    let started = Instant::now();
+    let request = body(request)?;
+    Ok(error::success(
+        state.watchlists.create(&request.name).await?,
+        started,
+    ))
+}
+
+async fn show(
+    State(state): State<GatewayState>,
+    id: Result<Path<Uuid>, PathRejection>,
+) -> Reply<Watchlist> {
+    let started = Instant::now();
+    Ok(error::success(
+        state.watchlists.get(path(id)?).await?,
+        started,
+    ))
+}
+
+async fn rename(
+    State(state): State<GatewayState>,
+    id: Result<Path<Uuid>, PathRejection>,
+    request: Result<Json<WatchlistName>, JsonRejection>,
+) -> Reply<Watchlist> {
+    let started = Instant::now();
+    let (id, request) = (path(id)?, body(request)?);
+    Ok(error::success(
+        state.watchlists.rename(id, &request.name).await?,
+        started,
+    ))
+}
+
+async fn delete(
+    State(state): State<GatewayState>,
+    id: Result<Path<Uuid>, PathRejection>,
+) -> Reply<Uuid> {
+    let started = Instant::now();
+    let id = path(id)?;
+    state.watchlists.delete(id).await?;
+    Ok(error::success(id, started))
+}
+
+async fn add_member(
+    State(state): State<GatewayState>,
+    id: Result<Path<Uuid>, PathRejection>,
+    request: Result<Json<NewMember>, JsonRejection>,
+) -> Reply<Watchlist> {
+    let started = Instant::now();
+    let (id, request) = (path(id)?, body(request)?);
+    let mut member = WatchlistMember::new(request.symbol).with_tags(request.tags);
+    member.notes = request.notes;
+    Ok(error::success(
+        state
+            .watchlists
+            .add_member(id, member, request.position)
+            .await?,
+        started,
+    ))
+}
+
+async fn update_member(
+    State(state): State<GatewayState>,
+    ids: Result<Path<(Uuid, String)>, PathRejection>,
+    request: Result<Json<MemberUpdate>, JsonRejection>,
+) -> Reply<Watchlist> {
+    let started = Instant::now();
+    let ((id, key), request) = (path(ids)?, body(request)?);
+    Ok(error::success(
+        state.watchlists.update_member(id, &key, request).await?,
+        started,
+    ))
+}
+
+async fn remove_member(
+    State(state): State<GatewayState>,
+    ids: Result<Path<(Uuid, String)>, PathRejection>,
+) -> Reply<Watchlist> {
+    let started = Instant::now();
+    let (id, key) = path(ids)?;
+    Ok(error::success(
+        state.watchlists.remove_member(id, &key).await?,
+        started,
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use database::{SqlitePool, SqlitePoolConfig};
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use shared_types::Exchange;
+    use tower::ServiceExt;
+
+    pub(crate) async fn state() -> GatewayState {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = WatchlistRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        GatewayState::new(Arc::new(WatchlistService::new(Arc::new(repository))))
+    }
+
+    async fn call(
+        state: &GatewayState,
+        method: Method,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = crate::app(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn symbol(code: &str) -> Value {
+        serde_json::to_value(Symbol::stock(code, code, Exchange::NASDAQ).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_watchlist_crud_over_http() {
+        let state = state().await;
+        let mut changes = state.watchlists.subscribe();
+
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            "/api/v1/watchlists",
+            json!({"name": "Tech"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let id = body["data"]["id"].as_str().unwrap().to_string();
+        let members = format!("/api/v1/watchlists/{}/members", id);
+
+        call(
+            &state,
+            Method::POST,
+            &members,
+            json!({"symbol": symbol("AAPL")}),
+        )
+        .await;
+        let (_, body) = call(
+            &state,
+            Method::POST,
+            &members,
+            json!({"symbol": symbol("MSFT"), "tags": ["cloud"], "position": 0}),
+        )
+        .await;
+        assert_eq!(body["data"]["members"][0]["symbol"]["code"], "MSFT");
+
+        let (status, body) = call(
+            &state,
+            Method::POST,
+            &members,
+            json!({"symbol": symbol("AAPL")}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["Validation"]["field"], "symbol");
+
+        let (_, body) = call(
+            &state,
+            Method::PATCH,
+            &format!("{}/AAPL@NASDAQ", members),
+            json!({"notes": "services", "position": 0}),
+        )
+        .await;
+        assert_eq!(body["data"]["members"][0]["notes"], "services");
+        assert_eq!(body["data"]["members"][1]["tags"], json!(["cloud"]));
+
+        // Leaving notes out keeps them; null clears them
+        let member = format!("{}/AAPL@NASDAQ", members);
+        let (_, body) = call(&state, Method::PATCH, &member, json!({"position": 0})).await;
+        assert_eq!(body["data"]["members"][0]["notes"], "services");
+        let (_, body) = call(&state, Method::PATCH, &member, json!({"notes": null})).await;
+        assert_eq!(body["data"]["members"][0]["notes"], Value::Null);
+        assert_eq!(body["data"]["members"][0]["symbol"]["code"], "AAPL");
+
+        let (status, _) = call(
+            &state,
+            Method::DELETE,
+            &format!("{}/NVDA@NASDAQ", members),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, body) = call(&state, Method::GET, "/api/v1/watchlists", Value::Null).await;
+        assert_eq!(body["data"][0]["members"].as_array().unwrap().len(), 2);
+
+        let (status, _) = call(
+            &state,
+            Method::DELETE,
+            &format!("/api/v1/watchlists/{}", id),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &state,
+            Method::GET,
+            &format!("/api/v1/watchlists/{}", id),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&state, Method::GET, "/api/v1/watchlists/nope", Value::Null).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Create, five edits and the delete were announced
+        let mut announced = 0;
+        while changes.try_recv().is_ok() {
+            announced += 1;
+        }
+        assert_eq!(announced, 7);
+    }
+}
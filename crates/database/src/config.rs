@@ -23,6 +23,12 @@ pub struct DatabaseConfigBuilder {
     chromadb: Option<ChromaDbConfig>,
 }
 
+impl Default for DatabaseConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DatabaseConfigBuilder {
     pub fn new() -> Self {
         Self {
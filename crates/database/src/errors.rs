@@ -78,20 +78,15 @@ pub enum DatabaseType {
     ChromaDB,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum ErrorSeverity {
     Info,
     Warning,
+    #[default]
     Error,
     Critical,
 }
 
-impl Default for ErrorSeverity {
-    fn default() -> Self {
-        ErrorSeverity::Error
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum QueryType {
     Select,
@@ -186,6 +181,19 @@ impl DatabaseError {
         }
     }
 
+    pub fn serialization(
+        database: DatabaseType,
+        data_type: impl Into<String>,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        DatabaseError::Serialization {
+            message: message.into(),
+            database,
+            data_type: data_type.into(),
+            context: ErrorContext::new("serialization").with_severity(ErrorSeverity::Error),
+        }
+    }
+
     pub fn with_context(
         mut self,
         key: impl Into<Cow<'static, str>>,
@@ -270,7 +278,7 @@ impl ErrorContext {
     }
 
     pub fn timestamp(&self) -> SystemTime {
-        *self.timestamp_cell.get_or_init(|| SystemTime::now())
+        *self.timestamp_cell.get_or_init(SystemTime::now)
     }
 
     pub fn add_context(
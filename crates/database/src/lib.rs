@@ -1,10 +1,15 @@
+// `DatabaseError` carries rich context on every variant, so results are large by design.
+#![allow(clippy::result_large_err)]
+
 pub mod config;
 pub mod errors;
 pub mod health;
 pub mod manager;
 pub mod migrations;
 pub mod pools;
+pub mod repositories;
 
 pub use config::*;
 pub use errors::*;
 pub use pools::*;
+pub use repositories::*;
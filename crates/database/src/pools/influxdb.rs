@@ -1 +1,151 @@
+use crate::config::{DatabaseConfig, InfluxDbConfig};
+use crate::errors::{DatabaseError, DatabaseResult, DatabaseType, QueryType};
+use influxdb::{Client, InfluxDbWriteable, Timestamp, WriteQuery};
+use rust_decimal::prelude::ToPrimitive;
+use shared_types::OHLCV;
+use std::time::Duration;
 
+/// Measurement that bars are written to
+pub const OHLCV_MEASUREMENT: &str = "ohlcv";
+
+/// InfluxDB client for time-series market data
+///
+/// Bars are written through the v1-compatible `/write` endpoint with the
+/// configured bucket as the database, tagged by symbol, exchange and timeframe.
+pub struct InfluxDbPool {
+    client: Client,
+    config: InfluxDbConfig,
+}
+
+impl InfluxDbPool {
+    pub fn new(config: InfluxDbConfig) -> DatabaseResult<Self> {
+        config.validate().map_err(|e| {
+            DatabaseError::from(e).with_context("database", DatabaseType::InfluxDB.to_string())
+        })?;
+        let client = Client::new(config.url.as_str(), config.bucket.as_str())
+            .with_token(config.token.as_str());
+        Ok(Self { client, config })
+    }
+
+    pub fn from_database_config(db_config: &DatabaseConfig) -> DatabaseResult<Self> {
+        Self::new(db_config.influxdb.clone())
+    }
+
+    pub fn config(&self) -> &InfluxDbConfig {
+        &self.config
+    }
+
+    /// Write bars as points; re-writing a bar overwrites the existing point
+    pub async fn write_bars(&self, bars: &[OHLCV]) -> DatabaseResult<usize> {
+        if bars.is_empty() {
+            return Ok(0);
+        }
+
+        let points = bars
+            .iter()
+            .map(point)
+            .collect::<DatabaseResult<Vec<WriteQuery>>>()?;
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+
+        tokio::time::timeout(timeout, self.client.query(points))
+            .await
+            .map_err(|_| DatabaseError::timeout(DatabaseType::InfluxDB, "write_bars", timeout))?
+            .map_err(|e| {
+                DatabaseError::query_failed(
+                    DatabaseType::InfluxDB,
+                    QueryType::Insert,
+                    format!("Failed to write bars: {}", e),
+                )
+                .with_context("bucket", self.config.bucket.clone())
+            })?;
+
+        Ok(bars.len())
+    }
+}
+
+fn point(bar: &OHLCV) -> DatabaseResult<WriteQuery> {
+    let nanos = bar.timestamp.timestamp_nanos_opt().ok_or_else(|| {
+        DatabaseError::serialization(
+            DatabaseType::InfluxDB,
+            "OHLCV",
+            format!(
+                "Timestamp {} is outside the nanosecond range",
+                bar.timestamp
+            ),
+        )
+    })?;
+    let field = |name: &str, value: rust_decimal::Decimal| {
+        value.to_f64().ok_or_else(|| {
+            DatabaseError::serialization(
+                DatabaseType::InfluxDB,
+                "Decimal",
+                format!("Field '{}' is not representable as f64", name),
+            )
+        })
+    };
+
+    Ok(Timestamp::Nanoseconds(nanos as u128)
+        .into_query(OHLCV_MEASUREMENT)
+        .add_tag("symbol", bar.symbol.code.clone())
+        .add_tag("exchange", bar.symbol.exchange.to_string())
+        .add_tag("timeframe", bar.timeframe.to_string())
+        .add_field("open", field("open", bar.open)?)
+        .add_field("high", field("high", bar.high)?)
+        .add_field("low", field("low", bar.low)?)
+        .add_field("close", field("close", bar.close)?)
+        .add_field("volume", field("volume", bar.volume)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use shared_types::{Exchange, Symbol, TimeFrame};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_write_bars_sends_line_protocol() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/write"))
+            .and(query_param("db", "market_data"))
+            .and(header("Authorization", "Token secret"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = InfluxDbPool::new(InfluxDbConfig {
+            url: server.uri(),
+            token: "secret".to_string(),
+            org: "trading-org".to_string(),
+            bucket: "market_data".to_string(),
+            timeout_secs: 5,
+        })
+        .unwrap();
+
+        let price = Decimal::new(16756, 2);
+        let bar = OHLCV::new(
+            Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap(),
+            TimeFrame::OneDay,
+            Utc.with_ymd_and_hms(2024, 5, 13, 0, 0, 0).unwrap(),
+            price,
+            price,
+            price,
+            price,
+            Decimal::new(2_414_859, 0),
+        )
+        .unwrap();
+
+        assert_eq!(pool.write_bars(&[bar]).await.unwrap(), 1);
+        assert_eq!(pool.write_bars(&[]).await.unwrap(), 0);
+
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(body.starts_with("ohlcv,symbol=IBM,exchange=NYSE,timeframe=1d "));
+        assert!(body.contains("close=167.56"));
+        assert!(body.ends_with(" 1715558400000000000"));
+    }
+}
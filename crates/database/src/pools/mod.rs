@@ -1,5 +1,7 @@
+pub mod influxdb;
 pub mod redis;
 pub mod sqlite;
 
+pub use influxdb::{InfluxDbPool, OHLCV_MEASUREMENT};
 pub use redis::{RedisHealthStatus, RedisMetrics, RedisPool, RedisPoolConfig};
 pub use sqlite::{HealthStatus, PoolMetrics, SqlitePool, SqlitePoolConfig};
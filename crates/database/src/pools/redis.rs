@@ -409,7 +409,7 @@ impl RedisPool {
 
     async fn acquire_connection(
         &self,
-    ) -> DatabaseResult<bb8::PooledConnection<RedisConnectionManager>> {
+    ) -> DatabaseResult<bb8::PooledConnection<'_, RedisConnectionManager>> {
         let start = Instant::now();
         self.metrics.increment_active();
 
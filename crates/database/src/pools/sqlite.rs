@@ -408,6 +408,12 @@ impl SqlitePool {
     pub fn config(&self) -> &SqlitePoolConfig {
         &self.config
     }
+
+    /// Underlying sqlx pool, for repositories that need bound parameters
+    pub fn pool(&self) -> &SqlxSqlitePool {
+        &self.pool
+    }
+
     pub fn is_closed(&self) -> bool {
         self.pool.is_closed()
     }
@@ -0,0 +1,300 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_decimal, parse_enum, parse_uuid, to_json};
+use chrono::{DateTime, Utc};
+use shared_types::{AlertEvent, AlertRule};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS alert_rules (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        symbol_code TEXT NOT NULL,
+        symbol TEXT NOT NULL,
+        timeframe TEXT NOT NULL,
+        condition TEXT NOT NULL,
+        mode TEXT NOT NULL,
+        cooldown_seconds INTEGER NOT NULL,
+        active INTEGER NOT NULL,
+        last_triggered TEXT,
+        created_at TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_alert_rules_symbol ON alert_rules (symbol_code, timeframe)",
+    "CREATE TABLE IF NOT EXISTS alert_history (
+        id TEXT PRIMARY KEY,
+        rule_id TEXT NOT NULL,
+        rule_name TEXT NOT NULL,
+        symbol_code TEXT NOT NULL,
+        timeframe TEXT NOT NULL,
+        triggered_at TEXT NOT NULL,
+        price TEXT NOT NULL,
+        value TEXT,
+        message TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_alert_history_rule ON alert_history (rule_id, triggered_at)",
+    "CREATE INDEX IF NOT EXISTS idx_alert_history_symbol
+        ON alert_history (symbol_code, triggered_at)",
+];
+
+const RULE_COLUMNS: &str = "id, name, symbol, timeframe, condition, mode, cooldown_seconds, active,
+     last_triggered, created_at";
+
+const EVENT_COLUMNS: &str =
+    "id, rule_id, rule_name, symbol_code, timeframe, triggered_at, price, value, message";
+
+/// SQLite storage for alert rules and the alerts they have fired
+pub struct AlertRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl AlertRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the rule and history tables if they do not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert a rule, or replace the stored copy with the same id
+    pub async fn save_rule(&self, rule: &AlertRule) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO alert_rules
+             (id, name, symbol_code, symbol, timeframe, condition, mode, cooldown_seconds, active,
+              last_triggered, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(rule.id.to_string())
+        .bind(&rule.name)
+        .bind(&rule.symbol.code)
+        .bind(to_json("Symbol", &rule.symbol)?)
+        .bind(rule.timeframe.to_string())
+        .bind(to_json("AlertCondition", &rule.condition)?)
+        .bind(rule.mode.to_string())
+        .bind(rule.cooldown_seconds.min(i64::MAX as u64) as i64)
+        .bind(rule.active)
+        .bind(rule.last_triggered)
+        .bind(rule.created_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_rule(&self, id: Uuid) -> DatabaseResult<Option<AlertRule>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM alert_rules WHERE id = ?",
+            RULE_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(self.pool.pool())
+        .await?;
+        row.map(|row| rule_from_row(&row)).transpose()
+    }
+
+    /// Every rule, active or not, oldest first
+    pub async fn rules(&self) -> DatabaseResult<Vec<AlertRule>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM alert_rules ORDER BY created_at",
+            RULE_COLUMNS
+        ))
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(rule_from_row).collect()
+    }
+
+    /// Rules still being evaluated, oldest first
+    pub async fn active_rules(&self) -> DatabaseResult<Vec<AlertRule>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM alert_rules WHERE active = 1 ORDER BY created_at",
+            RULE_COLUMNS
+        ))
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(rule_from_row).collect()
+    }
+
+    /// Remove a rule; its history is kept. Returns whether a rule was deleted
+    pub async fn delete_rule(&self, id: Uuid) -> DatabaseResult<bool> {
+        let result = sqlx::query("DELETE FROM alert_rules WHERE id = ?")
+            .bind(id.to_string())
+            .execute(self.pool.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn record_event(&self, event: &AlertEvent) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO alert_history
+             (id, rule_id, rule_name, symbol_code, timeframe, triggered_at, price, value, message)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(event.id.to_string())
+        .bind(event.rule_id.to_string())
+        .bind(&event.rule_name)
+        .bind(&event.symbol)
+        .bind(event.timeframe.to_string())
+        .bind(event.triggered_at)
+        .bind(event.price.to_string())
+        .bind(event.value.map(|value| value.to_string()))
+        .bind(&event.message)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Latest firings of a rule, newest first
+    pub async fn rule_history(&self, rule_id: Uuid, limit: u32) -> DatabaseResult<Vec<AlertEvent>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM alert_history WHERE rule_id = ?
+             ORDER BY triggered_at DESC LIMIT ?",
+            EVENT_COLUMNS
+        ))
+        .bind(rule_id.to_string())
+        .bind(limit as i64)
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(event_from_row).collect()
+    }
+
+    /// Alerts fired on a symbol since `since`, newest first
+    pub async fn symbol_history(
+        &self,
+        symbol_code: &str,
+        since: DateTime<Utc>,
+    ) -> DatabaseResult<Vec<AlertEvent>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM alert_history WHERE symbol_code = ? AND triggered_at >= ?
+             ORDER BY triggered_at DESC",
+            EVENT_COLUMNS
+        ))
+        .bind(symbol_code)
+        .bind(since)
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(event_from_row).collect()
+    }
+}
+
+fn rule_from_row(row: &SqliteRow) -> DatabaseResult<AlertRule> {
+    Ok(AlertRule {
+        id: parse_uuid("id", &row.try_get::<String, _>("id")?)?,
+        name: row.try_get("name")?,
+        symbol: from_json("Symbol", &row.try_get::<String, _>("symbol")?)?,
+        timeframe: parse_enum("timeframe", &row.try_get::<String, _>("timeframe")?)?,
+        condition: from_json("AlertCondition", &row.try_get::<String, _>("condition")?)?,
+        mode: parse_enum("mode", &row.try_get::<String, _>("mode")?)?,
+        cooldown_seconds: row.try_get::<i64, _>("cooldown_seconds")?.max(0) as u64,
+        active: row.try_get("active")?,
+        last_triggered: row.try_get("last_triggered")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn event_from_row(row: &SqliteRow) -> DatabaseResult<AlertEvent> {
+    Ok(AlertEvent {
+        id: parse_uuid("id", &row.try_get::<String, _>("id")?)?,
+        rule_id: parse_uuid("rule_id", &row.try_get::<String, _>("rule_id")?)?,
+        rule_name: row.try_get("rule_name")?,
+        symbol: row.try_get("symbol_code")?,
+        timeframe: parse_enum("timeframe", &row.try_get::<String, _>("timeframe")?)?,
+        triggered_at: row.try_get("triggered_at")?,
+        price: parse_decimal("price", &row.try_get::<String, _>("price")?)?,
+        value: row
+            .try_get::<Option<String>, _>("value")?
+            .map(|value| parse_decimal("value", &value))
+            .transpose()?,
+        message: row.try_get("message")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use chrono::{Duration as ChronoDuration, TimeZone};
+    use rust_decimal::Decimal;
+    use shared_types::{
+        AlertCondition, AlertMode, Comparison, Exchange, Symbol, TechnicalIndicator, TimeFrame,
+    };
+    use std::time::Duration;
+
+    async fn repository() -> AlertRepository {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = AlertRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    #[tokio::test]
+    async fn test_rules_and_history_round_trip() {
+        let repository = repository().await;
+        let mut rule = AlertRule::new(
+            "oversold",
+            Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap(),
+            TimeFrame::OneHour,
+            AlertCondition::Indicator {
+                indicator: TechnicalIndicator::RSI,
+                period: Some(14),
+                comparison: Comparison::Below,
+                threshold: Decimal::new(30, 0),
+            },
+        )
+        .with_mode(AlertMode::OneShot)
+        .with_cooldown(Duration::from_secs(600));
+        repository.save_rule(&rule).await.unwrap();
+        assert_eq!(
+            repository.get_rule(rule.id).await.unwrap(),
+            Some(rule.clone())
+        );
+
+        let fired_at = Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap();
+        rule.active = false;
+        rule.last_triggered = Some(fired_at);
+        repository.save_rule(&rule).await.unwrap();
+        assert!(repository.active_rules().await.unwrap().is_empty());
+        assert_eq!(
+            repository.rules().await.unwrap(),
+            std::slice::from_ref(&rule)
+        );
+
+        let event = AlertEvent {
+            id: Uuid::new_v4(),
+            rule_id: rule.id,
+            rule_name: rule.name.clone(),
+            symbol: "AAPL".to_string(),
+            timeframe: TimeFrame::OneHour,
+            triggered_at: fired_at,
+            price: Decimal::new(17_250, 2),
+            value: Some(Decimal::new(2_815, 2)),
+            message: "oversold: RSI(14) 28.15 is below 30".to_string(),
+        };
+        repository.record_event(&event).await.unwrap();
+        assert_eq!(
+            repository.rule_history(rule.id, 10).await.unwrap(),
+            std::slice::from_ref(&event)
+        );
+        assert_eq!(
+            repository
+                .symbol_history("AAPL", fired_at - ChronoDuration::days(1))
+                .await
+                .unwrap(),
+            [event]
+        );
+
+        assert!(repository.delete_rule(rule.id).await.unwrap());
+        assert!(!repository.delete_rule(rule.id).await.unwrap());
+        assert_eq!(repository.rule_history(rule.id, 10).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_enum, parse_uuid, to_json};
+use chrono::{DateTime, Utc};
+use shared_types::{Symbol, TimeFrame};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS backfill_jobs (
+        id TEXT PRIMARY KEY,
+        symbol_key TEXT NOT NULL,
+        symbol TEXT NOT NULL,
+        timeframe TEXT NOT NULL,
+        start_time TEXT NOT NULL,
+        end_time TEXT NOT NULL,
+        cursor TEXT NOT NULL,
+        status TEXT NOT NULL,
+        bars_written INTEGER NOT NULL,
+        last_error TEXT,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_backfill_jobs_status ON backfill_jobs (status)",
+];
+
+const COLUMNS: &str = "id, symbol, timeframe, start_time, end_time, cursor, status, bars_written,
+     last_error, created_at, updated_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackfillStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl fmt::Display for BackfillStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackfillStatus::Pending => write!(f, "pending"),
+            BackfillStatus::Running => write!(f, "running"),
+            BackfillStatus::Completed => write!(f, "completed"),
+            BackfillStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl FromStr for BackfillStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(BackfillStatus::Pending),
+            "running" => Ok(BackfillStatus::Running),
+            "completed" => Ok(BackfillStatus::Completed),
+            "failed" => Ok(BackfillStatus::Failed),
+            _ => Err(format!("Invalid backfill status: {}", s)),
+        }
+    }
+}
+
+/// A historical download and how far it has got
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackfillJob {
+    pub id: Uuid,
+    pub symbol: Symbol,
+    pub timeframe: TimeFrame,
+
+    /// Requested range, inclusive
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+
+    /// Start of the next window to fetch; everything before it has been stored
+    pub cursor: DateTime<Utc>,
+
+    pub status: BackfillStatus,
+    pub bars_written: u64,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BackfillJob {
+    pub fn new(
+        symbol: Symbol,
+        timeframe: TimeFrame,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            symbol,
+            timeframe,
+            start_time,
+            end_time,
+            cursor: start_time,
+            status: BackfillStatus::Pending,
+            bars_written: 0,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// SQLite checkpoints for backfill jobs so they can resume after a restart
+pub struct BackfillRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl BackfillRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the job table if it does not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn create_job(&self, job: &BackfillJob) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO backfill_jobs
+             (id, symbol_key, symbol, timeframe, start_time, end_time, cursor, status,
+              bars_written, last_error, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(job.id.to_string())
+        .bind(job.symbol.full_identifier())
+        .bind(to_json("Symbol", &job.symbol)?)
+        .bind(job.timeframe.to_string())
+        .bind(job.start_time)
+        .bind(job.end_time)
+        .bind(job.cursor)
+        .bind(job.status.to_string())
+        .bind(job.bars_written as i64)
+        .bind(&job.last_error)
+        .bind(job.created_at)
+        .bind(job.updated_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Record that everything before `cursor` is stored
+    pub async fn save_checkpoint(
+        &self,
+        id: Uuid,
+        cursor: DateTime<Utc>,
+        bars_written: u64,
+    ) -> DatabaseResult<()> {
+        sqlx::query(
+            "UPDATE backfill_jobs
+             SET cursor = ?, bars_written = ?, status = ?, last_error = NULL, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(cursor)
+        .bind(bars_written as i64)
+        .bind(BackfillStatus::Running.to_string())
+        .bind(Utc::now())
+        .bind(id.to_string())
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_status(
+        &self,
+        id: Uuid,
+        status: BackfillStatus,
+        last_error: Option<&str>,
+    ) -> DatabaseResult<()> {
+        sqlx::query(
+            "UPDATE backfill_jobs SET status = ?, last_error = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(status.to_string())
+        .bind(last_error)
+        .bind(Utc::now())
+        .bind(id.to_string())
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_job(&self, id: Uuid) -> DatabaseResult<Option<BackfillJob>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM backfill_jobs WHERE id = ?",
+            COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(self.pool.pool())
+        .await?;
+        row.map(|row| job_from_row(&row)).transpose()
+    }
+
+    /// Jobs that have not completed, oldest first
+    pub async fn incomplete_jobs(&self) -> DatabaseResult<Vec<BackfillJob>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM backfill_jobs WHERE status != ? ORDER BY created_at",
+            COLUMNS
+        ))
+        .bind(BackfillStatus::Completed.to_string())
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(job_from_row).collect()
+    }
+}
+
+fn job_from_row(row: &SqliteRow) -> DatabaseResult<BackfillJob> {
+    Ok(BackfillJob {
+        id: parse_uuid("id", &row.try_get::<String, _>("id")?)?,
+        symbol: from_json("Symbol", &row.try_get::<String, _>("symbol")?)?,
+        timeframe: parse_enum("timeframe", &row.try_get::<String, _>("timeframe")?)?,
+        start_time: row.try_get("start_time")?,
+        end_time: row.try_get("end_time")?,
+        cursor: row.try_get("cursor")?,
+        status: parse_enum("status", &row.try_get::<String, _>("status")?)?,
+        bars_written: row.try_get::<i64, _>("bars_written")? as u64,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use chrono::TimeZone;
+    use shared_types::Exchange;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_checkpoints_survive_reload() {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = BackfillRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+
+        let job = BackfillJob::new(
+            Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap(),
+            TimeFrame::OneHour,
+            Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        );
+        repository.create_job(&job).await.unwrap();
+
+        let cursor = Utc.with_ymd_and_hms(2021, 6, 1, 0, 0, 0).unwrap();
+        repository
+            .save_checkpoint(job.id, cursor, 5_000)
+            .await
+            .unwrap();
+        repository
+            .set_status(job.id, BackfillStatus::Failed, Some("provider down"))
+            .await
+            .unwrap();
+
+        let loaded = repository.get_job(job.id).await.unwrap().unwrap();
+        assert_eq!(loaded.cursor, cursor);
+        assert_eq!(loaded.bars_written, 5_000);
+        assert_eq!(loaded.status, BackfillStatus::Failed);
+        assert_eq!(loaded.last_error.as_deref(), Some("provider down"));
+        assert_eq!(loaded.timeframe, TimeFrame::OneHour);
+        assert_eq!(repository.incomplete_jobs().await.unwrap().len(), 1);
+
+        repository
+            .set_status(job.id, BackfillStatus::Completed, None)
+            .await
+            .unwrap();
+        assert!(repository.incomplete_jobs().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, to_json};
+use chrono::NaiveDate;
+use shared_types::{CorporateAction, Symbol};
+use sqlx::Row;
+use std::sync::Arc;
+
+const SCHEMA: &[&str] = &["CREATE TABLE IF NOT EXISTS corporate_actions (
+        symbol_key TEXT NOT NULL,
+        action_type TEXT NOT NULL,
+        ex_date TEXT NOT NULL,
+        action TEXT NOT NULL,
+        PRIMARY KEY (symbol_key, action_type, ex_date)
+    )"];
+
+fn action_type(action: &CorporateAction) -> &'static str {
+    match action {
+        CorporateAction::Split(_) => "split",
+        CorporateAction::Dividend(_) => "dividend",
+    }
+}
+
+/// SQLite store of splits and dividends, at most one of each kind per symbol and ex-date
+pub struct CorporateActionRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl CorporateActionRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the corporate action table if it does not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert actions, replacing any stored for the same symbol, kind and ex-date
+    pub async fn upsert_actions(&self, actions: &[CorporateAction]) -> DatabaseResult<usize> {
+        let mut transaction = self.pool.begin_transaction().await?;
+        for action in actions {
+            sqlx::query(
+                "INSERT INTO corporate_actions (symbol_key, action_type, ex_date, action)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(symbol_key, action_type, ex_date) DO UPDATE SET
+                    action = excluded.action",
+            )
+            .bind(action.symbol().full_identifier())
+            .bind(action_type(action))
+            .bind(action.ex_date())
+            .bind(to_json("CorporateAction", action)?)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(actions.len())
+    }
+
+    /// Actions for a symbol with ex-dates in `[from, to]`, oldest first
+    pub async fn actions_for(
+        &self,
+        symbol: &Symbol,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> DatabaseResult<Vec<CorporateAction>> {
+        let rows = sqlx::query(
+            "SELECT action FROM corporate_actions
+             WHERE symbol_key = ? AND ex_date >= ? AND ex_date <= ?
+             ORDER BY ex_date, action_type",
+        )
+        .bind(symbol.full_identifier())
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.iter()
+            .map(|row| from_json("CorporateAction", &row.try_get::<String, _>("action")?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use rust_decimal::Decimal;
+    use shared_types::{Dividend, Exchange, Split};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_upserts_and_filters_by_ex_date() {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository =
+            CorporateActionRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+
+        let aapl = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let split: CorporateAction =
+            Split::new(aapl.clone(), date(2020, 8, 31), Decimal::new(4, 0)).into();
+        let dividend = Dividend::new(aapl.clone(), date(2020, 8, 7), Decimal::new(82, 2));
+        repository
+            .upsert_actions(&[split.clone(), dividend.clone().into()])
+            .await
+            .unwrap();
+
+        // A corrected dividend replaces the stored one
+        let corrected: CorporateAction = dividend.with_pay_date(date(2020, 8, 13)).into();
+        repository
+            .upsert_actions(std::slice::from_ref(&corrected))
+            .await
+            .unwrap();
+
+        let actions = repository
+            .actions_for(&aapl, date(2020, 1, 1), date(2020, 12, 31))
+            .await
+            .unwrap();
+        assert_eq!(actions, vec![corrected, split.clone()]);
+
+        let later = repository
+            .actions_for(&aapl, date(2020, 8, 8), date(2024, 1, 1))
+            .await
+            .unwrap();
+        assert_eq!(later, vec![split]);
+    }
+}
@@ -0,0 +1,79 @@
+pub mod alerts;
+pub mod backfill;
+pub mod corporate_actions;
+pub mod notifications;
+pub mod ohlcv;
+pub mod portfolio;
+pub mod screens;
+pub mod symbols;
+pub mod watchlists;
+
+pub use alerts::AlertRepository;
+pub use backfill::{BackfillJob, BackfillRepository, BackfillStatus};
+pub use corporate_actions::CorporateActionRepository;
+pub use notifications::NotificationRepository;
+pub use ohlcv::OhlcvRepository;
+pub use portfolio::{PortfolioRecord, PortfolioRepository};
+pub use screens::ScreenRepository;
+pub use symbols::{
+    ListingColumns, ListingImport, ListingOptions, ListingRowError, SymbolRepository,
+};
+pub use watchlists::WatchlistRepository;
+
+use crate::errors::{DatabaseError, DatabaseResult, DatabaseType};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+// Decimals and nested types are stored as TEXT so no precision is lost in SQLite.
+
+pub(crate) fn parse_decimal(column: &str, value: &str) -> DatabaseResult<Decimal> {
+    Decimal::from_str(value).map_err(|e| {
+        DatabaseError::serialization(
+            DatabaseType::SQLite,
+            "Decimal",
+            format!("Invalid decimal in column '{}': {}", column, e),
+        )
+    })
+}
+
+pub(crate) fn to_json<T: Serialize>(data_type: &str, value: &T) -> DatabaseResult<String> {
+    serde_json::to_string(value).map_err(|e| {
+        DatabaseError::serialization(
+            DatabaseType::SQLite,
+            data_type.to_string(),
+            format!("Failed to serialize {}: {}", data_type, e),
+        )
+    })
+}
+
+pub(crate) fn from_json<T: DeserializeOwned>(data_type: &str, value: &str) -> DatabaseResult<T> {
+    serde_json::from_str(value).map_err(|e| {
+        DatabaseError::serialization(
+            DatabaseType::SQLite,
+            data_type.to_string(),
+            format!("Failed to deserialize {}: {}", data_type, e),
+        )
+    })
+}
+
+pub(crate) fn parse_enum<T>(column: &str, value: &str) -> DatabaseResult<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    T::from_str(value).map_err(|e| {
+        DatabaseError::serialization(DatabaseType::SQLite, column.to_string(), e.to_string())
+    })
+}
+
+pub(crate) fn parse_uuid(column: &str, value: &str) -> DatabaseResult<Uuid> {
+    Uuid::parse_str(value).map_err(|e| {
+        DatabaseError::serialization(
+            DatabaseType::SQLite,
+            "Uuid",
+            format!("Invalid UUID in column '{}': {}", column, e),
+        )
+    })
+}
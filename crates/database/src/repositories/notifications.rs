@@ -0,0 +1,244 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_uuid, to_json};
+use chrono::Utc;
+use shared_types::{DeadLetter, DeliveryPreferences};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS notification_preferences (
+        user_id TEXT PRIMARY KEY,
+        channels TEXT NOT NULL,
+        symbols TEXT NOT NULL,
+        enabled INTEGER NOT NULL,
+        updated_at TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS notification_dead_letters (
+        id TEXT PRIMARY KEY,
+        notification_id TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        channel TEXT NOT NULL,
+        notification TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        error TEXT NOT NULL,
+        failed_at TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_notification_dead_letters_failed
+        ON notification_dead_letters (failed_at)",
+];
+
+const DEAD_LETTER_COLUMNS: &str = "id, notification, attempts, error, failed_at";
+
+/// SQLite storage for per-user delivery preferences and undeliverable notifications
+pub struct NotificationRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl NotificationRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the preference and dead-letter tables if they do not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert or replace a user's preferences
+    pub async fn save_preferences(&self, preferences: &DeliveryPreferences) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO notification_preferences
+             (user_id, channels, symbols, enabled, updated_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&preferences.user_id)
+        .bind(to_json("NotificationChannel", &preferences.channels)?)
+        .bind(to_json("symbols", &preferences.symbols)?)
+        .bind(preferences.enabled)
+        .bind(Utc::now())
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn preferences(&self, user_id: &str) -> DatabaseResult<Option<DeliveryPreferences>> {
+        let row = sqlx::query(
+            "SELECT user_id, channels, symbols, enabled FROM notification_preferences
+             WHERE user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_optional(self.pool.pool())
+        .await?;
+        row.map(|row| preferences_from_row(&row)).transpose()
+    }
+
+    /// Preferences of every user with notifications enabled
+    pub async fn enabled_preferences(&self) -> DatabaseResult<Vec<DeliveryPreferences>> {
+        let rows = sqlx::query(
+            "SELECT user_id, channels, symbols, enabled FROM notification_preferences
+             WHERE enabled = 1 ORDER BY user_id",
+        )
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(preferences_from_row).collect()
+    }
+
+    pub async fn delete_preferences(&self, user_id: &str) -> DatabaseResult<bool> {
+        let result = sqlx::query("DELETE FROM notification_preferences WHERE user_id = ?")
+            .bind(user_id)
+            .execute(self.pool.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn push_dead_letter(&self, letter: &DeadLetter) -> DatabaseResult<()> {
+        let notification = &letter.notification;
+        sqlx::query(
+            "INSERT INTO notification_dead_letters
+             (id, notification_id, user_id, channel, notification, attempts, error, failed_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(letter.id.to_string())
+        .bind(notification.id.to_string())
+        .bind(&notification.user_id)
+        .bind(notification.channel.kind().to_string())
+        .bind(to_json("Notification", notification)?)
+        .bind(letter.attempts as i64)
+        .bind(&letter.error)
+        .bind(letter.failed_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn dead_letter(&self, id: Uuid) -> DatabaseResult<Option<DeadLetter>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM notification_dead_letters WHERE id = ?",
+            DEAD_LETTER_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(self.pool.pool())
+        .await?;
+        row.map(|row| dead_letter_from_row(&row)).transpose()
+    }
+
+    /// Oldest undelivered notifications first
+    pub async fn dead_letters(&self, limit: u32) -> DatabaseResult<Vec<DeadLetter>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM notification_dead_letters ORDER BY failed_at LIMIT ?",
+            DEAD_LETTER_COLUMNS
+        ))
+        .bind(limit as i64)
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(dead_letter_from_row).collect()
+    }
+
+    /// Drop a dead letter, e.g. once it has been redelivered
+    pub async fn remove_dead_letter(&self, id: Uuid) -> DatabaseResult<bool> {
+        let result = sqlx::query("DELETE FROM notification_dead_letters WHERE id = ?")
+            .bind(id.to_string())
+            .execute(self.pool.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn preferences_from_row(row: &SqliteRow) -> DatabaseResult<DeliveryPreferences> {
+    Ok(DeliveryPreferences {
+        user_id: row.try_get("user_id")?,
+        channels: from_json(
+            "NotificationChannel",
+            &row.try_get::<String, _>("channels")?,
+        )?,
+        symbols: from_json("symbols", &row.try_get::<String, _>("symbols")?)?,
+        enabled: row.try_get("enabled")?,
+    })
+}
+
+fn dead_letter_from_row(row: &SqliteRow) -> DatabaseResult<DeadLetter> {
+    Ok(DeadLetter {
+        id: parse_uuid("id", &row.try_get::<String, _>("id")?)?,
+        notification: from_json("Notification", &row.try_get::<String, _>("notification")?)?,
+        attempts: row.try_get::<i64, _>("attempts")?.max(0) as u32,
+        error: row.try_get("error")?,
+        failed_at: row.try_get("failed_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use chrono::TimeZone;
+    use shared_types::{Notification, NotificationChannel};
+    use std::time::Duration;
+
+    async fn repository() -> NotificationRepository {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository =
+            NotificationRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    #[tokio::test]
+    async fn test_preferences_and_dead_letters_round_trip() {
+        let repository = repository().await;
+        let chat = NotificationChannel::Chat {
+            url: "https://hooks.example.com/T000/B000".to_string(),
+        };
+        let mut preferences = DeliveryPreferences::new("trader-1")
+            .with_channel(chat.clone())
+            .with_symbols(["AAPL", "MSFT"]);
+        repository.save_preferences(&preferences).await.unwrap();
+        assert_eq!(
+            repository.preferences("trader-1").await.unwrap(),
+            Some(preferences.clone())
+        );
+
+        preferences.enabled = false;
+        repository.save_preferences(&preferences).await.unwrap();
+        assert!(repository.enabled_preferences().await.unwrap().is_empty());
+        assert!(repository.delete_preferences("trader-1").await.unwrap());
+        assert_eq!(repository.preferences("trader-1").await.unwrap(), None);
+
+        let letter = DeadLetter {
+            id: Uuid::new_v4(),
+            notification: Notification {
+                id: Uuid::new_v4(),
+                user_id: "trader-1".to_string(),
+                channel: chat,
+                subject: "AAPL alert: breakout".to_string(),
+                body: "breakout: close 106 crossed above 105".to_string(),
+                payload: serde_json::json!({"category": "rule"}),
+                created_at: Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap(),
+            },
+            attempts: 4,
+            error: "HTTP 503: unavailable".to_string(),
+            failed_at: Utc.with_ymd_and_hms(2024, 3, 4, 15, 1, 0).unwrap(),
+        };
+        repository.push_dead_letter(&letter).await.unwrap();
+        assert_eq!(
+            repository.dead_letters(10).await.unwrap(),
+            std::slice::from_ref(&letter)
+        );
+        assert_eq!(
+            repository.dead_letter(letter.id).await.unwrap(),
+            Some(letter.clone())
+        );
+        assert!(repository.remove_dead_letter(letter.id).await.unwrap());
+        assert!(repository.dead_letters(10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_decimal, to_json};
+use chrono::{DateTime, Utc};
+use shared_types::{Symbol, TimeFrame, OHLCV};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::sync::Arc;
+
+const SCHEMA: &[&str] = &["CREATE TABLE IF NOT EXISTS ohlcv_bars (
+        symbol_key TEXT NOT NULL,
+        timeframe TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        open TEXT NOT NULL,
+        high TEXT NOT NULL,
+        low TEXT NOT NULL,
+        close TEXT NOT NULL,
+        volume TEXT NOT NULL,
+        metadata TEXT NOT NULL,
+        PRIMARY KEY (symbol_key, timeframe, timestamp)
+    )"];
+
+/// SQLite store of historical bars, keyed by symbol, timeframe and bar start
+pub struct OhlcvRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl OhlcvRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the bar table if it does not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert bars, replacing any already stored for the same interval
+    pub async fn upsert_bars(&self, bars: &[OHLCV]) -> DatabaseResult<usize> {
+        let mut transaction = self.pool.begin_transaction().await?;
+        for bar in bars {
+            sqlx::query(
+                "INSERT INTO ohlcv_bars
+                 (symbol_key, timeframe, timestamp, open, high, low, close, volume, metadata)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(symbol_key, timeframe, timestamp) DO UPDATE SET
+                    open = excluded.open,
+                    high = excluded.high,
+                    low = excluded.low,
+                    close = excluded.close,
+                    volume = excluded.volume,
+                    metadata = excluded.metadata",
+            )
+            .bind(bar.symbol.full_identifier())
+            .bind(bar.timeframe.to_string())
+            .bind(bar.timestamp)
+            .bind(bar.open.to_string())
+            .bind(bar.high.to_string())
+            .bind(bar.low.to_string())
+            .bind(bar.close.to_string())
+            .bind(bar.volume.to_string())
+            .bind(to_json("OHLCV metadata", &bar.metadata)?)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(bars.len())
+    }
+
+    /// Bars starting within `[from, to]`, oldest first
+    pub async fn load_bars(
+        &self,
+        symbol: &Symbol,
+        timeframe: &TimeFrame,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> DatabaseResult<Vec<OHLCV>> {
+        let rows = sqlx::query(
+            "SELECT timestamp, open, high, low, close, volume, metadata
+             FROM ohlcv_bars
+             WHERE symbol_key = ? AND timeframe = ? AND timestamp >= ? AND timestamp <= ?
+             ORDER BY timestamp",
+        )
+        .bind(symbol.full_identifier())
+        .bind(timeframe.to_string())
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.iter()
+            .map(|row| bar_from_row(symbol, timeframe, row))
+            .collect()
+    }
+
+    /// The most recent `limit` bars, oldest first
+    pub async fn latest_bars(
+        &self,
+        symbol: &Symbol,
+        timeframe: &TimeFrame,
+        limit: u32,
+    ) -> DatabaseResult<Vec<OHLCV>> {
+        let rows = sqlx::query(
+            "SELECT timestamp, open, high, low, close, volume, metadata
+             FROM ohlcv_bars
+             WHERE symbol_key = ? AND timeframe = ?
+             ORDER BY timestamp DESC
+             LIMIT ?",
+        )
+        .bind(symbol.full_identifier())
+        .bind(timeframe.to_string())
+        .bind(limit as i64)
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.iter()
+            .rev()
+            .map(|row| bar_from_row(symbol, timeframe, row))
+            .collect()
+    }
+
+    /// Start times of stored bars within `[from, to]`, oldest first
+    pub async fn bar_timestamps(
+        &self,
+        symbol: &Symbol,
+        timeframe: &TimeFrame,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> DatabaseResult<Vec<DateTime<Utc>>> {
+        let rows = sqlx::query(
+            "SELECT timestamp FROM ohlcv_bars
+             WHERE symbol_key = ? AND timeframe = ? AND timestamp >= ? AND timestamp <= ?
+             ORDER BY timestamp",
+        )
+        .bind(symbol.full_identifier())
+        .bind(timeframe.to_string())
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.into_iter()
+            .map(|row| Ok(row.try_get("timestamp")?))
+            .collect()
+    }
+}
+
+fn bar_from_row(symbol: &Symbol, timeframe: &TimeFrame, row: &SqliteRow) -> DatabaseResult<OHLCV> {
+    Ok(OHLCV {
+        symbol: symbol.clone(),
+        timeframe: timeframe.clone(),
+        timestamp: row.try_get("timestamp")?,
+        open: parse_decimal("open", &row.try_get::<String, _>("open")?)?,
+        high: parse_decimal("high", &row.try_get::<String, _>("high")?)?,
+        low: parse_decimal("low", &row.try_get::<String, _>("low")?)?,
+        close: parse_decimal("close", &row.try_get::<String, _>("close")?)?,
+        volume: parse_decimal("volume", &row.try_get::<String, _>("volume")?)?,
+        metadata: from_json("OHLCV metadata", &row.try_get::<String, _>("metadata")?)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+    use shared_types::Exchange;
+    use std::time::Duration;
+
+    async fn create_test_repository() -> OhlcvRepository {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = OhlcvRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    fn bar(day: u32, close: i64) -> OHLCV {
+        let close = Decimal::new(close, 0);
+        OHLCV::new(
+            Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap(),
+            TimeFrame::OneDay,
+            Utc.with_ymd_and_hms(2024, 5, day, 0, 0, 0).unwrap(),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1_000, 0),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_and_loads_in_order() {
+        let repository = create_test_repository().await;
+        let symbol = bar(13, 167).symbol;
+
+        repository
+            .upsert_bars(&[bar(14, 170), bar(13, 167)])
+            .await
+            .unwrap();
+        // A revised bar for the same interval replaces the stored one
+        let mut revised = bar(14, 171);
+        revised.add_metadata("provider", serde_json::json!("alpha_vantage"));
+        repository
+            .upsert_bars(std::slice::from_ref(&revised))
+            .await
+            .unwrap();
+
+        let loaded = repository
+            .load_bars(
+                &symbol,
+                &TimeFrame::OneDay,
+                Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 5, 31, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(loaded, vec![bar(13, 167), revised.clone()]);
+
+        repository.upsert_bars(&[bar(10, 160)]).await.unwrap();
+        let latest = repository
+            .latest_bars(&symbol, &TimeFrame::OneDay, 2)
+            .await
+            .unwrap();
+        assert_eq!(latest, vec![bar(13, 167), revised.clone()]);
+
+        let timestamps = repository
+            .bar_timestamps(
+                &symbol,
+                &TimeFrame::OneDay,
+                Utc.with_ymd_and_hms(2024, 5, 14, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 5, 31, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(timestamps, vec![revised.timestamp]);
+
+        let hourly = repository
+            .bar_timestamps(
+                &symbol,
+                &TimeFrame::OneHour,
+                Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 5, 31, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(hourly.is_empty());
+    }
+}
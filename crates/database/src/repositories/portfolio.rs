@@ -0,0 +1,540 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_decimal, parse_enum, parse_uuid, to_json};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use shared_types::{CashFlow, CostBasisMethod, Fill, Portfolio, PortfolioSnapshot, RiskLimits};
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS portfolios (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        cost_basis_method TEXT NOT NULL,
+        starting_cash TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS portfolio_fills (
+        fill_id TEXT PRIMARY KEY,
+        portfolio_id TEXT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+        symbol_key TEXT NOT NULL,
+        symbol TEXT NOT NULL,
+        side TEXT NOT NULL,
+        quantity TEXT NOT NULL,
+        price TEXT NOT NULL,
+        fees TEXT NOT NULL,
+        order_id TEXT,
+        executed_at TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_portfolio_fills_portfolio
+        ON portfolio_fills (portfolio_id, executed_at)",
+    "CREATE TABLE IF NOT EXISTS portfolio_cash_flows (
+        flow_id TEXT PRIMARY KEY,
+        portfolio_id TEXT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+        amount TEXT NOT NULL,
+        occurred_at TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_portfolio_cash_flows_portfolio
+        ON portfolio_cash_flows (portfolio_id, occurred_at)",
+    "CREATE TABLE IF NOT EXISTS portfolio_state (
+        portfolio_id TEXT PRIMARY KEY REFERENCES portfolios(id) ON DELETE CASCADE,
+        snapshot TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS portfolio_snapshots (
+        portfolio_id TEXT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+        as_of_date TEXT NOT NULL,
+        as_of TEXT NOT NULL,
+        snapshot TEXT NOT NULL,
+        PRIMARY KEY (portfolio_id, as_of_date)
+    )",
+    "CREATE TABLE IF NOT EXISTS portfolio_risk_limits (
+        portfolio_id TEXT PRIMARY KEY REFERENCES portfolios(id) ON DELETE CASCADE,
+        limits TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )",
+];
+
+/// Portfolio header as stored in SQLite
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub cost_basis_method: CostBasisMethod,
+    pub starting_cash: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+/// SQLite persistence for portfolios, their trade ledger and booked state
+pub struct PortfolioRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl PortfolioRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the portfolio tables if they do not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn create_portfolio(&self, record: &PortfolioRecord) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO portfolios (id, name, cost_basis_method, starting_cash, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(record.id.to_string())
+        .bind(&record.name)
+        .bind(record.cost_basis_method.to_string())
+        .bind(record.starting_cash.to_string())
+        .bind(record.created_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_portfolio(&self, id: Uuid) -> DatabaseResult<Option<PortfolioRecord>> {
+        let row = sqlx::query(
+            "SELECT id, name, cost_basis_method, starting_cash, created_at
+             FROM portfolios WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(self.pool.pool())
+        .await?;
+
+        row.map(|row| {
+            Ok(PortfolioRecord {
+                id,
+                name: row.try_get("name")?,
+                cost_basis_method: parse_enum(
+                    "cost_basis_method",
+                    &row.try_get::<String, _>("cost_basis_method")?,
+                )?,
+                starting_cash: parse_decimal(
+                    "starting_cash",
+                    &row.try_get::<String, _>("starting_cash")?,
+                )?,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Append a fill to the trade ledger
+    pub async fn record_fill(&self, fill: &Fill) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO portfolio_fills
+             (fill_id, portfolio_id, symbol_key, symbol, side, quantity, price, fees, order_id, executed_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(fill.fill_id.to_string())
+        .bind(fill.portfolio_id.to_string())
+        .bind(fill.symbol.full_identifier())
+        .bind(to_json("Symbol", &fill.symbol)?)
+        .bind(fill.side.to_string())
+        .bind(fill.quantity.to_string())
+        .bind(fill.price.to_string())
+        .bind(fill.fees.to_string())
+        .bind(&fill.order_id)
+        .bind(fill.executed_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Load the full trade ledger for a portfolio in execution order
+    pub async fn load_fills(&self, portfolio_id: Uuid) -> DatabaseResult<Vec<Fill>> {
+        let rows = sqlx::query(
+            "SELECT fill_id, symbol, side, quantity, price, fees, order_id, executed_at
+             FROM portfolio_fills WHERE portfolio_id = ?
+             ORDER BY executed_at, rowid",
+        )
+        .bind(portfolio_id.to_string())
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(Fill {
+                    fill_id: parse_uuid("fill_id", &row.try_get::<String, _>("fill_id")?)?,
+                    portfolio_id,
+                    symbol: from_json("Symbol", &row.try_get::<String, _>("symbol")?)?,
+                    side: parse_enum("side", &row.try_get::<String, _>("side")?)?,
+                    quantity: parse_decimal("quantity", &row.try_get::<String, _>("quantity")?)?,
+                    price: parse_decimal("price", &row.try_get::<String, _>("price")?)?,
+                    fees: parse_decimal("fees", &row.try_get::<String, _>("fees")?)?,
+                    order_id: row.try_get("order_id")?,
+                    executed_at: row.try_get("executed_at")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Append a deposit or withdrawal to the cash ledger
+    pub async fn record_cash_flow(&self, flow: &CashFlow) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO portfolio_cash_flows (flow_id, portfolio_id, amount, occurred_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(flow.flow_id.to_string())
+        .bind(flow.portfolio_id.to_string())
+        .bind(flow.amount.to_string())
+        .bind(flow.occurred_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Load every deposit and withdrawal for a portfolio, oldest first
+    pub async fn load_cash_flows(&self, portfolio_id: Uuid) -> DatabaseResult<Vec<CashFlow>> {
+        let rows = sqlx::query(
+            "SELECT flow_id, amount, occurred_at
+             FROM portfolio_cash_flows WHERE portfolio_id = ?
+             ORDER BY occurred_at, rowid",
+        )
+        .bind(portfolio_id.to_string())
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(CashFlow {
+                    flow_id: parse_uuid("flow_id", &row.try_get::<String, _>("flow_id")?)?,
+                    portfolio_id,
+                    amount: parse_decimal("amount", &row.try_get::<String, _>("amount")?)?,
+                    occurred_at: row.try_get("occurred_at")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Store the latest booked state of a portfolio
+    pub async fn save_snapshot(
+        &self,
+        portfolio_id: Uuid,
+        portfolio: &Portfolio,
+    ) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO portfolio_state (portfolio_id, snapshot, updated_at)
+             VALUES (?, ?, ?)
+             ON CONFLICT(portfolio_id) DO UPDATE SET
+                snapshot = excluded.snapshot,
+                updated_at = excluded.updated_at",
+        )
+        .bind(portfolio_id.to_string())
+        .bind(to_json("Portfolio", portfolio)?)
+        .bind(portfolio.last_updated)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn load_snapshot(&self, portfolio_id: Uuid) -> DatabaseResult<Option<Portfolio>> {
+        let row = sqlx::query("SELECT snapshot FROM portfolio_state WHERE portfolio_id = ?")
+            .bind(portfolio_id.to_string())
+            .fetch_optional(self.pool.pool())
+            .await?;
+
+        row.map(|row| from_json("Portfolio", &row.try_get::<String, _>("snapshot")?))
+            .transpose()
+    }
+
+    /// Store the end-of-day valuation, replacing any earlier snapshot for the same day
+    pub async fn save_daily_snapshot(&self, snapshot: &PortfolioSnapshot) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO portfolio_snapshots (portfolio_id, as_of_date, as_of, snapshot)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(portfolio_id, as_of_date) DO UPDATE SET
+                as_of = excluded.as_of,
+                snapshot = excluded.snapshot",
+        )
+        .bind(snapshot.portfolio_id.to_string())
+        .bind(snapshot.as_of.date_naive().to_string())
+        .bind(snapshot.as_of)
+        .bind(to_json("PortfolioSnapshot", snapshot)?)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Load daily snapshots taken between `from` and `to` (inclusive), oldest first
+    pub async fn load_snapshots(
+        &self,
+        portfolio_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> DatabaseResult<Vec<PortfolioSnapshot>> {
+        let rows = sqlx::query(
+            "SELECT snapshot FROM portfolio_snapshots
+             WHERE portfolio_id = ? AND as_of >= ? AND as_of <= ?
+             ORDER BY as_of",
+        )
+        .bind(portfolio_id.to_string())
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        rows.into_iter()
+            .map(|row| from_json("PortfolioSnapshot", &row.try_get::<String, _>("snapshot")?))
+            .collect()
+    }
+
+    /// Store the pre-trade risk limits configured for a portfolio
+    pub async fn save_risk_limits(
+        &self,
+        portfolio_id: Uuid,
+        limits: &RiskLimits,
+    ) -> DatabaseResult<()> {
+        sqlx::query(
+            "INSERT INTO portfolio_risk_limits (portfolio_id, limits, updated_at)
+             VALUES (?, ?, ?)
+             ON CONFLICT(portfolio_id) DO UPDATE SET
+                limits = excluded.limits,
+                updated_at = excluded.updated_at",
+        )
+        .bind(portfolio_id.to_string())
+        .bind(to_json("RiskLimits", limits)?)
+        .bind(Utc::now())
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn load_risk_limits(&self, portfolio_id: Uuid) -> DatabaseResult<Option<RiskLimits>> {
+        let row = sqlx::query("SELECT limits FROM portfolio_risk_limits WHERE portfolio_id = ?")
+            .bind(portfolio_id.to_string())
+            .fetch_optional(self.pool.pool())
+            .await?;
+
+        row.map(|row| from_json("RiskLimits", &row.try_get::<String, _>("limits")?))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use chrono::TimeZone;
+    use shared_types::{AssetClass, Exchange, Symbol, TradeSide};
+    use std::time::Duration;
+
+    async fn create_test_repository() -> PortfolioRepository {
+        // A single connection keeps every query on the same in-memory database
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = PortfolioRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    fn create_test_record() -> PortfolioRecord {
+        PortfolioRecord {
+            id: Uuid::new_v4(),
+            name: "Growth".to_string(),
+            cost_basis_method: CostBasisMethod::Lifo,
+            starting_cash: Decimal::new(1_000_000, 2),
+            created_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_portfolio_round_trip() {
+        let repository = create_test_repository().await;
+        let record = create_test_record();
+
+        repository.create_portfolio(&record).await.unwrap();
+
+        let loaded = repository.get_portfolio(record.id).await.unwrap();
+        assert_eq!(loaded, Some(record));
+        assert!(repository
+            .get_portfolio(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fill_ledger_round_trip() {
+        let repository = create_test_repository().await;
+        let record = create_test_record();
+        repository.create_portfolio(&record).await.unwrap();
+
+        let symbol = Symbol::stock("MSFT", "Microsoft", Exchange::NASDAQ).unwrap();
+        let later = Fill::new(
+            record.id,
+            symbol.clone(),
+            TradeSide::Sell,
+            Decimal::new(5, 0),
+            Decimal::new(41025, 2),
+            Utc.with_ymd_and_hms(2024, 1, 3, 15, 0, 0).unwrap(),
+        );
+        let earlier = Fill::new(
+            record.id,
+            symbol,
+            TradeSide::Buy,
+            Decimal::new(10, 0),
+            Decimal::new(40000, 2),
+            Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap(),
+        )
+        .with_fees(Decimal::new(125, 2))
+        .with_order_id("ord-1");
+
+        repository.record_fill(&later).await.unwrap();
+        repository.record_fill(&earlier).await.unwrap();
+
+        let fills = repository.load_fills(record.id).await.unwrap();
+        assert_eq!(fills, vec![earlier.clone(), later]);
+
+        // Fill IDs are unique in the ledger
+        assert!(repository.record_fill(&earlier).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cash_flow_ledger_round_trip() {
+        let repository = create_test_repository().await;
+        let record = create_test_record();
+        repository.create_portfolio(&record).await.unwrap();
+
+        let withdrawal = CashFlow::new(
+            record.id,
+            Decimal::new(-2_500, 0),
+            Utc.with_ymd_and_hms(2024, 2, 1, 9, 0, 0).unwrap(),
+        );
+        let deposit = CashFlow::new(
+            record.id,
+            Decimal::new(10_000, 2),
+            Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap(),
+        );
+        repository.record_cash_flow(&withdrawal).await.unwrap();
+        repository.record_cash_flow(&deposit).await.unwrap();
+
+        let flows = repository.load_cash_flows(record.id).await.unwrap();
+        assert_eq!(flows, vec![deposit.clone(), withdrawal]);
+        assert!(repository.record_cash_flow(&deposit).await.is_err());
+        assert!(repository
+            .load_cash_flows(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_upsert() {
+        let repository = create_test_repository().await;
+        let record = create_test_record();
+        repository.create_portfolio(&record).await.unwrap();
+
+        let mut portfolio = Portfolio {
+            name: record.name.clone(),
+            base_currency: "USD".to_string(),
+            positions: Vec::new(),
+            cash_balance: record.starting_cash,
+            total_value: record.starting_cash,
+            total_pnl: Decimal::ZERO,
+            last_updated: Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap(),
+        };
+        repository
+            .save_snapshot(record.id, &portfolio)
+            .await
+            .unwrap();
+
+        portfolio.cash_balance = Decimal::new(500, 0);
+        repository
+            .save_snapshot(record.id, &portfolio)
+            .await
+            .unwrap();
+
+        let loaded = repository.load_snapshot(record.id).await.unwrap();
+        assert_eq!(loaded, Some(portfolio));
+    }
+
+    #[tokio::test]
+    async fn test_daily_snapshots_keep_latest_per_day() {
+        let repository = create_test_repository().await;
+        let record = create_test_record();
+        repository.create_portfolio(&record).await.unwrap();
+
+        let snapshot_at = |day: u32, hour: u32, value: i64| PortfolioSnapshot {
+            portfolio_id: record.id,
+            as_of: Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap(),
+            total_value: Decimal::new(value, 0),
+            cash_balance: Decimal::new(value, 0),
+            net_contributions: record.starting_cash,
+            positions: Vec::new(),
+        };
+
+        repository
+            .save_daily_snapshot(&snapshot_at(3, 21, 10_100))
+            .await
+            .unwrap();
+        repository
+            .save_daily_snapshot(&snapshot_at(2, 14, 9_900))
+            .await
+            .unwrap();
+        // Re-snapshotting the same day replaces the earlier valuation
+        repository
+            .save_daily_snapshot(&snapshot_at(2, 21, 10_000))
+            .await
+            .unwrap();
+
+        let loaded = repository
+            .load_snapshots(
+                record.id,
+                Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            loaded,
+            vec![snapshot_at(2, 21, 10_000), snapshot_at(3, 21, 10_100)]
+        );
+
+        let first_day = repository
+            .load_snapshots(
+                record.id,
+                Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 2, 23, 59, 59).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(first_day.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_risk_limits_round_trip() {
+        let repository = create_test_repository().await;
+        let record = create_test_record();
+        repository.create_portfolio(&record).await.unwrap();
+
+        assert!(repository
+            .load_risk_limits(record.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let limits = RiskLimits::unlimited()
+            .with_max_position_notional(Decimal::new(25_000, 0))
+            .with_max_asset_class_notional(AssetClass::Crypto, Decimal::new(5_000, 0));
+        repository
+            .save_risk_limits(record.id, &limits)
+            .await
+            .unwrap();
+
+        let loaded = repository.load_risk_limits(record.id).await.unwrap();
+        assert_eq!(loaded, Some(limits));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_uuid, to_json};
+use shared_types::SavedScreen;
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA: &[&str] = &["CREATE TABLE IF NOT EXISTS saved_screens (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        criteria TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )"];
+
+const COLUMNS: &str = "id, name, criteria, created_at, updated_at";
+
+/// SQLite storage for named screener criteria
+pub struct ScreenRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl ScreenRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the saved screen table if it does not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert or replace a screen
+    pub async fn save(&self, screen: &SavedScreen) -> DatabaseResult<()> {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO saved_screens ({}) VALUES (?, ?, ?, ?, ?)",
+            COLUMNS
+        ))
+        .bind(screen.id.to_string())
+        .bind(&screen.name)
+        .bind(to_json("ScreenCriteria", &screen.criteria)?)
+        .bind(screen.created_at)
+        .bind(screen.updated_at)
+        .execute(self.pool.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: Uuid) -> DatabaseResult<Option<SavedScreen>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM saved_screens WHERE id = ?",
+            COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(self.pool.pool())
+        .await?;
+        row.map(|row| screen_from_row(&row)).transpose()
+    }
+
+    /// Every saved screen, by name
+    pub async fn list(&self) -> DatabaseResult<Vec<SavedScreen>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM saved_screens ORDER BY name, created_at",
+            COLUMNS
+        ))
+        .fetch_all(self.pool.pool())
+        .await?;
+        rows.iter().map(screen_from_row).collect()
+    }
+
+    pub async fn delete(&self, id: Uuid) -> DatabaseResult<bool> {
+        let result = sqlx::query("DELETE FROM saved_screens WHERE id = ?")
+            .bind(id.to_string())
+            .execute(self.pool.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn screen_from_row(row: &SqliteRow) -> DatabaseResult<SavedScreen> {
+    Ok(SavedScreen {
+        id: parse_uuid("id", &row.try_get::<String, _>("id")?)?,
+        name: row.try_get("name")?,
+        criteria: from_json("ScreenCriteria", &row.try_get::<String, _>("criteria")?)?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use shared_types::{Exchange, ScreenCriteria, ScreenUniverse, TimeFrame};
+    use std::time::Duration;
+
+    async fn repository() -> ScreenRepository {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = ScreenRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    #[tokio::test]
+    async fn test_saved_screens_round_trip() {
+        let repository = repository().await;
+        let oversold = SavedScreen::new(
+            "Oversold on volume",
+            ScreenCriteria::new("rsi(14) < 30 and volume > 2 * avg_volume(20)")
+                .with_universe(ScreenUniverse {
+                    exchanges: vec![Exchange::NASDAQ],
+                    ..ScreenUniverse::default()
+                })
+                .with_column("rsi", "rsi(14)")
+                .with_sort("rsi", false),
+        )
+        .unwrap();
+        let breakout = SavedScreen::new(
+            "Breakouts",
+            ScreenCriteria::new("close > highest(20)").with_timeframe(TimeFrame::OneHour),
+        )
+        .unwrap();
+        repository.save(&oversold).await.unwrap();
+        repository.save(&breakout).await.unwrap();
+
+        assert_eq!(
+            repository.get(oversold.id).await.unwrap(),
+            Some(oversold.clone())
+        );
+        assert_eq!(
+            repository.list().await.unwrap(),
+            [breakout.clone(), oversold.clone()]
+        );
+        assert!(repository.delete(breakout.id).await.unwrap());
+        assert!(!repository.delete(breakout.id).await.unwrap());
+        assert_eq!(repository.list().await.unwrap(), [oversold]);
+    }
+}
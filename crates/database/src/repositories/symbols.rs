@@ -0,0 +1,859 @@
+use crate::errors::{DatabaseError, DatabaseResult, DatabaseType};
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, to_json};
+use chrono::Utc;
+use shared_types::{
+    AssetClass, Exchange, IdentifierKind, Symbol, SymbolMatch, SymbolNormalizer,
+    SymbolSearchRequest, SymbolSearchResponse, Vendor,
+};
+use sqlx::Row;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS symbols (
+        symbol_key TEXT PRIMARY KEY,
+        code TEXT NOT NULL,
+        display_name TEXT NOT NULL,
+        asset_class TEXT NOT NULL,
+        exchange TEXT NOT NULL,
+        sector TEXT,
+        industry TEXT,
+        is_active INTEGER NOT NULL,
+        symbol TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )",
+    // Trigram tokens match any substring of three or more characters, which
+    // also lets misspelled queries share tokens with the intended name
+    "CREATE VIRTUAL TABLE IF NOT EXISTS symbols_fts USING fts5(
+        code, display_name, sector, industry,
+        content = 'symbols', content_rowid = 'rowid', tokenize = 'trigram'
+    )",
+    "CREATE TRIGGER IF NOT EXISTS symbols_fts_insert AFTER INSERT ON symbols BEGIN
+        INSERT INTO symbols_fts (rowid, code, display_name, sector, industry)
+        VALUES (new.rowid, new.code, new.display_name, new.sector, new.industry);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS symbols_fts_delete AFTER DELETE ON symbols BEGIN
+        INSERT INTO symbols_fts (symbols_fts, rowid, code, display_name, sector, industry)
+        VALUES ('delete', old.rowid, old.code, old.display_name, old.sector, old.industry);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS symbols_fts_update AFTER UPDATE ON symbols BEGIN
+        INSERT INTO symbols_fts (symbols_fts, rowid, code, display_name, sector, industry)
+        VALUES ('delete', old.rowid, old.code, old.display_name, old.sector, old.industry);
+        INSERT INTO symbols_fts (rowid, code, display_name, sector, industry)
+        VALUES (new.rowid, new.code, new.display_name, new.sector, new.industry);
+    END",
+    // Vendor tickers and security identifiers, each namespace mapping to one symbol
+    "CREATE TABLE IF NOT EXISTS symbol_aliases (
+        namespace TEXT NOT NULL,
+        alias TEXT NOT NULL,
+        symbol_key TEXT NOT NULL,
+        PRIMARY KEY (namespace, alias)
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_symbol_aliases_symbol ON symbol_aliases (symbol_key)",
+];
+
+const ALIAS_UPSERT: &str = "INSERT INTO symbol_aliases (namespace, alias, symbol_key)
+     VALUES (?, ?, ?)
+     ON CONFLICT(namespace, alias) DO UPDATE SET symbol_key = excluded.symbol_key";
+
+/// Most rows pulled from the index for scoring; `total_matches` counts within these
+const MAX_CANDIDATES: u32 = 500;
+const DEFAULT_LIMIT: u32 = 20;
+
+/// Lowest string similarity accepted as a fuzzy match
+const FUZZY_THRESHOLD: f64 = 0.85;
+
+/// Searchable fields and the weight of a match in each
+const FIELDS: [(&str, f64); 4] = [
+    ("code", 1.0),
+    ("display_name", 0.95),
+    ("sector", 0.6),
+    ("industry", 0.6),
+];
+
+/// Header names of the columns in a listing file, matched case-insensitively
+///
+/// Only the code and name columns are required; rows fall back to the
+/// [`ListingOptions`] defaults for any other column missing from the header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingColumns {
+    pub code: String,
+    pub name: String,
+    pub exchange: String,
+    pub asset_class: String,
+    pub currency: String,
+    pub sector: String,
+    pub industry: String,
+    pub active: String,
+}
+
+impl Default for ListingColumns {
+    fn default() -> Self {
+        Self {
+            code: "symbol".to_string(),
+            name: "name".to_string(),
+            exchange: "exchange".to_string(),
+            asset_class: "asset_class".to_string(),
+            currency: "currency".to_string(),
+            sector: "sector".to_string(),
+            industry: "industry".to_string(),
+            active: "active".to_string(),
+        }
+    }
+}
+
+impl ListingColumns {
+    pub fn with_code(mut self, column: impl Into<String>) -> Self {
+        self.code = column.into();
+        self
+    }
+
+    pub fn with_name(mut self, column: impl Into<String>) -> Self {
+        self.name = column.into();
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingOptions {
+    pub delimiter: u8,
+    pub columns: ListingColumns,
+
+    /// Used for rows without an exchange column
+    pub exchange: Exchange,
+    pub asset_class: AssetClass,
+    pub currency: String,
+}
+
+impl Default for ListingOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            columns: ListingColumns::default(),
+            exchange: Exchange::Other("UNKNOWN".to_string()),
+            asset_class: AssetClass::Stock,
+            currency: "USD".to_string(),
+        }
+    }
+}
+
+impl ListingOptions {
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_columns(mut self, columns: ListingColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn with_exchange(mut self, exchange: Exchange) -> Self {
+        self.exchange = exchange;
+        self
+    }
+
+    pub fn with_asset_class(mut self, asset_class: AssetClass) -> Self {
+        self.asset_class = asset_class;
+        self
+    }
+}
+
+/// A listing row that could not be turned into a symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingRowError {
+    /// 1-based line in the file, counting the header
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ListingImport {
+    pub imported: usize,
+    pub errors: Vec<ListingRowError>,
+}
+
+/// SQLite symbol master with a full-text index over code, name, sector and industry,
+/// plus an alias table mapping vendor tickers and security identifiers to symbols
+pub struct SymbolRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl SymbolRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the symbol table, its search index and the triggers keeping them in step
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert symbols, replacing any stored under the same `code@exchange`
+    ///
+    /// ISIN, CUSIP and FIGI identifiers in the symbols' metadata are registered
+    /// as aliases so [`SymbolRepository::find_by_identifier`] can resolve them.
+    pub async fn upsert_symbols(&self, symbols: &[Symbol]) -> DatabaseResult<usize> {
+        let updated_at = Utc::now();
+        let mut transaction = self.pool.begin_transaction().await?;
+        for symbol in symbols {
+            sqlx::query(
+                "INSERT INTO symbols (symbol_key, code, display_name, asset_class, exchange,
+                    sector, industry, is_active, symbol, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(symbol_key) DO UPDATE SET
+                    code = excluded.code,
+                    display_name = excluded.display_name,
+                    asset_class = excluded.asset_class,
+                    exchange = excluded.exchange,
+                    sector = excluded.sector,
+                    industry = excluded.industry,
+                    is_active = excluded.is_active,
+                    symbol = excluded.symbol,
+                    updated_at = excluded.updated_at",
+            )
+            .bind(symbol.full_identifier())
+            .bind(&symbol.code)
+            .bind(&symbol.display_name)
+            .bind(symbol.asset_class.to_string())
+            .bind(symbol.exchange.to_string())
+            .bind(&symbol.sector)
+            .bind(&symbol.industry)
+            .bind(symbol.is_active)
+            .bind(to_json("Symbol", symbol)?)
+            .bind(updated_at)
+            .execute(&mut *transaction)
+            .await?;
+
+            // Identifiers the symbol no longer carries must stop resolving to it
+            sqlx::query(
+                "DELETE FROM symbol_aliases WHERE symbol_key = ? AND namespace IN (?, ?, ?)",
+            )
+            .bind(symbol.full_identifier())
+            .bind(IdentifierKind::Isin.as_str())
+            .bind(IdentifierKind::Cusip.as_str())
+            .bind(IdentifierKind::Figi.as_str())
+            .execute(&mut *transaction)
+            .await?;
+            for (kind, value) in symbol.identifiers() {
+                sqlx::query(ALIAS_UPSERT)
+                    .bind(kind.as_str())
+                    .bind(value)
+                    .bind(symbol.full_identifier())
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok(symbols.len())
+    }
+
+    /// Map a ticker or identifier in `namespace` to a symbol, replacing any previous mapping
+    pub async fn add_alias(
+        &self,
+        namespace: &str,
+        alias: &str,
+        symbol: &Symbol,
+    ) -> DatabaseResult<()> {
+        sqlx::query(ALIAS_UPSERT)
+            .bind(namespace)
+            .bind(alias.trim().to_uppercase())
+            .bind(symbol.full_identifier())
+            .execute(self.pool.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// Stored symbol an alias points at, if both exist
+    pub async fn resolve_alias(
+        &self,
+        namespace: &str,
+        alias: &str,
+    ) -> DatabaseResult<Option<Symbol>> {
+        let row = sqlx::query(
+            "SELECT s.symbol FROM symbol_aliases a
+             JOIN symbols s ON s.symbol_key = a.symbol_key
+             WHERE a.namespace = ? AND a.alias = ?",
+        )
+        .bind(namespace)
+        .bind(alias.trim().to_uppercase())
+        .fetch_optional(self.pool.pool())
+        .await?;
+        row.map(|row| from_json("Symbol", &row.try_get::<String, _>("symbol")?))
+            .transpose()
+    }
+
+    pub async fn find_by_identifier(
+        &self,
+        kind: IdentifierKind,
+        value: &str,
+    ) -> DatabaseResult<Option<Symbol>> {
+        self.resolve_alias(kind.as_str(), value).await
+    }
+
+    /// Normalizer seeded with every stored vendor ticker alias and active US listing
+    pub async fn load_normalizer(&self) -> DatabaseResult<SymbolNormalizer> {
+        let rows = sqlx::query(
+            "SELECT a.namespace, a.alias, s.symbol FROM symbol_aliases a
+             JOIN symbols s ON s.symbol_key = a.symbol_key",
+        )
+        .fetch_all(self.pool.pool())
+        .await?;
+
+        let mut normalizer = SymbolNormalizer::new();
+        for row in rows {
+            // Identifier namespaces are not vendors
+            let Ok(vendor) = row.try_get::<String, _>("namespace")?.parse::<Vendor>() else {
+                continue;
+            };
+            let symbol = from_json("Symbol", &row.try_get::<String, _>("symbol")?)?;
+            normalizer.add_alias(vendor, &row.try_get::<String, _>("alias")?, symbol);
+        }
+        for symbol in self.active_symbols().await? {
+            normalizer.add_listing(symbol);
+        }
+        Ok(normalizer)
+    }
+
+    pub async fn get(&self, code: &str, exchange: &Exchange) -> DatabaseResult<Option<Symbol>> {
+        let row = sqlx::query("SELECT symbol FROM symbols WHERE symbol_key = ?")
+            .bind(format!("{}@{}", code.to_uppercase(), exchange))
+            .fetch_optional(self.pool.pool())
+            .await?;
+        row.map(|row| from_json("Symbol", &row.try_get::<String, _>("symbol")?))
+            .transpose()
+    }
+
+    /// Every active symbol, by code and exchange
+    pub async fn active_symbols(&self) -> DatabaseResult<Vec<Symbol>> {
+        let rows =
+            sqlx::query("SELECT symbol FROM symbols WHERE is_active = 1 ORDER BY symbol_key")
+                .fetch_all(self.pool.pool())
+                .await?;
+        rows.iter()
+            .map(|row| from_json("Symbol", &row.try_get::<String, _>("symbol")?))
+            .collect()
+    }
+
+    /// Parse a delimited listing file and upsert every valid row
+    ///
+    /// Malformed rows are reported rather than failing the import; a header
+    /// without the code or name column is an error.
+    pub async fn import_listings<R: Read>(
+        &self,
+        reader: R,
+        options: &ListingOptions,
+    ) -> DatabaseResult<ListingImport> {
+        let (symbols, errors) = parse_listings(reader, options)?;
+        let imported = self.upsert_symbols(&symbols).await?;
+        Ok(ListingImport { imported, errors })
+    }
+
+    /// Ranked search over the symbol master
+    ///
+    /// Candidates come from the trigram index (and a prefix scan for queries
+    /// too short to form a trigram), then each is scored against the query:
+    /// exact, prefix, word and substring matches rank above fuzzy ones, and a
+    /// field only appears in `matched_fields` if it scored on its own.
+    pub async fn search(
+        &self,
+        request: &SymbolSearchRequest,
+    ) -> DatabaseResult<SymbolSearchResponse> {
+        let query = request.query.trim().to_lowercase();
+        let mut response = SymbolSearchResponse {
+            query: request.query.clone(),
+            symbols: Vec::new(),
+            total_matches: 0,
+        };
+        if query.is_empty() {
+            return Ok(response);
+        }
+
+        let mut filters = String::new();
+        if request.asset_class.is_some() {
+            filters.push_str(" AND s.asset_class = ?");
+        }
+        if request.exchange.is_some() {
+            filters.push_str(" AND s.exchange = ?");
+        }
+        if !request.include_inactive {
+            filters.push_str(" AND s.is_active = 1");
+        }
+
+        let mut candidates: HashMap<String, Symbol> = HashMap::new();
+        let prefix = format!("{}%", escape_like(&query));
+        let sql = format!(
+            "SELECT s.symbol_key, s.symbol FROM symbols s
+             WHERE (lower(s.code) LIKE ? ESCAPE '\\' OR lower(s.display_name) LIKE ? ESCAPE '\\'){}
+             LIMIT ?",
+            filters
+        );
+        self.collect_candidates(&sql, &[&prefix, &prefix], request, &mut candidates)
+            .await?;
+        if let Some(expression) = trigram_query(&query) {
+            let sql = format!(
+                "SELECT s.symbol_key, s.symbol FROM symbols_fts
+                 JOIN symbols s ON s.rowid = symbols_fts.rowid
+                 WHERE symbols_fts MATCH ?{}
+                 ORDER BY symbols_fts.rank LIMIT ?",
+                filters
+            );
+            self.collect_candidates(&sql, &[&expression], request, &mut candidates)
+                .await?;
+        }
+
+        let mut matches: Vec<SymbolMatch> = candidates
+            .values()
+            .filter_map(|symbol| score_symbol(&query, symbol))
+            .collect();
+        matches.sort_by(|a, b| {
+            b.match_score
+                .total_cmp(&a.match_score)
+                .then_with(|| a.symbol.code.len().cmp(&b.symbol.code.len()))
+                .then_with(|| a.symbol.full_identifier().cmp(&b.symbol.full_identifier()))
+        });
+        response.total_matches = matches.len() as u64;
+        matches.truncate(request.limit.unwrap_or(DEFAULT_LIMIT) as usize);
+        response.symbols = matches;
+        Ok(response)
+    }
+
+    async fn collect_candidates(
+        &self,
+        sql: &str,
+        patterns: &[&str],
+        request: &SymbolSearchRequest,
+        candidates: &mut HashMap<String, Symbol>,
+    ) -> DatabaseResult<()> {
+        let mut statement = sqlx::query(sql);
+        for pattern in patterns {
+            statement = statement.bind(*pattern);
+        }
+        if let Some(asset_class) = &request.asset_class {
+            statement = statement.bind(asset_class.to_string());
+        }
+        if let Some(exchange) = &request.exchange {
+            statement = statement.bind(exchange.to_string());
+        }
+        let rows = statement
+            .bind(MAX_CANDIDATES)
+            .fetch_all(self.pool.pool())
+            .await?;
+        for row in rows {
+            let key: String = row.try_get("symbol_key")?;
+            if let Entry::Vacant(entry) = candidates.entry(key) {
+                entry.insert(from_json("Symbol", &row.try_get::<String, _>("symbol")?)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// FTS5 expression matching rows that share any trigram with the query
+fn trigram_query(query: &str) -> Option<String> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut trigrams: Vec<String> = chars
+        .windows(3)
+        .map(|window| window.iter().collect::<String>())
+        .filter(|trigram| !trigram.contains(char::is_whitespace))
+        .collect();
+    trigrams.sort();
+    trigrams.dedup();
+    if trigrams.is_empty() {
+        return None;
+    }
+    Some(
+        trigrams
+            .iter()
+            .map(|trigram| format!("\"{}\"", trigram.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" OR "),
+    )
+}
+
+/// How well a lowercase query matches one field value, from 0 to 1
+fn field_score(query: &str, value: &str) -> Option<f64> {
+    let value = value.to_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+    if value == query {
+        return Some(1.0);
+    }
+    let coverage = query.chars().count() as f64 / value.chars().count() as f64;
+    if value.starts_with(query) {
+        return Some(0.8 + 0.15 * coverage);
+    }
+    let words: Vec<&str> = value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.contains(&query) {
+        return Some(0.75);
+    }
+    if words.iter().any(|word| word.starts_with(query)) {
+        return Some(0.65 + 0.1 * coverage.min(1.0));
+    }
+    if value.contains(query) {
+        return Some(0.55);
+    }
+
+    // Typos: compare against the whole value and each word
+    let similarity = std::iter::once(value.as_str())
+        .chain(words.iter().copied())
+        .map(|candidate| strsim::jaro_winkler(query, candidate))
+        .fold(0.0, f64::max);
+    (similarity >= FUZZY_THRESHOLD).then_some(similarity * 0.6)
+}
+
+/// Weighted best field score and the fields that matched
+fn score_symbol(query: &str, symbol: &Symbol) -> Option<SymbolMatch> {
+    let values = [
+        Some(symbol.code.as_str()),
+        Some(symbol.display_name.as_str()),
+        symbol.sector.as_deref(),
+        symbol.industry.as_deref(),
+    ];
+    let mut best: f64 = 0.0;
+    let mut matched_fields = Vec::new();
+    for ((field, weight), value) in FIELDS.iter().zip(values) {
+        if let Some(score) = value.and_then(|value| field_score(query, value)) {
+            best = best.max(score * weight);
+            matched_fields.push(field.to_string());
+        }
+    }
+    (!matched_fields.is_empty()).then(|| SymbolMatch {
+        symbol: symbol.clone(),
+        match_score: best,
+        matched_fields,
+    })
+}
+
+fn parse_listings<R: Read>(
+    reader: R,
+    options: &ListingOptions,
+) -> DatabaseResult<(Vec<Symbol>, Vec<ListingRowError>)> {
+    let invalid =
+        |message: String| DatabaseError::serialization(DatabaseType::SQLite, "Listing", message);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid(format!("Failed to read listing header: {}", e)))?
+        .clone();
+    let position = |column: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(column))
+    };
+    let columns = &options.columns;
+    let code = position(&columns.code)
+        .ok_or_else(|| invalid(format!("Listing has no '{}' column", columns.code)))?;
+    let name = position(&columns.name)
+        .ok_or_else(|| invalid(format!("Listing has no '{}' column", columns.name)))?;
+    let exchange = position(&columns.exchange);
+    let asset_class = position(&columns.asset_class);
+    let currency = position(&columns.currency);
+    let sector = position(&columns.sector);
+    let industry = position(&columns.industry);
+    let active = position(&columns.active);
+
+    let mut symbols = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(ListingRowError {
+                    line: e.position().map_or(0, |position| position.line()),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .filter(|value| !value.is_empty())
+        };
+
+        let row = || -> Result<Symbol, String> {
+            let code = field(Some(code)).ok_or("missing symbol code")?;
+            let name = field(Some(name)).ok_or("missing name")?;
+            let asset_class = match field(asset_class) {
+                Some(value) => value.parse().map_err(|e| format!("{}", e))?,
+                None => options.asset_class.clone(),
+            };
+            let exchange = match field(exchange) {
+                Some(value) => value.parse().map_err(|e| format!("{}", e))?,
+                None => options.exchange.clone(),
+            };
+            let currency = field(currency).unwrap_or(&options.currency);
+            let mut symbol = Symbol::new(
+                code.to_string(),
+                name.to_string(),
+                asset_class,
+                exchange,
+                currency.to_string(),
+            )
+            .map_err(|e| e.to_string())?;
+            symbol.sector = field(sector).map(str::to_string);
+            symbol.industry = field(industry).map(str::to_string);
+            if let Some(value) = field(active) {
+                symbol.is_active = match value.to_lowercase().as_str() {
+                    "true" | "1" | "y" | "yes" | "active" => true,
+                    "false" | "0" | "n" | "no" | "inactive" | "delisted" => false,
+                    other => return Err(format!("invalid active flag '{}'", other)),
+                };
+            }
+            Ok(symbol)
+        };
+        match row() {
+            Ok(symbol) => symbols.push(symbol),
+            Err(message) => errors.push(ListingRowError { line, message }),
+        }
+    }
+    Ok((symbols, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use std::time::Duration;
+
+    async fn repository() -> SymbolRepository {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = SymbolRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    fn search(query: &str) -> SymbolSearchRequest {
+        SymbolSearchRequest {
+            query: query.to_string(),
+            asset_class: None,
+            exchange: None,
+            limit: None,
+            include_inactive: false,
+        }
+    }
+
+    fn stock(code: &str, name: &str, sector: &str, industry: &str) -> Symbol {
+        let mut symbol = Symbol::stock(code, name, Exchange::NASDAQ).unwrap();
+        symbol.sector = Some(sector.to_string());
+        symbol.industry = Some(industry.to_string());
+        symbol
+    }
+
+    #[tokio::test]
+    async fn test_ranked_and_fuzzy_search() {
+        let repository = repository().await;
+        let mut delisted = stock("APPN", "Appian Corporation", "Technology", "Software");
+        delisted.is_active = false;
+        repository
+            .upsert_symbols(&[
+                stock("AAPL", "Apple Inc.", "Technology", "Consumer Electronics"),
+                stock("APLE", "Apple Hospitality REIT", "Real Estate", "REIT"),
+                stock("MSFT", "Microsoft Corporation", "Technology", "Software"),
+                delisted,
+            ])
+            .await
+            .unwrap();
+
+        let response = repository.search(&search("aapl")).await.unwrap();
+        assert_eq!(response.symbols[0].symbol.code, "AAPL");
+        assert_eq!(response.symbols[0].match_score, 1.0);
+        assert_eq!(response.symbols[0].matched_fields, vec!["code"]);
+
+        // Both Apples match by name; the ticker APLE is a code prefix too
+        let response = repository.search(&search("apple")).await.unwrap();
+        let codes: Vec<&str> = response
+            .symbols
+            .iter()
+            .map(|found| found.symbol.code.as_str())
+            .collect();
+        assert_eq!(codes, vec!["AAPL", "APLE"]);
+        assert_eq!(
+            response.symbols[1].matched_fields,
+            vec!["code", "display_name"]
+        );
+
+        // A misspelling still finds the company through trigram candidates
+        let response = repository.search(&search("microsfot")).await.unwrap();
+        assert_eq!(response.symbols[0].symbol.code, "MSFT");
+        assert!(response.symbols[0].match_score < 0.6);
+        assert_eq!(response.symbols[0].matched_fields, vec!["display_name"]);
+
+        // Sector matches, with inactive symbols only on request
+        let response = repository.search(&search("software")).await.unwrap();
+        assert_eq!(response.total_matches, 1);
+        assert_eq!(response.symbols[0].matched_fields, vec!["industry"]);
+        let response = repository
+            .search(&SymbolSearchRequest {
+                include_inactive: true,
+                ..search("software")
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.total_matches, 2);
+
+        let response = repository
+            .search(&SymbolSearchRequest {
+                exchange: Some(Exchange::NYSE),
+                ..search("apple")
+            })
+            .await
+            .unwrap();
+        assert!(response.symbols.is_empty());
+
+        let active: Vec<String> = repository
+            .active_symbols()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.code)
+            .collect();
+        assert_eq!(active, ["AAPL", "APLE", "MSFT"]);
+    }
+
+    #[tokio::test]
+    async fn test_imports_listing_file() {
+        let repository = repository().await;
+        let listing = "Symbol|Security Name|Asset_Class|Active\n\
+                       AAPL|Apple Inc. - Common Stock|stock|Y\n\
+                       QQQ|Invesco QQQ Trust, Series 1|etf|Y\n\
+                       ZZZZ||stock|Y\n\
+                       File Creation Time: 0718202418:01|||\n";
+        let options = ListingOptions::default()
+            .with_delimiter(b'|')
+            .with_exchange(Exchange::NASDAQ)
+            .with_columns(
+                ListingColumns::default()
+                    .with_code("Symbol")
+                    .with_name("Security Name"),
+            );
+
+        let report = repository
+            .import_listings(listing.as_bytes(), &options)
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 2);
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|error| error.line)
+                .collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+
+        let qqq = repository
+            .get("qqq", &Exchange::NASDAQ)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(qqq.asset_class, AssetClass::ETF);
+        assert_eq!(
+            repository.search(&search("QQ")).await.unwrap().symbols[0].symbol,
+            qqq
+        );
+
+        let missing = repository
+            .import_listings(
+                "ticker,name\nAAPL,Apple\n".as_bytes(),
+                &ListingOptions::default(),
+            )
+            .await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_aliases_and_identifiers() {
+        let repository = repository().await;
+        let mut apple = stock("AAPL", "Apple Inc.", "Technology", "Consumer Electronics");
+        apple
+            .set_identifier(IdentifierKind::Isin, "US0378331005")
+            .unwrap();
+        let bitcoin = Symbol::crypto("BTC", "USD", Exchange::Kraken).unwrap();
+        repository
+            .upsert_symbols(&[apple.clone(), bitcoin.clone()])
+            .await
+            .unwrap();
+        repository
+            .add_alias(Vendor::Kraken.as_str(), "xxbtzusd", &bitcoin)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .find_by_identifier(IdentifierKind::Isin, "us0378331005")
+                .await
+                .unwrap(),
+            Some(apple.clone())
+        );
+        assert_eq!(
+            repository.resolve_alias("kraken", "XBTUSD").await.unwrap(),
+            None
+        );
+
+        let normalizer = repository.load_normalizer().await.unwrap();
+        assert_eq!(
+            normalizer.vendor_ticker(&bitcoin, Vendor::Kraken),
+            "XXBTZUSD"
+        );
+        assert_eq!(
+            normalizer.normalize(Vendor::Kraken, "XXBTZUSD").unwrap(),
+            bitcoin
+        );
+        let listed = normalizer.normalize(Vendor::AlphaVantage, "AAPL").unwrap();
+        assert_eq!(listed.exchange, Exchange::NASDAQ);
+        assert_eq!(listed.timezone, "America/New_York");
+
+        // A corrected ISIN replaces the old one rather than adding to it
+        let mut corrected = apple.clone();
+        corrected
+            .set_identifier(IdentifierKind::Isin, "US0231351067")
+            .unwrap();
+        repository
+            .upsert_symbols(&[corrected.clone()])
+            .await
+            .unwrap();
+        assert_eq!(
+            repository
+                .find_by_identifier(IdentifierKind::Isin, "US0378331005")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            repository
+                .find_by_identifier(IdentifierKind::Isin, "US0231351067")
+                .await
+                .unwrap(),
+            Some(corrected)
+        );
+    }
+}
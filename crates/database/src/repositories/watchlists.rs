@@ -0,0 +1,231 @@
+use crate::errors::DatabaseResult;
+use crate::pools::SqlitePool;
+use crate::repositories::{from_json, parse_uuid, to_json};
+use shared_types::{Watchlist, WatchlistMember};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS watchlists (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS watchlist_members (
+        watchlist_id TEXT NOT NULL REFERENCES watchlists(id) ON DELETE CASCADE,
+        symbol_key TEXT NOT NULL,
+        symbol TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        tags TEXT NOT NULL,
+        notes TEXT,
+        added_at TEXT NOT NULL,
+        PRIMARY KEY (watchlist_id, symbol_key)
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_watchlist_members_symbol ON watchlist_members (symbol_key)",
+];
+
+const MEMBER_COLUMNS: &str = "watchlist_id, symbol, tags, notes, added_at";
+
+/// SQLite persistence for watchlists and their ordered members
+pub struct WatchlistRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl WatchlistRepository {
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Create the watchlist tables if they do not exist
+    pub async fn migrate(&self) -> DatabaseResult<()> {
+        for statement in SCHEMA {
+            self.pool.execute(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert or replace a watchlist together with its members and their order
+    pub async fn save(&self, watchlist: &Watchlist) -> DatabaseResult<()> {
+        let mut transaction = self.pool.begin_transaction().await?;
+        sqlx::query(
+            "INSERT INTO watchlists (id, name, created_at, updated_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                updated_at = excluded.updated_at",
+        )
+        .bind(watchlist.id.to_string())
+        .bind(&watchlist.name)
+        .bind(watchlist.created_at)
+        .bind(watchlist.updated_at)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query("DELETE FROM watchlist_members WHERE watchlist_id = ?")
+            .bind(watchlist.id.to_string())
+            .execute(&mut *transaction)
+            .await?;
+        for (position, member) in watchlist.members.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO watchlist_members
+                 (watchlist_id, symbol_key, symbol, position, tags, notes, added_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(watchlist.id.to_string())
+            .bind(member.key())
+            .bind(to_json("Symbol", &member.symbol)?)
+            .bind(position as i64)
+            .bind(to_json("tags", &member.tags)?)
+            .bind(&member.notes)
+            .bind(member.added_at)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: Uuid) -> DatabaseResult<Option<Watchlist>> {
+        let row =
+            sqlx::query("SELECT id, name, created_at, updated_at FROM watchlists WHERE id = ?")
+                .bind(id.to_string())
+                .fetch_optional(self.pool.pool())
+                .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let mut watchlist = watchlist_from_row(&row)?;
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM watchlist_members WHERE watchlist_id = ? ORDER BY position",
+            MEMBER_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_all(self.pool.pool())
+        .await?;
+        watchlist.members = rows
+            .iter()
+            .map(|row| member_from_row(row).map(|(_, member)| member))
+            .collect::<DatabaseResult<_>>()?;
+        Ok(Some(watchlist))
+    }
+
+    /// Every watchlist with its members, by name
+    pub async fn list(&self) -> DatabaseResult<Vec<Watchlist>> {
+        let rows = sqlx::query(
+            "SELECT id, name, created_at, updated_at FROM watchlists ORDER BY name, created_at",
+        )
+        .fetch_all(self.pool.pool())
+        .await?;
+        let mut watchlists = rows
+            .iter()
+            .map(watchlist_from_row)
+            .collect::<DatabaseResult<Vec<_>>>()?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM watchlist_members ORDER BY watchlist_id, position",
+            MEMBER_COLUMNS
+        ))
+        .fetch_all(self.pool.pool())
+        .await?;
+        let mut members: HashMap<Uuid, Vec<WatchlistMember>> = HashMap::new();
+        for row in &rows {
+            let (watchlist_id, member) = member_from_row(row)?;
+            members.entry(watchlist_id).or_default().push(member);
+        }
+        for watchlist in &mut watchlists {
+            watchlist.members = members.remove(&watchlist.id).unwrap_or_default();
+        }
+        Ok(watchlists)
+    }
+
+    /// Remove a watchlist and its members. Returns whether one was deleted
+    pub async fn delete(&self, id: Uuid) -> DatabaseResult<bool> {
+        let mut transaction = self.pool.begin_transaction().await?;
+        sqlx::query("DELETE FROM watchlist_members WHERE watchlist_id = ?")
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await?;
+        let result = sqlx::query("DELETE FROM watchlists WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn watchlist_from_row(row: &SqliteRow) -> DatabaseResult<Watchlist> {
+    Ok(Watchlist {
+        id: parse_uuid("id", &row.try_get::<String, _>("id")?)?,
+        name: row.try_get("name")?,
+        members: Vec::new(),
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn member_from_row(row: &SqliteRow) -> DatabaseResult<(Uuid, WatchlistMember)> {
+    Ok((
+        parse_uuid("watchlist_id", &row.try_get::<String, _>("watchlist_id")?)?,
+        WatchlistMember {
+            symbol: from_json("Symbol", &row.try_get::<String, _>("symbol")?)?,
+            tags: from_json("tags", &row.try_get::<String, _>("tags")?)?,
+            notes: row.try_get("notes")?,
+            added_at: row.try_get("added_at")?,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SqlitePoolConfig;
+    use shared_types::{Exchange, Symbol};
+    use std::time::Duration;
+
+    async fn repository() -> WatchlistRepository {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let repository = WatchlistRepository::new(Arc::new(SqlitePool::new(config).await.unwrap()));
+        repository.migrate().await.unwrap();
+        repository
+    }
+
+    fn member(code: &str) -> WatchlistMember {
+        WatchlistMember::new(Symbol::stock(code, code, Exchange::NASDAQ).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_watchlists_round_trip_in_order() {
+        let repository = repository().await;
+        let mut tech = Watchlist::new("Tech").unwrap();
+        tech.add(member("MSFT").with_tags(["cloud"])).unwrap();
+        tech.add(member("AAPL").with_notes("services growth"))
+            .unwrap();
+        repository.save(&tech).await.unwrap();
+        let energy = Watchlist::new("Energy").unwrap();
+        repository.save(&energy).await.unwrap();
+
+        tech.move_member("AAPL@NASDAQ", 0).unwrap();
+        tech.remove("MSFT@NASDAQ").unwrap();
+        tech.add(member("NVDA")).unwrap();
+        tech.rename("Big tech").unwrap();
+        repository.save(&tech).await.unwrap();
+
+        assert_eq!(repository.get(tech.id).await.unwrap(), Some(tech.clone()));
+        assert_eq!(
+            repository.list().await.unwrap(),
+            [tech.clone(), energy.clone()]
+        );
+
+        assert!(repository.delete(tech.id).await.unwrap());
+        assert!(!repository.delete(tech.id).await.unwrap());
+        assert_eq!(repository.list().await.unwrap(), [energy]);
+    }
+}
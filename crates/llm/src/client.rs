@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use shared_types::AnalysisError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LlmError {
+    /// Outages, missing models and timeouts, as the analysis layer reports them
+    #[error(transparent)]
+    Analysis(#[from] AnalysisError),
+
+    #[error("Request to '{service}' failed (status {status:?}): {message}")]
+    Http {
+        service: String,
+        status: Option<u16>,
+        message: String,
+    },
+
+    #[error("Could not parse response from '{service}': {message}")]
+    Parse { service: String, message: String },
+}
+
+impl LlmError {
+    /// Whether another service, or the same one later, might answer the request
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LlmError::Analysis(
+                AnalysisError::AIServiceUnavailable { .. }
+                | AnalysisError::ModelLoadingFailed { .. }
+                | AnalysisError::AnalysisTimeout { .. },
+            ) => true,
+            LlmError::Analysis(_) => false,
+            LlmError::Http { status, .. } => {
+                status.is_none_or(|status| status == 429 || status >= 500)
+            }
+            LlmError::Parse { .. } => false,
+        }
+    }
+}
+
+impl From<LlmError> for AnalysisError {
+    fn from(error: LlmError) -> Self {
+        match error {
+            LlmError::Analysis(error) => error,
+            LlmError::Http { service, .. } | LlmError::Parse { service, .. } => {
+                AnalysisError::AIServiceUnavailable { service }
+            }
+        }
+    }
+}
+
+pub type LlmResult<T> = Result<T, LlmError>;
+
+/// Reply tokens in the order they are generated
+pub type TokenStream = BoxStream<'static, LlmResult<String>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// One turn of a conversation, serialized the way both Ollama and OpenAI expect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// A conversation to complete; the model comes from the client's configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+impl ChatRequest {
+    pub fn new(messages: impl IntoIterator<Item = ChatMessage>) -> Self {
+        Self {
+            messages: messages.into_iter().collect(),
+            temperature: None,
+            max_tokens: None,
+        }
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatResponse {
+    pub content: String,
+    pub model: String,
+
+    /// Name of the client that answered, which differs from the one asked after a fallback
+    pub service: String,
+
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+}
+
+/// A chat model behind some API
+#[async_trait]
+pub trait LlmClient: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn chat(&self, request: &ChatRequest) -> LlmResult<ChatResponse>;
+
+    /// Complete the conversation, yielding tokens as they arrive
+    ///
+    /// Errors reaching the service are returned before any stream exists;
+    /// a failure partway through the reply ends the stream with an error.
+    async fn stream(&self, request: &ChatRequest) -> LlmResult<TokenStream>;
+}
@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use shared_types::AnalysisError;
+use std::sync::Arc;
+
+use crate::client::{ChatRequest, ChatResponse, LlmClient, LlmError, LlmResult, TokenStream};
+
+/// [`LlmClient`] that tries a list of clients in order
+///
+/// A client that is unreachable, times out, lacks the model or answers with a
+/// server error (see [`LlmError::is_retryable`]) is passed over for the next
+/// one. Errors about the request itself are returned straight away. Streams
+/// only fall back until their first token: after that, the caller has already
+/// seen part of one model's reply and a failure ends the stream.
+pub struct FallbackClient {
+    name: String,
+    clients: Vec<Arc<dyn LlmClient>>,
+}
+
+impl FallbackClient {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            clients: Vec::new(),
+        }
+    }
+
+    /// Add a client, tried after every client added before it
+    pub fn with_client(mut self, client: Arc<dyn LlmClient>) -> Self {
+        self.clients.push(client);
+        self
+    }
+
+    /// Names of the clients, in the order they are tried
+    pub fn services(&self) -> Vec<&str> {
+        self.clients.iter().map(|client| client.name()).collect()
+    }
+
+    fn exhausted(&self, last_error: Option<LlmError>) -> LlmError {
+        last_error.unwrap_or_else(|| {
+            AnalysisError::AIServiceUnavailable {
+                service: self.name.clone(),
+            }
+            .into()
+        })
+    }
+}
+
+/// Log a failure and say whether the next client should be tried
+fn falls_through(client: &dyn LlmClient, error: &LlmError) -> bool {
+    if error.is_retryable() {
+        tracing::warn!(service = client.name(), %error, "Falling back to next LLM client");
+    }
+    error.is_retryable()
+}
+
+#[async_trait]
+impl LlmClient for FallbackClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> LlmResult<ChatResponse> {
+        let mut last_error = None;
+        for client in &self.clients {
+            match client.chat(request).await {
+                Ok(response) => return Ok(response),
+                Err(error) if falls_through(client.as_ref(), &error) => last_error = Some(error),
+                Err(error) => return Err(error),
+            }
+        }
+        Err(self.exhausted(last_error))
+    }
+
+    async fn stream(&self, request: &ChatRequest) -> LlmResult<TokenStream> {
+        let mut last_error = None;
+        for client in &self.clients {
+            let mut tokens = match client.stream(request).await {
+                Ok(tokens) => tokens,
+                Err(error) if falls_through(client.as_ref(), &error) => {
+                    last_error = Some(error);
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            match tokens.next().await {
+                Some(Ok(first)) => {
+                    return Ok(stream::once(async { Ok(first) }).chain(tokens).boxed())
+                }
+                None => return Ok(stream::empty().boxed()),
+                Some(Err(error)) if falls_through(client.as_ref(), &error) => {
+                    last_error = Some(error)
+                }
+                Some(Err(error)) => return Err(error),
+            }
+        }
+        Err(self.exhausted(last_error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ChatMessage;
+    use crate::ollama::{OllamaClient, OllamaConfig};
+    use crate::openai::{OpenAiClient, OpenAiConfig};
+    use futures::TryStreamExt;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn openai_server() -> (MockServer, Arc<dyn LlmClient>) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(json!({"stream": false})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "gpt-3.5-turbo",
+                "choices": [{"message": {"role": "assistant", "content": "From OpenAI."}}],
+            })))
+            .mount(&server)
+            .await;
+        let event = json!({"choices": [{"delta": {"content": "From OpenAI."}}]});
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("data: {}\n\ndata: [DONE]\n\n", event)),
+            )
+            .mount(&server)
+            .await;
+        let client = OpenAiClient::new(
+            OpenAiConfig::new("sk-test", "gpt-3.5-turbo").with_base_url(server.uri()),
+        )
+        .unwrap();
+        (server, Arc::new(client))
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest::new([ChatMessage::user("Explain the MACD crossover.")])
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_when_ollama_is_down() {
+        let (_server, openai) = openai_server().await;
+        // Nothing listens on the discard port, so connecting fails at once
+        let offline = OllamaClient::new(OllamaConfig::new("http://127.0.0.1:9", "llama2")).unwrap();
+        let client = FallbackClient::new("llm")
+            .with_client(Arc::new(offline))
+            .with_client(openai);
+        assert_eq!(client.services(), ["ollama", "openai"]);
+
+        let reply = client.chat(&request()).await.unwrap();
+        assert_eq!(reply.content, "From OpenAI.");
+        assert_eq!(reply.service, "openai");
+
+        let ollama = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("loading"))
+            .mount(&ollama)
+            .await;
+        let overloaded = OllamaClient::new(OllamaConfig::new(ollama.uri(), "llama2")).unwrap();
+        let (_server, openai) = openai_server().await;
+        let client = FallbackClient::new("llm")
+            .with_client(Arc::new(overloaded))
+            .with_client(openai);
+        let tokens: Vec<String> = client
+            .stream(&request())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(tokens, ["From OpenAI."]);
+    }
+
+    #[tokio::test]
+    async fn test_request_errors_do_not_fall_back() {
+        let ollama = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_json(json!({"error": "invalid options"})),
+            )
+            .mount(&ollama)
+            .await;
+        let (openai_server, openai) = openai_server().await;
+        let client = FallbackClient::new("llm")
+            .with_client(Arc::new(
+                OllamaClient::new(OllamaConfig::new(ollama.uri(), "llama2")).unwrap(),
+            ))
+            .with_client(openai);
+
+        assert!(matches!(
+            client.chat(&request()).await,
+            Err(LlmError::Http {
+                status: Some(400),
+                ..
+            })
+        ));
+        assert!(openai_server.received_requests().await.unwrap().is_empty());
+        assert_eq!(
+            FallbackClient::new("llm").chat(&request()).await,
+            Err(LlmError::Analysis(AnalysisError::AIServiceUnavailable {
+                service: "llm".to_string()
+            }))
+        );
+    }
+}
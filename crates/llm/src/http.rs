@@ -0,0 +1,154 @@
+//! Request plumbing shared by the HTTP clients
+
+use futures::stream::{self, StreamExt};
+use reqwest::Response;
+use serde_json::Value;
+use shared_types::AnalysisError;
+use std::time::Duration;
+
+use crate::client::{LlmError, LlmResult};
+
+pub(crate) fn build_client(service: &str, timeout: Duration) -> LlmResult<reqwest::Client> {
+    // Only connecting is bounded here; replies are bounded per request so
+    // that streams can run for as long as tokens keep coming
+    reqwest::Client::builder()
+        .connect_timeout(timeout)
+        .build()
+        .map_err(|e| LlmError::Http {
+            service: service.to_string(),
+            status: None,
+            message: e.to_string(),
+        })
+}
+
+pub(crate) fn timed_out(timeout: Duration) -> LlmError {
+    AnalysisError::AnalysisTimeout {
+        timeout_seconds: timeout.as_secs_f64().ceil() as u64,
+    }
+    .into()
+}
+
+/// Classify a transport failure, which has no response to inspect
+pub(crate) fn request_error(service: &str, timeout: Duration, error: reqwest::Error) -> LlmError {
+    if error.is_timeout() {
+        timed_out(timeout)
+    } else if error.is_connect() {
+        AnalysisError::AIServiceUnavailable {
+            service: service.to_string(),
+        }
+        .into()
+    } else {
+        LlmError::Http {
+            service: service.to_string(),
+            status: error.status().map(|s| s.as_u16()),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Pass successful responses through and turn the rest into errors
+///
+/// A 404 from a chat endpoint means the model is not installed or not
+/// offered, which is reported as `ModelLoadingFailed` so a fallback can try
+/// a service that has it.
+pub(crate) async fn check_status(
+    service: &str,
+    model: &str,
+    response: Response,
+) -> LlmResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = error_message(&body).unwrap_or_else(|| status.to_string());
+    if status.as_u16() == 404 {
+        return Err(AnalysisError::ModelLoadingFailed {
+            model_name: model.to_string(),
+            error: message,
+        }
+        .into());
+    }
+    Err(LlmError::Http {
+        service: service.to_string(),
+        status: Some(status.as_u16()),
+        message,
+    })
+}
+
+/// The message from an `{"error": "..."}` or `{"error": {"message": "..."}}` body
+pub(crate) fn error_message(body: &str) -> Option<String> {
+    let error = serde_json::from_str::<Value>(body)
+        .ok()?
+        .get("error")?
+        .clone();
+    match error {
+        Value::String(message) => Some(message),
+        error => error
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    }
+}
+
+pub(crate) fn parse<T: serde::de::DeserializeOwned>(service: &str, text: &str) -> LlmResult<T> {
+    serde_json::from_str(text).map_err(|e| LlmError::Parse {
+        service: service.to_string(),
+        message: format!("invalid JSON: {}", e),
+    })
+}
+
+struct Lines {
+    response: Option<Response>,
+    buffer: Vec<u8>,
+}
+
+/// Split a streamed body into lines, failing if no bytes arrive for `idle`
+pub(crate) fn lines(
+    service: &'static str,
+    response: Response,
+    idle: Duration,
+) -> stream::BoxStream<'static, LlmResult<String>> {
+    let state = Lines {
+        response: Some(response),
+        buffer: Vec::new(),
+    };
+    stream::unfold(state, move |mut state| async move {
+        loop {
+            if let Some(end) = state.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = state.buffer.drain(..=end).collect();
+                return Some((decode(service, line), state));
+            }
+            let response = state.response.as_mut()?;
+            let failure = match tokio::time::timeout(idle, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => {
+                    state.buffer.extend_from_slice(&chunk);
+                    continue;
+                }
+                Ok(Ok(None)) => {
+                    state.response = None;
+                    if state.buffer.is_empty() {
+                        return None;
+                    }
+                    let line = std::mem::take(&mut state.buffer);
+                    return Some((decode(service, line), state));
+                }
+                Ok(Err(e)) => request_error(service, idle, e),
+                Err(_) => timed_out(idle),
+            };
+            state.response = None;
+            state.buffer.clear();
+            return Some((Err(failure), state));
+        }
+    })
+    .boxed()
+}
+
+fn decode(service: &str, line: Vec<u8>) -> LlmResult<String> {
+    String::from_utf8(line)
+        .map(|line| line.trim_end_matches(['\r', '\n']).to_string())
+        .map_err(|e| LlmError::Parse {
+            service: service.to_string(),
+            message: e.to_string(),
+        })
+}
@@ -0,0 +1,21 @@
+//! LLM clients for narrative analysis
+//!
+//! [`LlmClient`] is implemented for a local Ollama server and for any
+//! OpenAI-compatible chat completions API. [`FallbackClient`] chains them in
+//! priority order, so a missing local model or an offline Ollama falls through
+//! to the hosted API configured under `[ai]`.
+
+pub mod client;
+pub mod fallback;
+mod http;
+pub mod ollama;
+pub mod openai;
+pub mod settings;
+
+pub use client::{
+    ChatMessage, ChatRequest, ChatResponse, ChatRole, LlmClient, LlmError, LlmResult, TokenStream,
+};
+pub use fallback::FallbackClient;
+pub use ollama::{OllamaClient, OllamaConfig};
+pub use openai::{OpenAiClient, OpenAiConfig};
+pub use settings::LlmConfig;
@@ -0,0 +1,277 @@
+use async_trait::async_trait;
+use futures::{future, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::client::{
+    ChatMessage, ChatRequest, ChatResponse, LlmClient, LlmError, LlmResult, TokenStream,
+};
+use crate::http;
+
+const SERVICE: &str = "ollama";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OllamaConfig {
+    /// `[ai] ollama_url`
+    pub base_url: String,
+
+    /// `[ai] ollama_model`
+    pub model: String,
+
+    /// Limit on a whole reply, or on each pause while one is streamed
+    pub timeout: Duration,
+}
+
+impl OllamaConfig {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            model: model.into(),
+            // Local models can take a while to load on the first request
+            timeout: Duration::from_secs(120),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// [`LlmClient`] for a local Ollama server's `/api/chat` endpoint
+pub struct OllamaClient {
+    config: OllamaConfig,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct OllamaRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    options: Options,
+}
+
+#[derive(Serialize)]
+struct Options {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+/// A whole reply, or one line of a streamed one
+#[derive(Deserialize)]
+struct OllamaReply {
+    #[serde(default)]
+    model: String,
+    message: Option<ReplyMessage>,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReplyMessage {
+    content: String,
+}
+
+impl OllamaClient {
+    pub fn new(config: OllamaConfig) -> LlmResult<Self> {
+        let client = http::build_client(SERVICE, config.timeout)?;
+        Ok(Self { config, client })
+    }
+
+    fn request(&self, request: &ChatRequest, stream: bool) -> reqwest::RequestBuilder {
+        let url = format!("{}/api/chat", self.config.base_url.trim_end_matches('/'));
+        tracing::debug!(service = SERVICE, model = %self.config.model, stream, "Sending chat");
+        self.client.post(url).json(&OllamaRequest {
+            model: &self.config.model,
+            messages: &request.messages,
+            stream,
+            options: Options {
+                temperature: request.temperature,
+                num_predict: request.max_tokens,
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    fn name(&self) -> &str {
+        SERVICE
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> LlmResult<ChatResponse> {
+        let timeout = self.config.timeout;
+        let response = self
+            .request(request, false)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| http::request_error(SERVICE, timeout, e))?;
+        let response = http::check_status(SERVICE, &self.config.model, response).await?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| http::request_error(SERVICE, timeout, e))?;
+
+        let reply: OllamaReply = http::parse(SERVICE, &text)?;
+        if let Some(message) = reply.error {
+            return Err(stream_error(message));
+        }
+        let message = reply.message.ok_or_else(|| LlmError::Parse {
+            service: SERVICE.to_string(),
+            message: "reply has no message".to_string(),
+        })?;
+        Ok(ChatResponse {
+            content: message.content,
+            model: reply.model,
+            service: SERVICE.to_string(),
+            prompt_tokens: reply.prompt_eval_count,
+            completion_tokens: reply.eval_count,
+        })
+    }
+
+    async fn stream(&self, request: &ChatRequest) -> LlmResult<TokenStream> {
+        let timeout = self.config.timeout;
+        let response = tokio::time::timeout(timeout, self.request(request, true).send())
+            .await
+            .map_err(|_| http::timed_out(timeout))?
+            .map_err(|e| http::request_error(SERVICE, timeout, e))?;
+        let response = http::check_status(SERVICE, &self.config.model, response).await?;
+
+        // Newline-delimited JSON, one object per token and a final `done` object
+        let tokens = http::lines(SERVICE, response, timeout)
+            .try_filter(|line| future::ready(!line.trim().is_empty()))
+            .and_then(|line| future::ready(http::parse::<OllamaReply>(SERVICE, &line)))
+            .try_take_while(|reply| future::ready(Ok(!reply.done)))
+            .and_then(|reply| {
+                future::ready(match reply.error {
+                    Some(message) => Err(stream_error(message)),
+                    None => Ok(reply.message.map(|message| message.content)),
+                })
+            })
+            .try_filter_map(|content| future::ready(Ok(content.filter(|c| !c.is_empty()))));
+        Ok(tokens.boxed())
+    }
+}
+
+/// An error Ollama reports inside a successful response
+fn stream_error(message: String) -> LlmError {
+    LlmError::Http {
+        service: SERVICE.to_string(),
+        status: None,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shared_types::AnalysisError;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request() -> ChatRequest {
+        ChatRequest::new([
+            ChatMessage::system("You summarise market news."),
+            ChatMessage::user("Summarise AAPL today."),
+        ])
+        .with_temperature(0.2)
+    }
+
+    #[tokio::test]
+    async fn test_chat_and_stream() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "llama2",
+                "stream": false,
+                "messages": [{"role": "system"}, {"role": "user", "content": "Summarise AAPL today."}],
+                "options": {"temperature": 0.2},
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "llama2",
+                "message": {"role": "assistant", "content": "Apple rose 2%."},
+                "done": true,
+                "prompt_eval_count": 21,
+                "eval_count": 6,
+            })))
+            .mount(&server)
+            .await;
+        let lines = [
+            json!({"model": "llama2", "message": {"role": "assistant", "content": "Apple"}, "done": false}),
+            json!({"model": "llama2", "message": {"role": "assistant", "content": " rose"}, "done": false}),
+            json!({"model": "llama2", "message": {"role": "assistant", "content": " 2%."}, "done": false}),
+            json!({"model": "llama2", "message": {"role": "assistant", "content": ""}, "done": true}),
+        ];
+        let body: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(OllamaConfig::new(server.uri(), "llama2")).unwrap();
+        let reply = client.chat(&request()).await.unwrap();
+        assert_eq!(reply.content, "Apple rose 2%.");
+        assert_eq!(reply.service, "ollama");
+        assert_eq!(reply.completion_tokens, Some(6));
+
+        let tokens: Vec<String> = client
+            .stream(&request())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(tokens, ["Apple", " rose", " 2%."]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_model_and_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({"model": "mistral"})))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "error": "model \"mistral\" not found, try pulling it first"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({"model": "llama2"})))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let missing = OllamaClient::new(OllamaConfig::new(server.uri(), "mistral")).unwrap();
+        let error = missing.chat(&request()).await.unwrap_err();
+        assert_eq!(
+            error,
+            LlmError::Analysis(AnalysisError::ModelLoadingFailed {
+                model_name: "mistral".to_string(),
+                error: "model \"mistral\" not found, try pulling it first".to_string(),
+            })
+        );
+        assert!(error.is_retryable());
+
+        let slow = OllamaClient::new(
+            OllamaConfig::new(server.uri(), "llama2").with_timeout(Duration::from_millis(200)),
+        )
+        .unwrap();
+        assert!(matches!(
+            slow.stream(&request()).await,
+            Err(LlmError::Analysis(AnalysisError::AnalysisTimeout { .. }))
+        ));
+    }
+}
@@ -0,0 +1,357 @@
+use async_trait::async_trait;
+use futures::{future, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+use crate::client::{
+    ChatMessage, ChatRequest, ChatResponse, LlmClient, LlmError, LlmResult, TokenStream,
+};
+use crate::http;
+
+const SERVICE: &str = "openai";
+
+#[derive(Clone, PartialEq)]
+pub struct OpenAiConfig {
+    /// `[ai] openai_api_key`
+    pub api_key: String,
+
+    /// `[ai] openai_model`
+    pub model: String,
+
+    /// API root; any server implementing `/chat/completions` works
+    pub base_url: String,
+
+    /// Limit on a whole reply, or on each pause while one is streamed
+    pub timeout: Duration,
+}
+
+impl OpenAiConfig {
+    pub fn new(api_key: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: model.into(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl fmt::Debug for OpenAiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenAiConfig")
+            .field("api_key", &"<redacted>")
+            .field("model", &self.model)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// [`LlmClient`] for OpenAI's chat completions API and compatible servers
+pub struct OpenAiClient {
+    config: OpenAiConfig,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct CompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Completion {
+    #[serde(default)]
+    model: String,
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct ChoiceMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: Option<u32>,
+    completion_tokens: Option<u32>,
+}
+
+/// One server-sent event of a streamed completion
+#[derive(Deserialize)]
+struct CompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    delta: Delta,
+}
+
+#[derive(Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+impl OpenAiClient {
+    pub fn new(config: OpenAiConfig) -> LlmResult<Self> {
+        let client = http::build_client(SERVICE, config.timeout)?;
+        Ok(Self { config, client })
+    }
+
+    fn request(&self, request: &ChatRequest, stream: bool) -> reqwest::RequestBuilder {
+        let url = format!(
+            "{}/chat/completions",
+            self.config.base_url.trim_end_matches('/')
+        );
+        tracing::debug!(service = SERVICE, model = %self.config.model, stream, "Sending chat");
+        self.client
+            .post(url)
+            .bearer_auth(&self.config.api_key)
+            .json(&CompletionRequest {
+                model: &self.config.model,
+                messages: &request.messages,
+                stream,
+                temperature: request.temperature,
+                max_tokens: request.max_tokens,
+            })
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    fn name(&self) -> &str {
+        SERVICE
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> LlmResult<ChatResponse> {
+        let timeout = self.config.timeout;
+        let response = self
+            .request(request, false)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| http::request_error(SERVICE, timeout, e))?;
+        let response = http::check_status(SERVICE, &self.config.model, response).await?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| http::request_error(SERVICE, timeout, e))?;
+
+        let completion: Completion = http::parse(SERVICE, &text)?;
+        let content = completion
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| LlmError::Parse {
+                service: SERVICE.to_string(),
+                message: "completion has no choices".to_string(),
+            })?;
+        let usage = completion.usage;
+        Ok(ChatResponse {
+            content,
+            model: completion.model,
+            service: SERVICE.to_string(),
+            prompt_tokens: usage.as_ref().and_then(|usage| usage.prompt_tokens),
+            completion_tokens: usage.as_ref().and_then(|usage| usage.completion_tokens),
+        })
+    }
+
+    async fn stream(&self, request: &ChatRequest) -> LlmResult<TokenStream> {
+        let timeout = self.config.timeout;
+        let response = tokio::time::timeout(timeout, self.request(request, true).send())
+            .await
+            .map_err(|_| http::timed_out(timeout))?
+            .map_err(|e| http::request_error(SERVICE, timeout, e))?;
+        let response = http::check_status(SERVICE, &self.config.model, response).await?;
+
+        // Server-sent events: `data: {chunk}` lines, blank separators, and a
+        // closing `data: [DONE]`
+        let tokens = http::lines(SERVICE, response, timeout)
+            .try_filter_map(|line| {
+                future::ready(Ok(line
+                    .strip_prefix("data:")
+                    .map(|data| data.trim().to_string())))
+            })
+            .try_take_while(|data| future::ready(Ok(data != "[DONE]")))
+            .and_then(|data| future::ready(token(&data)))
+            .try_filter_map(|content| future::ready(Ok(content.filter(|c| !c.is_empty()))));
+        Ok(tokens.boxed())
+    }
+}
+
+fn token(data: &str) -> LlmResult<Option<String>> {
+    let chunk: CompletionChunk = http::parse(SERVICE, data)?;
+    if let Some(error) = chunk.error {
+        return Err(LlmError::Http {
+            service: SERVICE.to_string(),
+            status: None,
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .map_or_else(|| error.to_string(), str::to_string),
+        });
+    }
+    Ok(chunk
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.delta.content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shared_types::AnalysisError;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client(server: &MockServer, model: &str) -> OpenAiClient {
+        OpenAiClient::new(
+            OpenAiConfig::new("sk-test", model).with_base_url(format!("{}/v1", server.uri())),
+        )
+        .unwrap()
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest::new([ChatMessage::user("Is MSFT overbought?")]).with_max_tokens(64)
+    }
+
+    #[tokio::test]
+    async fn test_chat_and_stream() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(body_partial_json(json!({
+                "model": "gpt-3.5-turbo",
+                "stream": false,
+                "max_tokens": 64,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "gpt-3.5-turbo-0125",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "RSI is 74."}}],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 5, "total_tokens": 17},
+            })))
+            .mount(&server)
+            .await;
+        let events = [
+            json!({"choices": [{"index": 0, "delta": {"role": "assistant"}}]}),
+            json!({"choices": [{"index": 0, "delta": {"content": "RSI"}}]}),
+            json!({"choices": [{"index": 0, "delta": {"content": " is 74."}}]}),
+            json!({"choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]}),
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {}\n\n", event))
+            .chain(["data: [DONE]\n\n".to_string()])
+            .collect();
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+
+        let client = client(&server, "gpt-3.5-turbo");
+        let reply = client.chat(&request()).await.unwrap();
+        assert_eq!(reply.content, "RSI is 74.");
+        assert_eq!(reply.model, "gpt-3.5-turbo-0125");
+        assert_eq!(
+            (reply.prompt_tokens, reply.completion_tokens),
+            (Some(12), Some(5))
+        );
+
+        let tokens: Vec<String> = client
+            .stream(&request())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(tokens, ["RSI", " is 74."]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_classified() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"model": "gpt-5"})))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "error": {"message": "The model `gpt-5` does not exist", "code": "model_not_found"}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"model": "gpt-3.5-turbo"})))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": {"message": "Incorrect API key provided", "code": "invalid_api_key"}
+            })))
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            client(&server, "gpt-5").chat(&request()).await.unwrap_err(),
+            LlmError::Analysis(AnalysisError::ModelLoadingFailed {
+                model_name: "gpt-5".to_string(),
+                error: "The model `gpt-5` does not exist".to_string(),
+            })
+        );
+        let unauthorized = client(&server, "gpt-3.5-turbo")
+            .stream(&request())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            unauthorized,
+            LlmError::Http {
+                service: "openai".to_string(),
+                status: Some(401),
+                message: "Incorrect API key provided".to_string(),
+            }
+        );
+        assert!(!unauthorized.is_retryable());
+    }
+
+    #[test]
+    fn test_debug_redacts_the_api_key() {
+        let debug = format!("{:?}", OpenAiConfig::new("sk-secret", "gpt-4o"));
+        assert!(debug.contains("gpt-4o"));
+        assert!(!debug.contains("sk-secret"));
+    }
+}
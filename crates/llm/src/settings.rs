@@ -0,0 +1,95 @@
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::client::LlmResult;
+use crate::fallback::FallbackClient;
+use crate::ollama::{OllamaClient, OllamaConfig};
+use crate::openai::{OpenAiClient, OpenAiConfig};
+
+/// The key shipped in the sample configuration, which means "no key"
+const PLACEHOLDER_API_KEY: &str = "your-openai-api-key-here";
+
+/// The `[ai]` section of the application configuration
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LlmConfig {
+    pub ollama_url: String,
+    pub ollama_model: String,
+
+    #[serde(default)]
+    pub openai_api_key: Option<String>,
+
+    #[serde(default = "default_openai_model")]
+    pub openai_model: String,
+
+    /// Root of the OpenAI-compatible API, when it is not OpenAI itself
+    #[serde(default)]
+    pub openai_url: Option<String>,
+
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_openai_model() -> String {
+    "gpt-3.5-turbo".to_string()
+}
+
+fn default_timeout_seconds() -> u64 {
+    120
+}
+
+impl LlmConfig {
+    /// Read the `[ai]` section of a configuration file
+    pub fn from_file(path: &str) -> Result<Self, config::ConfigError> {
+        let settings = config::Config::builder()
+            .add_source(config::File::with_name(path))
+            .build()?;
+
+        settings.get("ai")
+    }
+
+    /// The OpenAI key, unless it is unset or still the sample placeholder
+    pub fn openai_api_key(&self) -> Option<&str> {
+        self.openai_api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty() && *key != PLACEHOLDER_API_KEY)
+    }
+
+    /// Ollama first, then the OpenAI-compatible API when a key is configured
+    pub fn client(&self) -> LlmResult<FallbackClient> {
+        let timeout = Duration::from_secs(self.timeout_seconds);
+        let ollama = OllamaClient::new(
+            OllamaConfig::new(&self.ollama_url, &self.ollama_model).with_timeout(timeout),
+        )?;
+        let mut client = FallbackClient::new("ai").with_client(Arc::new(ollama));
+
+        if let Some(api_key) = self.openai_api_key() {
+            let mut config = OpenAiConfig::new(api_key, &self.openai_model).with_timeout(timeout);
+            if let Some(url) = &self.openai_url {
+                config = config.with_base_url(url);
+            }
+            client = client.with_client(Arc::new(OpenAiClient::new(config)?));
+        }
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_development_config_uses_ollama_only() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/development.toml");
+        let mut config = LlmConfig::from_file(path).unwrap();
+        assert_eq!(config.ollama_url, "http://localhost:11434");
+        assert_eq!(config.ollama_model, "llama2");
+        assert_eq!(config.openai_model, "gpt-3.5-turbo");
+        assert_eq!(config.openai_api_key(), None);
+        assert_eq!(config.client().unwrap().services(), ["ollama"]);
+
+        config.openai_api_key = Some("sk-live".to_string());
+        assert_eq!(config.client().unwrap().services(), ["ollama", "openai"]);
+    }
+}
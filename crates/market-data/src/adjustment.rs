@@ -0,0 +1,284 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use shared_types::{CorporateAction, DataAdjustment, MarketDataResponse, OHLCV};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AdjustmentError {
+    #[error("Split of {symbol} on {ex_date} has non-positive ratio {ratio}")]
+    InvalidSplitRatio {
+        symbol: String,
+        ex_date: NaiveDate,
+        ratio: Decimal,
+    },
+
+    #[error("Dividend of {amount} for {symbol} on {ex_date} is not below the prior close {close}")]
+    DividendExceedsClose {
+        symbol: String,
+        ex_date: NaiveDate,
+        amount: Decimal,
+        close: Decimal,
+    },
+
+    #[error("Bars are already {0:?}-adjusted")]
+    AlreadyAdjusted(DataAdjustment),
+}
+
+/// Back-adjusts raw bars for splits and dividends
+///
+/// Bars before an action's ex-date are restated on the share basis of the
+/// `as_of` date: a split divides prices and multiplies volume by its ratio,
+/// and a dividend scales prices by `1 - amount / prior close`, where the prior
+/// close is the raw close of the last bar before the ex-date. Actions after
+/// `as_of` are ignored, so history can be viewed as it looked on that date.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorporateActionAdjuster {
+    actions: Vec<CorporateAction>,
+}
+
+impl CorporateActionAdjuster {
+    pub fn new(actions: Vec<CorporateAction>) -> Self {
+        Self { actions }
+    }
+
+    /// Adjust `bars` in place, returning the actions that changed at least one bar
+    ///
+    /// Only actions for each bar's own symbol are applied. Prices are rounded to
+    /// four decimal places and volume to whole units.
+    pub fn adjust(
+        &self,
+        bars: &mut [OHLCV],
+        adjustment: &DataAdjustment,
+        as_of: NaiveDate,
+    ) -> Result<Vec<CorporateAction>, AdjustmentError> {
+        let mut actions: Vec<&CorporateAction> = self
+            .actions
+            .iter()
+            .filter(|action| action.is_covered_by(adjustment) && action.ex_date() <= as_of)
+            .collect();
+        for action in &actions {
+            if let CorporateAction::Split(split) = action {
+                if split.ratio <= Decimal::ZERO {
+                    return Err(AdjustmentError::InvalidSplitRatio {
+                        symbol: split.symbol.full_identifier(),
+                        ex_date: split.ex_date,
+                        ratio: split.ratio,
+                    });
+                }
+            }
+        }
+        // Newest first, matching the walk over the bars below
+        actions.sort_by_key(|action| std::cmp::Reverse(action.ex_date()));
+
+        let mut order: Vec<usize> = (0..bars.len()).collect();
+        order.sort_by_key(|index| std::cmp::Reverse(bars[*index].timestamp));
+
+        let mut applied = vec![false; actions.len()];
+        let mut factors: Vec<(String, Decimal, Decimal)> = Vec::new();
+        for index in order {
+            let bar = &mut bars[index];
+            let key = bar.symbol.full_identifier();
+            let date = bar.timestamp.date_naive();
+
+            let position = match factors.iter().position(|(symbol, _, _)| *symbol == key) {
+                Some(position) => position,
+                None => {
+                    factors.push((key.clone(), Decimal::ONE, Decimal::ONE));
+                    factors.len() - 1
+                }
+            };
+            let (_, price_factor, volume_factor) = &mut factors[position];
+
+            // Fold in actions taking effect after this bar, the newest bar before
+            // their ex-date; its raw close is the dividend's reference price
+            for (action, applied) in actions.iter().zip(applied.iter_mut()) {
+                if *applied || action.ex_date() <= date || action.symbol().full_identifier() != key
+                {
+                    continue;
+                }
+                match action {
+                    CorporateAction::Split(split) => {
+                        *price_factor /= split.ratio;
+                        *volume_factor *= split.ratio;
+                    }
+                    CorporateAction::Dividend(dividend) => {
+                        if dividend.amount >= bar.close {
+                            return Err(AdjustmentError::DividendExceedsClose {
+                                symbol: key,
+                                ex_date: dividend.ex_date,
+                                amount: dividend.amount,
+                                close: bar.close,
+                            });
+                        }
+                        *price_factor *= (bar.close - dividend.amount) / bar.close;
+                    }
+                }
+                *applied = true;
+            }
+
+            if *price_factor != Decimal::ONE || *volume_factor != Decimal::ONE {
+                let scale = |price: Decimal| (price * *price_factor).round_dp(4);
+                bar.open = scale(bar.open);
+                bar.high = scale(bar.high);
+                bar.low = scale(bar.low);
+                bar.close = scale(bar.close);
+                bar.volume = (bar.volume * *volume_factor).round_dp(0);
+            }
+        }
+
+        let mut applied: Vec<CorporateAction> = actions
+            .into_iter()
+            .zip(applied)
+            .filter(|(_, applied)| *applied)
+            .map(|(action, _)| action.clone())
+            .collect();
+        applied.reverse();
+        Ok(applied)
+    }
+
+    /// Adjust a response of raw bars and record what was applied on it
+    pub fn adjust_response(
+        &self,
+        response: &mut MarketDataResponse,
+        adjustment: &DataAdjustment,
+        as_of: NaiveDate,
+    ) -> Result<(), AdjustmentError> {
+        if response.adjustment != DataAdjustment::None {
+            return Err(AdjustmentError::AlreadyAdjusted(
+                response.adjustment.clone(),
+            ));
+        }
+        response.corporate_actions = self.adjust(&mut response.bars, adjustment, as_of)?;
+        response.adjustment = adjustment.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use shared_types::{Dividend, Exchange, Split, Symbol, TimeFrame};
+
+    fn aapl() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// Unadjusted daily closes around Apple's 7:1 (2014) and 4:1 (2020) splits
+    /// and its August 2020 dividend
+    fn history() -> Vec<OHLCV> {
+        [
+            (date(2014, 6, 6), "645.57", 12_497_800),
+            (date(2014, 6, 9), "93.70", 75_415_000),
+            (date(2020, 8, 6), "455.61", 43_679_900),
+            (date(2020, 8, 7), "444.45", 49_511_400),
+            (date(2020, 8, 28), "499.23", 46_907_500),
+            (date(2020, 8, 31), "129.04", 225_702_700),
+        ]
+        .into_iter()
+        .map(|(day, close, volume)| {
+            let close: Decimal = close.parse().unwrap();
+            OHLCV::new(
+                aapl(),
+                TimeFrame::OneDay,
+                Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).unwrap()),
+                close,
+                close,
+                close,
+                close,
+                Decimal::from(volume),
+            )
+            .unwrap()
+        })
+        .collect()
+    }
+
+    fn adjuster() -> CorporateActionAdjuster {
+        CorporateActionAdjuster::new(vec![
+            Split::new(aapl(), date(2014, 6, 9), Decimal::new(7, 0)).into(),
+            Dividend::new(aapl(), date(2020, 8, 7), Decimal::new(82, 2)).into(),
+            Split::new(aapl(), date(2020, 8, 31), Decimal::new(4, 0)).into(),
+        ])
+    }
+
+    fn closes(bars: &[OHLCV]) -> Vec<Decimal> {
+        bars.iter().map(|bar| bar.close).collect()
+    }
+
+    #[test]
+    fn test_split_history_matches_published_adjusted_prices() {
+        let mut bars = history();
+        let applied = adjuster()
+            .adjust(&mut bars, &DataAdjustment::Splits, date(2024, 1, 1))
+            .unwrap();
+
+        assert_eq!(applied.len(), 2);
+        // 645.57 / 28, 93.70 / 4, 455.61 / 4, ...
+        assert_eq!(
+            closes(&bars),
+            ["23.0561", "23.425", "113.9025", "111.1125", "124.8075", "129.04"]
+                .map(|close| close.parse::<Decimal>().unwrap())
+        );
+        assert_eq!(bars[0].volume, Decimal::from(349_938_400));
+        assert_eq!(bars[5].volume, Decimal::from(225_702_700));
+
+        // As of 2018 only the 2014 split has happened
+        let mut bars = history();
+        adjuster()
+            .adjust(&mut bars, &DataAdjustment::Splits, date(2018, 1, 1))
+            .unwrap();
+        assert_eq!(bars[0].close, "92.2243".parse::<Decimal>().unwrap());
+        assert_eq!(bars[4].close, "499.23".parse::<Decimal>().unwrap());
+    }
+
+    #[test]
+    fn test_dividends_use_prior_close() {
+        let mut bars = history();
+        let applied = adjuster()
+            .adjust(&mut bars, &DataAdjustment::Dividends, date(2024, 1, 1))
+            .unwrap();
+        assert_eq!(applied.len(), 1);
+
+        // 455.61 * (1 - 0.82 / 455.61) = 455.61 - 0.82
+        assert_eq!(bars[2].close, "454.79".parse::<Decimal>().unwrap());
+        // 645.57 * 454.79 / 455.61
+        assert_eq!(bars[0].close, "644.4081".parse::<Decimal>().unwrap());
+        assert_eq!(bars[0].volume, Decimal::from(12_497_800));
+        assert_eq!(bars[3].close, "444.45".parse::<Decimal>().unwrap());
+    }
+
+    #[test]
+    fn test_response_records_applied_actions() {
+        let mut response = MarketDataResponse {
+            symbol: aapl(),
+            timeframe: TimeFrame::OneDay,
+            bars: history()[3..].to_vec(),
+            adjustment: DataAdjustment::None,
+            corporate_actions: Vec::new(),
+            includes_extended_hours: false,
+            last_updated: Utc::now(),
+        };
+
+        adjuster()
+            .adjust_response(&mut response, &DataAdjustment::All, date(2024, 1, 1))
+            .unwrap();
+        // Only the 2020 split falls inside the returned bars
+        assert_eq!(response.adjustment, DataAdjustment::All);
+        assert_eq!(
+            response.corporate_actions,
+            vec![CorporateAction::Split(Split::new(
+                aapl(),
+                date(2020, 8, 31),
+                Decimal::new(4, 0)
+            ))]
+        );
+        assert_eq!(
+            adjuster().adjust_response(&mut response, &DataAdjustment::All, date(2024, 1, 1)),
+            Err(AdjustmentError::AlreadyAdjusted(DataAdjustment::All))
+        );
+    }
+}
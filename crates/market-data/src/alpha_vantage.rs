@@ -0,0 +1,864 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+use shared_types::{
+    AssetClass, CorporateAction, DataAdjustment, Dividend, Exchange, MarketDataError,
+    MarketDataRequest, MarketDataResponse, Quote, Split, Symbol, SymbolMatch, SymbolNormalizer,
+    SymbolSearchRequest, SymbolSearchResponse, TimeFrame, Vendor, OHLCV,
+};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::provider::{MarketDataProvider, ProviderError, ProviderResult};
+use crate::rate_limit::RateLimiter;
+
+const PROVIDER: &str = "alpha_vantage";
+
+/// The compact output size returns this many of the most recent bars
+const COMPACT_OUTPUT_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaVantageConfig {
+    /// `[external_apis] alpha_vantage_key`
+    pub api_key: String,
+
+    /// API root, overridden in tests to point at a mock server
+    pub base_url: String,
+
+    /// `[external_apis] alpha_vantage_requests_per_minute`
+    pub requests_per_minute: u32,
+
+    /// Per-request timeout
+    pub timeout: Duration,
+}
+
+impl AlphaVantageConfig {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: "https://www.alphavantage.co".to_string(),
+            requests_per_minute: 5,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = requests_per_minute;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// [`MarketDataProvider`] backed by the Alpha Vantage REST API
+///
+/// Covers US and major international equities and ETFs. The configured
+/// per-minute budget is enforced locally: once it is spent, calls fail with
+/// `MarketDataError::RateLimitExceeded` instead of waiting, so callers can
+/// fall back to another provider.
+pub struct AlphaVantageProvider {
+    config: AlphaVantageConfig,
+    client: reqwest::Client,
+    limiter: RateLimiter,
+    normalizer: SymbolNormalizer,
+}
+
+/// Where a bar series comes from and how its prices must be adjusted
+struct SeriesQuery {
+    function: &'static str,
+    interval: Option<&'static str>,
+    adjusted_endpoint: bool,
+}
+
+impl AlphaVantageProvider {
+    pub fn new(config: AlphaVantageConfig) -> ProviderResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| ProviderError::Http {
+                provider: PROVIDER.to_string(),
+                status: None,
+                message: e.to_string(),
+            })?;
+        let limiter = RateLimiter::per_minute(config.requests_per_minute);
+
+        Ok(Self {
+            config,
+            client,
+            limiter,
+            normalizer: SymbolNormalizer::new(),
+        })
+    }
+
+    /// Resolve US search results against the symbol master's listings
+    ///
+    /// Alpha Vantage only reports "United States" as the region, so without
+    /// a listing a US result cannot be placed on NYSE or NASDAQ and is left
+    /// out of searches.
+    pub fn with_normalizer(mut self, normalizer: SymbolNormalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Issue a `/query` call and surface Alpha Vantage's in-body errors
+    async fn query(&self, subject: &str, params: &[(&str, &str)]) -> ProviderResult<Value> {
+        if let Err(wait) = self.limiter.try_acquire() {
+            return Err(rate_limited(wait));
+        }
+
+        let url = format!("{}/query", self.config.base_url.trim_end_matches('/'));
+        tracing::debug!(provider = PROVIDER, ?params, "Sending request");
+
+        let response = self
+            .client
+            .get(&url)
+            .query(params)
+            .query(&[("apikey", self.config.api_key.as_str())])
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() || e.is_connect() {
+                    ProviderError::MarketData(MarketDataError::DataProviderUnavailable {
+                        provider: PROVIDER.to_string(),
+                    })
+                } else {
+                    http_error(e.status().map(|s| s.as_u16()), e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if status.as_u16() == 429 {
+            return Err(rate_limited(Duration::from_secs(60)));
+        }
+        if !status.is_success() {
+            return Err(http_error(Some(status.as_u16()), status.to_string()));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| parse_error(format!("invalid JSON: {}", e)))?;
+
+        if body.get("Error Message").is_some() {
+            return Err(MarketDataError::SymbolNotFound {
+                symbol: subject.to_string(),
+            }
+            .into());
+        }
+        // Throttling is reported with HTTP 200 and a "Note" or "Information" body
+        if let Some(message) = body
+            .get("Note")
+            .or_else(|| body.get("Information"))
+            .and_then(Value::as_str)
+        {
+            tracing::warn!(provider = PROVIDER, message, "Request throttled");
+            return Err(rate_limited(Duration::from_secs(60)));
+        }
+
+        Ok(body)
+    }
+
+    fn series_query(request: &MarketDataRequest) -> ProviderResult<SeriesQuery> {
+        let intraday = |interval| SeriesQuery {
+            function: "TIME_SERIES_INTRADAY",
+            interval: Some(interval),
+            adjusted_endpoint: false,
+        };
+        let adjusted = !matches!(request.adjustment, DataAdjustment::None);
+        let periodic = |raw, adjusted_function| SeriesQuery {
+            function: if adjusted { adjusted_function } else { raw },
+            interval: None,
+            adjusted_endpoint: adjusted,
+        };
+
+        let query = match request.timeframe {
+            TimeFrame::OneMinute => intraday("1min"),
+            TimeFrame::FiveMinutes => intraday("5min"),
+            TimeFrame::FifteenMinutes => intraday("15min"),
+            TimeFrame::ThirtyMinutes => intraday("30min"),
+            TimeFrame::OneHour => intraday("60min"),
+            TimeFrame::OneDay => periodic("TIME_SERIES_DAILY", "TIME_SERIES_DAILY_ADJUSTED"),
+            TimeFrame::OneWeek => periodic("TIME_SERIES_WEEKLY", "TIME_SERIES_WEEKLY_ADJUSTED"),
+            TimeFrame::OneMonth => periodic("TIME_SERIES_MONTHLY", "TIME_SERIES_MONTHLY_ADJUSTED"),
+            ref other => {
+                return Err(unsupported(format!("{} bars", other)));
+            }
+        };
+
+        // Only daily data reports split coefficients; intraday and weekly/monthly
+        // adjusted series are adjusted for splits and dividends together
+        let partial = matches!(
+            request.adjustment,
+            DataAdjustment::Splits | DataAdjustment::Dividends
+        );
+        if partial && request.timeframe != TimeFrame::OneDay {
+            return Err(unsupported(format!(
+                "{:?} adjustment for {} bars",
+                request.adjustment, request.timeframe
+            )));
+        }
+
+        Ok(query)
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for AlphaVantageProvider {
+    fn name(&self) -> &str {
+        PROVIDER
+    }
+
+    async fn fetch_bars(&self, request: &MarketDataRequest) -> ProviderResult<MarketDataResponse> {
+        check_asset_class(&request.symbol)?;
+        if let (Some(start), Some(end)) = (request.start_time, request.end_time) {
+            if start > end {
+                return Err(MarketDataError::InvalidTimeRange {
+                    start: start.to_rfc3339(),
+                    end: end.to_rfc3339(),
+                }
+                .into());
+            }
+        }
+
+        let series = Self::series_query(request)?;
+        let ticker = provider_symbol(&request.symbol);
+        let compact =
+            request.start_time.is_none() && request.limit.is_some_and(|n| n <= COMPACT_OUTPUT_SIZE);
+        let adjust_intraday = !matches!(request.adjustment, DataAdjustment::None);
+
+        let mut params = vec![
+            ("function", series.function),
+            ("symbol", ticker.as_str()),
+            ("outputsize", if compact { "compact" } else { "full" }),
+        ];
+        if let Some(interval) = series.interval {
+            params.push(("interval", interval));
+            params.push(("adjusted", if adjust_intraday { "true" } else { "false" }));
+            params.push((
+                "extended_hours",
+                if request.include_extended_hours {
+                    "true"
+                } else {
+                    "false"
+                },
+            ));
+        }
+
+        let body = self.query(&ticker, &params).await?;
+        let (mut bars, mut corporate_actions) =
+            parse_series(&body, request, series.adjusted_endpoint)?;
+
+        bars.retain(|bar| {
+            request
+                .start_time
+                .is_none_or(|start| bar.timestamp >= start)
+                && request.end_time.is_none_or(|end| bar.timestamp <= end)
+        });
+        if let Some(limit) = request.limit {
+            let excess = bars.len().saturating_sub(limit as usize);
+            bars.drain(..excess);
+        }
+        if bars.is_empty() {
+            return Err(MarketDataError::NoDataAvailable.into());
+        }
+        // Only report actions that moved at least one of the returned bars
+        let first_date = bars[0].timestamp.date_naive();
+        corporate_actions.retain(|action| {
+            action.is_covered_by(&request.adjustment) && action.ex_date() > first_date
+        });
+
+        Ok(MarketDataResponse {
+            symbol: request.symbol.clone(),
+            timeframe: request.timeframe.clone(),
+            bars,
+            adjustment: request.adjustment.clone(),
+            corporate_actions,
+            includes_extended_hours: series.interval.is_some() && request.include_extended_hours,
+            last_updated: Utc::now(),
+        })
+    }
+
+    async fn search_symbols(
+        &self,
+        request: &SymbolSearchRequest,
+    ) -> ProviderResult<SymbolSearchResponse> {
+        let body = self
+            .query(
+                &request.query,
+                &[("function", "SYMBOL_SEARCH"), ("keywords", &request.query)],
+            )
+            .await?;
+        let matches = body
+            .get("bestMatches")
+            .and_then(Value::as_array)
+            .ok_or_else(|| parse_error("missing bestMatches"))?;
+
+        let query = request.query.to_uppercase();
+        let mut symbols: Vec<SymbolMatch> = matches
+            .iter()
+            .filter_map(Value::as_object)
+            .filter_map(|entry| parse_search_match(&self.normalizer, entry, &query))
+            .filter(|found| {
+                request
+                    .asset_class
+                    .as_ref()
+                    .is_none_or(|class| &found.symbol.asset_class == class)
+                    && request
+                        .exchange
+                        .as_ref()
+                        .is_none_or(|exchange| &found.symbol.exchange == exchange)
+            })
+            .collect();
+
+        let total_matches = symbols.len() as u64;
+        if let Some(limit) = request.limit {
+            symbols.truncate(limit as usize);
+        }
+
+        Ok(SymbolSearchResponse {
+            query: request.query.clone(),
+            symbols,
+            total_matches,
+        })
+    }
+
+    async fn latest_quote(&self, symbol: &Symbol) -> ProviderResult<Quote> {
+        check_asset_class(symbol)?;
+        let ticker = provider_symbol(symbol);
+        let body = self
+            .query(
+                &ticker,
+                &[("function", "GLOBAL_QUOTE"), ("symbol", &ticker)],
+            )
+            .await?;
+
+        let quote = body
+            .get("Global Quote")
+            .and_then(Value::as_object)
+            .ok_or_else(|| parse_error("missing Global Quote"))?;
+        // Unknown symbols come back as an empty quote object
+        if quote.is_empty() {
+            return Err(MarketDataError::SymbolNotFound { symbol: ticker }.into());
+        }
+
+        let trading_day = NaiveDate::parse_from_str(text(quote, "latest trading day")?, "%Y-%m-%d")
+            .map_err(|e| parse_error(format!("latest trading day: {}", e)))?;
+        let timezone = Tz::from_str(&symbol.timezone).unwrap_or(Tz::UTC);
+
+        Ok(Quote {
+            symbol: symbol.clone(),
+            price: decimal(quote, "price")?,
+            open: decimal(quote, "open")?,
+            high: decimal(quote, "high")?,
+            low: decimal(quote, "low")?,
+            previous_close: decimal(quote, "previous close")?,
+            change: decimal(quote, "change")?,
+            change_percent: parse_decimal(
+                "change percent",
+                text(quote, "change percent")?.trim_end_matches('%'),
+            )?,
+            volume: decimal(quote, "volume")?,
+            timestamp: local_to_utc(
+                timezone,
+                trading_day.and_hms_opt(0, 0, 0).unwrap_or_default(),
+            )?,
+        })
+    }
+}
+
+/// Parse a `Time Series (...)` object into bars, oldest first
+///
+/// Adjusted series also yield the splits and dividends they report, newest first.
+fn parse_series(
+    body: &Value,
+    request: &MarketDataRequest,
+    adjusted_endpoint: bool,
+) -> ProviderResult<(Vec<OHLCV>, Vec<CorporateAction>)> {
+    let timezone = body
+        .get("Meta Data")
+        .and_then(Value::as_object)
+        .and_then(|meta| lookup(meta, "Time Zone"))
+        .and_then(Value::as_str)
+        .map(|zone| Tz::from_str(zone).map_err(|e| parse_error(format!("time zone: {}", e))))
+        .transpose()?
+        .unwrap_or(chrono_tz::US::Eastern);
+
+    let series = body
+        .as_object()
+        .and_then(|root| {
+            root.iter()
+                .find(|(key, _)| key.contains("Time Series"))
+                .and_then(|(_, series)| series.as_object())
+        })
+        .ok_or_else(|| parse_error("missing time series"))?;
+
+    let mut rows = Vec::with_capacity(series.len());
+    for (stamp, entry) in series {
+        let entry = entry
+            .as_object()
+            .ok_or_else(|| parse_error(format!("bar {} is not an object", stamp)))?;
+        let local = NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| {
+                NaiveDate::parse_from_str(stamp, "%Y-%m-%d")
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .map_err(|e| parse_error(format!("timestamp {}: {}", stamp, e)))?;
+        rows.push((local_to_utc(timezone, local)?, local.date(), entry));
+    }
+    rows.sort_by_key(|(timestamp, _, _)| *timestamp);
+
+    // Walk newest to oldest so each bar sees the splits that happened after it
+    let mut bars = Vec::with_capacity(rows.len());
+    let mut later_splits = Decimal::ONE;
+    let mut actions = Vec::new();
+    for (timestamp, date, entry) in rows.into_iter().rev() {
+        let raw = [
+            decimal(entry, "open")?,
+            decimal(entry, "high")?,
+            decimal(entry, "low")?,
+            decimal(entry, "close")?,
+        ];
+        let volume = decimal(entry, "volume")?;
+
+        let (price_factor, volume_factor) = if adjusted_endpoint {
+            let total = if raw[3].is_zero() {
+                Decimal::ONE
+            } else {
+                decimal(entry, "adjusted close")? / raw[3]
+            };
+            match request.adjustment {
+                DataAdjustment::Splits => (Decimal::ONE / later_splits, later_splits),
+                DataAdjustment::Dividends => (total * later_splits, Decimal::ONE),
+                _ => (total, later_splits),
+            }
+        } else {
+            (Decimal::ONE, Decimal::ONE)
+        };
+
+        if let Some(split) = lookup(entry, "split coefficient") {
+            let split = parse_decimal("split coefficient", split.as_str().unwrap_or("1"))?;
+            if !split.is_zero() {
+                later_splits *= split;
+            }
+            if !split.is_zero() && split != Decimal::ONE {
+                actions.push(Split::new(request.symbol.clone(), date, split).into());
+            }
+        }
+        if adjusted_endpoint {
+            if let Some(amount) = lookup(entry, "dividend amount") {
+                let amount = parse_decimal("dividend amount", amount.as_str().unwrap_or("0"))?;
+                if amount > Decimal::ZERO {
+                    actions.push(Dividend::new(request.symbol.clone(), date, amount).into());
+                }
+            }
+        }
+
+        let [open, high, low, close] = raw.map(|price| (price * price_factor).round_dp(4));
+        let bar = OHLCV::new(
+            request.symbol.clone(),
+            request.timeframe.clone(),
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            (volume * volume_factor).round_dp(0),
+        )
+        .map_err(|e| parse_error(format!("bar {}: {}", timestamp, e)))?;
+        bars.push(bar);
+    }
+
+    bars.reverse();
+    Ok((bars, actions))
+}
+
+fn parse_search_match(
+    normalizer: &SymbolNormalizer,
+    entry: &Map<String, Value>,
+    query: &str,
+) -> Option<SymbolMatch> {
+    let ticker = lookup(entry, "symbol")?.as_str()?;
+    let name = lookup(entry, "name")?.as_str()?;
+    let region = lookup(entry, "region")?.as_str()?;
+    let currency = lookup(entry, "currency")?.as_str()?;
+    let asset_class = match lookup(entry, "type")?.as_str()? {
+        "Equity" => AssetClass::Stock,
+        "ETF" => AssetClass::ETF,
+        _ => return None,
+    };
+
+    let (code, exchange, timezone) = match ticker.rsplit_once('.') {
+        Some((code, "LON")) => (code, Exchange::LSE, "Europe/London"),
+        Some((code, "DEX")) => (code, Exchange::XETRA, "Europe/Berlin"),
+        Some((code, "TYO")) => (code, Exchange::TSE, "Asia/Tokyo"),
+        _ if region == "United States" => {
+            let Some(listing) = normalizer.listing(ticker) else {
+                tracing::debug!(provider = PROVIDER, ticker, "Skipping unlisted US match");
+                return None;
+            };
+            (ticker, listing.exchange.clone(), "America/New_York")
+        }
+        _ => (ticker, Exchange::Other(region.to_string()), "UTC"),
+    };
+
+    let mut symbol = Symbol::new(
+        code.to_string(),
+        name.to_string(),
+        asset_class,
+        exchange,
+        currency.to_string(),
+    )
+    .ok()?;
+    symbol.timezone = timezone.to_string();
+    symbol.add_metadata("alpha_vantage_symbol", ticker);
+    symbol.add_metadata("region", region);
+
+    let mut matched_fields = Vec::new();
+    if symbol.code.starts_with(query) || ticker.to_uppercase().starts_with(query) {
+        matched_fields.push("code".to_string());
+    }
+    if name.to_uppercase().contains(query) {
+        matched_fields.push("name".to_string());
+    }
+
+    Some(SymbolMatch {
+        symbol,
+        match_score: lookup(entry, "matchScore")
+            .and_then(Value::as_str)
+            .and_then(|score| score.parse().ok())
+            .unwrap_or(0.0),
+        matched_fields,
+    })
+}
+
+/// Ticker as Alpha Vantage expects it (e.g. "TSCO.LON" for London listings)
+fn provider_symbol(symbol: &Symbol) -> String {
+    Vendor::AlphaVantage.ticker(symbol)
+}
+
+fn check_asset_class(symbol: &Symbol) -> ProviderResult<()> {
+    match symbol.asset_class {
+        AssetClass::Stock | AssetClass::ETF => Ok(()),
+        ref other => Err(unsupported(format!("{:?} symbols", other))),
+    }
+}
+
+/// Find a field by name, ignoring Alpha Vantage's "1. " / "01. " ordinal prefixes
+fn lookup<'a>(object: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    object.iter().find_map(|(key, value)| {
+        let field = key
+            .split_once(". ")
+            .map_or(key.as_str(), |(_, field)| field);
+        (field == name).then_some(value)
+    })
+}
+
+fn text<'a>(object: &'a Map<String, Value>, name: &str) -> ProviderResult<&'a str> {
+    lookup(object, name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| parse_error(format!("missing field '{}'", name)))
+}
+
+fn decimal(object: &Map<String, Value>, name: &str) -> ProviderResult<Decimal> {
+    parse_decimal(name, text(object, name)?)
+}
+
+fn parse_decimal(name: &str, value: &str) -> ProviderResult<Decimal> {
+    Decimal::from_str(value).map_err(|e| parse_error(format!("{} '{}': {}", name, value, e)))
+}
+
+fn local_to_utc(timezone: Tz, local: NaiveDateTime) -> ProviderResult<DateTime<Utc>> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| parse_error(format!("{} does not exist in {}", local, timezone)))
+}
+
+fn rate_limited(wait: Duration) -> ProviderError {
+    let retry_after = Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+    MarketDataError::RateLimitExceeded {
+        provider: PROVIDER.to_string(),
+        retry_after: retry_after.to_rfc3339(),
+    }
+    .into()
+}
+
+fn http_error(status: Option<u16>, message: String) -> ProviderError {
+    ProviderError::Http {
+        provider: PROVIDER.to_string(),
+        status,
+        message,
+    }
+}
+
+fn parse_error(message: impl Into<String>) -> ProviderError {
+    ProviderError::Parse {
+        provider: PROVIDER.to_string(),
+        message: message.into(),
+    }
+}
+
+fn unsupported(operation: impl Into<String>) -> ProviderError {
+    ProviderError::Unsupported {
+        provider: PROVIDER.to_string(),
+        operation: operation.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn serve(function: &str, fixture: &str) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/query"))
+            .and(query_param("function", function))
+            .and(query_param("apikey", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(fixture))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn provider(server: &MockServer) -> AlphaVantageProvider {
+        AlphaVantageProvider::new(
+            AlphaVantageConfig::new("test-key")
+                .with_base_url(server.uri())
+                .with_requests_per_minute(100),
+        )
+        .unwrap()
+    }
+
+    fn ibm() -> Symbol {
+        Symbol::stock("IBM", "International Business Machines", Exchange::NYSE).unwrap()
+    }
+
+    fn request(timeframe: TimeFrame, adjustment: DataAdjustment) -> MarketDataRequest {
+        MarketDataRequest {
+            symbol: ibm(),
+            timeframe,
+            start_time: None,
+            end_time: None,
+            limit: None,
+            include_extended_hours: false,
+            adjustment,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_daily_bars_honour_adjustment() {
+        let server = serve(
+            "TIME_SERIES_DAILY_ADJUSTED",
+            include_str!("../fixtures/alpha_vantage/time_series_daily_adjusted_ibm.json"),
+        )
+        .await;
+        let provider = provider(&server);
+
+        // 2:1 split and a 1.67 dividend on 2024-05-10; the 05-09 bar traded pre-split
+        let splits = provider
+            .fetch_bars(&request(TimeFrame::OneDay, DataAdjustment::Splits))
+            .await
+            .unwrap();
+        assert_eq!(splits.bars.len(), 3);
+        assert_eq!(splits.bars[0].open, Decimal::new(166, 0));
+        assert_eq!(splits.bars[0].volume, Decimal::new(4_244_102, 0));
+        assert_eq!(splits.bars[2].close, Decimal::new(16756, 2));
+        assert_eq!(
+            splits.corporate_actions,
+            vec![CorporateAction::Split(Split::new(
+                splits.symbol.clone(),
+                NaiveDate::from_ymd_opt(2024, 5, 10).unwrap(),
+                Decimal::new(2, 0),
+            ))]
+        );
+
+        let all = provider
+            .fetch_bars(&request(TimeFrame::OneDay, DataAdjustment::All))
+            .await
+            .unwrap();
+        assert_eq!(all.bars[0].close, Decimal::new(16548, 2));
+        assert_eq!(all.bars[1].close, Decimal::new(16715, 2));
+        assert_eq!(all.corporate_actions.len(), 2);
+
+        let dividends = provider
+            .fetch_bars(&request(TimeFrame::OneDay, DataAdjustment::Dividends))
+            .await
+            .unwrap();
+        assert_eq!(dividends.bars[0].close, Decimal::new(33096, 2));
+        assert_eq!(dividends.bars[0].volume, Decimal::new(2_122_051, 0));
+
+        // Daily bars start at local midnight in New York
+        assert_eq!(
+            all.bars[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 5, 9, 4, 0, 0).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_intraday_bars_and_limits() {
+        let server = serve(
+            "TIME_SERIES_INTRADAY",
+            include_str!("../fixtures/alpha_vantage/time_series_intraday_ibm.json"),
+        )
+        .await;
+        let provider = provider(&server);
+
+        let mut latest = request(TimeFrame::FiveMinutes, DataAdjustment::None);
+        latest.limit = Some(1);
+        let response = provider.fetch_bars(&latest).await.unwrap();
+
+        assert_eq!(response.bars.len(), 1);
+        assert_eq!(
+            response.bars[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 5, 13, 13, 35, 0).unwrap()
+        );
+        assert_eq!(response.bars[0].close, Decimal::new(16781, 2));
+
+        // Intraday data cannot be adjusted for splits alone
+        let split_only = provider
+            .fetch_bars(&request(TimeFrame::FiveMinutes, DataAdjustment::Splits))
+            .await;
+        assert!(matches!(split_only, Err(ProviderError::Unsupported { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_symbol_search_maps_listings() {
+        let server = serve(
+            "SYMBOL_SEARCH",
+            include_str!("../fixtures/alpha_vantage/symbol_search_tesco.json"),
+        )
+        .await;
+        let listed = provider(&server).with_normalizer(
+            SymbolNormalizer::new()
+                .with_listing(Symbol::stock("TSCDF", "Tesco plc", Exchange::NASDAQ).unwrap()),
+        );
+
+        let response = listed
+            .search_symbols(&SymbolSearchRequest {
+                query: "tsc".to_string(),
+                asset_class: Some(AssetClass::Stock),
+                exchange: None,
+                limit: Some(5),
+                include_inactive: false,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.total_matches, 2);
+        let london = &response.symbols[0];
+        assert_eq!(london.symbol.code, "TSCO");
+        assert_eq!(london.symbol.exchange, Exchange::LSE);
+        assert_eq!(london.symbol.currency, "GBX");
+        assert_eq!(london.matched_fields, vec!["code".to_string()]);
+        assert!((london.match_score - 0.7273).abs() < 1e-9);
+        assert_eq!(provider_symbol(&london.symbol), "TSCO.LON");
+        let us = &response.symbols[1];
+        assert_eq!(us.symbol.exchange, Exchange::NASDAQ);
+        assert_eq!(us.symbol.timezone, "America/New_York");
+
+        // Without a listing the US result has no exchange and is left out
+        let response = provider(&server)
+            .search_symbols(&SymbolSearchRequest {
+                query: "tsc".to_string(),
+                asset_class: Some(AssetClass::Stock),
+                exchange: None,
+                limit: Some(5),
+                include_inactive: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.total_matches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_latest_quote() {
+        let server = serve(
+            "GLOBAL_QUOTE",
+            include_str!("../fixtures/alpha_vantage/global_quote_ibm.json"),
+        )
+        .await;
+
+        let quote = provider(&server).latest_quote(&ibm()).await.unwrap();
+        assert_eq!(quote.price, Decimal::new(16756, 2));
+        assert_eq!(quote.change_percent, Decimal::new(2453, 4));
+        assert_eq!(quote.volume, Decimal::new(2_414_859, 0));
+
+        let empty = serve(
+            "GLOBAL_QUOTE",
+            include_str!("../fixtures/alpha_vantage/global_quote_empty.json"),
+        )
+        .await;
+        assert_eq!(
+            provider(&empty).latest_quote(&ibm()).await,
+            Err(ProviderError::MarketData(MarketDataError::SymbolNotFound {
+                symbol: "IBM".to_string()
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits() {
+        let server = serve(
+            "GLOBAL_QUOTE",
+            include_str!("../fixtures/alpha_vantage/rate_limited.json"),
+        )
+        .await;
+
+        // Throttling reported by the API
+        let throttled = provider(&server).latest_quote(&ibm()).await.unwrap_err();
+        assert!(matches!(
+            throttled,
+            ProviderError::MarketData(MarketDataError::RateLimitExceeded { .. })
+        ));
+        assert!(throttled.is_retryable());
+
+        // Local budget is spent before the request is sent
+        let quotes = serve(
+            "GLOBAL_QUOTE",
+            include_str!("../fixtures/alpha_vantage/global_quote_ibm.json"),
+        )
+        .await;
+        let limited = AlphaVantageProvider::new(
+            AlphaVantageConfig::new("test-key")
+                .with_base_url(quotes.uri())
+                .with_requests_per_minute(1),
+        )
+        .unwrap();
+        assert!(limited.latest_quote(&ibm()).await.is_ok());
+        let spent = limited.latest_quote(&ibm()).await.unwrap_err();
+        assert!(matches!(
+            spent,
+            ProviderError::MarketData(MarketDataError::RateLimitExceeded { .. })
+        ));
+        assert!(spent.is_retryable());
+        assert_eq!(quotes.received_requests().await.unwrap().len(), 1);
+
+        let invalid = serve(
+            "TIME_SERIES_DAILY",
+            include_str!("../fixtures/alpha_vantage/invalid_symbol.json"),
+        )
+        .await;
+        assert!(matches!(
+            provider(&invalid)
+                .fetch_bars(&request(TimeFrame::OneDay, DataAdjustment::None))
+                .await,
+            Err(ProviderError::MarketData(
+                MarketDataError::SymbolNotFound { .. }
+            ))
+        ));
+    }
+}
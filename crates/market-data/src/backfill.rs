@@ -0,0 +1,727 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use database::{
+    BackfillJob, BackfillRepository, BackfillStatus, DatabaseError, DatabaseResult, InfluxDbPool,
+    OhlcvRepository,
+};
+use shared_types::{
+    DataAdjustment, MarketDataError, MarketDataRequest, Symbol, TimeFrame, TradingCalendar, OHLCV,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::provider::{MarketDataProvider, ProviderError};
+use crate::quality::expects_bar;
+
+/// A destination for backfilled bars
+#[async_trait]
+pub trait BarStore: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Persist bars, overwriting any already stored for the same interval
+    async fn write_bars(&self, bars: &[OHLCV]) -> DatabaseResult<usize>;
+}
+
+#[async_trait]
+impl BarStore for OhlcvRepository {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn write_bars(&self, bars: &[OHLCV]) -> DatabaseResult<usize> {
+        self.upsert_bars(bars).await
+    }
+}
+
+#[async_trait]
+impl BarStore for InfluxDbPool {
+    fn name(&self) -> &str {
+        "influxdb"
+    }
+
+    async fn write_bars(&self, bars: &[OHLCV]) -> DatabaseResult<usize> {
+        InfluxDbPool::write_bars(self, bars).await
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BackfillError {
+    #[error(transparent)]
+    Provider(#[from] ProviderError),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error("Backfill job {0} not found")]
+    JobNotFound(Uuid),
+
+    #[error("Backfill request for {symbol} needs both a start and an end time")]
+    UnboundedRange { symbol: String },
+}
+
+pub type BackfillResult<T> = Result<T, BackfillError>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackfillConfig {
+    /// Bars requested per provider call
+    pub window_bars: u32,
+
+    /// Pause between provider calls to stay inside the provider's rate limit
+    pub request_interval: Duration,
+
+    /// Rate-limit rejections tolerated per window before the job fails
+    pub max_rate_limit_retries: u32,
+
+    /// Upper bound on a single wait for a provider's `retry_after`
+    pub max_rate_limit_wait: Duration,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            window_bars: 1000,
+            request_interval: Duration::ZERO,
+            max_rate_limit_retries: 5,
+            max_rate_limit_wait: Duration::from_secs(60),
+        }
+    }
+}
+
+impl BackfillConfig {
+    pub fn with_window_bars(mut self, window_bars: u32) -> Self {
+        self.window_bars = window_bars.max(1);
+        self
+    }
+
+    pub fn with_request_interval(mut self, request_interval: Duration) -> Self {
+        self.request_interval = request_interval;
+        self
+    }
+
+    pub fn with_max_rate_limit_retries(mut self, retries: u32) -> Self {
+        self.max_rate_limit_retries = retries;
+        self
+    }
+
+    pub fn with_max_rate_limit_wait(mut self, wait: Duration) -> Self {
+        self.max_rate_limit_wait = wait;
+        self
+    }
+}
+
+/// Outcome of running a backfill job to completion
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackfillReport {
+    pub job_id: Uuid,
+
+    /// Cursor the run picked up from (the job start for a fresh job)
+    pub resumed_from: DateTime<Utc>,
+
+    pub windows_fetched: usize,
+    pub bars_written: u64,
+
+    /// Gaps found in the stored range after the main pass
+    pub gaps_found: usize,
+
+    /// Gaps the provider had data for; the rest are typically market closures
+    pub gaps_filled: usize,
+}
+
+/// Downloads history in provider-sized windows with SQLite checkpoints
+///
+/// A job's cursor is saved after every window, so a job interrupted by a crash
+/// or a provider failure resumes where it stopped. Once the range is covered,
+/// the stored bars are scanned for gaps and missing intervals are requested
+/// again. Bars go to the SQLite store and any additional [`BarStore`]s.
+pub struct BackfillScheduler {
+    provider: Arc<dyn MarketDataProvider>,
+    jobs: Arc<BackfillRepository>,
+    bars: Arc<OhlcvRepository>,
+    stores: Vec<Arc<dyn BarStore>>,
+    config: BackfillConfig,
+}
+
+impl BackfillScheduler {
+    pub fn new(
+        provider: Arc<dyn MarketDataProvider>,
+        jobs: Arc<BackfillRepository>,
+        bars: Arc<OhlcvRepository>,
+    ) -> Self {
+        Self {
+            provider,
+            jobs,
+            bars,
+            stores: Vec::new(),
+            config: BackfillConfig::default(),
+        }
+    }
+
+    /// Also write bars to `store`, e.g. an [`InfluxDbPool`]
+    pub fn with_store(mut self, store: Arc<dyn BarStore>) -> Self {
+        self.stores.push(store);
+        self
+    }
+
+    pub fn with_config(mut self, config: BackfillConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register a job for the request's time range
+    pub async fn schedule(&self, request: &MarketDataRequest) -> BackfillResult<BackfillJob> {
+        let (Some(start), Some(end)) = (request.start_time, request.end_time) else {
+            return Err(BackfillError::UnboundedRange {
+                symbol: request.symbol.code.clone(),
+            });
+        };
+        if start > end {
+            return Err(ProviderError::from(MarketDataError::InvalidTimeRange {
+                start: start.to_rfc3339(),
+                end: end.to_rfc3339(),
+            })
+            .into());
+        }
+
+        let job = BackfillJob::new(
+            request.symbol.clone(),
+            request.timeframe.clone(),
+            start,
+            end,
+        );
+        self.jobs.create_job(&job).await?;
+        tracing::info!(job_id = %job.id, symbol = %job.symbol.code, "Scheduled backfill");
+        Ok(job)
+    }
+
+    /// Run every job that has not completed, oldest first
+    pub async fn resume_all(&self) -> BackfillResult<Vec<BackfillResult<BackfillReport>>> {
+        let jobs = self.jobs.incomplete_jobs().await?;
+        let mut reports = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            reports.push(self.run(job.id).await);
+        }
+        Ok(reports)
+    }
+
+    /// Run a job from its checkpoint to the end of its range, then fill gaps
+    pub async fn run(&self, job_id: Uuid) -> BackfillResult<BackfillReport> {
+        let job = self
+            .jobs
+            .get_job(job_id)
+            .await?
+            .ok_or(BackfillError::JobNotFound(job_id))?;
+
+        match self.run_job(&job).await {
+            Ok(report) => {
+                self.jobs
+                    .set_status(job.id, BackfillStatus::Completed, None)
+                    .await?;
+                Ok(report)
+            }
+            Err(error) => {
+                tracing::warn!(job_id = %job.id, %error, "Backfill failed; progress is checkpointed");
+                self.jobs
+                    .set_status(job.id, BackfillStatus::Failed, Some(&error.to_string()))
+                    .await?;
+                Err(error)
+            }
+        }
+    }
+
+    async fn run_job(&self, job: &BackfillJob) -> BackfillResult<BackfillReport> {
+        let mut report = BackfillReport {
+            job_id: job.id,
+            resumed_from: job.cursor,
+            windows_fetched: 0,
+            bars_written: job.bars_written,
+            gaps_found: 0,
+            gaps_filled: 0,
+        };
+
+        let windows = plan_windows(
+            job.cursor,
+            job.end_time,
+            &job.timeframe,
+            self.config.window_bars,
+        );
+        for (start, end) in windows {
+            let written = self
+                .fetch_and_store(&job.symbol, &job.timeframe, start, end)
+                .await?;
+            report.windows_fetched += 1;
+            report.bars_written += written as u64;
+
+            let next = job.timeframe.advance(end, 1);
+            self.jobs
+                .save_checkpoint(job.id, next, report.bars_written)
+                .await?;
+        }
+
+        let stored = self
+            .bars
+            .bar_timestamps(&job.symbol, &job.timeframe, job.start_time, job.end_time)
+            .await?;
+        let calendar = TradingCalendar::for_exchange(&job.symbol.exchange);
+        let gaps = detect_gaps(
+            &stored,
+            job.start_time,
+            job.end_time,
+            &job.timeframe,
+            calendar.as_ref(),
+        );
+        report.gaps_found = gaps.len();
+        for (start, end) in gaps {
+            let written = self
+                .fetch_and_store(&job.symbol, &job.timeframe, start, end)
+                .await?;
+            if written > 0 {
+                report.gaps_filled += 1;
+                report.bars_written += written as u64;
+            }
+        }
+
+        tracing::info!(
+            job_id = %job.id,
+            bars = report.bars_written,
+            gaps = report.gaps_found,
+            filled = report.gaps_filled,
+            "Backfill complete"
+        );
+        Ok(report)
+    }
+
+    /// Fetch one window, waiting out rate limits, and write it to every store
+    async fn fetch_and_store(
+        &self,
+        symbol: &Symbol,
+        timeframe: &TimeFrame,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> BackfillResult<usize> {
+        let request = MarketDataRequest {
+            symbol: symbol.clone(),
+            timeframe: timeframe.clone(),
+            start_time: Some(start),
+            end_time: Some(end),
+            limit: None,
+            include_extended_hours: false,
+            adjustment: DataAdjustment::None,
+        };
+
+        let mut rate_limited = 0;
+        let bars = loop {
+            if !self.config.request_interval.is_zero() {
+                tokio::time::sleep(self.config.request_interval).await;
+            }
+            match self.provider.fetch_bars(&request).await {
+                Ok(response) => break response.bars,
+                Err(ProviderError::MarketData(MarketDataError::NoDataAvailable)) => {
+                    return Ok(0);
+                }
+                Err(ProviderError::MarketData(MarketDataError::RateLimitExceeded {
+                    retry_after,
+                    ..
+                })) if rate_limited < self.config.max_rate_limit_retries => {
+                    rate_limited += 1;
+                    let wait = retry_wait(&retry_after).min(self.config.max_rate_limit_wait);
+                    tracing::debug!(?wait, "Rate limited during backfill, waiting");
+                    tokio::time::sleep(wait).await;
+                }
+                Err(error) => return Err(error.into()),
+            }
+        };
+
+        let bars: Vec<OHLCV> = bars
+            .into_iter()
+            .filter(|bar| bar.timestamp >= start && bar.timestamp <= end)
+            .collect();
+        if bars.is_empty() {
+            return Ok(0);
+        }
+
+        self.bars.upsert_bars(&bars).await?;
+        for store in &self.stores {
+            store.write_bars(&bars).await?;
+        }
+        Ok(bars.len())
+    }
+}
+
+/// How long to wait for a `retry_after` given as a timestamp or in seconds
+fn retry_wait(retry_after: &str) -> Duration {
+    if let Ok(at) = DateTime::parse_from_rfc3339(retry_after) {
+        return (at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO);
+    }
+    retry_after
+        .parse()
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(1))
+}
+
+/// Split `[start, end]` into inclusive windows of at most `window_bars` bar slots
+pub fn plan_windows(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    timeframe: &TimeFrame,
+    window_bars: u32,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let span = window_bars.max(1) as i32 - 1;
+
+    let mut windows = Vec::new();
+    let mut cursor = start;
+    while cursor <= end {
+        let window_end = timeframe.advance(cursor, span).min(end);
+        windows.push((cursor, window_end));
+        cursor = timeframe.advance(window_end, 1);
+        if cursor == window_end {
+            break;
+        }
+    }
+    windows
+}
+
+/// Ranges within `[start, end]` where at least one expected bar is missing
+///
+/// `timestamps` must be sorted. With a `calendar`, only slots where the
+/// exchange trades count as missing, the same way the quality monitor judges
+/// gaps, so nights, weekends and holidays are not requested again. Each gap
+/// is returned as the inclusive range from the first to the last missing
+/// bar start between two stored bars, ready to be requested again.
+pub fn detect_gaps(
+    timestamps: &[DateTime<Utc>],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    timeframe: &TimeFrame,
+    calendar: Option<&TradingCalendar>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    if start > end {
+        return Vec::new();
+    }
+
+    // Stored bars split the range into runs of empty slots
+    let mut bounds = Vec::with_capacity(timestamps.len() + 2);
+    bounds.push(timeframe.advance(start, -1));
+    bounds.extend(timestamps.iter().copied());
+    bounds.push(timeframe.advance(end, 1));
+
+    bounds
+        .windows(2)
+        .filter_map(|pair| {
+            let mut missing = Vec::new();
+            let mut slot = timeframe.advance(pair[0], 1);
+            while slot < pair[1] {
+                if expects_bar(calendar, timeframe, slot) {
+                    missing.push(slot);
+                }
+                slot = timeframe.advance(slot, 1);
+            }
+            Some((*missing.first()?, *missing.last()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration as ChronoDuration, TimeZone};
+    use database::{SqlitePool, SqlitePoolConfig};
+    use rust_decimal::Decimal;
+    use shared_types::{
+        Exchange, MarketDataResponse, Quote, SymbolSearchRequest, SymbolSearchResponse,
+    };
+    use std::sync::Mutex;
+
+    use crate::provider::ProviderResult;
+
+    /// Serves hourly bars for every requested hour except `missing`, and fails
+    /// once after `fail_after` calls
+    struct HistoryStub {
+        missing: Vec<DateTime<Utc>>,
+        fail_after: Option<usize>,
+        requests: Mutex<Vec<(DateTime<Utc>, DateTime<Utc>)>>,
+    }
+
+    impl HistoryStub {
+        fn new(missing: Vec<DateTime<Utc>>, fail_after: Option<usize>) -> Arc<Self> {
+            Arc::new(Self {
+                missing,
+                fail_after,
+                requests: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn requests(&self) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for HistoryStub {
+        fn name(&self) -> &str {
+            "history"
+        }
+
+        async fn fetch_bars(
+            &self,
+            request: &MarketDataRequest,
+        ) -> ProviderResult<MarketDataResponse> {
+            let (start, end) = (request.start_time.unwrap(), request.end_time.unwrap());
+            let calls = {
+                let mut requests = self.requests.lock().unwrap();
+                requests.push((start, end));
+                requests.len()
+            };
+            if self.fail_after.is_some_and(|limit| calls > limit) {
+                return Err(MarketDataError::DataProviderUnavailable {
+                    provider: "history".to_string(),
+                }
+                .into());
+            }
+
+            let mut bars = Vec::new();
+            let mut timestamp = start;
+            while timestamp <= end {
+                if !self.missing.contains(&timestamp) {
+                    let price = Decimal::new(100, 0);
+                    bars.push(
+                        OHLCV::new(
+                            request.symbol.clone(),
+                            request.timeframe.clone(),
+                            timestamp,
+                            price,
+                            price,
+                            price,
+                            price,
+                            Decimal::ONE,
+                        )
+                        .unwrap(),
+                    );
+                }
+                timestamp += ChronoDuration::hours(1);
+            }
+            if bars.is_empty() {
+                return Err(MarketDataError::NoDataAvailable.into());
+            }
+            Ok(MarketDataResponse {
+                symbol: request.symbol.clone(),
+                timeframe: request.timeframe.clone(),
+                bars,
+                adjustment: DataAdjustment::None,
+                corporate_actions: Vec::new(),
+                includes_extended_hours: false,
+                last_updated: Utc::now(),
+            })
+        }
+
+        async fn search_symbols(
+            &self,
+            _request: &SymbolSearchRequest,
+        ) -> ProviderResult<SymbolSearchResponse> {
+            Err(ProviderError::Unsupported {
+                provider: self.name().to_string(),
+                operation: "symbol search".to_string(),
+            })
+        }
+
+        async fn latest_quote(&self, _symbol: &Symbol) -> ProviderResult<Quote> {
+            Err(ProviderError::Unsupported {
+                provider: self.name().to_string(),
+                operation: "quotes".to_string(),
+            })
+        }
+    }
+
+    async fn repositories() -> (Arc<BackfillRepository>, Arc<OhlcvRepository>) {
+        let config = SqlitePoolConfig::builder()
+            .url("sqlite::memory:")
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(1))
+            .enable_wal(false)
+            .build();
+        let pool = Arc::new(SqlitePool::new(config).await.unwrap());
+        let jobs = Arc::new(BackfillRepository::new(pool.clone()));
+        let bars = Arc::new(OhlcvRepository::new(pool));
+        jobs.migrate().await.unwrap();
+        bars.migrate().await.unwrap();
+        (jobs, bars)
+    }
+
+    fn hour(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 13, hour, 0, 0).unwrap()
+    }
+
+    fn hourly_request(start: u32, end: u32) -> MarketDataRequest {
+        MarketDataRequest {
+            symbol: Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap(),
+            timeframe: TimeFrame::OneHour,
+            start_time: Some(hour(start)),
+            end_time: Some(hour(end)),
+            limit: None,
+            include_extended_hours: false,
+            adjustment: DataAdjustment::None,
+        }
+    }
+
+    #[test]
+    fn test_plan_windows_and_detect_gaps() {
+        let windows = plan_windows(hour(0), hour(9), &TimeFrame::OneHour, 4);
+        assert_eq!(
+            windows,
+            vec![(hour(0), hour(3)), (hour(4), hour(7)), (hour(8), hour(9))]
+        );
+
+        let stored = [hour(1), hour(2), hour(5), hour(6)];
+        assert_eq!(
+            detect_gaps(&stored, hour(0), hour(8), &TimeFrame::OneHour, None),
+            vec![(hour(0), hour(0)), (hour(3), hour(4)), (hour(7), hour(8))]
+        );
+        assert!(detect_gaps(&stored[..2], hour(1), hour(2), &TimeFrame::OneHour, None).is_empty());
+        assert_eq!(
+            detect_gaps(&[], hour(0), hour(1), &TimeFrame::OneHour, None),
+            vec![(hour(0), hour(1))]
+        );
+    }
+
+    #[test]
+    fn test_detect_gaps_follows_trading_calendar() {
+        let nyse = TradingCalendar::for_exchange(&Exchange::NYSE).unwrap();
+        let tuesday = |hour| Utc.with_ymd_and_hms(2024, 5, 14, hour, 0, 0).unwrap();
+
+        // Monday's last regular-session hour, then Tuesday's 10:00 New York bar:
+        // nothing is expected overnight, but the 09:00 bar runs into the open
+        // and the 16:00 UTC bar is missing too
+        let stored = [hour(19), tuesday(14), tuesday(15), tuesday(17)];
+        assert_eq!(
+            detect_gaps(
+                &stored,
+                hour(19),
+                tuesday(17),
+                &TimeFrame::OneHour,
+                Some(&nyse)
+            ),
+            vec![(tuesday(13), tuesday(13)), (tuesday(16), tuesday(16))]
+        );
+        assert_eq!(
+            detect_gaps(&stored, hour(19), tuesday(17), &TimeFrame::OneHour, None).len(),
+            2
+        );
+
+        // Daily bars skip the weekend and the Memorial Day holiday
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 0, 0, 0).unwrap();
+        let stored = [day(24), day(28)];
+        assert!(detect_gaps(&stored, day(24), day(28), &TimeFrame::OneDay, Some(&nyse)).is_empty());
+        assert_eq!(
+            detect_gaps(&stored, day(24), day(29), &TimeFrame::OneDay, Some(&nyse)),
+            vec![(day(29), day(29))]
+        );
+    }
+
+    #[test]
+    fn test_detect_gaps_in_monthly_series() {
+        let nyse = TradingCalendar::for_exchange(&Exchange::NYSE).unwrap();
+        let month = |m| Utc.with_ymd_and_hms(2024, m, 1, 0, 0, 0).unwrap();
+
+        // Months are stored on the 1st, whatever their length; New Year's Day
+        // and the 1st of a weekend still begin a month that trades
+        let stored = [month(1), month(2), month(3), month(4), month(6)];
+        assert!(
+            detect_gaps(&stored[..4], month(1), month(4), &TimeFrame::OneMonth, None).is_empty()
+        );
+        assert_eq!(
+            detect_gaps(
+                &stored,
+                month(1),
+                month(8),
+                &TimeFrame::OneMonth,
+                Some(&nyse)
+            ),
+            vec![(month(5), month(5)), (month(7), month(8))]
+        );
+        assert_eq!(
+            plan_windows(month(1), month(12), &TimeFrame::OneMonth, 6),
+            vec![(month(1), month(6)), (month(7), month(12))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resumes_from_checkpoint_after_failure() {
+        let (jobs, bars) = repositories().await;
+        let config = BackfillConfig::default().with_window_bars(4);
+
+        // The provider goes down after two windows
+        let flaky = HistoryStub::new(Vec::new(), Some(2));
+        let scheduler = BackfillScheduler::new(flaky.clone(), jobs.clone(), bars.clone())
+            .with_config(config.clone());
+        let job = scheduler.schedule(&hourly_request(0, 11)).await.unwrap();
+        assert!(scheduler.run(job.id).await.is_err());
+
+        let checkpoint = jobs.get_job(job.id).await.unwrap().unwrap();
+        assert_eq!(checkpoint.status, BackfillStatus::Failed);
+        assert_eq!(checkpoint.cursor, hour(8));
+        assert_eq!(checkpoint.bars_written, 8);
+
+        // A fresh scheduler (e.g. after a restart) picks the job up at the cursor
+        let healthy = HistoryStub::new(Vec::new(), None);
+        let scheduler =
+            BackfillScheduler::new(healthy.clone(), jobs.clone(), bars.clone()).with_config(config);
+        let reports = scheduler.resume_all().await.unwrap();
+        let report = reports[0].as_ref().unwrap();
+
+        assert_eq!(report.resumed_from, hour(8));
+        assert_eq!(report.windows_fetched, 1);
+        assert_eq!(report.bars_written, 12);
+        assert_eq!(healthy.requests(), vec![(hour(8), hour(11))]);
+        assert!(jobs.incomplete_jobs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refills_gaps_and_writes_every_store() {
+        struct CountingStore(Mutex<usize>);
+
+        #[async_trait]
+        impl BarStore for CountingStore {
+            fn name(&self) -> &str {
+                "counting"
+            }
+
+            async fn write_bars(&self, bars: &[OHLCV]) -> DatabaseResult<usize> {
+                *self.0.lock().unwrap() += bars.len();
+                Ok(bars.len())
+            }
+        }
+
+        let (jobs, bars) = repositories().await;
+        // Hours 15 and 18 are missing (e.g. a trading halt), so the retry finds nothing
+        let provider = HistoryStub::new(vec![hour(15), hour(18)], None);
+        let extra = Arc::new(CountingStore(Mutex::new(0)));
+        let scheduler = BackfillScheduler::new(provider.clone(), jobs, bars.clone())
+            .with_store(extra.clone())
+            .with_config(BackfillConfig::default().with_window_bars(24));
+
+        let job = scheduler.schedule(&hourly_request(13, 20)).await.unwrap();
+        let report = scheduler.run(job.id).await.unwrap();
+
+        assert_eq!(report.bars_written, 6);
+        assert_eq!(report.gaps_found, 2);
+        assert_eq!(report.gaps_filled, 0);
+        assert_eq!(*extra.0.lock().unwrap(), 6);
+        assert_eq!(
+            provider.requests()[1..],
+            [(hour(15), hour(15)), (hour(18), hour(18))]
+        );
+
+        assert!(matches!(
+            scheduler
+                .schedule(&MarketDataRequest {
+                    end_time: None,
+                    ..hourly_request(0, 1)
+                })
+                .await,
+            Err(BackfillError::UnboundedRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,732 @@
+use async_trait::async_trait;
+use serde_json::json;
+use shared_types::{
+    AssetClass, ExternalServiceError, MarketDataError, MarketDataRequest, MarketDataResponse,
+    Quote, Symbol, SymbolSearchRequest, SymbolSearchResponse, TradingCalendar,
+};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::provider::{MarketDataProvider, ProviderError, ProviderResult};
+
+/// `OHLCV::metadata` key naming the provider that served a bar
+pub const PROVIDER_METADATA_KEY: &str = "provider";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive health failures before a provider is taken out of rotation
+    pub failure_threshold: u32,
+
+    /// How long an open circuit stays open before a trial request is allowed
+    pub cooldown: Duration,
+
+    /// Trial requests allowed in flight at once while half-open; the rest
+    /// skip the provider as if the circuit were still open
+    pub half_open_trials: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+            half_open_trials: 1,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_half_open_trials(mut self, half_open_trials: u32) -> Self {
+        self.half_open_trials = half_open_trials.max(1);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+
+    /// Provider is skipped until the cooldown elapses
+    Open,
+
+    /// Cooldown elapsed; the next requests, up to `half_open_trials`, are trials
+    HalfOpen,
+}
+
+/// Point-in-time health of one provider behind a [`CompositeProvider`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderHealth {
+    pub provider: String,
+
+    pub priority: u32,
+
+    pub state: CircuitState,
+
+    pub consecutive_failures: u32,
+
+    /// Most recent health failure, cleared on success
+    pub last_error: Option<ExternalServiceError>,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    last_error: Option<ExternalServiceError>,
+    trials_in_flight: u32,
+}
+
+impl Breaker {
+    fn state(&self, config: &CircuitBreakerConfig) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened) if opened.elapsed() < config.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether a request may be sent, as `Some(true)` when it takes a trial slot
+    fn admit(&mut self, config: &CircuitBreakerConfig) -> Option<bool> {
+        match self.state(config) {
+            CircuitState::Closed => Some(false),
+            CircuitState::Open => None,
+            CircuitState::HalfOpen if self.trials_in_flight < config.half_open_trials => {
+                self.trials_in_flight += 1;
+                Some(true)
+            }
+            CircuitState::HalfOpen => None,
+        }
+    }
+
+    fn finish_trial(&mut self) {
+        self.trials_in_flight = self.trials_in_flight.saturating_sub(1);
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.last_error = None;
+    }
+
+    fn record_failure(&mut self, error: ExternalServiceError, config: &CircuitBreakerConfig) {
+        self.consecutive_failures += 1;
+        self.last_error = Some(error);
+        // A failed half-open trial re-opens the circuit for another cooldown
+        if self.consecutive_failures >= config.failure_threshold {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+struct Route {
+    provider: Arc<dyn MarketDataProvider>,
+    priority: u32,
+    asset_classes: Option<Vec<AssetClass>>,
+    breaker: Mutex<Breaker>,
+}
+
+impl Route {
+    fn serves(&self, asset_class: Option<&AssetClass>) -> bool {
+        match (&self.asset_classes, asset_class) {
+            (Some(classes), Some(asset_class)) => classes.contains(asset_class),
+            _ => true,
+        }
+    }
+
+    fn breaker(&self) -> std::sync::MutexGuard<'_, Breaker> {
+        self.breaker.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Half-open trial slot, handed back when the request finishes or is dropped
+struct Trial<'a>(&'a Route);
+
+impl Drop for Trial<'_> {
+    fn drop(&mut self) {
+        self.0.breaker().finish_trial();
+    }
+}
+
+/// Routes requests across several providers with failover
+///
+/// Providers are tried in ascending priority order among those routed for the
+/// request's asset class. A provider that reports an outage or quota problem
+/// (see [`ProviderError::service_error`]) is skipped in favour of the next one,
+/// and after `failure_threshold` consecutive failures its circuit opens so it
+/// is not called again until the cooldown has passed. Errors about the request
+/// itself, such as an unknown symbol, are returned without failover.
+///
+/// Crypto is typically routed to one [`ExchangeRestProvider`] per exchange
+/// and equities to Alpha Vantage. A provider that does not support a request,
+/// like an exchange asked for another exchange's listing, passes it on.
+///
+/// [`ExchangeRestProvider`]: crate::ExchangeRestProvider
+pub struct CompositeProvider {
+    name: String,
+    routes: Vec<Route>,
+    breaker_config: CircuitBreakerConfig,
+}
+
+impl CompositeProvider {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            routes: Vec::new(),
+            breaker_config: CircuitBreakerConfig::default(),
+        }
+    }
+
+    /// Add a provider for every asset class; lower priority values are tried first
+    pub fn with_provider(self, provider: Arc<dyn MarketDataProvider>, priority: u32) -> Self {
+        self.push_route(provider, priority, None)
+    }
+
+    /// Add a provider that only serves the given asset classes
+    pub fn with_routed_provider(
+        self,
+        provider: Arc<dyn MarketDataProvider>,
+        priority: u32,
+        asset_classes: impl IntoIterator<Item = AssetClass>,
+    ) -> Self {
+        let asset_classes = asset_classes.into_iter().collect();
+        self.push_route(provider, priority, Some(asset_classes))
+    }
+
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breaker_config = config;
+        self
+    }
+
+    fn push_route(
+        mut self,
+        provider: Arc<dyn MarketDataProvider>,
+        priority: u32,
+        asset_classes: Option<Vec<AssetClass>>,
+    ) -> Self {
+        self.routes.push(Route {
+            provider,
+            priority,
+            asset_classes,
+            breaker: Mutex::new(Breaker::default()),
+        });
+        // Stable sort keeps registration order among equal priorities
+        self.routes.sort_by_key(|route| route.priority);
+        self
+    }
+
+    /// Circuit state of every provider, in priority order
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        self.routes
+            .iter()
+            .map(|route| {
+                let breaker = route.breaker();
+                ProviderHealth {
+                    provider: route.provider.name().to_string(),
+                    priority: route.priority,
+                    state: breaker.state(&self.breaker_config),
+                    consecutive_failures: breaker.consecutive_failures,
+                    last_error: breaker.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Run `call` against each eligible provider until one succeeds
+    ///
+    /// Returns the result together with the name of the provider that served it.
+    async fn route<T, F, Fut>(
+        &self,
+        asset_class: Option<&AssetClass>,
+        call: F,
+    ) -> ProviderResult<(T, String)>
+    where
+        F: Fn(Arc<dyn MarketDataProvider>) -> Fut,
+        Fut: Future<Output = ProviderResult<T>>,
+    {
+        let mut last_error = None;
+
+        for route in self.routes.iter().filter(|route| route.serves(asset_class)) {
+            let name = route.provider.name().to_string();
+            let Some(trial) = route.breaker().admit(&self.breaker_config) else {
+                tracing::debug!(provider = %name, "Circuit open, skipping provider");
+                continue;
+            };
+            let _trial = trial.then(|| Trial(route));
+
+            match call(Arc::clone(&route.provider)).await {
+                Ok(value) => {
+                    route.breaker().record_success();
+                    return Ok((value, name));
+                }
+                Err(error) => {
+                    if let Some(service_error) = error.service_error() {
+                        tracing::warn!(provider = %name, error = %service_error, "Failing over to next provider");
+                        route
+                            .breaker()
+                            .record_failure(service_error, &self.breaker_config);
+                    } else if !matches!(error, ProviderError::Unsupported { .. }) {
+                        return Err(error);
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ExternalServiceError::ThirdPartyServiceDown {
+                service: self.name.clone(),
+            }
+            .into()
+        }))
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for CompositeProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch_bars(&self, request: &MarketDataRequest) -> ProviderResult<MarketDataResponse> {
+        let (mut response, served_by) = self
+            .route(Some(&request.symbol.asset_class), |provider| async move {
+                provider.fetch_bars(request).await
+            })
+            .await?;
+
+        // Providers differ in whether they honour the flag, so intraday bars
+        // that do not overlap the regular session are dropped here when it is off
+        let intraday = request.timeframe.to_seconds() < 86_400;
+        if intraday && !request.include_extended_hours {
+            if let Some(calendar) = TradingCalendar::for_exchange(&request.symbol.exchange) {
+                let length = chrono::Duration::seconds(request.timeframe.to_seconds() as i64);
+                response
+                    .bars
+                    .retain(|bar| calendar.is_open_during(bar.timestamp, bar.timestamp + length));
+                response.includes_extended_hours = false;
+                if response.bars.is_empty() {
+                    return Err(MarketDataError::NoDataAvailable.into());
+                }
+            }
+        }
+
+        for bar in &mut response.bars {
+            bar.add_metadata(PROVIDER_METADATA_KEY, json!(served_by));
+        }
+        Ok(response)
+    }
+
+    async fn search_symbols(
+        &self,
+        request: &SymbolSearchRequest,
+    ) -> ProviderResult<SymbolSearchResponse> {
+        let (response, _) = self
+            .route(request.asset_class.as_ref(), |provider| async move {
+                provider.search_symbols(request).await
+            })
+            .await?;
+        Ok(response)
+    }
+
+    async fn latest_quote(&self, symbol: &Symbol) -> ProviderResult<Quote> {
+        let (quote, _) = self
+            .route(Some(&symbol.asset_class), |provider| async move {
+                provider.latest_quote(symbol).await
+            })
+            .await?;
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use shared_types::{DataAdjustment, Exchange, TimeFrame, OHLCV};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider that counts calls and either serves one bar or fails
+    struct StubProvider {
+        name: &'static str,
+        failure: Option<ProviderError>,
+        delay: Duration,
+        calls: AtomicUsize,
+    }
+
+    impl StubProvider {
+        fn healthy(name: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                failure: None,
+                delay: Duration::ZERO,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn failing(name: &'static str, failure: ProviderError) -> Arc<Self> {
+            Self::slow(name, failure, Duration::ZERO)
+        }
+
+        fn slow(name: &'static str, failure: ProviderError, delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                failure: Some(failure),
+                delay,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for StubProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn fetch_bars(
+            &self,
+            request: &MarketDataRequest,
+        ) -> ProviderResult<MarketDataResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            if let Some(failure) = &self.failure {
+                return Err(failure.clone());
+            }
+            let price = Decimal::new(100, 0);
+            // 08:00, 09:00 and 10:00 in New York: a pre-market bar, one that
+            // runs into the 09:30 open and a regular one
+            let bars = [12, 13, 14]
+                .into_iter()
+                .map(|hour| {
+                    OHLCV::new(
+                        request.symbol.clone(),
+                        request.timeframe.clone(),
+                        Utc.with_ymd_and_hms(2024, 5, 13, hour, 0, 0).unwrap(),
+                        price,
+                        price,
+                        price,
+                        price,
+                        Decimal::new(1000, 0),
+                    )
+                    .unwrap()
+                })
+                .collect();
+            Ok(MarketDataResponse {
+                symbol: request.symbol.clone(),
+                timeframe: request.timeframe.clone(),
+                bars,
+                adjustment: request.adjustment.clone(),
+                corporate_actions: Vec::new(),
+                includes_extended_hours: request.include_extended_hours,
+                last_updated: Utc::now(),
+            })
+        }
+
+        async fn search_symbols(
+            &self,
+            _request: &SymbolSearchRequest,
+        ) -> ProviderResult<SymbolSearchResponse> {
+            Err(ProviderError::Unsupported {
+                provider: self.name.to_string(),
+                operation: "symbol search".to_string(),
+            })
+        }
+
+        async fn latest_quote(&self, _symbol: &Symbol) -> ProviderResult<Quote> {
+            Err(ProviderError::Unsupported {
+                provider: self.name.to_string(),
+                operation: "quotes".to_string(),
+            })
+        }
+    }
+
+    fn bars_request(symbol: Symbol) -> MarketDataRequest {
+        MarketDataRequest {
+            symbol,
+            timeframe: TimeFrame::OneDay,
+            start_time: None,
+            end_time: None,
+            limit: None,
+            include_extended_hours: false,
+            adjustment: DataAdjustment::None,
+        }
+    }
+
+    fn served_by(response: &MarketDataResponse) -> &str {
+        response.bars[0]
+            .get_metadata(PROVIDER_METADATA_KEY)
+            .and_then(|value| value.as_str())
+            .unwrap()
+    }
+
+    fn down(service: &str) -> ProviderError {
+        ExternalServiceError::ThirdPartyServiceDown {
+            service: service.to_string(),
+        }
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_asset_class_and_priority() {
+        let exchange = StubProvider::healthy("binance");
+        let equities = StubProvider::healthy("alpha_vantage");
+        let fallback = StubProvider::healthy("fallback");
+        let composite = CompositeProvider::new("router")
+            .with_provider(fallback.clone(), 10)
+            .with_routed_provider(exchange.clone(), 0, [AssetClass::Crypto])
+            .with_routed_provider(equities.clone(), 1, [AssetClass::Stock, AssetClass::ETF]);
+
+        let btc = Symbol::crypto("BTC", "USD", Exchange::Binance).unwrap();
+        let response = composite.fetch_bars(&bars_request(btc)).await.unwrap();
+        assert_eq!(served_by(&response), "binance");
+
+        let ibm = Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap();
+        let response = composite.fetch_bars(&bars_request(ibm)).await.unwrap();
+        assert_eq!(served_by(&response), "alpha_vantage");
+
+        let eur = Symbol::forex("EUR", "USD").unwrap();
+        let response = composite.fetch_bars(&bars_request(eur)).await.unwrap();
+        assert_eq!(served_by(&response), "fallback");
+        assert_eq!(
+            (exchange.calls(), equities.calls(), fallback.calls()),
+            (1, 1, 1)
+        );
+
+        // Nobody serves search: the last Unsupported error comes back
+        let search = composite
+            .search_symbols(&SymbolSearchRequest {
+                query: "IBM".to_string(),
+                asset_class: None,
+                exchange: None,
+                limit: None,
+                include_inactive: false,
+            })
+            .await;
+        assert!(matches!(search, Err(ProviderError::Unsupported { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_routes_crypto_to_exchange_klines() {
+        use crate::ExchangeRestProvider;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let open = Utc
+            .with_ymd_and_hms(2024, 5, 13, 0, 0, 0)
+            .unwrap()
+            .timestamp();
+        Mock::given(method("GET"))
+            .and(path("/0/public/OHLC"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "error": [],
+                "result": {
+                    "XXBTZUSD": [[open, "61500", "63400", "60900", "62980.5", "62200", "1520.3", 48211]],
+                    "last": open
+                }
+            })))
+            .mount(&server)
+            .await;
+        let exchange = |exchange: Exchange| -> Arc<dyn MarketDataProvider> {
+            Arc::new(
+                ExchangeRestProvider::new(&exchange)
+                    .unwrap()
+                    .with_base_url(server.uri()),
+            )
+        };
+        let equities = StubProvider::healthy("alpha_vantage");
+        let composite = CompositeProvider::new("router")
+            .with_routed_provider(exchange(Exchange::Binance), 0, [AssetClass::Crypto])
+            .with_routed_provider(exchange(Exchange::Kraken), 1, [AssetClass::Crypto])
+            .with_routed_provider(equities.clone(), 0, [AssetClass::Stock]);
+
+        // Binance passes on a Kraken listing; Kraken's klines serve it
+        let btc = Symbol::crypto("BTC", "USD", Exchange::Kraken).unwrap();
+        let response = composite.fetch_bars(&bars_request(btc)).await.unwrap();
+        assert_eq!(served_by(&response), "kraken");
+        assert_eq!(response.bars[0].close, Decimal::new(629805, 1));
+        assert_eq!(equities.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_extended_hours_filtered_by_calendar() {
+        let composite =
+            CompositeProvider::new("router").with_provider(StubProvider::healthy("stub"), 0);
+        let intraday = |symbol, include_extended_hours| MarketDataRequest {
+            timeframe: TimeFrame::OneHour,
+            include_extended_hours,
+            ..bars_request(symbol)
+        };
+        let ibm = Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap();
+
+        let regular = composite
+            .fetch_bars(&intraday(ibm.clone(), false))
+            .await
+            .unwrap();
+        let timestamps: Vec<_> = regular.bars.iter().map(|bar| bar.timestamp).collect();
+        assert_eq!(
+            timestamps,
+            [
+                Utc.with_ymd_and_hms(2024, 5, 13, 13, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 5, 13, 14, 0, 0).unwrap()
+            ]
+        );
+
+        let extended = composite.fetch_bars(&intraday(ibm, true)).await.unwrap();
+        assert_eq!(extended.bars.len(), 3);
+        assert!(extended.includes_extended_hours);
+
+        // Crypto has no extended session to strip
+        let btc = Symbol::crypto("BTC", "USD", Exchange::Binance).unwrap();
+        let crypto = composite.fetch_bars(&intraday(btc, false)).await.unwrap();
+        assert_eq!(crypto.bars.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failover_and_circuit_breaker() {
+        let primary = StubProvider::failing("primary", down("primary"));
+        let backup = StubProvider::healthy("backup");
+        let composite = CompositeProvider::new("router")
+            .with_provider(primary.clone(), 0)
+            .with_provider(backup.clone(), 1)
+            .with_circuit_breaker(
+                CircuitBreakerConfig::default()
+                    .with_failure_threshold(2)
+                    .with_cooldown(Duration::from_secs(3600)),
+            );
+        let request = bars_request(Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap());
+
+        for _ in 0..3 {
+            let response = composite.fetch_bars(&request).await.unwrap();
+            assert_eq!(served_by(&response), "backup");
+        }
+
+        // Two failures opened the circuit, so the third request skipped the primary
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(backup.calls(), 3);
+
+        let health = composite.health();
+        assert_eq!(health[0].state, CircuitState::Open);
+        assert_eq!(health[0].consecutive_failures, 2);
+        assert_eq!(
+            health[0].last_error,
+            Some(ExternalServiceError::ThirdPartyServiceDown {
+                service: "primary".to_string()
+            })
+        );
+        assert_eq!(health[1].state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_trial_and_request_errors() {
+        let throttled = StubProvider::failing(
+            "throttled",
+            MarketDataError::RateLimitExceeded {
+                provider: "throttled".to_string(),
+                retry_after: "2024-05-13T00:01:00Z".to_string(),
+            }
+            .into(),
+        );
+        let composite = CompositeProvider::new("router")
+            .with_provider(throttled.clone(), 0)
+            .with_circuit_breaker(
+                CircuitBreakerConfig::default()
+                    .with_failure_threshold(1)
+                    .with_cooldown(Duration::ZERO),
+            );
+        let request = bars_request(Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap());
+
+        let error = composite.fetch_bars(&request).await.unwrap_err();
+        assert!(matches!(
+            error.service_error(),
+            Some(ExternalServiceError::QuotaExceeded { .. })
+        ));
+        // With no cooldown the circuit is immediately half-open and retried
+        assert_eq!(composite.health()[0].state, CircuitState::HalfOpen);
+        let _ = composite.fetch_bars(&request).await;
+        assert_eq!(throttled.calls(), 2);
+
+        // An unknown symbol is not a provider fault: no failover, no breaker change
+        let missing = StubProvider::failing(
+            "missing",
+            MarketDataError::SymbolNotFound {
+                symbol: "IBM".to_string(),
+            }
+            .into(),
+        );
+        let backup = StubProvider::healthy("backup");
+        let composite = CompositeProvider::new("router")
+            .with_provider(missing.clone(), 0)
+            .with_provider(backup.clone(), 1);
+        assert!(matches!(
+            composite.fetch_bars(&request).await,
+            Err(ProviderError::MarketData(
+                MarketDataError::SymbolNotFound { .. }
+            ))
+        ));
+        assert_eq!(backup.calls(), 0);
+        assert_eq!(composite.health()[0].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_allows_one_trial_at_a_time() {
+        let slow = StubProvider::slow("slow", down("slow"), Duration::from_millis(50));
+        let composite = CompositeProvider::new("router")
+            .with_provider(slow.clone(), 0)
+            .with_circuit_breaker(
+                CircuitBreakerConfig::default()
+                    .with_failure_threshold(1)
+                    .with_cooldown(Duration::ZERO),
+            );
+        let request = bars_request(Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap());
+        assert!(composite.fetch_bars(&request).await.is_err());
+        assert_eq!(composite.health()[0].state, CircuitState::HalfOpen);
+
+        // Only one of the concurrent requests reaches the recovering provider
+        let (first, second) = tokio::join!(
+            composite.fetch_bars(&request),
+            composite.fetch_bars(&request)
+        );
+        assert!(first.is_err() && second.is_err());
+        assert_eq!(slow.calls(), 2);
+
+        // The slot is handed back once the trial finishes
+        assert!(composite.fetch_bars(&request).await.is_err());
+        assert_eq!(slow.calls(), 3);
+
+        let mut breaker = Breaker::default();
+        let config = CircuitBreakerConfig::default()
+            .with_failure_threshold(1)
+            .with_cooldown(Duration::ZERO)
+            .with_half_open_trials(2);
+        assert_eq!(breaker.admit(&config), Some(false));
+        breaker.record_failure(
+            ExternalServiceError::ThirdPartyServiceDown {
+                service: "slow".to_string(),
+            },
+            &config,
+        );
+        assert_eq!(breaker.admit(&config), Some(true));
+        assert_eq!(breaker.admit(&config), Some(true));
+        assert_eq!(breaker.admit(&config), None);
+        breaker.finish_trial();
+        assert_eq!(breaker.admit(&config), Some(true));
+    }
+}
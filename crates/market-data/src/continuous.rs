@@ -0,0 +1,222 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde_json::json;
+use shared_types::{FuturesChain, OHLCV};
+use thiserror::Error;
+
+/// `OHLCV::metadata` key naming the contract a continuous bar was taken from
+pub const CONTRACT_METADATA_KEY: &str = "contract";
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ContinuousError {
+    #[error("No {to} bar on {date} to measure the roll from {from}")]
+    MissingRollPrice {
+        from: String,
+        to: String,
+        date: NaiveDate,
+    },
+
+    #[error("Cannot ratio-adjust across the non-positive {contract} close on {date}")]
+    NonPositiveClose { contract: String, date: NaiveDate },
+
+    #[error("No bars for any contract of the {0} chain")]
+    NoData(String),
+}
+
+/// How earlier contracts are shifted to remove the price gap at each roll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackAdjustment {
+    /// Splice raw prices, leaving the roll gaps in the series
+    None,
+    /// Add the gap between the new and old contract's close to all earlier bars
+    Difference,
+    /// Scale earlier bars by the ratio of the closes, preserving percentage returns
+    Ratio,
+}
+
+/// Stitches a futures chain into one back-adjusted series
+///
+/// Each bar is taken from the chain's active contract on its date, and at
+/// every roll the gap is measured on the last bar of the outgoing contract,
+/// which the incoming contract must also have traded. The newest contract is
+/// left unadjusted, so recent prices match what is quoted today.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuousContract {
+    chain: FuturesChain,
+    method: BackAdjustment,
+}
+
+impl ContinuousContract {
+    pub fn new(chain: FuturesChain, method: BackAdjustment) -> Self {
+        Self { chain, method }
+    }
+
+    /// Build the continuous series from bars of the chain's contracts, in any order
+    ///
+    /// Bars are matched to contracts by symbol code; the result is relabelled
+    /// with a `<root>1!` symbol and records each bar's source contract.
+    pub fn build(&self, bars: &[OHLCV]) -> Result<Vec<OHLCV>, ContinuousError> {
+        let mut segments: Vec<(String, Vec<&OHLCV>)> = Vec::new();
+        for contract in &self.chain.contracts {
+            let code = contract.code();
+            let mut segment: Vec<&OHLCV> = bars
+                .iter()
+                .filter(|bar| {
+                    bar.symbol.code == code
+                        && self.chain.active_contract(bar.timestamp.date_naive()) == Some(contract)
+                })
+                .collect();
+            segment.sort_by_key(|bar| bar.timestamp);
+            if !segment.is_empty() {
+                segments.push((code, segment));
+            }
+        }
+        let newest = segments
+            .last()
+            .and_then(|(_, segment)| segment.last())
+            .ok_or_else(|| ContinuousError::NoData(self.chain.root.clone()))?;
+
+        let mut symbol = newest.symbol.clone();
+        symbol.code = format!("{}1!", self.chain.root);
+        symbol.display_name = format!("{} continuous", self.chain.root);
+        symbol.derivative = None;
+
+        // Walk back from the newest contract, accumulating the roll gaps
+        let mut offset = Decimal::ZERO;
+        let mut factor = Decimal::ONE;
+        let mut continuous = Vec::new();
+        for index in (0..segments.len()).rev() {
+            let (code, segment) = &segments[index];
+            for bar in segment.iter().rev() {
+                let mut adjusted = (*bar).clone();
+                let adjust = |price: Decimal| match self.method {
+                    BackAdjustment::None => price,
+                    BackAdjustment::Difference => price + offset,
+                    BackAdjustment::Ratio => (price * factor).round_dp(4),
+                };
+                adjusted.open = adjust(bar.open);
+                adjusted.high = adjust(bar.high);
+                adjusted.low = adjust(bar.low);
+                adjusted.close = adjust(bar.close);
+                adjusted.symbol = symbol.clone();
+                adjusted.add_metadata(CONTRACT_METADATA_KEY, json!(code));
+                continuous.push(adjusted);
+            }
+
+            let Some((previous, previous_segment)) = index.checked_sub(1).map(|i| &segments[i])
+            else {
+                break;
+            };
+            let outgoing = previous_segment.last().expect("segments are non-empty");
+            let date = outgoing.timestamp.date_naive();
+            let incoming = bars
+                .iter()
+                .find(|bar| bar.symbol.code == *code && bar.timestamp == outgoing.timestamp)
+                .ok_or_else(|| ContinuousError::MissingRollPrice {
+                    from: previous.clone(),
+                    to: code.clone(),
+                    date,
+                })?;
+            offset += incoming.close - outgoing.close;
+            if self.method == BackAdjustment::Ratio {
+                if outgoing.close <= Decimal::ZERO {
+                    return Err(ContinuousError::NonPositiveClose {
+                        contract: previous.clone(),
+                        date,
+                    });
+                }
+                factor *= incoming.close / outgoing.close;
+            }
+        }
+        continuous.reverse();
+        Ok(continuous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use shared_types::{Exchange, FutureSpec, RollSchedule, Symbol, TimeFrame};
+
+    fn bar(contract: &Symbol, day: u32, close: i64) -> OHLCV {
+        let close = Decimal::new(close, 0);
+        OHLCV::new(
+            contract.clone(),
+            TimeFrame::OneDay,
+            Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap(),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1000, 0),
+        )
+        .unwrap()
+    }
+
+    /// ESH4 rolls into ESM4 on 7 March 2024; on the 6th they closed 20 points apart
+    fn fixture() -> (ContinuousContract, Vec<OHLCV>) {
+        let march = FutureSpec::parse("ESH4", 2024).unwrap();
+        let june = FutureSpec::parse("ESM4", 2024).unwrap();
+        let chain =
+            FuturesChain::new(vec![march.clone(), june.clone()], RollSchedule::default()).unwrap();
+        let exchange = Exchange::Other("CME".to_string());
+        let h4 = Symbol::future(march, exchange.clone(), "USD").unwrap();
+        let m4 = Symbol::future(june, exchange, "USD").unwrap();
+        let bars = vec![
+            bar(&h4, 5, 5000),
+            bar(&h4, 6, 5100),
+            bar(&m4, 6, 5120),
+            bar(&h4, 7, 5110),
+            bar(&m4, 7, 5150),
+        ];
+        (ContinuousContract::new(chain, BackAdjustment::None), bars)
+    }
+
+    fn closes(bars: &[OHLCV]) -> Vec<Decimal> {
+        bars.iter().map(|bar| bar.close).collect()
+    }
+
+    #[test]
+    fn test_back_adjusts_across_roll() {
+        let (raw, bars) = fixture();
+        let spliced = raw.build(&bars).unwrap();
+        assert_eq!(closes(&spliced), [5000, 5100, 5150].map(Decimal::from));
+        assert_eq!(spliced[0].symbol.code, "ES1!");
+        assert_eq!(
+            spliced[2].get_metadata(CONTRACT_METADATA_KEY),
+            Some(&json!("ESM24"))
+        );
+
+        let difference = ContinuousContract::new(raw.chain.clone(), BackAdjustment::Difference);
+        assert_eq!(
+            closes(&difference.build(&bars).unwrap()),
+            [5020, 5120, 5150].map(Decimal::from)
+        );
+
+        let ratio = ContinuousContract::new(raw.chain.clone(), BackAdjustment::Ratio);
+        // 5000 * 5120 / 5100
+        assert_eq!(
+            ratio.build(&bars).unwrap()[0].close,
+            "5019.6078".parse::<Decimal>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_roll_needs_overlapping_bar() {
+        let (raw, mut bars) = fixture();
+        bars.remove(2);
+        assert_eq!(
+            raw.build(&bars),
+            Err(ContinuousError::MissingRollPrice {
+                from: "ESH24".to_string(),
+                to: "ESM24".to_string(),
+                date: NaiveDate::from_ymd_opt(2024, 3, 6).unwrap(),
+            })
+        );
+        assert!(matches!(
+            raw.build(&[]),
+            Err(ContinuousError::NoData(root)) if root == "ES"
+        ));
+    }
+}
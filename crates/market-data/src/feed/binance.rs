@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use shared_types::{Tick, TimeFrame, TradeSide};
+
+use super::rest::{Kline, KlineError, KlineQuery};
+use super::{decimal, FeedEvent, Subscriptions};
+
+pub(super) const DEFAULT_URL: &str = "wss://stream.binance.com:9443/ws";
+
+pub(super) const REST_URL: &str = "https://api.binance.com";
+
+/// Klines returned by one `/api/v3/klines` call
+const KLINES_PER_REQUEST: u32 = 1000;
+
+pub(super) fn interval(timeframe: &TimeFrame) -> Option<&'static str> {
+    match timeframe {
+        TimeFrame::OneMinute => Some("1m"),
+        TimeFrame::FiveMinutes => Some("5m"),
+        TimeFrame::FifteenMinutes => Some("15m"),
+        TimeFrame::ThirtyMinutes => Some("30m"),
+        TimeFrame::OneHour => Some("1h"),
+        TimeFrame::FourHours => Some("4h"),
+        TimeFrame::OneDay => Some("1d"),
+        TimeFrame::OneWeek => Some("1w"),
+        TimeFrame::OneMonth => Some("1M"),
+        TimeFrame::Custom { .. } => None,
+    }
+}
+
+pub(super) fn subscriptions(feed: &Subscriptions) -> Vec<Value> {
+    let kline = feed.timeframe.as_ref().and_then(interval);
+    let streams: Vec<String> = feed
+        .symbols
+        .iter()
+        .flat_map(|symbol| {
+            let stream = feed.venue.pair_code(symbol).to_lowercase();
+            let trade = feed.trades.then(|| format!("{}@trade", stream));
+            let kline = kline.map(|interval| format!("{}@kline_{}", stream, interval));
+            trade.into_iter().chain(kline)
+        })
+        .collect();
+
+    vec![json!({ "method": "SUBSCRIBE", "params": streams, "id": 1 })]
+}
+
+pub(super) fn parse(frame: &Value, feed: &Subscriptions) -> Result<Vec<FeedEvent>, String> {
+    // Combined streams wrap each event as {"stream": ..., "data": {...}}
+    let event = frame.get("data").unwrap_or(frame);
+    let Some(symbol) = event
+        .get("s")
+        .and_then(Value::as_str)
+        .and_then(|code| feed.symbol(code))
+    else {
+        // Subscription acks ({"result": null, "id": 1}) and unknown pairs
+        return Ok(Vec::new());
+    };
+
+    match event.get("e").and_then(Value::as_str) {
+        Some("trade") if feed.trades => {
+            let side = match event.get("m").and_then(Value::as_bool) {
+                Some(true) => Some(TradeSide::Sell),
+                Some(false) => Some(TradeSide::Buy),
+                None => None,
+            };
+            Ok(vec![FeedEvent::Trade(Tick {
+                symbol: symbol.clone(),
+                price: decimal(event.get("p"), "p")?,
+                quantity: decimal(event.get("q"), "q")?,
+                side,
+                trade_id: event.get("t").map(Value::to_string),
+                timestamp: millis(event.get("T"), "T")?,
+            })])
+        }
+        Some("kline") if feed.timeframe.is_some() => {
+            let kline = event.get("k").ok_or("kline without 'k'")?;
+            let bar = feed.bar(
+                symbol,
+                millis(kline.get("t"), "t")?,
+                [
+                    decimal(kline.get("o"), "o")?,
+                    decimal(kline.get("h"), "h")?,
+                    decimal(kline.get("l"), "l")?,
+                    decimal(kline.get("c"), "c")?,
+                    decimal(kline.get("v"), "v")?,
+                ],
+            )?;
+            let is_closed = kline.get("x").and_then(Value::as_bool).unwrap_or(false);
+            Ok(vec![FeedEvent::Bar { bar, is_closed }])
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+pub(super) fn klines_query(
+    pair: &str,
+    timeframe: &TimeFrame,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Option<KlineQuery> {
+    let mut params = vec![
+        ("symbol", pair.to_string()),
+        ("interval", interval(timeframe)?.to_string()),
+        ("limit", KLINES_PER_REQUEST.to_string()),
+    ];
+    if let Some(start) = start {
+        params.push(("startTime", start.timestamp_millis().to_string()));
+    }
+    if let Some(end) = end {
+        params.push(("endTime", end.timestamp_millis().to_string()));
+    }
+    Some(KlineQuery {
+        path: "/api/v3/klines".to_string(),
+        params,
+    })
+}
+
+/// `[[open time, open, high, low, close, volume, close time, ...], ...]`, oldest first
+///
+/// Errors come back as `{"code": -1121, "msg": "Invalid symbol."}`.
+pub(super) fn parse_klines(body: &Value) -> Result<Vec<Kline>, KlineError> {
+    if let Some(code) = body.get("code").and_then(Value::as_i64) {
+        return Err(match code {
+            -1121 => KlineError::UnknownPair,
+            -1003 => KlineError::RateLimited,
+            _ => KlineError::Rejected(body.get("msg").map(Value::to_string).unwrap_or_default()),
+        });
+    }
+    let rows = body.as_array().ok_or("klines are not an array")?;
+    rows.iter()
+        .map(|row| {
+            let field = |index: usize| row.get(index);
+            Ok((
+                millis(field(0), "open time")?,
+                [
+                    decimal(field(1), "open")?,
+                    decimal(field(2), "high")?,
+                    decimal(field(3), "low")?,
+                    decimal(field(4), "close")?,
+                    decimal(field(5), "volume")?,
+                ],
+            ))
+        })
+        .collect()
+}
+
+fn millis(value: Option<&Value>, field: &str) -> Result<DateTime<Utc>, String> {
+    value
+        .and_then(Value::as_i64)
+        .and_then(DateTime::from_timestamp_millis)
+        .ok_or_else(|| format!("missing or invalid '{}'", field))
+}
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use shared_types::{Tick, TimeFrame, TradeSide};
+
+use super::rest::{Kline, KlineError, KlineQuery};
+use super::{decimal, rfc3339, FeedEvent, Subscriptions};
+
+pub(super) const DEFAULT_URL: &str = "wss://advanced-trade-ws.coinbase.com";
+
+pub(super) const REST_URL: &str = "https://api.coinbase.com";
+
+/// Candles one `/candles` call may span
+const CANDLES_PER_REQUEST: i32 = 350;
+
+/// The Advanced Trade `candles` channel only publishes five-minute candles
+pub(super) fn supports(timeframe: &TimeFrame) -> bool {
+    *timeframe == TimeFrame::FiveMinutes
+}
+
+/// Advanced Trade REST candle granularity
+fn granularity(timeframe: &TimeFrame) -> Option<&'static str> {
+    match timeframe {
+        TimeFrame::OneMinute => Some("ONE_MINUTE"),
+        TimeFrame::FiveMinutes => Some("FIVE_MINUTE"),
+        TimeFrame::FifteenMinutes => Some("FIFTEEN_MINUTE"),
+        TimeFrame::ThirtyMinutes => Some("THIRTY_MINUTE"),
+        TimeFrame::OneHour => Some("ONE_HOUR"),
+        TimeFrame::OneDay => Some("ONE_DAY"),
+        _ => None,
+    }
+}
+
+/// `GET /api/v3/brokerage/market/products/{pair}/candles` over at most 350 candles
+///
+/// The endpoint needs both ends of the range, so an open end is now and an
+/// open start is as far back as one call reaches; a range longer than one
+/// call is cut short at its end.
+pub(super) fn klines_query(
+    pair: &str,
+    timeframe: &TimeFrame,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Option<KlineQuery> {
+    let granularity = granularity(timeframe)?;
+    let span = chrono::Duration::seconds(timeframe.to_seconds() as i64) * (CANDLES_PER_REQUEST - 1);
+    let end = end.unwrap_or_else(Utc::now);
+    let (start, end) = match start {
+        Some(start) => (start, end.min(start + span)),
+        None => (end - span, end),
+    };
+    Some(KlineQuery {
+        path: format!("/api/v3/brokerage/market/products/{}/candles", pair),
+        params: vec![
+            ("start", start.timestamp().to_string()),
+            ("end", end.timestamp().to_string()),
+            ("granularity", granularity.to_string()),
+        ],
+    })
+}
+
+/// `{"candles": [{"start": "<unix seconds>", "low", "high", "open", "close", "volume"}]}`, newest first
+///
+/// Unknown products answer HTTP 404, which the caller maps.
+pub(super) fn parse_klines(body: &Value) -> Result<Vec<Kline>, KlineError> {
+    let candles = body
+        .get("candles")
+        .and_then(Value::as_array)
+        .ok_or("response without 'candles'")?;
+    let mut klines = candles
+        .iter()
+        .map(|candle| {
+            let start = candle
+                .get("start")
+                .and_then(Value::as_str)
+                .and_then(|start| start.parse::<i64>().ok())
+                .and_then(|start| DateTime::from_timestamp(start, 0))
+                .ok_or("missing or invalid 'start'")?;
+            Ok((
+                start,
+                [
+                    decimal(candle.get("open"), "open")?,
+                    decimal(candle.get("high"), "high")?,
+                    decimal(candle.get("low"), "low")?,
+                    decimal(candle.get("close"), "close")?,
+                    decimal(candle.get("volume"), "volume")?,
+                ],
+            ))
+        })
+        .collect::<Result<Vec<Kline>, KlineError>>()?;
+    klines.reverse();
+    Ok(klines)
+}
+
+pub(super) fn subscriptions(feed: &Subscriptions) -> Vec<Value> {
+    let products: Vec<String> = feed
+        .symbols
+        .iter()
+        .map(|symbol| feed.venue.pair_code(symbol))
+        .collect();
+    let subscribe =
+        |channel| json!({ "type": "subscribe", "product_ids": products, "channel": channel });
+
+    let mut messages = Vec::new();
+    if feed.trades {
+        messages.push(subscribe("market_trades"));
+    }
+    if feed.timeframe.is_some() {
+        messages.push(subscribe("candles"));
+    }
+    messages
+}
+
+pub(super) fn parse(frame: &Value, feed: &Subscriptions) -> Result<Vec<FeedEvent>, String> {
+    let channel = frame.get("channel").and_then(Value::as_str);
+    let updates = frame
+        .get("events")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut events = Vec::new();
+    for update in updates {
+        match channel {
+            Some("market_trades") if feed.trades => {
+                for trade in entries(update, "trades") {
+                    let Some(symbol) = product(trade, feed) else {
+                        continue;
+                    };
+                    let side = match trade.get("side").and_then(Value::as_str) {
+                        Some("BUY") => Some(TradeSide::Buy),
+                        Some("SELL") => Some(TradeSide::Sell),
+                        _ => None,
+                    };
+                    events.push(FeedEvent::Trade(Tick {
+                        symbol: symbol.clone(),
+                        price: decimal(trade.get("price"), "price")?,
+                        quantity: decimal(trade.get("size"), "size")?,
+                        side,
+                        trade_id: trade
+                            .get("trade_id")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                        timestamp: rfc3339(trade.get("time"), "time")?,
+                    }));
+                }
+            }
+            Some("candles") if feed.timeframe.is_some() => {
+                for candle in entries(update, "candles") {
+                    let Some(symbol) = product(candle, feed) else {
+                        continue;
+                    };
+                    let start = candle
+                        .get("start")
+                        .and_then(Value::as_str)
+                        .and_then(|start| start.parse().ok())
+                        .and_then(|start| DateTime::from_timestamp(start, 0))
+                        .ok_or("missing or invalid candle 'start'")?;
+                    let bar = feed.bar(
+                        symbol,
+                        start,
+                        [
+                            decimal(candle.get("open"), "open")?,
+                            decimal(candle.get("high"), "high")?,
+                            decimal(candle.get("low"), "low")?,
+                            decimal(candle.get("close"), "close")?,
+                            decimal(candle.get("volume"), "volume")?,
+                        ],
+                    )?;
+                    // Candles carry no completion flag; the feed closes them when the next starts
+                    events.push(FeedEvent::Bar {
+                        bar,
+                        is_closed: false,
+                    });
+                }
+            }
+            // subscriptions acks, heartbeats and unsubscribed channels
+            _ => {}
+        }
+    }
+    Ok(events)
+}
+
+fn entries<'a>(update: &'a Value, key: &str) -> &'a [Value] {
+    update
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn product<'a>(entry: &Value, feed: &'a Subscriptions) -> Option<&'a shared_types::Symbol> {
+    entry
+        .get("product_id")
+        .and_then(Value::as_str)
+        .and_then(|code| feed.symbol(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Venue;
+    use super::*;
+    use rust_decimal::Decimal;
+    use shared_types::{Exchange, Symbol};
+
+    #[test]
+    fn test_parses_captured_frames() {
+        let feed = Subscriptions {
+            venue: Venue::Coinbase,
+            symbols: vec![Symbol::crypto("BTC", "USD", Exchange::Coinbase).unwrap()],
+            trades: true,
+            timeframe: Some(TimeFrame::FiveMinutes),
+        };
+        assert_eq!(subscriptions(&feed)[0]["product_ids"], json!(["BTC-USD"]));
+
+        let events: Vec<FeedEvent> = include_str!("../../fixtures/feeds/coinbase_btcusd.jsonl")
+            .lines()
+            .flat_map(|line| parse(&serde_json::from_str(line).unwrap(), &feed).unwrap())
+            .collect();
+        assert_eq!(events.len(), 3);
+
+        let FeedEvent::Trade(sell) = &events[1] else {
+            panic!("expected a trade");
+        };
+        assert_eq!(sell.side, Some(TradeSide::Sell));
+        assert_eq!(sell.quantity, Decimal::new(5, 1));
+
+        let FeedEvent::Bar { bar, .. } = &events[2] else {
+            panic!("expected a candle");
+        };
+        assert_eq!(bar.timeframe, TimeFrame::FiveMinutes);
+        assert_eq!(bar.timestamp.timestamp(), 1_715_608_800);
+        assert_eq!(bar.close, Decimal::new(6715055, 2));
+    }
+}
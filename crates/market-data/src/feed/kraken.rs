@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use shared_types::{Tick, TimeFrame, TradeSide};
+
+use super::rest::{Kline, KlineError, KlineQuery};
+use super::{decimal, rfc3339, FeedEvent, Subscriptions};
+
+pub(super) const DEFAULT_URL: &str = "wss://ws.kraken.com/v2";
+
+pub(super) const REST_URL: &str = "https://api.kraken.com";
+
+/// OHLC interval in minutes
+pub(super) fn interval(timeframe: &TimeFrame) -> Option<u32> {
+    match timeframe {
+        TimeFrame::OneMinute => Some(1),
+        TimeFrame::FiveMinutes => Some(5),
+        TimeFrame::FifteenMinutes => Some(15),
+        TimeFrame::ThirtyMinutes => Some(30),
+        TimeFrame::OneHour => Some(60),
+        TimeFrame::FourHours => Some(240),
+        TimeFrame::OneDay => Some(1440),
+        TimeFrame::OneWeek => Some(10080),
+        TimeFrame::OneMonth | TimeFrame::Custom { .. } => None,
+    }
+}
+
+/// `GET /0/public/OHLC`, returning up to the latest 720 klines after `start`
+///
+/// The REST API names pairs without the slash and calls bitcoin XBT and
+/// dogecoin XDG ("BTC/USD" is "XBTUSD"). It takes no end time; the caller
+/// trims the reply.
+pub(super) fn klines_query(
+    pair: &str,
+    timeframe: &TimeFrame,
+    start: Option<DateTime<Utc>>,
+) -> Option<KlineQuery> {
+    let (base, quote) = pair.split_once('/').unwrap_or((pair, ""));
+    let rest_asset = |asset: &str| match asset {
+        "BTC" => "XBT".to_string(),
+        "DOGE" => "XDG".to_string(),
+        other => other.to_string(),
+    };
+    let mut params = vec![
+        ("pair", format!("{}{}", rest_asset(base), rest_asset(quote))),
+        ("interval", interval(timeframe)?.to_string()),
+    ];
+    if let Some(start) = start {
+        // `since` is exclusive; step back a second to keep the starting kline
+        params.push(("since", (start.timestamp() - 1).to_string()));
+    }
+    Some(KlineQuery {
+        path: "/0/public/OHLC".to_string(),
+        params,
+    })
+}
+
+/// `{"error": [], "result": {"<pair>": [[time, open, high, low, close, vwap, volume, count]], "last": n}}`
+///
+/// Errors come back with HTTP 200 in the `error` array.
+pub(super) fn parse_klines(body: &Value) -> Result<Vec<Kline>, KlineError> {
+    let errors = body
+        .get("error")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if let Some(error) = errors.iter().filter_map(Value::as_str).next() {
+        return Err(if error.contains("Unknown asset pair") {
+            KlineError::UnknownPair
+        } else if error.contains("Rate limit") || error.contains("Too many requests") {
+            KlineError::RateLimited
+        } else if error.starts_with("EService") {
+            KlineError::Unavailable
+        } else {
+            KlineError::Rejected(error.to_string())
+        });
+    }
+    let rows = body
+        .get("result")
+        .and_then(Value::as_object)
+        .and_then(|result| {
+            result
+                .iter()
+                .find(|(key, _)| key.as_str() != "last")
+                .and_then(|(_, rows)| rows.as_array())
+        })
+        .ok_or("response without OHLC rows")?;
+    rows.iter()
+        .map(|row| {
+            let field = |index: usize| row.get(index);
+            let time = field(0)
+                .and_then(Value::as_i64)
+                .and_then(|time| DateTime::from_timestamp(time, 0))
+                .ok_or("missing or invalid time")?;
+            Ok((
+                time,
+                [
+                    decimal(field(1), "open")?,
+                    decimal(field(2), "high")?,
+                    decimal(field(3), "low")?,
+                    decimal(field(4), "close")?,
+                    decimal(field(6), "volume")?,
+                ],
+            ))
+        })
+        .collect()
+}
+
+pub(super) fn subscriptions(feed: &Subscriptions) -> Vec<Value> {
+    let pairs: Vec<String> = feed
+        .symbols
+        .iter()
+        .map(|symbol| feed.venue.pair_code(symbol))
+        .collect();
+
+    let mut messages = Vec::new();
+    if feed.trades {
+        messages.push(json!({
+            "method": "subscribe",
+            "params": { "channel": "trade", "symbol": pairs, "snapshot": false }
+        }));
+    }
+    if let Some(minutes) = feed.timeframe.as_ref().and_then(interval) {
+        messages.push(json!({
+            "method": "subscribe",
+            "params": { "channel": "ohlc", "symbol": pairs, "interval": minutes }
+        }));
+    }
+    messages
+}
+
+pub(super) fn parse(frame: &Value, feed: &Subscriptions) -> Result<Vec<FeedEvent>, String> {
+    let channel = frame.get("channel").and_then(Value::as_str);
+    let data = frame
+        .get("data")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut events = Vec::new();
+    for entry in data {
+        let Some(symbol) = entry
+            .get("symbol")
+            .and_then(Value::as_str)
+            .and_then(|pair| feed.symbol(pair))
+        else {
+            continue;
+        };
+
+        match channel {
+            Some("trade") if feed.trades => {
+                let side = match entry.get("side").and_then(Value::as_str) {
+                    Some("buy") => Some(TradeSide::Buy),
+                    Some("sell") => Some(TradeSide::Sell),
+                    _ => None,
+                };
+                events.push(FeedEvent::Trade(Tick {
+                    symbol: symbol.clone(),
+                    price: decimal(entry.get("price"), "price")?,
+                    quantity: decimal(entry.get("qty"), "qty")?,
+                    side,
+                    trade_id: entry.get("trade_id").map(Value::to_string),
+                    timestamp: rfc3339(entry.get("timestamp"), "timestamp")?,
+                }));
+            }
+            Some("ohlc") if feed.timeframe.is_some() => {
+                let bar = feed.bar(
+                    symbol,
+                    rfc3339(entry.get("interval_begin"), "interval_begin")?,
+                    [
+                        decimal(entry.get("open"), "open")?,
+                        decimal(entry.get("high"), "high")?,
+                        decimal(entry.get("low"), "low")?,
+                        decimal(entry.get("close"), "close")?,
+                        decimal(entry.get("volume"), "volume")?,
+                    ],
+                )?;
+                // Updates stream while the interval forms; the feed closes it when the next begins
+                events.push(FeedEvent::Bar {
+                    bar,
+                    is_closed: false,
+                });
+            }
+            // status, heartbeat and subscription acks
+            _ => {}
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Venue;
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use shared_types::{Exchange, Symbol};
+
+    #[test]
+    fn test_parses_captured_frames() {
+        let feed = Subscriptions {
+            venue: Venue::Kraken,
+            symbols: vec![Symbol::crypto("BTC", "USD", Exchange::Kraken).unwrap()],
+            trades: true,
+            timeframe: Some(TimeFrame::OneMinute),
+        };
+        let messages = subscriptions(&feed);
+        assert_eq!(messages[1]["params"]["symbol"], json!(["BTC/USD"]));
+        assert_eq!(messages[1]["params"]["interval"], json!(1));
+
+        let events: Vec<FeedEvent> = include_str!("../../fixtures/feeds/kraken_btcusd.jsonl")
+            .lines()
+            .flat_map(|line| parse(&serde_json::from_str(line).unwrap(), &feed).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+
+        let FeedEvent::Trade(tick) = &events[0] else {
+            panic!("expected a trade");
+        };
+        assert_eq!(tick.price, Decimal::new(671234, 1));
+        assert_eq!(tick.quantity, Decimal::new(15, 4));
+        assert_eq!(tick.side, Some(TradeSide::Sell));
+
+        let FeedEvent::Bar { bar, .. } = &events[1] else {
+            panic!("expected a candle");
+        };
+        assert_eq!(
+            bar.timestamp,
+            Utc.with_ymd_and_hms(2024, 5, 13, 14, 0, 0).unwrap()
+        );
+        assert_eq!(bar.volume, Decimal::new(15012, 4));
+    }
+}
@@ -0,0 +1,839 @@
+//! Streaming trade and kline feeds from crypto exchange WebSockets, and their REST klines
+
+mod binance;
+mod coinbase;
+mod kraken;
+mod rest;
+
+pub use rest::ExchangeRestProvider;
+
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use shared_types::{
+    DataAdjustment, Exchange, MarketDataRequest, NetworkError, Symbol, Tick, TimeFrame, Vendor,
+    OHLCV,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::composite::PROVIDER_METADATA_KEY;
+use crate::provider::{MarketDataProvider, ProviderError, ProviderResult};
+
+/// Something that happened on a feed
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedEvent {
+    /// A trade print
+    Trade(Tick),
+
+    /// A kline update; `is_closed` is false while the interval is still forming
+    Bar { bar: OHLCV, is_closed: bool },
+}
+
+/// Exponential backoff between reconnection attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+
+    pub max_delay: Duration,
+
+    /// Give up after this many consecutive failed attempts (`None` retries forever)
+    ///
+    /// A connection that closes before the venue sends anything counts as a
+    /// failed attempt, so a server that accepts and immediately drops the
+    /// socket still backs off and eventually gives up.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Delay before the given attempt (1-based), doubling each time up to `max_delay`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(16);
+        self.initial_delay
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Venue {
+    Binance,
+    Coinbase,
+    Kraken,
+}
+
+impl Venue {
+    /// The venue serving `exchange`, or `Unsupported` naming `provider`
+    ///
+    /// Bitfinex is modelled as an exchange but its feeds and klines are not
+    /// implemented; it is refused like any exchange without a venue.
+    fn for_exchange(exchange: &Exchange, provider: &str) -> ProviderResult<Self> {
+        match exchange {
+            Exchange::Binance => Ok(Venue::Binance),
+            Exchange::Coinbase => Ok(Venue::Coinbase),
+            Exchange::Kraken => Ok(Venue::Kraken),
+            Exchange::Bitfinex => Err(ProviderError::Unsupported {
+                provider: provider.to_string(),
+                operation: "Bitfinex, which has no feed implementation".to_string(),
+            }),
+            other => Err(ProviderError::Unsupported {
+                provider: provider.to_string(),
+                operation: format!("streaming from {}", other),
+            }),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Venue::Binance => "binance",
+            Venue::Coinbase => "coinbase",
+            Venue::Kraken => "kraken",
+        }
+    }
+
+    fn default_url(self) -> &'static str {
+        match self {
+            Venue::Binance => binance::DEFAULT_URL,
+            Venue::Coinbase => coinbase::DEFAULT_URL,
+            Venue::Kraken => kraken::DEFAULT_URL,
+        }
+    }
+
+    fn rest_url(self) -> &'static str {
+        match self {
+            Venue::Binance => binance::REST_URL,
+            Venue::Coinbase => coinbase::REST_URL,
+            Venue::Kraken => kraken::REST_URL,
+        }
+    }
+
+    /// The klines call for a pair and range, if the venue serves the timeframe
+    fn klines_query(
+        self,
+        symbol: &Symbol,
+        timeframe: &TimeFrame,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Option<rest::KlineQuery> {
+        let pair = self.pair_code(symbol);
+        match self {
+            Venue::Binance => binance::klines_query(&pair, timeframe, start, end),
+            Venue::Coinbase => coinbase::klines_query(&pair, timeframe, start, end),
+            Venue::Kraken => kraken::klines_query(&pair, timeframe, start),
+        }
+    }
+
+    fn parse_klines(self, body: &Value) -> Result<Vec<rest::Kline>, rest::KlineError> {
+        match self {
+            Venue::Binance => binance::parse_klines(body),
+            Venue::Coinbase => coinbase::parse_klines(body),
+            Venue::Kraken => kraken::parse_klines(body),
+        }
+    }
+
+    /// Venue spelling of a pair (e.g. "BTCUSD", "BTC-USD", "BTC/USD")
+    fn pair_code(self, symbol: &Symbol) -> String {
+        let vendor = match self {
+            Venue::Binance => Vendor::Binance,
+            Venue::Coinbase => Vendor::Coinbase,
+            Venue::Kraken => Vendor::Kraken,
+        };
+        vendor.ticker(symbol)
+    }
+
+    fn supports_timeframe(self, timeframe: &TimeFrame) -> bool {
+        match self {
+            Venue::Binance => binance::interval(timeframe).is_some(),
+            Venue::Coinbase => coinbase::supports(timeframe),
+            Venue::Kraken => kraken::interval(timeframe).is_some(),
+        }
+    }
+
+    fn subscriptions(self, feed: &Subscriptions) -> Vec<Value> {
+        match self {
+            Venue::Binance => binance::subscriptions(feed),
+            Venue::Coinbase => coinbase::subscriptions(feed),
+            Venue::Kraken => kraken::subscriptions(feed),
+        }
+    }
+
+    fn parse(self, frame: &Value, feed: &Subscriptions) -> Result<Vec<FeedEvent>, String> {
+        match self {
+            Venue::Binance => binance::parse(frame, feed),
+            Venue::Coinbase => coinbase::parse(frame, feed),
+            Venue::Kraken => kraken::parse(frame, feed),
+        }
+    }
+}
+
+/// What a feed is subscribed to, shared with the venue parsers
+struct Subscriptions {
+    venue: Venue,
+    symbols: Vec<Symbol>,
+    trades: bool,
+    timeframe: Option<TimeFrame>,
+}
+
+impl Subscriptions {
+    fn symbol(&self, pair_code: &str) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .find(|symbol| self.venue.pair_code(symbol) == pair_code)
+    }
+
+    fn bar(
+        &self,
+        symbol: &Symbol,
+        timestamp: DateTime<Utc>,
+        [open, high, low, close, volume]: [Decimal; 5],
+    ) -> Result<OHLCV, String> {
+        let timeframe = self
+            .timeframe
+            .clone()
+            .ok_or_else(|| "kline received without a kline subscription".to_string())?;
+        let mut bar = OHLCV::new(
+            symbol.clone(),
+            timeframe,
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        )
+        .map_err(|e| e.to_string())?;
+        bar.add_metadata(PROVIDER_METADATA_KEY, json!(self.venue.name()));
+        Ok(bar)
+    }
+}
+
+/// WebSocket client for a crypto exchange's public market data stream
+///
+/// The feed runs on a background task. Every failed connection or dropped
+/// stream is reported as `NetworkError::WebSocketConnectionFailed` and followed
+/// by a reconnect under the [`ReconnectPolicy`]. When a resync provider is set,
+/// klines missed while disconnected are fetched over REST and emitted before
+/// live updates resume.
+pub struct ExchangeFeed {
+    subscriptions: Subscriptions,
+    url: String,
+    reconnect: ReconnectPolicy,
+    connect_timeout: Duration,
+    buffer: usize,
+    resync: Option<Arc<dyn MarketDataProvider>>,
+}
+
+impl ExchangeFeed {
+    /// Trade feed for `symbols` on a supported exchange (Binance, Coinbase, Kraken)
+    pub fn new(exchange: &Exchange, symbols: Vec<Symbol>) -> ProviderResult<Self> {
+        let venue = Venue::for_exchange(exchange, "exchange_feed")?;
+
+        Ok(Self {
+            subscriptions: Subscriptions {
+                venue,
+                symbols,
+                trades: true,
+                timeframe: None,
+            },
+            url: venue.default_url().to_string(),
+            reconnect: ReconnectPolicy::default(),
+            connect_timeout: Duration::from_secs(10),
+            buffer: 1024,
+            resync: None,
+        })
+    }
+
+    /// Override the endpoint, e.g. to point at a local test server
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    pub fn with_trades(mut self, trades: bool) -> Self {
+        self.subscriptions.trades = trades;
+        self
+    }
+
+    /// Also subscribe to klines of the given timeframe
+    pub fn with_bars(mut self, timeframe: TimeFrame) -> Self {
+        self.subscriptions.timeframe = Some(timeframe);
+        self
+    }
+
+    pub fn with_reconnect(mut self, reconnect: ReconnectPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Backfill klines missed during disconnects from a REST provider
+    pub fn with_resync(mut self, provider: Arc<dyn MarketDataProvider>) -> Self {
+        self.resync = Some(provider);
+        self
+    }
+
+    /// Connect and start streaming on a background task
+    pub fn start(self) -> ProviderResult<FeedSubscription> {
+        let venue = self.subscriptions.venue;
+        if let Some(timeframe) = &self.subscriptions.timeframe {
+            if !venue.supports_timeframe(timeframe) {
+                return Err(ProviderError::Unsupported {
+                    provider: venue.name().to_string(),
+                    operation: format!("{} klines", timeframe),
+                });
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let task = tokio::spawn(self.run(sender));
+        Ok(FeedSubscription { receiver, task })
+    }
+
+    async fn run(self, sender: mpsc::Sender<Result<FeedEvent, NetworkError>>) {
+        let venue = self.subscriptions.venue;
+        let mut last_bar: HashMap<String, DateTime<Utc>> = HashMap::new();
+        let mut forming: HashMap<String, OHLCV> = HashMap::new();
+        let mut failures = 0;
+        let mut connected_before = false;
+
+        loop {
+            let reason = match self.connect().await {
+                Ok(mut socket) => {
+                    let mut received = false;
+                    if connected_before {
+                        for event in self.resync(&last_bar).await {
+                            if sender.send(Ok(event)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    connected_before = true;
+
+                    let reason = loop {
+                        let frame = match socket.next().await {
+                            Some(Ok(Message::Text(text))) => {
+                                // Only a session that delivers data ends the backoff
+                                if !received {
+                                    received = true;
+                                    failures = 0;
+                                }
+                                text
+                            }
+                            Some(Ok(Message::Close(frame))) => {
+                                break format!("closed by server: {:?}", frame);
+                            }
+                            Some(Ok(_)) => continue,
+                            Some(Err(e)) => break e.to_string(),
+                            None => break "stream ended".to_string(),
+                        };
+
+                        let events = serde_json::from_str(frame.as_str())
+                            .map_err(|e| e.to_string())
+                            .and_then(|frame| venue.parse(&frame, &self.subscriptions));
+                        let events = match events {
+                            Ok(events) => events,
+                            Err(error) => {
+                                tracing::warn!(venue = venue.name(), %error, "Skipping malformed frame");
+                                continue;
+                            }
+                        };
+
+                        for event in events {
+                            let mut outgoing = vec![];
+                            if let FeedEvent::Bar { bar, is_closed } = &event {
+                                outgoing.extend(close_previous(&mut forming, bar, *is_closed));
+                                last_bar.insert(bar.symbol.code.clone(), bar.timestamp);
+                            }
+                            outgoing.push(event);
+                            for event in outgoing {
+                                if sender.send(Ok(event)).await.is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                    };
+                    // Bars still forming at disconnect are covered by the resync
+                    forming.clear();
+                    if !received {
+                        failures += 1;
+                    }
+                    reason
+                }
+                Err(reason) => {
+                    failures += 1;
+                    reason
+                }
+            };
+
+            tracing::warn!(venue = venue.name(), %reason, "Feed disconnected");
+            let error = NetworkError::WebSocketConnectionFailed { reason };
+            if sender.send(Err(error)).await.is_err() {
+                return;
+            }
+            if self
+                .reconnect
+                .max_attempts
+                .is_some_and(|max| failures >= max)
+            {
+                return;
+            }
+            tokio::time::sleep(self.reconnect.delay(failures.max(1))).await;
+        }
+    }
+
+    async fn connect(
+        &self,
+    ) -> Result<
+        tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+        String,
+    > {
+        let connect = tokio_tungstenite::connect_async(self.url.as_str());
+        let (mut socket, _) = tokio::time::timeout(self.connect_timeout, connect)
+            .await
+            .map_err(|_| format!("connection to {} timed out", self.url))?
+            .map_err(|e| e.to_string())?;
+
+        for subscription in self.subscriptions.venue.subscriptions(&self.subscriptions) {
+            socket
+                .send(Message::text(subscription.to_string()))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(socket)
+    }
+
+    /// Fetch klines from the last one seen up to now for every symbol
+    async fn resync(&self, last_bar: &HashMap<String, DateTime<Utc>>) -> Vec<FeedEvent> {
+        let (Some(provider), Some(timeframe)) = (&self.resync, &self.subscriptions.timeframe)
+        else {
+            return Vec::new();
+        };
+        let interval = chrono::Duration::seconds(timeframe.to_seconds() as i64);
+        let now = Utc::now();
+
+        let mut events = Vec::new();
+        for symbol in &self.subscriptions.symbols {
+            let Some(since) = last_bar.get(&symbol.code) else {
+                continue;
+            };
+            let request = MarketDataRequest {
+                symbol: symbol.clone(),
+                timeframe: timeframe.clone(),
+                start_time: Some(*since),
+                end_time: None,
+                limit: None,
+                include_extended_hours: true,
+                adjustment: DataAdjustment::None,
+            };
+
+            match provider.fetch_bars(&request).await {
+                Ok(response) => {
+                    tracing::info!(
+                        symbol = %symbol.code,
+                        bars = response.bars.len(),
+                        "Resynced klines after reconnect"
+                    );
+                    events.extend(response.bars.into_iter().map(|mut bar| {
+                        if bar.get_metadata(PROVIDER_METADATA_KEY).is_none() {
+                            bar.add_metadata(PROVIDER_METADATA_KEY, json!(provider.name()));
+                        }
+                        let is_closed = bar.timestamp + interval <= now;
+                        FeedEvent::Bar { bar, is_closed }
+                    }));
+                }
+                Err(error) => {
+                    tracing::warn!(symbol = %symbol.code, %error, "Kline resync failed");
+                }
+            }
+        }
+        events
+    }
+}
+
+/// Receiving end of a running [`ExchangeFeed`]; dropping it stops the feed
+pub struct FeedSubscription {
+    receiver: mpsc::Receiver<Result<FeedEvent, NetworkError>>,
+    task: JoinHandle<()>,
+}
+
+impl FeedSubscription {
+    /// Next event or connection failure; `None` once the feed has given up
+    pub async fn next(&mut self) -> Option<Result<FeedEvent, NetworkError>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for FeedSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Close the symbol's forming bar once a later interval starts
+///
+/// Coinbase and Kraken send no completion flag, so the first update of the
+/// next interval is what marks the previous bar as final.
+fn close_previous(
+    forming: &mut HashMap<String, OHLCV>,
+    bar: &OHLCV,
+    is_closed: bool,
+) -> Option<FeedEvent> {
+    let previous = if is_closed {
+        forming.remove(&bar.symbol.code)
+    } else {
+        forming.insert(bar.symbol.code.clone(), bar.clone())
+    }?;
+
+    (previous.timestamp < bar.timestamp).then_some(FeedEvent::Bar {
+        bar: previous,
+        is_closed: true,
+    })
+}
+
+/// Decimal from a JSON string ("0.001") or number (0.001)
+fn decimal(value: Option<&Value>, field: &str) -> Result<Decimal, String> {
+    let text = match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Number(number)) => number.to_string(),
+        _ => return Err(format!("missing field '{}'", field)),
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|e| format!("{} '{}': {}", field, text, e))
+}
+
+fn rfc3339(value: Option<&Value>, field: &str) -> Result<DateTime<Utc>, String> {
+    let text = value
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing field '{}'", field))?;
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| format!("{} '{}': {}", field, text, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use shared_types::{MarketDataResponse, Quote, SymbolSearchRequest, SymbolSearchResponse};
+    use tokio::net::TcpListener;
+
+    const BINANCE_FRAMES: &str = include_str!("../../fixtures/feeds/binance_btcusd.jsonl");
+
+    fn btc_usd() -> Symbol {
+        Symbol::crypto("BTC", "USD", Exchange::Binance).unwrap()
+    }
+
+    /// Accept one connection per entry in `sessions`, record the client's
+    /// subscription message, replay the frames, then close the socket
+    async fn replay_server(sessions: Vec<Vec<String>>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (subscribed, received) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            for frames in sessions {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                if let Some(Ok(Message::Text(text))) = socket.next().await {
+                    let _ = subscribed.send(text.to_string()).await;
+                }
+                for frame in frames {
+                    socket.send(Message::text(frame)).await.unwrap();
+                }
+                let _ = socket.close(None).await;
+            }
+        });
+        (url, received)
+    }
+
+    fn frames(fixture: &str) -> Vec<String> {
+        fixture.lines().map(str::to_string).collect()
+    }
+
+    struct RestStub;
+
+    #[async_trait]
+    impl MarketDataProvider for RestStub {
+        fn name(&self) -> &str {
+            "binance_rest"
+        }
+
+        async fn fetch_bars(
+            &self,
+            request: &MarketDataRequest,
+        ) -> ProviderResult<MarketDataResponse> {
+            let start = request.start_time.unwrap();
+            let price = Decimal::new(67_000, 0);
+            let bars = (0..3)
+                .map(|i| {
+                    OHLCV::new(
+                        request.symbol.clone(),
+                        request.timeframe.clone(),
+                        start + chrono::Duration::minutes(i),
+                        price,
+                        price,
+                        price,
+                        price,
+                        Decimal::ONE,
+                    )
+                    .unwrap()
+                })
+                .collect();
+            Ok(MarketDataResponse {
+                symbol: request.symbol.clone(),
+                timeframe: request.timeframe.clone(),
+                bars,
+                adjustment: DataAdjustment::None,
+                corporate_actions: Vec::new(),
+                includes_extended_hours: true,
+                last_updated: Utc::now(),
+            })
+        }
+
+        async fn search_symbols(
+            &self,
+            _request: &SymbolSearchRequest,
+        ) -> ProviderResult<SymbolSearchResponse> {
+            Err(ProviderError::Unsupported {
+                provider: self.name().to_string(),
+                operation: "symbol search".to_string(),
+            })
+        }
+
+        async fn latest_quote(&self, _symbol: &Symbol) -> ProviderResult<Quote> {
+            Err(ProviderError::Unsupported {
+                provider: self.name().to_string(),
+                operation: "quotes".to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_binance_trades_and_klines() {
+        let (url, mut subscribed) = replay_server(vec![frames(BINANCE_FRAMES)]).await;
+        let mut feed = ExchangeFeed::new(&Exchange::Binance, vec![btc_usd()])
+            .unwrap()
+            .with_url(url)
+            .with_bars(TimeFrame::OneMinute)
+            .with_reconnect(ReconnectPolicy::default().with_max_attempts(1))
+            .start()
+            .unwrap();
+
+        let subscription: Value = serde_json::from_str(&subscribed.recv().await.unwrap()).unwrap();
+        assert_eq!(
+            subscription["params"],
+            json!(["btcusd@trade", "btcusd@kline_1m"])
+        );
+
+        let Some(Ok(FeedEvent::Trade(tick))) = feed.next().await else {
+            panic!("expected a trade");
+        };
+        assert_eq!(tick.price, Decimal::new(6712345, 2));
+        assert_eq!(tick.quantity, Decimal::new(15, 3));
+        assert_eq!(tick.side, Some(shared_types::TradeSide::Sell));
+        assert_eq!(tick.trade_id.as_deref(), Some("3579011"));
+
+        let Some(Ok(FeedEvent::Bar { bar, is_closed })) = feed.next().await else {
+            panic!("expected a kline");
+        };
+        assert!(!is_closed);
+        assert_eq!(
+            bar.timestamp,
+            Utc.with_ymd_and_hms(2024, 5, 13, 14, 0, 0).unwrap()
+        );
+
+        let Some(Ok(FeedEvent::Bar { bar, is_closed })) = feed.next().await else {
+            panic!("expected a kline");
+        };
+        assert!(is_closed);
+        assert_eq!(bar.close, Decimal::new(6712345, 2));
+        assert_eq!(
+            bar.get_metadata(PROVIDER_METADATA_KEY),
+            Some(&json!("binance"))
+        );
+
+        // Server closes the socket after replaying
+        assert!(matches!(
+            feed.next().await,
+            Some(Err(NetworkError::WebSocketConnectionFailed { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_resyncs_missed_klines() {
+        let (url, _subscribed) = replay_server(vec![frames(BINANCE_FRAMES), Vec::new()]).await;
+        let mut feed = ExchangeFeed::new(&Exchange::Binance, vec![btc_usd()])
+            .unwrap()
+            .with_url(url)
+            .with_trades(false)
+            .with_bars(TimeFrame::OneMinute)
+            .with_resync(Arc::new(RestStub))
+            .with_reconnect(
+                ReconnectPolicy::default()
+                    .with_initial_delay(Duration::from_millis(50))
+                    .with_max_attempts(1),
+            )
+            .start()
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Some(event) = feed.next().await {
+            events.push(event);
+        }
+
+        // 2 live klines, disconnect, 3 resynced klines, then a silent session
+        // that uses up the single attempt
+        let resynced: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(FeedEvent::Bar { bar, .. })
+                    if bar.get_metadata(PROVIDER_METADATA_KEY) == Some(&json!("binance_rest")) =>
+                {
+                    Some(bar.timestamp)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            resynced.first(),
+            Some(&Utc.with_ymd_and_hms(2024, 5, 13, 14, 0, 0).unwrap())
+        );
+        assert_eq!(resynced.len(), 3);
+
+        let failures = events.iter().filter(|event| event.is_err()).count();
+        assert_eq!(failures, 2);
+    }
+
+    #[tokio::test]
+    async fn test_silent_sessions_count_towards_max_attempts() {
+        // Every session is accepted and closed without a single frame
+        let (url, _subscribed) = replay_server(vec![Vec::new(); 5]).await;
+        let mut feed = ExchangeFeed::new(&Exchange::Binance, vec![btc_usd()])
+            .unwrap()
+            .with_url(url)
+            .with_reconnect(
+                ReconnectPolicy::default()
+                    .with_initial_delay(Duration::from_millis(10))
+                    .with_max_attempts(2),
+            )
+            .start()
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Some(event) = feed.next().await {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| event.is_err()));
+    }
+
+    #[tokio::test]
+    async fn test_connection_failure_is_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let mut feed = ExchangeFeed::new(&Exchange::Kraken, vec![btc_usd()])
+            .unwrap()
+            .with_url(url)
+            .with_reconnect(ReconnectPolicy::default().with_max_attempts(1))
+            .start()
+            .unwrap();
+
+        assert!(matches!(
+            feed.next().await,
+            Some(Err(NetworkError::WebSocketConnectionFailed { .. }))
+        ));
+        assert!(feed.next().await.is_none());
+
+        assert!(ExchangeFeed::new(&Exchange::NYSE, Vec::new()).is_err());
+        assert!(ExchangeFeed::new(&Exchange::Coinbase, vec![btc_usd()])
+            .unwrap()
+            .with_bars(TimeFrame::OneMinute)
+            .start()
+            .is_err());
+    }
+
+    #[test]
+    fn test_next_interval_closes_forming_bar() {
+        let bar_at = |minute| {
+            let price = Decimal::new(67_000, 0);
+            let start = Utc.with_ymd_and_hms(2024, 5, 13, 14, minute, 0).unwrap();
+            OHLCV::new(
+                btc_usd(),
+                TimeFrame::OneMinute,
+                start,
+                price,
+                price,
+                price,
+                price,
+                Decimal::ONE,
+            )
+            .unwrap()
+        };
+        let mut forming = HashMap::new();
+
+        assert_eq!(close_previous(&mut forming, &bar_at(0), false), None);
+        assert_eq!(close_previous(&mut forming, &bar_at(0), false), None);
+        assert_eq!(
+            close_previous(&mut forming, &bar_at(1), false),
+            Some(FeedEvent::Bar {
+                bar: bar_at(0),
+                is_closed: true
+            })
+        );
+
+        // An explicitly closed bar clears its own forming entry
+        assert_eq!(close_previous(&mut forming, &bar_at(1), true), None);
+        assert!(forming.is_empty());
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = ReconnectPolicy::default()
+            .with_initial_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(1));
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+        assert_eq!(policy.delay(10), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,433 @@
+//! Klines over REST from the exchanges [`ExchangeFeed`](super::ExchangeFeed) streams
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use shared_types::{
+    DataAdjustment, Exchange, MarketDataError, MarketDataRequest, MarketDataResponse, Quote,
+    Symbol, SymbolSearchRequest, SymbolSearchResponse, OHLCV,
+};
+use std::time::Duration;
+
+use super::Venue;
+use crate::composite::PROVIDER_METADATA_KEY;
+use crate::provider::{MarketDataProvider, ProviderError, ProviderResult};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A kline's open time and its open, high, low, close and volume
+pub(super) type Kline = (DateTime<Utc>, [Decimal; 5]);
+
+/// Path and query string of one venue's klines call
+pub(super) struct KlineQuery {
+    pub(super) path: String,
+    pub(super) params: Vec<(&'static str, String)>,
+}
+
+/// Why a venue's klines reply holds no klines
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum KlineError {
+    UnknownPair,
+    RateLimited,
+    Unavailable,
+
+    /// Refused for another reason the venue states
+    Rejected(String),
+
+    Malformed(String),
+}
+
+impl From<&str> for KlineError {
+    fn from(message: &str) -> Self {
+        KlineError::Malformed(message.to_string())
+    }
+}
+
+impl From<String> for KlineError {
+    fn from(message: String) -> Self {
+        KlineError::Malformed(message)
+    }
+}
+
+/// [`MarketDataProvider`] for the public klines endpoints of Binance, Coinbase and Kraken
+///
+/// Serves historical bars for pairs listed on its exchange, one call per
+/// request: at most 1000 klines from Binance, 350 from Coinbase and the
+/// latest 720 from Kraken. That covers the gap an [`ExchangeFeed`] reconnect
+/// leaves, which is what it is mostly used for through
+/// [`ExchangeFeed::with_resync`]. Symbol search and quotes are not offered.
+///
+/// [`ExchangeFeed`]: super::ExchangeFeed
+/// [`ExchangeFeed::with_resync`]: super::ExchangeFeed::with_resync
+pub struct ExchangeRestProvider {
+    venue: Venue,
+    exchange: Exchange,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl ExchangeRestProvider {
+    /// Klines from a supported exchange (Binance, Coinbase, Kraken)
+    pub fn new(exchange: &Exchange) -> ProviderResult<Self> {
+        let venue = Venue::for_exchange(exchange, "exchange_rest")?;
+        let client = reqwest::Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .build()
+            .map_err(|e| http_error(venue, None, e.to_string()))?;
+        Ok(Self {
+            venue,
+            exchange: exchange.clone(),
+            base_url: venue.rest_url().to_string(),
+            client,
+        })
+    }
+
+    /// Override the API root, e.g. to point at a mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    async fn get(&self, symbol: &Symbol, query: KlineQuery) -> ProviderResult<Value> {
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), query.path);
+        tracing::debug!(provider = self.venue.name(), %url, "Sending request");
+
+        let response = self
+            .client
+            .get(&url)
+            .query(&query.params)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() || e.is_connect() {
+                    unavailable(self.venue)
+                } else {
+                    http_error(self.venue, e.status().map(|s| s.as_u16()), e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        let body: Option<Value> = serde_json::from_str(&text).ok();
+        if status.is_success() {
+            return body.ok_or_else(|| parse_error(self.venue, "invalid JSON"));
+        }
+        match status.as_u16() {
+            // Binance answers 418 once an IP keeps going after its 429s
+            429 | 418 => Err(rate_limited(self.venue)),
+            404 => Err(not_found(symbol)),
+            _ => match body.as_ref().map(|body| self.venue.parse_klines(body)) {
+                Some(Err(KlineError::UnknownPair)) => Err(not_found(symbol)),
+                _ => Err(http_error(
+                    self.venue,
+                    Some(status.as_u16()),
+                    text.chars().take(200).collect(),
+                )),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for ExchangeRestProvider {
+    fn name(&self) -> &str {
+        self.venue.name()
+    }
+
+    async fn fetch_bars(&self, request: &MarketDataRequest) -> ProviderResult<MarketDataResponse> {
+        if request.symbol.exchange != self.exchange {
+            return Err(unsupported(
+                self.venue,
+                format!("{} symbols", request.symbol.exchange),
+            ));
+        }
+        if let (Some(start), Some(end)) = (request.start_time, request.end_time) {
+            if start > end {
+                return Err(MarketDataError::InvalidTimeRange {
+                    start: start.to_rfc3339(),
+                    end: end.to_rfc3339(),
+                }
+                .into());
+            }
+        }
+        let query = self
+            .venue
+            .klines_query(
+                &request.symbol,
+                &request.timeframe,
+                request.start_time,
+                request.end_time,
+            )
+            .ok_or_else(|| unsupported(self.venue, format!("{} klines", request.timeframe)))?;
+
+        let body = self.get(&request.symbol, query).await?;
+        let klines = self
+            .venue
+            .parse_klines(&body)
+            .map_err(|error| match error {
+                KlineError::UnknownPair => not_found(&request.symbol),
+                KlineError::RateLimited => rate_limited(self.venue),
+                KlineError::Unavailable => unavailable(self.venue),
+                KlineError::Rejected(message) => http_error(self.venue, Some(200), message),
+                KlineError::Malformed(message) => parse_error(self.venue, message),
+            })?;
+
+        let mut bars = Vec::with_capacity(klines.len());
+        for (timestamp, [open, high, low, close, volume]) in klines {
+            let in_range = request.start_time.is_none_or(|start| timestamp >= start)
+                && request.end_time.is_none_or(|end| timestamp <= end);
+            if !in_range {
+                continue;
+            }
+            let mut bar = OHLCV::new(
+                request.symbol.clone(),
+                request.timeframe.clone(),
+                timestamp,
+                open,
+                high,
+                low,
+                close,
+                volume,
+            )
+            .map_err(|e| parse_error(self.venue, e.to_string()))?;
+            bar.add_metadata(PROVIDER_METADATA_KEY, json!(self.venue.name()));
+            bars.push(bar);
+        }
+        if let Some(limit) = request.limit {
+            let excess = bars.len().saturating_sub(limit as usize);
+            bars.drain(..excess);
+        }
+        if bars.is_empty() {
+            return Err(MarketDataError::NoDataAvailable.into());
+        }
+
+        Ok(MarketDataResponse {
+            symbol: request.symbol.clone(),
+            timeframe: request.timeframe.clone(),
+            bars,
+            adjustment: DataAdjustment::None,
+            corporate_actions: Vec::new(),
+            includes_extended_hours: true,
+            last_updated: Utc::now(),
+        })
+    }
+
+    async fn search_symbols(
+        &self,
+        _request: &SymbolSearchRequest,
+    ) -> ProviderResult<SymbolSearchResponse> {
+        Err(unsupported(self.venue, "symbol search"))
+    }
+
+    async fn latest_quote(&self, _symbol: &Symbol) -> ProviderResult<Quote> {
+        Err(unsupported(self.venue, "quotes"))
+    }
+}
+
+fn not_found(symbol: &Symbol) -> ProviderError {
+    MarketDataError::SymbolNotFound {
+        symbol: symbol.full_identifier(),
+    }
+    .into()
+}
+
+fn rate_limited(venue: Venue) -> ProviderError {
+    let retry_after = Utc::now() + chrono::Duration::seconds(60);
+    MarketDataError::RateLimitExceeded {
+        provider: venue.name().to_string(),
+        retry_after: retry_after.to_rfc3339(),
+    }
+    .into()
+}
+
+fn unavailable(venue: Venue) -> ProviderError {
+    MarketDataError::DataProviderUnavailable {
+        provider: venue.name().to_string(),
+    }
+    .into()
+}
+
+fn http_error(venue: Venue, status: Option<u16>, message: String) -> ProviderError {
+    ProviderError::Http {
+        provider: venue.name().to_string(),
+        status,
+        message,
+    }
+}
+
+fn parse_error(venue: Venue, message: impl Into<String>) -> ProviderError {
+    ProviderError::Parse {
+        provider: venue.name().to_string(),
+        message: message.into(),
+    }
+}
+
+fn unsupported(venue: Venue, operation: impl Into<String>) -> ProviderError {
+    ProviderError::Unsupported {
+        provider: venue.name().to_string(),
+        operation: operation.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use shared_types::TimeFrame;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request(exchange: Exchange, start: DateTime<Utc>) -> MarketDataRequest {
+        MarketDataRequest {
+            symbol: Symbol::crypto("BTC", "USD", exchange).unwrap(),
+            timeframe: TimeFrame::OneMinute,
+            start_time: Some(start),
+            end_time: None,
+            limit: None,
+            include_extended_hours: true,
+            adjustment: DataAdjustment::None,
+        }
+    }
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 13, 14, 0, 0).unwrap()
+    }
+
+    async fn provider(exchange: Exchange, server: &MockServer) -> ExchangeRestProvider {
+        ExchangeRestProvider::new(&exchange)
+            .unwrap()
+            .with_base_url(server.uri())
+    }
+
+    #[tokio::test]
+    async fn test_klines_from_each_venue() {
+        let server = MockServer::start().await;
+        let millis = start().timestamp_millis();
+        Mock::given(method("GET"))
+            .and(path("/api/v3/klines"))
+            .and(query_param("symbol", "BTCUSD"))
+            .and(query_param("interval", "1m"))
+            .and(query_param("startTime", millis.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                [
+                    millis,
+                    "67000.1",
+                    "67010",
+                    "66990",
+                    "67005.5",
+                    "1.25",
+                    millis + 59_999
+                ],
+                [
+                    millis + 60_000,
+                    "67005.5",
+                    "67020",
+                    "67000",
+                    "67015",
+                    "0.75",
+                    millis + 119_999
+                ]
+            ])))
+            .mount(&server)
+            .await;
+        let seconds = start().timestamp();
+        Mock::given(method("GET"))
+            .and(path("/api/v3/brokerage/market/products/BTC-USD/candles"))
+            .and(query_param("granularity", "ONE_MINUTE"))
+            .and(query_param("start", seconds.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"candles": [
+                {"start": (seconds + 60).to_string(), "low": "67000", "high": "67020", "open": "67005.5", "close": "67015", "volume": "0.75"},
+                {"start": seconds.to_string(), "low": "66990", "high": "67010", "open": "67000.1", "close": "67005.5", "volume": "1.25"}
+            ]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/0/public/OHLC"))
+            .and(query_param("pair", "XBTUSD"))
+            .and(query_param("interval", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "error": [],
+                "result": {
+                    "XXBTZUSD": [
+                        [seconds - 60, "66900", "66990", "66900", "67000.1", "66950", "3", 12],
+                        [seconds, "67000.1", "67010", "66990", "67005.5", "67001", "1.25", 9],
+                        [seconds + 60, "67005.5", "67020", "67000", "67015", "67010", "0.75", 4]
+                    ],
+                    "last": seconds
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        for (exchange, name) in [
+            (Exchange::Binance, "binance"),
+            (Exchange::Coinbase, "coinbase"),
+            (Exchange::Kraken, "kraken"),
+        ] {
+            let provider = provider(exchange.clone(), &server).await;
+            let response = provider
+                .fetch_bars(&request(exchange, start()))
+                .await
+                .unwrap();
+            assert_eq!(response.bars.len(), 2, "{}", name);
+            assert_eq!(response.bars[0].timestamp, start());
+            assert_eq!(response.bars[0].open, Decimal::new(670001, 1));
+            assert_eq!(response.bars[1].close, Decimal::new(67015, 0));
+            assert_eq!(response.bars[1].volume, Decimal::new(75, 2));
+            assert_eq!(
+                response.bars[0].get_metadata(PROVIDER_METADATA_KEY),
+                Some(&json!(name))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_unsupported_exchanges() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/klines"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .set_body_json(json!({"code": -1121, "msg": "Invalid symbol."})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/0/public/OHLC"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"error": ["EGeneral:Too many requests"], "result": {}})),
+            )
+            .mount(&server)
+            .await;
+
+        let binance = provider(Exchange::Binance, &server).await;
+        let error = binance
+            .fetch_bars(&request(Exchange::Binance, start()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ProviderError::MarketData(MarketDataError::SymbolNotFound { .. })
+        ));
+        // A Binance provider does not serve another exchange's listings
+        let error = binance
+            .fetch_bars(&request(Exchange::Kraken, start()))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ProviderError::Unsupported { .. }));
+
+        let error = provider(Exchange::Kraken, &server)
+            .await
+            .fetch_bars(&request(Exchange::Kraken, start()))
+            .await
+            .unwrap_err();
+        assert!(error.is_retryable());
+
+        assert!(matches!(
+            ExchangeRestProvider::new(&Exchange::Bitfinex),
+            Err(ProviderError::Unsupported { .. })
+        ));
+    }
+}
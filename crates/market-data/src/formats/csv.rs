@@ -0,0 +1,308 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use shared_types::{OHLCVError, Symbol, TimeFrame, OHLCV};
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use super::{missing, FormatError, FormatResult, ImportReport};
+
+/// How timestamps are written in the timestamp column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// `2024-05-13T14:00:00Z`
+    Rfc3339,
+    UnixSeconds,
+    UnixMillis,
+    /// A chrono `strftime` pattern read as UTC, e.g. `%Y-%m-%d` or `%Y-%m-%d %H:%M:%S`
+    Pattern(String),
+}
+
+impl TimestampFormat {
+    fn parse(&self, value: &str) -> Option<DateTime<Utc>> {
+        match self {
+            TimestampFormat::Rfc3339 => DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|at| at.with_timezone(&Utc)),
+            TimestampFormat::UnixSeconds => DateTime::from_timestamp(value.parse().ok()?, 0),
+            TimestampFormat::UnixMillis => DateTime::from_timestamp_millis(value.parse().ok()?),
+            TimestampFormat::Pattern(pattern) => NaiveDateTime::parse_from_str(value, pattern)
+                .or_else(|_| {
+                    NaiveDate::parse_from_str(value, pattern)
+                        .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+                })
+                .ok()
+                .map(|at| at.and_utc()),
+        }
+    }
+
+    fn format(&self, timestamp: &DateTime<Utc>) -> String {
+        match self {
+            TimestampFormat::Rfc3339 => timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            TimestampFormat::UnixSeconds => timestamp.timestamp().to_string(),
+            TimestampFormat::UnixMillis => timestamp.timestamp_millis().to_string(),
+            TimestampFormat::Pattern(pattern) => timestamp.format(pattern).to_string(),
+        }
+    }
+}
+
+/// Header names of the OHLCV columns; matched case-insensitively on import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvColumns {
+    pub timestamp: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: String,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: "volume".to_string(),
+        }
+    }
+}
+
+impl CsvColumns {
+    pub fn with_timestamp(mut self, name: impl Into<String>) -> Self {
+        self.timestamp = name.into();
+        self
+    }
+
+    fn names(&self) -> [&str; 6] {
+        [
+            &self.timestamp,
+            &self.open,
+            &self.high,
+            &self.low,
+            &self.close,
+            &self.volume,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub columns: CsvColumns,
+    pub timestamp_format: TimestampFormat,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            columns: CsvColumns::default(),
+            timestamp_format: TimestampFormat::Rfc3339,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_columns(mut self, columns: CsvColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn with_timestamp_format(mut self, timestamp_format: TimestampFormat) -> Self {
+        self.timestamp_format = timestamp_format;
+        self
+    }
+}
+
+/// Read bars for one symbol from a CSV with a header row
+///
+/// Other columns in the file are ignored. A missing OHLCV column fails the
+/// import; unparseable or invalid rows are collected in the report.
+pub fn read_csv<R: Read>(
+    reader: R,
+    symbol: &Symbol,
+    timeframe: &TimeFrame,
+    options: &CsvOptions,
+) -> FormatResult<ImportReport> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .trim(::csv::Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+
+    let headers = reader.headers()?.clone();
+    let mut indices = [0; 6];
+    for (index, name) in indices.iter_mut().zip(options.columns.names()) {
+        *index = headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or_else(|| FormatError::MissingColumn(name.to_string()))?;
+    }
+
+    let mut report = ImportReport::default();
+    for (row, record) in reader.records().enumerate() {
+        let bar = record
+            .map_err(|e| OHLCVError::ValidationError(e.to_string()))
+            .and_then(|record| {
+                let field = |position: usize| {
+                    record
+                        .get(indices[position])
+                        .filter(|value| !value.is_empty())
+                        .ok_or_else(|| missing(options.columns.names()[position]))
+                };
+                let price = |position: usize| {
+                    let value = field(position)?;
+                    Decimal::from_str(value)
+                        .or_else(|_| Decimal::from_scientific(value))
+                        .map_err(|_| {
+                            OHLCVError::ValidationError(format!(
+                                "'{}' is not a number in column '{}'",
+                                value,
+                                options.columns.names()[position]
+                            ))
+                        })
+                };
+
+                let raw_timestamp = field(0)?;
+                let timestamp = options
+                    .timestamp_format
+                    .parse(raw_timestamp)
+                    .ok_or_else(|| {
+                        OHLCVError::ValidationError(format!(
+                            "'{}' does not match timestamp format {:?}",
+                            raw_timestamp, options.timestamp_format
+                        ))
+                    })?;
+                OHLCV::new(
+                    symbol.clone(),
+                    timeframe.clone(),
+                    timestamp,
+                    price(1)?,
+                    price(2)?,
+                    price(3)?,
+                    price(4)?,
+                    price(5)?,
+                )
+            });
+        report.push(row, bar);
+    }
+    Ok(report)
+}
+
+/// Write bars with a header row, returning the number of rows written
+pub fn write_csv<W: Write>(writer: W, bars: &[OHLCV], options: &CsvOptions) -> FormatResult<usize> {
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(writer);
+
+    writer.write_record(options.columns.names())?;
+    for bar in bars {
+        writer.write_record([
+            options.timestamp_format.format(&bar.timestamp),
+            bar.open.to_string(),
+            bar.high.to_string(),
+            bar.low.to_string(),
+            bar.close.to_string(),
+            bar.volume.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(bars.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use shared_types::Exchange;
+
+    fn ibm() -> Symbol {
+        Symbol::stock("IBM", "IBM", Exchange::NYSE).unwrap()
+    }
+
+    #[test]
+    fn test_reads_pandas_export_and_reports_bad_rows() {
+        let file = "\
+Date;Open;High;Low;Close;Adj Close;Volume
+2024-05-13;167.06;169.40;166.76;167.56;165.89;2414859
+2024-05-14;167.80;168.20;abc;168.00;166.33;1800000
+2024-05-15;168.00;167.00;169.00;168.50;166.82;1200000
+2024-05-16;168.90;170.12;168.35;169.97;168.28;
+2024-05-17;169.50;170.30;168.90;169.03;167.35;2010450
+";
+        let options = CsvOptions::default()
+            .with_delimiter(b';')
+            .with_columns(CsvColumns::default().with_timestamp("date"))
+            .with_timestamp_format(TimestampFormat::Pattern("%Y-%m-%d".to_string()));
+
+        let report = read_csv(file.as_bytes(), &ibm(), &TimeFrame::OneDay, &options).unwrap();
+
+        assert_eq!(report.bars.len(), 2);
+        assert_eq!(
+            report.bars[1].timestamp,
+            Utc.with_ymd_and_hms(2024, 5, 17, 0, 0, 0).unwrap()
+        );
+        assert_eq!(report.bars[0].close, Decimal::new(16756, 2));
+
+        let rejected: Vec<usize> = report.errors.iter().map(|e| e.row).collect();
+        assert_eq!(rejected, vec![1, 2, 3]);
+        assert!(matches!(
+            report.errors[1].error,
+            OHLCVError::InvalidHighLow { .. }
+        ));
+        assert!(matches!(
+            &report.errors[2].error,
+            OHLCVError::MissingData { field } if field == "volume"
+        ));
+    }
+
+    #[test]
+    fn test_round_trips_and_rejects_missing_columns() {
+        let price = Decimal::new(6712345, 2);
+        let bars: Vec<OHLCV> = (0..3)
+            .map(|minute| {
+                OHLCV::new(
+                    ibm(),
+                    TimeFrame::OneMinute,
+                    Utc.with_ymd_and_hms(2024, 5, 13, 14, minute, 0).unwrap(),
+                    price,
+                    price,
+                    price,
+                    price,
+                    Decimal::new(15, 4),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        for format in [
+            TimestampFormat::Rfc3339,
+            TimestampFormat::UnixSeconds,
+            TimestampFormat::UnixMillis,
+        ] {
+            let options = CsvOptions::default().with_timestamp_format(format);
+            let mut buffer = Vec::new();
+            assert_eq!(write_csv(&mut buffer, &bars, &options).unwrap(), 3);
+
+            let report =
+                read_csv(buffer.as_slice(), &ibm(), &TimeFrame::OneMinute, &options).unwrap();
+            assert!(report.is_clean());
+            assert_eq!(report.bars, bars);
+        }
+
+        let result = read_csv(
+            "timestamp,open,high,low,close\n".as_bytes(),
+            &ibm(),
+            &TimeFrame::OneMinute,
+            &CsvOptions::default(),
+        );
+        assert!(matches!(result, Err(FormatError::MissingColumn(column)) if column == "volume"));
+    }
+}
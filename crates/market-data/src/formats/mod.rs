@@ -0,0 +1,75 @@
+//! File import and export of OHLCV series
+//!
+//! Imports validate every row through [`OHLCV::new`]. Rows that fail are
+//! reported in the [`ImportReport`] alongside the bars that loaded, so one bad
+//! line does not reject a whole file.
+
+pub mod csv;
+pub mod parquet;
+
+use arrow::error::ArrowError;
+use shared_types::{OHLCVError, OHLCV};
+use thiserror::Error;
+
+pub use self::csv::{read_csv, write_csv, CsvColumns, CsvOptions, TimestampFormat};
+pub use self::parquet::{read_parquet, write_parquet, write_series};
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] ::csv::Error),
+
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ::parquet::errors::ParquetError),
+
+    #[error("Column '{0}' not found")]
+    MissingColumn(String),
+
+    #[error("Invalid series: {0}")]
+    Series(#[from] OHLCVError),
+
+    #[error("Column '{column}' has unsupported type {data_type}")]
+    UnsupportedColumn { column: String, data_type: String },
+}
+
+pub type FormatResult<T> = Result<T, FormatError>;
+
+/// A row that could not be imported
+#[derive(Debug)]
+pub struct RowError {
+    /// Zero-based index of the data row, not counting any header
+    pub row: usize,
+    pub error: OHLCVError,
+}
+
+/// Bars loaded from a file and the rows that were rejected
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub bars: Vec<OHLCV>,
+    pub errors: Vec<RowError>,
+}
+
+impl ImportReport {
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn push(&mut self, row: usize, bar: Result<OHLCV, OHLCVError>) {
+        match bar {
+            Ok(bar) => self.bars.push(bar),
+            Err(error) => self.errors.push(RowError { row, error }),
+        }
+    }
+}
+
+fn missing(field: &str) -> OHLCVError {
+    OHLCVError::MissingData {
+        field: field.to_string(),
+    }
+}
@@ -0,0 +1,226 @@
+use arrow::array::{Array, ArrayRef, Float64Array, RecordBatch, TimestampNanosecondArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, TimeUnit};
+use chrono::DateTime;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::ChunkReader;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use shared_types::{OHLCVError, OhlcvSeries, Symbol, TimeFrame, OHLCV};
+use std::io::Write;
+
+use super::{missing, FormatError, FormatResult, ImportReport};
+
+const PRICE_COLUMNS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+/// Write bars of one series as a Snappy-compressed Parquet file
+///
+/// Uses the [`OhlcvSeries::arrow_schema`] layout, with prices as float64 so
+/// files load directly into pandas and polars.
+pub fn write_parquet<W: Write + Send>(writer: W, bars: &[OHLCV]) -> FormatResult<usize> {
+    if bars.is_empty() {
+        return Ok(0);
+    }
+    let series = OhlcvSeries::try_from(bars.to_vec())?;
+    write_series(writer, &series)
+}
+
+/// Write a columnar series as a Snappy-compressed Parquet file
+pub fn write_series<W: Write + Send>(writer: W, series: &OhlcvSeries) -> FormatResult<usize> {
+    let batch = series.to_record_batch()?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(series.len())
+}
+
+/// Read bars for one symbol from a Parquet file
+///
+/// Expects `timestamp`, `open`, `high`, `low`, `close` and `volume` columns.
+/// The timestamp may use any Arrow time unit; prices may be any numeric type.
+pub fn read_parquet<R: ChunkReader + 'static>(
+    reader: R,
+    symbol: &Symbol,
+    timeframe: &TimeFrame,
+) -> FormatResult<ImportReport> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(reader)?.build()?;
+
+    let mut report = ImportReport::default();
+    let mut row = 0;
+    for batch in reader {
+        let batch = batch?;
+        let timestamps = column(
+            &batch,
+            "timestamp",
+            &DataType::Timestamp(TimeUnit::Nanosecond, None),
+        )?;
+        let timestamps = timestamps
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .expect("cast to nanosecond timestamps");
+        let prices = PRICE_COLUMNS
+            .iter()
+            .map(|name| column(&batch, name, &DataType::Float64))
+            .collect::<FormatResult<Vec<_>>>()?;
+        let prices: Vec<&Float64Array> = prices
+            .iter()
+            .map(|array| {
+                array
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .expect("cast to float64")
+            })
+            .collect();
+
+        for index in 0..batch.num_rows() {
+            let value = |position: usize| -> Result<Decimal, OHLCVError> {
+                let array = prices[position];
+                if array.is_null(index) {
+                    return Err(missing(PRICE_COLUMNS[position]));
+                }
+                Decimal::from_f64(array.value(index)).ok_or_else(|| {
+                    OHLCVError::ValidationError(format!(
+                        "{} is not a valid {}",
+                        array.value(index),
+                        PRICE_COLUMNS[position]
+                    ))
+                })
+            };
+            let bar = (|| {
+                if timestamps.is_null(index) {
+                    return Err(missing("timestamp"));
+                }
+                OHLCV::new(
+                    symbol.clone(),
+                    timeframe.clone(),
+                    DateTime::from_timestamp_nanos(timestamps.value(index)),
+                    value(0)?,
+                    value(1)?,
+                    value(2)?,
+                    value(3)?,
+                    value(4)?,
+                )
+            })();
+            report.push(row, bar);
+            row += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// The named column cast to `data_type`
+fn column(batch: &RecordBatch, name: &str, data_type: &DataType) -> FormatResult<ArrayRef> {
+    let array = batch
+        .column_by_name(name)
+        .ok_or_else(|| FormatError::MissingColumn(name.to_string()))?;
+    let supported = match data_type {
+        DataType::Timestamp(..) => matches!(array.data_type(), DataType::Timestamp(..)),
+        _ => array.data_type().is_numeric(),
+    };
+    if !supported {
+        return Err(FormatError::UnsupportedColumn {
+            column: name.to_string(),
+            data_type: array.data_type().to_string(),
+        });
+    }
+    Ok(cast(array, data_type)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, TimestampMillisecondArray};
+    use arrow::datatypes::{Field, Schema};
+    use bytes::Bytes;
+    use chrono::{TimeZone, Utc};
+    use shared_types::Exchange;
+    use std::sync::Arc;
+
+    fn btc() -> Symbol {
+        Symbol::crypto("BTC", "USD", Exchange::Coinbase).unwrap()
+    }
+
+    #[test]
+    fn test_round_trips_series() {
+        let bars: Vec<OHLCV> = (0..4)
+            .map(|hour| {
+                OHLCV::new(
+                    btc(),
+                    TimeFrame::OneHour,
+                    Utc.with_ymd_and_hms(2024, 5, 13, hour, 0, 0).unwrap(),
+                    Decimal::new(6712345, 2),
+                    Decimal::new(6750000, 2),
+                    Decimal::new(6700001, 2),
+                    Decimal::new(6740050, 2),
+                    Decimal::new(15012, 4),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let mut buffer = Vec::new();
+        assert_eq!(write_parquet(&mut buffer, &bars).unwrap(), 4);
+
+        let report = read_parquet(Bytes::from(buffer), &btc(), &TimeFrame::OneHour).unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.bars, bars);
+    }
+
+    #[test]
+    fn test_reads_foreign_schema_and_reports_bad_rows() {
+        // Millisecond timestamps and integer volume, as written by other tools
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                true,
+            ),
+            Field::new("open", DataType::Float64, true),
+            Field::new("high", DataType::Float64, true),
+            Field::new("low", DataType::Float64, true),
+            Field::new("close", DataType::Float64, true),
+            Field::new("volume", DataType::Int64, true),
+        ]));
+        let start = Utc.with_ymd_and_hms(2024, 5, 13, 0, 0, 0).unwrap();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampMillisecondArray::from(vec![
+                    Some(start.timestamp_millis()),
+                    None,
+                    Some(start.timestamp_millis() + 60_000),
+                ])),
+                Arc::new(Float64Array::from(vec![10.0, 10.0, 10.0])),
+                Arc::new(Float64Array::from(vec![11.0, 11.0, 9.0])),
+                Arc::new(Float64Array::from(vec![9.5, 9.5, 9.5])),
+                Arc::new(Float64Array::from(vec![10.5, 10.5, 10.5])),
+                Arc::new(Int64Array::from(vec![100, 100, 100])),
+            ],
+        )
+        .unwrap();
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let report = read_parquet(Bytes::from(buffer), &btc(), &TimeFrame::OneMinute).unwrap();
+        assert_eq!(report.bars.len(), 1);
+        assert_eq!(report.bars[0].timestamp, start);
+        assert_eq!(report.bars[0].volume, Decimal::new(100, 0));
+        assert_eq!(report.errors.len(), 2);
+        assert!(matches!(
+            &report.errors[0].error,
+            OHLCVError::MissingData { field } if field == "timestamp"
+        ));
+        assert!(matches!(
+            report.errors[1].error,
+            OHLCVError::InvalidHighLow { .. }
+        ));
+    }
+}
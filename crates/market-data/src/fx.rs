@@ -0,0 +1,262 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use shared_types::{
+    major_currency, DataAdjustment, FxError, FxRates, MarketDataError, MarketDataRequest, Symbol,
+    TimeFrame, TRIANGULATION_CURRENCY,
+};
+use std::sync::{Arc, RwLock};
+
+use crate::provider::{MarketDataProvider, ProviderError, ProviderResult};
+
+/// Exchange rates loaded from a provider's forex data
+///
+/// Every currency is fetched against USD, the currency [`FxRates`]
+/// triangulates through, so one series per currency is enough to convert
+/// between any two of them.
+pub struct FxRateService {
+    provider: Arc<dyn MarketDataProvider>,
+    rates: RwLock<FxRates>,
+}
+
+impl FxRateService {
+    pub fn new(provider: Arc<dyn MarketDataProvider>) -> Self {
+        Self {
+            provider,
+            rates: RwLock::new(FxRates::new()),
+        }
+    }
+
+    /// Load daily closes between `start` and `end`, returning how many rates were added
+    pub async fn load_history(
+        &self,
+        currencies: &[&str],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> ProviderResult<usize> {
+        let mut loaded = 0;
+        for pair in pairs(currencies)? {
+            let request = MarketDataRequest {
+                symbol: pair,
+                timeframe: TimeFrame::OneDay,
+                start_time: Some(start),
+                end_time: Some(end),
+                limit: None,
+                include_extended_hours: false,
+                adjustment: DataAdjustment::None,
+            };
+            let response = self.provider.fetch_bars(&request).await?;
+            let mut rates = self.rates.write().unwrap_or_else(|e| e.into_inner());
+            rates
+                .add_bars(&response.bars)
+                .map_err(|error| self.rejected(error))?;
+            loaded += response.bars.len();
+        }
+        Ok(loaded)
+    }
+
+    /// Record the latest quote of each currency against USD
+    pub async fn refresh(&self, currencies: &[&str]) -> ProviderResult<()> {
+        for pair in pairs(currencies)? {
+            let quote = self.provider.latest_quote(&pair).await?;
+            let (base, quote_currency) = pair.base_quote().expect("forex symbols are pairs");
+            self.rates
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .add_rate(&base, &quote_currency, quote.timestamp, quote.price)
+                .map_err(|error| self.rejected(error))?;
+        }
+        Ok(())
+    }
+
+    /// Copy of the rates loaded so far, e.g. for a `PortfolioAccount`
+    pub fn rates(&self) -> FxRates {
+        self.rates.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Units of `to` per unit of `from` as of `at`
+    pub fn rate(&self, from: &str, to: &str, at: DateTime<Utc>) -> Result<Decimal, FxError> {
+        self.rates
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .rate(from, to, at)
+    }
+
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Decimal, FxError> {
+        Ok(amount * self.rate(from, to, at)?)
+    }
+
+    /// Provider data that `FxRates` refuses, such as a non-positive close
+    fn rejected(&self, error: FxError) -> ProviderError {
+        ProviderError::Parse {
+            provider: self.provider.name().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// One `<currency>/USD` forex symbol per distinct non-USD major currency
+fn pairs(currencies: &[&str]) -> ProviderResult<Vec<Symbol>> {
+    let mut majors: Vec<String> = currencies
+        .iter()
+        .map(|currency| major_currency(currency))
+        .filter(|currency| currency != TRIANGULATION_CURRENCY)
+        .collect();
+    majors.sort();
+    majors.dedup();
+    majors
+        .iter()
+        .map(|currency| {
+            Symbol::forex(currency, TRIANGULATION_CURRENCY).map_err(|_| {
+                ProviderError::from(MarketDataError::InvalidSymbolFormat {
+                    symbol: currency.clone(),
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use shared_types::{
+        MarketDataResponse, Quote, SymbolSearchRequest, SymbolSearchResponse, OHLCV,
+    };
+    use std::sync::Mutex;
+
+    /// Serves GBP/USD at 1.25 and EUR/USD at 1.10 for two days, then quotes 1.30 and 1.20
+    #[derive(Default)]
+    struct ForexStub {
+        requested: Mutex<Vec<String>>,
+    }
+
+    fn close(symbol: &Symbol, latest: bool) -> Decimal {
+        match (symbol.code.as_str(), latest) {
+            ("GBP/USD", false) => Decimal::new(125, 2),
+            ("GBP/USD", true) => Decimal::new(130, 2),
+            (_, false) => Decimal::new(110, 2),
+            (_, true) => Decimal::new(120, 2),
+        }
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap()
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for ForexStub {
+        fn name(&self) -> &str {
+            "forex"
+        }
+
+        async fn fetch_bars(
+            &self,
+            request: &MarketDataRequest,
+        ) -> ProviderResult<MarketDataResponse> {
+            self.requested
+                .lock()
+                .unwrap()
+                .push(request.symbol.code.clone());
+            let price = close(&request.symbol, false);
+            let bars = [day(1), day(2)]
+                .into_iter()
+                .map(|timestamp| {
+                    OHLCV::new(
+                        request.symbol.clone(),
+                        TimeFrame::OneDay,
+                        timestamp,
+                        price,
+                        price,
+                        price,
+                        price,
+                        Decimal::ZERO,
+                    )
+                    .unwrap()
+                })
+                .collect();
+            Ok(MarketDataResponse {
+                symbol: request.symbol.clone(),
+                timeframe: TimeFrame::OneDay,
+                bars,
+                adjustment: DataAdjustment::None,
+                corporate_actions: Vec::new(),
+                includes_extended_hours: false,
+                last_updated: Utc::now(),
+            })
+        }
+
+        async fn search_symbols(
+            &self,
+            _request: &SymbolSearchRequest,
+        ) -> ProviderResult<SymbolSearchResponse> {
+            Err(ProviderError::Unsupported {
+                provider: self.name().to_string(),
+                operation: "symbol search".to_string(),
+            })
+        }
+
+        async fn latest_quote(&self, symbol: &Symbol) -> ProviderResult<Quote> {
+            let price = close(symbol, true);
+            Ok(Quote {
+                symbol: symbol.clone(),
+                price,
+                open: price,
+                high: price,
+                low: price,
+                previous_close: price,
+                change: Decimal::ZERO,
+                change_percent: Decimal::ZERO,
+                volume: Decimal::ZERO,
+                timestamp: day(5),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loads_history_and_refreshes_against_usd() {
+        let provider = Arc::new(ForexStub::default());
+        let service = FxRateService::new(provider.clone());
+
+        let loaded = service
+            .load_history(&["GBX", "EUR", "USD", "GBP"], day(1), day(2))
+            .await
+            .unwrap();
+        assert_eq!(loaded, 4);
+        assert_eq!(
+            *provider.requested.lock().unwrap(),
+            vec!["EUR/USD".to_string(), "GBP/USD".to_string()]
+        );
+        assert_eq!(
+            service
+                .convert(Decimal::new(1000, 0), "GBX", "USD", day(3))
+                .unwrap(),
+            Decimal::new(125, 1)
+        );
+
+        service.refresh(&["EUR", "GBP"]).await.unwrap();
+        assert_eq!(
+            service.rate("EUR", "USD", day(5)).unwrap(),
+            Decimal::new(120, 2)
+        );
+        assert_eq!(
+            service.rate("EUR", "USD", day(4)).unwrap(),
+            Decimal::new(110, 2)
+        );
+        // 1.20 USD per EUR over 1.30 USD per GBP
+        assert_eq!(
+            service
+                .rates()
+                .rate("EUR", "GBP", day(5))
+                .unwrap()
+                .round_dp(4),
+            Decimal::new(9231, 4)
+        );
+    }
+}
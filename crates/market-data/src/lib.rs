@@ -0,0 +1,31 @@
+//! Market data provider abstraction and adapters for external data sources
+
+pub mod adjustment;
+pub mod alpha_vantage;
+pub mod backfill;
+pub mod composite;
+pub mod continuous;
+pub mod feed;
+pub mod formats;
+pub mod fx;
+pub mod provider;
+pub mod quality;
+pub mod rate_limit;
+
+pub use adjustment::{AdjustmentError, CorporateActionAdjuster};
+pub use alpha_vantage::{AlphaVantageConfig, AlphaVantageProvider};
+pub use backfill::{
+    BackfillConfig, BackfillError, BackfillReport, BackfillResult, BackfillScheduler, BarStore,
+};
+pub use composite::{
+    CircuitBreakerConfig, CircuitState, CompositeProvider, ProviderHealth, PROVIDER_METADATA_KEY,
+};
+pub use continuous::{BackAdjustment, ContinuousContract, ContinuousError, CONTRACT_METADATA_KEY};
+pub use feed::{ExchangeFeed, ExchangeRestProvider, FeedEvent, FeedSubscription, ReconnectPolicy};
+pub use formats::{FormatError, FormatResult, ImportReport, RowError};
+pub use fx::FxRateService;
+pub use provider::{MarketDataProvider, ProviderError, ProviderResult};
+pub use quality::{
+    DataQualityMonitor, QualityConfig, QualityIssue, QualityIssueKind, QualityScore,
+};
+pub use rate_limit::RateLimiter;
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use shared_types::{
+    ExternalServiceError, MarketDataError, MarketDataRequest, MarketDataResponse, Quote, Symbol,
+    SymbolSearchRequest, SymbolSearchResponse,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    #[error(transparent)]
+    MarketData(#[from] MarketDataError),
+
+    #[error(transparent)]
+    ExternalService(#[from] ExternalServiceError),
+
+    #[error("Request to '{provider}' failed (status {status:?}): {message}")]
+    Http {
+        provider: String,
+        status: Option<u16>,
+        message: String,
+    },
+
+    #[error("Could not parse response from '{provider}': {message}")]
+    Parse { provider: String, message: String },
+
+    #[error("Provider '{provider}' does not support {operation}")]
+    Unsupported { provider: String, operation: String },
+}
+
+impl ProviderError {
+    /// Classify the error as a provider health problem, if it is one
+    ///
+    /// Outages and throttling are reported as `ThirdPartyServiceDown` and
+    /// `QuotaExceeded`; errors about the request itself return `None`.
+    pub fn service_error(&self) -> Option<ExternalServiceError> {
+        match self {
+            ProviderError::MarketData(MarketDataError::DataProviderUnavailable { provider }) => {
+                Some(ExternalServiceError::ThirdPartyServiceDown {
+                    service: provider.clone(),
+                })
+            }
+            ProviderError::MarketData(MarketDataError::RateLimitExceeded {
+                provider,
+                retry_after,
+            }) => Some(ExternalServiceError::QuotaExceeded {
+                service: provider.clone(),
+                usage: "exhausted".to_string(),
+                quota: format!("resets at {}", retry_after),
+            }),
+            ProviderError::MarketData(_) => None,
+            ProviderError::ExternalService(error) => Some(error.clone()),
+            ProviderError::Http {
+                provider,
+                status: Some(429),
+                message,
+            } => Some(ExternalServiceError::QuotaExceeded {
+                service: provider.clone(),
+                usage: message.clone(),
+                quota: "unknown".to_string(),
+            }),
+            ProviderError::Http {
+                provider, status, ..
+            } if status.is_none_or(|status| status >= 500) => {
+                Some(ExternalServiceError::ThirdPartyServiceDown {
+                    service: provider.clone(),
+                })
+            }
+            ProviderError::Http { .. }
+            | ProviderError::Parse { .. }
+            | ProviderError::Unsupported { .. } => None,
+        }
+    }
+
+    /// Whether the same request might succeed later or against another provider
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.service_error(),
+            Some(
+                ExternalServiceError::ThirdPartyServiceDown { .. }
+                    | ExternalServiceError::QuotaExceeded { .. }
+                    | ExternalServiceError::ServiceDegraded { .. }
+            )
+        )
+    }
+}
+
+pub type ProviderResult<T> = Result<T, ProviderError>;
+
+/// A source of market data (REST API, exchange feed, local store, ...)
+#[async_trait]
+pub trait MarketDataProvider: Send + Sync {
+    /// Short provider name used in errors and logs (e.g. "alpha_vantage")
+    fn name(&self) -> &str;
+
+    /// Historical bars for a symbol, oldest first
+    async fn fetch_bars(&self, request: &MarketDataRequest) -> ProviderResult<MarketDataResponse>;
+
+    /// Look up symbols by code or name
+    async fn search_symbols(
+        &self,
+        request: &SymbolSearchRequest,
+    ) -> ProviderResult<SymbolSearchResponse>;
+
+    /// Most recent quote for a symbol
+    async fn latest_quote(&self, symbol: &Symbol) -> ProviderResult<Quote>;
+}
@@ -0,0 +1,876 @@
+use chrono::{DateTime, Duration as ChronoDuration, DurationRound, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared_types::{
+    AssetClass, ErrorCode, ErrorSeverity, Symbol, TimeFrame, TradingCalendar, WebSocketMessage,
+    WebSocketMessageType, OHLCV,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use uuid::Uuid;
+
+use crate::composite::PROVIDER_METADATA_KEY;
+
+/// Timestamps per series remembered for duplicate and cross-provider checks
+const RECENT_TIMESTAMPS: usize = 64;
+
+/// Fewest returns a spike is judged against
+const MIN_SPIKE_SAMPLE: usize = 10;
+
+/// Upper bound on bar slots walked when sizing a gap
+const MAX_GAP_SCAN: usize = 10_000;
+
+/// Clock difference with a provider tolerated before a bar counts as from the future
+const CLOCK_SKEW_SECONDS: i64 = 5;
+
+/// Coarsest grid intraday bars must start on; hourly bars may start on the half hour
+const ALIGNMENT_SECONDS: i64 = 1800;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityConfig {
+    /// Bar intervals without a new bar, while the market is open, before a series is stale
+    pub stale_after_bars: u32,
+
+    /// Missing bar slots, counting only trading sessions, tolerated before a gap is flagged
+    pub max_missing_bars: u32,
+
+    /// Consecutive zero-volume bars that make a suspicious run
+    pub zero_volume_run: usize,
+
+    /// Standard deviations of recent log returns beyond which a move is a spike
+    pub spike_sigma: f64,
+
+    /// Recent returns the spike threshold is estimated from
+    pub spike_window: usize,
+
+    /// Relative close difference between providers tolerated for the same bar
+    pub disagreement_tolerance: Decimal,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            stale_after_bars: 2,
+            max_missing_bars: 0,
+            zero_volume_run: 5,
+            spike_sigma: 6.0,
+            spike_window: 50,
+            disagreement_tolerance: Decimal::new(5, 3),
+        }
+    }
+}
+
+impl QualityConfig {
+    pub fn with_stale_after_bars(mut self, bars: u32) -> Self {
+        self.stale_after_bars = bars;
+        self
+    }
+
+    pub fn with_max_missing_bars(mut self, bars: u32) -> Self {
+        self.max_missing_bars = bars;
+        self
+    }
+
+    pub fn with_zero_volume_run(mut self, bars: usize) -> Self {
+        self.zero_volume_run = bars.max(1);
+        self
+    }
+
+    pub fn with_spike_sigma(mut self, sigma: f64) -> Self {
+        self.spike_sigma = sigma;
+        self
+    }
+
+    pub fn with_spike_window(mut self, returns: usize) -> Self {
+        self.spike_window = returns.max(MIN_SPIKE_SAMPLE);
+        self
+    }
+
+    pub fn with_disagreement_tolerance(mut self, tolerance: Decimal) -> Self {
+        self.disagreement_tolerance = tolerance;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssueKind {
+    /// No new bar for longer than expected while the market is open
+    Stale,
+    /// A provider sent two bars for the same bar interval
+    DuplicateTimestamp,
+    /// A bar stamped off its timeframe's boundaries
+    MisalignedTimestamp,
+    /// A bar stamped later than the time it was received
+    FutureTimestamp,
+    /// Bars are missing for trading sessions between two received bars
+    Gap,
+    /// A run of bars with no traded volume
+    ZeroVolumeRun,
+    /// Close-to-close move far outside recent volatility
+    PriceSpike,
+    /// Providers report materially different closes for the same bar
+    ProviderDisagreement,
+    /// Prices that break the OHLC relationships, or an open far from the
+    /// previous close for the series' volatility
+    OhlcInconsistency,
+}
+
+impl QualityIssueKind {
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            Self::OhlcInconsistency | Self::Stale | Self::FutureTimestamp => ErrorSeverity::Error,
+            Self::PriceSpike
+            | Self::ProviderDisagreement
+            | Self::Gap
+            | Self::MisalignedTimestamp => ErrorSeverity::Warning,
+            Self::DuplicateTimestamp | Self::ZeroVolumeRun => ErrorSeverity::Info,
+        }
+    }
+
+    /// Share of a bar's worth of quality each occurrence costs
+    ///
+    /// Staleness is not counted per occurrence; it halves the score while it lasts.
+    fn penalty(&self) -> f64 {
+        match self {
+            Self::OhlcInconsistency | Self::PriceSpike | Self::FutureTimestamp => 1.0,
+            Self::ProviderDisagreement
+            | Self::Gap
+            | Self::ZeroVolumeRun
+            | Self::MisalignedTimestamp => 0.5,
+            Self::DuplicateTimestamp => 0.25,
+            Self::Stale => 0.0,
+        }
+    }
+}
+
+/// One problem found in a symbol's bars
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityIssue {
+    /// Symbol identifier, e.g. "AAPL@NASDAQ"
+    pub symbol: String,
+    pub timeframe: TimeFrame,
+    pub kind: QualityIssueKind,
+    pub severity: ErrorSeverity,
+
+    /// Timestamp of the bar the issue was found on, or of the last bar when stale
+    pub timestamp: DateTime<Utc>,
+
+    /// Provider of the offending bar, when the bar named one
+    pub provider: Option<String>,
+
+    pub message: String,
+}
+
+impl QualityIssue {
+    /// Error code clients already handle for this kind of issue, if any
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self.kind {
+            QualityIssueKind::Stale => Some(ErrorCode::DataStale),
+            _ => None,
+        }
+    }
+
+    /// WebSocket `alert` message announcing the issue to subscribers
+    pub fn to_alert(&self) -> WebSocketMessage {
+        WebSocketMessage {
+            message_id: Uuid::new_v4(),
+            message_type: WebSocketMessageType::Alert,
+            payload: json!({
+                "category": "data_quality",
+                "code": self.error_code(),
+                "issue": self,
+            }),
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Quality of one symbol's series since the monitor started watching it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityScore {
+    /// Symbol identifier, e.g. "AAPL@NASDAQ"
+    pub symbol: String,
+    pub timeframe: TimeFrame,
+
+    /// Distinct bars received, not counting duplicates or other providers' copies
+    pub bars: u64,
+
+    pub issues: BTreeMap<QualityIssueKind, u64>,
+
+    pub stale: bool,
+
+    /// 1.0 for a clean series, falling towards 0.0 as issues accumulate
+    pub score: f64,
+}
+
+#[derive(Debug)]
+struct SeriesState {
+    symbol: Symbol,
+    timeframe: TimeFrame,
+    calendar: Option<TradingCalendar>,
+    last_timestamp: Option<DateTime<Utc>>,
+    last_close: Option<Decimal>,
+    returns: VecDeque<f64>,
+    zero_volume_run: usize,
+    /// Providers and closes of the latest bar intervals, keyed by interval
+    /// start; `None` for unlabelled bars
+    recent: BTreeMap<DateTime<Utc>, Vec<(Option<String>, Decimal)>>,
+    bars: u64,
+    issues: BTreeMap<QualityIssueKind, u64>,
+    stale: bool,
+}
+
+/// Watches incoming bars for data problems and scores each series
+///
+/// Bars are fed in as they arrive with [`observe`](Self::observe), from any
+/// number of providers; copies of the same bar from different providers (told
+/// apart by [`PROVIDER_METADATA_KEY`]) are compared with each other rather than
+/// treated as duplicates. Gaps and staleness only count trading sessions of the
+/// symbol's exchange, so weekends and holidays are not flagged. Series are
+/// kept per symbol identifier, so one code listed on two exchanges is two
+/// series. Each issue can be turned into a WebSocket alert with
+/// [`QualityIssue::to_alert`].
+#[derive(Debug, Default)]
+pub struct DataQualityMonitor {
+    config: QualityConfig,
+    series: HashMap<(String, TimeFrame), SeriesState>,
+}
+
+impl DataQualityMonitor {
+    pub fn new(config: QualityConfig) -> Self {
+        Self {
+            config,
+            series: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &QualityConfig {
+        &self.config
+    }
+
+    /// Check a newly received bar against the series so far
+    pub fn observe(&mut self, bar: &OHLCV) -> Vec<QualityIssue> {
+        self.observe_at(bar, Utc::now())
+    }
+
+    /// Check a bar received at `now`
+    ///
+    /// A bar stamped after `now` is reported and otherwise ignored, so it
+    /// cannot hide the gaps and staleness of the bars that follow.
+    pub fn observe_at(&mut self, bar: &OHLCV, now: DateTime<Utc>) -> Vec<QualityIssue> {
+        let config = &self.config;
+        let symbol = bar.symbol.full_identifier();
+        let state = self
+            .series
+            .entry((symbol.clone(), bar.timeframe.clone()))
+            .or_insert_with(|| SeriesState {
+                symbol: bar.symbol.clone(),
+                timeframe: bar.timeframe.clone(),
+                calendar: TradingCalendar::for_exchange(&bar.symbol.exchange),
+                last_timestamp: None,
+                last_close: None,
+                returns: VecDeque::new(),
+                zero_volume_run: 0,
+                recent: BTreeMap::new(),
+                bars: 0,
+                issues: BTreeMap::new(),
+                stale: false,
+            });
+        let provider = bar
+            .metadata
+            .get(PROVIDER_METADATA_KEY)
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+
+        let mut found = Vec::new();
+        if bar.timestamp > now + ChronoDuration::seconds(CLOCK_SKEW_SECONDS) {
+            found.push((
+                QualityIssueKind::FutureTimestamp,
+                format!("Bar at {} received at {}", bar.timestamp, now),
+            ));
+        } else {
+            check_bar(config, state, bar, provider.as_ref(), &mut found);
+        }
+
+        found
+            .into_iter()
+            .map(|(kind, message)| {
+                *state.issues.entry(kind).or_default() += 1;
+                QualityIssue {
+                    symbol: symbol.clone(),
+                    timeframe: bar.timeframe.clone(),
+                    kind,
+                    severity: kind.severity(),
+                    timestamp: bar.timestamp,
+                    provider: provider.clone(),
+                    message,
+                }
+            })
+            .collect()
+    }
+
+    /// Flag series that have gone quiet while their market is open
+    ///
+    /// A series is reported once per silence; the next bar clears it.
+    pub fn check_staleness(&mut self, now: DateTime<Utc>) -> Vec<QualityIssue> {
+        let mut issues = Vec::new();
+        for ((code, timeframe), state) in self.series.iter_mut() {
+            let Some(last) = state.last_timestamp else {
+                continue;
+            };
+            let deadline = timeframe.advance(last, self.config.stale_after_bars as i32 + 1);
+            if state.stale
+                || now < deadline
+                || !expects_bar(state.calendar.as_ref(), timeframe, now)
+            {
+                continue;
+            }
+            state.stale = true;
+            *state.issues.entry(QualityIssueKind::Stale).or_default() += 1;
+            issues.push(QualityIssue {
+                symbol: code.clone(),
+                timeframe: timeframe.clone(),
+                kind: QualityIssueKind::Stale,
+                severity: QualityIssueKind::Stale.severity(),
+                timestamp: last,
+                provider: None,
+                message: format!("Data is stale, last updated: {}", last),
+            });
+        }
+        issues.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        issues
+    }
+
+    pub fn score(&self, symbol: &str, timeframe: &TimeFrame) -> Option<QualityScore> {
+        self.series
+            .get(&(symbol.to_string(), timeframe.clone()))
+            .map(|state| score(symbol, timeframe, state))
+    }
+
+    /// Scores of every series seen, worst first
+    pub fn scores(&self) -> Vec<QualityScore> {
+        let mut scores: Vec<QualityScore> = self
+            .series
+            .iter()
+            .map(|((code, timeframe), state)| score(code, timeframe, state))
+            .collect();
+        scores.sort_by(|a, b| {
+            a.score
+                .total_cmp(&b.score)
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        scores
+    }
+}
+
+fn score(symbol: &str, timeframe: &TimeFrame, state: &SeriesState) -> QualityScore {
+    let penalty: f64 = state
+        .issues
+        .iter()
+        .map(|(kind, count)| kind.penalty() * *count as f64)
+        .sum();
+    let mut score = (1.0 - penalty / state.bars.max(1) as f64).clamp(0.0, 1.0);
+    if state.stale {
+        score *= 0.5;
+    }
+    QualityScore {
+        symbol: symbol.to_string(),
+        timeframe: timeframe.clone(),
+        bars: state.bars,
+        issues: state.issues.clone(),
+        stale: state.stale,
+        score,
+    }
+}
+
+/// Checks of a bar that is not from the future
+///
+/// Every copy is checked for broken prices, alignment, duplicates and
+/// provider disagreement; the first copy of a new interval also extends the
+/// series and is checked for gaps, volume, its open and spikes.
+fn check_bar(
+    config: &QualityConfig,
+    state: &mut SeriesState,
+    bar: &OHLCV,
+    provider: Option<&String>,
+    found: &mut Vec<(QualityIssueKind, String)>,
+) {
+    let consistent = match bar.validate_prices() {
+        Ok(()) => true,
+        Err(error) => {
+            found.push((QualityIssueKind::OhlcInconsistency, error.to_string()));
+            false
+        }
+    };
+
+    let interval = interval_start(&state.timeframe, bar.timestamp);
+    if interval != bar.timestamp {
+        found.push((
+            QualityIssueKind::MisalignedTimestamp,
+            format!(
+                "Bar at {} is not on a {} boundary; its interval starts at {}",
+                bar.timestamp, state.timeframe, interval
+            ),
+        ));
+    }
+
+    let copies = state.recent.entry(interval).or_default();
+    if copies.iter().any(|(seen, _)| seen.as_ref() == provider) {
+        found.push((
+            QualityIssueKind::DuplicateTimestamp,
+            format!("Bar for {} received more than once", interval),
+        ));
+        return;
+    }
+    if !copies.is_empty() {
+        for (other, close) in copies.iter() {
+            let floor = close.min(&bar.close);
+            if *floor <= Decimal::ZERO {
+                continue;
+            }
+            let difference = (bar.close - close).abs() / floor;
+            if difference > config.disagreement_tolerance {
+                found.push((
+                    QualityIssueKind::ProviderDisagreement,
+                    format!(
+                        "Close {} differs from {} reported by {} by {}%",
+                        bar.close,
+                        close,
+                        other.as_deref().unwrap_or("another provider"),
+                        (difference * Decimal::ONE_HUNDRED).round_dp(2)
+                    ),
+                ));
+            }
+        }
+        copies.push((provider.cloned(), bar.close));
+        return;
+    }
+
+    copies.push((provider.cloned(), bar.close));
+    while state.recent.len() > RECENT_TIMESTAMPS {
+        state.recent.pop_first();
+    }
+    state.bars += 1;
+    state.stale = false;
+    if state.last_timestamp.is_some_and(|last| interval <= last) {
+        return;
+    }
+
+    if let Some(last) = state.last_timestamp {
+        let missing = missing_bars(state, last, interval);
+        if missing > config.max_missing_bars as usize {
+            found.push((
+                QualityIssueKind::Gap,
+                format!("{} bars missing since {}", missing, last),
+            ));
+        }
+    }
+    state.last_timestamp = Some(interval);
+
+    if counts_volume(&state.symbol) && bar.volume.is_zero() {
+        state.zero_volume_run += 1;
+        if state.zero_volume_run == config.zero_volume_run {
+            found.push((
+                QualityIssueKind::ZeroVolumeRun,
+                format!("{} consecutive bars without volume", config.zero_volume_run),
+            ));
+        }
+    } else {
+        state.zero_volume_run = 0;
+    }
+
+    // Broken bars would poison the volatility estimate
+    if consistent {
+        if let Some(message) = check_open(config, state, bar.open) {
+            found.push((QualityIssueKind::OhlcInconsistency, message));
+        }
+        if let Some(message) = check_spike(config, state, bar.close) {
+            found.push((QualityIssueKind::PriceSpike, message));
+        }
+        state.last_close = Some(bar.close);
+    }
+}
+
+/// Start of the bar interval `at` falls in
+///
+/// Daily and longer bars start at midnight UTC. Intraday bars start on
+/// multiples of their length from midnight, except that bars longer than
+/// half an hour may start on any half hour, as hourly bars of sessions that
+/// open at 9:30 do.
+fn interval_start(timeframe: &TimeFrame, at: DateTime<Utc>) -> DateTime<Utc> {
+    let midnight = at.duration_trunc(ChronoDuration::days(1)).unwrap_or(at);
+    let length = timeframe.to_seconds().max(1) as i64;
+    if length >= TimeFrame::OneDay.to_seconds() as i64 {
+        return midnight;
+    }
+    let grid = length.min(ALIGNMENT_SECONDS);
+    let elapsed = (at - midnight).num_seconds();
+    midnight + ChronoDuration::seconds(elapsed - elapsed % grid)
+}
+
+/// Bar slots strictly between two bars that fall in a trading session
+fn missing_bars(state: &SeriesState, last: DateTime<Utc>, next: DateTime<Utc>) -> usize {
+    let mut slot = state.timeframe.advance(last, 1);
+    let mut missing = 0;
+    let mut scanned = 0;
+    while slot < next && scanned < MAX_GAP_SCAN {
+        if expects_bar(state.calendar.as_ref(), &state.timeframe, slot) {
+            missing += 1;
+        }
+        slot = state.timeframe.advance(slot, 1);
+        scanned += 1;
+    }
+    missing
+}
+
+/// Whether a bar is expected at `at`: when an intraday bar's interval overlaps
+/// the regular session, on trading days for daily bars, which are stamped
+/// with their trading date at midnight UTC, and for weekly and monthly bars
+/// when their period holds a trading day. Without a calendar every slot is
+/// expected.
+pub(crate) fn expects_bar(
+    calendar: Option<&TradingCalendar>,
+    timeframe: &TimeFrame,
+    at: DateTime<Utc>,
+) -> bool {
+    let Some(calendar) = calendar else {
+        return true;
+    };
+    let day = TimeFrame::OneDay.to_seconds();
+    if timeframe.to_seconds() > day {
+        let end = timeframe.advance(at, 1).date_naive();
+        at.date_naive()
+            .iter_days()
+            .take_while(|date| *date < end)
+            .any(|date| calendar.is_trading_day(date))
+    } else if timeframe.to_seconds() == day {
+        calendar.is_trading_day(at.date_naive())
+    } else {
+        calendar.is_open_during(at, timeframe.advance(at, 1))
+    }
+}
+
+/// Spot FX and indices have no exchange volume, so empty bars are normal
+fn counts_volume(symbol: &Symbol) -> bool {
+    !matches!(symbol.asset_class, AssetClass::Forex | AssetClass::Index)
+}
+
+/// Log return from the previous close to `price`
+fn log_return(state: &SeriesState, price: Decimal) -> Option<f64> {
+    let previous = state.last_close?.to_f64()?;
+    let current = price.to_f64()?;
+    if previous <= 0.0 || current <= 0.0 {
+        return None;
+    }
+    Some((current / previous).ln())
+}
+
+/// Mean and standard deviation of the recent returns, once there are enough
+fn volatility(state: &SeriesState) -> Option<(f64, f64)> {
+    let returns = &state.returns;
+    if returns.len() < MIN_SPIKE_SAMPLE {
+        return None;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>()
+        / (returns.len() - 1) as f64)
+        .sqrt();
+    (std_dev > 0.0).then_some((mean, std_dev))
+}
+
+/// Compare the move from the previous close to the open with recent returns
+///
+/// An open the series could not plausibly have gapped to means the bar was
+/// stitched from another session or instrument.
+fn check_open(config: &QualityConfig, state: &SeriesState, open: Decimal) -> Option<String> {
+    let gap = log_return(state, open)?;
+    let (mean, std_dev) = volatility(state)?;
+    let sigmas = (gap - mean).abs() / std_dev;
+    (sigmas > config.spike_sigma).then(|| {
+        format!(
+            "Open {} is {:.1} standard deviations from the previous close {}",
+            open,
+            sigmas,
+            state.last_close.unwrap_or_default()
+        )
+    })
+}
+
+/// Compare the close-to-close log return with recent ones, then remember it
+///
+/// Spikes are kept out of the window so one bad tick does not widen the band.
+fn check_spike(config: &QualityConfig, state: &mut SeriesState, close: Decimal) -> Option<String> {
+    let log_return = log_return(state, close)?;
+
+    if let Some((mean, std_dev)) = volatility(state) {
+        let sigmas = (log_return - mean).abs() / std_dev;
+        if sigmas > config.spike_sigma {
+            return Some(format!(
+                "Close moved {:.2}% from {}, {:.1} standard deviations",
+                (log_return.exp() - 1.0) * 100.0,
+                state.last_close?,
+                sigmas
+            ));
+        }
+    }
+    state.returns.push_back(log_return);
+    while state.returns.len() > config.spike_window {
+        state.returns.pop_front();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, NaiveDate, TimeZone};
+    use shared_types::Exchange;
+    use std::collections::HashMap as Metadata;
+
+    fn apple() -> Symbol {
+        Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap()
+    }
+
+    fn at(date: NaiveDate) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    /// Daily bar built field by field, so broken prices get through
+    fn bar(date: NaiveDate, close: i64, volume: i64) -> OHLCV {
+        let close = Decimal::new(close, 2);
+        OHLCV {
+            symbol: apple(),
+            timeframe: TimeFrame::OneDay,
+            timestamp: at(date),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: Decimal::new(volume, 0),
+            metadata: Metadata::new(),
+        }
+    }
+
+    fn from(bar: OHLCV, provider: &str) -> OHLCV {
+        let mut bar = bar;
+        bar.metadata
+            .insert(PROVIDER_METADATA_KEY.to_string(), json!(provider));
+        bar
+    }
+
+    /// US trading days from 2 January 2024
+    fn trading_days(count: usize) -> Vec<NaiveDate> {
+        let calendar = TradingCalendar::for_exchange(&Exchange::NASDAQ).unwrap();
+        NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .iter_days()
+            .filter(|date| calendar.is_trading_day(*date))
+            .take(count)
+            .collect()
+    }
+
+    fn kinds(issues: &[QualityIssue]) -> Vec<QualityIssueKind> {
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn test_clean_series_skips_weekends_and_holidays() {
+        let mut monitor = DataQualityMonitor::default();
+        for (index, date) in trading_days(40).into_iter().enumerate() {
+            // Alternating closes give a steady volatility for the spike check
+            let close = if index % 2 == 0 { 10_000 } else { 10_100 };
+            assert!(
+                monitor.observe(&bar(date, close, 1000)).is_empty(),
+                "{}",
+                date
+            );
+        }
+        let score = monitor.score("AAPL@NASDAQ", &TimeFrame::OneDay).unwrap();
+        assert_eq!(score.bars, 40);
+        assert_eq!(score.score, 1.0);
+        assert!(monitor.score("MSFT@NASDAQ", &TimeFrame::OneDay).is_none());
+    }
+
+    #[test]
+    fn test_flags_series_problems() {
+        let mut monitor = DataQualityMonitor::default();
+        let days = trading_days(30);
+        for (index, date) in days[..20].iter().enumerate() {
+            let close = if index % 2 == 0 { 10_000 } else { 10_100 };
+            monitor.observe(&bar(*date, close, 1000));
+        }
+
+        // 20% jump during the session against roughly 1% daily moves
+        let mut jump = bar(days[20], 12_100, 1000);
+        jump.open = Decimal::new(10_100, 2);
+        jump.low = jump.open;
+        let issues = monitor.observe(&jump);
+        assert_eq!(kinds(&issues), [QualityIssueKind::PriceSpike]);
+        assert_eq!(issues[0].severity, ErrorSeverity::Warning);
+
+        let issues = monitor.observe(&jump);
+        assert_eq!(kinds(&issues), [QualityIssueKind::DuplicateTimestamp]);
+
+        // Skipping a trading day leaves a gap
+        let issues = monitor.observe(&bar(days[22], 12_100, 1000));
+        assert_eq!(kinds(&issues), [QualityIssueKind::Gap]);
+        assert!(issues[0].message.starts_with("1 bars missing"));
+
+        let mut broken = bar(days[23], 12_100, 1000);
+        broken.high = Decimal::new(12_000, 2);
+        let issues = monitor.observe(&broken);
+        assert_eq!(kinds(&issues), [QualityIssueKind::OhlcInconsistency]);
+        assert_eq!(issues[0].severity, ErrorSeverity::Error);
+
+        let mut issues = Vec::new();
+        for date in &days[24..29] {
+            issues.extend(monitor.observe(&bar(*date, 12_100, 0)));
+        }
+        assert_eq!(kinds(&issues), [QualityIssueKind::ZeroVolumeRun]);
+
+        let score = monitor.score("AAPL@NASDAQ", &TimeFrame::OneDay).unwrap();
+        assert_eq!(score.bars, 28);
+        assert_eq!(score.issues.values().sum::<u64>(), 5);
+        // 1 + 0.25 + 0.5 + 1 + 0.5 penalty over 28 bars
+        assert!((score.score - (1.0 - 3.25 / 28.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_monthly_series_steps_by_calendar_month() {
+        let mut monitor = DataQualityMonitor::default();
+        let month = |m| NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+        let monthly = |m| OHLCV {
+            timeframe: TimeFrame::OneMonth,
+            ..bar(month(m), 10_000, 1000)
+        };
+
+        // Thirty-day steps would expect a bar on Jan 31 and drift off the 1st
+        for m in 1..=4 {
+            assert!(monitor.observe(&monthly(m)).is_empty(), "{}", month(m));
+        }
+        let issues = monitor.observe(&monthly(6));
+        assert_eq!(kinds(&issues), [QualityIssueKind::Gap]);
+        assert!(issues[0].message.starts_with("1 bars missing"));
+
+        // Two missed months make the series stale
+        let june = at(month(6));
+        assert!(monitor
+            .check_staleness(june + ChronoDuration::days(80))
+            .is_empty());
+        let stale = monitor.check_staleness(at(month(9)) + ChronoDuration::hours(15));
+        assert_eq!(kinds(&stale), [QualityIssueKind::Stale]);
+    }
+
+    #[test]
+    fn test_flags_bad_timestamps_and_opens() {
+        let mut monitor = DataQualityMonitor::default();
+        let days = trading_days(14);
+        for (index, date) in days[..12].iter().enumerate() {
+            let close = if index % 2 == 0 { 10_000 } else { 10_100 };
+            monitor.observe(&bar(*date, close, 1000));
+        }
+
+        // Opening 30% away from a 101.00 close, then trading back to it
+        let mut stitched = bar(days[12], 10_100, 1000);
+        stitched.open = Decimal::new(13_100, 2);
+        stitched.high = stitched.open;
+        let issues = monitor.observe(&stitched);
+        assert_eq!(kinds(&issues), [QualityIssueKind::OhlcInconsistency]);
+        assert!(issues[0].message.starts_with("Open 131.00 is"));
+
+        // A daily bar stamped mid-morning, in an interval already received
+        let mut shifted = bar(days[12], 10_100, 1000);
+        shifted.timestamp += ChronoDuration::hours(14);
+        let issues = monitor.observe(&shifted);
+        assert_eq!(
+            kinds(&issues),
+            [
+                QualityIssueKind::MisalignedTimestamp,
+                QualityIssueKind::DuplicateTimestamp
+            ]
+        );
+
+        // Tomorrow's bar is reported but does not move the series on
+        let received = at(days[12]) + ChronoDuration::hours(22);
+        let issues = monitor.observe_at(&bar(days[13], 10_000, 1000), received);
+        assert_eq!(kinds(&issues), [QualityIssueKind::FutureTimestamp]);
+        assert_eq!(issues[0].severity, ErrorSeverity::Error);
+        assert_eq!(
+            monitor
+                .score("AAPL@NASDAQ", &TimeFrame::OneDay)
+                .unwrap()
+                .bars,
+            13
+        );
+        assert!(monitor.observe(&bar(days[13], 10_000, 1000)).is_empty());
+
+        // The same code on another exchange is a separate series
+        let mut listed = bar(days[13], 20_000, 1000);
+        listed.symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NYSE).unwrap();
+        assert!(monitor.observe(&listed).is_empty());
+        assert_eq!(
+            monitor.score("AAPL@NYSE", &TimeFrame::OneDay).unwrap().bars,
+            1
+        );
+    }
+
+    #[test]
+    fn test_provider_disagreement_and_staleness_alerts() {
+        let mut monitor = DataQualityMonitor::new(
+            QualityConfig::default().with_disagreement_tolerance(Decimal::new(1, 2)),
+        );
+        let days = trading_days(3);
+        assert!(monitor
+            .observe(&from(bar(days[0], 10_000, 1000), "alpha"))
+            .is_empty());
+        // Within 1% of the first provider's close
+        assert!(monitor
+            .observe(&from(bar(days[0], 10_050, 1000), "beta"))
+            .is_empty());
+        let issues = monitor.observe(&from(bar(days[0], 10_500, 1000), "gamma"));
+        assert_eq!(
+            kinds(&issues),
+            [
+                QualityIssueKind::ProviderDisagreement,
+                QualityIssueKind::ProviderDisagreement
+            ]
+        );
+        assert_eq!(issues[0].provider.as_deref(), Some("gamma"));
+        assert_eq!(
+            monitor
+                .score("AAPL@NASDAQ", &TimeFrame::OneDay)
+                .unwrap()
+                .bars,
+            1
+        );
+
+        // Saturday is not a trading day, so nothing is expected
+        let saturday = days[0]
+            .iter_days()
+            .find(|date| date.weekday() == chrono::Weekday::Sat)
+            .unwrap();
+        assert!(monitor.check_staleness(at(saturday)).is_empty());
+
+        let later = at(days[2]) + ChronoDuration::days(7);
+        let stale = monitor.check_staleness(later);
+        assert_eq!(kinds(&stale), [QualityIssueKind::Stale]);
+        assert!(monitor.check_staleness(later).is_empty());
+        assert!(monitor.scores()[0].stale);
+
+        let alert = stale[0].to_alert();
+        assert_eq!(alert.message_type, WebSocketMessageType::Alert);
+        assert_eq!(alert.payload["code"], "MD_007");
+        assert_eq!(alert.payload["issue"]["kind"], "stale");
+        assert_eq!(alert.payload["issue"]["symbol"], "AAPL@NASDAQ");
+
+        monitor.observe(&from(bar(days[1], 10_000, 1000), "alpha"));
+        assert!(
+            !monitor
+                .score("AAPL@NASDAQ", &TimeFrame::OneDay)
+                .unwrap()
+                .stale
+        );
+    }
+}
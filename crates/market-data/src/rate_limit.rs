@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Sliding-window request budget (e.g. 5 requests per minute)
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: usize,
+    window: Duration,
+    requests: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1) as usize,
+            window,
+            requests: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn per_minute(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(60))
+    }
+
+    /// Take a slot if one is free, otherwise return how long until one frees up
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+
+        while requests
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= self.window)
+        {
+            requests.pop_front();
+        }
+
+        if requests.len() < self.capacity {
+            requests.push_back(now);
+            return Ok(());
+        }
+
+        let oldest = requests.front().copied().unwrap_or(now);
+        Err(self.window.saturating_sub(now.duration_since(oldest)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_frees_slots() {
+        let limiter = RateLimiter::new(2, Duration::from_millis(50));
+
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_ok());
+        let wait = limiter.try_acquire().unwrap_err();
+        assert!(wait <= Duration::from_millis(50));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.try_acquire().is_ok());
+    }
+}
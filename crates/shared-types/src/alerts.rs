@@ -0,0 +1,246 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{Symbol, TechnicalIndicator, TimeFrame, WebSocketMessage, WebSocketMessageType};
+
+/// Side of a level a price or indicator is compared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// Currently above the threshold
+    Above,
+    /// Currently below the threshold
+    Below,
+    /// Moved from at or below the threshold to above it on the latest bar
+    CrossesAbove,
+    /// Moved from at or above the threshold to below it on the latest bar
+    CrossesBelow,
+}
+
+impl Comparison {
+    /// Whether `current`, coming from `previous`, satisfies the comparison
+    pub fn matches<T: PartialOrd>(&self, previous: Option<T>, current: T, threshold: T) -> bool {
+        match self {
+            Comparison::Above => current > threshold,
+            Comparison::Below => current < threshold,
+            Comparison::CrossesAbove => {
+                current > threshold && previous.is_some_and(|previous| previous <= threshold)
+            }
+            Comparison::CrossesBelow => {
+                current < threshold && previous.is_some_and(|previous| previous >= threshold)
+            }
+        }
+    }
+}
+
+/// What has to happen on a bar for an alert to fire
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// The close compared with `level`; the crossing comparisons fire once per cross
+    Price {
+        comparison: Comparison,
+        level: Decimal,
+    },
+
+    /// Close-to-close change over the last `window_bars` bars reaches `percent`
+    ///
+    /// A positive `percent` waits for a rise of at least that much, a negative
+    /// one for a fall.
+    PercentChange { window_bars: u32, percent: Decimal },
+
+    /// An indicator value compared with a threshold, e.g. RSI(14) below 30
+    ///
+    /// MACD compares its histogram, Bollinger bands compare %B (0 at the lower
+    /// band, 1 at the upper) and moving averages compare the close's percent
+    /// distance from the average.
+    Indicator {
+        indicator: TechnicalIndicator,
+        /// Lookback in bars, defaulting to the indicator's usual period
+        #[serde(default)]
+        period: Option<u32>,
+        comparison: Comparison,
+        threshold: Decimal,
+    },
+
+    /// A candlestick pattern completes on the latest bar
+    Pattern { pattern: String },
+
+    /// A rule written in the alert expression language, e.g.
+    /// `rsi(14) < 30 and close > sma(200)`
+    Expression { expression: String },
+}
+
+/// Whether a rule keeps firing after the first time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMode {
+    /// Fire once, then deactivate
+    OneShot,
+    /// Fire whenever the condition holds and the cooldown has passed
+    #[default]
+    Recurring,
+}
+
+impl fmt::Display for AlertMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertMode::OneShot => write!(f, "one_shot"),
+            AlertMode::Recurring => write!(f, "recurring"),
+        }
+    }
+}
+
+impl FromStr for AlertMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one_shot" => Ok(AlertMode::OneShot),
+            "recurring" => Ok(AlertMode::Recurring),
+            _ => Err(format!("Invalid alert mode: {}", s)),
+        }
+    }
+}
+
+/// A user's alert on one symbol and timeframe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: Uuid,
+    pub name: String,
+    pub symbol: Symbol,
+    pub timeframe: TimeFrame,
+    pub condition: AlertCondition,
+
+    #[serde(default)]
+    pub mode: AlertMode,
+
+    /// Minimum time between two firings, measured on bar timestamps (in seconds)
+    #[serde(default)]
+    pub cooldown_seconds: u64,
+
+    /// Inactive rules are kept but not evaluated; one-shot rules deactivate when they fire
+    pub active: bool,
+
+    pub last_triggered: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AlertRule {
+    pub fn new(
+        name: impl Into<String>,
+        symbol: Symbol,
+        timeframe: TimeFrame,
+        condition: AlertCondition,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            symbol,
+            timeframe,
+            condition,
+            mode: AlertMode::default(),
+            cooldown_seconds: 0,
+            active: true,
+            last_triggered: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: AlertMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: std::time::Duration) -> Self {
+        self.cooldown_seconds = cooldown.as_secs();
+        self
+    }
+
+    /// Whether a firing at `at` would fall inside the cooldown of the last one
+    pub fn cooling_down(&self, at: DateTime<Utc>) -> bool {
+        let cooldown = Duration::seconds(self.cooldown_seconds.min(i64::MAX as u64) as i64);
+        // Never fire twice for the same or an older bar
+        self.last_triggered
+            .is_some_and(|last| at <= last || at - last < cooldown)
+    }
+}
+
+/// One firing of an alert rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub symbol: String,
+    pub timeframe: TimeFrame,
+
+    /// Timestamp of the bar that fired the rule
+    pub triggered_at: DateTime<Utc>,
+
+    /// Close of that bar
+    pub price: Decimal,
+
+    /// Indicator value or percent change that met the condition, if any
+    pub value: Option<Decimal>,
+
+    pub message: String,
+}
+
+impl AlertEvent {
+    /// WebSocket `alert` message delivering the event to subscribers
+    pub fn to_message(&self) -> WebSocketMessage {
+        WebSocketMessage {
+            message_id: Uuid::new_v4(),
+            message_type: WebSocketMessageType::Alert,
+            payload: serde_json::json!({
+                "category": "rule",
+                "event": self,
+            }),
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Exchange;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_comparisons_and_cooldown() {
+        assert!(Comparison::Below.matches(None, 25, 30));
+        assert!(Comparison::CrossesAbove.matches(Some(30), 31, 30));
+        assert!(!Comparison::CrossesAbove.matches(Some(31), 32, 30));
+        assert!(!Comparison::CrossesBelow.matches(None, 29, 30));
+
+        let at = Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap();
+        let mut rule = AlertRule::new(
+            "oversold",
+            Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap(),
+            TimeFrame::OneHour,
+            AlertCondition::Indicator {
+                indicator: TechnicalIndicator::RSI,
+                period: None,
+                comparison: Comparison::Below,
+                threshold: Decimal::new(30, 0),
+            },
+        )
+        .with_cooldown(std::time::Duration::from_secs(3 * 3600));
+        assert!(!rule.cooling_down(at));
+        rule.last_triggered = Some(at);
+        assert!(rule.cooling_down(at + Duration::hours(2)));
+        assert!(!rule.cooling_down(at + Duration::hours(3)));
+
+        let json = serde_json::to_value(&rule).unwrap();
+        assert_eq!(json["condition"]["type"], "indicator");
+        assert_eq!(json["condition"]["indicator"], "rsi");
+        assert_eq!(json["mode"], "recurring");
+        assert_eq!("one_shot".parse::<AlertMode>(), Ok(AlertMode::OneShot));
+    }
+}
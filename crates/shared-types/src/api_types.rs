@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::{Symbol, TimeFrame, OHLCV};
+use crate::{CorporateAction, Symbol, TimeFrame, TradeSide, OHLCV};
 
 // ============================================================================
 // Generic API Response Structure
@@ -183,6 +183,10 @@ pub struct MarketDataResponse {
     /// Data adjustment applied
     pub adjustment: DataAdjustment,
 
+    /// Splits and dividends the bars were adjusted for
+    #[serde(default)]
+    pub corporate_actions: Vec<CorporateAction>,
+
     /// Whether data includes extended hours
     pub includes_extended_hours: bool,
 
@@ -190,6 +194,56 @@ pub struct MarketDataResponse {
     pub last_updated: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    /// Quoted symbol
+    pub symbol: Symbol,
+
+    /// Last traded price
+    pub price: Decimal,
+
+    /// Session open, high and low
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+
+    /// Previous session close
+    pub previous_close: Decimal,
+
+    /// Price change since the previous close
+    pub change: Decimal,
+
+    /// Percentage change since the previous close (1.5 = 1.5%)
+    pub change_percent: Decimal,
+
+    /// Session volume
+    pub volume: Decimal,
+
+    /// Time of the quote (start of the trading day if the provider only gives a date)
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    /// Traded symbol
+    pub symbol: Symbol,
+
+    /// Execution price
+    pub price: Decimal,
+
+    /// Executed quantity in base units
+    pub quantity: Decimal,
+
+    /// Aggressor (taker) side, if the venue reports it
+    pub side: Option<TradeSide>,
+
+    /// Venue trade identifier
+    pub trade_id: Option<String>,
+
+    /// Execution time
+    pub timestamp: DateTime<Utc>,
+}
+
 // ============================================================================
 // Symbol Search Types
 // ============================================================================
@@ -600,6 +654,41 @@ pub struct RiskAssessment {
 
     /// Liquidity assessment
     pub liquidity: LiquidityLevel,
+
+    /// Measurements behind the assessment (absent when produced by a model alone)
+    #[serde(default)]
+    pub metrics: Option<RiskMetrics>,
+}
+
+/// Deterministic risk figures computed from price history
+///
+/// Losses are positive fractions of value over a one-day horizon (0.02 = 2%).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskMetrics {
+    /// Confidence level used for VaR and CVaR (e.g. 0.95)
+    pub confidence_level: f64,
+
+    /// Number of daily returns the figures are based on
+    pub observations: usize,
+
+    /// Value at Risk from the empirical return distribution
+    pub historical_var: f64,
+
+    /// Expected loss beyond the historical VaR (expected shortfall)
+    pub historical_cvar: f64,
+
+    /// Value at Risk assuming normally distributed returns
+    pub parametric_var: f64,
+
+    /// Expected shortfall assuming normally distributed returns
+    pub parametric_cvar: f64,
+
+    /// Annualized realized volatility of daily returns
+    pub annualized_volatility: f64,
+
+    /// Average daily traded value (close * volume) over the lookback window;
+    /// for a portfolio, that of its least liquid holding
+    pub average_daily_value_traded: Decimal,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -656,6 +745,181 @@ pub enum LiquidityLevel {
     VeryLow,
 }
 
+// ============================================================================
+// Correlation Analysis Types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CorrelationMethod {
+    #[default]
+    #[serde(rename = "pearson")]
+    Pearson,
+    #[serde(rename = "spearman")]
+    Spearman,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorrelationAnalysisResponse {
+    /// Symbols in matrix order
+    pub symbols: Vec<Symbol>,
+
+    /// Method used for the correlation matrices
+    pub method: CorrelationMethod,
+
+    /// First and last aligned timestamp
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+
+    /// Number of aligned returns used
+    pub observations: usize,
+
+    /// Correlation of returns, `correlation_matrix[i][j]` between symbols i and j
+    pub correlation_matrix: Vec<Vec<f64>>,
+
+    /// Sample covariance of returns
+    pub covariance_matrix: Vec<Vec<f64>>,
+
+    /// Correlation matrices over a rolling window (empty if not requested)
+    pub rolling: Vec<RollingCorrelation>,
+
+    /// Beta of each symbol against the benchmark (if one was requested)
+    pub betas: Vec<BetaEstimate>,
+
+    /// Engle-Granger cointegration test for every pair
+    pub cointegration: Vec<CointegrationResult>,
+
+    /// When the analysis was produced
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingCorrelation {
+    /// Timestamp of the last return in the window
+    pub timestamp: DateTime<Utc>,
+
+    /// Correlation matrix over the window
+    pub matrix: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BetaEstimate {
+    pub symbol: Symbol,
+    pub benchmark: Symbol,
+
+    /// Covariance with the benchmark divided by benchmark variance
+    pub beta: f64,
+
+    /// Pearson correlation with the benchmark
+    pub correlation: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CointegrationResult {
+    pub first: Symbol,
+    pub second: Symbol,
+
+    /// Units of `second` per unit of `first` in the spread (log prices)
+    pub hedge_ratio: f64,
+
+    /// Intercept of the cointegrating regression
+    pub intercept: f64,
+
+    /// Dickey-Fuller statistic of the spread
+    pub adf_statistic: f64,
+
+    /// 5% critical value the statistic is compared against
+    pub critical_value: f64,
+
+    /// Whether the spread is stationary at the 5% level
+    pub is_cointegrated: bool,
+
+    /// Mean-reversion half-life of the spread in bars (if it mean-reverts)
+    pub half_life: Option<f64>,
+}
+
+// ============================================================================
+// Portfolio Performance Types
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioPerformance {
+    /// Portfolio that was analyzed
+    pub portfolio_id: Uuid,
+
+    /// First and last snapshot in the analyzed period
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+
+    /// Portfolio value at the start and end of the period
+    pub starting_value: Decimal,
+    pub ending_value: Decimal,
+
+    /// Net deposits minus withdrawals during the period
+    pub net_flows: Decimal,
+
+    /// Time-weighted return for the period (0.05 = 5%)
+    pub time_weighted_return: f64,
+
+    /// Money-weighted return (IRR) for the period, if it could be solved
+    pub money_weighted_return: Option<f64>,
+
+    /// Annualized volatility of daily returns
+    pub annualized_volatility: f64,
+
+    /// Worst peak-to-trough decline (0.2 = -20%)
+    pub max_drawdown: f64,
+
+    /// Drawdown at every snapshot
+    pub drawdown_series: Vec<DrawdownPoint>,
+
+    /// Comparison against a benchmark (if one was supplied)
+    pub benchmark: Option<BenchmarkComparison>,
+
+    /// P&L contribution per position
+    pub attribution: Vec<PositionAttribution>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawdownPoint {
+    pub timestamp: DateTime<Utc>,
+
+    /// Cumulative time-weighted growth of 1.0
+    pub index_value: f64,
+
+    /// Decline from the running peak (0.0 at a new high)
+    pub drawdown: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    /// Benchmark symbol
+    pub symbol: Symbol,
+
+    /// Benchmark return over the same dates
+    pub benchmark_return: f64,
+
+    /// Beta of portfolio returns against the benchmark
+    pub beta: Option<f64>,
+
+    /// Portfolio return minus benchmark return
+    pub excess_return: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionAttribution {
+    /// Position symbol
+    pub symbol: Symbol,
+
+    /// Realized plus unrealized P&L earned during the period
+    pub pnl: Decimal,
+
+    /// P&L as a fraction of starting capital
+    pub contribution: f64,
+
+    /// Average absolute weight in the portfolio during the period
+    pub average_weight: f64,
+}
+
 // ============================================================================
 // WebSocket Message Types
 // ============================================================================
@@ -767,6 +1031,28 @@ impl<T> ApiResponse<T> {
     }
 }
 
+impl PaginationInfo {
+    /// Page `page`, counting from 1, of `page_size` items out of `total_items`
+    pub fn new(page: u32, page_size: u32, total_items: u64) -> Self {
+        let page_size = page_size.max(1);
+        let page = page.max(1);
+        let total_pages = total_items.div_ceil(page_size as u64) as u32;
+        Self {
+            page,
+            page_size,
+            total_pages,
+            total_items,
+            has_next: page < total_pages,
+            has_previous: page > 1,
+        }
+    }
+
+    /// Index of the first item on the page
+    pub fn offset(&self) -> usize {
+        (self.page as usize - 1) * self.page_size as usize
+    }
+}
+
 impl Default for ResponseMetadata {
     fn default() -> Self {
         Self {
@@ -819,6 +1105,20 @@ mod tests {
         assert_eq!(response.error, Some(error));
     }
 
+    #[test]
+    fn test_pagination_info() {
+        let pagination = PaginationInfo::new(2, 50, 120);
+        assert_eq!(pagination.total_pages, 3);
+        assert_eq!(pagination.offset(), 50);
+        assert!(pagination.has_next && pagination.has_previous);
+
+        let last = PaginationInfo::new(3, 50, 120);
+        assert!(!last.has_next);
+        let empty = PaginationInfo::new(0, 0, 0);
+        assert_eq!((empty.page, empty.page_size, empty.total_pages), (1, 1, 0));
+        assert!(!empty.has_next && !empty.has_previous);
+    }
+
     #[test]
     fn test_market_data_request() {
         let symbol = create_test_symbol();
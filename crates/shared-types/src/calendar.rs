@@ -0,0 +1,565 @@
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::collections::BTreeSet;
+
+use crate::{Exchange, MarketStatus};
+
+/// Trading hours and holidays of an exchange
+///
+/// Sessions are described relative to the exchange's local midnight on the
+/// trading date, so overnight sessions (CME Globex, spot FX) start on the
+/// previous calendar day. Holidays are generated from each exchange's rules
+/// rather than a fixed table, so any year can be queried; one-off closures
+/// such as national days of mourning are not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingCalendar {
+    venue: Venue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Venue {
+    UsEquities,
+    London,
+    Xetra,
+    Tokyo,
+    CmeGlobex,
+    Forex,
+    Crypto,
+}
+
+/// A span of `status`, in seconds from local midnight of the trading date
+struct Window {
+    start: i64,
+    end: i64,
+    status: MarketStatus,
+}
+
+const HOUR: i64 = 3600;
+const MINUTE: i64 = 60;
+
+impl TradingCalendar {
+    /// Calendar for an exchange; `None` for venues without a known schedule
+    pub fn for_exchange(exchange: &Exchange) -> Option<Self> {
+        let venue = match exchange {
+            Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX => Venue::UsEquities,
+            Exchange::LSE => Venue::London,
+            Exchange::XETRA => Venue::Xetra,
+            Exchange::TSE => Venue::Tokyo,
+            Exchange::COMEX | Exchange::NYMEX => Venue::CmeGlobex,
+            Exchange::Forex => Venue::Forex,
+            Exchange::Binance | Exchange::Coinbase | Exchange::Kraken | Exchange::Bitfinex => {
+                Venue::Crypto
+            }
+            Exchange::Other(_) => return None,
+        };
+        Some(Self { venue })
+    }
+
+    pub fn timezone(&self) -> Tz {
+        match self.venue {
+            Venue::UsEquities | Venue::CmeGlobex | Venue::Forex => chrono_tz::America::New_York,
+            Venue::London => chrono_tz::Europe::London,
+            Venue::Xetra => chrono_tz::Europe::Berlin,
+            Venue::Tokyo => chrono_tz::Asia::Tokyo,
+            Venue::Crypto => chrono_tz::UTC,
+        }
+    }
+
+    /// Whether the exchange is closed for the whole local date
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        match self.venue {
+            Venue::UsEquities => us_holidays(date.year()).contains(&date),
+            Venue::London => uk_holidays(date.year()).contains(&date),
+            Venue::Xetra => xetra_holidays(date.year()).contains(&date),
+            Venue::Tokyo => tse_holidays(date.year()).contains(&date),
+            // Globex only shuts fully for these; other US holidays are shortened
+            Venue::CmeGlobex => {
+                let year = date.year();
+                date == easter_sunday(year) - Duration::days(2)
+                    || date == observed(NaiveDate::from_ymd_opt(year, 12, 25).unwrap())
+                    || date == observed(NaiveDate::from_ymd_opt(year, 1, 1).unwrap())
+            }
+            Venue::Forex | Venue::Crypto => false,
+        }
+    }
+
+    /// Whether the exchange has a session on the local date
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        match self.venue {
+            Venue::Crypto => true,
+            _ => !weekend && !self.is_holiday(date),
+        }
+    }
+
+    /// Local time of an early close on the date, if the regular session is shortened
+    pub fn early_close(&self, date: NaiveDate) -> Option<NaiveTime> {
+        if !self.is_trading_day(date) {
+            return None;
+        }
+        let (year, month, day) = (date.year(), date.month(), date.day());
+        let weekday = !matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        let time = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0);
+        match self.venue {
+            Venue::UsEquities => {
+                let thanksgiving = nth_weekday(year, 11, Weekday::Thu, 4);
+                let shortened = (month == 7 && day == 3 && weekday)
+                    || date == thanksgiving + Duration::days(1)
+                    || (month == 12 && day == 24 && weekday);
+                if shortened {
+                    time(13, 0)
+                } else {
+                    None
+                }
+            }
+            Venue::London if month == 12 && (day == 24 || day == 31) => time(12, 30),
+            // Globex halts at 12:00 Chicago time on US holidays it trades through
+            Venue::CmeGlobex if us_holidays(year).contains(&date) => time(13, 0),
+            _ => None,
+        }
+    }
+
+    /// Market status at an instant
+    ///
+    /// Outside every session, including lunch breaks, the market is `Closed`.
+    pub fn status_at(&self, at: DateTime<Utc>) -> MarketStatus {
+        let local = at.with_timezone(&self.timezone()).naive_local();
+        // An overnight session belongs to the next day's trading date
+        for date in [local.date(), local.date() + Duration::days(1)] {
+            let offset = (local - date.and_time(NaiveTime::MIN)).num_seconds();
+            if let Some(window) = self
+                .windows(date)
+                .into_iter()
+                .find(|window| window.start <= offset && offset < window.end)
+            {
+                return window.status;
+            }
+        }
+        MarketStatus::Closed
+    }
+
+    /// Whether any part of `[start, end)` falls in a regular session
+    ///
+    /// Suits bars, which cover an interval rather than an instant: an hourly
+    /// bar from 09:00 New York includes the 09:30 open.
+    pub fn is_open_during(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        if end <= start {
+            return self.status_at(start) == MarketStatus::Open;
+        }
+        let tz = self.timezone();
+        let first = start.with_timezone(&tz).date_naive();
+        // An overnight session belongs to the next day's trading date
+        let last = end.with_timezone(&tz).date_naive() + Duration::days(1);
+        first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .any(|date| {
+                let local = |offset: i64| {
+                    let time = date.and_time(NaiveTime::MIN) + Duration::seconds(offset);
+                    tz.from_local_datetime(&time)
+                        .earliest()
+                        .map(|time| time.with_timezone(&Utc))
+                };
+                self.windows(date)
+                    .into_iter()
+                    .filter(|window| window.status == MarketStatus::Open)
+                    .any(|window| match (local(window.start), local(window.end)) {
+                        (Some(open), Some(close)) => open < end && start < close,
+                        _ => false,
+                    })
+            })
+    }
+
+    /// Start of the next regular session strictly after `after`
+    pub fn next_open(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let tz = self.timezone();
+        let first = after.with_timezone(&tz).date_naive();
+        // Long holiday runs (Golden Week, year end in Tokyo) stay well inside two weeks
+        (0..15)
+            .map(|days| first + Duration::days(days))
+            .flat_map(|date| {
+                self.windows(date)
+                    .into_iter()
+                    .filter(|window| window.status == MarketStatus::Open)
+                    .filter_map(move |window| {
+                        let start = date.and_time(NaiveTime::MIN) + Duration::seconds(window.start);
+                        tz.from_local_datetime(&start).earliest()
+                    })
+            })
+            .map(|start| start.with_timezone(&Utc))
+            .find(|start| *start > after)
+    }
+
+    fn windows(&self, date: NaiveDate) -> Vec<Window> {
+        if !self.is_trading_day(date) {
+            return Vec::new();
+        }
+        let window = |start, end, status| Window { start, end, status };
+        let close = |regular: i64| {
+            self.early_close(date)
+                .map(|time| i64::from(time.num_seconds_from_midnight()))
+                .unwrap_or(regular)
+        };
+
+        match self.venue {
+            Venue::UsEquities => {
+                let end = close(16 * HOUR);
+                vec![
+                    window(4 * HOUR, 9 * HOUR + 30 * MINUTE, MarketStatus::PreMarket),
+                    window(9 * HOUR + 30 * MINUTE, end, MarketStatus::Open),
+                    // After-hours trading runs four hours past the close, early or not
+                    window(end, end + 4 * HOUR, MarketStatus::AfterMarket),
+                ]
+            }
+            Venue::London => vec![
+                window(7 * HOUR + 50 * MINUTE, 8 * HOUR, MarketStatus::PreMarket),
+                window(8 * HOUR, close(16 * HOUR + 30 * MINUTE), MarketStatus::Open),
+            ],
+            Venue::Xetra => vec![window(
+                9 * HOUR,
+                17 * HOUR + 30 * MINUTE,
+                MarketStatus::Open,
+            )],
+            Venue::Tokyo => {
+                // The afternoon session was extended by 30 minutes from 5 November 2024
+                let afternoon_close = if date >= NaiveDate::from_ymd_opt(2024, 11, 5).unwrap() {
+                    15 * HOUR + 30 * MINUTE
+                } else {
+                    15 * HOUR
+                };
+                vec![
+                    window(9 * HOUR, 11 * HOUR + 30 * MINUTE, MarketStatus::Open),
+                    window(12 * HOUR + 30 * MINUTE, afternoon_close, MarketStatus::Open),
+                ]
+            }
+            Venue::CmeGlobex => vec![window(-6 * HOUR, close(17 * HOUR), MarketStatus::Open)],
+            Venue::Forex => vec![window(-7 * HOUR, 17 * HOUR, MarketStatus::Open)],
+            Venue::Crypto => vec![window(0, 24 * HOUR, MarketStatus::Open)],
+        }
+    }
+}
+
+/// Easter Sunday in the Gregorian calendar (anonymous Gregorian algorithm)
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+/// US rule: Saturday holidays are observed on Friday, Sunday holidays on Monday
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+fn us_holidays(year: i32) -> BTreeSet<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let mut holidays = BTreeSet::from([
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        easter_sunday(year) - Duration::days(2),
+        last_weekday(year, 5, Weekday::Mon),
+        observed(date(7, 4)),
+        nth_weekday(year, 9, Weekday::Mon, 1),
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        observed(date(12, 25)),
+    ]);
+    // NYSE does not close on the Friday before a Saturday New Year's Day
+    if date(1, 1).weekday() != Weekday::Sat {
+        holidays.insert(observed(date(1, 1)));
+    }
+    if year >= 2022 {
+        holidays.insert(observed(date(6, 19)));
+    }
+    holidays
+}
+
+fn uk_holidays(year: i32) -> BTreeSet<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let next_monday = |date: NaiveDate| match date.weekday() {
+        Weekday::Sat => date + Duration::days(2),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    };
+    let easter = easter_sunday(year);
+
+    // A weekend Christmas or Boxing Day moves to the next weekday not already taken
+    let mut christmas_days = Vec::new();
+    for mut day in [date(12, 25), date(12, 26)] {
+        while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) || christmas_days.contains(&day)
+        {
+            day += Duration::days(1);
+        }
+        christmas_days.push(day);
+    }
+
+    BTreeSet::from([
+        next_monday(date(1, 1)),
+        easter - Duration::days(2),
+        easter + Duration::days(1),
+        nth_weekday(year, 5, Weekday::Mon, 1),
+        last_weekday(year, 5, Weekday::Mon),
+        last_weekday(year, 8, Weekday::Mon),
+        christmas_days[0],
+        christmas_days[1],
+    ])
+}
+
+fn xetra_holidays(year: i32) -> BTreeSet<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let easter = easter_sunday(year);
+    BTreeSet::from([
+        date(1, 1),
+        easter - Duration::days(2),
+        easter + Duration::days(1),
+        date(5, 1),
+        date(12, 24),
+        date(12, 25),
+        date(12, 26),
+        date(12, 31),
+    ])
+}
+
+/// Japanese national holidays (rules in force since 2020) plus the exchange's year-end closure
+fn tse_holidays(year: i32) -> BTreeSet<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    // Equinox days, valid for 1980-2099
+    let since = year - 1980;
+    let vernal = (20.8431 + 0.242194 * since as f64 - (since / 4) as f64) as u32;
+    let autumnal = (23.2488 + 0.242194 * since as f64 - (since / 4) as f64) as u32;
+
+    let mut holidays = BTreeSet::from([
+        date(1, 1),
+        nth_weekday(year, 1, Weekday::Mon, 2),
+        date(2, 11),
+        date(2, 23),
+        date(3, vernal),
+        date(4, 29),
+        date(5, 3),
+        date(5, 4),
+        date(5, 5),
+        nth_weekday(year, 7, Weekday::Mon, 3),
+        date(8, 11),
+        nth_weekday(year, 9, Weekday::Mon, 3),
+        date(9, autumnal),
+        nth_weekday(year, 10, Weekday::Mon, 2),
+        date(11, 3),
+        date(11, 23),
+    ]);
+
+    // A holiday on Sunday moves to the next day that is not already a holiday
+    let sundays: Vec<NaiveDate> = holidays
+        .iter()
+        .filter(|day| day.weekday() == Weekday::Sun)
+        .copied()
+        .collect();
+    for sunday in sundays {
+        let mut substitute = sunday + Duration::days(1);
+        while holidays.contains(&substitute) {
+            substitute += Duration::days(1);
+        }
+        holidays.insert(substitute);
+    }
+    // A day sandwiched between two holidays is a holiday too
+    let sandwiched: Vec<NaiveDate> = holidays
+        .iter()
+        .map(|day| *day + Duration::days(1))
+        .filter(|day| !holidays.contains(day) && holidays.contains(&(*day + Duration::days(1))))
+        .collect();
+    holidays.extend(sandwiched);
+
+    holidays.extend([date(1, 2), date(1, 3), date(12, 31)]);
+    holidays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(tz: Tz, year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        tz.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_us_equity_sessions_and_holidays() {
+        let nyse = TradingCalendar::for_exchange(&Exchange::NYSE).unwrap();
+        let ny = nyse.timezone();
+
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 5, 13, 3, 59)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 5, 13, 9, 29)),
+            MarketStatus::PreMarket
+        );
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 5, 13, 9, 30)),
+            MarketStatus::Open
+        );
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 5, 13, 16, 0)),
+            MarketStatus::AfterMarket
+        );
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 5, 13, 20, 0)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 5, 11, 12, 0)),
+            MarketStatus::Closed
+        );
+
+        // 2024: Good Friday 29 March, Juneteenth, Independence Day on a Thursday
+        for holiday in [date(2024, 3, 29), date(2024, 6, 19), date(2024, 7, 4)] {
+            assert!(nyse.is_holiday(holiday), "{}", holiday);
+        }
+        // 2022: New Year's Day on a Saturday is not observed; Christmas moves to Monday
+        assert!(!nyse.is_holiday(date(2021, 12, 31)));
+        assert!(nyse.is_holiday(date(2022, 12, 26)));
+
+        // Bars overlapping the open count as in session, bars ending at it do not
+        let hour = chrono::Duration::hours(1);
+        let nine = at(ny, 2024, 5, 13, 9, 0);
+        assert!(nyse.is_open_during(nine, nine + hour));
+        assert!(!nyse.is_open_during(nine - hour, nine));
+        assert!(!nyse.is_open_during(at(ny, 2024, 5, 13, 16, 0), at(ny, 2024, 5, 13, 17, 0)));
+        // A daily bar over the weekend never overlaps a session
+        assert!(!nyse.is_open_during(at(ny, 2024, 5, 11, 0, 0), at(ny, 2024, 5, 12, 0, 0)));
+
+        // Half day after Thanksgiving 2024
+        let friday = date(2024, 11, 29);
+        assert_eq!(nyse.early_close(friday), NaiveTime::from_hms_opt(13, 0, 0));
+        assert_eq!(
+            nyse.status_at(at(ny, 2024, 11, 29, 13, 30)),
+            MarketStatus::AfterMarket
+        );
+
+        // The Friday session after the Thursday holiday
+        assert_eq!(
+            nyse.next_open(at(ny, 2024, 7, 3, 16, 0)),
+            Some(at(ny, 2024, 7, 5, 9, 30))
+        );
+    }
+
+    #[test]
+    fn test_international_calendars() {
+        let lse = TradingCalendar::for_exchange(&Exchange::LSE).unwrap();
+        let london = lse.timezone();
+        // Boxing Day 2021 fell on a Sunday: holidays on Monday 27 and Tuesday 28
+        assert!(lse.is_holiday(date(2021, 12, 27)));
+        assert!(lse.is_holiday(date(2021, 12, 28)));
+        assert!(lse.is_holiday(date(2024, 8, 26)));
+        assert_eq!(
+            lse.status_at(at(london, 2024, 12, 24, 12, 45)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            lse.status_at(at(london, 2024, 12, 23, 12, 45)),
+            MarketStatus::Open
+        );
+
+        let xetra = TradingCalendar::for_exchange(&Exchange::XETRA).unwrap();
+        assert!(xetra.is_holiday(date(2024, 5, 1)));
+        assert!(xetra.is_trading_day(date(2024, 5, 20)));
+
+        let tse = TradingCalendar::for_exchange(&Exchange::TSE).unwrap();
+        let tokyo = tse.timezone();
+        // Lunch break, and the 15:30 close from November 2024
+        assert_eq!(
+            tse.status_at(at(tokyo, 2024, 5, 13, 12, 0)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            tse.status_at(at(tokyo, 2024, 5, 13, 15, 10)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            tse.status_at(at(tokyo, 2024, 11, 13, 15, 10)),
+            MarketStatus::Open
+        );
+        // Vernal equinox 2024, Children's Day substitute, autumnal equinox substitute
+        for holiday in [date(2024, 3, 20), date(2024, 5, 6), date(2024, 9, 23)] {
+            assert!(tse.is_holiday(holiday), "{}", holiday);
+        }
+        assert!(!tse.is_trading_day(date(2025, 1, 3)));
+    }
+
+    #[test]
+    fn test_around_the_clock_markets() {
+        let comex = TradingCalendar::for_exchange(&Exchange::COMEX).unwrap();
+        let ny = comex.timezone();
+        // Globex opens Sunday evening, pauses daily 17:00-18:00 and closes on Friday
+        assert_eq!(
+            comex.status_at(at(ny, 2024, 5, 12, 18, 30)),
+            MarketStatus::Open
+        );
+        assert_eq!(
+            comex.status_at(at(ny, 2024, 5, 13, 17, 30)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            comex.status_at(at(ny, 2024, 5, 17, 18, 30)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            comex.status_at(at(ny, 2024, 5, 27, 14, 0)),
+            MarketStatus::Closed
+        );
+        assert_eq!(
+            comex.status_at(at(ny, 2024, 5, 27, 12, 0)),
+            MarketStatus::Open
+        );
+        // Memorial Day trading stops at 13:00 New York
+        assert_eq!(
+            comex.early_close(date(2024, 5, 27)),
+            NaiveTime::from_hms_opt(13, 0, 0)
+        );
+        assert_eq!(
+            comex.status_at(at(ny, 2024, 5, 27, 13, 15)),
+            MarketStatus::Closed
+        );
+        // An overnight Globex session is found from the previous evening
+        assert!(comex.is_open_during(at(ny, 2024, 5, 12, 18, 0), at(ny, 2024, 5, 12, 19, 0)));
+
+        let binance = TradingCalendar::for_exchange(&Exchange::Binance).unwrap();
+        assert_eq!(
+            binance.status_at(at(chrono_tz::UTC, 2024, 12, 25, 3, 0)),
+            MarketStatus::Open
+        );
+
+        assert!(TradingCalendar::for_exchange(&Exchange::Other("OTC".to_string())).is_none());
+    }
+}
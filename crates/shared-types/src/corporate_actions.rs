@@ -0,0 +1,139 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{DataAdjustment, Symbol};
+
+/// A stock split (or reverse split) taking effect on its ex-date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+    pub symbol: Symbol,
+
+    /// First trading day on the post-split share basis
+    pub ex_date: NaiveDate,
+
+    /// New shares per old share: 4 for a 4-for-1 split, 0.1 for a 1-for-10 reverse split
+    pub ratio: Decimal,
+}
+
+impl Split {
+    pub fn new(symbol: Symbol, ex_date: NaiveDate, ratio: Decimal) -> Self {
+        Self {
+            symbol,
+            ex_date,
+            ratio,
+        }
+    }
+}
+
+/// A cash dividend; shares bought on or after the ex-date do not receive it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dividend {
+    pub symbol: Symbol,
+    pub ex_date: NaiveDate,
+
+    /// Cash per share in the symbol's currency, on the share basis of the ex-date
+    pub amount: Decimal,
+
+    pub pay_date: Option<NaiveDate>,
+}
+
+impl Dividend {
+    pub fn new(symbol: Symbol, ex_date: NaiveDate, amount: Decimal) -> Self {
+        Self {
+            symbol,
+            ex_date,
+            amount,
+            pay_date: None,
+        }
+    }
+
+    pub fn with_pay_date(mut self, pay_date: NaiveDate) -> Self {
+        self.pay_date = Some(pay_date);
+        self
+    }
+}
+
+/// An event that changes how historical prices compare to current ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CorporateAction {
+    #[serde(rename = "split")]
+    Split(Split),
+    #[serde(rename = "dividend")]
+    Dividend(Dividend),
+}
+
+impl CorporateAction {
+    pub fn symbol(&self) -> &Symbol {
+        match self {
+            CorporateAction::Split(split) => &split.symbol,
+            CorporateAction::Dividend(dividend) => &dividend.symbol,
+        }
+    }
+
+    pub fn ex_date(&self) -> NaiveDate {
+        match self {
+            CorporateAction::Split(split) => split.ex_date,
+            CorporateAction::Dividend(dividend) => dividend.ex_date,
+        }
+    }
+
+    /// Whether prices adjusted with `adjustment` account for this action
+    pub fn is_covered_by(&self, adjustment: &DataAdjustment) -> bool {
+        matches!(
+            (self, adjustment),
+            (_, DataAdjustment::All)
+                | (CorporateAction::Split(_), DataAdjustment::Splits)
+                | (CorporateAction::Dividend(_), DataAdjustment::Dividends)
+        )
+    }
+}
+
+impl From<Split> for CorporateAction {
+    fn from(split: Split) -> Self {
+        CorporateAction::Split(split)
+    }
+}
+
+impl From<Dividend> for CorporateAction {
+    fn from(dividend: Dividend) -> Self {
+        CorporateAction::Dividend(dividend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Exchange;
+
+    #[test]
+    fn test_coverage_and_serialization() {
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let split: CorporateAction = Split::new(
+            symbol.clone(),
+            NaiveDate::from_ymd_opt(2020, 8, 31).unwrap(),
+            Decimal::new(4, 0),
+        )
+        .into();
+        let dividend: CorporateAction = Dividend::new(
+            symbol,
+            NaiveDate::from_ymd_opt(2020, 8, 7).unwrap(),
+            Decimal::new(205, 3),
+        )
+        .into();
+
+        assert!(split.is_covered_by(&DataAdjustment::Splits));
+        assert!(!split.is_covered_by(&DataAdjustment::Dividends));
+        assert!(dividend.is_covered_by(&DataAdjustment::All));
+        assert!(!dividend.is_covered_by(&DataAdjustment::None));
+
+        let json = serde_json::to_value(&split).unwrap();
+        assert_eq!(json["type"], "split");
+        assert_eq!(json["ex_date"], "2020-08-31");
+        assert_eq!(
+            serde_json::from_value::<CorporateAction>(json).unwrap(),
+            split
+        );
+    }
+}
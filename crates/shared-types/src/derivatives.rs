@@ -0,0 +1,480 @@
+use chrono::{Duration, NaiveDate, Weekday};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{AssetClass, Exchange, Symbol, SymbolError};
+
+/// Futures delivery month codes, January to December
+const MONTH_CODES: [char; 12] = ['F', 'G', 'H', 'J', 'K', 'M', 'N', 'Q', 'U', 'V', 'X', 'Z'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OptionRight {
+    #[serde(rename = "call")]
+    Call,
+    #[serde(rename = "put")]
+    Put,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExerciseStyle {
+    #[serde(rename = "american")]
+    American,
+    #[serde(rename = "european")]
+    European,
+}
+
+/// Terms of a listed option contract
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionSpec {
+    /// OCC root, usually the underlying's ticker
+    pub underlying: String,
+    pub expiry: NaiveDate,
+    pub strike: Decimal,
+    pub right: OptionRight,
+    pub style: ExerciseStyle,
+
+    /// Units of the underlying per contract
+    pub multiplier: Decimal,
+}
+
+impl OptionSpec {
+    /// Standard US equity option: American exercise, 100 shares per contract
+    pub fn new(
+        underlying: &str,
+        expiry: NaiveDate,
+        strike: Decimal,
+        right: OptionRight,
+    ) -> Result<Self, SymbolError> {
+        let underlying = underlying.trim().to_uppercase();
+        if underlying.is_empty()
+            || underlying.len() > 6
+            || !underlying.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(SymbolError::InvalidFormat(format!(
+                "option root '{}' must be 1-6 letters or digits",
+                underlying
+            )));
+        }
+        if strike <= Decimal::ZERO {
+            return Err(SymbolError::InvalidFormat(format!(
+                "option strike {} must be positive",
+                strike
+            )));
+        }
+        Ok(Self {
+            underlying,
+            expiry,
+            strike,
+            right,
+            style: ExerciseStyle::American,
+            multiplier: Decimal::new(100, 0),
+        })
+    }
+
+    pub fn with_style(mut self, style: ExerciseStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: Decimal) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Parse an OCC option symbol, padded ("AAPL  240621C00190000") or not
+    ///
+    /// The last 15 characters are the expiry as YYMMDD, `C` or `P`, and the
+    /// strike in thousandths of a dollar; everything before them is the root.
+    pub fn parse_occ(symbol: &str) -> Result<Self, SymbolError> {
+        let invalid = || SymbolError::InvalidFormat(format!("OCC option symbol '{}'", symbol));
+        let symbol = symbol.trim();
+        if !symbol.is_ascii() || symbol.len() < 16 {
+            return Err(invalid());
+        }
+        let (root, terms) = symbol.split_at(symbol.len() - 15);
+
+        let expiry = NaiveDate::parse_from_str(&terms[..6], "%y%m%d").map_err(|_| invalid())?;
+        let right = match &terms[6..7] {
+            "C" | "c" => OptionRight::Call,
+            "P" | "p" => OptionRight::Put,
+            _ => return Err(invalid()),
+        };
+        if !terms[7..].chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let thousandths: i64 = terms[7..].parse().map_err(|_| invalid())?;
+        Self::new(
+            root.trim(),
+            expiry,
+            Decimal::new(thousandths, 3).normalize(),
+            right,
+        )
+    }
+
+    /// OCC symbol without root padding, as used for `Symbol::code`
+    pub fn occ_code(&self) -> String {
+        format!("{}{}", self.underlying, self.occ_terms())
+    }
+
+    /// 21-character OCC symbol with the root padded to six characters
+    pub fn occ_symbol(&self) -> String {
+        format!("{:<6}{}", self.underlying, self.occ_terms())
+    }
+
+    fn occ_terms(&self) -> String {
+        let right = match self.right {
+            OptionRight::Call => 'C',
+            OptionRight::Put => 'P',
+        };
+        let thousandths = (self.strike * Decimal::new(1000, 0)).trunc().to_string();
+        format!(
+            "{}{}{:0>8}",
+            self.expiry.format("%y%m%d"),
+            right,
+            thousandths
+        )
+    }
+}
+
+/// Terms of one futures contract month
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FutureSpec {
+    /// Product root (e.g. "ES", "CL")
+    pub root: String,
+    pub year: i32,
+    pub month: u32,
+
+    /// Last trading day
+    pub expiry: NaiveDate,
+
+    /// Contract value per point of price
+    pub multiplier: Decimal,
+}
+
+impl FutureSpec {
+    /// Contract month with expiry defaulting to its third Friday, the equity index convention
+    pub fn new(root: &str, year: i32, month: u32) -> Result<Self, SymbolError> {
+        let root = root.trim().to_uppercase();
+        if root.is_empty() || !root.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(SymbolError::InvalidFormat(format!(
+                "futures root '{}'",
+                root
+            )));
+        }
+        let expiry = NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Fri, 3)
+            .ok_or_else(|| {
+                SymbolError::InvalidFormat(format!("futures month {}-{}", year, month))
+            })?;
+        Ok(Self {
+            root,
+            year,
+            month,
+            expiry,
+            multiplier: Decimal::ONE,
+        })
+    }
+
+    pub fn with_expiry(mut self, expiry: NaiveDate) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: Decimal) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Parse a contract code such as "ESZ6", "CLF25" or "ESZ2026"
+    ///
+    /// Single-digit years are resolved to the year ending in that digit
+    /// nearest `reference_year`, preferring the future on a tie, so codes
+    /// keep meaning the right decade for about five years either way.
+    pub fn parse(code: &str, reference_year: i32) -> Result<Self, SymbolError> {
+        let invalid = || SymbolError::InvalidFormat(format!("futures contract code '{}'", code));
+        let code = code.trim().to_uppercase();
+        let digits = code.chars().rev().take_while(char::is_ascii_digit).count();
+        if digits == 0 || code.len() < digits + 2 || !code.is_ascii() {
+            return Err(invalid());
+        }
+        let (head, year) = code.split_at(code.len() - digits);
+        let (root, month_code) = head.split_at(head.len() - 1);
+        let month = MONTH_CODES
+            .iter()
+            .position(|candidate| month_code.starts_with(*candidate))
+            .ok_or_else(invalid)? as u32
+            + 1;
+        let year: i32 = year.parse().map_err(|_| invalid())?;
+        let year = match digits {
+            1 => (reference_year - 4..=reference_year + 5)
+                .find(|candidate| candidate.rem_euclid(10) == year)
+                .ok_or_else(invalid)?,
+            2 => reference_year - reference_year.rem_euclid(100) + year,
+            4 => year,
+            _ => return Err(invalid()),
+        };
+        if root.is_empty() {
+            return Err(invalid());
+        }
+        Self::new(root, year, month)
+    }
+
+    pub fn month_code(&self) -> char {
+        MONTH_CODES[(self.month - 1) as usize]
+    }
+
+    /// Contract code with a two-digit year, e.g. "ESZ26"
+    ///
+    /// Exchanges quote a single year digit, but that reuses the same code
+    /// every decade and would give contracts ten years apart one symbol key.
+    pub fn code(&self) -> String {
+        format!(
+            "{}{}{:02}",
+            self.root,
+            self.month_code(),
+            self.year.rem_euclid(100)
+        )
+    }
+}
+
+/// Contract terms carried on a `Symbol`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DerivativeSpec {
+    #[serde(rename = "option")]
+    Option(OptionSpec),
+    #[serde(rename = "future")]
+    Future(FutureSpec),
+}
+
+/// When a continuous series moves from one contract to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollSchedule {
+    /// Calendar days before expiry on which the next contract takes over
+    pub days_before_expiry: i64,
+}
+
+impl Default for RollSchedule {
+    fn default() -> Self {
+        Self {
+            days_before_expiry: 8,
+        }
+    }
+}
+
+impl RollSchedule {
+    pub fn roll_date(&self, contract: &FutureSpec) -> NaiveDate {
+        contract.expiry - Duration::days(self.days_before_expiry)
+    }
+}
+
+/// Consecutive contract months of one product and how to roll between them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuturesChain {
+    pub root: String,
+
+    /// Ordered by expiry
+    pub contracts: Vec<FutureSpec>,
+    pub roll: RollSchedule,
+}
+
+impl FuturesChain {
+    /// Chain of `contracts`, which must all share one root
+    pub fn new(contracts: Vec<FutureSpec>, roll: RollSchedule) -> Result<Self, SymbolError> {
+        let root = contracts
+            .first()
+            .map(|contract| contract.root.clone())
+            .ok_or_else(|| SymbolError::InvalidFormat("empty futures chain".to_string()))?;
+        if let Some(other) = contracts.iter().find(|contract| contract.root != root) {
+            return Err(SymbolError::InvalidFormat(format!(
+                "{} does not belong to the {} chain",
+                other.code(),
+                root
+            )));
+        }
+        let mut contracts = contracts;
+        contracts.sort_by_key(|contract| contract.expiry);
+        Ok(Self {
+            root,
+            contracts,
+            roll,
+        })
+    }
+
+    /// Contract held on `date`: the first whose roll date has not yet passed
+    pub fn active_contract(&self, date: NaiveDate) -> Option<&FutureSpec> {
+        self.contracts
+            .iter()
+            .find(|contract| date < self.roll.roll_date(contract))
+    }
+}
+
+impl fmt::Display for OptionRight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionRight::Call => write!(f, "Call"),
+            OptionRight::Put => write!(f, "Put"),
+        }
+    }
+}
+
+impl Symbol {
+    /// Option symbol coded by its unpadded OCC symbol, premiums quoted in `currency`
+    pub fn option(
+        spec: OptionSpec,
+        exchange: Exchange,
+        currency: &str,
+    ) -> Result<Self, SymbolError> {
+        let display_name = format!(
+            "{} {} {} {}",
+            spec.underlying, spec.expiry, spec.strike, spec.right
+        );
+        let mut symbol = Self::new(
+            spec.occ_code(),
+            display_name,
+            AssetClass::Option,
+            exchange,
+            currency.to_string(),
+        )?;
+        symbol.tick_size = Decimal::new(1, 2);
+        symbol.contract_size = spec.multiplier;
+        symbol.derivative = Some(DerivativeSpec::Option(spec));
+        Ok(symbol)
+    }
+
+    /// Futures symbol coded like "ESZ26"
+    pub fn future(
+        spec: FutureSpec,
+        exchange: Exchange,
+        currency: &str,
+    ) -> Result<Self, SymbolError> {
+        let display_name = format!(
+            "{} {}",
+            spec.root,
+            NaiveDate::from_ymd_opt(spec.year, spec.month, 1)
+                .map(|month| month.format("%b %Y").to_string())
+                .unwrap_or_default()
+        );
+        let mut symbol = Self::new(
+            spec.code(),
+            display_name,
+            AssetClass::Future,
+            exchange,
+            currency.to_string(),
+        )?;
+        symbol.contract_size = spec.multiplier;
+        symbol.derivative = Some(DerivativeSpec::Future(spec));
+        Ok(symbol)
+    }
+
+    pub fn option_spec(&self) -> Option<&OptionSpec> {
+        match &self.derivative {
+            Some(DerivativeSpec::Option(spec)) => Some(spec),
+            _ => None,
+        }
+    }
+
+    pub fn future_spec(&self) -> Option<&FutureSpec> {
+        match &self.derivative {
+            Some(DerivativeSpec::Future(spec)) => Some(spec),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_occ_round_trip() {
+        let spec = OptionSpec::parse_occ("AAPL  240621C00190000").unwrap();
+        assert_eq!(spec.underlying, "AAPL");
+        assert_eq!(spec.expiry, date(2024, 6, 21));
+        assert_eq!(spec.strike, Decimal::new(190, 0));
+        assert_eq!(spec.right, OptionRight::Call);
+        assert_eq!(spec.occ_symbol(), "AAPL  240621C00190000");
+
+        let put = OptionSpec::parse_occ("SPXW241220P05912500").unwrap();
+        assert_eq!(put.underlying, "SPXW");
+        assert_eq!(put.strike, Decimal::new(59125, 1));
+        assert_eq!(put.occ_code(), "SPXW241220P05912500");
+
+        assert!(OptionSpec::parse_occ("AAPL240621X00190000").is_err());
+        assert!(OptionSpec::parse_occ("AAPL241321C00190000").is_err());
+
+        let symbol = Symbol::option(
+            OptionSpec::parse_occ("GOOGL 250117P00150000").unwrap(),
+            Exchange::Other("OPRA".to_string()),
+            "USD",
+        )
+        .unwrap();
+        assert_eq!(symbol.code, "GOOGL250117P00150000");
+        assert_eq!(symbol.contract_size, Decimal::new(100, 0));
+        assert_eq!(symbol.option_spec().unwrap().right, OptionRight::Put);
+
+        let json = serde_json::to_string(&symbol).unwrap();
+        assert_eq!(serde_json::from_str::<Symbol>(&json).unwrap(), symbol);
+    }
+
+    #[test]
+    fn test_futures_codes() {
+        let es = FutureSpec::parse("ESZ6", 2026).unwrap();
+        assert_eq!((es.root.as_str(), es.year, es.month), ("ES", 2026, 12));
+        assert_eq!(es.expiry, date(2026, 12, 18));
+        assert_eq!(es.code(), "ESZ26");
+        // Contracts a decade apart keep distinct codes
+        assert_eq!(FutureSpec::parse("ESZ36", 2026).unwrap().code(), "ESZ36");
+
+        // A single digit resolves to the nearest decade
+        assert_eq!(FutureSpec::parse("CLF9", 2026).unwrap().year, 2029);
+        assert_eq!(FutureSpec::parse("CLF2", 2026).unwrap().year, 2022);
+        assert_eq!(FutureSpec::parse("CLF25", 2026).unwrap().year, 2025);
+        assert!(FutureSpec::parse("ESA6", 2026).is_err());
+        assert!(FutureSpec::parse("Z6", 2026).is_err());
+
+        let symbol = Symbol::future(
+            es.with_multiplier(Decimal::new(50, 0)),
+            Exchange::Other("CME".to_string()),
+            "USD",
+        )
+        .unwrap();
+        assert_eq!(symbol.display_name, "ES Dec 2026");
+        assert_eq!(symbol.contract_size, Decimal::new(50, 0));
+    }
+
+    #[test]
+    fn test_chain_rolls_before_expiry() {
+        let chain = FuturesChain::new(
+            vec![
+                FutureSpec::parse("ESM4", 2024).unwrap(),
+                FutureSpec::parse("ESH4", 2024).unwrap(),
+            ],
+            RollSchedule::default(),
+        )
+        .unwrap();
+        // March 2024 expiry is the 15th; the roll is eight days earlier
+        assert_eq!(
+            chain.active_contract(date(2024, 3, 6)).unwrap().code(),
+            "ESH24"
+        );
+        assert_eq!(
+            chain.active_contract(date(2024, 3, 7)).unwrap().code(),
+            "ESM24"
+        );
+        assert!(chain.active_contract(date(2024, 6, 20)).is_none());
+
+        assert!(FuturesChain::new(
+            vec![
+                FutureSpec::parse("ESH4", 2024).unwrap(),
+                FutureSpec::parse("NQH4", 2024).unwrap(),
+            ],
+            RollSchedule::default(),
+        )
+        .is_err());
+    }
+}
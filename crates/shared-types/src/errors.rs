@@ -629,6 +629,36 @@ pub enum BackoffStrategy {
     Jittered,
 }
 
+impl RetryStrategy {
+    /// Whether to retry after `failures` failed attempts whose last failure matched `condition`
+    ///
+    /// An empty `retry_conditions` list retries on any condition.
+    pub fn should_retry_after(&self, failures: u32, condition: &str) -> bool {
+        self.should_retry
+            && failures <= self.max_attempts
+            && (self.retry_conditions.is_empty()
+                || self.retry_conditions.iter().any(|known| known == condition))
+    }
+
+    /// Wait before retry number `retry`, counting from 1
+    ///
+    /// Jittered backoff is exponential backoff scaled by a factor between 0.5
+    /// and 1, so clients retrying together spread out.
+    pub fn delay_before_retry(&self, retry: u32) -> std::time::Duration {
+        let base = std::time::Duration::from_secs(self.delay_seconds);
+        let exponential = || base.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        match self.backoff_strategy {
+            BackoffStrategy::Fixed => base,
+            BackoffStrategy::Linear => base.saturating_mul(retry.max(1)),
+            BackoffStrategy::Exponential => exponential(),
+            BackoffStrategy::Jittered => {
+                let noise = Utc::now().timestamp_subsec_nanos() as f64 / 1e9;
+                exponential().mul_f64(0.5 + noise / 2.0)
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Error Builder and Helper Methods
 // ============================================================================
@@ -674,9 +704,7 @@ impl TradingError {
 
     /// Check if retry is recommended
     pub fn should_retry(&self) -> bool {
-        self.retry_strategy
-            .as_ref()
-            .map_or(false, |s| s.should_retry)
+        self.retry_strategy.as_ref().is_some_and(|s| s.should_retry)
     }
 
     /// Get user-friendly error message
@@ -898,6 +926,36 @@ mod tests {
         assert_eq!(error.error_chain[1].message, "Network timeout");
     }
 
+    #[test]
+    fn test_retry_strategy_schedule() {
+        let mut strategy = RetryStrategy {
+            should_retry: true,
+            max_attempts: 2,
+            delay_seconds: 3,
+            backoff_strategy: BackoffStrategy::Exponential,
+            retry_conditions: vec!["timeout".to_string()],
+        };
+        assert!(strategy.should_retry_after(2, "timeout"));
+        assert!(!strategy.should_retry_after(3, "timeout"));
+        assert!(!strategy.should_retry_after(1, "client_error"));
+        assert_eq!(
+            strategy.delay_before_retry(3),
+            std::time::Duration::from_secs(12)
+        );
+
+        strategy.backoff_strategy = BackoffStrategy::Linear;
+        assert_eq!(
+            strategy.delay_before_retry(3),
+            std::time::Duration::from_secs(9)
+        );
+        strategy.backoff_strategy = BackoffStrategy::Jittered;
+        let delay = strategy.delay_before_retry(2);
+        assert!(
+            delay >= std::time::Duration::from_secs(3)
+                && delay <= std::time::Duration::from_secs(6)
+        );
+    }
+
     #[test]
     fn test_retry_strategy() {
         let retry_strategy = RetryStrategy {
@@ -1077,7 +1135,6 @@ mod tests {
         };
 
         // If we reach here, all error types can be created successfully
-        assert!(true);
     }
 
     #[test]
@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+use crate::{Symbol, OHLCV};
+
+/// Base currency of portfolios that do not choose one
+pub const DEFAULT_BASE_CURRENCY: &str = "USD";
+
+/// Currency that crosses are triangulated through when no direct rate is known
+pub const TRIANGULATION_CURRENCY: &str = "USD";
+
+/// Minor-unit quote currencies and the major currency they are a hundredth of
+const MINOR_UNITS: &[(&str, &str)] = &[("GBX", "GBP"), ("ZAC", "ZAR"), ("ILA", "ILS")];
+
+#[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FxError {
+    #[error("No {from}/{to} rate at or before {at}")]
+    MissingRate {
+        from: String,
+        to: String,
+        at: DateTime<Utc>,
+    },
+
+    #[error("{0} is not a currency pair")]
+    NotACurrencyPair(String),
+
+    #[error("Invalid {pair} rate: {rate}")]
+    InvalidRate { pair: String, rate: Decimal },
+}
+
+impl Symbol {
+    /// Currency the symbol's prices are quoted in
+    ///
+    /// Pairs are priced in their quote currency; everything else in `currency`.
+    pub fn price_currency(&self) -> &str {
+        self.quote_currency.as_deref().unwrap_or(&self.currency)
+    }
+}
+
+/// Historical exchange rates between currency pairs
+///
+/// Rates are stored as quote currency per unit of base, the way forex
+/// symbols are priced, and looked up as of a timestamp: the latest rate at or
+/// before it is used. A pair can be read in either direction, and crosses
+/// without a rate of their own go through [`TRIANGULATION_CURRENCY`].
+/// Minor units such as pence (GBX) convert through their major currency.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FxRates {
+    rates: HashMap<String, BTreeMap<DateTime<Utc>, Decimal>>,
+}
+
+impl FxRates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the rate of `base` in `quote` at `at`
+    pub fn add_rate(
+        &mut self,
+        base: &str,
+        quote: &str,
+        at: DateTime<Utc>,
+        rate: Decimal,
+    ) -> Result<(), FxError> {
+        let (base, quote) = (base.to_uppercase(), quote.to_uppercase());
+        if rate <= Decimal::ZERO {
+            return Err(FxError::InvalidRate {
+                pair: pair_key(&base, &quote),
+                rate,
+            });
+        }
+        self.rates
+            .entry(pair_key(&base, &quote))
+            .or_default()
+            .insert(at, rate);
+        Ok(())
+    }
+
+    /// Record a forex or crypto pair's close as its rate at the bar's timestamp
+    pub fn add_bar(&mut self, bar: &OHLCV) -> Result<(), FxError> {
+        let (base, quote) = bar
+            .symbol
+            .base_quote()
+            .ok_or_else(|| FxError::NotACurrencyPair(bar.symbol.code.clone()))?;
+        self.add_rate(&base, &quote, bar.timestamp, bar.close)
+    }
+
+    /// Record every bar, stopping at the first that is not a pair
+    pub fn add_bars(&mut self, bars: &[OHLCV]) -> Result<(), FxError> {
+        bars.iter().try_for_each(|bar| self.add_bar(bar))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rates.is_empty()
+    }
+
+    /// Units of `to` per unit of `from` as of `at`
+    pub fn rate(&self, from: &str, to: &str, at: DateTime<Utc>) -> Result<Decimal, FxError> {
+        let (from, from_scale) = major_unit(from);
+        let (to, to_scale) = major_unit(to);
+        let missing = || FxError::MissingRate {
+            from: from.clone(),
+            to: to.clone(),
+            at,
+        };
+
+        let rate = if from == to {
+            Decimal::ONE
+        } else if let Some(rate) = self.pair_rate(&from, &to, at) {
+            rate
+        } else if from != TRIANGULATION_CURRENCY && to != TRIANGULATION_CURRENCY {
+            let to_pivot = self
+                .pair_rate(&from, TRIANGULATION_CURRENCY, at)
+                .ok_or_else(missing)?;
+            let from_pivot = self
+                .pair_rate(TRIANGULATION_CURRENCY, &to, at)
+                .ok_or_else(missing)?;
+            to_pivot * from_pivot
+        } else {
+            return Err(missing());
+        };
+        Ok(rate * from_scale / to_scale)
+    }
+
+    /// Most recent rate, whenever it was recorded
+    pub fn latest_rate(&self, from: &str, to: &str) -> Result<Decimal, FxError> {
+        self.rate(from, to, DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Convert `amount` of `from` into `to` as of `at`
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Decimal, FxError> {
+        Ok(amount * self.rate(from, to, at)?)
+    }
+
+    /// Direct or inverted rate of one stored pair
+    fn pair_rate(&self, from: &str, to: &str, at: DateTime<Utc>) -> Option<Decimal> {
+        let as_of = |key: String| {
+            self.rates
+                .get(&key)
+                .and_then(|series| series.range(..=at).next_back())
+                .map(|(timestamp, rate)| (*timestamp, *rate))
+        };
+        // Prefer whichever direction was quoted more recently
+        match (as_of(pair_key(from, to)), as_of(pair_key(to, from))) {
+            (Some((direct_at, rate)), Some((inverse_at, _))) if direct_at >= inverse_at => {
+                Some(rate)
+            }
+            (_, Some((_, inverse))) => Some(Decimal::ONE / inverse),
+            (Some((_, rate)), None) => Some(rate),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Currency that rates for `currency` are quoted in, e.g. GBP for pence (GBX)
+pub fn major_currency(currency: &str) -> String {
+    major_unit(currency).0
+}
+
+fn pair_key(base: &str, quote: &str) -> String {
+    format!("{}/{}", base, quote)
+}
+
+/// Major currency and how many of it one unit of `currency` is worth
+fn major_unit(currency: &str) -> (String, Decimal) {
+    let currency = currency.to_uppercase();
+    match MINOR_UNITS.iter().find(|(minor, _)| *minor == currency) {
+        Some((_, major)) => (major.to_string(), Decimal::new(1, 2)),
+        None => (currency, Decimal::ONE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Exchange, TimeFrame};
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap()
+    }
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn rates() -> FxRates {
+        let mut rates = FxRates::new();
+        for (pair, at, close) in [
+            ("GBP/USD", day(1), "1.25"),
+            ("GBP/USD", day(5), "1.28"),
+            ("EUR/USD", day(1), "1.10"),
+            ("USD/JPY", day(1), "150"),
+        ] {
+            let (base, quote) = pair.split_once('/').unwrap();
+            let symbol = Symbol::forex(base, quote).unwrap();
+            let bar = OHLCV::new(
+                symbol,
+                TimeFrame::OneDay,
+                at,
+                dec(close),
+                dec(close),
+                dec(close),
+                dec(close),
+                Decimal::ZERO,
+            )
+            .unwrap();
+            rates.add_bar(&bar).unwrap();
+        }
+        rates
+    }
+
+    #[test]
+    fn test_direct_inverse_and_historical_rates() {
+        let rates = rates();
+        assert_eq!(rates.rate("GBP", "USD", day(3)).unwrap(), dec("1.25"));
+        assert_eq!(rates.rate("gbp", "usd", day(6)).unwrap(), dec("1.28"));
+        assert_eq!(rates.latest_rate("USD", "USD").unwrap(), Decimal::ONE);
+        assert_eq!(
+            rates
+                .convert(dec("150"), "JPY", "USD", day(2))
+                .unwrap()
+                .round_dp(10),
+            Decimal::ONE
+        );
+        // Pence convert through sterling
+        assert_eq!(
+            rates.convert(dec("1000"), "GBX", "USD", day(2)).unwrap(),
+            dec("12.5")
+        );
+        assert_eq!(
+            rates.rate(
+                "GBP",
+                "USD",
+                Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap()
+            ),
+            Err(FxError::MissingRate {
+                from: "GBP".to_string(),
+                to: "USD".to_string(),
+                at: Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_crosses_triangulate_through_usd() {
+        let rates = rates();
+        // 1.28 USD per GBP over 1.10 USD per EUR
+        assert_eq!(
+            rates.rate("GBP", "EUR", day(9)).unwrap().round_dp(6),
+            dec("1.163636")
+        );
+        assert_eq!(
+            rates.rate("EUR", "JPY", day(9)).unwrap().round_dp(6),
+            dec("165")
+        );
+        assert!(rates.rate("EUR", "CHF", day(9)).is_err());
+
+        let stock = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        assert_eq!(stock.price_currency(), "USD");
+        assert_eq!(Symbol::forex("EUR", "GBP").unwrap().price_currency(), "GBP");
+        assert!(FxRates::new()
+            .add_rate("EUR", "USD", day(1), Decimal::ZERO)
+            .is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{Symbol, SymbolError};
+
+/// Security identifier schemes kept in `Symbol::metadata`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IdentifierKind {
+    /// ISO 6166 International Securities Identification Number
+    #[serde(rename = "isin")]
+    Isin,
+    /// Nine-character North American identifier
+    #[serde(rename = "cusip")]
+    Cusip,
+    /// OpenFIGI Financial Instrument Global Identifier
+    #[serde(rename = "figi")]
+    Figi,
+}
+
+impl IdentifierKind {
+    pub const ALL: [IdentifierKind; 3] = [
+        IdentifierKind::Isin,
+        IdentifierKind::Cusip,
+        IdentifierKind::Figi,
+    ];
+
+    /// Metadata key the identifier is stored under, also used as its alias namespace
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdentifierKind::Isin => "isin",
+            IdentifierKind::Cusip => "cusip",
+            IdentifierKind::Figi => "figi",
+        }
+    }
+
+    /// Uppercase `value` and check its length, character set and check digit
+    pub fn validate(&self, value: &str) -> Result<String, SymbolError> {
+        let value = value.trim().to_uppercase();
+        let invalid = || SymbolError::InvalidFormat(format!("{} '{}'", self, value));
+        let chars: Vec<char> = value.chars().collect();
+
+        let valid = match self {
+            IdentifierKind::Isin => {
+                chars.len() == 12
+                    && chars[..2].iter().all(char::is_ascii_uppercase)
+                    && chars[2..11].iter().all(char::is_ascii_alphanumeric)
+                    && chars[11].is_ascii_digit()
+                    && isin_checksum_valid(&chars)
+            }
+            IdentifierKind::Cusip => {
+                chars.len() == 9
+                    && chars[..8]
+                        .iter()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '*' | '@' | '#'))
+                    && chars[8].to_digit(10) == Some(double_add_double(&chars[..8]))
+            }
+            IdentifierKind::Figi => {
+                // Two-letter prefix, 'G', eight consonants or digits, check digit
+                let consonant = |c: &char| {
+                    c.is_ascii_digit() || (c.is_ascii_uppercase() && !"AEIOU".contains(*c))
+                };
+                chars.len() == 12
+                    && chars[..2].iter().all(consonant)
+                    && !["BS", "BM", "GG", "GB", "GH", "KY", "VG"].contains(&&value[..2])
+                    && chars[2] == 'G'
+                    && chars[3..11].iter().all(consonant)
+                    && chars[11].to_digit(10) == Some(double_add_double(&chars[..11]))
+            }
+        };
+        if valid {
+            Ok(value)
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl fmt::Display for IdentifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentifierKind::Isin => write!(f, "ISIN"),
+            IdentifierKind::Cusip => write!(f, "CUSIP"),
+            IdentifierKind::Figi => write!(f, "FIGI"),
+        }
+    }
+}
+
+/// Digits 0-9 as themselves, letters from 10, then the CUSIP specials `*@#`
+fn char_value(c: char) -> u32 {
+    match c {
+        '0'..='9' => c as u32 - '0' as u32,
+        'A'..='Z' => c as u32 - 'A' as u32 + 10,
+        '*' => 36,
+        '@' => 37,
+        '#' => 38,
+        _ => 0,
+    }
+}
+
+/// Check digit shared by CUSIP and FIGI: double every second value, sum the digits
+fn double_add_double(chars: &[char]) -> u32 {
+    let sum: u32 = chars
+        .iter()
+        .enumerate()
+        .map(|(index, c)| {
+            let value = char_value(*c) * if index % 2 == 1 { 2 } else { 1 };
+            value / 10 + value % 10
+        })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+/// Luhn over the digit string formed by expanding letters to two digits
+fn isin_checksum_valid(chars: &[char]) -> bool {
+    let digits: Vec<u32> = chars
+        .iter()
+        .flat_map(|c| {
+            let value = char_value(*c);
+            if value >= 10 {
+                vec![value / 10, value % 10]
+            } else {
+                vec![value]
+            }
+        })
+        .collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| {
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                *digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+impl Symbol {
+    /// Validate and record a security identifier in the symbol's metadata
+    pub fn set_identifier(&mut self, kind: IdentifierKind, value: &str) -> Result<(), SymbolError> {
+        let value = kind.validate(value)?;
+        self.metadata.insert(kind.as_str().to_string(), value);
+        Ok(())
+    }
+
+    pub fn identifier(&self, kind: IdentifierKind) -> Option<&str> {
+        self.metadata.get(kind.as_str()).map(String::as_str)
+    }
+
+    /// All identifiers recorded on the symbol
+    pub fn identifiers(&self) -> Vec<(IdentifierKind, &str)> {
+        IdentifierKind::ALL
+            .into_iter()
+            .filter_map(|kind| self.identifier(kind).map(|value| (kind, value)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Exchange;
+
+    #[test]
+    fn test_check_digits() {
+        // Apple Inc. common stock
+        assert_eq!(
+            IdentifierKind::Isin.validate("us0378331005").unwrap(),
+            "US0378331005"
+        );
+        assert!(IdentifierKind::Cusip.validate("037833100").is_ok());
+        assert!(IdentifierKind::Figi.validate("BBG000B9XRY4").is_ok());
+        // GB00B03MLX29 is Royal Dutch Shell A
+        assert!(IdentifierKind::Isin.validate("GB00B03MLX29").is_ok());
+
+        assert!(IdentifierKind::Isin.validate("US0378331006").is_err());
+        assert!(IdentifierKind::Cusip.validate("037833101").is_err());
+        assert!(IdentifierKind::Figi.validate("BBG000B9XRY5").is_err());
+        assert!(IdentifierKind::Figi.validate("BBA000B9XRY4").is_err());
+    }
+
+    #[test]
+    fn test_identifiers_in_metadata() {
+        let mut symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        symbol
+            .set_identifier(IdentifierKind::Isin, "US0378331005")
+            .unwrap();
+        assert!(symbol
+            .set_identifier(IdentifierKind::Cusip, "12345")
+            .is_err());
+
+        assert_eq!(symbol.get_metadata("isin").unwrap(), "US0378331005");
+        assert_eq!(
+            symbol.identifiers(),
+            vec![(IdentifierKind::Isin, "US0378331005")]
+        );
+    }
+}
@@ -1,16 +1,40 @@
 //! Shared types for the Trading Intelligence Orchestrator
 
 // Placeholder for now - we'll implement these modules next
+pub mod alerts;
 pub mod api_types;
+pub mod calendar;
+pub mod corporate_actions;
+pub mod derivatives;
 pub mod errors;
+pub mod fx;
+pub mod identifiers;
+pub mod normalize;
+pub mod notifications;
 pub mod ohlcv;
+pub mod portfolio;
+pub mod screener;
+pub mod series;
 pub mod symbol;
 pub mod timeframe;
 pub mod validation;
+pub mod watchlist;
 
+pub use alerts::*;
 pub use api_types::*;
+pub use calendar::*;
+pub use corporate_actions::*;
+pub use derivatives::*;
 pub use errors::*;
+pub use fx::*;
+pub use identifiers::*;
+pub use normalize::*;
+pub use notifications::*;
 pub use ohlcv::*;
+pub use portfolio::*;
+pub use screener::*;
+pub use series::*;
 pub use symbol::*;
 pub use timeframe::*;
 pub use validation::*;
+pub use watchlist::*;
@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{AssetClass, Exchange, Symbol, SymbolError};
+
+/// Quote currencies recognised at the end of an unseparated pair, longest first
+const QUOTE_CURRENCIES: &[&str] = &[
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "USD", "EUR", "GBP", "JPY", "AUD", "CAD", "CHF",
+    "TRY", "BRL", "DAI", "BTC", "ETH", "BNB",
+];
+
+/// Venue-specific asset codes and their canonical spelling
+const ASSET_ALIASES: &[(&str, &str)] = &[("XBT", "BTC"), ("XDG", "DOGE")];
+
+/// Data vendors with their own ticker conventions
+///
+/// Canonical symbols use the `Symbol` constructors' spelling: `BTC-USD` for
+/// crypto pairs, `EUR/USD` for forex and the bare listing code for stocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Vendor {
+    #[serde(rename = "binance")]
+    Binance,
+    #[serde(rename = "coinbase")]
+    Coinbase,
+    #[serde(rename = "kraken")]
+    Kraken,
+    #[serde(rename = "alpha_vantage")]
+    AlphaVantage,
+    #[serde(rename = "oanda")]
+    Oanda,
+}
+
+impl Vendor {
+    /// Name used for alias namespaces and `<name>_symbol` metadata overrides
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Vendor::Binance => "binance",
+            Vendor::Coinbase => "coinbase",
+            Vendor::Kraken => "kraken",
+            Vendor::AlphaVantage => "alpha_vantage",
+            Vendor::Oanda => "oanda",
+        }
+    }
+
+    /// Exchange whose listings the vendor's tickers name, for venues that are exchanges
+    pub fn exchange(&self) -> Option<Exchange> {
+        match self {
+            Vendor::Binance => Some(Exchange::Binance),
+            Vendor::Coinbase => Some(Exchange::Coinbase),
+            Vendor::Kraken => Some(Exchange::Kraken),
+            Vendor::AlphaVantage | Vendor::Oanda => None,
+        }
+    }
+
+    /// Vendor ticker for a symbol: its `<vendor>_symbol` metadata if set, else the convention
+    pub fn ticker(&self, symbol: &Symbol) -> String {
+        if let Some(ticker) = symbol.get_metadata(&format!("{}_symbol", self.as_str())) {
+            return ticker.clone();
+        }
+        let pair = symbol.base_quote();
+        match (self, pair) {
+            (Vendor::Binance, Some((base, quote))) => format!("{}{}", base, quote),
+            (Vendor::Coinbase, Some((base, quote))) => format!("{}-{}", base, quote),
+            (Vendor::Kraken, Some((base, quote))) => format!("{}/{}", base, quote),
+            (Vendor::Oanda, Some((base, quote))) => format!("{}_{}", base, quote),
+            (Vendor::AlphaVantage, _) => match symbol.exchange {
+                Exchange::LSE => format!("{}.LON", symbol.code),
+                Exchange::XETRA => format!("{}.DEX", symbol.code),
+                Exchange::TSE => format!("{}.TYO", symbol.code),
+                _ => symbol.code.clone(),
+            },
+            (_, None) => symbol.code.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Vendor {
+    type Err = SymbolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "binance" => Ok(Vendor::Binance),
+            "coinbase" => Ok(Vendor::Coinbase),
+            "kraken" => Ok(Vendor::Kraken),
+            "alpha_vantage" => Ok(Vendor::AlphaVantage),
+            "oanda" => Ok(Vendor::Oanda),
+            _ => Err(SymbolError::InvalidExchange(s.to_string())),
+        }
+    }
+}
+
+impl Symbol {
+    /// Base and quote currency of a crypto or forex pair
+    pub fn base_quote(&self) -> Option<(String, String)> {
+        if !matches!(self.asset_class, AssetClass::Crypto | AssetClass::Forex) {
+            return None;
+        }
+        let base = self.code.split(['-', '/']).next().unwrap_or(&self.code);
+        let quote = self
+            .quote_currency
+            .clone()
+            .unwrap_or_else(|| self.currency.clone());
+        Some((base.to_string(), quote))
+    }
+}
+
+/// Maps vendor tickers to canonical symbols and back
+///
+/// Explicit aliases win over the vendor conventions, so tickers that cannot
+/// be parsed (renamed listings, venue-specific contract codes) can still be
+/// resolved once registered. Bare US tickers carry no exchange, so they are
+/// looked up in the US listings taken from the symbol master.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolNormalizer {
+    aliases: HashMap<(Vendor, String), Symbol>,
+    listings: HashMap<String, Symbol>,
+}
+
+impl SymbolNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_alias(mut self, vendor: Vendor, ticker: &str, symbol: Symbol) -> Self {
+        self.add_alias(vendor, ticker, symbol);
+        self
+    }
+
+    pub fn add_alias(&mut self, vendor: Vendor, ticker: &str, symbol: Symbol) {
+        self.aliases
+            .insert((vendor, ticker.trim().to_uppercase()), symbol);
+    }
+
+    pub fn with_listing(mut self, symbol: Symbol) -> Self {
+        self.add_listing(symbol);
+        self
+    }
+
+    /// Register a NYSE, NASDAQ or AMEX listing; symbols on other exchanges are ignored
+    pub fn add_listing(&mut self, mut symbol: Symbol) {
+        if is_us_exchange(&symbol.exchange) {
+            symbol.timezone = US_TIMEZONE.to_string();
+            self.listings.insert(symbol.code.to_uppercase(), symbol);
+        }
+    }
+
+    /// US listing for a bare ticker
+    pub fn listing(&self, code: &str) -> Option<&Symbol> {
+        self.listings.get(&code.trim().to_uppercase())
+    }
+
+    /// Canonical symbol for a vendor ticker
+    ///
+    /// Without an alias only the code, asset class and exchange can be
+    /// inferred, so the display name is the canonical code. A bare Alpha
+    /// Vantage ticker must be a registered listing, since the ticker alone
+    /// does not say whether it trades on NYSE or NASDAQ.
+    pub fn normalize(&self, vendor: Vendor, ticker: &str) -> Result<Symbol, SymbolError> {
+        let ticker = ticker.trim().to_uppercase();
+        if let Some(symbol) = self.aliases.get(&(vendor, ticker.clone())) {
+            return Ok(symbol.clone());
+        }
+
+        match vendor {
+            Vendor::Binance | Vendor::Coinbase | Vendor::Kraken => {
+                let (base, quote) = split_pair(vendor, &ticker)?;
+                let exchange = vendor.exchange().expect("crypto venues are exchanges");
+                Symbol::crypto(&base, &quote, exchange)
+            }
+            Vendor::Oanda => {
+                let (base, quote) = split_pair(vendor, &ticker)?;
+                Symbol::forex(&base, &quote)
+            }
+            Vendor::AlphaVantage => {
+                let (code, exchange, currency, timezone) = match ticker.rsplit_once('.') {
+                    Some((code, "LON")) => (code, Exchange::LSE, "GBP", "Europe/London"),
+                    Some((code, "DEX")) => (code, Exchange::XETRA, "EUR", "Europe/Berlin"),
+                    Some((code, "TYO")) => (code, Exchange::TSE, "JPY", "Asia/Tokyo"),
+                    _ => {
+                        return self.listing(&ticker).cloned().ok_or_else(|| {
+                            SymbolError::NotFound(format!("{} is not a known US listing", ticker))
+                        })
+                    }
+                };
+                let mut symbol = Symbol::new(
+                    code.to_string(),
+                    code.to_string(),
+                    AssetClass::Stock,
+                    exchange,
+                    currency.to_string(),
+                )?;
+                symbol.timezone = timezone.to_string();
+                Ok(symbol)
+            }
+        }
+    }
+
+    /// Vendor ticker for a canonical symbol, preferring a registered alias
+    pub fn vendor_ticker(&self, symbol: &Symbol, vendor: Vendor) -> String {
+        let key = symbol.full_identifier();
+        let mut aliases: Vec<&String> = self
+            .aliases
+            .iter()
+            .filter(|((alias_vendor, _), aliased)| {
+                *alias_vendor == vendor && aliased.full_identifier() == key
+            })
+            .map(|((_, ticker), _)| ticker)
+            .collect();
+        // Deterministic when several tickers alias one symbol
+        aliases.sort();
+        match aliases.first() {
+            Some(ticker) => (*ticker).clone(),
+            None => vendor.ticker(symbol),
+        }
+    }
+}
+
+/// Timezone of the US exchanges bare Alpha Vantage tickers trade on
+const US_TIMEZONE: &str = "America/New_York";
+
+fn is_us_exchange(exchange: &Exchange) -> bool {
+    matches!(exchange, Exchange::NYSE | Exchange::NASDAQ | Exchange::AMEX)
+}
+
+/// Split "BTC-USD", "XBT/USD", "EUR_USD", "BTCUSDT" or Kraken's "XXBTZUSD"
+fn split_pair(vendor: Vendor, ticker: &str) -> Result<(String, String), SymbolError> {
+    let invalid = || SymbolError::InvalidFormat(format!("unrecognised pair '{}'", ticker));
+
+    let (base, quote) = if let Some((base, quote)) = ticker.split_once(['-', '/', '_', '.', ':']) {
+        (base, quote)
+    } else if vendor == Vendor::Kraken
+        && ticker.len() == 8
+        && ticker.is_ascii()
+        && ticker.starts_with(['X', 'Z'])
+        && ticker[4..].starts_with(['X', 'Z'])
+    {
+        // Kraken's legacy four-letter asset codes with class prefixes
+        (&ticker[1..4], &ticker[5..])
+    } else {
+        QUOTE_CURRENCIES
+            .iter()
+            .find(|quote| ticker.len() > quote.len() && ticker.ends_with(*quote))
+            .map(|quote| (&ticker[..ticker.len() - quote.len()], *quote))
+            .ok_or_else(invalid)?
+    };
+
+    if base.is_empty() || quote.is_empty() {
+        return Err(invalid());
+    }
+    let canonical = |code: &str| {
+        ASSET_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == code)
+            .map_or(code, |(_, canonical)| canonical)
+            .to_string()
+    };
+    Ok((canonical(base), canonical(quote)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vendor_spellings_share_one_canonical_symbol() {
+        let normalizer = SymbolNormalizer::new();
+        let btc = |vendor, ticker| normalizer.normalize(vendor, ticker).unwrap();
+
+        assert_eq!(btc(Vendor::Coinbase, "BTC-USD").code, "BTC-USD");
+        assert_eq!(btc(Vendor::Kraken, "XBT/USD").code, "BTC-USD");
+        assert_eq!(btc(Vendor::Kraken, "XXBTZUSD").code, "BTC-USD");
+        let binance = btc(Vendor::Binance, "btcusdt");
+        assert_eq!(binance.code, "BTC-USDT");
+        assert_eq!(binance.exchange, Exchange::Binance);
+        assert_eq!(
+            normalizer.vendor_ticker(&binance, Vendor::Binance),
+            "BTCUSDT"
+        );
+        assert_eq!(
+            normalizer.vendor_ticker(&binance, Vendor::Kraken),
+            "BTC/USDT"
+        );
+
+        let eur = normalizer.normalize(Vendor::Oanda, "EUR_USD").unwrap();
+        assert_eq!(eur, Symbol::forex("EUR", "USD").unwrap());
+        assert_eq!(normalizer.vendor_ticker(&eur, Vendor::Oanda), "EUR_USD");
+
+        let tesco = normalizer
+            .normalize(Vendor::AlphaVantage, "TSCO.LON")
+            .unwrap();
+        assert_eq!(
+            (tesco.code.as_str(), &tesco.exchange),
+            ("TSCO", &Exchange::LSE)
+        );
+        assert_eq!(Vendor::AlphaVantage.ticker(&tesco), "TSCO.LON");
+
+        assert!(normalizer.normalize(Vendor::Binance, "NOTAPAIR").is_err());
+    }
+
+    #[test]
+    fn test_bare_us_tickers_resolve_through_listings() {
+        let normalizer = SymbolNormalizer::new();
+        assert!(matches!(
+            normalizer.normalize(Vendor::AlphaVantage, "IBM"),
+            Err(SymbolError::NotFound(_))
+        ));
+
+        let ibm = Symbol::stock("IBM", "International Business Machines", Exchange::NYSE).unwrap();
+        let normalizer = normalizer
+            .with_listing(ibm)
+            .with_listing(Symbol::stock("VOD", "Vodafone", Exchange::LSE).unwrap());
+        let resolved = normalizer.normalize(Vendor::AlphaVantage, "ibm").unwrap();
+        assert_eq!(resolved.exchange, Exchange::NYSE);
+        assert_eq!(resolved.timezone, "America/New_York");
+        assert_eq!(resolved.display_name, "International Business Machines");
+        assert!(normalizer.listing("VOD").is_none());
+    }
+
+    #[test]
+    fn test_kraken_legacy_codes_only_apply_to_kraken() {
+        let normalizer = SymbolNormalizer::new();
+        let kraken = normalizer.normalize(Vendor::Kraken, "XETHZEUR").unwrap();
+        assert_eq!(kraken.code, "ETH-EUR");
+
+        // On Binance the same spelling is an "XETHZ" asset quoted in EUR
+        let binance = normalizer.normalize(Vendor::Binance, "XETHZEUR").unwrap();
+        assert_eq!(binance.code, "XETHZ-EUR");
+    }
+
+    #[test]
+    fn test_aliases_override_conventions() {
+        let matic = Symbol::crypto("POL", "USD", Exchange::Coinbase).unwrap();
+        let normalizer =
+            SymbolNormalizer::new().with_alias(Vendor::Coinbase, "MATIC-USD", matic.clone());
+
+        assert_eq!(
+            normalizer.normalize(Vendor::Coinbase, "matic-usd").unwrap(),
+            matic
+        );
+        assert_eq!(
+            normalizer.vendor_ticker(&matic, Vendor::Coinbase),
+            "MATIC-USD"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::AlertEvent;
+
+/// Kind of outbound channel, used to pick the sender that delivers it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    Webhook,
+    Email,
+    Chat,
+}
+
+impl fmt::Display for ChannelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChannelKind::Webhook => "webhook",
+            ChannelKind::Email => "email",
+            ChannelKind::Chat => "chat",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ChannelKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "webhook" => Ok(ChannelKind::Webhook),
+            "email" => Ok(ChannelKind::Email),
+            "chat" => Ok(ChannelKind::Chat),
+            _ => Err(format!("Unknown notification channel: {}", s)),
+        }
+    }
+}
+
+/// Where one user wants notifications delivered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    /// JSON POST of the notification, signed with HMAC-SHA256 when a secret is set
+    Webhook {
+        url: String,
+        #[serde(default)]
+        secret: Option<String>,
+    },
+
+    /// Plain-text email through the configured SMTP relay
+    Email { to: String },
+
+    /// Slack-compatible incoming webhook taking a `{"text": ...}` body
+    Chat { url: String },
+}
+
+impl NotificationChannel {
+    pub fn kind(&self) -> ChannelKind {
+        match self {
+            NotificationChannel::Webhook { .. } => ChannelKind::Webhook,
+            NotificationChannel::Email { .. } => ChannelKind::Email,
+            NotificationChannel::Chat { .. } => ChannelKind::Chat,
+        }
+    }
+}
+
+/// A user's delivery settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryPreferences {
+    pub user_id: String,
+
+    /// Every channel receives every notification the user wants
+    pub channels: Vec<NotificationChannel>,
+
+    /// Symbol codes to notify about; empty means all
+    #[serde(default)]
+    pub symbols: Vec<String>,
+
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl DeliveryPreferences {
+    pub fn new(user_id: impl Into<String>) -> Self {
+        Self {
+            user_id: user_id.into(),
+            channels: Vec::new(),
+            symbols: Vec::new(),
+            enabled: true,
+        }
+    }
+
+    pub fn with_channel(mut self, channel: NotificationChannel) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    pub fn with_symbols<I, S>(mut self, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.symbols = symbols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether the user wants alerts about `symbol_code`
+    pub fn wants(&self, symbol_code: &str) -> bool {
+        self.enabled
+            && (self.symbols.is_empty()
+                || self
+                    .symbols
+                    .iter()
+                    .any(|symbol| symbol.eq_ignore_ascii_case(symbol_code)))
+    }
+}
+
+/// One message bound for one channel of one user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: String,
+    pub channel: NotificationChannel,
+    pub subject: String,
+    pub body: String,
+
+    /// Structured content for webhook receivers
+    pub payload: serde_json::Value,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn from_alert(
+        user_id: impl Into<String>,
+        channel: NotificationChannel,
+        event: &AlertEvent,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id: user_id.into(),
+            channel,
+            subject: format!("{} alert: {}", event.symbol, event.rule_name),
+            body: format!(
+                "{}\n\n{} {} closed at {} on the bar of {}.",
+                event.message,
+                event.symbol,
+                event.timeframe,
+                event.price,
+                event.triggered_at.format("%Y-%m-%d %H:%M UTC")
+            ),
+            payload: serde_json::json!({
+                "category": "rule",
+                "event": event,
+            }),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A notification given up on after its retries ran out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub notification: Notification,
+
+    /// Attempts made before giving up
+    pub attempts: u32,
+
+    /// The last delivery error
+    pub error: String,
+
+    pub failed_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeFrame;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_preferences_filter_and_alert_notification() {
+        let preferences = DeliveryPreferences::new("trader-1")
+            .with_channel(NotificationChannel::Email {
+                to: "trader@example.com".to_string(),
+            })
+            .with_symbols(["AAPL"]);
+        assert!(preferences.wants("aapl"));
+        assert!(!preferences.wants("MSFT"));
+
+        let json = serde_json::to_value(&preferences).unwrap();
+        assert_eq!(json["channels"][0]["type"], "email");
+        assert_eq!(
+            serde_json::from_value::<DeliveryPreferences>(serde_json::json!({
+                "user_id": "trader-2",
+                "channels": [{"type": "webhook", "url": "https://example.com/hook"}],
+            }))
+            .unwrap()
+            .channels[0],
+            NotificationChannel::Webhook {
+                url: "https://example.com/hook".to_string(),
+                secret: None,
+            }
+        );
+
+        let event = AlertEvent {
+            id: Uuid::new_v4(),
+            rule_id: Uuid::new_v4(),
+            rule_name: "breakout".to_string(),
+            symbol: "AAPL".to_string(),
+            timeframe: TimeFrame::OneHour,
+            triggered_at: Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap(),
+            price: Decimal::new(106, 0),
+            value: None,
+            message: "breakout: close 106 crossed above 105".to_string(),
+        };
+        let notification =
+            Notification::from_alert("trader-1", preferences.channels[0].clone(), &event);
+        assert_eq!(notification.subject, "AAPL alert: breakout");
+        assert!(notification
+            .body
+            .contains("closed at 106 on the bar of 2024-03-04 15:00 UTC"));
+        assert_eq!(notification.channel.kind(), ChannelKind::Email);
+        assert_eq!(notification.payload["event"]["rule_name"], "breakout");
+    }
+}
@@ -82,6 +82,7 @@ pub enum OHLCVError {
 
 impl OHLCV {
     /// Create a new OHLCV with validation
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         symbol: Symbol,
         timeframe: TimeFrame,
@@ -110,7 +111,9 @@ impl OHLCV {
     }
 
     /// Validate price relationships
-    fn validate_prices(&self) -> Result<(), OHLCVError> {
+    ///
+    /// `new` runs this already; bars deserialized or built field by field are unchecked.
+    pub fn validate_prices(&self) -> Result<(), OHLCVError> {
         // Check all prices are positive
         if self.open <= Decimal::ZERO
             || self.high <= Decimal::ZERO
@@ -632,7 +635,7 @@ mod tests {
 
         assert!(ohlcv1 < ohlcv2);
 
-        let mut ohlcv_vec = vec![ohlcv2.clone(), ohlcv1.clone()];
+        let mut ohlcv_vec = [ohlcv2.clone(), ohlcv1.clone()];
         ohlcv_vec.sort();
         assert_eq!(ohlcv_vec[0], ohlcv1);
         assert_eq!(ohlcv_vec[1], ohlcv2);
@@ -0,0 +1,552 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::validation::{validate_non_negative_volume, validate_positive_price};
+use crate::{AssetClass, PositionSide, RiskTolerance, Symbol};
+
+// ============================================================================
+// Trade Ledger Types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// How closing fills are matched against open lots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostBasisMethod {
+    /// First in, first out
+    #[default]
+    Fifo,
+    /// Last in, first out
+    Lifo,
+    /// Single blended lot at the weighted average cost
+    AverageCost,
+}
+
+/// A single execution booked against a portfolio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct Fill {
+    /// Unique fill ID
+    pub fill_id: Uuid,
+
+    /// Portfolio this fill belongs to
+    pub portfolio_id: Uuid,
+
+    /// Instrument that was traded
+    #[validate(nested)]
+    pub symbol: Symbol,
+
+    /// Buy or sell
+    pub side: TradeSide,
+
+    /// Filled quantity (always positive, direction comes from `side`)
+    #[validate(custom(function = "validate_positive_price"))]
+    pub quantity: Decimal,
+
+    /// Execution price per unit
+    #[validate(custom(function = "validate_positive_price"))]
+    pub price: Decimal,
+
+    /// Commissions and fees charged for this fill
+    #[validate(custom(function = "validate_non_negative_volume"))]
+    pub fees: Decimal,
+
+    /// Broker order ID (if any)
+    pub order_id: Option<String>,
+
+    /// When the fill was executed
+    pub executed_at: DateTime<Utc>,
+}
+
+impl Fill {
+    /// Create a fill with no fees
+    pub fn new(
+        portfolio_id: Uuid,
+        symbol: Symbol,
+        side: TradeSide,
+        quantity: Decimal,
+        price: Decimal,
+        executed_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            fill_id: Uuid::new_v4(),
+            portfolio_id,
+            symbol,
+            side,
+            quantity,
+            price,
+            fees: Decimal::ZERO,
+            order_id: None,
+            executed_at,
+        }
+    }
+
+    /// Set the fees charged for this fill
+    pub fn with_fees(mut self, fees: Decimal) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Set the originating order ID
+    pub fn with_order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.order_id = Some(order_id.into());
+        self
+    }
+
+    /// Cash value of the fill before fees (quantity * price * contract size)
+    pub fn notional(&self) -> Decimal {
+        self.quantity * self.price * self.symbol.contract_size
+    }
+
+    /// Quantity with sign applied (+ for buys, - for sells)
+    pub fn signed_quantity(&self) -> Decimal {
+        match self.side {
+            TradeSide::Buy => self.quantity,
+            TradeSide::Sell => -self.quantity,
+        }
+    }
+}
+
+/// A deposit or withdrawal of base-currency cash, kept in the ledger next to fills
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CashFlow {
+    /// Unique cash flow ID
+    pub flow_id: Uuid,
+
+    /// Portfolio the cash moved into or out of
+    pub portfolio_id: Uuid,
+
+    /// Positive for deposits, negative for withdrawals
+    pub amount: Decimal,
+
+    /// When the cash moved
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl CashFlow {
+    pub fn new(portfolio_id: Uuid, amount: Decimal, occurred_at: DateTime<Utc>) -> Self {
+        Self {
+            flow_id: Uuid::new_v4(),
+            portfolio_id,
+            amount,
+            occurred_at,
+        }
+    }
+}
+
+/// An order that has not been sent yet, as seen by pre-trade risk checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct OrderRequest {
+    /// Client order ID (if any)
+    pub order_id: Option<String>,
+
+    /// Portfolio the order trades for
+    pub portfolio_id: Uuid,
+
+    /// Instrument to trade
+    #[validate(nested)]
+    pub symbol: Symbol,
+
+    /// Buy or sell
+    pub side: TradeSide,
+
+    /// Order quantity (always positive, direction comes from `side`)
+    #[validate(custom(function = "validate_positive_price"))]
+    pub quantity: Decimal,
+
+    /// Expected execution price (limit price, or last trade for market orders)
+    #[validate(custom(function = "validate_positive_price"))]
+    pub price: Decimal,
+
+    /// Whether the order may execute in pre- and post-market sessions
+    #[serde(default)]
+    pub extended_hours: bool,
+}
+
+impl OrderRequest {
+    pub fn new(
+        portfolio_id: Uuid,
+        symbol: Symbol,
+        side: TradeSide,
+        quantity: Decimal,
+        price: Decimal,
+    ) -> Self {
+        Self {
+            order_id: None,
+            portfolio_id,
+            symbol,
+            side,
+            quantity,
+            price,
+            extended_hours: false,
+        }
+    }
+
+    /// Allow the order to execute outside the regular session
+    pub fn with_extended_hours(mut self, extended_hours: bool) -> Self {
+        self.extended_hours = extended_hours;
+        self
+    }
+
+    /// Set the client order ID
+    pub fn with_order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.order_id = Some(order_id.into());
+        self
+    }
+
+    /// Quantity with sign applied (+ for buys, - for sells)
+    pub fn signed_quantity(&self) -> Decimal {
+        match self.side {
+            TradeSide::Buy => self.quantity,
+            TradeSide::Sell => -self.quantity,
+        }
+    }
+}
+
+// ============================================================================
+// Risk Limits
+// ============================================================================
+
+/// Pre-trade limits for a single portfolio. `None` disables a check.
+///
+/// Ratios are fractions of portfolio equity (0.25 = 25%).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskLimits {
+    /// Maximum absolute quantity held in any one instrument
+    pub max_position_quantity: Option<Decimal>,
+
+    /// Maximum absolute notional held in any one instrument
+    pub max_position_notional: Option<Decimal>,
+
+    /// Per-instrument notional limits keyed by `Symbol::full_identifier`,
+    /// replacing `max_position_notional` for those instruments
+    #[serde(default)]
+    pub symbol_position_notional: HashMap<String, Decimal>,
+
+    /// Per-asset-class notional limits for a single instrument, replacing
+    /// `max_position_notional` unless the instrument has its own override
+    #[serde(default)]
+    pub asset_class_position_notional: HashMap<AssetClass, Decimal>,
+
+    /// Maximum gross notional per asset class
+    #[serde(default)]
+    pub max_asset_class_notional: HashMap<AssetClass, Decimal>,
+
+    /// Maximum loss since the start of the trading day, as a fraction of opening equity
+    pub max_daily_loss: Option<Decimal>,
+
+    /// Maximum gross exposure divided by equity
+    pub max_leverage: Option<Decimal>,
+
+    /// Maximum share of equity in a single instrument
+    pub max_concentration: Option<Decimal>,
+}
+
+impl RiskLimits {
+    /// Limits that never reject anything
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_position_quantity(mut self, quantity: Decimal) -> Self {
+        self.max_position_quantity = Some(quantity);
+        self
+    }
+
+    pub fn with_max_position_notional(mut self, notional: Decimal) -> Self {
+        self.max_position_notional = Some(notional);
+        self
+    }
+
+    /// Override the single-instrument notional limit for one instrument
+    pub fn with_symbol_position_notional(mut self, symbol: &Symbol, notional: Decimal) -> Self {
+        self.symbol_position_notional
+            .insert(symbol.full_identifier(), notional);
+        self
+    }
+
+    /// Override the single-instrument notional limit for an asset class
+    pub fn with_asset_class_position_notional(
+        mut self,
+        asset_class: AssetClass,
+        notional: Decimal,
+    ) -> Self {
+        self.asset_class_position_notional
+            .insert(asset_class, notional);
+        self
+    }
+
+    /// Notional limit for a position in `symbol`, most specific override first
+    pub fn position_notional_limit(&self, symbol: &Symbol) -> Option<Decimal> {
+        self.symbol_position_notional
+            .get(&symbol.full_identifier())
+            .or_else(|| self.asset_class_position_notional.get(&symbol.asset_class))
+            .copied()
+            .or(self.max_position_notional)
+    }
+
+    pub fn with_max_asset_class_notional(
+        mut self,
+        asset_class: AssetClass,
+        notional: Decimal,
+    ) -> Self {
+        self.max_asset_class_notional.insert(asset_class, notional);
+        self
+    }
+
+    pub fn with_max_daily_loss(mut self, fraction: Decimal) -> Self {
+        self.max_daily_loss = Some(fraction);
+        self
+    }
+
+    pub fn with_max_leverage(mut self, leverage: Decimal) -> Self {
+        self.max_leverage = Some(leverage);
+        self
+    }
+
+    pub fn with_max_concentration(mut self, fraction: Decimal) -> Self {
+        self.max_concentration = Some(fraction);
+        self
+    }
+}
+
+impl From<&RiskTolerance> for RiskLimits {
+    /// Default limit profile for a risk tolerance preset
+    fn from(tolerance: &RiskTolerance) -> Self {
+        let (leverage, concentration, daily_loss) = match tolerance {
+            RiskTolerance::Conservative => (Decimal::ONE, Decimal::new(10, 2), Decimal::new(2, 2)),
+            RiskTolerance::Moderate => {
+                (Decimal::new(15, 1), Decimal::new(20, 2), Decimal::new(5, 2))
+            }
+            RiskTolerance::Aggressive => {
+                (Decimal::new(3, 0), Decimal::new(35, 2), Decimal::new(10, 2))
+            }
+        };
+
+        RiskLimits::unlimited()
+            .with_max_leverage(leverage)
+            .with_max_concentration(concentration)
+            .with_max_daily_loss(daily_loss)
+    }
+}
+
+// ============================================================================
+// Portfolio Snapshots
+// ============================================================================
+
+/// Point-in-time valuation of a portfolio, recorded once per day for performance analysis
+///
+/// All values, including each position's, are in the portfolio's base currency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    /// Portfolio this snapshot belongs to
+    pub portfolio_id: Uuid,
+
+    /// Valuation time
+    pub as_of: DateTime<Utc>,
+
+    /// Cash plus signed market value of all positions
+    pub total_value: Decimal,
+
+    /// Cash balance
+    pub cash_balance: Decimal,
+
+    /// Cumulative deposits minus withdrawals at this point
+    pub net_contributions: Decimal,
+
+    /// Every instrument the portfolio has traded, including closed ones
+    pub positions: Vec<PositionSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub symbol: Symbol,
+
+    /// Direction of the open quantity (None when flat)
+    pub side: Option<PositionSide>,
+
+    /// Open quantity (always non-negative)
+    pub quantity: Decimal,
+
+    /// Signed market value: positive for longs, negative for shorts
+    pub market_value: Decimal,
+
+    pub unrealized_pnl: Decimal,
+    pub realized_pnl: Decimal,
+
+    /// Currency P&L on the position's cost, realized and open
+    #[serde(default)]
+    pub fx_pnl: Decimal,
+}
+
+impl PortfolioSnapshot {
+    /// Total P&L of a position (realized + unrealized + FX), zero if never traded
+    pub fn position_pnl(&self, symbol: &Symbol) -> Decimal {
+        let key = symbol.full_identifier();
+        self.positions
+            .iter()
+            .find(|position| position.symbol.full_identifier() == key)
+            .map(|position| position.realized_pnl + position.unrealized_pnl + position.fx_pnl)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for TradeSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeSide::Buy => write!(f, "buy"),
+            TradeSide::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+impl FromStr for TradeSide {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "buy" => Ok(TradeSide::Buy),
+            "sell" => Ok(TradeSide::Sell),
+            _ => Err(format!("Invalid trade side: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for CostBasisMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CostBasisMethod::Fifo => write!(f, "fifo"),
+            CostBasisMethod::Lifo => write!(f, "lifo"),
+            CostBasisMethod::AverageCost => write!(f, "average_cost"),
+        }
+    }
+}
+
+impl FromStr for CostBasisMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fifo" => Ok(CostBasisMethod::Fifo),
+            "lifo" => Ok(CostBasisMethod::Lifo),
+            "average_cost" | "average" => Ok(CostBasisMethod::AverageCost),
+            _ => Err(format!("Invalid cost basis method: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Exchange;
+
+    #[test]
+    fn test_fill_notional_and_sign() {
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let fill = Fill::new(
+            Uuid::new_v4(),
+            symbol,
+            TradeSide::Sell,
+            Decimal::new(10, 0),
+            Decimal::new(15050, 2),
+            Utc::now(),
+        )
+        .with_fees(Decimal::ONE);
+
+        assert_eq!(fill.notional(), Decimal::new(1505, 0));
+        assert_eq!(fill.signed_quantity(), Decimal::new(-10, 0));
+        assert!(fill.validate().is_ok());
+    }
+
+    #[test]
+    fn test_fill_rejects_non_positive_quantity() {
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let fill = Fill::new(
+            Uuid::new_v4(),
+            symbol,
+            TradeSide::Buy,
+            Decimal::ZERO,
+            Decimal::new(100, 0),
+            Utc::now(),
+        );
+
+        assert!(fill.validate().is_err());
+    }
+
+    #[test]
+    fn test_enum_round_trip() {
+        for method in [
+            CostBasisMethod::Fifo,
+            CostBasisMethod::Lifo,
+            CostBasisMethod::AverageCost,
+        ] {
+            assert_eq!(
+                CostBasisMethod::from_str(&method.to_string()).unwrap(),
+                method
+            );
+        }
+        assert_eq!(TradeSide::from_str("SELL").unwrap(), TradeSide::Sell);
+        assert!(TradeSide::from_str("hold").is_err());
+    }
+
+    #[test]
+    fn test_risk_tolerance_presets_tighten_with_caution() {
+        let conservative = RiskLimits::from(&RiskTolerance::Conservative);
+        let aggressive = RiskLimits::from(&RiskTolerance::Aggressive);
+
+        assert!(conservative.max_leverage < aggressive.max_leverage);
+        assert!(conservative.max_concentration < aggressive.max_concentration);
+        assert!(conservative.max_daily_loss < aggressive.max_daily_loss);
+        assert_eq!(RiskLimits::unlimited().max_leverage, None);
+
+        let limits = RiskLimits::unlimited()
+            .with_max_asset_class_notional(AssetClass::Crypto, Decimal::new(5_000, 0));
+        let json = serde_json::to_string(&limits).unwrap();
+        assert_eq!(serde_json::from_str::<RiskLimits>(&json).unwrap(), limits);
+    }
+
+    #[test]
+    fn test_position_notional_overrides() {
+        let aapl = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let msft = Symbol::stock("MSFT", "Microsoft", Exchange::NASDAQ).unwrap();
+        let btc = Symbol::crypto("BTC", "USD", Exchange::Binance).unwrap();
+
+        let limits = RiskLimits::unlimited()
+            .with_max_position_notional(Decimal::new(10_000, 0))
+            .with_asset_class_position_notional(AssetClass::Stock, Decimal::new(20_000, 0))
+            .with_symbol_position_notional(&aapl, Decimal::new(50_000, 0));
+        assert_eq!(
+            limits.position_notional_limit(&aapl),
+            Some(Decimal::new(50_000, 0))
+        );
+        assert_eq!(
+            limits.position_notional_limit(&msft),
+            Some(Decimal::new(20_000, 0))
+        );
+        assert_eq!(
+            limits.position_notional_limit(&btc),
+            Some(Decimal::new(10_000, 0))
+        );
+        assert_eq!(RiskLimits::unlimited().position_notional_limit(&aapl), None);
+
+        // Limits stored before the overrides existed still load
+        let json = r#"{"max_position_quantity":null,"max_position_notional":"5","max_daily_loss":null,"max_leverage":null,"max_concentration":null}"#;
+        let loaded: RiskLimits = serde_json::from_str(json).unwrap();
+        assert!(loaded.symbol_position_notional.is_empty());
+        assert_eq!(
+            loaded.position_notional_limit(&btc),
+            Some(Decimal::new(5, 0))
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{AssetClass, Exchange, PaginationInfo, Symbol, TimeFrame};
+
+/// Longest saved screen name accepted
+pub const MAX_SCREEN_NAME: usize = 100;
+
+/// Rows per page when a request does not say
+pub const DEFAULT_SCREEN_PAGE_SIZE: u32 = 50;
+
+/// Most rows returned on one page
+pub const MAX_SCREEN_PAGE_SIZE: u32 = 500;
+
+/// Columns every row has, which sorts can name alongside the screen's own
+pub const BUILT_IN_COLUMNS: [&str; 3] = ["symbol", "close", "volume"];
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScreenCriteriaError {
+    #[error("Screen names must be 1 to {MAX_SCREEN_NAME} characters")]
+    InvalidName,
+
+    #[error("Column names must be unique and not reuse a built-in column: {0}")]
+    DuplicateColumn(String),
+
+    #[error("Cannot sort by {0}, which is not a column of the screen")]
+    UnknownSortColumn(String),
+}
+
+/// Which symbols of the symbol master a screen runs over
+///
+/// Every non-empty list must match; empty lists match everything. Inactive
+/// symbols are never screened.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScreenUniverse {
+    #[serde(default)]
+    pub exchanges: Vec<Exchange>,
+
+    #[serde(default)]
+    pub asset_classes: Vec<AssetClass>,
+
+    /// Matched case-insensitively
+    #[serde(default)]
+    pub sectors: Vec<String>,
+}
+
+impl ScreenUniverse {
+    pub fn includes(&self, symbol: &Symbol) -> bool {
+        symbol.is_active
+            && (self.exchanges.is_empty() || self.exchanges.contains(&symbol.exchange))
+            && (self.asset_classes.is_empty() || self.asset_classes.contains(&symbol.asset_class))
+            && (self.sectors.is_empty()
+                || symbol.sector.as_deref().is_some_and(|sector| {
+                    self.sectors
+                        .iter()
+                        .any(|wanted| wanted.eq_ignore_ascii_case(sector))
+                }))
+    }
+}
+
+/// A named value computed for every matching symbol, e.g. `volume / avg_volume(20)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenColumn {
+    pub name: String,
+
+    /// Numeric formula in the alert expression language
+    pub formula: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenSort {
+    /// One of the screen's columns or a [`BUILT_IN_COLUMNS`] entry
+    pub column: String,
+
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// What to screen for and how to present the matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenCriteria {
+    #[serde(default)]
+    pub universe: ScreenUniverse,
+
+    #[serde(default = "default_timeframe")]
+    pub timeframe: TimeFrame,
+
+    /// Condition in the alert expression language, evaluated on each symbol's latest bar
+    pub filter: String,
+
+    #[serde(default)]
+    pub columns: Vec<ScreenColumn>,
+
+    /// By symbol when absent
+    #[serde(default)]
+    pub sort: Option<ScreenSort>,
+}
+
+fn default_timeframe() -> TimeFrame {
+    TimeFrame::OneDay
+}
+
+impl ScreenCriteria {
+    pub fn new(filter: impl Into<String>) -> Self {
+        Self {
+            universe: ScreenUniverse::default(),
+            timeframe: default_timeframe(),
+            filter: filter.into(),
+            columns: Vec::new(),
+            sort: None,
+        }
+    }
+
+    pub fn with_universe(mut self, universe: ScreenUniverse) -> Self {
+        self.universe = universe;
+        self
+    }
+
+    pub fn with_timeframe(mut self, timeframe: TimeFrame) -> Self {
+        self.timeframe = timeframe;
+        self
+    }
+
+    pub fn with_column(mut self, name: impl Into<String>, formula: impl Into<String>) -> Self {
+        self.columns.push(ScreenColumn {
+            name: name.into(),
+            formula: formula.into(),
+        });
+        self
+    }
+
+    pub fn with_sort(mut self, column: impl Into<String>, descending: bool) -> Self {
+        self.sort = Some(ScreenSort {
+            column: column.into(),
+            descending,
+        });
+        self
+    }
+
+    /// Check the column names and sort; the expressions are checked when compiled
+    pub fn validate(&self) -> Result<(), ScreenCriteriaError> {
+        for (index, column) in self.columns.iter().enumerate() {
+            if BUILT_IN_COLUMNS.contains(&column.name.as_str())
+                || self.columns[..index]
+                    .iter()
+                    .any(|earlier| earlier.name == column.name)
+            {
+                return Err(ScreenCriteriaError::DuplicateColumn(column.name.clone()));
+            }
+        }
+        if let Some(sort) = &self.sort {
+            if !BUILT_IN_COLUMNS.contains(&sort.column.as_str())
+                && !self.columns.iter().any(|column| column.name == sort.column)
+            {
+                return Err(ScreenCriteriaError::UnknownSortColumn(sort.column.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Criteria stored under a name to be run again later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedScreen {
+    pub id: Uuid,
+    pub name: String,
+    pub criteria: ScreenCriteria,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SavedScreen {
+    pub fn new(
+        name: impl Into<String>,
+        criteria: ScreenCriteria,
+    ) -> Result<Self, ScreenCriteriaError> {
+        let name = name.into().trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_SCREEN_NAME {
+            return Err(ScreenCriteriaError::InvalidName);
+        }
+        criteria.validate()?;
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name,
+            criteria,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+/// One symbol that passed a screen, as of its latest bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenRow {
+    pub symbol: Symbol,
+
+    /// Start of the bar the filter held on
+    pub timestamp: DateTime<Utc>,
+
+    pub close: Decimal,
+    pub volume: Decimal,
+
+    /// Each screen column by name; `None` while its indicators warm up
+    pub values: BTreeMap<String, Option<f64>>,
+}
+
+/// One page of a screen's matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenPage {
+    pub rows: Vec<ScreenRow>,
+    pub pagination: PaginationInfo,
+
+    /// Symbols in the universe
+    pub screened: usize,
+
+    /// Symbols without enough bars for the filter to have a value
+    pub insufficient_data: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_criteria_validation_and_universe() {
+        let criteria = ScreenCriteria::new("rsi(14) < 30")
+            .with_column("rsi", "rsi(14)")
+            .with_sort("rsi", false);
+        assert!(criteria.validate().is_ok());
+        assert_eq!(
+            criteria.clone().with_column("close", "close").validate(),
+            Err(ScreenCriteriaError::DuplicateColumn("close".to_string()))
+        );
+        assert_eq!(
+            criteria.clone().with_sort("atr", true).validate(),
+            Err(ScreenCriteriaError::UnknownSortColumn("atr".to_string()))
+        );
+        assert_eq!(
+            SavedScreen::new(" ", criteria.clone()),
+            Err(ScreenCriteriaError::InvalidName)
+        );
+
+        let parsed: ScreenCriteria =
+            serde_json::from_value(serde_json::json!({"filter": "close > sma(50)"})).unwrap();
+        assert_eq!(parsed, ScreenCriteria::new("close > sma(50)"));
+
+        let universe = ScreenUniverse {
+            exchanges: vec![Exchange::NASDAQ],
+            sectors: vec!["technology".to_string()],
+            ..ScreenUniverse::default()
+        };
+        let mut apple = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        assert!(!universe.includes(&apple));
+        apple.sector = Some("Technology".to_string());
+        assert!(universe.includes(&apple));
+        apple.is_active = false;
+        assert!(!universe.includes(&apple));
+    }
+}
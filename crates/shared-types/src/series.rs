@@ -0,0 +1,335 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{OHLCVError, Symbol, TimeFrame, OHLCV};
+
+/// Bars of one symbol and timeframe stored column by column
+///
+/// Holds the symbol once instead of per bar, and keeps each price column
+/// contiguous so analytics can work on slices. Converting to and from
+/// `Vec<OHLCV>` is lossless, including bar metadata. With the `arrow` feature
+/// the series can also be exported as an Arrow [`RecordBatch`](arrow::array::RecordBatch).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhlcvSeries {
+    symbol: Symbol,
+    timeframe: TimeFrame,
+    timestamps: Vec<DateTime<Utc>>,
+    open: Vec<Decimal>,
+    high: Vec<Decimal>,
+    low: Vec<Decimal>,
+    close: Vec<Decimal>,
+    volume: Vec<Decimal>,
+
+    /// Metadata of the bars that have any, by row
+    metadata: BTreeMap<usize, HashMap<String, serde_json::Value>>,
+}
+
+impl OhlcvSeries {
+    pub fn new(symbol: Symbol, timeframe: TimeFrame) -> Self {
+        Self::with_capacity(symbol, timeframe, 0)
+    }
+
+    pub fn with_capacity(symbol: Symbol, timeframe: TimeFrame, capacity: usize) -> Self {
+        Self {
+            symbol,
+            timeframe,
+            timestamps: Vec::with_capacity(capacity),
+            open: Vec::with_capacity(capacity),
+            high: Vec::with_capacity(capacity),
+            low: Vec::with_capacity(capacity),
+            close: Vec::with_capacity(capacity),
+            volume: Vec::with_capacity(capacity),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Build a series from bars, which must all share `symbol` and `timeframe`
+    pub fn from_bars(
+        symbol: Symbol,
+        timeframe: TimeFrame,
+        bars: impl IntoIterator<Item = OHLCV>,
+    ) -> Result<Self, OHLCVError> {
+        let bars = bars.into_iter();
+        let mut series = Self::with_capacity(symbol, timeframe, bars.size_hint().0);
+        for bar in bars {
+            series.push(bar)?;
+        }
+        Ok(series)
+    }
+
+    /// Append a bar, rejecting bars of another symbol or timeframe
+    pub fn push(&mut self, bar: OHLCV) -> Result<(), OHLCVError> {
+        if bar.symbol.full_identifier() != self.symbol.full_identifier() {
+            return Err(OHLCVError::DataConsistency(format!(
+                "Bar for {} does not belong to series {}",
+                bar.symbol.full_identifier(),
+                self.symbol.full_identifier()
+            )));
+        }
+        if bar.timeframe != self.timeframe {
+            return Err(OHLCVError::DataConsistency(format!(
+                "Bar timeframe {} does not match series timeframe {}",
+                bar.timeframe, self.timeframe
+            )));
+        }
+
+        if !bar.metadata.is_empty() {
+            self.metadata.insert(self.len(), bar.metadata);
+        }
+        self.timestamps.push(bar.timestamp);
+        self.open.push(bar.open);
+        self.high.push(bar.high);
+        self.low.push(bar.low);
+        self.close.push(bar.close);
+        self.volume.push(bar.volume);
+        Ok(())
+    }
+
+    pub fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    pub fn timeframe(&self) -> &TimeFrame {
+        &self.timeframe
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    pub fn timestamps(&self) -> &[DateTime<Utc>] {
+        &self.timestamps
+    }
+
+    pub fn open(&self) -> &[Decimal] {
+        &self.open
+    }
+
+    pub fn high(&self) -> &[Decimal] {
+        &self.high
+    }
+
+    pub fn low(&self) -> &[Decimal] {
+        &self.low
+    }
+
+    pub fn close(&self) -> &[Decimal] {
+        &self.close
+    }
+
+    pub fn volume(&self) -> &[Decimal] {
+        &self.volume
+    }
+
+    /// Rebuild the bar at `index`
+    pub fn get(&self, index: usize) -> Option<OHLCV> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(OHLCV {
+            symbol: self.symbol.clone(),
+            timeframe: self.timeframe.clone(),
+            timestamp: self.timestamps[index],
+            open: self.open[index],
+            high: self.high[index],
+            low: self.low[index],
+            close: self.close[index],
+            volume: self.volume[index],
+            metadata: self.metadata.get(&index).cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Rebuild every bar, in series order
+    pub fn iter(&self) -> impl Iterator<Item = OHLCV> + '_ {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+
+    pub fn into_bars(self) -> Vec<OHLCV> {
+        self.iter().collect()
+    }
+}
+
+impl TryFrom<Vec<OHLCV>> for OhlcvSeries {
+    type Error = OHLCVError;
+
+    /// Takes the symbol and timeframe from the first bar; an empty `Vec` is an error
+    fn try_from(bars: Vec<OHLCV>) -> Result<Self, Self::Error> {
+        let first = bars.first().ok_or_else(|| OHLCVError::MissingData {
+            field: "bars".to_string(),
+        })?;
+        let (symbol, timeframe) = (first.symbol.clone(), first.timeframe.clone());
+        Self::from_bars(symbol, timeframe, bars)
+    }
+}
+
+impl From<OhlcvSeries> for Vec<OHLCV> {
+    fn from(series: OhlcvSeries) -> Self {
+        series.into_bars()
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod columnar {
+    use super::OhlcvSeries;
+    use crate::{Symbol, TimeFrame};
+    use arrow::array::{ArrayRef, Float64Array, RecordBatch, TimestampNanosecondArray};
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use arrow::error::ArrowError;
+    use rust_decimal::prelude::ToPrimitive;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    const PRICE_COLUMNS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+    impl OhlcvSeries {
+        /// Arrow schema of [`OhlcvSeries::to_record_batch`]
+        ///
+        /// A non-null UTC nanosecond `timestamp` followed by float64 `open`,
+        /// `high`, `low`, `close` and `volume`. The symbol and timeframe are
+        /// recorded in the schema metadata.
+        pub fn arrow_schema(symbol: &Symbol, timeframe: &TimeFrame) -> Schema {
+            let mut fields = vec![Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+                false,
+            )];
+            fields.extend(
+                PRICE_COLUMNS
+                    .iter()
+                    .map(|name| Field::new(*name, DataType::Float64, false)),
+            );
+
+            Schema::new(fields).with_metadata(HashMap::from([
+                ("symbol".to_string(), symbol.full_identifier()),
+                ("timeframe".to_string(), timeframe.to_string()),
+            ]))
+        }
+
+        /// Export as an Arrow batch with float64 prices; bar metadata is not included
+        ///
+        /// The price columns are contiguous non-null `Float64Array`s, so their
+        /// `values()` can be handed to slice-based indicators without copying.
+        pub fn to_record_batch(&self) -> Result<RecordBatch, ArrowError> {
+            let timestamps = self
+                .timestamps
+                .iter()
+                .map(|timestamp| {
+                    timestamp.timestamp_nanos_opt().ok_or_else(|| {
+                        ArrowError::ComputeError(format!(
+                            "Timestamp {} is outside the nanosecond range",
+                            timestamp
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<i64>, _>>()?;
+            let float = |column: &[Decimal]| -> ArrayRef {
+                Arc::new(Float64Array::from_iter_values(
+                    column
+                        .iter()
+                        .map(|value| value.to_f64().unwrap_or(f64::NAN)),
+                ))
+            };
+
+            RecordBatch::try_new(
+                Arc::new(Self::arrow_schema(&self.symbol, &self.timeframe)),
+                vec![
+                    Arc::new(TimestampNanosecondArray::from(timestamps).with_timezone("UTC")),
+                    float(&self.open),
+                    float(&self.high),
+                    float(&self.low),
+                    float(&self.close),
+                    float(&self.volume),
+                ],
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Exchange;
+    use chrono::TimeZone;
+
+    fn bar(symbol: &Symbol, minute: u32, close: i64) -> OHLCV {
+        let close = Decimal::new(close, 2);
+        OHLCV::new(
+            symbol.clone(),
+            TimeFrame::OneMinute,
+            Utc.with_ymd_and_hms(2024, 1, 2, 14, minute, 0).unwrap(),
+            close,
+            close,
+            close,
+            close,
+            Decimal::new(1_000, 0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trips_bars_with_metadata() {
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let mut tagged = bar(&symbol, 1, 18512);
+        tagged.add_metadata("provider", serde_json::json!("alpha_vantage"));
+        let bars = vec![bar(&symbol, 0, 18500), tagged, bar(&symbol, 2, 18498)];
+
+        let series = OhlcvSeries::try_from(bars.clone()).unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series.close()[1], Decimal::new(18512, 2));
+        assert_eq!(
+            series.get(1).unwrap().get_metadata("provider"),
+            Some(&serde_json::json!("alpha_vantage"))
+        );
+        assert_eq!(Vec::<OHLCV>::from(series), bars);
+    }
+
+    #[test]
+    fn test_rejects_foreign_bars() {
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let other = Symbol::stock("MSFT", "Microsoft", Exchange::NASDAQ).unwrap();
+        let mut series = OhlcvSeries::new(symbol.clone(), TimeFrame::OneMinute);
+
+        assert!(matches!(
+            series.push(bar(&other, 0, 40000)),
+            Err(OHLCVError::DataConsistency(_))
+        ));
+        let mut hourly = bar(&symbol, 0, 18500);
+        hourly.timeframe = TimeFrame::OneHour;
+        assert!(series.push(hourly).is_err());
+        assert!(series.is_empty());
+        assert!(OhlcvSeries::try_from(Vec::new()).is_err());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_exports_record_batch() {
+        use arrow::array::{Array, Float64Array};
+
+        let symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        let series = OhlcvSeries::from_bars(
+            symbol.clone(),
+            TimeFrame::OneMinute,
+            [bar(&symbol, 0, 18550)],
+        )
+        .unwrap();
+
+        let batch = series.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.schema().metadata()["timeframe"], "1m");
+        let close = batch
+            .column_by_name("close")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(close.value(0), 185.5);
+        assert_eq!(close.null_count(), 0);
+    }
+}
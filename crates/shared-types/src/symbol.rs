@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
@@ -5,6 +6,8 @@ use std::str::FromStr;
 use thiserror::Error;
 use validator::Validate;
 
+use crate::{DerivativeSpec, MarketDataError, TradingCalendar};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AssetClass {
     #[serde(rename = "stock")]
@@ -84,10 +87,13 @@ pub enum MarketStatus {
     Suspended,
 }
 
+/// Longest symbol code accepted, so unpadded OCC option symbols with six-letter roots fit
+pub const MAX_SYMBOL_CODE: u64 = 21;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 pub struct Symbol {
-    /// Primary symbol code (e.g., "AAPL", "BTC-USD", "EUR/USD")
-    #[validate(length(min = 1, max = 20))]
+    /// Primary symbol code (e.g., "AAPL", "BTC-USD", "EUR/USD", "ESZ26")
+    #[validate(length(min = 1, max = MAX_SYMBOL_CODE))]
     pub code: String,
 
     /// Human-readable display name
@@ -100,8 +106,8 @@ pub struct Symbol {
     /// Exchange where this symbol is traded
     pub exchange: Exchange,
 
-    /// Base currency (e.g., "USD", "EUR")
-    #[validate(length(equal = 3))]
+    /// Base currency (e.g., "USD", "EUR"); crypto quote assets may be longer ("USDT")
+    #[validate(length(min = 3, max = 5))]
     pub currency: String,
 
     /// Quote currency for forex pairs (e.g., "USD" in "EUR/USD")
@@ -130,6 +136,10 @@ pub struct Symbol {
 
     /// Additional flexible metadata
     pub metadata: HashMap<String, String>,
+
+    /// Contract terms for options and futures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivative: Option<DerivativeSpec>,
 }
 
 #[derive(Error, Debug)]
@@ -174,6 +184,7 @@ impl Symbol {
             sector: None,
             industry: None,
             metadata: HashMap::new(),
+            derivative: None,
         };
 
         symbol
@@ -233,16 +244,17 @@ impl Symbol {
 
     /// Check if symbol is valid for the given exchange
     pub fn is_valid_for_exchange(&self) -> bool {
-        match (&self.asset_class, &self.exchange) {
-            (AssetClass::Stock, Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX) => true,
+        matches!(
+            (&self.asset_class, &self.exchange),
             (
+                AssetClass::Stock,
+                Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX
+            ) | (
                 AssetClass::Crypto,
                 Exchange::Binance | Exchange::Coinbase | Exchange::Kraken | Exchange::Bitfinex,
-            ) => true,
-            (AssetClass::Forex, Exchange::Forex) => true,
-            (AssetClass::Commodity, Exchange::COMEX | Exchange::NYMEX) => true,
-            _ => false,
-        }
+            ) | (AssetClass::Forex, Exchange::Forex)
+                | (AssetClass::Commodity, Exchange::COMEX | Exchange::NYMEX)
+        )
     }
 
     /// Get the full symbol identifier (code@exchange)
@@ -274,6 +286,44 @@ impl Symbol {
             )
     }
 
+    /// Market status at an instant from the exchange's trading calendar
+    ///
+    /// Suspended or inactive symbols stay `Suspended`; exchanges without a
+    /// calendar fall back to the stored `market_status`.
+    pub fn market_status_at(&self, at: DateTime<Utc>) -> MarketStatus {
+        if !self.is_active || self.market_status == MarketStatus::Suspended {
+            return MarketStatus::Suspended;
+        }
+        match TradingCalendar::for_exchange(&self.exchange) {
+            Some(calendar) => calendar.status_at(at),
+            None => self.market_status.clone(),
+        }
+    }
+
+    /// Check if symbol is tradeable at an instant, optionally in pre- and post-market sessions
+    pub fn is_tradeable_at(&self, at: DateTime<Utc>, include_extended_hours: bool) -> bool {
+        match self.market_status_at(at) {
+            MarketStatus::Open => true,
+            MarketStatus::PreMarket | MarketStatus::AfterMarket => include_extended_hours,
+            MarketStatus::Closed | MarketStatus::Suspended => false,
+        }
+    }
+
+    /// Fail with `MarketClosed` unless the symbol is tradeable at the instant
+    pub fn ensure_market_open(
+        &self,
+        at: DateTime<Utc>,
+        include_extended_hours: bool,
+    ) -> Result<(), MarketDataError> {
+        if self.is_tradeable_at(at, include_extended_hours) {
+            Ok(())
+        } else {
+            Err(MarketDataError::MarketClosed {
+                symbol: self.full_identifier(),
+            })
+        }
+    }
+
     /// Get display string for UI
     pub fn display(&self) -> String {
         format!("{} ({})", self.display_name, self.code)
@@ -511,4 +561,25 @@ mod tests {
 
         assert_eq!(symbol, deserialized);
     }
+
+    #[test]
+    fn test_market_status_from_calendar() {
+        use chrono::TimeZone;
+
+        let mut symbol = Symbol::stock("AAPL", "Apple Inc.", Exchange::NASDAQ).unwrap();
+        // 08:00 New York on a Monday is pre-market
+        let early = Utc.with_ymd_and_hms(2024, 5, 13, 12, 0, 0).unwrap();
+
+        assert_eq!(symbol.market_status_at(early), MarketStatus::PreMarket);
+        assert!(symbol.is_tradeable_at(early, true));
+        assert_eq!(
+            symbol.ensure_market_open(early, false),
+            Err(MarketDataError::MarketClosed {
+                symbol: symbol.full_identifier()
+            })
+        );
+
+        symbol.set_market_status(MarketStatus::Suspended);
+        assert_eq!(symbol.market_status_at(early), MarketStatus::Suspended);
+    }
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration as ChronoDuration, Months, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
@@ -78,6 +79,41 @@ impl TimeFrame {
         Duration::from_secs(self.to_seconds())
     }
 
+    /// Start of the bar `bars` bars after the one starting at `at`
+    ///
+    /// Monthly timeframes step by calendar months rather than the 30 days of
+    /// [`TimeFrame::to_seconds`], so a bar on the 1st is followed by one on
+    /// the next 1st. A negative `bars` steps back.
+    pub fn advance(&self, at: DateTime<Utc>, bars: i32) -> DateTime<Utc> {
+        let months = match self {
+            TimeFrame::OneMonth => Some(1),
+            TimeFrame::Custom {
+                value,
+                unit: TimeUnit::Months,
+            } => Some(*value),
+            _ => None,
+        };
+        let limit = if bars < 0 {
+            DateTime::<Utc>::MIN_UTC
+        } else {
+            DateTime::<Utc>::MAX_UTC
+        };
+        let stepped = match months {
+            Some(months) => {
+                let months = Months::new(months.saturating_mul(bars.unsigned_abs()));
+                if bars < 0 {
+                    at.checked_sub_months(months)
+                } else {
+                    at.checked_add_months(months)
+                }
+            }
+            None => ChronoDuration::try_seconds(self.to_seconds().max(1) as i64)
+                .and_then(|step| step.checked_mul(bars))
+                .and_then(|span| at.checked_add_signed(span)),
+        };
+        stepped.unwrap_or(limit)
+    }
+
     /// Check if this is a standard predefined timeframe
     pub fn is_standard(&self) -> bool {
         !matches!(self, TimeFrame::Custom { .. })
@@ -132,16 +168,16 @@ impl FromStr for TimeFrame {
             return Err(TimeFrameError::InvalidFormat(s.to_string()));
         }
 
-        let (number_part, unit_part) = if s.ends_with('m') && s != "1m" {
-            (&s[..s.len() - 1], "m")
-        } else if s.ends_with('h') {
-            (&s[..s.len() - 1], "h")
-        } else if s.ends_with('d') {
-            (&s[..s.len() - 1], "d")
-        } else if s.ends_with('w') {
-            (&s[..s.len() - 1], "w")
-        } else if s.ends_with('M') {
-            (&s[..s.len() - 1], "M")
+        let (number_part, unit_part) = if let Some(n) = s.strip_suffix('m') {
+            (n, "m")
+        } else if let Some(n) = s.strip_suffix('h') {
+            (n, "h")
+        } else if let Some(n) = s.strip_suffix('d') {
+            (n, "d")
+        } else if let Some(n) = s.strip_suffix('w') {
+            (n, "w")
+        } else if let Some(n) = s.strip_suffix('M') {
+            (n, "M")
         } else {
             return Err(TimeFrameError::InvalidFormat(s.to_string()));
         };
@@ -229,6 +265,7 @@ impl<'de> Deserialize<'de> for TimeFrame {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_standard_timeframes() {
@@ -247,6 +284,35 @@ mod tests {
         assert_eq!(tf.to_seconds(), 259200);
     }
 
+    #[test]
+    fn test_advance_by_calendar_months() {
+        let at = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            TimeFrame::OneMonth.advance(at(2024, 1, 1), 1),
+            at(2024, 2, 1)
+        );
+        assert_eq!(
+            TimeFrame::OneMonth.advance(at(2024, 2, 1), 1),
+            at(2024, 3, 1)
+        );
+        assert_eq!(
+            TimeFrame::OneMonth.advance(at(2024, 3, 1), -3),
+            at(2023, 12, 1)
+        );
+        let quarter = TimeFrame::custom(3, TimeUnit::Months).unwrap();
+        assert_eq!(quarter.advance(at(2024, 1, 1), 2), at(2024, 7, 1));
+
+        assert_eq!(
+            TimeFrame::OneDay.advance(at(2024, 2, 28), 2),
+            at(2024, 3, 1)
+        );
+        assert_eq!(
+            TimeFrame::OneWeek.advance(at(2024, 1, 8), -1),
+            at(2024, 1, 1)
+        );
+    }
+
     #[test]
     fn test_from_str_standard() {
         assert_eq!(TimeFrame::from_str("1m").unwrap(), TimeFrame::OneMinute);
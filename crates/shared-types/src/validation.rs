@@ -1,4 +1,4 @@
-use crate::Symbol;
+use crate::{Symbol, DEFAULT_BASE_CURRENCY, MAX_SYMBOL_CODE};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -6,13 +6,13 @@ use validator::{Validate, ValidationError};
 
 // Custom validation functions for trading data
 pub fn validate_symbol(symbol: &str) -> Result<(), ValidationError> {
-    if symbol.is_empty() || symbol.len() > 20 {
+    if symbol.is_empty() || symbol.len() as u64 > MAX_SYMBOL_CODE {
         return Err(ValidationError::new("invalid_symbol_length"));
     }
 
     if !symbol
         .chars()
-        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '/'))
     {
         return Err(ValidationError::new("invalid_symbol_format"));
     }
@@ -65,7 +65,11 @@ pub struct PatternMatch {
 
 // Portfolio and Position types
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+fn default_base_currency() -> String {
+    DEFAULT_BASE_CURRENCY.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 pub struct Position {
     #[validate(nested)]
     pub symbol: Symbol,
@@ -85,18 +89,22 @@ pub struct Position {
     pub realized_pnl: Decimal,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PositionSide {
     Long,
     Short,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 pub struct Portfolio {
     #[validate(length(min = 1, max = 100))]
     pub name: String,
 
+    /// Currency the cash balance, total value and total P&L are reported in
+    #[serde(default = "default_base_currency")]
+    pub base_currency: String,
+
     pub positions: Vec<Position>,
     pub cash_balance: Decimal,
     pub total_value: Decimal,
@@ -126,6 +134,16 @@ mod tests {
         // Invalid symbol - too long
         let long_symbol = Symbol::forex("VERYLONGSYMBOLNAME123", "USDGBP");
         assert!(long_symbol.is_err());
+
+        // Canonical forex pairs are slash-separated
+        assert!(validate_symbol("EUR/USD").is_ok());
+        assert!(validate_symbol("BTC_USD").is_err());
+
+        // Agrees with `Symbol` on the longest code
+        let occ = "SPXWLL250117P05900000";
+        assert_eq!(occ.len() as u64, MAX_SYMBOL_CODE);
+        assert!(validate_symbol(occ).is_ok());
+        assert!(validate_symbol(&format!("{}X", occ)).is_err());
     }
 
     #[test]
@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{SubscriptionRequest, SubscriptionType, Symbol, TimeFrame};
+
+/// Longest watchlist name accepted
+pub const MAX_WATCHLIST_NAME: usize = 100;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum WatchlistError {
+    #[error("Watchlist names must be 1 to {MAX_WATCHLIST_NAME} characters")]
+    InvalidName,
+
+    #[error("{0} is already on the watchlist")]
+    DuplicateMember(String),
+
+    #[error("{0} is not on the watchlist")]
+    NotAMember(String),
+}
+
+/// A symbol on a watchlist with the user's tags and notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistMember {
+    pub symbol: Symbol,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub notes: Option<String>,
+
+    pub added_at: DateTime<Utc>,
+}
+
+impl WatchlistMember {
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            tags: Vec::new(),
+            notes: None,
+            added_at: Utc::now(),
+        }
+    }
+
+    /// Tags are trimmed, and blank or repeated ones dropped
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = clean_tags(tags);
+        self
+    }
+
+    pub fn with_notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+
+    /// Key members are addressed by, `CODE@EXCHANGE`
+    pub fn key(&self) -> String {
+        self.symbol.full_identifier()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+}
+
+fn clean_tags<I, S>(tags: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.into().trim().to_string();
+        if !tag.is_empty() && !cleaned.iter().any(|seen| seen.eq_ignore_ascii_case(&tag)) {
+            cleaned.push(tag);
+        }
+    }
+    cleaned
+}
+
+/// A named, ordered list of symbols to follow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub id: Uuid,
+    pub name: String,
+
+    /// In the user's chosen order
+    pub members: Vec<WatchlistMember>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Watchlist {
+    pub fn new(name: impl Into<String>) -> Result<Self, WatchlistError> {
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name: valid_name(name.into())?,
+            members: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn rename(&mut self, name: impl Into<String>) -> Result<(), WatchlistError> {
+        self.name = valid_name(name.into())?;
+        self.touch();
+        Ok(())
+    }
+
+    pub fn member(&self, key: &str) -> Option<&WatchlistMember> {
+        self.members.iter().find(|member| member.key() == key)
+    }
+
+    pub fn contains(&self, symbol: &Symbol) -> bool {
+        self.member(&symbol.full_identifier()).is_some()
+    }
+
+    /// Append a member at the end of the list
+    pub fn add(&mut self, member: WatchlistMember) -> Result<(), WatchlistError> {
+        if self.contains(&member.symbol) {
+            return Err(WatchlistError::DuplicateMember(member.key()));
+        }
+        self.members.push(member);
+        self.touch();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Result<WatchlistMember, WatchlistError> {
+        let index = self.position(key)?;
+        self.touch();
+        Ok(self.members.remove(index))
+    }
+
+    /// Move a member to `position`, counting from 0; positions past the end move it last
+    pub fn move_member(&mut self, key: &str, position: usize) -> Result<(), WatchlistError> {
+        let member = self.members.remove(self.position(key)?);
+        self.members
+            .insert(position.min(self.members.len()), member);
+        self.touch();
+        Ok(())
+    }
+
+    /// Replace a member's tags and notes
+    pub fn annotate<I, S>(
+        &mut self,
+        key: &str,
+        tags: I,
+        notes: Option<String>,
+    ) -> Result<(), WatchlistError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let index = self.position(key)?;
+        let member = &mut self.members[index];
+        member.tags = clean_tags(tags);
+        member.notes = notes.filter(|notes| !notes.trim().is_empty());
+        self.touch();
+        Ok(())
+    }
+
+    /// Members carrying `tag`, in list order
+    pub fn tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a WatchlistMember> {
+        self.members
+            .iter()
+            .filter(move |member| member.has_tag(tag))
+    }
+
+    /// One market data subscription per member, tagged with the watchlist id
+    pub fn subscriptions(&self, timeframe: Option<TimeFrame>) -> Vec<SubscriptionRequest> {
+        self.members
+            .iter()
+            .map(|member| SubscriptionRequest {
+                subscription_type: SubscriptionType::MarketData,
+                symbol: member.symbol.clone(),
+                timeframe: timeframe.clone(),
+                parameters: HashMap::from([(
+                    "watchlist_id".to_string(),
+                    serde_json::Value::String(self.id.to_string()),
+                )]),
+            })
+            .collect()
+    }
+
+    fn position(&self, key: &str) -> Result<usize, WatchlistError> {
+        self.members
+            .iter()
+            .position(|member| member.key() == key)
+            .ok_or_else(|| WatchlistError::NotAMember(key.to_string()))
+    }
+
+    fn touch(&mut self) {
+        self.updated_at = Utc::now();
+    }
+}
+
+fn valid_name(name: String) -> Result<String, WatchlistError> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_WATCHLIST_NAME {
+        return Err(WatchlistError::InvalidName);
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Exchange;
+
+    fn stock(code: &str) -> Symbol {
+        Symbol::stock(code, code, Exchange::NASDAQ).unwrap()
+    }
+
+    #[test]
+    fn test_members_are_ordered_and_annotated() {
+        let mut watchlist = Watchlist::new("  Tech  ").unwrap();
+        assert_eq!(watchlist.name, "Tech");
+        watchlist.add(WatchlistMember::new(stock("AAPL"))).unwrap();
+        watchlist
+            .add(WatchlistMember::new(stock("MSFT")).with_tags(["cloud", " Cloud ", ""]))
+            .unwrap();
+        watchlist.add(WatchlistMember::new(stock("NVDA"))).unwrap();
+        assert_eq!(
+            watchlist.add(WatchlistMember::new(stock("AAPL"))),
+            Err(WatchlistError::DuplicateMember("AAPL@NASDAQ".to_string()))
+        );
+        assert_eq!(watchlist.members[1].tags, ["cloud"]);
+
+        watchlist.move_member("NVDA@NASDAQ", 0).unwrap();
+        watchlist.move_member("AAPL@NASDAQ", 10).unwrap();
+        let order: Vec<&str> = watchlist
+            .members
+            .iter()
+            .map(|member| member.symbol.code.as_str())
+            .collect();
+        assert_eq!(order, ["NVDA", "MSFT", "AAPL"]);
+
+        watchlist
+            .annotate(
+                "NVDA@NASDAQ",
+                ["ai", "semis"],
+                Some("earnings 28th".to_string()),
+            )
+            .unwrap();
+        assert_eq!(watchlist.tagged("AI").count(), 1);
+        assert_eq!(
+            watchlist.member("NVDA@NASDAQ").unwrap().notes.as_deref(),
+            Some("earnings 28th")
+        );
+
+        watchlist.remove("MSFT@NASDAQ").unwrap();
+        assert_eq!(
+            watchlist.remove("MSFT@NASDAQ"),
+            Err(WatchlistError::NotAMember("MSFT@NASDAQ".to_string()))
+        );
+        assert_eq!(watchlist.rename(" "), Err(WatchlistError::InvalidName));
+
+        let subscriptions = watchlist.subscriptions(Some(TimeFrame::OneMinute));
+        assert_eq!(subscriptions.len(), 2);
+        assert_eq!(subscriptions[0].symbol.code, "NVDA");
+        assert_eq!(
+            subscriptions[0].parameters["watchlist_id"],
+            watchlist.id.to_string()
+        );
+    }
+}